@@ -0,0 +1,112 @@
+//! Parsing, structural hashing, graph construction and grep shield
+//! throughput on synthetic projects from `testkit`.
+//!
+//! `cargo bench -p anatomist` for full runs, `-- --quick` for a smoke run,
+//! `-- --save-baseline <name>` / `--baseline <name>` to compare two commits.
+
+use anatomist::graph::build_reference_graph;
+use anatomist::scan::grep_shield;
+use anatomist::ParserHost;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use std::path::PathBuf;
+use testkit::SyntheticProject;
+use tree_sitter::{Node, Parser};
+
+/// Writes `project` to a fresh temp directory, canonicalized as a scan would.
+fn materialize(project: &SyntheticProject, name: &str) -> PathBuf {
+    let root = project.write_temp(name).unwrap();
+    dunce::canonicalize(root).unwrap()
+}
+
+/// `ParserHost::dissect` on one module of roughly `kib` KiB.
+fn bench_dissect(c: &mut Criterion) {
+    let mut group = c.benchmark_group("dissect");
+    for kib in [64, 256, 1024] {
+        // Each generated function is ~170 bytes.
+        let project = SyntheticProject::new(1).with_functions(kib * 1024 / 170);
+        let root = materialize(&project, &format!("bench_dissect_{kib}"));
+        let path = root.join(project.module_path(0));
+        let bytes = std::fs::metadata(&path).unwrap().len();
+        let mut host = ParserHost::new().unwrap();
+
+        group.throughput(Throughput::Bytes(bytes));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(format!("{kib}KiB")),
+            &path,
+            |b, path| b.iter(|| host.dissect(path).unwrap()),
+        );
+        std::fs::remove_dir_all(root).ok();
+    }
+    group.finish();
+}
+
+/// The body block of the first function in `tree`.
+fn function_body(tree: &tree_sitter::Tree) -> Node<'_> {
+    let function = tree.root_node().named_child(0).unwrap();
+    function.child_by_field_name("body").unwrap()
+}
+
+/// `forge::compute_structural_hash` on deeply nested vs long flat bodies of
+/// similar size.
+fn bench_structural_hash(c: &mut Criterion) {
+    let mut parser = Parser::new();
+    parser
+        .set_language(&tree_sitter_python::LANGUAGE.into())
+        .unwrap();
+    let mut group = c.benchmark_group("structural_hash");
+    for (shape, source) in [
+        ("deep_200", testkit::deep_function(200)),
+        ("wide_400", testkit::wide_function(400)),
+    ] {
+        let tree = parser.parse(&source, None).unwrap();
+        let body = function_body(&tree);
+        group.throughput(Throughput::Bytes(source.len() as u64));
+        group.bench_function(shape, |b| {
+            b.iter(|| forge::compute_structural_hash(body, source.as_bytes()))
+        });
+    }
+    group.finish();
+}
+
+/// `build_reference_graph` end to end on a 1000-module project.
+fn bench_reference_graph(c: &mut Criterion) {
+    let project = SyntheticProject::new(1000);
+    let root = materialize(&project, "bench_reference_graph");
+    let mut host = ParserHost::new().unwrap();
+
+    let mut group = c.benchmark_group("reference_graph");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(project.files() as u64));
+    group.bench_function("1000_files", |b| {
+        b.iter(|| build_reference_graph(&root, &mut host).unwrap())
+    });
+    group.finish();
+    std::fs::remove_dir_all(root).ok();
+}
+
+/// `grep_shield` over 200 YAML files, looking for every function name.
+fn bench_grep_shield(c: &mut Criterion) {
+    let project = SyntheticProject::new(200).with_config_files(200);
+    let root = materialize(&project, "bench_grep_shield");
+    let names = project.function_names();
+    let bytes: u64 = (0..200)
+        .map(|n| project.config_source(n).len() as u64)
+        .sum();
+
+    let mut group = c.benchmark_group("grep_shield");
+    group.throughput(Throughput::Bytes(bytes));
+    group.bench_function("200_yaml_files", |b| {
+        b.iter(|| grep_shield(&names, &root).unwrap())
+    });
+    group.finish();
+    std::fs::remove_dir_all(root).ok();
+}
+
+criterion_group!(
+    benches,
+    bench_dissect,
+    bench_structural_hash,
+    bench_reference_graph,
+    bench_grep_shield
+);
+criterion_main!(benches);
@@ -0,0 +1,239 @@
+//! Incremental entity cache (`.janitor/cache/`).
+//!
+//! After a file is dissected, its entity list is stored rkyv-serialized in
+//! `.janitor/cache/{blake3(file_path)}.rkyv` together with the file's content
+//! hash. On the next scan a file whose bytes are unchanged is served
+//! from the cache instead of being re-parsed.
+//!
+//! An entry is only trusted when its schema version, the Janitor version, and
+//! the fingerprint of the registered heuristics all match the current run —
+//! any of those changing can alter the extracted entities.
+
+use crate::parser::ParserHost;
+use crate::{AnatomistError, Entity};
+use rkyv::{Archive, Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// Bump whenever [`CacheEntry`] or [`Entity`] changes layout, or `dissect` extracts
+/// a different set of entities.
+pub const CACHE_SCHEMA_VERSION: u32 = 9;
+
+/// On-disk record for one source file.
+#[derive(Debug, Archive, Deserialize, Serialize)]
+struct CacheEntry {
+    schema_version: u32,
+    janitor_version: String,
+    heuristics: String,
+    content_hash: [u8; 32],
+    entities: Vec<Entity>,
+}
+
+/// Per-project entity cache rooted at `.janitor/cache/`.
+pub struct EntityCache {
+    dir: PathBuf,
+    heuristics: String,
+    visited: HashSet<String>,
+    /// Files served from the cache during this run.
+    pub hits: usize,
+    /// Files dissected because no valid cache entry existed.
+    pub misses: usize,
+}
+
+impl EntityCache {
+    /// Opens (creating if needed) the cache directory under `project_root`.
+    ///
+    /// `host` supplies the heuristic fingerprint baked into every entry.
+    pub fn open(project_root: &Path, host: &ParserHost) -> Result<Self, AnatomistError> {
+        let dir = project_root.join(".janitor").join("cache");
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            heuristics: host.heuristic_fingerprint(),
+            visited: HashSet::new(),
+            hits: 0,
+            misses: 0,
+        })
+    }
+
+    /// Returns the entities of `path`, from the cache when its content is unchanged.
+    ///
+    /// On a miss the file is dissected with `host` and the entry is (re)written;
+    /// failure to write the entry is not an error.
+    pub fn dissect(
+        &mut self,
+        host: &mut ParserHost,
+        path: &Path,
+    ) -> Result<Vec<Entity>, AnatomistError> {
+        let source = common::source::read(path)?;
+        self.dissect_source(host, path, &source)
+    }
+
+    /// Like [`Self::dissect`], for the already-read text of `path` (see
+    /// [`ParserHost::dissect_source`]).
+    pub fn dissect_source(
+        &mut self,
+        host: &mut ParserHost,
+        path: &Path,
+        source: &[u8],
+    ) -> Result<Vec<Entity>, AnatomistError> {
+        let entry_path = self.entry_path(path);
+        if let Some(name) = entry_path.file_name() {
+            self.visited.insert(name.to_string_lossy().into_owned());
+        }
+
+        let content_hash = *blake3::hash(source).as_bytes();
+
+        if let Some(entities) = self.load(&entry_path, &content_hash) {
+            self.hits += 1;
+            return Ok(entities);
+        }
+
+        let entities = host.dissect_source(path, source)?;
+        self.misses += 1;
+
+        let entry = CacheEntry {
+            schema_version: CACHE_SCHEMA_VERSION,
+            janitor_version: env!("CARGO_PKG_VERSION").to_string(),
+            heuristics: self.heuristics.clone(),
+            content_hash,
+            entities,
+        };
+        if let Ok(bytes) = rkyv::to_bytes::<rkyv::rancor::Error>(&entry) {
+            let _ = std::fs::write(&entry_path, &bytes);
+        }
+        Ok(entry.entities)
+    }
+
+    /// Deletes entries for files not requested during this run (deleted or renamed sources).
+    ///
+    /// Returns the number of entries removed.
+    pub fn prune(&self) -> usize {
+        let Ok(read_dir) = std::fs::read_dir(&self.dir) else {
+            return 0;
+        };
+        let mut removed = 0;
+        for dir_entry in read_dir.flatten() {
+            let name = dir_entry.file_name().to_string_lossy().into_owned();
+            if name.ends_with(".rkyv")
+                && !self.visited.contains(&name)
+                && std::fs::remove_file(dir_entry.path()).is_ok()
+            {
+                removed += 1;
+            }
+        }
+        removed
+    }
+
+    fn entry_path(&self, path: &Path) -> PathBuf {
+        let key = blake3::hash(path.to_string_lossy().as_bytes());
+        self.dir.join(format!("{}.rkyv", key.to_hex()))
+    }
+
+    fn load(&self, entry_path: &Path, content_hash: &[u8; 32]) -> Option<Vec<Entity>> {
+        let bytes = std::fs::read(entry_path).ok()?;
+        let entry = rkyv::from_bytes::<CacheEntry, rkyv::rancor::Error>(&bytes).ok()?;
+        let valid = entry.schema_version == CACHE_SCHEMA_VERSION
+            && entry.janitor_version == env!("CARGO_PKG_VERSION")
+            && entry.heuristics == self.heuristics
+            && &entry.content_hash == content_hash;
+        valid.then_some(entry.entities)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn entry_count(dir: &Path) -> usize {
+        fs::read_dir(dir.join(".janitor/cache")).unwrap().count()
+    }
+
+    #[test]
+    fn test_unchanged_file_hits_cache() {
+        let tmp = std::env::temp_dir().join("test_cache_unchanged");
+        fs::create_dir_all(&tmp).ok();
+        let file = tmp.join("a.py");
+        fs::write(&file, b"def f():\n    pass\n").ok();
+
+        let mut host = ParserHost::new().unwrap();
+        let mut cache = EntityCache::open(&tmp, &host).unwrap();
+        let first = cache.dissect(&mut host, &file).unwrap();
+        assert_eq!((cache.hits, cache.misses), (0, 1));
+
+        let mut cache = EntityCache::open(&tmp, &host).unwrap();
+        let second = cache.dissect(&mut host, &file).unwrap();
+        assert_eq!((cache.hits, cache.misses), (1, 0));
+        assert_eq!(first, second);
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_modified_file_is_reparsed() {
+        let tmp = std::env::temp_dir().join("test_cache_modified");
+        fs::create_dir_all(&tmp).ok();
+        let file = tmp.join("a.py");
+        fs::write(&file, b"def f():\n    pass\n").ok();
+
+        let mut host = ParserHost::new().unwrap();
+        EntityCache::open(&tmp, &host)
+            .unwrap()
+            .dissect(&mut host, &file)
+            .unwrap();
+
+        fs::write(&file, b"def g():\n    pass\n").ok();
+        let mut cache = EntityCache::open(&tmp, &host).unwrap();
+        let entities = cache.dissect(&mut host, &file).unwrap();
+        assert_eq!((cache.hits, cache.misses), (0, 1));
+        assert_eq!(entities[0].name, "g");
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_deleted_file_entry_pruned() {
+        let tmp = std::env::temp_dir().join("test_cache_deleted");
+        fs::create_dir_all(&tmp).ok();
+        let kept = tmp.join("kept.py");
+        let gone = tmp.join("gone.py");
+        fs::write(&kept, b"def f():\n    pass\n").ok();
+        fs::write(&gone, b"def g():\n    pass\n").ok();
+
+        let mut host = ParserHost::new().unwrap();
+        let mut cache = EntityCache::open(&tmp, &host).unwrap();
+        cache.dissect(&mut host, &kept).unwrap();
+        cache.dissect(&mut host, &gone).unwrap();
+        assert_eq!(entry_count(&tmp), 2);
+
+        fs::remove_file(&gone).ok();
+        let mut cache = EntityCache::open(&tmp, &host).unwrap();
+        cache.dissect(&mut host, &kept).unwrap();
+        assert_eq!(cache.prune(), 1);
+        assert_eq!(entry_count(&tmp), 1);
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_heuristic_change_invalidates() {
+        let tmp = std::env::temp_dir().join("test_cache_heuristics");
+        fs::create_dir_all(&tmp).ok();
+        let file = tmp.join("a.py");
+        fs::write(&file, b"def f():\n    pass\n").ok();
+
+        let mut host = ParserHost::new().unwrap();
+        EntityCache::open(&tmp, &host)
+            .unwrap()
+            .dissect(&mut host, &file)
+            .unwrap();
+
+        host.register_heuristic(Box::new(crate::heuristics::pytest::PytestFixtureHeuristic));
+        let mut cache = EntityCache::open(&tmp, &host).unwrap();
+        cache.dissect(&mut host, &file).unwrap();
+        assert_eq!((cache.hits, cache.misses), (0, 1));
+
+        fs::remove_dir_all(tmp).ok();
+    }
+}
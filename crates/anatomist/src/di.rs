@@ -0,0 +1,341 @@
+//! Dependency-injection container and service-registry detection.
+//!
+//! DI frameworks (dependency-injector, punq, lagom, hand-rolled dict registries)
+//! wire implementations declaratively:
+//!
+//! ```text
+//! container.register(EmailSender, SmtpEmailSender)
+//! email = providers.Factory(SmtpEmailSender)
+//! SERVICES = {"email": SmtpEmailSender}
+//! ```
+//!
+//! The concrete class is referenced exactly once — as a bare argument — and is
+//! never called directly, so the call-site pass in [`crate::graph`] never links it.
+//! This module extracts those registration arguments so the graph builder can
+//! emit reference edges and the pipeline can assign [`crate::Protection::ConfigReference`].
+
+use std::sync::OnceLock;
+use tree_sitter::{Node, Query, QueryCursor, StreamingIterator};
+
+/// Default registration call names (matched against the final identifier of the callee).
+pub const DEFAULT_REGISTRATION_CALLS: &[&str] = &[
+    "register",
+    "bind",
+    "add_singleton",
+    "add_transient",
+    "add_scoped",
+    "Factory",
+    "Singleton",
+];
+
+/// Words that mark a dict assigned to a name as a service registry
+/// (`SERVICES`, `payment_backends`, `HANDLER_REGISTRY`), matched case-insensitively.
+const REGISTRY_NAMES: &[&str] = &[
+    "service",
+    "registry",
+    "provider",
+    "factories",
+    "factory",
+    "handler",
+    "backend",
+    "plugin",
+    "binding",
+    "container",
+];
+
+/// Configurable DI registration rule set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiRules {
+    /// Callee names whose arguments are treated as registered implementations.
+    pub registration_calls: Vec<String>,
+    /// When `true`, dict registries count as registrations: a dict literal
+    /// assigned to a registry-like name (see [`REGISTRY_NAMES`]) whose keys
+    /// are all strings and whose values are all class or function names.
+    pub dict_registries: bool,
+}
+
+impl Default for DiRules {
+    fn default() -> Self {
+        Self {
+            registration_calls: DEFAULT_REGISTRATION_CALLS
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            dict_registries: true,
+        }
+    }
+}
+
+impl DiRules {
+    /// The rules a `[di]` table asks for, with the defaults filling in what
+    /// it leaves unset.
+    pub fn from_config(config: &common::config::DiConfig) -> Self {
+        let defaults = Self::default();
+        Self {
+            registration_calls: config
+                .registration_calls
+                .clone()
+                .unwrap_or(defaults.registration_calls),
+            dict_registries: config.dict_registries.unwrap_or(defaults.dict_registries),
+        }
+    }
+}
+
+/// The symbol a registration argument points at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiTarget {
+    /// Bare identifier or the final segment of an attribute (`services.Smtp` → `Smtp`).
+    Name(String),
+    /// Dotted-path string literal (`"app.services.Smtp"`).
+    DottedPath(String),
+}
+
+/// A single registration argument found in container setup code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiRegistration {
+    /// The registered implementation.
+    pub target: DiTarget,
+    /// Callee text (`"providers.Factory"`) or `"dict"` for dict registries.
+    pub call: String,
+    /// Start byte of the registration argument (used for caller attribution).
+    pub byte_offset: u32,
+    /// Line number of the registration argument (1-indexed).
+    pub line: u32,
+}
+
+static DI_QUERY: OnceLock<Query> = OnceLock::new();
+
+/// Extracts DI registration arguments from a parsed Python source tree.
+///
+/// Arguments are collected from calls whose callee name (the final identifier,
+/// e.g. `Factory` in `providers.Factory(...)`) is listed in `rules`, including
+/// keyword-argument values. The values of dict registries are collected when
+/// [`DiRules::dict_registries`] is set. String literals are only kept when they
+/// look like dotted import paths.
+pub fn extract_registrations(source: &[u8], root: Node, rules: &DiRules) -> Vec<DiRegistration> {
+    let query = DI_QUERY.get_or_init(|| {
+        Query::new(
+            &tree_sitter_python::LANGUAGE.into(),
+            r#"
+            (call
+              function: (identifier) @callee
+              arguments: (argument_list) @args)
+
+            (call
+              function: (attribute
+                attribute: (identifier) @callee)
+              arguments: (argument_list) @args)
+
+            (assignment
+              left: (identifier) @dict_name
+              right: (dictionary) @dict)
+            "#,
+        )
+        .expect("Invalid DI query")
+    });
+
+    let mut cursor = QueryCursor::new();
+    let mut matches = cursor.matches(query, root, source);
+    let mut registrations = Vec::new();
+
+    while let Some(m) = matches.next() {
+        let mut callee: Option<Node> = None;
+        let mut args: Option<Node> = None;
+        let mut dict_name: Option<Node> = None;
+        let mut dict: Option<Node> = None;
+
+        for capture in m.captures {
+            match query.capture_names()[capture.index as usize] {
+                "callee" => callee = Some(capture.node),
+                "args" => args = Some(capture.node),
+                "dict_name" => dict_name = Some(capture.node),
+                "dict" => dict = Some(capture.node),
+                _ => {}
+            }
+        }
+
+        if let (Some(name), Some(dict)) = (dict_name, dict) {
+            if rules.dict_registries {
+                registrations.extend(dict_registry(name, dict, source));
+            }
+            continue;
+        }
+
+        let (Some(callee), Some(args)) = (callee, args) else {
+            continue;
+        };
+        let Ok(name) = callee.utf8_text(source) else {
+            continue;
+        };
+        if !rules.registration_calls.iter().any(|c| c == name) {
+            continue;
+        }
+        let call = callee
+            .parent()
+            .and_then(|f| f.utf8_text(source).ok())
+            .unwrap_or(name)
+            .to_string();
+
+        let mut walker = args.walk();
+        for arg in args.named_children(&mut walker) {
+            let value = if arg.kind() == "keyword_argument" {
+                match arg.child_by_field_name("value") {
+                    Some(v) => v,
+                    None => continue,
+                }
+            } else {
+                arg
+            };
+            if let Some(target) = target_of(value, source) {
+                registrations.push(DiRegistration {
+                    target,
+                    call: call.clone(),
+                    byte_offset: value.start_byte() as u32,
+                    line: value.start_position().row as u32 + 1,
+                });
+            }
+        }
+    }
+
+    registrations
+}
+
+/// The values of `dict`, assigned to `name`, when it is a service registry:
+/// a registry-like name, string keys, and names as values. Anything else
+/// (`{"timeout": 30}`, `{Status.OK: handle_ok}`) registers nothing.
+fn dict_registry(name: Node, dict: Node, source: &[u8]) -> Vec<DiRegistration> {
+    let Ok(name) = name.utf8_text(source) else {
+        return Vec::new();
+    };
+    let name = name.to_ascii_lowercase();
+    if !REGISTRY_NAMES.iter().any(|word| name.contains(word)) {
+        return Vec::new();
+    }
+    let mut registrations = Vec::new();
+    let mut walker = dict.walk();
+    for pair in dict.named_children(&mut walker) {
+        if pair.kind() == "comment" {
+            continue;
+        }
+        let key = pair.child_by_field_name("key");
+        let value = pair.child_by_field_name("value");
+        let (Some(key), Some(value)) = (key, value) else {
+            return Vec::new();
+        };
+        if key.kind() != "string" || !matches!(value.kind(), "identifier" | "attribute") {
+            return Vec::new();
+        }
+        if let Some(target) = target_of(value, source) {
+            registrations.push(DiRegistration {
+                target,
+                call: "dict".to_string(),
+                byte_offset: value.start_byte() as u32,
+                line: value.start_position().row as u32 + 1,
+            });
+        }
+    }
+    registrations
+}
+
+/// Maps an argument node to a [`DiTarget`], or `None` for unsupported shapes.
+fn target_of(node: Node, source: &[u8]) -> Option<DiTarget> {
+    match node.kind() {
+        "identifier" => Some(DiTarget::Name(node.utf8_text(source).ok()?.to_string())),
+        "attribute" => {
+            let attr = node.child_by_field_name("attribute")?;
+            Some(DiTarget::Name(attr.utf8_text(source).ok()?.to_string()))
+        }
+        "string" => {
+            let mut walker = node.walk();
+            let content = node
+                .children(&mut walker)
+                .find(|c| c.kind() == "string_content")?;
+            let text = content.utf8_text(source).ok()?;
+            is_dotted_path(text).then(|| DiTarget::DottedPath(text.to_string()))
+        }
+        _ => None,
+    }
+}
+
+/// Returns `true` for `a.b.C`-shaped strings: at least one dot, identifier segments only.
+fn is_dotted_path(s: &str) -> bool {
+    s.contains('.')
+        && s.split('.').all(|seg| {
+            !seg.is_empty()
+                && !seg.starts_with(|c: char| c.is_ascii_digit())
+                && seg.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_')
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tree_sitter::Parser;
+
+    fn registrations(source: &str) -> Vec<DiRegistration> {
+        let mut parser = Parser::new();
+        parser
+            .set_language(&tree_sitter_python::LANGUAGE.into())
+            .unwrap();
+        let tree = parser.parse(source.as_bytes(), None).unwrap();
+        extract_registrations(source.as_bytes(), tree.root_node(), &DiRules::default())
+    }
+
+    #[test]
+    fn test_factory_and_register_calls() {
+        let regs = registrations(
+            "email = providers.Factory(SmtpEmailSender, host=settings.Host)\n\
+             container.register(EmailSender, services.Mailgun)\n\
+             print(Unrelated)\n",
+        );
+        let names: Vec<&DiTarget> = regs.iter().map(|r| &r.target).collect();
+        assert!(names.contains(&&DiTarget::Name("SmtpEmailSender".into())));
+        assert!(names.contains(&&DiTarget::Name("Host".into())));
+        assert!(names.contains(&&DiTarget::Name("EmailSender".into())));
+        assert!(names.contains(&&DiTarget::Name("Mailgun".into())));
+        assert!(!names.contains(&&DiTarget::Name("Unrelated".into())));
+        assert_eq!(regs[0].call, "providers.Factory");
+    }
+
+    #[test]
+    fn test_dict_registry_and_dotted_string() {
+        let regs = registrations(
+            "SERVICES = {\"email\": SmtpEmailSender}\n\
+             container.register(\"app.services.Sms\", \"not a path\")\n",
+        );
+        assert!(regs
+            .iter()
+            .any(|r| r.target == DiTarget::Name("SmtpEmailSender".into()) && r.call == "dict"));
+        assert!(regs
+            .iter()
+            .any(|r| r.target == DiTarget::DottedPath("app.services.Sms".into())));
+        assert_eq!(regs.len(), 2);
+    }
+
+    #[test]
+    fn test_only_registry_dicts_register() {
+        let regs = registrations(
+            "PAYMENT_BACKENDS = {\"stripe\": gateways.Stripe, \"paypal\": PayPal}\n\
+             LABELS = {\"ok\": Ok}\n\
+             handlers = {\"a\": on_a, \"retries\": 3}\n\
+             ROUTES = {Status.OK: handle_ok}\n\
+             render(context={\"user\": current_user})\n",
+        );
+        let names: Vec<&DiTarget> = regs.iter().map(|r| &r.target).collect();
+        assert_eq!(
+            names,
+            vec![
+                &DiTarget::Name("Stripe".into()),
+                &DiTarget::Name("PayPal".into())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_is_dotted_path() {
+        assert!(is_dotted_path("pkg.mod.Class"));
+        assert!(!is_dotted_path("Class"));
+        assert!(!is_dotted_path("pkg..Class"));
+        assert!(!is_dotted_path("v1.2"));
+    }
+}
@@ -2,142 +2,613 @@
 //!
 //! Two-pass pipeline:
 //! 1. **Index Pass**: Walk all `.py` files, extract entities, build `SymbolRegistry`, add nodes to graph.
-//! 2. **Link Pass**: Re-parse each file for imports + call sites, add symbol-to-symbol edges.
+//! 2. **Link Pass**: Re-parse each file for imports + call sites (and name reads of
+//!    module-level assignments), add symbol-to-symbol edges.
 
-use crate::imports::{extract_cpp_includes, extract_imports, resolve_import};
-use crate::{AnatomistError, Entity, ParserHost};
+use crate::cache::EntityCache;
+use crate::di::{extract_registrations, DiRules, DiTarget};
+use crate::imports::{
+    es_default_export, extract_cpp_includes, extract_es_imports, extract_imports, module_path,
+    resolve_es_import, resolve_import, source_roots, EsImport, ImportInfo, SCRIPT_EXTENSIONS,
+};
+use crate::progress::{self, PipelineEvent, Stage};
+use crate::unused_imports::{unused_in_tree, UnusedImport};
+use crate::{AnatomistError, Entity, EntityType, ParserHost};
+use common::diagnostics::{codes, Diagnostic, Diagnostics, Severity};
 use common::registry::{symbol_hash, SymbolEntry, SymbolRegistry};
-use memmap2::Mmap;
-use petgraph::graph::{DiGraph, NodeIndex};
+use common::source::SourceError;
+use petgraph::graph::{DiGraph, EdgeIndex, NodeIndex};
 use petgraph::visit::EdgeRef;
-use petgraph::Direction;
-use std::collections::{HashMap, HashSet};
-use std::fs::File;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::sync::OnceLock;
 use tree_sitter::{Node, Parser, Query, QueryCursor, StreamingIterator};
-use walkdir::WalkDir;
 
 /// Statistics about the reference graph.
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Default)]
 pub struct GraphStats {
     pub symbol_count: usize,
     pub edge_count: usize,
     pub file_count: usize,
     pub parse_errors: usize,
+    /// Python files whose entities were served from the [`EntityCache`].
+    pub cache_hits: usize,
+    /// Python files dissected in Pass 1 (cache misses, or every file without a cache).
+    pub files_parsed: usize,
+    /// Files that were transcoded, skipped or parsed with errors.
+    pub diagnostics: Diagnostics,
+}
+
+/// Reads `path` with [`common::source::read`] for indexing, recording a
+/// diagnostic when it was transcoded or could not be read. A transcoded file
+/// is still indexed; `None` means the file is left out.
+pub(crate) fn read_source(
+    path: &Path,
+    diagnostics: &mut Diagnostics,
+) -> Option<common::source::Source> {
+    let diagnostic = match common::source::read(path) {
+        Ok(source) if !source.is_transcoded() => return Some(source),
+        Ok(source) => {
+            diagnostics.push(
+                Diagnostic::new(
+                    Severity::Info,
+                    codes::NON_UTF8_SOURCE,
+                    "not valid UTF-8; read as Latin-1",
+                )
+                .at(file_key(path)),
+            );
+            return Some(source);
+        }
+        Err(e @ SourceError::IoError(_)) => {
+            Diagnostic::error(codes::UNREADABLE_FILE, format!("not indexed: {e}"))
+        }
+        Err(e) => Diagnostic::warning(codes::NUL_BYTE, format!("not indexed: {e}")),
+    };
+    diagnostics.push(diagnostic.at(file_key(path)));
+    None
+}
+
+/// BLAKE3 hash of `source` as it is on disk, or `None` when its text no
+/// longer encodes back to the file's encoding.
+pub(crate) fn source_hash(source: &common::source::Source) -> Option<[u8; 32]> {
+    let bytes = source.encoding().encode(source).ok()?;
+    Some(*blake3::hash(&bytes).as_bytes())
+}
+
+/// The diagnostic for a file the parser gave up on.
+pub(crate) fn parse_failure(path: &Path, error: &AnatomistError) -> Diagnostic {
+    Diagnostic::error(codes::PARSE_FAILURE, format!("not indexed: {error}")).at(file_key(path))
+}
+
+/// `path` canonicalized and normalized, as the graph keys files.
+pub(crate) fn file_key(path: &Path) -> String {
+    normalize_path(&dunce::canonicalize(path).unwrap_or_else(|_| path.to_path_buf()))
 }
 
 /// Cross-file reference graph with symbol registry.
 pub struct ReferenceGraph {
     pub registry: SymbolRegistry,
-    pub graph: DiGraph<u64, ()>,
+    /// Nodes are symbol ids; edges record where the reference was made.
+    pub graph: DiGraph<u64, EdgeInfo>,
     pub file_symbols: HashMap<String, Vec<u64>>,
     /// All entities extracted across the project (populated in Pass 1).
+    /// The scan pipeline takes them when it owns the graph.
     pub entities: Vec<Entity>,
+    /// Symbols registered with a DI container or service registry, mapped to the
+    /// registration site that referenced them.
+    pub di_registered: HashMap<u64, CallRef>,
+    /// Symbols named by a string literal passed to `getattr`, `setattr` or
+    /// `hasattr` anywhere in the project, mapped to the first such site.
+    pub dynamic_refs: HashMap<u64, CallRef>,
+    /// Symbols named in a string annotation (`def f(c: "Config")`,
+    /// `Optional["pkg.Config"]`) anywhere in the project, mapped to the first
+    /// such site (`"{file}:{line}"`). Names resolve through imports, same-file
+    /// definitions and module paths; one that resolves nowhere matches every
+    /// symbol named like its last segment. Forward references that resolve
+    /// through an import or a same-file definition are [`EdgeKind::TypeRef`]
+    /// edges too.
+    pub annotation_refs: HashMap<u64, String>,
     pub stats: GraphStats,
+    /// Files imported by the code cells of each Jupyter notebook, by file key.
+    /// Notebooks are entry points: what they import is not an orphan.
+    pub notebook_imports: HashMap<String, BTreeSet<String>>,
+    /// Directories the graph was built from, as file-key prefixes with a
+    /// trailing slash, longest first: the workspace roots (see
+    /// [`GraphOptions::workspace_roots`]), or just the project root.
+    pub roots: Vec<String>,
+    /// BLAKE3 hash of each indexed Python file's bytes on disk, as Pass 1
+    /// read them, by file key.
+    pub file_hashes: HashMap<String, [u8; 32]>,
+    /// Per-file results of the Python link pass, keyed like [`Self::file_symbols`],
+    /// kept for [`crate::incremental`].
+    pub(crate) python_links: HashMap<String, FileLinks>,
+}
+
+/// A call that references a symbol without an edge of its own: a DI
+/// registration, or a `getattr` naming it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallRef {
+    /// File key of the calling file.
+    pub file: String,
+    /// 1-based line of the call.
+    pub line: u32,
+    /// The call, abbreviated (`getattr(…, "name")`).
+    pub call: String,
+}
+
+impl CallRef {
+    /// `"{file}:{line}"`, for [`common::ProtectionDetail::at`].
+    pub fn location(&self) -> String {
+        format!("{}:{}", self.file, self.line)
+    }
 }
 
-/// Known Django / WSGI / ASGI / script entry-point filenames that should never
-/// be flagged as orphans even when no other file imports them.
-const ENTRY_POINT_FILENAMES: &[&str] = &["wsgi.py", "asgi.py", "manage.py", "main.py", "app.py"];
+/// Where a reference-graph edge comes from.
+///
+/// Repeated references between the same pair of symbols share one edge: `line`
+/// and `kind` describe the first site, `count` how many sites there are.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EdgeInfo {
+    /// 1-based line of the first referencing site in the caller's file.
+    pub line: u32,
+    pub kind: EdgeKind,
+    /// Number of referencing sites collapsed into this edge.
+    pub count: u32,
+}
+
+impl EdgeInfo {
+    /// A single reference at `line`.
+    pub fn new(line: u32, kind: EdgeKind) -> Self {
+        Self {
+            line,
+            kind,
+            count: 1,
+        }
+    }
+}
+
+/// The construct that produced a reference-graph edge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EdgeKind {
+    /// Call site, including `self.`/`cls.` calls and DI container registrations.
+    Call,
+    /// `from m import *` linking the importing module to `m`'s public symbols.
+    Import,
+    /// Name read of a module-level assignment or type alias.
+    Read,
+    /// Name used in a decorator of the referencing definition (`@audit`).
+    Decorator,
+    /// Base class (or metaclass) in a class definition's superclass list.
+    Inherit,
+    /// Name used in a parameter, return, or variable annotation.
+    TypeRef,
+    /// C++ `#include "..."` between file sentinels.
+    Include,
+    /// Any reference through a name imported only inside `if TYPE_CHECKING:`,
+    /// which type checkers see but nothing runs. Keeps its target alive unless
+    /// `type_checking_keeps_alive = false` in `.janitor.toml`.
+    TypeChecking,
+}
+
+impl EdgeKind {
+    /// Lowercase name for reports (`"call"`, `"include"`, ...).
+    pub fn label(self) -> &'static str {
+        match self {
+            EdgeKind::Call => "call",
+            EdgeKind::Import => "import",
+            EdgeKind::Read => "read",
+            EdgeKind::Decorator => "decorator",
+            EdgeKind::Inherit => "inherit",
+            EdgeKind::TypeRef => "type_ref",
+            EdgeKind::Include => "include",
+            EdgeKind::TypeChecking => "type_checking",
+        }
+    }
+}
+
+/// Known Django / WSGI / ASGI / script / JS bundle entry-point filenames that
+/// should never be flagged as orphans even when no other file imports them.
+pub const ENTRY_POINT_FILENAMES: &[&str] = &[
+    "wsgi.py",
+    "asgi.py",
+    "manage.py",
+    "main.py",
+    "app.py",
+    "index.js",
+    "index.jsx",
+    "index.ts",
+    "index.tsx",
+    "main.js",
+    "main.ts",
+];
 
 /// Directory segments whose files are discovered dynamically by frameworks (Scrapy, Celery, etc.)
 /// and therefore are never imported by other Python files. Files inside these directories
 /// are implicitly entry points and must not be flagged as orphans.
-const PLUGIN_ORPHAN_EXEMPT_DIRS: &[&str] = &["spiders", "plugins", "commands", "handlers", "tasks"];
+pub const PLUGIN_ORPHAN_EXEMPT_DIRS: &[&str] =
+    &["spiders", "plugins", "commands", "handlers", "tasks"];
+
+/// A file that is run or discovered rather than imported: a known entry point,
+/// one of `extra_entry_points`, or a file under one of `plugin_dirs`.
+fn is_entry_file<S: AsRef<str>>(
+    file_path: &str,
+    extra_entry_points: &[S],
+    plugin_dirs: &[S],
+) -> bool {
+    let filename = file_path.split('/').next_back().unwrap_or_default();
+    ENTRY_POINT_FILENAMES.contains(&filename)
+        || extra_entry_points.iter().any(|e| e.as_ref() == filename)
+        || file_path
+            .split('/')
+            .any(|seg| plugin_dirs.iter().any(|d| d.as_ref() == seg))
+}
 
 impl ReferenceGraph {
-    /// Returns the paths of **orphan files** — Python source files with zero
+    /// The root `file_key` belongs to, without its trailing slash: the
+    /// innermost of [`Self::roots`] containing it. `None` outside every root.
+    pub fn owning_root(&self, file_key: &str) -> Option<&str> {
+        self.roots
+            .iter()
+            .find(|root| file_key.starts_with(root.as_str()))
+            .map(|root| root.trim_end_matches('/'))
+    }
+
+    /// `file_key` relative to its owning root (see [`Self::owning_root`]),
+    /// or unchanged outside every root. Directory-name rules (entry points,
+    /// plugin and protected directories) match segments of this path, so a
+    /// root living under `/srv/jobs/` does not make every file a plugin.
+    pub fn root_relative<'a>(&self, file_key: &'a str) -> &'a str {
+        self.roots
+            .iter()
+            .find_map(|root| file_key.strip_prefix(root.as_str()))
+            .unwrap_or(file_key)
+    }
+
+    /// Returns the paths of **orphan files** — Python and JS/TS source files with zero
     /// incoming file-level dependencies that are not known entry points.
     ///
     /// A file is an orphan when:
-    /// 1. None of its symbols has an incoming edge from a symbol in a **different** file.
+    /// 1. None of its symbols has an incoming edge from a symbol in a **different** file,
+    ///    and no other Python file imports it (`from .reports import generate_report`
+    ///    in a package `__init__.py`, `importlib.import_module("pkg.reports")`).
     /// 2. Its filename is not in [`ENTRY_POINT_FILENAMES`].
     /// 3. Its filename is not `__init__.py` (package init files are always exempt).
     /// 4. It does not reside in a plugin directory (see [`PLUGIN_ORPHAN_EXEMPT_DIRS`]).
     ///
     /// Results are sorted for deterministic output.
     pub fn find_orphan_files(&self) -> Vec<String> {
-        // Build id → NodeIndex reverse map (O(n) graph walk).
-        let id_to_node: HashMap<u64, NodeIndex> = self
-            .graph
-            .node_indices()
-            .filter_map(|n| self.graph.node_weight(n).map(|&w| (w, n)))
+        self.find_orphan_files_with::<&str>(&[], PLUGIN_ORPHAN_EXEMPT_DIRS)
+    }
+
+    /// Same as [`find_orphan_files`](Self::find_orphan_files), additionally
+    /// exempting `extra_entry_points` file names and using `plugin_dirs` in place
+    /// of [`PLUGIN_ORPHAN_EXEMPT_DIRS`] (both from `.janitor.toml`).
+    pub fn find_orphan_files_with<S: AsRef<str>>(
+        &self,
+        extra_entry_points: &[S],
+        plugin_dirs: &[S],
+    ) -> Vec<String> {
+        let imported: HashSet<&str> = self
+            .file_dependencies()
+            .into_iter()
+            .map(|(_, target)| target)
+            .collect();
+
+        let mut orphans: Vec<String> = self
+            .file_symbols
+            .keys()
+            .filter(|file_path| {
+                // __init__.py is too risky to flag — always exempt.
+                !file_path.ends_with("/__init__.py")
+                    && !is_entry_file(
+                        self.root_relative(file_path),
+                        extra_entry_points,
+                        plugin_dirs,
+                    )
+                    && !imported.contains(file_path.as_str())
+            })
+            .cloned()
+            .collect();
+        orphans.sort();
+        orphans
+    }
+
+    /// [`find_orphan_packages_with`](Self::find_orphan_packages_with) with the
+    /// built-in entry points and [`PLUGIN_ORPHAN_EXEMPT_DIRS`].
+    pub fn find_orphan_packages(&self) -> Vec<String> {
+        self.find_orphan_packages_with::<&str>(&[], PLUGIN_ORPHAN_EXEMPT_DIRS)
+    }
+
+    /// Returns the directories of **orphan packages** — packages (directories
+    /// with an `__init__.py`) that no file outside them depends on, however
+    /// much their own modules use each other (`legacy/billing_v1/`).
+    ///
+    /// A package is skipped when it holds every file of the project, or any
+    /// file the per-file analysis exempts (entry points, `extra_entry_points`,
+    /// files under `plugin_dirs`). Only the outermost orphan package of a
+    /// subtree is reported. Results are sorted.
+    pub fn find_orphan_packages_with<S: AsRef<str>>(
+        &self,
+        extra_entry_points: &[S],
+        plugin_dirs: &[S],
+    ) -> Vec<String> {
+        let dependencies = self.file_dependencies();
+        let packages: BTreeSet<&str> = self
+            .file_symbols
+            .keys()
+            .filter_map(|f| f.strip_suffix("/__init__.py"))
             .collect();
 
-        // Build id → file_path from the registry.
+        let mut orphans: Vec<String> = Vec::new();
+        for package in packages {
+            let inside = |file: &str| {
+                file.strip_prefix(package)
+                    .is_some_and(|rest| rest.starts_with('/'))
+            };
+            let nested = orphans.iter().any(|outer| {
+                package
+                    .strip_prefix(outer.as_str())
+                    .is_some_and(|rest| rest.starts_with('/'))
+            });
+            if nested {
+                continue;
+            }
+            let members: Vec<&String> = self.file_symbols.keys().filter(|f| inside(f)).collect();
+            if members.len() == self.file_symbols.len()
+                || members
+                    .iter()
+                    .any(|f| is_entry_file(self.root_relative(f), extra_entry_points, plugin_dirs))
+            {
+                continue;
+            }
+            let used_from_outside = dependencies
+                .iter()
+                .any(|&(source, target)| inside(target) && !inside(source));
+            if !used_from_outside {
+                orphans.push(package.to_string());
+            }
+        }
+        orphans
+    }
+
+    /// Every `(importer, imported)` pair of distinct files: a symbol edge
+    /// across files, or a Python import resolved to a file even when no
+    /// call follows (`from .reports import generate_report` in a package
+    /// `__init__.py`, `importlib.import_module("pkg.reports")`, `import
+    /// report` in a notebook).
+    fn file_dependencies(&self) -> HashSet<(&str, &str)> {
         let id_to_file: HashMap<u64, &str> = self
             .registry
             .entries
             .iter()
             .map(|e| (e.id, e.file_path.as_str()))
             .collect();
+        let file_of = |n: NodeIndex| id_to_file.get(&self.graph[n]).copied();
 
-        let mut orphans = Vec::new();
-
-        for (file_path, symbol_ids) in &self.file_symbols {
-            let filename = file_path.split('/').next_back().unwrap_or_default();
+        let mut dependencies: HashSet<(&str, &str)> = self
+            .graph
+            .edge_references()
+            .filter_map(|edge| Some((file_of(edge.source())?, file_of(edge.target())?)))
+            .collect();
+        for (file, links) in &self.python_links {
+            dependencies.extend(
+                links
+                    .imported_files
+                    .iter()
+                    .map(|target| (file.as_str(), target.as_str())),
+            );
+        }
+        for (notebook, imported) in &self.notebook_imports {
+            dependencies.extend(
+                imported
+                    .iter()
+                    .map(|target| (notebook.as_str(), target.as_str())),
+            );
+        }
+        dependencies.retain(|(source, target)| source != target);
+        dependencies
+    }
 
-            // __init__.py is too risky to flag — always exempt.
-            if filename == "__init__.py" {
-                continue;
+    /// Every name found in a Python string that loads code by name
+    /// (`INSTALLED_APPS`, `"app.main:create_app"`; see [`FileLinks::string_names`])
+    /// in the files `include` accepts, mapped to its first `(file, line)` in
+    /// file order.
+    pub(crate) fn string_names(
+        &self,
+        include: impl Fn(&str) -> bool,
+    ) -> HashMap<&str, (&str, u32)> {
+        let mut files: Vec<&String> = self.python_links.keys().filter(|f| include(f)).collect();
+        files.sort();
+        let mut names: HashMap<&str, (&str, u32)> = HashMap::new();
+        for file in files {
+            for (name, line) in &self.python_links[file].string_names {
+                names.entry(name).or_insert((file, *line));
             }
+        }
+        names
+    }
 
-            // Known entry points are never orphans.
-            if ENTRY_POINT_FILENAMES.contains(&filename) {
-                continue;
-            }
+    /// Writes the graph as Graphviz DOT: one `cluster_*` subgraph per file,
+    /// nodes labelled with their qualified name.
+    ///
+    /// Returns [`AnatomistError::UnknownSymbol`] when `options.focus` matches nothing.
+    pub fn export_dot<W: Write>(
+        &self,
+        mut writer: W,
+        options: &ExportOptions,
+    ) -> Result<(), AnatomistError> {
+        let Selection { nodes, edges } = self.export_selection(options)?;
 
-            // Plugin/framework-managed directories: files here are discovered dynamically,
-            // so they have no incoming import edges by design — not a true orphan.
-            if file_path
-                .split('/')
-                .any(|seg| PLUGIN_ORPHAN_EXEMPT_DIRS.contains(&seg))
-            {
-                continue;
+        let mut files: BTreeMap<&str, Vec<&SymbolEntry>> = BTreeMap::new();
+        for entry in &nodes {
+            files
+                .entry(entry.file_path.as_str())
+                .or_default()
+                .push(entry);
+        }
+
+        writeln!(writer, "digraph references {{")?;
+        writeln!(writer, "    rankdir=LR;")?;
+        writeln!(writer, "    node [shape=box];")?;
+        for (i, (file, entries)) in files.iter().enumerate() {
+            writeln!(writer, "    subgraph cluster_{} {{", i)?;
+            writeln!(writer, "        label=\"{}\";", dot_escape(file))?;
+            for entry in entries {
+                writeln!(
+                    writer,
+                    "        n{} [label=\"{}\"];",
+                    entry.id,
+                    dot_escape(&entry.qualified_name)
+                )?;
             }
+            writeln!(writer, "    }}")?;
+        }
+        for (src, tgt) in &edges {
+            writeln!(writer, "    n{} -> n{};", src, tgt)?;
+        }
+        writeln!(writer, "}}")?;
+        Ok(())
+    }
 
-            // Set of this file's own symbol IDs for quick cross-file check.
-            let my_ids: HashSet<u64> = symbol_ids.iter().copied().collect();
+    /// Writes the graph as a JSON adjacency list: `{"nodes": [...]}`, where each
+    /// node is its [`SymbolEntry`] plus an `edges` array of referenced symbol ids.
+    ///
+    /// Returns [`AnatomistError::UnknownSymbol`] when `options.focus` matches nothing.
+    pub fn export_json<W: Write>(
+        &self,
+        writer: W,
+        options: &ExportOptions,
+    ) -> Result<(), AnatomistError> {
+        #[derive(serde::Serialize)]
+        struct JsonNode<'a> {
+            #[serde(flatten)]
+            entry: &'a SymbolEntry,
+            edges: Vec<u64>,
+        }
+        #[derive(serde::Serialize)]
+        struct JsonGraph<'a> {
+            nodes: Vec<JsonNode<'a>>,
+        }
 
-            // A file has an incoming dependency if any of its symbols is
-            // referenced (incoming edge) by a symbol from a different file.
-            let has_incoming = symbol_ids.iter().any(|&sym_id| {
-                let node = match id_to_node.get(&sym_id) {
-                    Some(&n) => n,
-                    None => return false,
-                };
-                self.graph
-                    .edges_directed(node, Direction::Incoming)
-                    .any(|edge| {
-                        let src_weight = match self.graph.node_weight(edge.source()) {
-                            Some(&w) => w,
-                            None => return false,
-                        };
-                        // The caller must live in a different file.
-                        let src_file = id_to_file.get(&src_weight).copied().unwrap_or_default();
-                        src_file != file_path.as_str() && !my_ids.contains(&src_weight)
-                    })
-            });
+        let Selection { nodes, edges } = self.export_selection(options)?;
+        let mut adjacency: HashMap<u64, Vec<u64>> = HashMap::new();
+        for &(src, tgt) in &edges {
+            adjacency.entry(src).or_default().push(tgt);
+        }
+        let doc = JsonGraph {
+            nodes: nodes
+                .into_iter()
+                .map(|entry| JsonNode {
+                    edges: adjacency.remove(&entry.id).unwrap_or_default(),
+                    entry,
+                })
+                .collect(),
+        };
+        serde_json::to_writer_pretty(writer, &doc).map_err(std::io::Error::from)?;
+        Ok(())
+    }
+
+    /// The part of the graph kept by `options`.
+    fn export_selection(&self, options: &ExportOptions) -> Result<Selection<'_>, AnatomistError> {
+        let id_to_node: HashMap<u64, NodeIndex> = self
+            .graph
+            .node_indices()
+            .map(|n| (self.graph[n], n))
+            .collect();
 
-            if !has_incoming {
-                orphans.push(file_path.clone());
+        let keep: HashSet<u64> = match &options.focus {
+            None => id_to_node.keys().copied().collect(),
+            Some(focus) => {
+                // Breadth-first over edges in both directions, `depth` hops out.
+                let mut frontier: Vec<NodeIndex> = self
+                    .registry
+                    .entries
+                    .iter()
+                    .filter(|e| base_qualified_name(&e.qualified_name) == focus.as_str())
+                    .filter_map(|e| id_to_node.get(&e.id).copied())
+                    .collect();
+                if frontier.is_empty() {
+                    return Err(AnatomistError::UnknownSymbol(focus.clone()));
+                }
+                let mut keep: HashSet<u64> = frontier.iter().map(|&n| self.graph[n]).collect();
+                for _ in 0..options.depth {
+                    let mut next = Vec::new();
+                    for node in frontier {
+                        for neighbor in self.graph.neighbors_undirected(node) {
+                            if keep.insert(self.graph[neighbor]) {
+                                next.push(neighbor);
+                            }
+                        }
+                    }
+                    frontier = next;
+                }
+                keep
             }
-        }
+        };
 
-        orphans.sort();
-        orphans
+        let mut nodes: Vec<&SymbolEntry> = self
+            .registry
+            .entries
+            .iter()
+            .filter(|e| keep.contains(&e.id))
+            .collect();
+        nodes.sort_by(|a, b| (&a.file_path, a.start_byte).cmp(&(&b.file_path, b.start_byte)));
+
+        let edges = self
+            .graph
+            .edge_references()
+            .map(|e| (self.graph[e.source()], self.graph[e.target()]))
+            .filter(|(src, tgt)| keep.contains(src) && keep.contains(tgt))
+            .collect();
+        Ok(Selection { nodes, edges })
+    }
+}
+
+/// Nodes and edges chosen for export.
+struct Selection<'a> {
+    /// Registry entries, ordered by file, then position.
+    nodes: Vec<&'a SymbolEntry>,
+    /// De-duplicated `(source_id, target_id)` pairs.
+    edges: BTreeSet<(u64, u64)>,
+}
+
+/// Restricts [`ReferenceGraph::export_dot`] / [`ReferenceGraph::export_json`] output.
+#[derive(Debug, Clone)]
+pub struct ExportOptions {
+    /// Qualified name of the symbol(s) to centre on; `None` exports everything.
+    pub focus: Option<String>,
+    /// Hops (callers and callees) kept around `focus`.
+    pub depth: usize,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            focus: None,
+            depth: 2,
+        }
     }
 }
 
+/// Escapes `s` for use inside a double-quoted DOT string.
+fn dot_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
 static CALL_QUERY: OnceLock<Query> = OnceLock::new();
 
-/// A call expression extracted from Python source.
+/// A call expression extracted from Python (or C++) source.
 struct CallSite {
     /// The called name ("func" or "method" in `obj.method()`).
     name: String,
     /// Start byte of the captured identifier node.
     byte_offset: u32,
+    /// 1-based line of the captured identifier node.
+    line: u32,
+    /// Object identifier of an attribute call (`"self"` in `self.method()`).
+    receiver: Option<String>,
 }
 
 /// Extracts all call sites from a parsed Python source tree.
@@ -168,9 +639,18 @@ fn extract_calls(source: &[u8], root: Node) -> Vec<CallSite> {
                 Ok(t) => t.to_string(),
                 Err(_) => continue,
             };
+            let receiver = node
+                .parent()
+                .filter(|p| p.kind() == "attribute")
+                .and_then(|p| p.child_by_field_name("object"))
+                .filter(|o| o.kind() == "identifier")
+                .and_then(|o| o.utf8_text(source).ok())
+                .map(str::to_string);
             calls.push(CallSite {
                 name: text,
                 byte_offset: node.start_byte() as u32,
+                line: node.start_position().row as u32 + 1,
+                receiver,
             });
         }
     }
@@ -178,572 +658,3187 @@ fn extract_calls(source: &[u8], root: Node) -> Vec<CallSite> {
     calls
 }
 
-/// Finds the innermost entity containing `byte_offset`.
-///
-/// `entries` is `(symbol_id, start_byte, end_byte)` for all entities in the source file.
-/// Returns the entry with the smallest span enclosing the offset.
-/// With a `__MODULE__` sentinel entry covering the whole file, this always returns `Some`.
-fn find_containing_entity(byte_offset: u32, entries: &[(u64, u32, u32)]) -> Option<u64> {
-    entries
-        .iter()
-        .filter(|(_, start, end)| *start <= byte_offset && byte_offset < *end)
-        .min_by_key(|(_, start, end)| end - start)
-        .map(|(id, _, _)| *id)
-}
-
-/// Builds a reference graph from a polyglot project directory.
-///
-/// # Algorithm
-/// 1. Walk directory for `.py` and C++ (`.cpp`, `.cxx`, `.cc`, `.h`, `.hpp`) files.
-/// 2. **Pass 1**: Extract Python entities, populate registry, add graph nodes.
-/// 3. **Pass 1b**: Extract C++ entities, register symbols and `__MODULE__` sentinels.
-/// 4. **Pass 2**: Re-parse Python files for imports + call sites; add symbol-to-symbol edges.
-/// 5. **Pass 2b**: Scan C++ files for `#include "..."` directives; add file-level edges.
-///
-/// # Memory
-/// - Registry stores all symbols (~80 bytes per symbol)
-/// - Graph stores node indices (8 bytes per node) + edges (~16 bytes per edge)
-/// - Per-file `Vec<Entity>` is dropped after indexing
-pub fn build_reference_graph(
-    project_root: &Path,
-    host: &mut ParserHost,
-) -> Result<ReferenceGraph, AnatomistError> {
-    let root = dunce::canonicalize(project_root)?;
-    let py_files = walk_py_files(&root)?;
-    let cpp_files = walk_cpp_files(&root)?;
-
-    let mut registry = SymbolRegistry::new();
-    let mut graph = DiGraph::new();
-    let mut file_symbols: HashMap<String, Vec<u64>> = HashMap::new();
-    let mut id_to_node: HashMap<u64, NodeIndex> = HashMap::new();
-    let mut all_entities: Vec<Entity> = Vec::new();
-    let mut stats = GraphStats {
-        file_count: py_files.len() + cpp_files.len(),
-        ..Default::default()
-    };
-
-    // PASS 1: Index symbols
-    for path in &py_files {
-        match host.dissect(path) {
-            Ok(entities) => {
-                // Compute canonical file key for __MODULE__ sentinel
-                let canonical = dunce::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
-                let file_key = normalize_path(&canonical);
-                let file_size = std::fs::metadata(path)
-                    .map(|m| m.len().min(u32::MAX as u64) as u32)
-                    .unwrap_or(0);
-
-                // Insert __MODULE__ virtual entry covering the entire file.
-                // Module-level calls (outside any func/class) are attributed to this symbol.
-                let module_sym_id = format!("{}::__MODULE__", file_key);
-                let module_hash = symbol_hash(&module_sym_id);
-                registry.insert(SymbolEntry {
-                    id: module_hash,
-                    name: "__MODULE__".to_string(),
-                    qualified_name: "__MODULE__".to_string(),
-                    file_path: file_key.clone(),
-                    entity_type: 0,
-                    start_line: 1,
-                    end_line: 0,
-                    start_byte: 0,
-                    end_byte: file_size,
-                    structural_hash: 0,
-                    protected_by: None,
-                });
-                let module_node = graph.add_node(module_hash);
-                id_to_node.insert(module_hash, module_node);
-                file_symbols.entry(file_key).or_default().push(module_hash);
-
-                for entity in entities {
-                    let symbol_id = entity.symbol_id();
-                    let hash = symbol_hash(&symbol_id);
-
-                    let entry = SymbolEntry {
-                        id: hash,
-                        name: entity.name.clone(),
-                        qualified_name: entity.qualified_name.clone(),
-                        file_path: entity.file_path.clone(),
-                        entity_type: entity.entity_type as u8,
-                        start_line: entity.start_line,
-                        end_line: entity.end_line,
-                        start_byte: entity.start_byte,
-                        end_byte: entity.end_byte,
-                        structural_hash: entity.structural_hash.unwrap_or(0),
-                        protected_by: entity.protected_by,
-                    };
-                    registry.insert(entry);
-
-                    let node_idx = graph.add_node(hash);
-                    id_to_node.insert(hash, node_idx);
+static DYNAMIC_QUERY: OnceLock<Query> = OnceLock::new();
 
-                    file_symbols
-                        .entry(entity.file_path.clone())
-                        .or_default()
-                        .push(hash);
+/// Attribute name accepted by a dynamic-access string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum NamePattern {
+    /// Plain literal: `getattr(obj, "compute_total")`.
+    Exact(String),
+    /// Static text around f-string interpolations: `f"handle_{event}"`.
+    Affix { prefix: String, suffix: String },
+}
 
-                    all_entities.push(entity);
-                    stats.symbol_count += 1;
-                }
-            }
-            Err(_) => {
-                stats.parse_errors += 1;
+impl NamePattern {
+    fn matches(&self, name: &str) -> bool {
+        match self {
+            NamePattern::Exact(exact) => name == exact,
+            NamePattern::Affix { prefix, suffix } => {
+                name.len() > prefix.len() + suffix.len()
+                    && name.starts_with(prefix.as_str())
+                    && name.ends_with(suffix.as_str())
             }
         }
     }
+}
 
-    // Build lookup: file_path -> [(name, id)]
-    let mut file_to_names: HashMap<String, Vec<(String, u64)>> = HashMap::new();
-    for entry in &registry.entries {
-        file_to_names
-            .entry(entry.file_path.clone())
-            .or_default()
-            .push((entry.name.clone(), entry.id));
-    }
+/// A string literal argument of `getattr`/`setattr`/`hasattr` or
+/// `importlib.import_module`.
+struct DynamicName {
+    pattern: NamePattern,
+    /// `true` for `import_module("pkg.mod")`: the literal is a dotted module path.
+    module: bool,
+    /// Call as written, for provenance (`getattr(…, f"handle_{event}")`).
+    call: String,
+    byte_offset: u32,
+    line: u32,
+}
 
-    // PASS 2: Link imports via call sites (symbol-to-symbol edges)
-    let mut parser = Parser::new();
-    parser
-        .set_language(&tree_sitter_python::LANGUAGE.into())
-        .map_err(|e| AnatomistError::ParseFailure(format!("Language load failed: {:?}", e)))?;
+/// Extracts the string literals naming attributes (`getattr`, `setattr`,
+/// `hasattr`; second argument) or modules (`import_module`; first argument).
+fn extract_dynamic_names(source: &[u8], root: Node) -> Vec<DynamicName> {
+    let query = DYNAMIC_QUERY.get_or_init(|| {
+        Query::new(
+            &tree_sitter_python::LANGUAGE.into(),
+            r#"
+            (call
+              function: [
+                (identifier) @func
+                (attribute attribute: (identifier) @func)
+              ]
+              arguments: (argument_list) @args)
+            "#,
+        )
+        .expect("Invalid dynamic access query")
+    });
 
-    for source_path in &py_files {
-        let file = match File::open(source_path) {
-            Ok(f) => f,
-            Err(_) => continue,
+    let mut cursor = QueryCursor::new();
+    let mut matches = cursor.matches(query, root, source);
+    let mut names = Vec::new();
+
+    while let Some(m) = matches.next() {
+        let (Some(func), Some(args)) = (m.captures.first(), m.captures.get(1)) else {
+            continue;
         };
-        let mmap = match unsafe { Mmap::map(&file) } {
-            Ok(m) => m,
-            Err(_) => continue,
+        let Ok(func) = func.node.utf8_text(source) else {
+            continue;
         };
-        let source = &mmap[..];
-
-        let tree = match parser.parse(source, None) {
-            Some(t) => t,
-            None => continue,
+        let (position, module) = match func {
+            "getattr" | "setattr" | "hasattr" => (1, false),
+            "import_module" => (0, true),
+            _ => continue,
         };
-
-        let imports = match extract_imports(source, tree.root_node()) {
-            Ok(imp) => imp,
-            Err(_) => continue,
+        let mut walker = args.node.walk();
+        let Some(arg) = args.node.named_children(&mut walker).nth(position) else {
+            continue;
         };
-
-        let source_canonical = match dunce::canonicalize(source_path) {
-            Ok(p) => p,
-            Err(_) => continue,
+        let Some(pattern) = string_pattern(arg, source) else {
+            continue;
         };
-        let source_file_key = normalize_path(&source_canonical);
+        let Ok(literal) = arg.utf8_text(source) else {
+            continue;
+        };
+        let call = if module {
+            format!("{}({})", func, literal)
+        } else {
+            format!("{}(…, {})", func, literal)
+        };
+        names.push(DynamicName {
+            pattern,
+            module,
+            call,
+            byte_offset: arg.start_byte() as u32,
+            line: arg.start_position().row as u32 + 1,
+        });
+    }
 
-        // Build import_targets: name -> [target_symbol_id]
-        let mut import_targets: HashMap<String, Vec<u64>> = HashMap::new();
-        for import in &imports {
-            let target_path = match resolve_import(&source_canonical, &import.raw_path, &root) {
-                Some(p) => p,
-                None => continue,
-            };
-            let target_file_key = normalize_path(&target_path);
-            let target_names = match file_to_names.get(&target_file_key) {
-                Some(names) => names,
-                None => continue,
-            };
-            for (name, id) in target_names {
-                if import.names.is_empty() || import.names.contains(name) {
-                    import_targets.entry(name.clone()).or_default().push(*id);
-                }
-            }
-        }
+    names
+}
 
-        if import_targets.is_empty() {
-            continue;
+/// The name a string literal node can stand for. F-strings yield their static
+/// prefix and suffix; `None` when nothing static remains or for escapes.
+fn string_pattern(node: Node, source: &[u8]) -> Option<NamePattern> {
+    if node.kind() != "string" {
+        return None;
+    }
+    let mut walker = node.walk();
+    let mut parts: Vec<Option<&str>> = Vec::new();
+    let mut formatted = false;
+    for child in node.children(&mut walker) {
+        match child.kind() {
+            "string_start" => formatted = child.utf8_text(source).ok()?.contains(['f', 'F']),
+            "string_content" => parts.push(Some(child.utf8_text(source).ok()?)),
+            "interpolation" => parts.push(None),
+            "string_end" => {}
+            _ => return None,
         }
+    }
+    let holes = parts.iter().filter(|p| p.is_none()).count();
+    if !formatted || holes == 0 {
+        let text: String = parts.into_iter().flatten().collect();
+        return (!text.is_empty()).then_some(NamePattern::Exact(text));
+    }
+    let prefix: String = parts.iter().map_while(|p| *p).collect();
+    let mut suffix: Vec<&str> = parts.iter().rev().map_while(|p| *p).collect();
+    suffix.reverse();
+    let suffix = suffix.concat();
+    if prefix.is_empty() && suffix.is_empty() {
+        return None;
+    }
+    Some(NamePattern::Affix { prefix, suffix })
+}
 
-        // Build source_entries: (symbol_id, start_byte, end_byte) for containment lookup
-        let source_entries: Vec<(u64, u32, u32)> = registry
-            .entries
-            .iter()
-            .filter(|e| e.file_path == source_file_key)
-            .map(|e| (e.id, e.start_byte, e.end_byte))
-            .collect();
+static STRING_QUERY: OnceLock<Query> = OnceLock::new();
 
-        // Extract call sites and emit directed edges
-        let calls = extract_calls(source, tree.root_node());
-        for call in calls {
-            let target_ids = match import_targets.get(&call.name) {
-                Some(ids) => ids,
-                None => continue,
+/// Names in the string literals that hand code to something loading it by
+/// name, with their 1-based line:
+///
+/// - entries of a collection literal that is assigned or passed as an
+///   argument: `__all__`, `INSTALLED_APPS`, Celery `task_routes`,
+///   `entry_points={"console_scripts": ["run = app.cli:main"]}`;
+/// - a whole dotted path or `"module:attr"` string that is assigned or passed
+///   as an argument: `ROOT_URLCONF = "app.urls"`, `uvicorn.run("app.main:app")`.
+///
+/// Each path yields its segments and dotted prefixes (`app.cli:main` gives
+/// `app`, `cli`, `main` and `app.cli`), so both symbol and module names
+/// match. Other strings (docstrings, messages, `getattr` names, which
+/// [`extract_dynamic_names`] handles) yield nothing.
+fn extract_string_names(source: &[u8], root: Node) -> Vec<(String, u32)> {
+    let query = STRING_QUERY.get_or_init(|| {
+        Query::new(
+            &tree_sitter_python::LANGUAGE.into(),
+            "(string (string_content) @content)",
+        )
+        .expect("Invalid string query")
+    });
+
+    let mut cursor = QueryCursor::new();
+    let mut matches = cursor.matches(query, root, source);
+    let mut names = Vec::new();
+    while let Some(m) = matches.next() {
+        for capture in m.captures {
+            let content = capture.node;
+            let Some(in_collection) = content.parent().and_then(string_context) else {
+                continue;
             };
-            let caller_id = match find_containing_entity(call.byte_offset, &source_entries) {
-                Some(id) => id,
-                None => continue,
+            let Ok(text) = content.utf8_text(source) else {
+                continue;
             };
-            let src_node = match id_to_node.get(&caller_id) {
-                Some(&n) => n,
-                None => continue,
+            if !in_collection && !is_dotted_reference(text) {
+                continue;
+            }
+            let start_line = content.start_position().row as u32 + 1;
+            let tokens = text.split('\n').enumerate().flat_map(|(row, line)| {
+                line.split(|c: char| !(c.is_alphanumeric() || c == '_' || c == '.'))
+                    .map(move |token| (token.trim_matches('.'), start_line + row as u32))
+            });
+            for (token, line) in tokens {
+                for (end, _) in token.match_indices('.').chain([(token.len(), "")]) {
+                    if end > 0 && token[..end].contains('.') {
+                        names.push((token[..end].to_string(), line));
+                    }
+                }
+                names.extend(
+                    token
+                        .split('.')
+                        .filter(|segment| !segment.is_empty())
+                        .map(|segment| (segment.to_string(), line)),
+                );
+            }
+        }
+    }
+    names
+}
+
+/// Where the string literal `string` hands its text over: `Some(true)` inside
+/// a collection literal, `Some(false)` as a bare value, when the collection or
+/// value is the right side of an assignment or an argument; `None` anywhere
+/// else (docstrings, operands, comparisons, ...).
+fn string_context(string: Node) -> Option<bool> {
+    let mut in_collection = false;
+    let mut value = string;
+    while let Some(parent) = value.parent() {
+        match parent.kind() {
+            "list" | "tuple" | "set" | "dictionary" | "pair" => in_collection = true,
+            "concatenated_string" | "parenthesized_expression" => {}
+            _ => break,
+        }
+        value = parent;
+    }
+    let holder = value.parent()?;
+    let holds = match holder.kind() {
+        "assignment" | "augmented_assignment" => holder.child_by_field_name("right") == Some(value),
+        "keyword_argument" => holder.child_by_field_name("value") == Some(value),
+        "argument_list" => true,
+        _ => false,
+    };
+    holds.then_some(in_collection)
+}
+
+/// `true` for `pkg.module`, `pkg.module.Class` and `pkg.module:attr`: names
+/// joined by dots, with at most one colon before the last part.
+fn is_dotted_reference(text: &str) -> bool {
+    let (module, attr) = text.split_once(':').unwrap_or((text, ""));
+    let is_name = |part: &str| {
+        part.chars()
+            .next()
+            .is_some_and(|c| c.is_alphabetic() || c == '_')
+            && part.chars().all(|c| c.is_alphanumeric() || c == '_')
+    };
+    (module.contains('.') || text.contains(':'))
+        && module.split('.').all(is_name)
+        && (attr.is_empty() && !text.contains(':') || attr.split('.').all(is_name))
+}
+
+static CPP_CALL_QUERY: OnceLock<Query> = OnceLock::new();
+
+/// Extracts call sites from a parsed C++ source tree: `f()`, `obj.f()`,
+/// `ptr->f()`, and `ns::f()` / `Foo::f()`.
+fn extract_cpp_calls(source: &[u8], root: Node) -> Vec<CallSite> {
+    let query = CPP_CALL_QUERY.get_or_init(|| {
+        Query::new(
+            &tree_sitter_cpp::LANGUAGE.into(),
+            r#"
+            (call_expression
+              function: (identifier) @callee)
+
+            (call_expression
+              function: (field_expression
+                field: (field_identifier) @callee))
+
+            (call_expression
+              function: (qualified_identifier
+                name: (identifier) @callee))
+            "#,
+        )
+        .expect("Invalid C++ call query")
+    });
+
+    let mut cursor = QueryCursor::new();
+    let mut matches = cursor.matches(query, root, source);
+    let mut calls = Vec::new();
+
+    while let Some(m) = matches.next() {
+        for capture in m.captures {
+            let node = capture.node;
+            let Ok(text) = node.utf8_text(source) else {
+                continue;
             };
-            for &target_id in target_ids {
-                if let Some(&tgt_node) = id_to_node.get(&target_id) {
-                    graph.add_edge(src_node, tgt_node, ());
-                    stats.edge_count += 1;
+            calls.push(CallSite {
+                name: text.to_string(),
+                byte_offset: node.start_byte() as u32,
+                line: node.start_position().row as u32 + 1,
+                receiver: None,
+            });
+        }
+    }
+
+    calls
+}
+
+/// What Pass 2d needs from one parsed JS/TS file.
+struct ScriptModule {
+    /// Canonical path, for resolving relative specifiers.
+    path: PathBuf,
+    imports: Vec<EsImport>,
+    /// Local name of the `export default` declaration.
+    default_export: Option<String>,
+    /// References with the edge kind they produce.
+    refs: Vec<(CallSite, EdgeKind)>,
+}
+
+/// Extracts references from a parsed JS/TS source tree: calls (`f()`, `obj.f()`,
+/// `this.f()`), `new Foo()`, JSX elements (`<Foo />`), and `extends Base`.
+///
+/// Member references carry their object as `receiver` only when it is `this`.
+fn extract_script_refs(source: &[u8], root: Node) -> Vec<(CallSite, EdgeKind)> {
+    fn site(source: &[u8], name: Node, receiver: Option<Node>) -> Option<CallSite> {
+        Some(CallSite {
+            name: name.utf8_text(source).ok()?.to_string(),
+            byte_offset: name.start_byte() as u32,
+            line: name.start_position().row as u32 + 1,
+            receiver: receiver
+                .filter(|r| r.kind() == "this")
+                .map(|_| "this".to_string()),
+        })
+    }
+    // The referenced name of `f` / `obj.f` / `<Foo.Bar>`.
+    fn callee(source: &[u8], node: Node) -> Option<CallSite> {
+        match node.kind() {
+            "identifier" | "type_identifier" => site(source, node, None),
+            "member_expression" | "nested_identifier" => site(
+                source,
+                node.child_by_field_name("property")?,
+                node.child_by_field_name("object"),
+            ),
+            _ => None,
+        }
+    }
+
+    let mut refs = Vec::new();
+    let mut stack = vec![root];
+    while let Some(node) = stack.pop() {
+        let reference = match node.kind() {
+            "call_expression" => node
+                .child_by_field_name("function")
+                .and_then(|f| callee(source, f))
+                .map(|c| (c, EdgeKind::Call)),
+            "new_expression" => node
+                .child_by_field_name("constructor")
+                .and_then(|f| callee(source, f))
+                .map(|c| (c, EdgeKind::Call)),
+            "jsx_opening_element" | "jsx_self_closing_element" => node
+                .child_by_field_name("name")
+                .and_then(|f| callee(source, f))
+                .map(|c| (c, EdgeKind::Call)),
+            "class_heritage" | "extends_clause" => {
+                // JS: `class_heritage > identifier`; TS: `class_heritage > extends_clause`.
+                let mut cursor = node.walk();
+                let bases: Vec<Node> = node.named_children(&mut cursor).collect();
+                refs.extend(
+                    bases
+                        .into_iter()
+                        .filter_map(|b| callee(source, b))
+                        .map(|c| (c, EdgeKind::Inherit)),
+                );
+                None
+            }
+            _ => None,
+        };
+        refs.extend(reference);
+        stack.extend(node.named_children(&mut node.walk()));
+    }
+
+    refs.sort_by_key(|(c, _)| c.byte_offset);
+    refs
+}
+
+/// Symbol ids `name` refers to when imported from the JS/TS module `target`.
+///
+/// `"default"` resolves to the `export default` declaration. Names the module
+/// does not define are followed through `export ... from` re-exports, at most
+/// [`MAX_REEXPORT_DEPTH`] hops.
+fn script_export_ids(
+    target: &str,
+    name: &str,
+    modules: &HashMap<String, ScriptModule>,
+    names: &HashMap<String, Vec<(String, u64)>>,
+    depth: usize,
+) -> Vec<u64> {
+    let Some(module) = modules.get(target) else {
+        return Vec::new();
+    };
+    let name = match (name, &module.default_export) {
+        ("default", Some(default)) => default.as_str(),
+        ("default", None) => return Vec::new(),
+        _ => name,
+    };
+    let ids: Vec<u64> = names
+        .get(target)
+        .into_iter()
+        .flatten()
+        .filter(|(n, _)| n == name)
+        .map(|(_, id)| *id)
+        .collect();
+    if !ids.is_empty() || depth >= MAX_REEXPORT_DEPTH {
+        return ids;
+    }
+
+    for reexport in module.imports.iter().filter(|i| i.reexport) {
+        let original = match reexport.names.iter().find(|(_, local)| local == name) {
+            Some((original, _)) => original.as_str(),
+            None if reexport.namespace => name,
+            None => continue,
+        };
+        let Some(next) = resolve_es_import(&module.path, &reexport.specifier) else {
+            continue;
+        };
+        let ids = script_export_ids(&normalize_path(&next), original, modules, names, depth + 1);
+        if !ids.is_empty() {
+            return ids;
+        }
+    }
+
+    Vec::new()
+}
+
+/// Syntactic positions where a name read may refer to any symbol, not just a
+/// module constant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RefContext {
+    /// `@audit`, `@registry.register`, `@retry(handler=hook)`.
+    Decorator,
+    /// `class Child(Base, metaclass=Meta)`.
+    Superclass,
+    /// Parameter, return, and variable annotations.
+    Annotation,
+}
+
+/// Byte ranges of every decorator, class superclass list, and `type`
+/// annotation node in a parsed Python source tree.
+fn reference_contexts(root: Node) -> Vec<(u32, u32, RefContext)> {
+    fn walk(node: Node<'_>, out: &mut Vec<(u32, u32, RefContext)>) {
+        let context = match node.kind() {
+            "decorator" => Some(RefContext::Decorator),
+            "type" => Some(RefContext::Annotation),
+            "argument_list"
+                if node.parent().is_some_and(|p| {
+                    p.kind() == "class_definition"
+                        && p.child_by_field_name("superclasses") == Some(node)
+                }) =>
+            {
+                Some(RefContext::Superclass)
+            }
+            _ => None,
+        };
+        if let Some(context) = context {
+            out.push((node.start_byte() as u32, node.end_byte() as u32, context));
+        }
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            walk(child, out);
+        }
+    }
+    let mut out = Vec::new();
+    walk(root, &mut out);
+    out
+}
+
+/// Extracts every identifier read (not bound) in a parsed Python source tree.
+///
+/// Module constants and type aliases are read, not called, so
+/// [`extract_calls`] never sees them. Names inside string annotations
+/// (forward references) count as reads at the string's offset. Binding
+/// positions — assignment and loop targets, `def`/`class` names, parameters,
+/// keyword-argument names, `global` declarations, and import statements — are
+/// skipped. Over-reporting a read only keeps a symbol alive, so anything not
+/// clearly a binding counts.
+fn extract_reads(source: &[u8], root: Node) -> Vec<CallSite> {
+    fn is_binding(node: Node<'_>, parent: Node<'_>) -> bool {
+        let is_field = |field: &str| parent.child_by_field_name(field) == Some(node);
+        match parent.kind() {
+            "assignment" | "for_statement" | "for_in_clause" => is_field("left"),
+            "function_definition" | "class_definition" | "keyword_argument" => is_field("name"),
+            "default_parameter" | "typed_default_parameter" => is_field("name"),
+            "pattern_list"
+            | "tuple_pattern"
+            | "list_pattern"
+            | "as_pattern_target"
+            | "parameters"
+            | "lambda_parameters"
+            | "typed_parameter"
+            | "list_splat_pattern"
+            | "dictionary_splat_pattern"
+            | "global_statement"
+            | "nonlocal_statement" => true,
+            _ => false,
+        }
+    }
+
+    fn walk(node: Node<'_>, source: &[u8], out: &mut Vec<CallSite>) {
+        match node.kind() {
+            "import_statement" | "import_from_statement" | "future_import_statement" => return,
+            // Forward references in annotations: `def f(v: "Vector[int]")`.
+            // `pkg.Name` reads `pkg`, then `Name` on it, as the code would.
+            "string" => {
+                for path in forward_ref(node, source)
+                    .map(forward_ref_names)
+                    .unwrap_or_default()
+                {
+                    let mut receiver: Option<&str> = None;
+                    for (end, segment) in dotted_segments(&path) {
+                        out.push(CallSite {
+                            name: segment.to_string(),
+                            byte_offset: node.start_byte() as u32,
+                            line: node.start_position().row as u32 + 1,
+                            receiver: receiver.map(str::to_string),
+                        });
+                        receiver = Some(&path[..end]);
+                    }
+                }
+                return;
+            }
+            "identifier" => {
+                let Some(parent) = node.parent() else {
+                    return;
+                };
+                if is_binding(node, parent) {
+                    return;
+                }
+                let Ok(text) = node.utf8_text(source) else {
+                    return;
+                };
+                // `obj.NAME`: only the object is a plain read.
+                let receiver = (parent.kind() == "attribute"
+                    && parent.child_by_field_name("attribute") == Some(node))
+                .then(|| {
+                    parent
+                        .child_by_field_name("object")
+                        .and_then(|o| o.utf8_text(source).ok())
+                        .unwrap_or_default()
+                        .to_string()
+                });
+                out.push(CallSite {
+                    name: text.to_string(),
+                    byte_offset: node.start_byte() as u32,
+                    line: node.start_position().row as u32 + 1,
+                    receiver,
+                });
+                return;
+            }
+            _ => {}
+        }
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            walk(child, source, out);
+        }
+    }
+
+    let mut reads = Vec::new();
+    walk(root, source, &mut reads);
+    reads
+}
+
+/// Dotted names inside the forward references of a parsed Python source
+/// tree (see [`forward_ref`]), with the 1-based line of each string:
+/// `Config` and `pkg.models.Order` from `def f(c: "Config") ->
+/// Optional["pkg.models.Order"]`.
+fn extract_annotation_names(source: &[u8], root: Node) -> Vec<(String, u32)> {
+    fn walk(node: Node<'_>, source: &[u8], out: &mut Vec<(String, u32)>) {
+        if node.kind() == "string" {
+            let line = node.start_position().row as u32 + 1;
+            for name in forward_ref(node, source)
+                .map(forward_ref_names)
+                .unwrap_or_default()
+            {
+                out.push((name, line));
+            }
+            return;
+        }
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            walk(child, source, out);
+        }
+    }
+    let mut names = Vec::new();
+    walk(root, source, &mut names);
+    names
+}
+
+/// The text of `string` when it is a forward reference: a whole annotation
+/// (`c: "Config"`) or a type argument inside one (`Optional["Config"]`). The
+/// values of `Literal[...]` and the metadata of `Annotated[T, ...]` are data,
+/// not types; so are f-strings and strings outside annotations.
+fn forward_ref<'s>(string: Node<'_>, source: &'s [u8]) -> Option<&'s str> {
+    let mut child = string;
+    let mut parent = string.parent()?;
+    if !matches!(parent.kind(), "type" | "subscript") {
+        return None;
+    }
+    // Whether `child` is the first argument of the subscript being climbed into.
+    let mut first_argument = true;
+    loop {
+        let base = match parent.kind() {
+            "type_parameter" => {
+                first_argument = parent.named_child(0) == Some(child);
+                None
+            }
+            "subscript" => {
+                first_argument = parent
+                    .children_by_field_name("subscript", &mut parent.walk())
+                    .next()
+                    == Some(child);
+                parent.child_by_field_name("value")
+            }
+            "generic_type" => parent.named_child(0),
+            _ => None,
+        };
+        if let Some(base) = base {
+            let base = base.utf8_text(source).ok()?;
+            match base.rsplit('.').next() {
+                Some("Literal") => return None,
+                Some("Annotated") if !first_argument => return None,
+                _ => {}
+            }
+        }
+        // The outermost `type` node is the annotation itself.
+        if parent.kind() == "type"
+            && parent
+                .parent()
+                .is_none_or(|p| !matches!(p.kind(), "type_parameter" | "subscript"))
+        {
+            break;
+        }
+        child = parent;
+        parent = parent.parent()?;
+    }
+
+    let mut cursor = string.walk();
+    let mut text = None;
+    for part in string.children(&mut cursor) {
+        match part.kind() {
+            "string_start" if part.utf8_text(source).ok()?.contains(['f', 'F', 'b', 'B']) => {
+                return None
+            }
+            "interpolation" => return None,
+            "string_content" => text = Some(part.utf8_text(source).ok()?),
+            _ => {}
+        }
+    }
+    Some(text.unwrap_or_default())
+}
+
+/// Dotted names a forward reference's text refers to: `Optional` and
+/// `pkg.models.Order` from `Optional[pkg.models.Order]`. Skips quoted text,
+/// `Literal[...]` values and `Annotated[T, ...]` metadata, as [`forward_ref`]
+/// does outside the string.
+fn forward_ref_names(text: &str) -> Vec<String> {
+    /// One open `[`: whether its arguments are data, and which argument is next.
+    struct Bracket {
+        literal: bool,
+        annotated: bool,
+        argument: usize,
+    }
+    let skipping = |open: &[Bracket]| {
+        open.iter()
+            .any(|b| b.literal || (b.annotated && b.argument > 0))
+    };
+
+    let mut names = Vec::new();
+    let mut open: Vec<Bracket> = Vec::new();
+    let mut last_name: Option<String> = None;
+    let mut chars = text.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        if c.is_alphabetic() || c == '_' {
+            let mut end = start + c.len_utf8();
+            while let Some(&(i, c)) = chars.peek() {
+                if !(c.is_alphanumeric() || c == '_' || c == '.') {
+                    break;
+                }
+                end = i + c.len_utf8();
+                chars.next();
+            }
+            let name = text[start..end].trim_end_matches('.');
+            if !skipping(&open) {
+                names.push(name.to_string());
+            }
+            last_name = Some(name.to_string());
+            continue;
+        }
+        match c {
+            '[' => {
+                let last = last_name.as_deref().and_then(|n| n.rsplit('.').next());
+                open.push(Bracket {
+                    literal: last == Some("Literal"),
+                    annotated: last == Some("Annotated"),
+                    argument: 0,
+                });
+            }
+            ']' => {
+                open.pop();
+            }
+            ',' => {
+                if let Some(bracket) = open.last_mut() {
+                    bracket.argument += 1;
+                }
+            }
+            '"' | '\'' => {
+                for (_, q) in chars.by_ref() {
+                    if q == c {
+                        break;
+                    }
+                }
+            }
+            // Digits and the rest of a number.
+            c if c.is_ascii_digit() => {
+                while chars
+                    .peek()
+                    .is_some_and(|(_, c)| c.is_alphanumeric() || *c == '.')
+                {
+                    chars.next();
                 }
             }
+            _ => {}
         }
+        last_name = None;
     }
+    names
+}
 
-    // PASS 1b: Index C++ symbols
-    for path in &cpp_files {
-        let file = match File::open(path) {
-            Ok(f) => f,
-            Err(_) => continue,
+/// Each segment of a dotted `path` with the byte offset where it ends:
+/// `("a", 1), ("b", 3)` for `a.b`.
+fn dotted_segments(path: &str) -> impl Iterator<Item = (usize, &str)> {
+    path.split('.').scan(0, |end, segment| {
+        let start = if *end == 0 { 0 } else { *end + 1 };
+        *end = start + segment.len();
+        Some((*end, segment))
+    })
+}
+
+/// Symbols a forward reference's dotted `name` resolves to in the file at
+/// `source`: a name its imports bind or it defines, `alias.Name` through an
+/// `import pkg.mod as alias` (or `from pkg import mod`), or an absolute
+/// `pkg.mod.Name`. Empty when nothing resolves.
+fn annotation_targets(
+    name: &str,
+    source: &Path,
+    imports: &[ImportInfo],
+    import_targets: &HashMap<String, Vec<u64>>,
+    local_names: Option<&Vec<(String, u64)>>,
+    roots: &[PathBuf],
+    file_to_names: &HashMap<String, Vec<(String, u64)>>,
+) -> Vec<u64> {
+    let Some((module, last)) = name.rsplit_once('.') else {
+        let mut ids = import_targets.get(name).cloned().unwrap_or_default();
+        ids.extend(
+            local_names
+                .into_iter()
+                .flatten()
+                .filter(|(n, _)| n == name)
+                .map(|(_, id)| *id),
+        );
+        return ids;
+    };
+    let (head, rest) = module
+        .split_once('.')
+        .map_or((module, None), |(head, rest)| (head, Some(rest)));
+    let bound = imports.iter().find_map(|import| {
+        if import.names.is_empty() {
+            return (import.alias.as_deref() == Some(head)).then(|| import.raw_path.clone());
+        }
+        let wanted = import
+            .names
+            .iter()
+            .find(|n| import.alias.as_deref().unwrap_or(n.as_str()) == head)?;
+        Some(if import.raw_path.ends_with('.') {
+            format!("{}{}", import.raw_path, wanted)
+        } else {
+            format!("{}.{}", import.raw_path, wanted)
+        })
+    });
+    let module = match (bound, rest) {
+        (Some(bound), Some(rest)) => format!("{}.{}", bound, rest),
+        (Some(bound), None) => bound,
+        (None, _) => module.to_string(),
+    };
+    resolve_import(source, &module, roots)
+        .and_then(|path| file_to_names.get(&normalize_path(&path)))
+        .into_iter()
+        .flatten()
+        .filter(|(n, _)| n == last)
+        .map(|(_, id)| *id)
+        .collect()
+}
+
+/// Maximum number of re-export hops followed for a single imported name.
+const MAX_REEXPORT_DEPTH: usize = 3;
+
+/// Resolves `name` through re-exports starting at `module_path` (typically a
+/// package `__init__.py` doing `from .core import name`).
+///
+/// Follows at most [`MAX_REEXPORT_DEPTH`] hops; `visited` breaks import cycles.
+/// Returns the symbol ids of the defining entities, or an empty vec.
+fn follow_reexport(
+    module_path: &Path,
+    name: &str,
+    roots: &[PathBuf],
+    file_to_names: &HashMap<String, Vec<(String, u64)>>,
+    parser: &mut Parser,
+    imports_cache: &mut HashMap<PathBuf, Vec<ImportInfo>>,
+    visited: &mut HashSet<PathBuf>,
+) -> Vec<u64> {
+    if visited.len() >= MAX_REEXPORT_DEPTH || !visited.insert(module_path.to_path_buf()) {
+        return Vec::new();
+    }
+
+    if !imports_cache.contains_key(module_path) {
+        let imports = common::source::read(module_path)
+            .ok()
+            .and_then(|source| {
+                let tree = parser.parse(&source, None)?;
+                extract_imports(&source, tree.root_node()).ok()
+            })
+            .unwrap_or_default();
+        imports_cache.insert(module_path.to_path_buf(), imports);
+    }
+    let imports = imports_cache[module_path].clone();
+
+    for import in &imports {
+        // The name this import binds locally, and what it is called at its source.
+        let original = if import.names.is_empty() {
+            // `from .core import *` (or a bare import): the name may live there as-is.
+            name
+        } else {
+            match import
+                .names
+                .iter()
+                .find(|n| import.alias.as_deref().unwrap_or(n.as_str()) == name)
+            {
+                Some(n) => n.as_str(),
+                None => continue,
+            }
         };
-        let mmap = match unsafe { Mmap::map(&file) } {
-            Ok(m) => m,
-            Err(_) => continue,
+        let Some(next) = resolve_import(module_path, &import.raw_path, roots) else {
+            continue;
         };
-        let source = &mmap[..];
-        let canonical = dunce::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
-        let file_key = normalize_path(&canonical);
-        let file_size = source.len().min(u32::MAX as usize) as u32;
+        let ids: Vec<u64> = file_to_names
+            .get(&normalize_path(&next))
+            .into_iter()
+            .flatten()
+            .filter(|(n, _)| n == original)
+            .map(|(_, id)| *id)
+            .collect();
+        if !ids.is_empty() {
+            return ids;
+        }
+        let ids = follow_reexport(
+            &next,
+            original,
+            roots,
+            file_to_names,
+            parser,
+            imports_cache,
+            visited,
+        );
+        if !ids.is_empty() {
+            return ids;
+        }
+    }
 
-        // __MODULE__ sentinel for file-level include edges
-        let module_sym_id = format!("{}::__MODULE__", file_key);
-        let module_hash = symbol_hash(&module_sym_id);
-        registry.insert(SymbolEntry {
-            id: module_hash,
-            name: "__MODULE__".to_string(),
-            qualified_name: "__MODULE__".to_string(),
-            file_path: file_key.clone(),
-            entity_type: 0,
-            start_line: 1,
-            end_line: 0,
-            start_byte: 0,
-            end_byte: file_size,
-            structural_hash: 0,
-            protected_by: None,
-        });
-        let module_node = graph.add_node(module_hash);
-        id_to_node.insert(module_hash, module_node);
-        file_symbols
-            .entry(file_key.clone())
+    Vec::new()
+}
+
+/// Names defined in submodule `name` of package `module`, for `from module import name`.
+fn submodule_names<'a>(
+    source_file: &Path,
+    module: &str,
+    name: &str,
+    roots: &[PathBuf],
+    file_to_names: &'a HashMap<String, Vec<(String, u64)>>,
+) -> impl Iterator<Item = &'a (String, u64)> {
+    submodule_path(source_file, module, name, roots)
+        .and_then(|path| file_to_names.get(&normalize_path(&path)))
+        .into_iter()
+        .flatten()
+}
+
+/// The file of submodule `name` of package `module`, when it exists.
+fn submodule_path(
+    source_file: &Path,
+    module: &str,
+    name: &str,
+    roots: &[PathBuf],
+) -> Option<PathBuf> {
+    // `from . import name` must not become `..name`.
+    let dotted = if module.ends_with('.') {
+        format!("{module}{name}")
+    } else {
+        format!("{module}.{name}")
+    };
+    resolve_import(source_file, &dotted, roots)
+}
+
+/// Finds the innermost entity containing `byte_offset`.
+///
+/// `entries` is `(symbol_id, start_byte, end_byte)` for all entities in the source file.
+/// Returns the entry with the smallest span enclosing the offset.
+/// With a `__MODULE__` sentinel entry covering the whole file, this always returns `Some`.
+fn find_containing_entity(byte_offset: u32, entries: &[(u64, u32, u32)]) -> Option<u64> {
+    entries
+        .iter()
+        .filter(|(_, start, end)| *start <= byte_offset && byte_offset < *end)
+        .min_by_key(|(_, start, end)| end - start)
+        .map(|(id, _, _)| *id)
+}
+
+/// Adds `src -> tgt`, or counts one more occurrence on the existing edge.
+/// Returns `true` when a new edge was added.
+fn add_reference(
+    graph: &mut DiGraph<u64, EdgeInfo>,
+    edge_ids: &mut HashMap<(NodeIndex, NodeIndex), EdgeIndex>,
+    src: NodeIndex,
+    tgt: NodeIndex,
+    info: EdgeInfo,
+) -> bool {
+    match edge_ids.entry((src, tgt)) {
+        Entry::Occupied(e) => {
+            graph[*e.get()].count += 1;
+            false
+        }
+        Entry::Vacant(e) => {
+            e.insert(graph.add_edge(src, tgt, info));
+            true
+        }
+    }
+}
+
+/// Strips the `#n` discriminator the parser adds to repeated definitions.
+fn base_qualified_name(qualified_name: &str) -> &str {
+    qualified_name
+        .split_once('#')
+        .map_or(qualified_name, |(base, _)| base)
+}
+
+/// Sets `module_path` on the entities dissected from the canonical Python file
+/// `path` (see [`module_path`]).
+pub(crate) fn assign_module_path(entities: &mut [Entity], path: &Path, roots: &[PathBuf]) {
+    let module = module_path(path, roots).unwrap_or_default();
+    for entity in entities {
+        entity.module_path.clone_from(&module);
+    }
+}
+
+/// The `__MODULE__` sentinel covering the whole file `file_key`: module-level
+/// calls are attributed to it and file-level edges (includes, star imports) use it.
+pub(crate) fn module_entry(file_key: &str, file_size: u32) -> SymbolEntry {
+    SymbolEntry {
+        id: symbol_hash(&format!("{}::__MODULE__", file_key)),
+        name: "__MODULE__".to_string(),
+        qualified_name: "__MODULE__".to_string(),
+        module_path: String::new(),
+        file_path: file_key.to_string(),
+        entity_type: 0,
+        start_line: 1,
+        end_line: 0,
+        start_byte: 0,
+        end_byte: file_size,
+        structural_hash: 0,
+        protected_by: None,
+        protection_detail: None,
+    }
+}
+
+/// Module-level assignments and type aliases, linked by name reads as well as calls.
+pub(crate) fn read_targets(registry: &SymbolRegistry) -> HashSet<u64> {
+    registry
+        .entries
+        .iter()
+        .filter(|e| {
+            e.entity_type == EntityType::Assignment as u8
+                || e.entity_type == EntityType::TypeAlias as u8
+        })
+        .map(|e| e.id)
+        .collect()
+}
+
+/// Lookup for import resolution: file_path -> [(name, id)].
+pub(crate) fn names_by_file(registry: &SymbolRegistry) -> HashMap<String, Vec<(String, u64)>> {
+    let mut file_to_names: HashMap<String, Vec<(String, u64)>> = HashMap::new();
+    for entry in &registry.entries {
+        file_to_names
+            .entry(entry.file_path.clone())
             .or_default()
-            .push(module_hash);
+            .push((entry.name.clone(), entry.id));
+    }
+    file_to_names
+}
+
+/// Inputs of the Python link pass (Pass 2), shared by every file it links.
+///
+/// [`crate::incremental`] reuses it to re-link single files after an edit.
+pub(crate) struct PyLinker<'a> {
+    roots: &'a [PathBuf],
+    options: &'a GraphOptions,
+    registry: &'a SymbolRegistry,
+    /// file_path -> [(name, id)] for every registered symbol.
+    file_to_names: &'a HashMap<String, Vec<(String, u64)>>,
+    /// Module-level assignments and type aliases, linked by name reads as well as calls.
+    read_target_ids: &'a HashSet<u64>,
+    id_to_node: &'a HashMap<u64, NodeIndex>,
+    parser: Parser,
+    /// Parsed imports of modules visited while following re-exports.
+    reexport_imports: HashMap<PathBuf, Vec<ImportInfo>>,
+}
+
+/// What linking one Python file produced besides its edges.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct FileLinks {
+    /// Edges added to the graph (repeat references to an existing edge excluded).
+    pub new_edges: usize,
+    /// DI registrations in this file: target symbol and registration site.
+    pub di_registered: Vec<(u64, CallRef)>,
+    /// Attribute names accessed by string, with their access site.
+    pub dynamic_names: Vec<(NamePattern, CallRef)>,
+    /// Symbols forward references in string annotations resolved to, with
+    /// their `"{file}:{line}"` site.
+    pub annotation_targets: Vec<(u64, String)>,
+    /// Last segments of forward references that resolved nowhere, matched by
+    /// name project-wide, with their `"{file}:{line}"` site.
+    pub annotation_names: Vec<(String, String)>,
+    /// Files whose symbols this file's imports resolved against, including
+    /// modules loaded with `importlib.import_module`.
+    pub imported_files: BTreeSet<String>,
+    /// Names in string literals that load code by name (see
+    /// [`extract_string_names`]), with their 1-based line.
+    pub string_names: Vec<(String, u32)>,
+    /// 1-based line of the first syntax error, if the file did not parse cleanly.
+    pub syntax_error: Option<usize>,
+    /// Import bindings the file never reads, found on the tree parsed here.
+    pub unused_imports: Vec<UnusedImport>,
+}
+
+impl FileLinks {
+    /// The diagnostic for a syntax error in `file_key`, if there was one.
+    pub(crate) fn syntax_diagnostic(&self, file_key: &str) -> Option<Diagnostic> {
+        self.syntax_error.map(|line| {
+            Diagnostic::warning(codes::SYNTAX_ERROR, format!("syntax error at line {line}"))
+                .at(file_key)
+        })
+    }
+}
+
+/// 1-based line of the first `ERROR` or `MISSING` node under `node`.
+fn first_syntax_error(node: Node) -> Option<usize> {
+    if !node.has_error() {
+        return None;
+    }
+    if node.is_error() || node.is_missing() {
+        return Some(node.start_position().row + 1);
+    }
+    let mut cursor = node.walk();
+    let first = node.children(&mut cursor).find_map(first_syntax_error);
+    first.or(Some(node.start_position().row + 1))
+}
+
+impl<'a> PyLinker<'a> {
+    pub(crate) fn new(
+        roots: &'a [PathBuf],
+        options: &'a GraphOptions,
+        registry: &'a SymbolRegistry,
+        file_to_names: &'a HashMap<String, Vec<(String, u64)>>,
+        read_target_ids: &'a HashSet<u64>,
+        id_to_node: &'a HashMap<u64, NodeIndex>,
+    ) -> Result<Self, AnatomistError> {
+        let mut parser = Parser::new();
+        parser
+            .set_language(&tree_sitter_python::LANGUAGE.into())
+            .map_err(|e| AnatomistError::ParseFailure(format!("Language load failed: {:?}", e)))?;
+        Ok(Self {
+            roots,
+            options,
+            registry,
+            file_to_names,
+            read_target_ids,
+            id_to_node,
+            parser,
+            reexport_imports: HashMap::new(),
+        })
+    }
+
+    /// Re-parses `source_path` for imports, call sites and name reads, and adds
+    /// the edges going out of its symbols.
+    pub(crate) fn link_file(
+        &mut self,
+        graph: &mut DiGraph<u64, EdgeInfo>,
+        edge_ids: &mut HashMap<(NodeIndex, NodeIndex), EdgeIndex>,
+        source_path: &Path,
+    ) -> FileLinks {
+        let (roots, options, registry) = (self.roots, self.options, self.registry);
+        let (file_to_names, read_target_ids, id_to_node) =
+            (self.file_to_names, self.read_target_ids, self.id_to_node);
+        let mut links = FileLinks::default();
+
+        let source = match common::source::read(source_path) {
+            Ok(source) => source,
+            Err(_) => return links,
+        };
+        let source = &source[..];
+
+        let tree = match self.parser.parse(source, None) {
+            Some(t) => t,
+            None => return links,
+        };
+        links.syntax_error = first_syntax_error(tree.root_node());
+
+        let mut imports = match extract_imports(source, tree.root_node()) {
+            Ok(imp) => imp,
+            Err(_) => return links,
+        };
+        // `if TYPE_CHECKING:` imports last, so the targets only they bind are known.
+        imports.sort_by_key(|import| import.type_checking);
+
+        let source_canonical = match dunce::canonicalize(source_path) {
+            Ok(p) => p,
+            Err(_) => return links,
+        };
+        let source_file_key = normalize_path(&source_canonical);
+
+        // Build import_targets: name -> [target_symbol_id]
+        let mut import_targets: HashMap<String, Vec<u64>> = HashMap::new();
+        let mut runtime_targets: Option<HashSet<u64>> = None;
+        for import in &imports {
+            if import.type_checking && runtime_targets.is_none() {
+                runtime_targets = Some(import_targets.values().flatten().copied().collect());
+            }
+            let Some(target_path) = resolve_import(&source_canonical, &import.raw_path, roots)
+            else {
+                // PEP 420 namespace package (no `__init__.py`): `from ns import mod`
+                // still binds modules.
+                for wanted in &import.names {
+                    links.imported_files.extend(
+                        submodule_path(&source_canonical, &import.raw_path, wanted, roots)
+                            .map(|p| normalize_path(&p)),
+                    );
+                    for (name, id) in submodule_names(
+                        &source_canonical,
+                        &import.raw_path,
+                        wanted,
+                        roots,
+                        file_to_names,
+                    ) {
+                        import_targets.entry(name.clone()).or_default().push(*id);
+                    }
+                }
+                continue;
+            };
+            links.imported_files.insert(normalize_path(&target_path));
+            let target_names = file_to_names.get(&normalize_path(&target_path));
+
+            if import.wildcard && !options.strict_star_imports {
+                // `from m import *`: any public symbol of `m` may be used under its bare
+                // name, so link them all from this module's sentinel.
+                let target_key = normalize_path(&target_path);
+                let module_hash = symbol_hash(&format!("{}::__MODULE__", source_file_key));
+                let kind = if import.type_checking {
+                    EdgeKind::TypeChecking
+                } else {
+                    EdgeKind::Import
+                };
+                if let Some(&src_node) = id_to_node.get(&module_hash) {
+                    for entry in registry.entries.iter().filter(|e| {
+                        e.file_path == target_key
+                            && !e.name.starts_with('_')
+                            && !e.qualified_name.contains('.')
+                    }) {
+                        if let Some(&tgt_node) = id_to_node.get(&entry.id) {
+                            if add_reference(
+                                graph,
+                                edge_ids,
+                                src_node,
+                                tgt_node,
+                                EdgeInfo::new(import.line, kind),
+                            ) {
+                                links.new_edges += 1;
+                            }
+                        }
+                    }
+                }
+            }
+
+            if import.names.is_empty() {
+                // `import m` / `import m as a`: calls reach members as `m.name()`.
+                for (name, id) in target_names.into_iter().flatten() {
+                    import_targets.entry(name.clone()).or_default().push(*id);
+                }
+                continue;
+            }
+
+            for wanted in &import.names {
+                let mut ids: Vec<u64> = target_names
+                    .into_iter()
+                    .flatten()
+                    .filter(|(n, _)| n == wanted)
+                    .map(|(_, id)| *id)
+                    .collect();
+                if ids.is_empty() {
+                    // Not defined there: follow `from .core import name` re-exports.
+                    ids = follow_reexport(
+                        &target_path,
+                        wanted,
+                        roots,
+                        file_to_names,
+                        &mut self.parser,
+                        &mut self.reexport_imports,
+                        &mut HashSet::new(),
+                    );
+                }
+                if ids.is_empty() {
+                    // `from pkg import module`: members are reached as `module.name()`,
+                    // as with `import pkg.module`.
+                    links.imported_files.extend(
+                        submodule_path(&source_canonical, &import.raw_path, wanted, roots)
+                            .map(|p| normalize_path(&p)),
+                    );
+                    for (name, id) in submodule_names(
+                        &source_canonical,
+                        &import.raw_path,
+                        wanted,
+                        roots,
+                        file_to_names,
+                    ) {
+                        import_targets.entry(name.clone()).or_default().push(*id);
+                    }
+                    continue;
+                }
+                // `from m import name as alias` binds only the alias locally.
+                let binding = import.alias.as_ref().unwrap_or(wanted);
+                import_targets
+                    .entry(binding.clone())
+                    .or_default()
+                    .extend(ids);
+            }
+        }
+        // `import m` and `from m import name` can both bind the same target.
+        for ids in import_targets.values_mut() {
+            ids.sort_unstable();
+            ids.dedup();
+        }
+        // Targets bound only by `if TYPE_CHECKING:` imports: every reference
+        // to them is an `EdgeKind::TypeChecking` edge.
+        let type_only: HashSet<u64> = match runtime_targets {
+            Some(runtime) => import_targets
+                .values()
+                .flatten()
+                .filter(|id| !runtime.contains(id))
+                .copied()
+                .collect(),
+            None => HashSet::new(),
+        };
+        let edge_kind = |target_id: u64, kind: EdgeKind| {
+            if type_only.contains(&target_id) {
+                EdgeKind::TypeChecking
+            } else {
+                kind
+            }
+        };
+
+        // Build source_entries: (symbol_id, start_byte, end_byte) for containment lookup
+        let source_entries: Vec<(u64, u32, u32)> = registry
+            .entries
+            .iter()
+            .filter(|e| e.file_path == source_file_key)
+            .map(|e| (e.id, e.start_byte, e.end_byte))
+            .collect();
+
+        // DI registrations: container arguments resolve through imports, same-file
+        // definitions, or (for dotted-path strings) absolute module resolution.
+        let local_names = file_to_names.get(&source_file_key);
+        for reg in extract_registrations(source, tree.root_node(), &options.di_rules) {
+            let mut target_ids: Vec<u64> = Vec::new();
+            match &reg.target {
+                DiTarget::Name(name) => {
+                    if let Some(ids) = import_targets.get(name) {
+                        target_ids.extend(ids);
+                    }
+                    if let Some(names) = local_names {
+                        target_ids
+                            .extend(names.iter().filter(|(n, _)| n == name).map(|(_, id)| *id));
+                    }
+                }
+                DiTarget::DottedPath(path) => {
+                    let Some((module, name)) = path.rsplit_once('.') else {
+                        continue;
+                    };
+                    let target_key = resolve_import(&source_canonical, module, roots)
+                        .map(|p| normalize_path(&p));
+                    links.imported_files.extend(target_key.clone());
+                    let target_names = target_key.and_then(|key| file_to_names.get(&key));
+                    if let Some(names) = target_names {
+                        target_ids
+                            .extend(names.iter().filter(|(n, _)| n == name).map(|(_, id)| *id));
+                    }
+                }
+            }
+            if target_ids.is_empty() {
+                continue;
+            }
+            let Some(caller_id) = find_containing_entity(reg.byte_offset, &source_entries) else {
+                continue;
+            };
+            let Some(&src_node) = id_to_node.get(&caller_id) else {
+                continue;
+            };
+            for target_id in target_ids {
+                if target_id == caller_id {
+                    continue;
+                }
+                if let Some(&tgt_node) = id_to_node.get(&target_id) {
+                    if add_reference(
+                        graph,
+                        edge_ids,
+                        src_node,
+                        tgt_node,
+                        EdgeInfo::new(reg.line, edge_kind(target_id, EdgeKind::Call)),
+                    ) {
+                        links.new_edges += 1;
+                    }
+                    links.di_registered.push((
+                        target_id,
+                        CallRef {
+                            file: source_file_key.clone(),
+                            line: reg.line,
+                            call: reg.call.clone(),
+                        },
+                    ));
+                }
+            }
+        }
+
+        links.string_names = extract_string_names(source, tree.root_node());
+
+        // `getattr(obj, "name")` may reach any symbol called `name`, so the names
+        // are matched project-wide after this pass. `import_module("pkg.mod")`
+        // links the caller to the module it loads.
+        for dynamic in extract_dynamic_names(source, tree.root_node()) {
+            if !dynamic.module {
+                let site = CallRef {
+                    file: source_file_key.clone(),
+                    line: dynamic.line,
+                    call: dynamic.call,
+                };
+                links.dynamic_names.push((dynamic.pattern, site));
+                continue;
+            }
+            let NamePattern::Exact(module) = &dynamic.pattern else {
+                continue;
+            };
+            let Some(target_path) = resolve_import(&source_canonical, module, roots) else {
+                continue;
+            };
+            links.imported_files.insert(normalize_path(&target_path));
+            let module_hash = symbol_hash(&format!("{}::__MODULE__", normalize_path(&target_path)));
+            let caller = find_containing_entity(dynamic.byte_offset, &source_entries)
+                .and_then(|id| id_to_node.get(&id));
+            if let (Some(&src_node), Some(&tgt_node)) = (caller, id_to_node.get(&module_hash)) {
+                if add_reference(
+                    graph,
+                    edge_ids,
+                    src_node,
+                    tgt_node,
+                    EdgeInfo::new(dynamic.line, EdgeKind::Import),
+                ) {
+                    links.new_edges += 1;
+                }
+            }
+        }
+
+        // Same-file qualified names, for resolving `name()`, `self.name()` and `cls.name()`.
+        // Conditional variants (`name#2`) resolve under their base name.
+        let mut qname_to_id: HashMap<&str, Vec<u64>> = HashMap::new();
+        let mut id_to_qname: HashMap<u64, &str> = HashMap::new();
+        for e in registry
+            .entries
+            .iter()
+            .filter(|e| e.file_path == source_file_key)
+        {
+            let base = base_qualified_name(&e.qualified_name);
+            qname_to_id.entry(base).or_default().push(e.id);
+            id_to_qname.insert(e.id, base);
+        }
+
+        // Extract call sites and emit directed edges
+        let calls = extract_calls(source, tree.root_node());
+        let call_offsets: HashSet<u32> = calls.iter().map(|c| c.byte_offset).collect();
+        for call in calls {
+            let caller_id = match find_containing_entity(call.byte_offset, &source_entries) {
+                Some(id) => id,
+                None => continue,
+            };
+            let src_node = match id_to_node.get(&caller_id) {
+                Some(&n) => n,
+                None => continue,
+            };
+
+            if matches!(call.receiver.as_deref(), Some("self" | "cls")) {
+                // Walk outwards from the caller's scope: `A.m.inner` tries `A.m.name`, then `A.name`.
+                let mut scope = id_to_qname.get(&caller_id).copied().unwrap_or("");
+                while let Some((parent, _)) = scope.rsplit_once('.') {
+                    let target = qname_to_id.get(format!("{}.{}", parent, call.name).as_str());
+                    if let Some(target_ids) = target {
+                        for &target_id in target_ids {
+                            if target_id == caller_id {
+                                continue;
+                            }
+                            if let Some(&tgt_node) = id_to_node.get(&target_id) {
+                                if add_reference(
+                                    graph,
+                                    edge_ids,
+                                    src_node,
+                                    tgt_node,
+                                    EdgeInfo::new(call.line, EdgeKind::Call),
+                                ) {
+                                    links.new_edges += 1;
+                                }
+                            }
+                        }
+                        break;
+                    }
+                    scope = parent;
+                }
+            }
+
+            // Bare calls to a top-level name this file defines. Builtins such as
+            // `list()` only link when the module shadows them with its own `def`.
+            let local_ids = match call.receiver {
+                None if call.name != "__MODULE__" => qname_to_id.get(call.name.as_str()),
+                _ => None,
+            };
+            for &target_id in local_ids.into_iter().flatten() {
+                if target_id == caller_id {
+                    continue;
+                }
+                if let Some(&tgt_node) = id_to_node.get(&target_id) {
+                    if add_reference(
+                        graph,
+                        edge_ids,
+                        src_node,
+                        tgt_node,
+                        EdgeInfo::new(call.line, EdgeKind::Call),
+                    ) {
+                        links.new_edges += 1;
+                    }
+                }
+            }
+
+            let target_ids = match import_targets.get(&call.name) {
+                Some(ids) => ids,
+                None => continue,
+            };
+            for &target_id in target_ids {
+                if let Some(&tgt_node) = id_to_node.get(&target_id) {
+                    if add_reference(
+                        graph,
+                        edge_ids,
+                        src_node,
+                        tgt_node,
+                        EdgeInfo::new(call.line, edge_kind(target_id, EdgeKind::Call)),
+                    ) {
+                        links.new_edges += 1;
+                    }
+                }
+            }
+        }
+
+        // Module-level assignments and type aliases are read by name: `TIMEOUT`
+        // locally, or `config.TIMEOUT` / imported `TIMEOUT` from another module.
+        let local_targets: HashMap<&str, u64> = registry
+            .entries
+            .iter()
+            .filter(|e| e.file_path == source_file_key && read_target_ids.contains(&e.id))
+            .map(|e| (e.name.as_str(), e.id))
+            .collect();
+        // In decorators, base class lists, and annotations a name may refer to
+        // any symbol (`@audit`, `class Child(Base)`, `def f(c: Config)`).
+        let contexts = reference_contexts(tree.root_node());
+        for read in extract_reads(source, tree.root_node()) {
+            let context = contexts
+                .iter()
+                .filter(|&&(start, end, _)| start <= read.byte_offset && read.byte_offset < end)
+                .min_by_key(|&&(start, end, _)| end - start)
+                .map(|&(_, _, context)| context);
+            let (targets, kind): (Vec<u64>, EdgeKind) = if let Some(context) = context {
+                // `@retry(...)` / `class A(make_base())` were linked as calls above.
+                if call_offsets.contains(&read.byte_offset) {
+                    continue;
+                }
+                let local = match read.receiver {
+                    None if read.name != "__MODULE__" => qname_to_id.get(read.name.as_str()),
+                    _ => None,
+                };
+                let imported = import_targets.get(&read.name);
+                let targets = local
+                    .into_iter()
+                    .chain(imported)
+                    .flatten()
+                    .copied()
+                    .collect();
+                let kind = match context {
+                    RefContext::Decorator => EdgeKind::Decorator,
+                    RefContext::Superclass => EdgeKind::Inherit,
+                    RefContext::Annotation => EdgeKind::TypeRef,
+                };
+                (targets, kind)
+            } else {
+                let local = read
+                    .receiver
+                    .is_none()
+                    .then(|| local_targets.get(read.name.as_str()))
+                    .flatten();
+                let imported = import_targets
+                    .get(&read.name)
+                    .into_iter()
+                    .flatten()
+                    .filter(|id| read_target_ids.contains(id));
+                let targets = local.into_iter().chain(imported).copied().collect();
+                (targets, EdgeKind::Read)
+            };
+            if targets.is_empty() {
+                continue;
+            }
+            let Some(caller_id) = find_containing_entity(read.byte_offset, &source_entries) else {
+                continue;
+            };
+            let Some(&src_node) = id_to_node.get(&caller_id) else {
+                continue;
+            };
+            for target_id in targets {
+                if target_id == caller_id {
+                    continue;
+                }
+                if let Some(&tgt_node) = id_to_node.get(&target_id) {
+                    if add_reference(
+                        graph,
+                        edge_ids,
+                        src_node,
+                        tgt_node,
+                        EdgeInfo::new(read.line, edge_kind(target_id, kind)),
+                    ) {
+                        links.new_edges += 1;
+                    }
+                }
+            }
+        }
+
+        // Forward references resolve like code: through imports, same-file
+        // definitions, or as absolute dotted paths (`"pkg.models.Config"` need
+        // not be imported). The last segment of one that resolves nowhere is
+        // matched project-wide after this pass, like `getattr` strings.
+        for (name, line) in extract_annotation_names(source, tree.root_node()) {
+            let site = format!("{}:{}", source_file_key, line);
+            let ids = annotation_targets(
+                &name,
+                &source_canonical,
+                &imports,
+                &import_targets,
+                local_names,
+                roots,
+                file_to_names,
+            );
+            if ids.is_empty() {
+                let last = name.rsplit('.').next().unwrap_or(&name);
+                links.annotation_names.push((last.to_string(), site));
+            } else {
+                links
+                    .annotation_targets
+                    .extend(ids.into_iter().map(|id| (id, site.clone())));
+            }
+        }
+
+        links.unused_imports = unused_in_tree(
+            source,
+            tree.root_node(),
+            &source_file_key,
+            &|module, name| submodule_path(&source_canonical, module, name, roots).is_some(),
+        );
+
+        links
+    }
+}
+
+/// Builds a reference graph from a polyglot project directory.
+///
+/// # Algorithm
+/// 1. Walk directory for `.py` and C++ (`.cpp`, `.cxx`, `.cc`, `.h`, `.hpp`) files.
+/// 2. **Pass 1**: Extract Python entities, populate registry, add graph nodes.
+/// 3. **Pass 1b**: Extract C++ entities, register symbols and `__MODULE__` sentinels.
+/// 4. **Pass 2**: Re-parse Python files for imports + call sites; add symbol-to-symbol edges.
+/// 5. **Pass 2b**: Scan C++ files for `#include "..."` directives; add file-level edges.
+///
+/// # Memory
+/// - Registry stores all symbols (~80 bytes per symbol)
+/// - Graph stores node indices (8 bytes per node) + edges (~16 bytes per edge)
+/// - Per-file `Vec<Entity>` is dropped after indexing
+pub fn build_reference_graph(
+    project_root: &Path,
+    host: &mut ParserHost,
+) -> Result<ReferenceGraph, AnatomistError> {
+    build_reference_graph_with_options(project_root, host, &GraphOptions::default(), None)
+}
+
+/// Linking behaviour for [`build_reference_graph_with_options`].
+#[derive(Debug, Clone, Default)]
+pub struct GraphOptions {
+    /// DI registration rule set (see [`crate::di`]).
+    pub di_rules: DiRules,
+    /// When `false`, `from m import *` links the importing module's `__MODULE__`
+    /// sentinel to every public top-level symbol of `m`. When `true`, star-imported
+    /// symbols are only linked through matching call sites.
+    pub strict_star_imports: bool,
+    /// Source roots for absolute imports, relative to the project root. `None`
+    /// auto-detects them (see [`crate::imports::source_roots`]).
+    pub source_roots: Option<Vec<String>>,
+    /// Monorepo roots relative to the project root (`[workspace] roots`).
+    /// When set, only files under them are indexed, and they replace
+    /// `source_roots` for import resolution, tried in this order.
+    pub workspace_roots: Vec<String>,
+}
+
+impl GraphOptions {
+    /// The canonical workspace roots, in order; empty in single-root mode.
+    /// Fails on a root that is not a directory rather than scanning without it.
+    pub fn workspace_dirs(&self, project_root: &Path) -> Result<Vec<PathBuf>, AnatomistError> {
+        let mut dirs: Vec<PathBuf> = Vec::new();
+        for root in &self.workspace_roots {
+            let dir = dunce::canonicalize(project_root.join(root))
+                .ok()
+                .filter(|dir| dir.is_dir())
+                .ok_or_else(|| AnatomistError::InvalidWorkspaceRoot(root.clone()))?;
+            if !dirs.contains(&dir) {
+                dirs.push(dir);
+            }
+        }
+        Ok(dirs)
+    }
+
+    /// Directories absolute imports resolve against (see [`source_roots`]).
+    pub(crate) fn import_roots(&self, project_root: &Path) -> Vec<PathBuf> {
+        if self.workspace_roots.is_empty() {
+            source_roots(project_root, self.source_roots.as_deref())
+        } else {
+            source_roots(project_root, Some(&self.workspace_roots))
+        }
+    }
+
+    /// [`ReferenceGraph::roots`] of a graph of `project_root`, whose
+    /// workspace roots are `workspace`.
+    fn root_prefixes(project_root: &Path, workspace: &[PathBuf]) -> Vec<String> {
+        let mut dirs = workspace.to_vec();
+        if dirs.is_empty() {
+            dirs.push(project_root.to_path_buf());
+        }
+        let mut prefixes: Vec<String> = dirs
+            .iter()
+            .map(|d| format!("{}/", normalize_path(d).trim_end_matches('/')))
+            .collect();
+        prefixes.sort_by_key(|p| std::cmp::Reverse(p.len()));
+        prefixes
+    }
+}
+
+/// Same as [`build_reference_graph`], with explicit [`GraphOptions`].
+///
+/// Pass 2 additionally links container registration arguments (see [`crate::di`])
+/// to the registered symbols and records each registration site in
+/// [`ReferenceGraph::di_registered`].
+///
+/// When `cache` is set, Pass 1 serves unchanged files from it and prunes entries
+/// for files that no longer exist.
+pub fn build_reference_graph_with_options(
+    project_root: &Path,
+    host: &mut ParserHost,
+    options: &GraphOptions,
+    cache: Option<&mut EntityCache>,
+) -> Result<ReferenceGraph, AnatomistError> {
+    build_reference_graph_observed(project_root, host, options, cache, &mut progress::silent)
+}
+
+/// Same as [`build_reference_graph_with_options`], reporting [`Stage::Parse`]
+/// and [`Stage::Link`] progress to `progress` (see [`crate::progress`]).
+pub fn build_reference_graph_observed(
+    project_root: &Path,
+    host: &mut ParserHost,
+    options: &GraphOptions,
+    mut cache: Option<&mut EntityCache>,
+    progress: &mut dyn FnMut(PipelineEvent),
+) -> Result<ReferenceGraph, AnatomistError> {
+    let root = dunce::canonicalize(project_root)?;
+    // Validated first: `import_roots` takes the workspace roots as given.
+    let workspace = options.workspace_dirs(&root)?;
+    let roots = options.import_roots(&root);
+    let root_prefixes = GraphOptions::root_prefixes(&root, &workspace);
+    let in_workspace =
+        |path: &PathBuf| workspace.is_empty() || workspace.iter().any(|dir| path.starts_with(dir));
+    let SourceFiles {
+        mut py_files,
+        mut cpp_files,
+        mut script_files,
+        mut notebooks,
+    } = SourceFiles::walk(&root)?;
+    for files in [
+        &mut py_files,
+        &mut cpp_files,
+        &mut script_files,
+        &mut notebooks,
+    ] {
+        files.retain(in_workspace);
+    }
+
+    let mut registry = SymbolRegistry::new();
+    let mut graph = DiGraph::new();
+    let mut edge_ids: HashMap<(NodeIndex, NodeIndex), EdgeIndex> = HashMap::new();
+    let mut file_symbols: HashMap<String, Vec<u64>> = HashMap::new();
+    let mut id_to_node: HashMap<u64, NodeIndex> = HashMap::new();
+    let mut all_entities: Vec<Entity> = Vec::new();
+    let mut di_registered: HashMap<u64, CallRef> = HashMap::new();
+    let mut file_hashes: HashMap<String, [u8; 32]> = HashMap::new();
+    // Attribute names accessed by string, with their access site.
+    let mut dynamic_names: Vec<(NamePattern, CallRef)> = Vec::new();
+    let mut stats = GraphStats {
+        file_count: py_files.len() + cpp_files.len() + script_files.len(),
+        ..Default::default()
+    };
+
+    // PASS 1: Index symbols
+    progress(PipelineEvent::StageStarted(Stage::Parse));
+    for (index, path) in py_files.iter().enumerate() {
+        let dissected = read_source(path, &mut stats.diagnostics).and_then(|source| {
+            let entities = match cache.as_deref_mut() {
+                Some(cache) => cache.dissect_source(host, path, &source),
+                None => host.dissect_source(path, &source),
+            };
+            entities
+                .map_err(|e| stats.diagnostics.push(parse_failure(path, &e)))
+                .ok()
+                .map(|entities| (entities, source.len(), source_hash(&source)))
+        });
+        match dissected {
+            Some((mut entities, len, hash)) => {
+                // Compute canonical file key for __MODULE__ sentinel
+                let canonical = dunce::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+                let file_key = normalize_path(&canonical);
+                if let Some(hash) = hash {
+                    file_hashes.insert(file_key.clone(), hash);
+                }
+                assign_module_path(&mut entities, &canonical, &roots);
+                let file_size = len.min(u32::MAX as usize) as u32;
+
+                // Insert __MODULE__ virtual entry covering the entire file.
+                // Module-level calls (outside any func/class) are attributed to this symbol.
+                let sentinel = module_entry(&file_key, file_size);
+                let module_hash = sentinel.id;
+                registry.insert(sentinel);
+                let module_node = graph.add_node(module_hash);
+                id_to_node.insert(module_hash, module_node);
+                file_symbols.entry(file_key).or_default().push(module_hash);
+
+                for entity in entities {
+                    let entry = SymbolEntry::from(&entity);
+                    let hash = entry.id;
+                    registry.insert(entry);
+
+                    let node_idx = graph.add_node(hash);
+                    id_to_node.insert(hash, node_idx);
+
+                    file_symbols
+                        .entry(entity.file_path.clone())
+                        .or_default()
+                        .push(hash);
+
+                    all_entities.push(entity);
+                    stats.symbol_count += 1;
+                }
+            }
+            None => {
+                stats.parse_errors += 1;
+            }
+        }
+        progress(PipelineEvent::FileParsed {
+            path: path.clone(),
+            index: index + 1,
+            total: py_files.len(),
+        });
+    }
+    match cache {
+        Some(cache) => {
+            cache.prune();
+            stats.cache_hits = cache.hits;
+            stats.files_parsed = cache.misses;
+        }
+        None => stats.files_parsed = py_files.len(),
+    }
+    progress(PipelineEvent::StageFinished {
+        stage: Stage::Parse,
+        protected: 0,
+    });
+
+    // Module-level assignments and type aliases, linked by name reads as well as calls.
+    let read_target_ids = read_targets(&registry);
+    let file_to_names = names_by_file(&registry);
+
+    // PASS 2: Link imports via call sites (symbol-to-symbol edges)
+    progress(PipelineEvent::StageStarted(Stage::Link));
+    let mut python_links: HashMap<String, FileLinks> = HashMap::new();
+    {
+        let mut linker = PyLinker::new(
+            &roots,
+            options,
+            &registry,
+            &file_to_names,
+            &read_target_ids,
+            &id_to_node,
+        )?;
+        for source_path in &py_files {
+            let links = linker.link_file(&mut graph, &mut edge_ids, source_path);
+            stats.edge_count += links.new_edges;
+            for (target_id, site) in &links.di_registered {
+                di_registered
+                    .entry(*target_id)
+                    .or_insert_with(|| site.clone());
+            }
+            dynamic_names.extend(links.dynamic_names.iter().cloned());
+            let canonical =
+                dunce::canonicalize(source_path).unwrap_or_else(|_| source_path.clone());
+            let file_key = normalize_path(&canonical);
+            stats.diagnostics.extend(links.syntax_diagnostic(&file_key));
+            python_links.insert(file_key, links);
+        }
+    }
+
+    // PASS 2 (notebooks): file-level import edges from Jupyter code cells.
+    let notebook_imports = link_notebooks(&notebooks, &roots, &mut stats.diagnostics)?;
+
+    // PASS 1b: Index C++ and JS/TS symbols
+    for path in cpp_files.iter().chain(&script_files) {
+        let Some(source) = read_source(path, &mut stats.diagnostics) else {
+            continue;
+        };
+        let source = &source[..];
+        let canonical = dunce::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        let file_key = normalize_path(&canonical);
+        let file_size = source.len().min(u32::MAX as usize) as u32;
+
+        // __MODULE__ sentinel for file-level include edges and module-level calls
+        let sentinel = module_entry(&file_key, file_size);
+        let module_hash = sentinel.id;
+        registry.insert(sentinel);
+        let module_node = graph.add_node(module_hash);
+        id_to_node.insert(module_hash, module_node);
+        file_symbols
+            .entry(file_key.clone())
+            .or_default()
+            .push(module_hash);
+
+        let extracted = match path.extension().and_then(|e| e.to_str()) {
+            Some("js" | "jsx") => ParserHost::extract_js_entities(source, &file_key),
+            Some("ts") => ParserHost::extract_ts_entities(source, &file_key, false),
+            Some("tsx") => ParserHost::extract_ts_entities(source, &file_key, true),
+            _ => ParserHost::extract_cpp_entities(source, &file_key),
+        };
+        match extracted {
+            Ok(entities) => {
+                for entity in entities {
+                    let entry = SymbolEntry::from(&entity);
+                    let hash = entry.id;
+                    registry.insert(entry);
+
+                    let node_idx = graph.add_node(hash);
+                    id_to_node.insert(hash, node_idx);
+                    file_symbols
+                        .entry(entity.file_path.clone())
+                        .or_default()
+                        .push(hash);
+
+                    all_entities.push(entity);
+                    stats.symbol_count += 1;
+                }
+            }
+            Err(e) => {
+                stats.diagnostics.push(parse_failure(path, &e));
+                stats.parse_errors += 1;
+            }
+        }
+    }
+
+    // Build C++ file-key index for include resolution
+    let cpp_file_keys: HashSet<String> = cpp_files
+        .iter()
+        .filter_map(|p| dunce::canonicalize(p).ok())
+        .map(|p| normalize_path(&p))
+        .collect();
+
+    // PASS 2b: Wire #include edges as __MODULE__ → __MODULE__ file-level links
+    let mut cpp_includes: HashMap<String, Vec<String>> = HashMap::new();
+    for source_path in &cpp_files {
+        let Ok(source) = common::source::read(source_path) else {
+            continue;
+        };
+        let includes = extract_cpp_includes(&source);
+        if includes.is_empty() {
+            continue;
+        }
+
+        let source_canonical = match dunce::canonicalize(source_path) {
+            Ok(p) => p,
+            Err(_) => continue,
+        };
+        let source_file_key = normalize_path(&source_canonical);
+        let src_module_id = symbol_hash(&format!("{}::__MODULE__", source_file_key));
+        let src_node = match id_to_node.get(&src_module_id) {
+            Some(&n) => n,
+            None => continue,
+        };
+        let source_dir = source_canonical
+            .parent()
+            .unwrap_or(root.as_path())
+            .to_path_buf();
+
+        for include in &includes {
+            // Try relative-to-source-dir first, then relative-to-project-root
+            let target_abs = [source_dir.join(&include.path), root.join(&include.path)]
+                .into_iter()
+                .find(|p| p.exists())
+                .and_then(|p| dunce::canonicalize(p).ok());
+
+            let Some(target_abs) = target_abs else {
+                continue;
+            };
+            let target_file_key = normalize_path(&target_abs);
+            if !cpp_file_keys.contains(&target_file_key) {
+                continue;
+            }
+            let tgt_module_id = symbol_hash(&format!("{}::__MODULE__", target_file_key));
+            if let Some(&tgt_node) = id_to_node.get(&tgt_module_id) {
+                if add_reference(
+                    &mut graph,
+                    &mut edge_ids,
+                    src_node,
+                    tgt_node,
+                    EdgeInfo::new(include.line, EdgeKind::Include),
+                ) {
+                    stats.edge_count += 1;
+                }
+            }
+            cpp_includes
+                .entry(source_file_key.clone())
+                .or_default()
+                .push(target_file_key);
+        }
+    }
+
+    // PASS 2c: Link C++ calls to functions and methods by name. Candidates in the
+    // calling file, its direct includes, or a source sharing an included header's
+    // stem (`util.h` → `util.cpp`) win; otherwise only a project-wide unique name links.
+    let mut cpp_functions: HashMap<&str, Vec<(&str, u64)>> = HashMap::new();
+    for entry in registry.entries.iter().filter(|e| {
+        cpp_file_keys.contains(&e.file_path)
+            && (e.entity_type == EntityType::FunctionDefinition as u8
+                || e.entity_type == EntityType::MethodDefinition as u8)
+    }) {
+        cpp_functions
+            .entry(entry.name.as_str())
+            .or_default()
+            .push((entry.file_path.as_str(), entry.id));
+    }
+    let file_stem = |key: &str| {
+        let name = key.rsplit('/').next().unwrap_or(key);
+        name.rsplit_once('.')
+            .map_or(name, |(stem, _)| stem)
+            .to_string()
+    };
+
+    let mut cpp_parser = Parser::new();
+    cpp_parser
+        .set_language(&tree_sitter_cpp::LANGUAGE.into())
+        .map_err(|e| AnatomistError::ParseFailure(format!("Grammar load failed: {e}")))?;
+    for source_path in &cpp_files {
+        let Ok(source) = common::source::read(source_path) else {
+            continue;
+        };
+        let Some(tree) = cpp_parser.parse(&source, None) else {
+            continue;
+        };
+        let calls = extract_cpp_calls(&source, tree.root_node());
+        if calls.is_empty() {
+            continue;
+        }
+        let Ok(source_canonical) = dunce::canonicalize(source_path) else {
+            continue;
+        };
+        let source_file_key = normalize_path(&source_canonical);
+        let included = cpp_includes
+            .get(&source_file_key)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        let included_stems: HashSet<String> = included.iter().map(|k| file_stem(k)).collect();
+        let in_neighborhood = |file: &str| {
+            file == source_file_key
+                || included.iter().any(|k| k == file)
+                || included_stems.contains(&file_stem(file))
+        };
+        let source_entries: Vec<(u64, u32, u32)> = registry
+            .entries
+            .iter()
+            .filter(|e| e.file_path == source_file_key)
+            .map(|e| (e.id, e.start_byte, e.end_byte))
+            .collect();
+
+        for call in &calls {
+            let Some(candidates) = cpp_functions.get(call.name.as_str()) else {
+                continue;
+            };
+            let near: Vec<u64> = candidates
+                .iter()
+                .filter(|(file, _)| in_neighborhood(file))
+                .map(|(_, id)| *id)
+                .collect();
+            let targets = if !near.is_empty() {
+                near
+            } else if candidates.len() == 1 {
+                vec![candidates[0].1]
+            } else {
+                continue;
+            };
+            let Some(caller_id) = find_containing_entity(call.byte_offset, &source_entries) else {
+                continue;
+            };
+            let Some(&src_node) = id_to_node.get(&caller_id) else {
+                continue;
+            };
+            for target_id in targets {
+                if target_id == caller_id {
+                    continue;
+                }
+                if let Some(&tgt_node) = id_to_node.get(&target_id) {
+                    if add_reference(
+                        &mut graph,
+                        &mut edge_ids,
+                        src_node,
+                        tgt_node,
+                        EdgeInfo::new(call.line, EdgeKind::Call),
+                    ) {
+                        stats.edge_count += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    // PASS 2d: Link JS/TS imports and call sites the way Pass 2 links Python.
+    let mut script_names: HashMap<String, Vec<(String, u64)>> = HashMap::new();
+    let mut script_modules: HashMap<String, ScriptModule> = HashMap::new();
+    let mut script_parser = Parser::new();
+    for source_path in &script_files {
+        let language: tree_sitter::Language = match source_path.extension().and_then(|e| e.to_str())
+        {
+            Some("ts") => tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+            Some("tsx") => tree_sitter_typescript::LANGUAGE_TSX.into(),
+            _ => tree_sitter_javascript::LANGUAGE.into(),
+        };
+        if script_parser.set_language(&language).is_err() {
+            continue;
+        }
+        let Ok(source) = common::source::read(source_path) else {
+            continue;
+        };
+        let Some(tree) = script_parser.parse(&source, None) else {
+            continue;
+        };
+        let Ok(source_canonical) = dunce::canonicalize(source_path) else {
+            continue;
+        };
+        let root_node = tree.root_node();
+        script_modules.insert(
+            normalize_path(&source_canonical),
+            ScriptModule {
+                path: source_canonical,
+                imports: extract_es_imports(&source, root_node),
+                default_export: es_default_export(&source, root_node),
+                refs: extract_script_refs(&source, root_node),
+            },
+        );
+    }
+    for entry in registry
+        .entries
+        .iter()
+        .filter(|e| script_modules.contains_key(&e.file_path) && e.name != "__MODULE__")
+    {
+        script_names
+            .entry(entry.file_path.clone())
+            .or_default()
+            .push((entry.name.clone(), entry.id));
+    }
+
+    for (source_file_key, module) in &script_modules {
+        // import_targets: local name -> [target_symbol_id]
+        let mut import_targets: HashMap<&str, Vec<u64>> = HashMap::new();
+        for import in module.imports.iter().filter(|i| !i.reexport) {
+            let Some(target_path) = resolve_es_import(&module.path, &import.specifier) else {
+                continue;
+            };
+            let target_key = normalize_path(&target_path);
+            if import.namespace {
+                // `ns.name()` / a destructured `require()`: members are reached by name.
+                for (name, id) in script_names.get(&target_key).into_iter().flatten() {
+                    import_targets.entry(name).or_default().push(*id);
+                }
+            }
+            for (imported, local) in &import.names {
+                let ids =
+                    script_export_ids(&target_key, imported, &script_modules, &script_names, 0);
+                import_targets.entry(local).or_default().extend(ids);
+            }
+        }
+        for ids in import_targets.values_mut() {
+            ids.sort_unstable();
+            ids.dedup();
+        }
+
+        let source_entries: Vec<(u64, u32, u32)> = registry
+            .entries
+            .iter()
+            .filter(|e| &e.file_path == source_file_key)
+            .map(|e| (e.id, e.start_byte, e.end_byte))
+            .collect();
+        let mut qname_to_id: HashMap<&str, Vec<u64>> = HashMap::new();
+        let mut id_to_qname: HashMap<u64, &str> = HashMap::new();
+        for e in registry
+            .entries
+            .iter()
+            .filter(|e| &e.file_path == source_file_key)
+        {
+            let base = base_qualified_name(&e.qualified_name);
+            qname_to_id.entry(base).or_default().push(e.id);
+            id_to_qname.insert(e.id, base);
+        }
+
+        for (call, kind) in &module.refs {
+            let Some(caller_id) = find_containing_entity(call.byte_offset, &source_entries) else {
+                continue;
+            };
+            let Some(&src_node) = id_to_node.get(&caller_id) else {
+                continue;
+            };
+
+            let mut target_ids: Vec<u64> = Vec::new();
+            match call.receiver.as_deref() {
+                Some("this") => {
+                    // `this.name()` inside `A.m` refers to `A.name`.
+                    let scope = id_to_qname.get(&caller_id).copied().unwrap_or("");
+                    if let Some((class, _)) = scope.rsplit_once('.') {
+                        let qname = format!("{}.{}", class, call.name);
+                        target_ids.extend(qname_to_id.get(qname.as_str()).into_iter().flatten());
+                    }
+                }
+                None if call.name != "__MODULE__" => {
+                    target_ids.extend(qname_to_id.get(call.name.as_str()).into_iter().flatten());
+                }
+                _ => {}
+            }
+            target_ids.extend(import_targets.get(call.name.as_str()).into_iter().flatten());
+
+            for target_id in target_ids {
+                if target_id == caller_id {
+                    continue;
+                }
+                if let Some(&tgt_node) = id_to_node.get(&target_id) {
+                    if add_reference(
+                        &mut graph,
+                        &mut edge_ids,
+                        src_node,
+                        tgt_node,
+                        EdgeInfo::new(call.line, *kind),
+                    ) {
+                        stats.edge_count += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    let dynamic_refs = match_dynamic_names(&registry, &dynamic_names);
+    let annotation_refs = {
+        let mut keys: Vec<&String> = python_links.keys().collect();
+        keys.sort();
+        match_annotation_names(&registry, keys.into_iter().map(|k| &python_links[k]))
+    };
+
+    progress(PipelineEvent::StageFinished {
+        stage: Stage::Link,
+        protected: 0,
+    });
+
+    Ok(ReferenceGraph {
+        registry,
+        graph,
+        file_symbols,
+        entities: all_entities,
+        di_registered,
+        dynamic_refs,
+        annotation_refs,
+        stats,
+        notebook_imports,
+        roots: root_prefixes,
+        file_hashes,
+        python_links,
+    })
+}
+
+/// The project files each of `notebooks` imports, keyed by notebook.
+///
+/// The code cells are parsed as one module (see
+/// [`crate::notebook::Notebook::code`]). Absolute imports are resolved
+/// against the notebook's own directory, where Jupyter starts its kernel,
+/// before `roots`. Notebooks that cannot be read, are not valid notebook
+/// JSON, or whose imports cannot be extracted are skipped with a warning in
+/// `diagnostics`.
+fn link_notebooks(
+    notebooks: &[PathBuf],
+    roots: &[PathBuf],
+    diagnostics: &mut Diagnostics,
+) -> Result<HashMap<String, BTreeSet<String>>, AnatomistError> {
+    let mut links: HashMap<String, BTreeSet<String>> = HashMap::new();
+    let mut parser = Parser::new();
+    parser
+        .set_language(&tree_sitter_python::LANGUAGE.into())
+        .map_err(|e| AnatomistError::ParseFailure(format!("Language load failed: {:?}", e)))?;
+    for path in notebooks {
+        let skip = |code: &'static str, message: String| {
+            Diagnostic::warning(code, format!("notebook not linked: {message}")).at(file_key(path))
+        };
+        let bytes = match std::fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                diagnostics.push(skip(codes::UNREADABLE_FILE, e.to_string()));
+                continue;
+            }
+        };
+        let Some(notebook) = crate::notebook::Notebook::parse(&bytes) else {
+            diagnostics.push(skip(codes::PARSE_FAILURE, "not nbformat 4 JSON".into()));
+            continue;
+        };
+        let code = notebook.code();
+        let Some(tree) = parser.parse(code.as_bytes(), None) else {
+            diagnostics.push(skip(
+                codes::PARSE_FAILURE,
+                "code cells did not parse".into(),
+            ));
+            continue;
+        };
+        let imports = match extract_imports(code.as_bytes(), tree.root_node()) {
+            Ok(imports) => imports,
+            Err(e) => {
+                diagnostics.push(skip(codes::PARSE_FAILURE, e.to_string()));
+                continue;
+            }
+        };
+
+        let canonical = dunce::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        let search: Vec<PathBuf> = canonical
+            .parent()
+            .map(Path::to_path_buf)
+            .into_iter()
+            .chain(roots.iter().cloned())
+            .collect();
+        let mut imported: BTreeSet<String> = BTreeSet::new();
+        for import in &imports {
+            imported.extend(
+                resolve_import(&canonical, &import.raw_path, &search).map(|p| normalize_path(&p)),
+            );
+            // `from pkg import module` imports the submodule's file too.
+            for name in &import.names {
+                imported.extend(
+                    submodule_path(&canonical, &import.raw_path, name, &search)
+                        .map(|p| normalize_path(&p)),
+                );
+            }
+        }
+        if !imported.is_empty() {
+            links.insert(normalize_path(&canonical), imported);
+        }
+    }
+    Ok(links)
+}
+
+/// Maps every symbol a string annotation names to the first such site: the
+/// resolved `annotation_targets` of each file, then the symbols whose name is
+/// one of the unresolved `annotation_names`.
+pub(crate) fn match_annotation_names<'a>(
+    registry: &SymbolRegistry,
+    links: impl IntoIterator<Item = &'a FileLinks>,
+) -> HashMap<u64, String> {
+    let mut refs: HashMap<u64, String> = HashMap::new();
+    let mut first_site: HashMap<&str, &str> = HashMap::new();
+    for file in links {
+        for (id, site) in &file.annotation_targets {
+            refs.entry(*id).or_insert_with(|| site.clone());
+        }
+        for (name, site) in &file.annotation_names {
+            first_site.entry(name.as_str()).or_insert(site.as_str());
+        }
+    }
+    if first_site.is_empty() {
+        return refs;
+    }
+    for e in registry.entries.iter().filter(|e| e.name != "__MODULE__") {
+        if let Some(site) = first_site.get(e.name.as_str()) {
+            refs.entry(e.id).or_insert_with(|| site.to_string());
+        }
+    }
+    refs
+}
+
+/// Maps every symbol named by a dynamic-access string to the first such site.
+pub(crate) fn match_dynamic_names(
+    registry: &SymbolRegistry,
+    dynamic_names: &[(NamePattern, CallRef)],
+) -> HashMap<u64, CallRef> {
+    let mut dynamic_refs: HashMap<u64, CallRef> = HashMap::new();
+    if dynamic_names.is_empty() {
+        return dynamic_refs;
+    }
+    for entry in registry.entries.iter().filter(|e| e.name != "__MODULE__") {
+        if let Some((_, site)) = dynamic_names.iter().find(|(p, _)| p.matches(&entry.name)) {
+            dynamic_refs.insert(entry.id, site.clone());
+        }
+    }
+    dynamic_refs
+}
+
+/// Walks a directory for `.py` files, skipping paths excluded by [`common::walk`].
+pub(crate) fn walk_py_files(root: &Path) -> Result<Vec<PathBuf>, AnatomistError> {
+    let mut files = Vec::new();
+
+    for entry in common::walk::walk(root) {
+        let entry = entry.map_err(|e| AnatomistError::IoError(std::io::Error::other(e)))?;
+        let path = entry.path();
+        if path.is_file() && path.extension().and_then(|s| s.to_str()) == Some("py") {
+            files.push(path.to_path_buf());
+        }
+    }
+
+    Ok(files)
+}
+
+/// C++ source and header extensions indexed by Pass 1b.
+pub(crate) const CPP_EXTENSIONS: &[&str] = &["cpp", "cxx", "cc", "h", "hpp"];
+
+/// The files a reference graph is built from, by language.
+#[derive(Debug, Default)]
+struct SourceFiles {
+    py_files: Vec<PathBuf>,
+    /// See [`CPP_EXTENSIONS`].
+    cpp_files: Vec<PathBuf>,
+    /// See [`SCRIPT_EXTENSIONS`].
+    script_files: Vec<PathBuf>,
+    /// Jupyter notebooks, linked by [`link_notebooks`].
+    notebooks: Vec<PathBuf>,
+}
+
+impl SourceFiles {
+    /// Sorts the files under `root` in a single walk, skipping the same paths
+    /// as [`walk_py_files`].
+    fn walk(root: &Path) -> Result<Self, AnatomistError> {
+        let mut files = Self::default();
+        for entry in common::walk::walk(root) {
+            let entry = entry.map_err(|e| AnatomistError::IoError(std::io::Error::other(e)))?;
+            let path = entry.path();
+            if !path.is_file() {
+                continue;
+            }
+            let list = match path.extension().and_then(|s| s.to_str()) {
+                Some("py") => &mut files.py_files,
+                Some("ipynb") => &mut files.notebooks,
+                Some(ext) if CPP_EXTENSIONS.contains(&ext) => &mut files.cpp_files,
+                Some(ext) if SCRIPT_EXTENSIONS.contains(&ext) => &mut files.script_files,
+                _ => continue,
+            };
+            list.push(path.to_path_buf());
+        }
+        Ok(files)
+    }
+}
+
+/// Normalizes a path for use as a HashMap key.
+///
+/// Converts to UTF-8 string with forward slashes, stripping UNC prefix on Windows.
+pub(crate) fn normalize_path(path: &Path) -> String {
+    dunce::simplified(path).to_string_lossy().replace('\\', "/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use petgraph::Direction;
+    use std::fs;
+
+    #[test]
+    fn test_empty_directory() {
+        let tmp = std::env::temp_dir().join("test_graph_empty");
+        fs::create_dir_all(&tmp).ok();
+
+        let mut host = ParserHost::new().unwrap();
+        let result = build_reference_graph(&tmp, &mut host);
+
+        assert!(result.is_ok());
+        let graph = result.unwrap();
+        assert_eq!(graph.stats.file_count, 0);
+        assert_eq!(graph.stats.symbol_count, 0);
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_single_file() {
+        let tmp = std::env::temp_dir().join("test_graph_single");
+        fs::create_dir_all(&tmp).ok();
+        let test_py = tmp.join("test.py");
+        fs::write(&test_py, "def foo():\n    pass\n").ok();
+
+        let mut host = ParserHost::new().unwrap();
+        let result = build_reference_graph(&tmp, &mut host);
+
+        assert!(result.is_ok());
+        let graph = result.unwrap();
+        assert_eq!(graph.stats.file_count, 1);
+        assert!(graph.stats.symbol_count >= 1);
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_two_file_import_edge() {
+        let tmp = std::env::temp_dir().join("test_graph_import");
+        fs::create_dir_all(&tmp).ok();
+
+        let mod_a = tmp.join("mod_a.py");
+        fs::write(&mod_a, "def helper():\n    pass\n").ok();
+
+        // main() calls helper() — must produce edge main → helper
+        let mod_b = tmp.join("mod_b.py");
+        fs::write(
+            &mod_b,
+            "from mod_a import helper\n\ndef main():\n    helper()\n",
+        )
+        .ok();
+
+        let mut host = ParserHost::new().unwrap();
+        let result = build_reference_graph(&tmp, &mut host);
+
+        assert!(result.is_ok());
+        let graph = result.unwrap();
+        assert_eq!(graph.stats.file_count, 2);
+        assert_eq!(
+            graph.stats.edge_count, 1,
+            "expected exactly 1 edge: main → helper"
+        );
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_two_file_es_import_edge() {
+        let tmp = std::env::temp_dir().join("test_graph_es_import");
+        fs::remove_dir_all(&tmp).ok();
+        fs::create_dir_all(&tmp).ok();
+
+        fs::write(tmp.join("mod_a.ts"), "export function helper(): void {}\n").ok();
+        // main() calls helper() through an extensionless import — edge main → helper
+        fs::write(
+            tmp.join("mod_b.ts"),
+            "import { helper } from './mod_a';\n\nexport function main() {\n  helper();\n}\n",
+        )
+        .ok();
+
+        let mut host = ParserHost::new().unwrap();
+        let graph = build_reference_graph(&tmp, &mut host).unwrap();
+        assert_eq!(graph.stats.file_count, 2);
+        assert_eq!(
+            graph.stats.edge_count, 1,
+            "expected exactly 1 edge: main → helper"
+        );
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_es_import_forms_edges() {
+        let tmp = std::env::temp_dir().join("test_graph_es_forms");
+        fs::remove_dir_all(&tmp).ok();
+        fs::create_dir_all(tmp.join("utils")).ok();
+        fs::write(tmp.join("utils/format.ts"), "export function fmt() {}\n").ok();
+        fs::write(tmp.join("utils/index.ts"), "export * from './format';\n").ok();
+        fs::write(
+            tmp.join("store.js"),
+            "export default class Store {\n  load() { this.save(); }\n  save() {}\n}\n",
+        )
+        .ok();
+        fs::write(
+            tmp.join("lib.js"),
+            "function run() {}\nmodule.exports = { run };\n",
+        )
+        .ok();
+        fs::write(
+            tmp.join("app.jsx"),
+            "import Store from './store';\nimport { fmt as format } from './utils';\nconst lib = require('./lib');\n\nexport function App() {\n  const s = new Store();\n  lib.run();\n  return <Panel title={format()} />;\n}\n\nfunction Panel() { return null; }\n",
+        )
+        .ok();
+
+        let mut host = ParserHost::new().unwrap();
+        let graph = build_reference_graph(&tmp, &mut host).unwrap();
+        let callers = |qname: &str| {
+            let id = graph
+                .registry
+                .entries
+                .iter()
+                .find(|e| e.qualified_name == qname)
+                .unwrap_or_else(|| panic!("{qname} not indexed"))
+                .id;
+            let node = graph
+                .graph
+                .node_indices()
+                .find(|&n| graph.graph[n] == id)
+                .unwrap();
+            graph
+                .graph
+                .edges_directed(node, Direction::Incoming)
+                .map(|e| {
+                    let source = graph.graph[e.source()];
+                    let entry = graph.registry.entries.iter().find(|r| r.id == source);
+                    entry.unwrap().qualified_name.clone()
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(callers("Store"), vec!["App"]);
+        assert_eq!(callers("fmt"), vec!["App"]);
+        assert_eq!(callers("run"), vec!["App"]);
+        assert_eq!(callers("Panel"), vec!["App"]);
+        assert_eq!(callers("Store.save"), vec!["Store.load"]);
+        assert!(callers("App").is_empty());
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_relative_import_edge() {
+        let tmp = std::env::temp_dir().join("test_graph_relative");
+        fs::create_dir_all(tmp.join("pkg")).ok();
+
+        let utils = tmp.join("pkg/utils.py");
+        fs::write(&utils, "def util():\n    pass\n").ok();
+
+        // run() calls util() — must produce edge run → util
+        let main = tmp.join("pkg/main.py");
+        fs::write(&main, "from .utils import util\n\ndef run():\n    util()\n").ok();
+
+        let mut host = ParserHost::new().unwrap();
+        let result = build_reference_graph(&tmp, &mut host);
+
+        assert!(result.is_ok());
+        let graph = result.unwrap();
+        assert_eq!(graph.stats.file_count, 2);
+        assert_eq!(
+            graph.stats.edge_count, 1,
+            "expected exactly 1 edge: run → util"
+        );
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_attribute_call_edge() {
+        let tmp = std::env::temp_dir().join("test_graph_attr_call");
+        fs::create_dir_all(&tmp).ok();
+
+        // utils.py defines process()
+        let utils = tmp.join("utils.py");
+        fs::write(&utils, "def process():\n    pass\n").ok();
+
+        // main.py: bare import, run() calls utils.process()
+        let main = tmp.join("main.py");
+        fs::write(&main, "import utils\n\ndef run():\n    utils.process()\n").ok();
+
+        let mut host = ParserHost::new().unwrap();
+        let result = build_reference_graph(&tmp, &mut host);
+
+        assert!(result.is_ok());
+        let graph = result.unwrap();
+        assert_eq!(graph.stats.file_count, 2);
+        assert_eq!(
+            graph.stats.edge_count, 1,
+            "expected exactly 1 edge: run → process"
+        );
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    /// Returns `true` if the symbol named `name` has at least one incoming edge.
+    fn is_referenced(graph: &ReferenceGraph, name: &str) -> bool {
+        graph
+            .registry
+            .entries
+            .iter()
+            .filter(|e| e.name == name)
+            .any(|e| {
+                graph.graph.node_indices().any(|n| {
+                    graph.graph[n] == e.id
+                        && graph
+                            .graph
+                            .edges_directed(n, Direction::Incoming)
+                            .next()
+                            .is_some()
+                })
+            })
+    }
+
+    #[test]
+    fn test_from_import_alias_edge() {
+        let tmp = std::env::temp_dir().join("test_graph_from_alias");
+        fs::create_dir_all(&tmp).ok();
+
+        fs::write(tmp.join("utils.py"), "def helper():\n    pass\n").ok();
+        fs::write(
+            tmp.join("main.py"),
+            "from utils import helper as h\n\ndef run():\n    h()\n",
+        )
+        .ok();
+
+        let mut host = ParserHost::new().unwrap();
+        let graph = build_reference_graph(&tmp, &mut host).unwrap();
+        assert!(is_referenced(&graph, "helper"));
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_module_alias_attribute_edge() {
+        let tmp = std::env::temp_dir().join("test_graph_module_alias");
+        fs::create_dir_all(&tmp).ok();
+
+        fs::write(tmp.join("utils.py"), "def helper():\n    pass\n").ok();
+        fs::write(
+            tmp.join("main.py"),
+            "import utils as u\n\ndef run():\n    u.helper()\n",
+        )
+        .ok();
+
+        let mut host = ParserHost::new().unwrap();
+        let graph = build_reference_graph(&tmp, &mut host).unwrap();
+        assert!(is_referenced(&graph, "helper"));
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_alias_shadows_imported_name() {
+        let tmp = std::env::temp_dir().join("test_graph_alias_shadow");
+        fs::create_dir_all(&tmp).ok();
+
+        // `h` is also a real function in utils, but the alias rebinds the local name.
+        fs::write(
+            tmp.join("utils.py"),
+            "def helper():\n    pass\n\ndef h():\n    pass\n",
+        )
+        .ok();
+        fs::write(
+            tmp.join("main.py"),
+            "from utils import helper as h\n\ndef run():\n    h()\n    helper()\n",
+        )
+        .ok();
 
-        match ParserHost::extract_cpp_entities(source, &file_key) {
-            Ok(entities) => {
-                for entity in entities {
-                    let symbol_id = entity.symbol_id();
-                    let hash = symbol_hash(&symbol_id);
-
-                    let entry = SymbolEntry {
-                        id: hash,
-                        name: entity.name.clone(),
-                        qualified_name: entity.qualified_name.clone(),
-                        file_path: entity.file_path.clone(),
-                        entity_type: entity.entity_type as u8,
-                        start_line: entity.start_line,
-                        end_line: entity.end_line,
-                        start_byte: entity.start_byte,
-                        end_byte: entity.end_byte,
-                        structural_hash: entity.structural_hash.unwrap_or(0),
-                        protected_by: entity.protected_by,
-                    };
-                    registry.insert(entry);
+        let mut host = ParserHost::new().unwrap();
+        let graph = build_reference_graph(&tmp, &mut host).unwrap();
+        assert!(is_referenced(&graph, "helper"));
+        assert!(!is_referenced(&graph, "h"));
+        assert_eq!(graph.stats.edge_count, 1, "only h() → helper resolves");
 
-                    let node_idx = graph.add_node(hash);
-                    id_to_node.insert(hash, node_idx);
-                    file_symbols
-                        .entry(entity.file_path.clone())
-                        .or_default()
-                        .push(hash);
+        fs::remove_dir_all(tmp).ok();
+    }
 
-                    all_entities.push(entity);
-                    stats.symbol_count += 1;
-                }
-            }
-            Err(_) => {
-                stats.parse_errors += 1;
-            }
-        }
+    #[test]
+    fn test_reexport_chain_and_cycle() {
+        let tmp = std::env::temp_dir().join("test_graph_reexport_chain");
+        fs::remove_dir_all(&tmp).ok();
+        fs::create_dir_all(tmp.join("pkg/sub")).ok();
+
+        // pkg → pkg.sub → pkg.sub.impl (two hops), plus a cycle between a and b.
+        fs::write(tmp.join("pkg/__init__.py"), "from .sub import deep\n").ok();
+        fs::write(
+            tmp.join("pkg/sub/__init__.py"),
+            "from .impl import deep as deep\n",
+        )
+        .ok();
+        fs::write(tmp.join("pkg/sub/impl.py"), "def deep():\n    pass\n").ok();
+        fs::write(tmp.join("a.py"), "from b import ghost\n").ok();
+        fs::write(
+            tmp.join("b.py"),
+            "from a import ghost\n\ndef lonely():\n    pass\n",
+        )
+        .ok();
+        fs::write(
+            tmp.join("main.py"),
+            "from pkg import deep\nfrom a import ghost\n\ndef run():\n    deep()\n    ghost()\n",
+        )
+        .ok();
+
+        let mut host = ParserHost::new().unwrap();
+        let graph = build_reference_graph(&tmp, &mut host).unwrap();
+        // `ghost` resolves nowhere, so the cycle adds no edge to anything.
+        let mut referenced: Vec<&str> = graph
+            .entities
+            .iter()
+            .map(|e| e.name.as_str())
+            .filter(|name| is_referenced(&graph, name))
+            .collect();
+        referenced.sort();
+        assert_eq!(referenced, vec!["deep"]);
+
+        fs::remove_dir_all(tmp).ok();
     }
 
-    // Build C++ file-key index for include resolution
-    let cpp_file_keys: HashSet<String> = cpp_files
-        .iter()
-        .filter_map(|p| dunce::canonicalize(p).ok())
-        .map(|p| normalize_path(&p))
-        .collect();
+    #[test]
+    fn test_no_edge_without_call() {
+        let tmp = std::env::temp_dir().join("test_graph_no_call");
+        fs::create_dir_all(&tmp).ok();
 
-    // PASS 2b: Wire #include edges as __MODULE__ → __MODULE__ file-level links
-    for source_path in &cpp_files {
-        let file = match File::open(source_path) {
-            Ok(f) => f,
-            Err(_) => continue,
-        };
-        let mmap = match unsafe { Mmap::map(&file) } {
-            Ok(m) => m,
-            Err(_) => continue,
-        };
-        let source = &mmap[..];
-        let includes = extract_cpp_includes(source);
-        if includes.is_empty() {
-            continue;
-        }
+        let utils = tmp.join("utils.py");
+        fs::write(&utils, "def func():\n    pass\n").ok();
 
-        let source_canonical = match dunce::canonicalize(source_path) {
-            Ok(p) => p,
-            Err(_) => continue,
-        };
-        let source_file_key = normalize_path(&source_canonical);
-        let src_module_id = symbol_hash(&format!("{}::__MODULE__", source_file_key));
-        let src_node = match id_to_node.get(&src_module_id) {
-            Some(&n) => n,
-            None => continue,
-        };
-        let source_dir = source_canonical
-            .parent()
-            .unwrap_or(root.as_path())
-            .to_path_buf();
+        // main.py imports but never calls func()
+        let main = tmp.join("main.py");
+        fs::write(&main, "from utils import func\n\ndef run():\n    pass\n").ok();
 
-        for include in &includes {
-            // Try relative-to-source-dir first, then relative-to-project-root
-            let target_abs = [source_dir.join(&include.path), root.join(&include.path)]
-                .into_iter()
-                .find(|p| p.exists())
-                .and_then(|p| dunce::canonicalize(p).ok());
+        let mut host = ParserHost::new().unwrap();
+        let result = build_reference_graph(&tmp, &mut host);
 
-            let Some(target_abs) = target_abs else {
-                continue;
-            };
-            let target_file_key = normalize_path(&target_abs);
-            if !cpp_file_keys.contains(&target_file_key) {
-                continue;
-            }
-            let tgt_module_id = symbol_hash(&format!("{}::__MODULE__", target_file_key));
-            if let Some(&tgt_node) = id_to_node.get(&tgt_module_id) {
-                graph.add_edge(src_node, tgt_node, ());
-                stats.edge_count += 1;
-            }
-        }
+        assert!(result.is_ok());
+        let graph = result.unwrap();
+        assert_eq!(graph.stats.file_count, 2);
+        assert_eq!(
+            graph.stats.edge_count, 0,
+            "expected 0 edges: import without call"
+        );
+
+        fs::remove_dir_all(tmp).ok();
     }
 
-    Ok(ReferenceGraph {
-        registry,
-        graph,
-        file_symbols,
-        entities: all_entities,
-        stats,
-    })
-}
+    #[test]
+    fn test_constant_read_edges() {
+        let tmp = std::env::temp_dir().join("test_graph_constant_reads");
+        fs::remove_dir_all(&tmp).ok();
+        fs::create_dir_all(&tmp).ok();
+        fs::write(
+            tmp.join("config.py"),
+            "LIMIT = 10\nSHADOWED = 1\nIMPORTED_ONLY = 2\nLOCAL = LIMIT * 2\n",
+        )
+        .ok();
+        // Imports, keyword names, and assignment targets are not reads.
+        fs::write(
+            tmp.join("main.py"),
+            "import config\nfrom config import IMPORTED_ONLY\n\nSHADOWED = 5\n\ndef run(LOCAL=None):\n    return config.LIMIT, f(SHADOWED=1)\n",
+        )
+        .ok();
 
-/// Walks a directory for `.py` files, skipping excluded directories.
-fn walk_py_files(root: &Path) -> Result<Vec<PathBuf>, AnatomistError> {
-    let mut files = Vec::new();
+        let mut host = ParserHost::new().unwrap();
+        let graph = build_reference_graph(&tmp, &mut host).unwrap();
 
-    for entry in WalkDir::new(root)
-        .into_iter()
-        .filter_entry(|e| !is_excluded(e.path()))
-    {
-        let entry = entry.map_err(|e| AnatomistError::IoError(e.into()))?;
-        let path = entry.path();
-        if path.is_file() && path.extension().and_then(|s| s.to_str()) == Some("py") {
-            files.push(path.to_path_buf());
-        }
+        assert!(is_referenced(&graph, "LIMIT"));
+        assert!(!is_referenced(&graph, "SHADOWED"));
+        assert!(!is_referenced(&graph, "IMPORTED_ONLY"));
+        assert!(!is_referenced(&graph, "LOCAL"));
+
+        fs::remove_dir_all(tmp).ok();
     }
 
-    Ok(files)
-}
+    #[test]
+    fn test_lambda_and_conditional_definition_edges() {
+        let tmp = std::env::temp_dir().join("test_graph_lambda_conditional");
+        fs::remove_dir_all(&tmp).ok();
+        fs::create_dir_all(&tmp).ok();
+        fs::write(
+            tmp.join("ports.py"),
+            "import sys\n\nif sys.platform == \"win32\":\n    def open_port():\n        return 1\nelse:\n    def open_port():\n        return 2\n\ndef process(evt):\n    return evt\n",
+        )
+        .ok();
+        fs::write(
+            tmp.join("events.py"),
+            "from ports import open_port, process\n\nhandler = lambda evt: process(open_port())\n",
+        )
+        .ok();
 
-/// Walks a directory for C++ source files (`.cpp`, `.cxx`, `.cc`, `.h`, `.hpp`),
-/// skipping the same excluded directories as [`walk_py_files`].
-fn walk_cpp_files(root: &Path) -> Result<Vec<PathBuf>, AnatomistError> {
-    let mut files = Vec::new();
+        let mut host = ParserHost::new().unwrap();
+        let graph = build_reference_graph(&tmp, &mut host).unwrap();
 
-    for entry in WalkDir::new(root)
-        .into_iter()
-        .filter_entry(|e| !is_excluded(e.path()))
-    {
-        let entry = entry.map_err(|e| AnatomistError::IoError(e.into()))?;
-        let path = entry.path();
-        if path.is_file() {
-            if let Some("cpp" | "cxx" | "cc" | "h" | "hpp") =
-                path.extension().and_then(|s| s.to_str())
-            {
-                files.push(path.to_path_buf());
-            }
+        let ids: Vec<u64> = graph.registry.entries.iter().map(|e| e.id).collect();
+        let unique: HashSet<u64> = ids.iter().copied().collect();
+        assert_eq!(ids.len(), unique.len());
+
+        let node_of = |qname: &str| {
+            let id = graph
+                .registry
+                .entries
+                .iter()
+                .find(|e| e.qualified_name == qname)
+                .unwrap()
+                .id;
+            graph
+                .graph
+                .node_indices()
+                .find(|&n| graph.graph[n] == id)
+                .unwrap()
+        };
+        let handler = node_of("handler");
+        for target in ["process", "open_port", "open_port#2"] {
+            assert!(
+                graph.graph.contains_edge(handler, node_of(target)),
+                "missing edge handler -> {}",
+                target
+            );
         }
-    }
 
-    Ok(files)
-}
+        fs::remove_dir_all(tmp).ok();
+    }
 
-/// Returns `true` if the path should be excluded from walking.
-fn is_excluded(path: &Path) -> bool {
-    if let Some(name) = path.file_name().and_then(|s| s.to_str()) {
-        matches!(
-            name,
-            "__pycache__"
-                | ".git"
-                | ".janitor"
-                | "venv"
-                | ".venv"
-                | "target"
-                | "node_modules"
-                | ".pytest_cache"
+    #[test]
+    fn test_edges_record_line_and_kind() {
+        let tmp = std::env::temp_dir().join("test_graph_edge_info");
+        fs::remove_dir_all(&tmp).ok();
+        fs::create_dir_all(&tmp).ok();
+        fs::write(tmp.join("lib.py"), "def target():\n    pass\n").ok();
+        fs::write(
+            tmp.join("app.py"),
+            "from lib import target\n\ndef run():\n    x = 1\n    return target()\n",
         )
-    } else {
-        false
-    }
-}
+        .ok();
+        fs::write(tmp.join("util.h"), "int helper();\n").ok();
+        fs::write(
+            tmp.join("main.cpp"),
+            "// entry\n#include \"util.h\"\nint main() { return helper(); }\n",
+        )
+        .ok();
 
-/// Normalizes a path for use as a HashMap key.
-///
-/// Converts to UTF-8 string with forward slashes, stripping UNC prefix on Windows.
-fn normalize_path(path: &Path) -> String {
-    dunce::simplified(path).to_string_lossy().replace('\\', "/")
-}
+        let mut host = ParserHost::new().unwrap();
+        let graph = build_reference_graph(&tmp, &mut host).unwrap();
+        let edge_into = |file: &str, qname: &str| {
+            let id = graph
+                .registry
+                .entries
+                .iter()
+                .find(|e| e.file_path.ends_with(file) && e.qualified_name == qname)
+                .unwrap()
+                .id;
+            let node = graph
+                .graph
+                .node_indices()
+                .find(|&n| graph.graph[n] == id)
+                .unwrap();
+            *graph
+                .graph
+                .edges_directed(node, Direction::Incoming)
+                .next()
+                .unwrap()
+                .weight()
+        };
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::fs;
+        assert_eq!(
+            edge_into("lib.py", "target"),
+            EdgeInfo::new(5, EdgeKind::Call)
+        );
+        assert_eq!(
+            edge_into("util.h", "__MODULE__"),
+            EdgeInfo::new(2, EdgeKind::Include)
+        );
+        assert_eq!(graph.stats.edge_count, graph.graph.edge_count());
+
+        fs::remove_dir_all(tmp).ok();
+    }
 
     #[test]
-    fn test_empty_directory() {
-        let tmp = std::env::temp_dir().join("test_graph_empty");
+    fn test_repeated_calls_collapse_into_one_edge() {
+        let tmp = std::env::temp_dir().join("test_graph_edge_dedup");
+        fs::remove_dir_all(&tmp).ok();
         fs::create_dir_all(&tmp).ok();
+        fs::write(tmp.join("lib.py"), "def target():\n    pass\n").ok();
+        fs::write(
+            tmp.join("app.py"),
+            "from lib import target\n\ndef run():\n    target()\n    target()\n    return target()\n\ndef other():\n    target()\n",
+        )
+        .ok();
 
         let mut host = ParserHost::new().unwrap();
-        let result = build_reference_graph(&tmp, &mut host);
+        let graph = build_reference_graph(&tmp, &mut host).unwrap();
 
-        assert!(result.is_ok());
-        let graph = result.unwrap();
-        assert_eq!(graph.stats.file_count, 0);
-        assert_eq!(graph.stats.symbol_count, 0);
+        assert_eq!(graph.stats.edge_count, 2);
+        assert_eq!(graph.graph.edge_count(), 2);
+        let counts: Vec<(u32, u32)> = graph
+            .graph
+            .edge_references()
+            .map(|e| (e.weight().line, e.weight().count))
+            .collect();
+        assert!(counts.contains(&(4, 3)));
+        assert!(counts.contains(&(9, 1)));
 
         fs::remove_dir_all(tmp).ok();
     }
 
     #[test]
-    fn test_single_file() {
-        let tmp = std::env::temp_dir().join("test_graph_single");
+    fn test_intra_file_call_edges() {
+        let tmp = std::env::temp_dir().join("test_graph_intra_file");
+        fs::remove_dir_all(&tmp).ok();
         fs::create_dir_all(&tmp).ok();
-        let test_py = tmp.join("test.py");
-        fs::write(&test_py, "def foo():\n    pass\n").ok();
+        fs::write(
+            tmp.join("main.py"),
+            "def helper():\n    return 1\n\ndef run():\n    return len(print(helper()))\n\ndef fact(n):\n    return fact(n - 1)\n",
+        )
+        .ok();
 
         let mut host = ParserHost::new().unwrap();
-        let result = build_reference_graph(&tmp, &mut host);
+        let graph = build_reference_graph(&tmp, &mut host).unwrap();
 
-        assert!(result.is_ok());
-        let graph = result.unwrap();
-        assert_eq!(graph.stats.file_count, 1);
-        assert!(graph.stats.symbol_count >= 1);
+        assert!(is_referenced(&graph, "helper"));
+        assert!(!is_referenced(&graph, "run"));
+        // Builtins the file does not define, and recursion, add no edges.
+        assert!(!is_referenced(&graph, "fact"));
+        assert_eq!(graph.stats.edge_count, 1);
 
         fs::remove_dir_all(tmp).ok();
     }
 
     #[test]
-    fn test_two_file_import_edge() {
-        let tmp = std::env::temp_dir().join("test_graph_import");
+    fn test_decorator_edges() {
+        let tmp = std::env::temp_dir().join("test_graph_decorators");
+        fs::remove_dir_all(&tmp).ok();
         fs::create_dir_all(&tmp).ok();
-
-        let mod_a = tmp.join("mod_a.py");
-        fs::write(&mod_a, "def helper():\n    pass\n").ok();
-
-        // main() calls helper() — must produce edge main → helper
-        let mod_b = tmp.join("mod_b.py");
         fs::write(
-            &mod_b,
-            "from mod_a import helper\n\ndef main():\n    helper()\n",
+            tmp.join("hooks.py"),
+            "def audit(fn):\n    return fn\n\ndef retry(handler=None):\n    return audit\n\ndef on_failure():\n    pass\n",
+        )
+        .ok();
+        fs::write(
+            tmp.join("jobs.py"),
+            "import hooks\nfrom hooks import retry, on_failure\n\ndef local_cache(fn):\n    return fn\n\n@local_cache\n@hooks.audit\ndef sync():\n    pass\n\n@retry(handler=on_failure)\ndef fetch():\n    pass\n",
         )
         .ok();
 
         let mut host = ParserHost::new().unwrap();
-        let result = build_reference_graph(&tmp, &mut host);
+        let graph = build_reference_graph(&tmp, &mut host).unwrap();
+        let node_of = |qname: &str| {
+            let id = graph
+                .registry
+                .entries
+                .iter()
+                .find(|e| e.qualified_name == qname)
+                .unwrap()
+                .id;
+            graph
+                .graph
+                .node_indices()
+                .find(|&n| graph.graph[n] == id)
+                .unwrap()
+        };
+        let edge = |from: &str, to: &str| {
+            graph
+                .graph
+                .find_edge(node_of(from), node_of(to))
+                .map(|e| graph.graph[e])
+        };
 
-        assert!(result.is_ok());
-        let graph = result.unwrap();
-        assert_eq!(graph.stats.file_count, 2);
         assert_eq!(
-            graph.stats.edge_count, 1,
-            "expected exactly 1 edge: main → helper"
+            edge("sync", "local_cache"),
+            Some(EdgeInfo::new(7, EdgeKind::Decorator))
+        );
+        assert_eq!(
+            edge("sync", "audit"),
+            Some(EdgeInfo::new(8, EdgeKind::Decorator))
+        );
+        assert_eq!(
+            edge("fetch", "retry"),
+            Some(EdgeInfo::new(12, EdgeKind::Call))
+        );
+        assert_eq!(
+            edge("fetch", "on_failure"),
+            Some(EdgeInfo::new(12, EdgeKind::Decorator))
         );
 
         fs::remove_dir_all(tmp).ok();
     }
 
     #[test]
-    fn test_relative_import_edge() {
-        let tmp = std::env::temp_dir().join("test_graph_relative");
-        fs::create_dir_all(tmp.join("pkg")).ok();
+    fn test_forward_refs_skip_literal_values_and_annotated_metadata() {
+        let source = b"def f(a: \"Config\", b: Optional[\"pkg.models.Order\"], c: Literal[\"Draft\"],\n      d: Annotated[\"Vector\", \"Meta\"], e: t.Literal[\"Sent\"], g: f\"{x}\") -> \"dict[str, Literal['Spam'], Annotated[Egg, Ham]]\":\n    return \"Plain\"\n";
+        let mut parser = Parser::new();
+        parser
+            .set_language(&tree_sitter_python::LANGUAGE.into())
+            .unwrap();
+        let tree = parser.parse(source, None).unwrap();
+        let names: Vec<String> = extract_annotation_names(source, tree.root_node())
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "Config",
+                "pkg.models.Order",
+                "Vector",
+                "dict",
+                "str",
+                "Literal",
+                "Annotated",
+                "Egg"
+            ]
+        );
+        assert_eq!(
+            dotted_segments("pkg.models.Order").collect::<Vec<_>>(),
+            vec![(3, "pkg"), (10, "models"), (16, "Order")]
+        );
+    }
 
-        let utils = tmp.join("pkg/utils.py");
-        fs::write(&utils, "def util():\n    pass\n").ok();
+    #[test]
+    fn test_string_names_come_from_registries_and_dotted_paths() {
+        let source = b"\"\"\"Mentions documented_only.\"\"\"\n\n__all__ = [\"exported\"]\nCELERY_TASK_ROUTES = {\n    \"app.tasks.send_email\": {\"queue\": \"mail\"},\n}\nAPP = \"app.main:create_app\"\nNAME = \"send_emails_later\"\nlog(\"create_report failed\")\nif mode == \"app.legacy\":\n    uvicorn.run(\"app.api:server\")\n";
+        let mut parser = Parser::new();
+        parser
+            .set_language(&tree_sitter_python::LANGUAGE.into())
+            .unwrap();
+        let tree = parser.parse(source, None).unwrap();
+        let names = extract_string_names(source, tree.root_node());
+        let found = |name: &str| names.iter().find(|(n, _)| n == name).map(|(_, line)| *line);
 
-        // run() calls util() — must produce edge run → util
-        let main = tmp.join("pkg/main.py");
-        fs::write(&main, "from .utils import util\n\ndef run():\n    util()\n").ok();
+        assert_eq!(found("exported"), Some(3));
+        assert_eq!(found("send_email"), Some(5));
+        assert_eq!(found("app.tasks"), Some(5));
+        assert_eq!(found("mail"), Some(5));
+        assert_eq!(found("create_app"), Some(7));
+        assert_eq!(found("app.api"), Some(11));
+        assert_eq!(found("server"), Some(11));
+        // Prose, plain values, comparisons and whole-word parts are not names.
+        assert_eq!(found("documented_only"), None);
+        assert_eq!(found("send_emails_later"), None);
+        assert_eq!(found("later"), None);
+        assert_eq!(found("create_report"), None);
+        assert_eq!(found("legacy"), None);
+    }
+
+    #[test]
+    fn test_inheritance_and_annotation_edges() {
+        let tmp = std::env::temp_dir().join("test_graph_inherit_typeref");
+        fs::remove_dir_all(&tmp).ok();
+        fs::create_dir_all(&tmp).ok();
+        fs::write(
+            tmp.join("base.py"),
+            "class Base:\n    pass\n\nclass Meta(type):\n    pass\n",
+        )
+        .ok();
+        fs::write(
+            tmp.join("app.py"),
+            "from base import Base, Meta\n\nclass Result:\n    pass\n\nclass Child(Base, metaclass=Meta):\n    def run(self, other: Base) -> Result:\n        pass\n",
+        )
+        .ok();
 
         let mut host = ParserHost::new().unwrap();
-        let result = build_reference_graph(&tmp, &mut host);
+        let graph = build_reference_graph(&tmp, &mut host).unwrap();
+        let node_of = |qname: &str| {
+            let id = graph
+                .registry
+                .entries
+                .iter()
+                .find(|e| e.qualified_name == qname)
+                .unwrap()
+                .id;
+            graph
+                .graph
+                .node_indices()
+                .find(|&n| graph.graph[n] == id)
+                .unwrap()
+        };
+        let kind = |from: &str, to: &str| {
+            graph
+                .graph
+                .find_edge(node_of(from), node_of(to))
+                .map(|e| graph.graph[e].kind)
+        };
 
-        assert!(result.is_ok());
-        let graph = result.unwrap();
-        assert_eq!(graph.stats.file_count, 2);
-        assert_eq!(
-            graph.stats.edge_count, 1,
-            "expected exactly 1 edge: run → util"
-        );
+        assert_eq!(kind("Child", "Base"), Some(EdgeKind::Inherit));
+        assert_eq!(kind("Child", "Meta"), Some(EdgeKind::Inherit));
+        assert_eq!(kind("Child.run", "Base"), Some(EdgeKind::TypeRef));
+        assert_eq!(kind("Child.run", "Result"), Some(EdgeKind::TypeRef));
 
         fs::remove_dir_all(tmp).ok();
     }
 
-    #[test]
-    fn test_attribute_call_edge() {
-        let tmp = std::env::temp_dir().join("test_graph_attr_call");
+    fn export_fixture(name: &str) -> (PathBuf, ReferenceGraph) {
+        let tmp = std::env::temp_dir().join(name);
+        fs::remove_dir_all(&tmp).ok();
         fs::create_dir_all(&tmp).ok();
+        fs::write(
+            tmp.join("a.py"),
+            "from b import helper\n\ndef main():\n    return helper()\n",
+        )
+        .ok();
+        fs::write(
+            tmp.join("b.py"),
+            "from c import leaf\n\ndef helper():\n    return leaf()\n",
+        )
+        .ok();
+        fs::write(
+            tmp.join("c.py"),
+            "def leaf():\n    return 1\n\ndef lonely():\n    pass\n",
+        )
+        .ok();
 
-        // utils.py defines process()
-        let utils = tmp.join("utils.py");
-        fs::write(&utils, "def process():\n    pass\n").ok();
+        let mut host = ParserHost::new().unwrap();
+        let graph = build_reference_graph(&tmp, &mut host).unwrap();
+        (tmp, graph)
+    }
 
-        // main.py: bare import, run() calls utils.process()
-        let main = tmp.join("main.py");
-        fs::write(&main, "import utils\n\ndef run():\n    utils.process()\n").ok();
+    fn id_of(graph: &ReferenceGraph, qname: &str) -> u64 {
+        graph
+            .registry
+            .entries
+            .iter()
+            .find(|e| e.qualified_name == qname)
+            .unwrap()
+            .id
+    }
 
-        let mut host = ParserHost::new().unwrap();
-        let result = build_reference_graph(&tmp, &mut host);
+    #[test]
+    fn test_export_dot_clusters_and_edges() {
+        let (tmp, graph) = export_fixture("test_graph_export_dot");
+        let mut out = Vec::new();
+        graph
+            .export_dot(&mut out, &ExportOptions::default())
+            .unwrap();
+        let dot = String::from_utf8(out).unwrap();
 
-        assert!(result.is_ok());
-        let graph = result.unwrap();
-        assert_eq!(graph.stats.file_count, 2);
-        assert_eq!(
-            graph.stats.edge_count, 1,
-            "expected exactly 1 edge: run → process"
+        assert!(dot.starts_with("digraph references {"));
+        assert_eq!(dot.matches("subgraph cluster_").count(), 3);
+        let (main, helper, leaf) = (
+            id_of(&graph, "main"),
+            id_of(&graph, "helper"),
+            id_of(&graph, "leaf"),
         );
+        assert!(dot.contains(&format!("n{} -> n{};", main, helper)));
+        assert!(dot.contains(&format!("n{} -> n{};", helper, leaf)));
+
+        // Every node sits in the cluster of its own file.
+        let clusters: Vec<&str> = dot.split("subgraph cluster_").skip(1).collect();
+        let cluster_of = |id: u64| {
+            let node = format!("n{} [label=", id);
+            clusters
+                .iter()
+                .position(|c| c.split("    }").next().unwrap().contains(&node))
+        };
+        assert!(clusters[cluster_of(main).unwrap()].contains("a.py\""));
+        assert!(clusters[cluster_of(leaf).unwrap()].contains("c.py\""));
+        assert_eq!(cluster_of(leaf), cluster_of(id_of(&graph, "lonely")));
 
         fs::remove_dir_all(tmp).ok();
     }
 
     #[test]
-    fn test_no_edge_without_call() {
-        let tmp = std::env::temp_dir().join("test_graph_no_call");
-        fs::create_dir_all(&tmp).ok();
-
-        let utils = tmp.join("utils.py");
-        fs::write(&utils, "def func():\n    pass\n").ok();
-
-        // main.py imports but never calls func()
-        let main = tmp.join("main.py");
-        fs::write(&main, "from utils import func\n\ndef run():\n    pass\n").ok();
+    fn test_export_focus_limits_neighborhood() {
+        let (tmp, graph) = export_fixture("test_graph_export_focus");
+        let options = ExportOptions {
+            focus: Some("leaf".to_string()),
+            depth: 1,
+        };
+        let mut out = Vec::new();
+        graph.export_json(&mut out, &options).unwrap();
+        let doc: serde_json::Value = serde_json::from_slice(&out).unwrap();
 
-        let mut host = ParserHost::new().unwrap();
-        let result = build_reference_graph(&tmp, &mut host);
+        let nodes = doc["nodes"].as_array().unwrap();
+        let names: Vec<&str> = nodes
+            .iter()
+            .map(|n| n["qualified_name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names.len(), 2);
+        assert!(names.contains(&"helper") && names.contains(&"leaf"));
+        let helper = nodes
+            .iter()
+            .find(|n| n["qualified_name"] == "helper")
+            .unwrap();
+        assert_eq!(helper["edges"], serde_json::json!([id_of(&graph, "leaf")]));
+        assert!(helper["file_path"].as_str().unwrap().ends_with("b.py"));
 
-        assert!(result.is_ok());
-        let graph = result.unwrap();
-        assert_eq!(graph.stats.file_count, 2);
-        assert_eq!(
-            graph.stats.edge_count, 0,
-            "expected 0 edges: import without call"
-        );
+        let unknown = ExportOptions {
+            focus: Some("missing".to_string()),
+            depth: 1,
+        };
+        assert!(matches!(
+            graph.export_dot(&mut Vec::new(), &unknown),
+            Err(AnatomistError::UnknownSymbol(_))
+        ));
 
         fs::remove_dir_all(tmp).ok();
     }
@@ -768,6 +3863,83 @@ mod tests {
         fs::remove_dir_all(tmp).ok();
     }
 
+    #[test]
+    fn test_skips_gitignored_dir() {
+        let tmp = std::env::temp_dir().join("test_graph_gitignore");
+        fs::create_dir_all(tmp.join("vendored")).ok();
+        fs::write(tmp.join(".gitignore"), "vendored/\n").ok();
+        fs::write(tmp.join("vendored/lib.py"), "def v():\n    pass\n").ok();
+        fs::write(tmp.join("app.py"), "def foo():\n    pass\n").ok();
+
+        let mut host = ParserHost::new().unwrap();
+        let graph = build_reference_graph(&tmp, &mut host).unwrap();
+        assert_eq!(graph.stats.file_count, 1);
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    /// `module_path` of the entity named `name`.
+    fn module_of<'a>(graph: &'a ReferenceGraph, name: &str) -> &'a str {
+        graph
+            .entities
+            .iter()
+            .find(|e| e.name == name)
+            .map(|e| e.module_path.as_str())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_module_path_src_layout() {
+        let tmp = std::env::temp_dir().join("test_graph_module_path_src");
+        fs::remove_dir_all(&tmp).ok();
+        fs::create_dir_all(tmp.join("src/app/api")).ok();
+        fs::write(tmp.join("src/app/__init__.py"), "def setup():\n    pass\n").ok();
+        fs::write(tmp.join("src/app/api/__init__.py"), "").ok();
+        fs::write(
+            tmp.join("src/app/api/handlers.py"),
+            "class Handler:\n    def get(self):\n        pass\n",
+        )
+        .ok();
+
+        let mut host = ParserHost::new().unwrap();
+        let graph = build_reference_graph(&tmp, &mut host).unwrap();
+        assert_eq!(module_of(&graph, "get"), "app.api.handlers");
+        assert_eq!(module_of(&graph, "setup"), "app");
+        let get = graph.entities.iter().find(|e| e.name == "get").unwrap();
+        assert_eq!(get.fully_qualified_name(), "app.api.handlers.Handler.get");
+        // IDs stay path-based.
+        assert!(get
+            .symbol_id()
+            .ends_with("src/app/api/handlers.py::Handler.get"));
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_module_path_flat_layout() {
+        let tmp = std::env::temp_dir().join("test_graph_module_path_flat");
+        fs::remove_dir_all(&tmp).ok();
+        fs::create_dir_all(tmp.join("billing")).ok();
+        fs::create_dir_all(tmp.join("auth")).ok();
+        fs::write(tmp.join("billing/models.py"), "def save():\n    pass\n").ok();
+        fs::write(tmp.join("auth/models.py"), "def save():\n    pass\n").ok();
+        fs::write(tmp.join("manage.py"), "def main():\n    pass\n").ok();
+
+        let mut host = ParserHost::new().unwrap();
+        let graph = build_reference_graph(&tmp, &mut host).unwrap();
+        let mut saves: Vec<String> = graph
+            .entities
+            .iter()
+            .filter(|e| e.name == "save")
+            .map(Entity::fully_qualified_name)
+            .collect();
+        saves.sort();
+        assert_eq!(saves, ["auth.models.save", "billing.models.save"]);
+        assert_eq!(module_of(&graph, "main"), "manage");
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
     #[test]
     fn test_orphan_file_detected() {
         let tmp = std::env::temp_dir().join("test_graph_orphan");
@@ -788,6 +3960,70 @@ mod tests {
         fs::remove_dir_all(tmp).ok();
     }
 
+    #[test]
+    fn test_dynamic_attribute_names() {
+        let tmp = std::env::temp_dir().join("test_graph_dynamic_names");
+        fs::remove_dir_all(&tmp).ok();
+        fs::create_dir_all(tmp.join("plugins")).unwrap();
+
+        fs::write(
+            tmp.join("handlers.py"),
+            "def handle_created(e):\n    pass\n\ndef handle_deleted(e):\n    pass\n\n\
+             def compute_total(o):\n    pass\n\ndef handle(e):\n    pass\n\n\
+             def unrelated():\n    pass\n",
+        )
+        .unwrap();
+        fs::write(tmp.join("plugins").join("__init__.py"), "").unwrap();
+        fs::write(
+            tmp.join("plugins").join("extra.py"),
+            "def load():\n    pass\n",
+        )
+        .unwrap();
+        fs::write(
+            tmp.join("dispatch.py"),
+            "import importlib\nimport handlers\n\n\
+             def dispatch(event):\n    return getattr(handlers, f\"handle_{event}\")(event)\n\n\
+             def total(order):\n    return getattr(order, \"compute_total\")()\n\n\
+             def plugins():\n    return importlib.import_module(\"plugins.extra\")\n",
+        )
+        .unwrap();
+
+        let mut host = ParserHost::new().unwrap();
+        let graph = build_reference_graph(&tmp, &mut host).unwrap();
+        let site_of = |name: &str| {
+            let entry = graph
+                .registry
+                .entries
+                .iter()
+                .find(|e| e.name == name)
+                .unwrap();
+            graph.dynamic_refs.get(&entry.id).cloned()
+        };
+
+        let created = site_of("handle_created").expect("f-string prefix match");
+        assert!(created.file.ends_with("dispatch.py"), "{:?}", created);
+        assert_eq!(created.line, 5);
+        assert_eq!(created.call, "getattr(…, f\"handle_{event}\")");
+        assert!(site_of("handle_deleted").is_some());
+        let total = site_of("compute_total").unwrap();
+        assert_eq!(
+            (total.line, total.call.as_str()),
+            (8, "getattr(…, \"compute_total\")")
+        );
+        // The static prefix alone is not a match, nor is an unrelated name.
+        assert_eq!(site_of("handle"), None);
+        assert_eq!(site_of("unrelated"), None);
+        // `import_module("plugins.extra")` keeps the loaded module from being an orphan.
+        let orphans = graph.find_orphan_files_with::<&str>(&[], &[]);
+        assert!(
+            !orphans.iter().any(|p| p.ends_with("extra.py")),
+            "{:?}",
+            orphans
+        );
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
     #[test]
     fn test_referenced_file_not_orphan() {
         let tmp = std::env::temp_dir().join("test_graph_ref_orphan");
@@ -813,6 +4049,87 @@ mod tests {
         fs::remove_dir_all(tmp).ok();
     }
 
+    #[test]
+    fn test_reexported_module_not_orphan() {
+        let tmp = std::env::temp_dir().join("test_graph_reexport_orphan");
+        fs::remove_dir_all(&tmp).ok();
+        fs::create_dir_all(tmp.join("pkg")).ok();
+
+        // Only the package root imports `reports`; nothing calls it in-project.
+        fs::write(
+            tmp.join("pkg/__init__.py"),
+            "from .reports import generate_report\n",
+        )
+        .ok();
+        fs::write(
+            tmp.join("pkg/reports.py"),
+            "def generate_report():\n    pass\n",
+        )
+        .ok();
+        fs::write(tmp.join("pkg/unused.py"), "def stale():\n    pass\n").ok();
+
+        let mut host = ParserHost::new().unwrap();
+        let result = build_reference_graph(&tmp, &mut host).unwrap();
+        let orphans = result.find_orphan_files();
+
+        assert!(!orphans.iter().any(|p| p.ends_with("reports.py")));
+        assert!(orphans.iter().any(|p| p.ends_with("pkg/unused.py")));
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_self_contained_package_is_orphan() {
+        let tmp = std::env::temp_dir().join("test_graph_orphan_package");
+        fs::remove_dir_all(&tmp).ok();
+        fs::create_dir_all(tmp.join("legacy/billing_v1")).ok();
+        fs::create_dir_all(tmp.join("shop")).ok();
+
+        // billing_v1's two modules use each other; nothing outside imports them.
+        fs::write(tmp.join("legacy/__init__.py"), "").ok();
+        fs::write(tmp.join("legacy/billing_v1/__init__.py"), "").ok();
+        fs::write(
+            tmp.join("legacy/billing_v1/invoice.py"),
+            "from legacy.billing_v1.tax import rate\n\ndef total(x):\n    return x * rate()\n",
+        )
+        .ok();
+        fs::write(
+            tmp.join("legacy/billing_v1/tax.py"),
+            "from legacy.billing_v1 import invoice\n\ndef rate():\n    return 1.2\n\ndef check():\n    return invoice.total(1)\n",
+        )
+        .ok();
+        // shop is used by main.py, so neither it nor the project root qualifies.
+        fs::write(tmp.join("shop/__init__.py"), "").ok();
+        fs::write(tmp.join("shop/cart.py"), "def add():\n    pass\n").ok();
+        fs::write(tmp.join("main.py"), "from shop.cart import add\n\nadd()\n").ok();
+
+        let mut host = ParserHost::new().unwrap();
+        let graph = build_reference_graph(&tmp, &mut host).unwrap();
+
+        // Per file, each module is imported by its sibling.
+        let orphans = graph.find_orphan_files();
+        assert!(
+            !orphans.iter().any(|p| p.contains("/billing_v1/")),
+            "{:?}",
+            orphans
+        );
+        // Per package, the outermost self-contained subtree is reported once.
+        let packages = graph.find_orphan_packages();
+        assert_eq!(packages.len(), 1, "{:?}", packages);
+        assert!(packages[0].ends_with("/legacy"), "{:?}", packages);
+
+        // An import from outside the subtree keeps it alive.
+        fs::write(
+            tmp.join("main.py"),
+            "from shop.cart import add\nfrom legacy.billing_v1.invoice import total\n\nadd()\n",
+        )
+        .ok();
+        let graph = build_reference_graph(&tmp, &mut host).unwrap();
+        assert!(graph.find_orphan_packages().is_empty());
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
     #[test]
     fn test_init_py_never_orphan() {
         let tmp = std::env::temp_dir().join("test_graph_init_orphan");
@@ -853,4 +4170,235 @@ mod tests {
 
         fs::remove_dir_all(tmp).ok();
     }
+
+    #[test]
+    fn test_latin1_file_is_transcoded_and_linked() {
+        let tmp = std::env::temp_dir().join("test_graph_latin1");
+        fs::remove_dir_all(&tmp).ok();
+        fs::create_dir_all(&tmp).ok();
+        fs::write(
+            tmp.join("legacy.py"),
+            b"# -*- coding: latin-1 -*-\n# r\xe9sum\xe9\ndef normalise(s):\n    return s.replace('\xe9', 'e')\n\ndef unused():\n    pass\n",
+        )
+        .ok();
+        fs::write(
+            tmp.join("main.py"),
+            "from legacy import normalise\n\nnormalise('x')\n",
+        )
+        .ok();
+        fs::write(tmp.join("merged.py"), b"def merged():\n\0   pass\n").ok();
+
+        let mut host = ParserHost::new().unwrap();
+        let graph = build_reference_graph(&tmp, &mut host).unwrap();
+
+        assert!(is_referenced(&graph, "normalise"));
+        assert!(!is_referenced(&graph, "unused"));
+        // Offsets index the transcoded text, which the deleter edits too.
+        let unused = graph.entities.iter().find(|e| e.name == "unused").unwrap();
+        let text = common::source::read(&tmp.join("legacy.py")).unwrap();
+        assert!(text[unused.start_byte as usize..].starts_with(b"def unused"));
+
+        assert!(!graph.entities.iter().any(|e| e.name == "merged"));
+        assert_eq!(graph.stats.parse_errors, 1);
+        assert_eq!(
+            diagnostic_codes(&graph),
+            vec![
+                ("legacy.py", Severity::Info, codes::NON_UTF8_SOURCE),
+                ("merged.py", Severity::Warning, codes::NUL_BYTE),
+            ]
+        );
+        let mut diagnostics = graph.stats.diagnostics.iter();
+        let merged = diagnostics.find(|d| d.code == codes::NUL_BYTE).unwrap();
+        assert_eq!(
+            merged.message,
+            "not indexed: contains a NUL byte at offset 14; not a Python source file"
+        );
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    /// `(file name, severity, code)` of each diagnostic, sorted by file.
+    fn diagnostic_codes(graph: &ReferenceGraph) -> Vec<(&str, Severity, &'static str)> {
+        let mut codes: Vec<_> = graph
+            .stats
+            .diagnostics
+            .iter()
+            .map(|d| {
+                let file = d.file.as_deref().unwrap_or_default();
+                (file.rsplit('/').next().unwrap(), d.severity, d.code)
+            })
+            .collect();
+        codes.sort();
+        codes
+    }
+
+    #[test]
+    fn test_syntax_error_is_one_diagnostic() {
+        let tmp = std::env::temp_dir().join("test_graph_syntax_error");
+        fs::remove_dir_all(&tmp).ok();
+        fs::create_dir_all(&tmp).ok();
+        fs::write(tmp.join("broken.py"), "def ok():\n    pass\n\ndef foo(\n").ok();
+        fs::write(tmp.join("main.py"), "from broken import ok\n\nok()\n").ok();
+
+        let mut host = ParserHost::new().unwrap();
+        let graph = build_reference_graph(&tmp, &mut host).unwrap();
+
+        assert!(is_referenced(&graph, "ok"));
+        assert_eq!(
+            diagnostic_codes(&graph),
+            vec![("broken.py", Severity::Warning, codes::SYNTAX_ERROR)]
+        );
+        assert_eq!(
+            graph.stats.diagnostics.iter().next().unwrap().message,
+            "syntax error at line 4"
+        );
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_broken_notebooks_are_diagnosed() {
+        let tmp = std::env::temp_dir().join("test_graph_broken_notebooks");
+        fs::remove_dir_all(&tmp).ok();
+        fs::create_dir_all(&tmp).ok();
+        fs::write(tmp.join("report.py"), "def summarize():\n    pass\n").ok();
+        fs::write(tmp.join("truncated.ipynb"), "{\"cells\": [").ok();
+        fs::write(
+            tmp.join("ok.ipynb"),
+            r#"{"cells": [{"cell_type": "code", "source": ["import report\n"]}]}"#,
+        )
+        .ok();
+
+        let mut host = ParserHost::new().unwrap();
+        let graph = build_reference_graph(&tmp, &mut host).unwrap();
+
+        assert_eq!(graph.notebook_imports.len(), 1);
+        assert_eq!(
+            diagnostic_codes(&graph),
+            vec![("truncated.ipynb", Severity::Warning, codes::PARSE_FAILURE)]
+        );
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_unreadable_file_is_one_diagnostic() {
+        let tmp = std::env::temp_dir().join("test_graph_unreadable");
+        fs::remove_dir_all(&tmp).ok();
+        fs::create_dir_all(&tmp).ok();
+        fs::write(tmp.join("ok.py"), "def ok():\n    pass\n").ok();
+        let gone = tmp.join("gone.py");
+        fs::write(&gone, "def gone():\n    pass\n").ok();
+
+        // Removed after the walk found it, so neither pass can read it.
+        let mut host = ParserHost::new().unwrap();
+        let graph = build_reference_graph_observed(
+            &tmp,
+            &mut host,
+            &GraphOptions::default(),
+            None,
+            &mut |event| {
+                if matches!(event, PipelineEvent::StageStarted(Stage::Parse)) {
+                    fs::remove_file(&gone).unwrap();
+                }
+            },
+        )
+        .unwrap();
+
+        assert!(graph.entities.iter().any(|e| e.name == "ok"));
+        assert_eq!(
+            diagnostic_codes(&graph),
+            vec![("gone.py", Severity::Error, codes::UNREADABLE_FILE)]
+        );
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_file_hashes_are_of_the_parsed_bytes() {
+        let tmp = std::env::temp_dir().join("test_graph_file_hashes");
+        fs::remove_dir_all(&tmp).ok();
+        fs::create_dir_all(&tmp).ok();
+        let original = b"def stale():\n    pass\n";
+        let edited = tmp.join("edited.py");
+        fs::write(&edited, original).ok();
+
+        // Edited after Pass 1 read it: the hash is still of what was parsed.
+        let mut host = ParserHost::new().unwrap();
+        let graph = build_reference_graph_observed(
+            &tmp,
+            &mut host,
+            &GraphOptions::default(),
+            None,
+            &mut |event| {
+                if matches!(event, PipelineEvent::StageStarted(Stage::Link)) {
+                    fs::write(&edited, "def fresh():\n    pass\n").unwrap();
+                }
+            },
+        )
+        .unwrap();
+
+        let key = file_key(&edited);
+        assert_eq!(
+            graph.file_hashes.get(&key),
+            Some(blake3::hash(original).as_bytes())
+        );
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_cpp_call_edges() {
+        let tmp = std::env::temp_dir().join("test_graph_cpp_calls");
+        fs::remove_dir_all(&tmp).ok();
+        fs::create_dir_all(&tmp).ok();
+        fs::write(
+            tmp.join("shape.h"),
+            "class Shape {\npublic:\n    int area();\n    int scale(int k) { return k * area(); }\n};\nint helper(int x);\nint unused(int x);\n",
+        )
+        .ok();
+        fs::write(
+            tmp.join("shape.cpp"),
+            "#include \"shape.h\"\n\nint Shape::area() { return helper(2); }\nint helper(int x) { return x; }\nint unused(int x) { return x; }\n",
+        )
+        .ok();
+        fs::write(
+            tmp.join("main.cpp"),
+            "#include \"shape.h\"\n\nint main() {\n    Shape s;\n    return s.scale(3);\n}\n",
+        )
+        .ok();
+
+        let mut host = ParserHost::new().unwrap();
+        let graph = build_reference_graph(&tmp, &mut host).unwrap();
+        let incoming = |qname: &str| {
+            let id = graph
+                .registry
+                .entries
+                .iter()
+                .find(|e| e.qualified_name == qname)
+                .unwrap_or_else(|| panic!("{qname} not indexed"))
+                .id;
+            let node = graph
+                .graph
+                .node_indices()
+                .find(|&n| graph.graph[n] == id)
+                .unwrap();
+            graph
+                .graph
+                .edges_directed(node, Direction::Incoming)
+                .map(|e| {
+                    let source = graph.graph[e.source()];
+                    let entry = graph.registry.entries.iter().find(|r| r.id == source);
+                    entry.unwrap().qualified_name.clone()
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(incoming("Shape.scale"), vec!["main"]);
+        assert_eq!(incoming("Shape.area"), vec!["Shape.scale"]);
+        assert_eq!(incoming("helper"), vec!["Shape.area"]);
+        assert!(incoming("unused").is_empty());
+
+        fs::remove_dir_all(tmp).ok();
+    }
 }
@@ -0,0 +1,286 @@
+//! Django framework detection heuristic.
+//!
+//! Django wires most user code by convention rather than by call: views are
+//! referenced from `urls.py` route tables, signal handlers are connected by a
+//! decorator, admin classes are registered with the admin site, and `Meta`
+//! inner classes are read by the model/form metaclass.
+
+use crate::graph::walk_py_files;
+use crate::{Heuristic, Protection};
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::OnceLock;
+use tree_sitter::{Parser, Query, QueryCursor, StreamingIterator};
+
+/// Decorators that register the decorated entity with Django.
+const DJANGO_DECORATORS: &[&[u8]] = &[b"receiver(", b"admin.register("];
+
+/// Import markers that make `*Admin` classes admin registrations.
+const ADMIN_IMPORTS: &[&[u8]] = &[b"django.contrib.admin", b"from django.contrib import admin"];
+
+/// Route functions whose arguments name views in `urls.py`.
+const ROUTE_CALLS: &[&str] = &["path", "re_path", "url"];
+
+/// Keyword arguments in a route whose values name code: the view itself, and
+/// the class-based view attributes `as_view(...)` accepts.
+const ROUTE_KWARGS: &[&str] = &[
+    "view",
+    "model",
+    "form_class",
+    "serializer_class",
+    "pagination_class",
+    "filterset_class",
+    "permission_classes",
+    "authentication_classes",
+];
+
+/// Detects Django signal handlers, admin classes, and `Meta` inner classes.
+///
+/// # Detection Rules
+/// 1. **Signal / admin decorators**: `@receiver(...)` or `@admin.register(...)`.
+/// 2. **Admin classes**: class names ending in `Admin` in a file importing `django.contrib.admin`.
+/// 3. **`Meta` inner classes**: `class Meta:` nested inside another class.
+///
+/// Views referenced from `urls.py` are handled by [`collect_url_references`],
+/// which the pipeline runs once per project.
+pub struct DjangoHeuristic;
+
+impl Heuristic for DjangoHeuristic {
+    fn apply(
+        &self,
+        source: &[u8],
+        node: &tree_sitter::Node<'_>,
+        _file_path: &str,
+    ) -> Option<Protection> {
+        // The entity node may be the `decorated_definition` or the definition inside it.
+        let (decorated, definition) = if node.kind() == "decorated_definition" {
+            (Some(*node), node.child_by_field_name("definition")?)
+        } else {
+            let parent = node.parent().filter(|p| p.kind() == "decorated_definition");
+            (parent, *node)
+        };
+
+        if let Some(decorated) = decorated {
+            let mut cursor = decorated.walk();
+            for child in decorated.children(&mut cursor) {
+                if child.kind() == "decorator" {
+                    let region = &source[child.start_byte()..child.end_byte().min(source.len())];
+                    if DJANGO_DECORATORS.iter().any(|d| contains_bytes(region, d)) {
+                        return Some(Protection::DjangoFramework);
+                    }
+                }
+            }
+        }
+
+        if definition.kind() != "class_definition" {
+            return None;
+        }
+        let name = definition
+            .child_by_field_name("name")
+            .and_then(|n| n.utf8_text(source).ok())?;
+
+        if name.ends_with("Admin") && ADMIN_IMPORTS.iter().any(|m| contains_bytes(source, m)) {
+            return Some(Protection::DjangoFramework);
+        }
+
+        if name == "Meta" {
+            let mut current = definition.parent();
+            while let Some(n) = current {
+                if n.kind() == "class_definition" {
+                    return Some(Protection::DjangoFramework);
+                }
+                current = n.parent();
+            }
+        }
+
+        None
+    }
+}
+
+static URL_QUERY: OnceLock<Query> = OnceLock::new();
+
+/// Collects names referenced from route calls in every `urls.py` under `root`.
+///
+/// For `path("x/", views.index)` and `path("y/", views.ItemView.as_view())` this
+/// yields `index`, `ItemView` (and the other identifiers in the positional
+/// arguments); dotted-path strings like `"app.views.index"` contribute their
+/// final segment. Of the keyword arguments only [`ROUTE_KWARGS`] are read, so
+/// `name="index"` or `template_name=...` reference nothing.
+pub fn collect_url_references(root: &Path) -> HashSet<String> {
+    let mut names = HashSet::new();
+    let Ok(files) = walk_py_files(root) else {
+        return names;
+    };
+
+    let mut parser = Parser::new();
+    if parser
+        .set_language(&tree_sitter_python::LANGUAGE.into())
+        .is_err()
+    {
+        return names;
+    }
+    let query = URL_QUERY.get_or_init(|| {
+        Query::new(
+            &tree_sitter_python::LANGUAGE.into(),
+            r#"
+            (call
+              function: (identifier) @route
+              arguments: (argument_list) @args)
+            "#,
+        )
+        .expect("Invalid urls.py query")
+    });
+
+    for path in files {
+        if path.file_name().and_then(|n| n.to_str()) != Some("urls.py") {
+            continue;
+        }
+        let Ok(source) = common::source::read(&path) else {
+            continue;
+        };
+        let Some(tree) = parser.parse(&source, None) else {
+            continue;
+        };
+
+        let mut cursor = QueryCursor::new();
+        let mut matches = cursor.matches(query, tree.root_node(), &source[..]);
+        while let Some(m) = matches.next() {
+            let (route, args) = (m.captures[0].node, m.captures[1].node);
+            if !route
+                .utf8_text(&source)
+                .is_ok_and(|r| ROUTE_CALLS.contains(&r))
+            {
+                continue;
+            }
+            collect_names(args, &source, &mut names);
+        }
+    }
+
+    names
+}
+
+/// Adds every identifier and dotted-string tail under `node` to `names`,
+/// skipping keyword arguments other than [`ROUTE_KWARGS`].
+fn collect_names(node: tree_sitter::Node<'_>, source: &[u8], names: &mut HashSet<String>) {
+    match node.kind() {
+        "keyword_argument" => {
+            let known = node
+                .child_by_field_name("name")
+                .and_then(|n| n.utf8_text(source).ok())
+                .is_some_and(|name| ROUTE_KWARGS.contains(&name));
+            if let Some(value) = node.child_by_field_name("value").filter(|_| known) {
+                collect_names(value, source, names);
+            }
+        }
+        "identifier" => {
+            if let Ok(text) = node.utf8_text(source) {
+                names.insert(text.to_string());
+            }
+        }
+        "string_content" => {
+            if let Ok(text) = node.utf8_text(source) {
+                if let Some((_, tail)) = text.rsplit_once('.') {
+                    if !tail.is_empty()
+                        && tail.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_')
+                    {
+                        names.insert(tail.to_string());
+                    }
+                }
+            }
+        }
+        _ => {
+            let mut cursor = node.walk();
+            for child in node.children(&mut cursor) {
+                collect_names(child, source, names);
+            }
+        }
+    }
+}
+
+/// Searches for a byte sequence within another byte slice.
+fn contains_bytes(haystack: &[u8], needle: &[u8]) -> bool {
+    needle.len() <= haystack.len() && haystack.windows(needle.len()).any(|w| w == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::ParserHost;
+    use std::fs;
+
+    fn protections(source: &str, file: &str) -> Vec<(String, Option<Protection>)> {
+        let mut host = ParserHost::new().unwrap();
+        host.register_heuristic(Box::new(DjangoHeuristic));
+        host.dissect_bytes(source.as_bytes(), file)
+            .unwrap()
+            .into_iter()
+            .map(|e| (e.qualified_name, e.protected_by))
+            .collect()
+    }
+
+    #[test]
+    fn test_receiver_admin_and_meta() {
+        let found = protections(
+            "from django.contrib import admin\n\
+             from django.dispatch import receiver\n\
+             \n\
+             @receiver(post_save)\n\
+             def on_save(sender, **kwargs):\n    pass\n\
+             \n\
+             class BookAdmin(admin.ModelAdmin):\n    pass\n\
+             \n\
+             class Book(Model):\n    class Meta:\n        ordering = ['id']\n\
+             \n\
+             def plain():\n    pass\n",
+            "shop/models.py",
+        );
+        let get = |q: &str| found.iter().find(|(n, _)| n == q).and_then(|(_, p)| *p);
+        assert_eq!(get("on_save"), Some(Protection::DjangoFramework));
+        assert_eq!(get("BookAdmin"), Some(Protection::DjangoFramework));
+        assert_eq!(get("Book.Meta"), Some(Protection::DjangoFramework));
+        assert_eq!(get("plain"), None);
+        assert_eq!(get("Book"), None);
+    }
+
+    #[test]
+    fn test_admin_suffix_requires_admin_import() {
+        let found = protections("class ReportAdmin:\n    pass\n", "reports.py");
+        assert_eq!(found[0].1, None);
+    }
+
+    #[test]
+    fn test_collect_url_references() {
+        let tmp = std::env::temp_dir().join("test_django_urls");
+        fs::create_dir_all(tmp.join("shop")).ok();
+        fs::write(
+            tmp.join("shop/urls.py"),
+            "from django.urls import path\nfrom . import views\n\nurlpatterns = [\n    path('', views.index),\n    path('b/', views.BookView.as_view(form_class=BookForm, template_name=tpl), name='book'),\n    path('c/', view=views.catalog, kwargs={'page': first_page}),\n    url(r'^old/$', 'shop.views.legacy'),\n]\n",
+        )
+        .ok();
+        fs::write(
+            tmp.join("shop/views.py"),
+            "def other(): path('x', not_a_url)\n",
+        )
+        .ok();
+
+        let names = collect_url_references(&tmp);
+        assert!(names.contains("index"));
+        assert!(names.contains("BookView"));
+        assert!(names.contains("legacy"));
+        assert!(names.contains("BookForm"));
+        assert!(names.contains("catalog"));
+        assert!(!names.contains("not_a_url"));
+        for kwarg in [
+            "name",
+            "template_name",
+            "tpl",
+            "view",
+            "kwargs",
+            "first_page",
+        ] {
+            assert!(!names.contains(kwarg), "{}", kwarg);
+        }
+
+        fs::remove_dir_all(tmp).ok();
+    }
+}
@@ -3,6 +3,7 @@
 //! This module defines the `Heuristic` trait and provides implementations
 //! for detecting protected entities based on various patterns and conventions.
 
+pub mod django;
 pub mod pytest;
 
 use crate::Protection;
@@ -53,4 +54,9 @@ pub trait Heuristic {
         node: &tree_sitter::Node<'_>,
         file_path: &str,
     ) -> Option<Protection>;
+
+    /// Stable identifier used to fingerprint the heuristic set (see [`crate::cache`]).
+    fn name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
 }
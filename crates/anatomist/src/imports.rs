@@ -15,8 +15,15 @@ pub struct ImportInfo {
     pub raw_path: String,
     /// Imported names (e.g., `["bar"]` from `"from foo import bar"`). Empty for bare imports.
     pub names: Vec<String>,
+    /// Local binding introduced by `as` (`"z"` in `from x import y as z`, `"fb"` in
+    /// `import foo.bar as fb`). An aliased import always carries at most one name.
+    pub alias: Option<String>,
+    /// `true` for `from module import *`.
+    pub wildcard: bool,
     /// Line number (1-indexed).
     pub line: u32,
+    /// Inside an `if TYPE_CHECKING:` block: seen by type checkers, never run.
+    pub type_checking: bool,
 }
 
 /// A local `#include` directive extracted from C++ source.
@@ -28,6 +35,27 @@ pub struct CppInclude {
     pub line: u32,
 }
 
+/// An ES module import, re-export, or `require()` extracted from JS/TS source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EsImport {
+    /// The module specifier as written (e.g., `"./utils"`).
+    pub specifier: String,
+    /// `(imported, local)` pairs: `("a", "b")` for `import { a as b }`,
+    /// `("default", "x")` for `import x from`. For re-exports, `local` is the
+    /// name this module exports.
+    pub names: Vec<(String, String)>,
+    /// Every export of the module is reachable: `import * as ns`, `require()`,
+    /// `export * from`.
+    pub namespace: bool,
+    /// `export ... from` rather than a local binding.
+    pub reexport: bool,
+    /// Line number (1-indexed).
+    pub line: u32,
+}
+
+/// JS/TS file extensions, in the order extensionless specifiers try them.
+pub const SCRIPT_EXTENSIONS: &[&str] = &["ts", "tsx", "js", "jsx"];
+
 static IMPORT_QUERY: OnceLock<Query> = OnceLock::new();
 
 /// Extracts import statements from Python source code.
@@ -49,6 +77,11 @@ pub fn extract_imports(source: &[u8], root: Node) -> Result<Vec<ImportInfo>, Ana
             (import_statement
               name: (dotted_name) @import_module)
 
+            (import_statement
+              name: (aliased_import
+                name: (dotted_name) @import_module
+                alias: (identifier) @alias))
+
             (import_from_statement
               module_name: (dotted_name) @from_module
               name: (dotted_name) @from_name)
@@ -57,6 +90,18 @@ pub fn extract_imports(source: &[u8], root: Node) -> Result<Vec<ImportInfo>, Ana
               module_name: (relative_import) @from_relative
               name: (dotted_name) @from_name_rel)
 
+            (import_from_statement
+              module_name: (dotted_name) @from_module
+              name: (aliased_import
+                name: (dotted_name) @from_name
+                alias: (identifier) @alias))
+
+            (import_from_statement
+              module_name: (relative_import) @from_relative
+              name: (aliased_import
+                name: (dotted_name) @from_name_rel
+                alias: (identifier) @alias))
+
             (import_from_statement
               module_name: (dotted_name) @from_module_star
               (wildcard_import))
@@ -77,7 +122,13 @@ pub fn extract_imports(source: &[u8], root: Node) -> Result<Vec<ImportInfo>, Ana
     while let Some(m) = matches.next() {
         let mut raw_path = String::new();
         let mut names = Vec::new();
+        let mut alias = None;
+        let mut wildcard = false;
         let mut line = 0;
+        let type_checking = m
+            .captures
+            .first()
+            .is_some_and(|c| in_type_checking_block(c.node, source));
 
         for capture in m.captures {
             let node = capture.node;
@@ -89,17 +140,21 @@ pub fn extract_imports(source: &[u8], root: Node) -> Result<Vec<ImportInfo>, Ana
                     raw_path = text.to_string();
                     line = node.start_position().row as u32 + 1;
                 }
-                "from_module" | "from_module_star" => {
+                "from_module" | "from_relative" => {
                     raw_path = text.to_string();
                     line = node.start_position().row as u32 + 1;
                 }
-                "from_relative" | "from_relative_star" => {
+                "from_module_star" | "from_relative_star" => {
                     raw_path = text.to_string();
+                    wildcard = true;
                     line = node.start_position().row as u32 + 1;
                 }
                 "from_name" | "from_name_rel" => {
                     names.push(text.to_string());
                 }
+                "alias" => {
+                    alias = Some(text.to_string());
+                }
                 _ => {}
             }
         }
@@ -108,7 +163,10 @@ pub fn extract_imports(source: &[u8], root: Node) -> Result<Vec<ImportInfo>, Ana
             imports.push(ImportInfo {
                 raw_path,
                 names,
+                alias,
+                wildcard,
                 line,
+                type_checking,
             });
         }
     }
@@ -118,9 +176,7 @@ pub fn extract_imports(source: &[u8], root: Node) -> Result<Vec<ImportInfo>, Ana
         let mut cursor_walk = root.walk();
         for child in root.children(&mut cursor_walk) {
             if child.kind() == "import_statement" || child.kind() == "import_from_statement" {
-                if let Some(info) = extract_import_manual(source, child) {
-                    imports.push(info);
-                }
+                imports.extend(extract_import_manual(source, child));
             }
         }
     }
@@ -129,72 +185,141 @@ pub fn extract_imports(source: &[u8], root: Node) -> Result<Vec<ImportInfo>, Ana
 }
 
 /// Manual fallback for import extraction when query doesn't match.
-fn extract_import_manual(source: &[u8], node: Node) -> Option<ImportInfo> {
+///
+/// Aliased names are split into their own [`ImportInfo`] so each carries one alias.
+fn extract_import_manual(source: &[u8], node: Node) -> Vec<ImportInfo> {
     let kind = node.kind();
     let line = node.start_position().row as u32 + 1;
+    let type_checking = in_type_checking_block(node, source);
+    let text = |n: Node| n.utf8_text(source).ok().map(str::to_string);
+    // (name, alias) for an `aliased_import` child.
+    let aliased = |n: Node| -> Option<(String, String)> {
+        Some((
+            text(n.child_by_field_name("name")?)?,
+            text(n.child_by_field_name("alias")?)?,
+        ))
+    };
+
+    let mut imports = Vec::new();
+    let mut cursor = node.walk();
 
     if kind == "import_statement" {
-        // Extract dotted name from children
-        let mut cursor = node.walk();
         for child in node.children(&mut cursor) {
-            if child.kind() == "dotted_name" {
-                let text = child.utf8_text(source).ok()?;
-                return Some(ImportInfo {
-                    raw_path: text.to_string(),
-                    names: vec![],
-                    line,
-                });
-            }
+            let (raw_path, alias) = match child.kind() {
+                "dotted_name" => match text(child) {
+                    Some(t) => (t, None),
+                    None => continue,
+                },
+                "aliased_import" => match aliased(child) {
+                    Some((name, alias)) => (name, Some(alias)),
+                    None => continue,
+                },
+                _ => continue,
+            };
+            imports.push(ImportInfo {
+                raw_path,
+                names: vec![],
+                alias,
+                wildcard: false,
+                line,
+                type_checking,
+            });
+            break;
         }
     } else if kind == "import_from_statement" {
         let mut raw_path = String::new();
         let mut names = Vec::new();
+        let mut aliased_names = Vec::new();
+        let mut wildcard = false;
 
-        let mut cursor = node.walk();
         for child in node.children(&mut cursor) {
             match child.kind() {
                 "dotted_name" | "relative_import" => {
+                    let Some(t) = text(child) else {
+                        return Vec::new();
+                    };
                     if raw_path.is_empty() {
-                        raw_path = child.utf8_text(source).ok()?.to_string();
+                        raw_path = t;
                     } else {
-                        names.push(child.utf8_text(source).ok()?.to_string());
+                        names.push(t);
                     }
                 }
+                "aliased_import" => aliased_names.extend(aliased(child)),
+                "wildcard_import" => wildcard = true,
                 _ => {}
             }
         }
 
         if !raw_path.is_empty() {
-            return Some(ImportInfo {
-                raw_path,
-                names,
-                line,
-            });
+            if !names.is_empty() || aliased_names.is_empty() {
+                imports.push(ImportInfo {
+                    raw_path: raw_path.clone(),
+                    names,
+                    alias: None,
+                    wildcard,
+                    line,
+                    type_checking,
+                });
+            }
+            for (name, alias) in aliased_names {
+                imports.push(ImportInfo {
+                    raw_path: raw_path.clone(),
+                    names: vec![name],
+                    alias: Some(alias),
+                    wildcard: false,
+                    line,
+                    type_checking,
+                });
+            }
         }
     }
 
-    None
+    imports
+}
+
+/// Whether `node` is in the body of an `if TYPE_CHECKING:` (or
+/// `if typing.TYPE_CHECKING:`) statement, not its `else` branch.
+pub(crate) fn in_type_checking_block(node: Node, source: &[u8]) -> bool {
+    let mut child = node;
+    while let Some(parent) = child.parent() {
+        if parent.kind() == "if_statement"
+            && parent.child_by_field_name("consequence") == Some(child)
+        {
+            let condition = parent
+                .child_by_field_name("condition")
+                .and_then(|c| c.utf8_text(source).ok())
+                .unwrap_or_default();
+            if condition == "TYPE_CHECKING" || condition.ends_with(".TYPE_CHECKING") {
+                return true;
+            }
+        }
+        child = parent;
+    }
+    false
 }
 
 /// Resolves a Python import path to an absolute file path.
 ///
+/// Relative imports resolve against `source_file`'s package; absolute imports
+/// try each of `source_roots` in order (see [`source_roots`]).
+///
 /// # Examples
 /// ```ignore
 /// let source_file = Path::new("/project/src/api/handlers.py");
-/// let project_root = Path::new("/project");
+/// let roots = [PathBuf::from("/project/src"), PathBuf::from("/project")];
 ///
 /// // Relative import: from ..utils import foo
-/// let result = resolve_import(source_file, "..utils", project_root);
+/// let result = resolve_import(source_file, "..utils", &roots);
 /// // Returns Some("/project/src/utils.py") or Some("/project/src/utils/__init__.py")
 ///
 /// // Absolute import: from mypackage.core import bar
-/// let result = resolve_import(source_file, "mypackage.core", project_root);
-/// // Returns Some("/project/mypackage/core.py") or Some("/project/mypackage/core/__init__.py")
+/// let result = resolve_import(source_file, "mypackage.core", &roots);
+/// // Returns Some("/project/src/mypackage/core.py") or Some("/project/mypackage/core.py")
 /// ```
 pub fn resolve_import(
     source_file: &Path,
     import_path: &str,
-    project_root: &Path,
+    source_roots: &[PathBuf],
 ) -> Option<PathBuf> {
     // Count leading dots for relative imports
     let dot_count = import_path.chars().take_while(|&c| c == '.').count();
@@ -213,9 +338,137 @@ pub fn resolve_import(
         };
         resolve_module_path(base, dotted)
     } else {
-        // Absolute import from project root
-        resolve_module_path(project_root, import_path)
+        // Absolute import: first source root that has the module wins.
+        source_roots
+            .iter()
+            .find_map(|root| resolve_module_path(root, import_path))
+    }
+}
+
+/// Directories that absolute imports are resolved against, in search order.
+///
+/// `configured` (`source_roots` in `.janitor.toml`, relative to `project_root`)
+/// replaces detection. Otherwise the roots are the `pyproject.toml` hints (see
+/// [`pyproject_source_roots`]), then `src/` when it holds Python packages. The
+/// project root itself is always searched last. Missing directories are skipped.
+pub fn source_roots(project_root: &Path, configured: Option<&[String]>) -> Vec<PathBuf> {
+    let relative: Vec<String> = match configured {
+        Some(roots) => roots.to_vec(),
+        None => {
+            let mut roots = std::fs::read_to_string(project_root.join("pyproject.toml"))
+                .map(|text| pyproject_source_roots(&text))
+                .unwrap_or_default();
+            if holds_python_packages(&project_root.join("src")) {
+                roots.push("src".to_string());
+            }
+            roots
+        }
+    };
+
+    let mut roots: Vec<PathBuf> = Vec::new();
+    let candidates = relative
+        .iter()
+        .map(|r| project_root.join(r))
+        .chain(std::iter::once(project_root.to_path_buf()));
+    for candidate in candidates {
+        if let Ok(dir) = dunce::canonicalize(candidate) {
+            if dir.is_dir() && !roots.contains(&dir) {
+                roots.push(dir);
+            }
+        }
+    }
+    roots
+}
+
+/// Dotted module path of `file` (`src/app/api/handlers.py` → `app.api.handlers`)
+/// relative to the first of `source_roots` (see [`source_roots`]) that contains
+/// it, so it matches what an absolute import of the file would say. A package's
+/// `__init__.py` is the package itself. `None` when no root contains `file`.
+pub fn module_path(file: &Path, source_roots: &[PathBuf]) -> Option<String> {
+    let relative = source_roots
+        .iter()
+        .find_map(|root| file.strip_prefix(root).ok())?;
+    let mut parts: Vec<String> = relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().into_owned())
+        .collect();
+    let last = parts.pop()?;
+    let stem = last
+        .rsplit_once('.')
+        .map_or(last.as_str(), |(stem, _)| stem);
+    if stem != "__init__" {
+        parts.push(stem.to_string());
     }
+    Some(parts.join("."))
+}
+
+/// `true` when `dir` has a subdirectory containing `.py` files (a regular or
+/// PEP 420 namespace package).
+fn holds_python_packages(dir: &Path) -> bool {
+    let has_py = |d: &Path| {
+        std::fs::read_dir(d).is_ok_and(|entries| {
+            entries
+                .flatten()
+                .any(|e| e.path().extension().is_some_and(|ext| ext == "py"))
+        })
+    };
+    std::fs::read_dir(dir).is_ok_and(|entries| {
+        entries
+            .flatten()
+            .any(|e| e.path().is_dir() && has_py(&e.path()))
+    })
+}
+
+/// Source roots named in `pyproject.toml` text, relative to the project root.
+///
+/// Recognises setuptools `[tool.setuptools.packages.find] where = ["src"]` and
+/// `package-dir = {"" = "src"}`, Poetry `packages = [{ include = "pkg", from = "src" }]`,
+/// and Hatch `[tool.hatch.build.targets.wheel] packages = ["src/pkg"]`.
+/// Text that is not valid TOML names no roots.
+pub fn pyproject_source_roots(text: &str) -> Vec<String> {
+    let Ok(doc) = text.parse::<toml::Table>() else {
+        return Vec::new();
+    };
+    let get = |path: &[&str]| {
+        path[1..]
+            .iter()
+            .try_fold(doc.get(path[0])?, |v, key| v.get(*key))
+    };
+    let strings = |v: Option<&toml::Value>| -> Vec<String> {
+        v.and_then(toml::Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|item| item.as_str().map(str::to_string))
+            .collect()
+    };
+
+    let mut roots = strings(get(&["tool", "setuptools", "packages", "find", "where"]));
+    roots.extend(
+        get(&["tool", "setuptools", "package-dir", ""])
+            .and_then(toml::Value::as_str)
+            .map(str::to_string),
+    );
+    roots.extend(
+        get(&["tool", "poetry", "packages"])
+            .and_then(toml::Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|package| package.get("from")?.as_str().map(str::to_string)),
+    );
+    roots.extend(
+        strings(get(&[
+            "tool", "hatch", "build", "targets", "wheel", "packages",
+        ]))
+        .iter()
+        .filter_map(|p| {
+            Path::new(p)
+                .parent()
+                .map(|d| d.to_string_lossy().into_owned())
+        }),
+    );
+    roots.retain(|r| !r.is_empty() && r != ".");
+    roots.dedup();
+    roots
 }
 
 /// Resolves a dotted module path to a file path.
@@ -251,6 +504,176 @@ fn resolve_module_path(base: &Path, dotted: &str) -> Option<PathBuf> {
     None
 }
 
+/// Extracts ES `import` statements, `export ... from` re-exports, and
+/// `require("...")` calls from a parsed JS/TS source tree.
+///
+/// `require()` binds the whole module (like `import * as`), whatever the
+/// pattern it is destructured into.
+pub fn extract_es_imports(source: &[u8], root: Node) -> Vec<EsImport> {
+    let text = |n: Node| n.utf8_text(source).unwrap_or("").to_string();
+    // Contents of a string literal node, without its quotes.
+    let string_value = |n: Node| -> Option<String> {
+        let raw = n.utf8_text(source).ok()?;
+        Some(raw.get(1..raw.len().checked_sub(1)?)?.to_string())
+    };
+
+    let mut imports = Vec::new();
+    let mut stack = vec![root];
+    while let Some(node) = stack.pop() {
+        let line = node.start_position().row as u32 + 1;
+        match node.kind() {
+            "import_statement" | "export_statement" => {
+                let reexport = node.kind() == "export_statement";
+                let Some(specifier) = node.child_by_field_name("source").and_then(string_value)
+                else {
+                    // `export function f() {}` and friends may nest requires.
+                    if reexport {
+                        stack.extend(node.named_children(&mut node.walk()));
+                    }
+                    continue;
+                };
+                let mut import = EsImport {
+                    specifier,
+                    names: Vec::new(),
+                    namespace: false,
+                    reexport,
+                    line,
+                };
+                let mut cursor = node.walk();
+                for child in node.children(&mut cursor) {
+                    match child.kind() {
+                        "*" | "namespace_export" => import.namespace = true,
+                        "import_clause" => {
+                            for part in child.named_children(&mut child.walk()) {
+                                match part.kind() {
+                                    "identifier" => {
+                                        import.names.push(("default".to_string(), text(part)))
+                                    }
+                                    "namespace_import" => import.namespace = true,
+                                    "named_imports" => {
+                                        import.names.extend(specifiers(part, &text));
+                                    }
+                                    _ => {}
+                                }
+                            }
+                        }
+                        "export_clause" => import.names.extend(specifiers(child, &text)),
+                        _ => {}
+                    }
+                }
+                // A bare `import "./polyfill"` runs the module for its side effects.
+                if import.names.is_empty() && !reexport {
+                    import.namespace = true;
+                }
+                imports.push(import);
+            }
+            "call_expression" => {
+                let is_require = node
+                    .child_by_field_name("function")
+                    .is_some_and(|f| f.kind() == "identifier" && text(f) == "require");
+                let argument = node
+                    .child_by_field_name("arguments")
+                    .and_then(|a| a.named_child(0))
+                    .filter(|a| a.kind() == "string");
+                if let (true, Some(argument)) = (is_require, argument) {
+                    if let Some(specifier) = string_value(argument) {
+                        imports.push(EsImport {
+                            specifier,
+                            names: Vec::new(),
+                            namespace: true,
+                            reexport: false,
+                            line,
+                        });
+                    }
+                }
+                stack.extend(node.named_children(&mut node.walk()));
+            }
+            _ => stack.extend(node.named_children(&mut node.walk())),
+        }
+    }
+
+    imports.sort_by_key(|i| i.line);
+    imports
+}
+
+/// `(name, alias-or-name)` for each `import_specifier` / `export_specifier` under `list`.
+fn specifiers(list: Node, text: &dyn Fn(Node) -> String) -> Vec<(String, String)> {
+    list.named_children(&mut list.walk())
+        .filter(|s| matches!(s.kind(), "import_specifier" | "export_specifier"))
+        .filter_map(|s| {
+            let name = text(s.child_by_field_name("name")?);
+            let local = s
+                .child_by_field_name("alias")
+                .map(text)
+                .unwrap_or_else(|| name.clone());
+            Some((name, local))
+        })
+        .collect()
+}
+
+/// Name of the declaration or identifier exported by `export default`, if any.
+pub fn es_default_export(source: &[u8], root: Node) -> Option<String> {
+    let mut cursor = root.walk();
+    let export = root.children(&mut cursor).find(|n| {
+        n.kind() == "export_statement" && n.children(&mut n.walk()).any(|c| c.kind() == "default")
+    })?;
+    let named = match export.child_by_field_name("declaration") {
+        Some(declaration) => declaration.child_by_field_name("name")?,
+        None => export
+            .child_by_field_name("value")
+            .filter(|v| v.kind() == "identifier")?,
+    };
+    named.utf8_text(source).ok().map(str::to_string)
+}
+
+/// `true` when `path` ends in one of [`SCRIPT_EXTENSIONS`].
+pub(crate) fn is_script_path(path: &str) -> bool {
+    path.rsplit_once('.')
+        .is_some_and(|(_, ext)| SCRIPT_EXTENSIONS.contains(&ext))
+}
+
+/// Resolves a relative ES module specifier to a project file.
+///
+/// Bare specifiers (`"react"`) name packages and resolve to `None`. Extensionless
+/// specifiers try each of [`SCRIPT_EXTENSIONS`], then `index.*` inside a directory;
+/// a `.js` specifier also matches the `.ts` source it is compiled from.
+pub fn resolve_es_import(source_file: &Path, specifier: &str) -> Option<PathBuf> {
+    if !specifier.starts_with("./") && !specifier.starts_with("../") {
+        return None;
+    }
+    let base = source_file.parent()?.join(specifier);
+    let is_script = |p: &Path| {
+        p.is_file()
+            && p.extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| SCRIPT_EXTENSIONS.contains(&e))
+    };
+
+    let stem = match specifier.strip_suffix(".js") {
+        Some(_) => base.with_extension(""),
+        None => base.clone(),
+    };
+    let with_extension = |p: &Path, ext: &str| {
+        let mut name = p.as_os_str().to_os_string();
+        name.push(".");
+        name.push(ext);
+        PathBuf::from(name)
+    };
+    std::iter::once(base.clone())
+        .chain(
+            SCRIPT_EXTENSIONS
+                .iter()
+                .map(|ext| with_extension(&stem, ext)),
+        )
+        .chain(
+            SCRIPT_EXTENSIONS
+                .iter()
+                .map(|ext| base.join(format!("index.{}", ext))),
+        )
+        .find(|p| is_script(p))
+        .and_then(|p| dunce::canonicalize(p).ok())
+}
+
 /// Extracts local `#include "..."` directives from C++ source bytes.
 ///
 /// Only captures double-quoted (local) includes. Angle-bracket system includes
@@ -371,6 +794,64 @@ mod tests {
         assert!(!imports.is_empty());
     }
 
+    #[test]
+    fn test_aliased_module_import() {
+        let imports = parse_imports("import foo.bar as fb");
+        assert_eq!(imports.len(), 1);
+        assert_eq!(imports[0].raw_path, "foo.bar");
+        assert!(imports[0].names.is_empty());
+        assert_eq!(imports[0].alias.as_deref(), Some("fb"));
+    }
+
+    #[test]
+    fn test_aliased_from_import() {
+        let imports = parse_imports("from utils import helper as h, other");
+        assert_eq!(imports.len(), 2);
+        let aliased = imports.iter().find(|i| i.alias.is_some()).unwrap();
+        assert_eq!(aliased.names, vec!["helper"]);
+        assert_eq!(aliased.alias.as_deref(), Some("h"));
+        let plain = imports.iter().find(|i| i.alias.is_none()).unwrap();
+        assert_eq!(plain.names, vec!["other"]);
+    }
+
+    #[test]
+    fn test_aliased_relative_import() {
+        let imports = parse_imports("from .utils import helper as h");
+        assert_eq!(imports.len(), 1);
+        assert_eq!(imports[0].raw_path, ".utils");
+        assert_eq!(imports[0].alias.as_deref(), Some("h"));
+    }
+
+    #[test]
+    fn test_wildcard_import() {
+        let imports = parse_imports("from constants import *\nfrom .utils import *\nimport os");
+        assert_eq!(imports.len(), 3);
+        assert!(imports[0].wildcard && imports[0].names.is_empty());
+        assert_eq!(imports[1].raw_path, ".utils");
+        assert!(imports[1].wildcard);
+        assert!(!imports[2].wildcard);
+    }
+
+    #[test]
+    fn test_type_checking_imports_are_marked() {
+        let imports = parse_imports(
+            "import typing\nif typing.TYPE_CHECKING:\n    from app.models import Order\nelse:\n    from app.stubs import Order\nif TYPE_CHECKING:\n    import app.config as cfg\n",
+        );
+        let marked: Vec<(&str, bool)> = imports
+            .iter()
+            .map(|i| (i.raw_path.as_str(), i.type_checking))
+            .collect();
+        assert_eq!(
+            marked,
+            vec![
+                ("typing", false),
+                ("app.models", true),
+                ("app.stubs", false),
+                ("app.config", true),
+            ]
+        );
+    }
+
     #[test]
     fn test_resolve_absolute() {
         let tmp = std::env::temp_dir().join("test_resolve_abs");
@@ -379,7 +860,7 @@ mod tests {
         fs::write(&module_py, "").ok();
 
         let source = tmp.join("main.py");
-        let result = resolve_import(&source, "mymod", &tmp);
+        let result = resolve_import(&source, "mymod", std::slice::from_ref(&tmp));
         assert!(result.is_some());
         assert!(result.unwrap().ends_with("mymod.py"));
 
@@ -394,7 +875,7 @@ mod tests {
         fs::write(&init_py, "").ok();
 
         let source = tmp.join("main.py");
-        let result = resolve_import(&source, "pkg", &tmp);
+        let result = resolve_import(&source, "pkg", std::slice::from_ref(&tmp));
         assert!(result.is_some());
         assert!(result.unwrap().ends_with("__init__.py"));
 
@@ -409,7 +890,7 @@ mod tests {
         fs::write(&utils_py, "").ok();
 
         let source = tmp.join("src/main.py");
-        let result = resolve_import(&source, ".utils", &tmp);
+        let result = resolve_import(&source, ".utils", std::slice::from_ref(&tmp));
         assert!(result.is_some());
         assert!(result.unwrap().ends_with("utils.py"));
 
@@ -424,7 +905,7 @@ mod tests {
         fs::write(&core_py, "").ok();
 
         let source = tmp.join("src/api/handlers.py");
-        let result = resolve_import(&source, "..core", &tmp);
+        let result = resolve_import(&source, "..core", std::slice::from_ref(&tmp));
         assert!(result.is_some());
         assert!(result.unwrap().ends_with("core.py"));
 
@@ -454,8 +935,138 @@ mod tests {
         let tmp = std::env::temp_dir().join("test_resolve_none");
         fs::create_dir_all(&tmp).ok();
         let source = tmp.join("main.py");
-        let result = resolve_import(&source, "nonexistent", &tmp);
+        let result = resolve_import(&source, "nonexistent", std::slice::from_ref(&tmp));
         assert!(result.is_none());
         fs::remove_dir_all(tmp).ok();
     }
+
+    #[test]
+    fn test_pyproject_source_roots() {
+        let text = r#"
+[project]
+name = "demo"
+
+[tool.setuptools.packages.find]
+where = ["src"]
+
+[tool.poetry]
+packages = [
+    { include = "app", from = "lib" },
+]
+
+[tool.hatch.build.targets.wheel]
+packages = ["python/pkg"]
+"#;
+        assert_eq!(pyproject_source_roots(text), vec!["src", "lib", "python"]);
+        assert_eq!(
+            pyproject_source_roots("[tool.setuptools]\npackage-dir = {\"\" = \"code\"}\n"),
+            vec!["code"]
+        );
+        assert_eq!(
+            pyproject_source_roots("[tool.setuptools.package-dir]\n\"\" = 'code' # flat\n"),
+            vec!["code"]
+        );
+        assert!(pyproject_source_roots("[tool.poetry\npackages = [").is_empty());
+    }
+
+    #[test]
+    fn test_source_roots_detects_src_and_config_overrides() {
+        let tmp = std::env::temp_dir().join("test_source_roots");
+        fs::remove_dir_all(&tmp).ok();
+        fs::create_dir_all(tmp.join("src/mypkg")).ok();
+        fs::create_dir_all(tmp.join("lib")).ok();
+        fs::write(tmp.join("src/mypkg/core.py"), "").ok();
+        let root = dunce::canonicalize(&tmp).unwrap();
+
+        let detected = source_roots(&root, None);
+        assert_eq!(detected, vec![root.join("src"), root.clone()]);
+        let source = root.join("tests/test_core.py");
+        let resolved = resolve_import(&source, "mypkg.core", &detected);
+        assert_eq!(resolved, Some(root.join("src/mypkg/core.py")));
+
+        let configured = source_roots(&root, Some(&["lib".to_string()]));
+        assert_eq!(configured, vec![root.join("lib"), root.clone()]);
+        assert!(resolve_import(&source, "mypkg.core", &configured).is_none());
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_module_path_is_relative_to_the_source_root() {
+        let root = PathBuf::from("/project");
+        let roots = vec![root.join("src"), root.clone()];
+        let module = |file: &str| module_path(&root.join(file), &roots);
+        assert_eq!(
+            module("src/app/api/handlers.py").as_deref(),
+            Some("app.api.handlers")
+        );
+        assert_eq!(module("src/app/__init__.py").as_deref(), Some("app"));
+        assert_eq!(module("tools/build.py").as_deref(), Some("tools.build"));
+        assert_eq!(module("main.py").as_deref(), Some("main"));
+        assert_eq!(module("__init__.py").as_deref(), Some(""));
+        assert_eq!(module_path(Path::new("/elsewhere/x.py"), &roots), None);
+    }
+
+    fn parse_es(source: &[u8]) -> Vec<EsImport> {
+        let mut parser = tree_sitter::Parser::new();
+        parser
+            .set_language(&tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into())
+            .unwrap();
+        let tree = parser.parse(source, None).unwrap();
+        extract_es_imports(source, tree.root_node())
+    }
+
+    #[test]
+    fn test_es_import_forms() {
+        let source = b"import Store, { fmt as format, parse } from './utils';\nimport * as api from \"../api\";\nimport './polyfill';\nexport { run } from './run';\nexport * from './all';\nconst lib = require('./lib');\nimport React from 'react';\n";
+        let imports = parse_es(source);
+        let specifiers: Vec<&str> = imports.iter().map(|i| i.specifier.as_str()).collect();
+        assert_eq!(
+            specifiers,
+            vec![
+                "./utils",
+                "../api",
+                "./polyfill",
+                "./run",
+                "./all",
+                "./lib",
+                "react"
+            ]
+        );
+
+        let pair = |a: &str, b: &str| (a.to_string(), b.to_string());
+        assert_eq!(
+            imports[0].names,
+            vec![
+                pair("default", "Store"),
+                pair("fmt", "format"),
+                pair("parse", "parse")
+            ]
+        );
+        assert!(!imports[0].namespace);
+        assert!(imports[1].namespace && imports[2].namespace);
+        assert!(imports[3].reexport && imports[3].names == vec![pair("run", "run")]);
+        assert!(imports[4].reexport && imports[4].namespace);
+        assert!(imports[5].namespace && !imports[5].reexport);
+        assert_eq!(imports[5].line, 6);
+    }
+
+    #[test]
+    fn test_resolve_es_import_extensionless() {
+        let tmp = std::env::temp_dir().join("test_resolve_es");
+        fs::remove_dir_all(&tmp).ok();
+        fs::create_dir_all(tmp.join("utils")).ok();
+        fs::write(tmp.join("helpers.ts"), "").ok();
+        fs::write(tmp.join("utils/index.ts"), "").ok();
+        let source = tmp.join("app.ts");
+
+        let resolved = |spec: &str| resolve_es_import(&source, spec);
+        assert!(resolved("./helpers").unwrap().ends_with("helpers.ts"));
+        assert!(resolved("./helpers.js").unwrap().ends_with("helpers.ts"));
+        assert!(resolved("./utils").unwrap().ends_with("utils/index.ts"));
+        assert!(resolved("./missing").is_none());
+        assert!(resolved("react").is_none());
+
+        fs::remove_dir_all(tmp).ok();
+    }
 }
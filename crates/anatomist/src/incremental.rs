@@ -0,0 +1,620 @@
+//! # Incremental Reference Graph
+//!
+//! Keeps a [`ReferenceGraph`] current as files change, for `janitor watch`.
+//! Only the edited Python files are dissected again:
+//!
+//! 1. Edges going *out of* a changed file's symbols are dropped, and its
+//!    registry entries and entities are replaced by a fresh dissection.
+//! 2. Graph nodes are keyed by symbol id, so a symbol that survives the edit
+//!    keeps the edges *into* it from other files. Nodes of symbols that are gone
+//!    (deleted, renamed, or in a removed file) are dropped with their edges; a
+//!    rename is the old path removed plus the new path created.
+//! 3. The changed files are linked again. A file that gained symbols also
+//!    re-links the files importing it, directly or through re-exports, since
+//!    their imports may resolve now. A new file re-links every Python file.
+//!
+//! C++, JS/TS and notebook edits rebuild the whole graph: their link passes
+//! resolve names project-wide and are not incremental.
+
+use crate::graph::{
+    assign_module_path, build_reference_graph_with_options, match_annotation_names,
+    match_dynamic_names, module_entry, names_by_file, normalize_path, parse_failure, read_source,
+    read_targets, source_hash, EdgeInfo, FileLinks, GraphOptions, PyLinker, ReferenceGraph,
+    CPP_EXTENSIONS,
+};
+use crate::imports::SCRIPT_EXTENSIONS;
+use crate::{AnatomistError, Entity, ParserHost};
+use common::registry::{symbol_hash, SymbolEntry};
+use common::walk::WalkFilter;
+use petgraph::graph::{DiGraph, EdgeIndex, NodeIndex};
+use petgraph::visit::EdgeRef;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// A reference graph plus what it takes to patch it file by file.
+pub struct IncrementalGraph {
+    root: PathBuf,
+    options: GraphOptions,
+    graph: ReferenceGraph,
+}
+
+/// What one [`IncrementalGraph::update`] did.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize)]
+pub struct GraphUpdate {
+    /// Python files dissected again.
+    pub files_parsed: usize,
+    /// Python files whose outgoing edges were rebuilt.
+    pub files_linked: usize,
+    /// Python files dropped from the graph (deleted, or renamed away).
+    pub files_removed: usize,
+    /// `true` when the whole graph was built from scratch (a C++ or JS/TS change).
+    pub rebuilt: bool,
+}
+
+/// A changed Python file, by path on disk and graph key.
+struct Changed {
+    path: PathBuf,
+    key: String,
+}
+
+impl IncrementalGraph {
+    /// Builds the full graph of `project_root` once.
+    pub fn build(
+        project_root: &Path,
+        host: &mut ParserHost,
+        options: GraphOptions,
+    ) -> Result<Self, AnatomistError> {
+        let root = dunce::canonicalize(project_root)?;
+        let graph = build_reference_graph_with_options(&root, host, &options, None)?;
+        Ok(Self {
+            root,
+            options,
+            graph,
+        })
+    }
+
+    /// The canonical project root.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn graph(&self) -> &ReferenceGraph {
+        &self.graph
+    }
+
+    /// Brings the graph up to date with `paths`, each created, modified or
+    /// deleted since the last update. Paths outside the project walk (see
+    /// [`common::walk`]) and files that are not source code are ignored.
+    pub fn update(
+        &mut self,
+        host: &mut ParserHost,
+        paths: &[PathBuf],
+    ) -> Result<GraphUpdate, AnatomistError> {
+        let mut changed: Vec<Changed> = Vec::new();
+        let mut seen: HashSet<String> = HashSet::new();
+        for path in self.project_paths(paths) {
+            match path.extension().and_then(|e| e.to_str()) {
+                Some("py") => {
+                    let key = normalize_path(&path);
+                    if seen.insert(key.clone()) {
+                        changed.push(Changed { path, key });
+                    }
+                }
+                Some(ext)
+                    if CPP_EXTENSIONS.contains(&ext)
+                        || SCRIPT_EXTENSIONS.contains(&ext)
+                        || ext == "ipynb" =>
+                {
+                    return self.rebuild(host);
+                }
+                _ => {}
+            }
+        }
+        if changed.is_empty() {
+            return Ok(GraphUpdate::default());
+        }
+
+        let mut update = GraphUpdate::default();
+        let mut old_ids: HashSet<u64> = HashSet::new();
+        let mut new_ids: HashSet<u64> = HashSet::new();
+        let mut grown: Vec<&str> = Vec::new();
+        let mut created = false;
+        let mut dissected: Vec<(&Changed, u32, Vec<Entity>)> = Vec::new();
+        let roots = self.options.import_roots(&self.root);
+        for file in &changed {
+            let before: HashSet<u64> = self
+                .graph
+                .file_symbols
+                .remove(&file.key)
+                .unwrap_or_default()
+                .into_iter()
+                .collect();
+            let existed = !before.is_empty();
+            self.graph
+                .registry
+                .entries
+                .retain(|e| e.file_path != file.key);
+            self.graph.entities.retain(|e| e.file_path != file.key);
+            self.graph.file_hashes.remove(&file.key);
+            self.graph
+                .stats
+                .diagnostics
+                .retain(|d| d.file.as_deref() != Some(file.key.as_str()));
+            self.forget_links(&file.key);
+
+            let mut after: HashSet<u64> = HashSet::new();
+            if file.path.is_file() {
+                let diagnostics = &mut self.graph.stats.diagnostics;
+                let result = read_source(&file.path, diagnostics).and_then(|source| {
+                    host.dissect_source(&file.path, &source)
+                        .map_err(|e| diagnostics.push(parse_failure(&file.path, &e)))
+                        .ok()
+                        .map(|entities| (entities, source.len(), source_hash(&source)))
+                });
+                match result {
+                    Some((mut entities, len, hash)) => {
+                        if let Some(hash) = hash {
+                            self.graph.file_hashes.insert(file.key.clone(), hash);
+                        }
+                        assign_module_path(&mut entities, Path::new(&file.key), &roots);
+                        let size = len.min(u32::MAX as usize) as u32;
+                        after.insert(module_entry(&file.key, size).id);
+                        after.extend(entities.iter().map(|e| symbol_hash(&e.symbol_id())));
+                        dissected.push((file, size, entities));
+                    }
+                    None => self.graph.stats.parse_errors += 1,
+                }
+                update.files_parsed += 1;
+                if !existed {
+                    created = true;
+                    self.graph.stats.file_count += 1;
+                }
+            } else if existed {
+                update.files_removed += 1;
+                self.graph.stats.file_count = self.graph.stats.file_count.saturating_sub(1);
+            }
+            if after.difference(&before).next().is_some() {
+                grown.push(&file.key);
+            }
+            old_ids.extend(before);
+            new_ids.extend(after);
+        }
+
+        // Files to link again: the changed ones that still exist, plus
+        // importers of any file that gained symbols.
+        let mut relink: BTreeSet<String> = dissected.iter().map(|(f, ..)| f.key.clone()).collect();
+        if created {
+            relink.extend(self.graph.python_links.keys().cloned());
+        } else {
+            for key in grown {
+                relink.extend(self.importers(key));
+            }
+        }
+        let mut unlink = old_ids.clone();
+        for key in &relink {
+            if let Some(ids) = self.graph.file_symbols.get(key) {
+                unlink.extend(ids);
+            }
+            self.forget_links(key);
+        }
+
+        // Outgoing edges of re-linked files go; so do the symbols that
+        // disappeared, with the edges into them.
+        let gone: HashSet<u64> = old_ids.difference(&new_ids).copied().collect();
+        let graph = &mut self.graph.graph;
+        graph.retain_edges(|g, e| {
+            g.edge_endpoints(e)
+                .is_some_and(|(source, _)| !unlink.contains(&g[source]))
+        });
+        graph.retain_nodes(|g, n| !gone.contains(&g[n]));
+
+        let mut id_to_node: HashMap<u64, NodeIndex> =
+            graph.node_indices().map(|n| (graph[n], n)).collect();
+        for (file, size, entities) in dissected {
+            let entries = std::iter::once(module_entry(&file.key, size))
+                .chain(entities.iter().map(SymbolEntry::from));
+            for entry in entries {
+                id_to_node
+                    .entry(entry.id)
+                    .or_insert_with(|| graph.add_node(entry.id));
+                self.graph
+                    .file_symbols
+                    .entry(entry.file_path.clone())
+                    .or_default()
+                    .push(entry.id);
+                self.graph.registry.insert(entry);
+            }
+            self.graph.entities.extend(entities);
+        }
+
+        update.files_linked = self.link(&relink, &id_to_node)?;
+        self.graph.dynamic_refs = {
+            let mut keys: Vec<&String> = self.graph.python_links.keys().collect();
+            keys.sort();
+            let names: Vec<_> = keys
+                .into_iter()
+                .flat_map(|k| self.graph.python_links[k].dynamic_names.iter().cloned())
+                .collect();
+            match_dynamic_names(&self.graph.registry, &names)
+        };
+        self.graph.annotation_refs = {
+            let mut keys: Vec<&String> = self.graph.python_links.keys().collect();
+            keys.sort();
+            match_annotation_names(
+                &self.graph.registry,
+                keys.into_iter().map(|k| &self.graph.python_links[k]),
+            )
+        };
+        self.graph.stats.symbol_count = self.graph.entities.len();
+        self.graph.stats.edge_count = self.graph.graph.edge_count();
+        self.graph.stats.files_parsed = update.files_parsed;
+        self.graph.stats.cache_hits = 0;
+        Ok(update)
+    }
+
+    /// The canonical form of each of `paths` that [`common::walk`] would
+    /// visit under one of the graph's roots; a deleted file counts by its name.
+    pub fn project_paths(&self, paths: &[PathBuf]) -> Vec<PathBuf> {
+        let mut filter = WalkFilter::new(&self.root);
+        paths
+            .iter()
+            .map(|p| canonical(p))
+            .filter(|p| filter.is_walked(p))
+            .filter(|p| self.graph.owning_root(&normalize_path(p)).is_some())
+            .collect()
+    }
+
+    /// Replaces the graph with a full build.
+    fn rebuild(&mut self, host: &mut ParserHost) -> Result<GraphUpdate, AnatomistError> {
+        self.graph = build_reference_graph_with_options(&self.root, host, &self.options, None)?;
+        Ok(GraphUpdate {
+            files_parsed: self.graph.stats.files_parsed,
+            files_linked: self.graph.python_links.len(),
+            files_removed: 0,
+            rebuilt: true,
+        })
+    }
+
+    /// Drops what linking `key` recorded outside the graph edges.
+    fn forget_links(&mut self, key: &str) {
+        self.graph.python_links.remove(key);
+        self.graph
+            .di_registered
+            .retain(|_, registered| registered.file != key);
+    }
+
+    /// Python files whose imports reach `key`, directly or through files that
+    /// import it in turn (package `__init__.py` re-exports).
+    fn importers(&self, key: &str) -> BTreeSet<String> {
+        let mut found: BTreeSet<String> = BTreeSet::new();
+        let mut pending = vec![key.to_string()];
+        while let Some(target) = pending.pop() {
+            for (file, links) in &self.graph.python_links {
+                if links.imported_files.contains(&target) && found.insert(file.clone()) {
+                    pending.push(file.clone());
+                }
+            }
+        }
+        found.remove(key);
+        found
+    }
+
+    /// Runs the Python link pass over `files` (graph keys), returning how many
+    /// could be linked.
+    fn link(
+        &mut self,
+        files: &BTreeSet<String>,
+        id_to_node: &HashMap<u64, NodeIndex>,
+    ) -> Result<usize, AnatomistError> {
+        let graph = &mut self.graph;
+        let roots = self.options.import_roots(&self.root);
+        let read_target_ids = read_targets(&graph.registry);
+        let file_to_names = names_by_file(&graph.registry);
+        let mut edge_ids = edge_index(&graph.graph);
+        let mut linked: Vec<(String, FileLinks)> = Vec::new();
+        {
+            let mut linker = PyLinker::new(
+                &roots,
+                &self.options,
+                &graph.registry,
+                &file_to_names,
+                &read_target_ids,
+                id_to_node,
+            )?;
+            for key in files {
+                let path = Path::new(key);
+                if path.is_file() {
+                    let links = linker.link_file(&mut graph.graph, &mut edge_ids, path);
+                    linked.push((key.clone(), links));
+                }
+            }
+        }
+        let count = linked.len();
+        for (key, links) in linked {
+            for (target_id, site) in &links.di_registered {
+                graph
+                    .di_registered
+                    .entry(*target_id)
+                    .or_insert_with(|| site.clone());
+            }
+            graph
+                .stats
+                .diagnostics
+                .extend(links.syntax_diagnostic(&key));
+            graph.python_links.insert(key, links);
+        }
+        Ok(count)
+    }
+}
+
+/// `(source, target)` -> edge, for adding references to existing edges.
+fn edge_index(graph: &DiGraph<u64, EdgeInfo>) -> HashMap<(NodeIndex, NodeIndex), EdgeIndex> {
+    graph
+        .edge_references()
+        .map(|e| ((e.source(), e.target()), e.id()))
+        .collect()
+}
+
+/// `path` resolved through symlinks; for a deleted file, its parent is
+/// resolved instead.
+fn canonical(path: &Path) -> PathBuf {
+    if let Ok(canonical) = dunce::canonicalize(path) {
+        return canonical;
+    }
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) => dunce::canonicalize(parent)
+            .map(|p| p.join(name))
+            .unwrap_or_else(|_| path.to_path_buf()),
+        _ => path.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::{run_on_graph, ScanOptions};
+    use std::fs;
+
+    /// Every edge as `(source symbol id, target symbol id)`.
+    fn edges(graph: &ReferenceGraph) -> BTreeSet<(u64, u64)> {
+        graph
+            .graph
+            .edge_references()
+            .map(|e| (graph.graph[e.source()], graph.graph[e.target()]))
+            .collect()
+    }
+
+    fn symbols(graph: &ReferenceGraph) -> BTreeSet<(String, String)> {
+        graph
+            .registry
+            .entries
+            .iter()
+            .map(|e| (e.file_path.clone(), e.fully_qualified_name()))
+            .collect()
+    }
+
+    /// Asserts the patched graph matches a full build of the same tree.
+    fn assert_matches_full_build(incremental: &IncrementalGraph, host: &mut ParserHost) {
+        let full =
+            build_reference_graph_with_options(incremental.root(), host, &Default::default(), None)
+                .unwrap();
+        let patched = incremental.graph();
+        assert_eq!(symbols(patched), symbols(&full));
+        assert_eq!(edges(patched), edges(&full));
+        assert_eq!(patched.graph.node_count(), full.graph.node_count());
+        assert_eq!(patched.stats.edge_count, full.stats.edge_count);
+        assert_eq!(patched.stats.symbol_count, full.stats.symbol_count);
+        assert_eq!(patched.stats.file_count, full.stats.file_count);
+    }
+
+    fn project(name: &str) -> PathBuf {
+        let tmp = std::env::temp_dir().join(name);
+        fs::remove_dir_all(&tmp).ok();
+        fs::create_dir_all(&tmp).unwrap();
+        fs::write(
+            tmp.join("a.py"),
+            "def helper():\n    pass\n\n\ndef other():\n    pass\n",
+        )
+        .unwrap();
+        fs::write(
+            tmp.join("b.py"),
+            "from a import helper\n\n\ndef main():\n    helper()\n",
+        )
+        .unwrap();
+        tmp
+    }
+
+    fn has_symbol(graph: &ReferenceGraph, qualified_name: &str) -> bool {
+        graph
+            .registry
+            .entries
+            .iter()
+            .any(|e| e.qualified_name == qualified_name)
+    }
+
+    #[test]
+    fn test_edit_keeps_edges_into_the_file() {
+        let tmp = project("test_incremental_edit");
+        let mut host = ParserHost::new().unwrap();
+        let mut incremental = IncrementalGraph::build(&tmp, &mut host, Default::default()).unwrap();
+        let edges_before = edges(incremental.graph());
+        assert_eq!(edges_before.len(), 1);
+
+        fs::write(
+            tmp.join("a.py"),
+            "# moved down a line\ndef helper():\n    return 1\n\n\ndef other():\n    pass\n",
+        )
+        .unwrap();
+        let update = incremental.update(&mut host, &[tmp.join("a.py")]).unwrap();
+
+        assert_eq!(update.files_parsed, 1);
+        assert_eq!(update.files_linked, 1);
+        assert!(!update.rebuilt);
+        assert_eq!(edges(incremental.graph()), edges_before);
+        assert_matches_full_build(&incremental, &mut host);
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_edit_drops_edges_out_of_the_file() {
+        let tmp = project("test_incremental_caller");
+        let mut host = ParserHost::new().unwrap();
+        let mut incremental = IncrementalGraph::build(&tmp, &mut host, Default::default()).unwrap();
+
+        fs::write(tmp.join("b.py"), "def main():\n    pass\n").unwrap();
+        incremental.update(&mut host, &[tmp.join("b.py")]).unwrap();
+
+        assert!(edges(incremental.graph()).is_empty());
+        assert_matches_full_build(&incremental, &mut host);
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_new_symbol_relinks_importers() {
+        let tmp = project("test_incremental_grown");
+        fs::write(
+            tmp.join("b.py"),
+            "from a import helper, late\n\n\ndef main():\n    helper()\n    late()\n",
+        )
+        .unwrap();
+        let mut host = ParserHost::new().unwrap();
+        let mut incremental = IncrementalGraph::build(&tmp, &mut host, Default::default()).unwrap();
+        assert_eq!(edges(incremental.graph()).len(), 1);
+
+        fs::write(
+            tmp.join("a.py"),
+            "def helper():\n    pass\n\n\ndef late():\n    pass\n",
+        )
+        .unwrap();
+        let update = incremental.update(&mut host, &[tmp.join("a.py")]).unwrap();
+
+        assert_eq!(update.files_parsed, 1);
+        assert_eq!(update.files_linked, 2);
+        assert_eq!(edges(incremental.graph()).len(), 2);
+        assert!(!has_symbol(incremental.graph(), "other"));
+        assert_matches_full_build(&incremental, &mut host);
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_rename_cleans_up_the_old_path() {
+        let tmp = project("test_incremental_rename");
+        let mut host = ParserHost::new().unwrap();
+        let mut incremental = IncrementalGraph::build(&tmp, &mut host, Default::default()).unwrap();
+        let old_key = normalize_path(&canonical(&tmp.join("a.py")));
+
+        fs::rename(tmp.join("a.py"), tmp.join("c.py")).unwrap();
+        fs::write(
+            tmp.join("b.py"),
+            "from c import helper\n\n\ndef main():\n    helper()\n",
+        )
+        .unwrap();
+        let update = incremental
+            .update(
+                &mut host,
+                &[tmp.join("a.py"), tmp.join("c.py"), tmp.join("b.py")],
+            )
+            .unwrap();
+
+        assert_eq!(update.files_removed, 1);
+        let graph = incremental.graph();
+        assert!(!graph.file_symbols.contains_key(&old_key));
+        assert!(graph
+            .registry
+            .entries
+            .iter()
+            .all(|e| e.file_path != old_key));
+        assert!(graph.entities.iter().all(|e| e.file_path != old_key));
+        assert_eq!(edges(graph).len(), 1);
+        assert_matches_full_build(&incremental, &mut host);
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_deleted_file_drops_edges_into_it() {
+        let tmp = project("test_incremental_delete");
+        let mut host = ParserHost::new().unwrap();
+        let mut incremental = IncrementalGraph::build(&tmp, &mut host, Default::default()).unwrap();
+
+        fs::remove_file(tmp.join("a.py")).unwrap();
+        let update = incremental.update(&mut host, &[tmp.join("a.py")]).unwrap();
+
+        assert_eq!(update.files_removed, 1);
+        assert_eq!(update.files_linked, 0);
+        assert!(!has_symbol(incremental.graph(), "helper"));
+        assert!(edges(incremental.graph()).is_empty());
+        assert_matches_full_build(&incremental, &mut host);
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_new_file_links_existing_imports() {
+        let tmp = project("test_incremental_create");
+        fs::write(
+            tmp.join("b.py"),
+            "from a import helper\nfrom c import util\n\n\ndef main():\n    helper()\n    util()\n",
+        )
+        .unwrap();
+        let mut host = ParserHost::new().unwrap();
+        let mut incremental = IncrementalGraph::build(&tmp, &mut host, Default::default()).unwrap();
+
+        fs::write(tmp.join("c.py"), "def util():\n    pass\n").unwrap();
+        incremental.update(&mut host, &[tmp.join("c.py")]).unwrap();
+
+        assert_eq!(edges(incremental.graph()).len(), 2);
+        assert_matches_full_build(&incremental, &mut host);
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_ignored_paths_leave_the_graph_alone() {
+        let tmp = project("test_incremental_ignored");
+        let mut host = ParserHost::new().unwrap();
+        let mut incremental = IncrementalGraph::build(&tmp, &mut host, Default::default()).unwrap();
+
+        fs::create_dir_all(tmp.join(".janitor")).unwrap();
+        fs::write(tmp.join(".janitor/x.py"), "def hidden():\n    pass\n").unwrap();
+        fs::write(tmp.join("notes.txt"), "helper\n").unwrap();
+        let update = incremental
+            .update(
+                &mut host,
+                &[tmp.join(".janitor/x.py"), tmp.join("notes.txt")],
+            )
+            .unwrap();
+
+        assert_eq!(update, GraphUpdate::default());
+        assert!(!has_symbol(incremental.graph(), "hidden"));
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_pipeline_on_patched_graph() {
+        let tmp = project("test_incremental_pipeline");
+        let mut host = ParserHost::new().unwrap();
+        let mut incremental = IncrementalGraph::build(&tmp, &mut host, Default::default()).unwrap();
+        let options = ScanOptions::default();
+        let dead = |graph: &ReferenceGraph| -> BTreeSet<String> {
+            run_on_graph(&tmp, &options, graph, &mut crate::progress::silent)
+                .unwrap()
+                .dead
+                .into_iter()
+                .map(|e| e.qualified_name)
+                .collect()
+        };
+        assert!(!dead(incremental.graph()).contains("helper"));
+
+        fs::write(tmp.join("b.py"), "def main():\n    pass\n").unwrap();
+        incremental.update(&mut host, &[tmp.join("b.py")]).unwrap();
+
+        assert!(dead(incremental.graph()).contains("helper"));
+
+        fs::remove_dir_all(tmp).ok();
+    }
+}
@@ -5,20 +5,27 @@
 //! **Core Types**:
 //! - `Entity`: Zero-copy representation of Python symbols (functions, classes, methods).
 //! - `EntityType`: 7 Python definition types (FunctionDefinition, ClassDefinition, etc.).
-//! - `Protection`: Enumeration of 16 pipeline protection gates (e.g., PytestFixture, FastApiOverride).
+//! - `Protection`: Enumeration of 23 pipeline protection gates (e.g., PytestFixture, FastApiOverride).
 //!
 //! **Design**:
 //! - Stores byte ranges (`start_byte..end_byte`) instead of full text for memory efficiency.
 //! - Uses `rkyv` for zero-copy serialization to Oracle's Datalog engine.
 //! - All public types derive `Archive, Deserialize, Serialize, CheckBytes` for cross-process IPC.
 
+pub mod cache;
+pub mod di;
 pub mod graph;
 pub mod heuristics;
 pub mod imports;
+pub mod incremental;
+pub mod notebook;
 pub mod parser;
 pub mod path_util;
 pub mod pipeline;
+pub mod pragma;
+pub mod progress;
 pub mod scan;
+pub mod unused_imports;
 pub mod wisdom;
 
 pub use pipeline::ScanResult;
@@ -29,15 +36,18 @@ pub use parser::ParserHost;
 // Protection is defined in `common` and re-exported here so that all
 // intra-crate modules that write `use crate::Protection` continue to
 // compile without modification.
-pub use common::Protection;
+pub use common::{Protection, ProtectionDetail};
 
+use common::registry::{symbol_hash, SymbolEntry};
 use rkyv::{Archive, Deserialize, Serialize};
 
 /// Python definition types recognized by the Anatomist.
 ///
 /// Maps to Tree-sitter node types: `function_definition`, `async_function_definition`,
 /// `class_definition`, `decorated_definition`, `assignment`, `type_alias`.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Archive, Deserialize, Serialize)]
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, Archive, Deserialize, Serialize, serde::Serialize,
+)]
 #[rkyv(derive(Debug))]
 #[repr(u8)]
 pub enum EntityType {
@@ -51,9 +61,9 @@ pub enum EntityType {
     MethodDefinition = 3,
     /// `@decorator\ndef foo(): ...` or `@decorator\nclass Foo: ...`
     DecoratedDefinition = 4,
-    /// `x = 42` (module or class-level)
+    /// `x = 42` (module level)
     Assignment = 5,
-    /// `type Alias = int` (PEP 613)
+    /// `type Alias = int` (PEP 695) or `Alias: TypeAlias = int` (PEP 613)
     TypeAlias = 6,
 }
 
@@ -67,7 +77,8 @@ pub enum EntityType {
 /// **Serialization**:
 /// - Derives `Archive, Deserialize, Serialize` for `rkyv` zero-copy IPC to Oracle.
 /// - Derives `CheckBytes` for safe deserialization (validates pointers/lengths).
-#[derive(Debug, Clone, PartialEq, Eq, Archive, Deserialize, Serialize)]
+/// - Derives `serde::Serialize` for the `scan --format json` report.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Deserialize, Serialize, serde::Serialize)]
 #[rkyv(derive(Debug))]
 #[repr(C)]
 pub struct Entity {
@@ -93,8 +104,16 @@ pub struct Entity {
     pub file_path: String,
 
     /// Fully qualified name (e.g., `"ClassName.method_name"`, `"module.function"`).
+    /// Repeated definitions in one file (e.g. per-platform `if`/`else` branches) get a
+    /// `#n` suffix from the second one on: `"handler"`, `"handler#2"`.
     pub qualified_name: String,
 
+    /// Dotted module of `file_path` relative to its source root
+    /// (`src/app/api/handlers.py` → `"app.api.handlers"`; see
+    /// [`imports::module_path`]). Set by the reference graph build; empty for
+    /// entities straight from [`ParserHost::dissect`].
+    pub module_path: String,
+
     /// Parent class name for methods (e.g., `"MyClass"` for `def MyClass.foo(self)`).
     pub parent_class: Option<String>,
 
@@ -104,6 +123,9 @@ pub struct Entity {
     /// Protection reason (if entity survived the pipeline). `None` = candidate for deletion.
     pub protected_by: Option<Protection>,
 
+    /// Stage and evidence behind `protected_by` (see [`Entity::protect`]).
+    pub protection_detail: Option<ProtectionDetail>,
+
     /// Decorator names (e.g., `["staticmethod", "pytest.fixture"]`).
     pub decorators: Vec<String>,
 
@@ -111,7 +133,12 @@ pub struct Entity {
     ///
     /// `Some(hash)` for functions and methods; `None` for classes and assignments.
     /// Two functions with identical control-flow but different variable names produce the same hash.
+    /// Functions with different parameter counts never share a hash.
     pub structural_hash: Option<u64>,
+
+    /// Structural nodes in the function body (see [`forge::StructuralFingerprint`]); 0 without a hash.
+    #[serde(skip)]
+    pub structural_nodes: u32,
 }
 
 impl Entity {
@@ -129,11 +156,14 @@ impl Entity {
     ///     end_line: 3,
     ///     file_path: "src/api.py".into(),
     ///     qualified_name: "api.foo".into(),
+    ///     module_path: String::new(),
     ///     parent_class: None,
     ///     base_classes: vec![],
     ///     protected_by: None,
+    ///     protection_detail: None,
     ///     decorators: vec![],
     ///     structural_hash: None,
+    ///     structural_nodes: 0,
     /// };
     /// assert_eq!(entity.symbol_id(), "src/api.py::api.foo");
     /// ```
@@ -141,6 +171,51 @@ impl Entity {
         format!("{}::{}", self.file_path, self.qualified_name)
     }
 
+    /// `module_path.qualified_name` (`"app.api.handlers.Handler.get"`), or just
+    /// the qualified name when the module is unknown. Unlike [`Entity::symbol_id`]
+    /// it names the symbol the way Python code and runtime logs do.
+    pub fn fully_qualified_name(&self) -> String {
+        if self.module_path.is_empty() {
+            self.qualified_name.clone()
+        } else {
+            format!("{}.{}", self.module_path, self.qualified_name)
+        }
+    }
+
+    /// Marks the entity protected by `by`, recording the deciding stage and why.
+    pub fn protect(&mut self, by: Protection, detail: ProtectionDetail) {
+        self.protected_by = Some(by);
+        self.protection_detail = Some(detail);
+    }
+
+    /// Checks the invariants every consumer of the byte range relies on:
+    /// `start_byte <= end_byte <= source_len`, `start_line <= end_line`, and a
+    /// non-empty name. The parser runs it on every entity it builds, so a bad
+    /// range fails the file instead of deleting or hashing the wrong bytes.
+    ///
+    /// # Errors
+    /// `ParseFailure` naming the file, line, symbol and offending values.
+    pub fn validate(&self, source_len: usize) -> Result<(), AnatomistError> {
+        let problem = if self.name.is_empty() {
+            "empty name".to_string()
+        } else if self.start_byte > self.end_byte {
+            format!("inverted byte range {}..{}", self.start_byte, self.end_byte)
+        } else if self.end_byte as usize > source_len {
+            format!(
+                "byte range {}..{} ends past the {}-byte source",
+                self.start_byte, self.end_byte, source_len
+            )
+        } else if self.start_line > self.end_line {
+            format!("inverted line range {}..{}", self.start_line, self.end_line)
+        } else {
+            return Ok(());
+        };
+        Err(AnatomistError::ParseFailure(format!(
+            "{}:{}: invalid entity `{}`: {}",
+            self.file_path, self.start_line, self.qualified_name, problem
+        )))
+    }
+
     /// Returns the byte length of the entity's source code.
     ///
     /// # Example
@@ -155,11 +230,14 @@ impl Entity {
     ///     end_line: 5,
     ///     file_path: "test.py".into(),
     ///     qualified_name: "foo".into(),
+    ///     module_path: String::new(),
     ///     parent_class: None,
     ///     base_classes: vec![],
     ///     protected_by: None,
+    ///     protection_detail: None,
     ///     decorators: vec![],
     ///     structural_hash: None,
+    ///     structural_nodes: 0,
     /// };
     /// assert_eq!(entity.byte_len(), 150);
     /// ```
@@ -181,11 +259,14 @@ impl Entity {
     ///     end_line: 3,
     ///     file_path: "test.py".into(),
     ///     qualified_name: "__init__".into(),
+    ///     module_path: String::new(),
     ///     parent_class: None,
     ///     base_classes: vec![],
     ///     protected_by: None,
+    ///     protection_detail: None,
     ///     decorators: vec![],
     ///     structural_hash: None,
+    ///     structural_nodes: 0,
     /// };
     /// assert!(dunder.is_dunder());
     ///
@@ -198,11 +279,14 @@ impl Entity {
     ///     end_line: 3,
     ///     file_path: "test.py".into(),
     ///     qualified_name: "foo".into(),
+    ///     module_path: String::new(),
     ///     parent_class: None,
     ///     base_classes: vec![],
     ///     protected_by: None,
+    ///     protection_detail: None,
     ///     decorators: vec![],
     ///     structural_hash: None,
+    ///     structural_nodes: 0,
     /// };
     /// assert!(!normal.is_dunder());
     /// ```
@@ -224,11 +308,14 @@ impl Entity {
     ///     end_line: 3,
     ///     file_path: "test.py".into(),
     ///     qualified_name: "_helper".into(),
+    ///     module_path: String::new(),
     ///     parent_class: None,
     ///     base_classes: vec![],
     ///     protected_by: None,
+    ///     protection_detail: None,
     ///     decorators: vec![],
     ///     structural_hash: None,
+    ///     structural_nodes: 0,
     /// };
     /// assert!(private.is_private());
     ///
@@ -241,11 +328,14 @@ impl Entity {
     ///     end_line: 3,
     ///     file_path: "test.py".into(),
     ///     qualified_name: "helper".into(),
+    ///     module_path: String::new(),
     ///     parent_class: None,
     ///     base_classes: vec![],
     ///     protected_by: None,
+    ///     protection_detail: None,
     ///     decorators: vec![],
     ///     structural_hash: None,
+    ///     structural_nodes: 0,
     /// };
     /// assert!(!public.is_private());
     ///
@@ -258,11 +348,14 @@ impl Entity {
     ///     end_line: 3,
     ///     file_path: "test.py".into(),
     ///     qualified_name: "__init__".into(),
+    ///     module_path: String::new(),
     ///     parent_class: None,
     ///     base_classes: vec![],
     ///     protected_by: None,
+    ///     protection_detail: None,
     ///     decorators: vec![],
     ///     structural_hash: None,
+    ///     structural_nodes: 0,
     /// };
     /// assert!(!dunder.is_private()); // Dunders are NOT considered private
     /// ```
@@ -271,6 +364,79 @@ impl Entity {
     }
 }
 
+impl forge::StructuralSymbol for Entity {
+    fn structural_hash(&self) -> Option<u64> {
+        self.structural_hash
+    }
+
+    fn structural_nodes(&self) -> u32 {
+        self.structural_nodes
+    }
+
+    fn file_path(&self) -> &str {
+        &self.file_path
+    }
+
+    fn qualified_name(&self) -> &str {
+        &self.qualified_name
+    }
+
+    fn start_byte(&self) -> u32 {
+        self.start_byte
+    }
+
+    fn end_byte(&self) -> u32 {
+        self.end_byte
+    }
+
+    fn line_count(&self) -> u32 {
+        self.end_line.saturating_sub(self.start_line) + 1
+    }
+}
+
+/// The registry record of an entity and its verdict, keyed by the hash of
+/// [`Entity::symbol_id`].
+impl From<&Entity> for SymbolEntry {
+    fn from(entity: &Entity) -> Self {
+        SymbolEntry {
+            id: symbol_hash(&entity.symbol_id()),
+            name: entity.name.clone(),
+            qualified_name: entity.qualified_name.clone(),
+            module_path: entity.module_path.clone(),
+            file_path: entity.file_path.clone(),
+            entity_type: entity.entity_type as u8,
+            start_line: entity.start_line,
+            end_line: entity.end_line,
+            start_byte: entity.start_byte,
+            end_byte: entity.end_byte,
+            structural_hash: entity.structural_hash.unwrap_or(0),
+            protected_by: entity.protected_by,
+            protection_detail: entity.protection_detail.clone(),
+        }
+    }
+}
+
+/// Same as `From<&Entity>`, moving the strings instead of cloning them.
+impl From<Entity> for SymbolEntry {
+    fn from(entity: Entity) -> Self {
+        SymbolEntry {
+            id: symbol_hash(&entity.symbol_id()),
+            entity_type: entity.entity_type as u8,
+            start_line: entity.start_line,
+            end_line: entity.end_line,
+            start_byte: entity.start_byte,
+            end_byte: entity.end_byte,
+            structural_hash: entity.structural_hash.unwrap_or(0),
+            protected_by: entity.protected_by,
+            protection_detail: entity.protection_detail,
+            name: entity.name,
+            qualified_name: entity.qualified_name,
+            module_path: entity.module_path,
+            file_path: entity.file_path,
+        }
+    }
+}
+
 /// Errors produced by the Anatomist crate.
 #[derive(Debug, thiserror::Error)]
 pub enum AnatomistError {
@@ -282,9 +448,21 @@ pub enum AnatomistError {
     #[error("I/O error: {0}")]
     IoError(#[from] std::io::Error),
 
+    /// The file contains a NUL byte or could not be read as text.
+    #[error("{0}")]
+    Source(#[from] common::source::SourceError),
+
     /// Byte range exceeds u32::MAX (file too large).
     #[error("Byte range overflow: file size exceeds 4GB limit")]
     ByteRangeOverflow,
+
+    /// No registry entry has the requested qualified name.
+    #[error("Unknown symbol: {0}")]
+    UnknownSymbol(String),
+
+    /// A `[workspace] roots` entry is not a directory under the project.
+    #[error("workspace root `{0}` is not a directory")]
+    InvalidWorkspaceRoot(String),
 }
 
 #[cfg(test)]
@@ -301,11 +479,14 @@ mod tests {
             end_line: 20,
             file_path: "src/test.py".into(),
             qualified_name: qname.unwrap_or(name).into(),
+            module_path: String::new(),
             parent_class: None,
             base_classes: vec![],
             protected_by: None,
+            protection_detail: None,
             decorators: vec![],
             structural_hash: None,
+            structural_nodes: 0,
         }
     }
 
@@ -343,6 +524,56 @@ mod tests {
         assert_eq!(entity.byte_len(), 0); // saturating_sub prevents underflow
     }
 
+    #[test]
+    fn test_validate_accepts_exactly_the_ranges_inside_the_source() {
+        let source_len = 10;
+        for start in 0..=12u32 {
+            for end in 0..=12u32 {
+                let mut entity = make_test_entity("foo", None);
+                entity.start_byte = start;
+                entity.end_byte = end;
+                let valid = start <= end && end as usize <= source_len;
+                assert_eq!(
+                    entity.validate(source_len).is_ok(),
+                    valid,
+                    "{}..{}",
+                    start,
+                    end
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_validate_reports_adversarial_entities_with_context() {
+        let message = |mutate: &dyn Fn(&mut Entity)| {
+            let mut entity = make_test_entity("foo", Some("Cls.foo"));
+            mutate(&mut entity);
+            match entity.validate(300) {
+                Err(AnatomistError::ParseFailure(message)) => message,
+                other => panic!("expected ParseFailure, got {:?}", other),
+            }
+        };
+
+        assert_eq!(
+            message(&|e| e.end_byte = 99),
+            "src/test.py:10: invalid entity `Cls.foo`: inverted byte range 100..99"
+        );
+        assert!(message(&|e| e.end_byte = u32::MAX).contains("ends past the 300-byte source"));
+        assert!(message(&|e| {
+            e.start_byte = u32::MAX;
+            e.end_byte = u32::MAX;
+        })
+        .contains("ends past"));
+        assert!(message(&|e| e.end_line = 9).contains("inverted line range 10..9"));
+        assert!(message(&|e| e.name.clear()).contains("empty name"));
+        // An empty range at the very end of the source is still in bounds.
+        let mut entity = make_test_entity("foo", None);
+        entity.start_byte = 300;
+        entity.end_byte = 300;
+        assert!(entity.validate(300).is_ok());
+    }
+
     #[test]
     fn test_is_dunder() {
         assert!(make_test_entity("__init__", None).is_dunder());
@@ -386,11 +617,16 @@ mod tests {
             end_line: 5,
             file_path: "src/lib.py".into(),
             qualified_name: "lib.test_func".into(),
+            module_path: String::new(),
             parent_class: None,
             base_classes: vec![],
             protected_by: Some(Protection::PytestFixture),
+            protection_detail: Some(
+                ProtectionDetail::new(0, "parser heuristic").at("conftest.py:3"),
+            ),
             decorators: vec!["pytest.fixture".into()],
             structural_hash: None,
+            structural_nodes: 0,
         };
 
         // Serialize with rkyv
@@ -402,5 +638,15 @@ mod tests {
         assert_eq!(archived.start_byte, 0);
         assert_eq!(archived.end_byte, 42);
         assert_eq!(archived.file_path.as_str(), "src/lib.py");
+        let detail = archived.protection_detail.as_ref().unwrap();
+        assert_eq!(detail.stage, 0);
+        assert_eq!(detail.reason.as_str(), "parser heuristic");
+        assert_eq!(
+            detail.location.as_ref().map(|l| l.as_str()),
+            Some("conftest.py:3")
+        );
+
+        let back: Entity = rkyv::deserialize::<_, rkyv::rancor::Error>(archived).unwrap();
+        assert_eq!(back, entity);
     }
 }
@@ -0,0 +1,148 @@
+//! Jupyter notebooks (`.ipynb`).
+//!
+//! A notebook is JSON: a list of cells, each with the `source` someone wrote
+//! and, for code cells, the `outputs` of its last run. Outputs hold base64
+//! images and rendered tables, so the grep shield searches only the sources
+//! of code and Markdown cells ([`Notebook::text`]), and the reference graph
+//! reads imports from the code cells ([`Notebook::code`]): a notebook is an
+//! entry point, so the modules it imports are not orphans.
+
+use serde_json::Value;
+
+/// The cells of a notebook that hold text someone wrote.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Notebook {
+    pub cells: Vec<Cell>,
+}
+
+/// One code or Markdown cell.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cell {
+    /// 0-based position in the notebook's `cells`, counting every cell.
+    pub index: u32,
+    pub kind: CellKind,
+    pub source: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CellKind {
+    Code,
+    Markdown,
+}
+
+/// Cell sources joined by newlines, with where each cell starts.
+#[derive(Debug, Clone, Default)]
+pub struct NotebookText {
+    pub text: String,
+    /// `(byte offset in text, cell index)`, in offset order.
+    starts: Vec<(usize, u32)>,
+}
+
+impl Notebook {
+    /// Parses nbformat 4 JSON. `None` when `bytes` is not a notebook; raw
+    /// cells and outputs are dropped.
+    pub fn parse(bytes: &[u8]) -> Option<Self> {
+        let json: Value = serde_json::from_slice(bytes).ok()?;
+        let cells = json.get("cells")?.as_array()?;
+        let cells = cells
+            .iter()
+            .enumerate()
+            .filter_map(|(index, cell)| {
+                let kind = match cell.get("cell_type")?.as_str()? {
+                    "code" => CellKind::Code,
+                    "markdown" => CellKind::Markdown,
+                    _ => return None,
+                };
+                // `source` is a string or a list of lines that keep their `\n`.
+                let source = match cell.get("source")? {
+                    Value::String(s) => s.clone(),
+                    Value::Array(lines) => lines.iter().filter_map(Value::as_str).collect(),
+                    _ => return None,
+                };
+                Some(Cell {
+                    index: index as u32,
+                    kind,
+                    source,
+                })
+            })
+            .collect();
+        Some(Self { cells })
+    }
+
+    /// Code and Markdown sources for the grep shield.
+    pub fn text(&self) -> NotebookText {
+        let mut text = NotebookText::default();
+        for cell in &self.cells {
+            text.starts.push((text.text.len(), cell.index));
+            text.text.push_str(&cell.source);
+            text.text.push('\n');
+        }
+        text
+    }
+
+    /// The code cells as one Python module. IPython magics and shell escapes
+    /// (`%matplotlib inline`, `!pip install x`) become blank lines, and cells
+    /// run by a cell magic (`%%bash`) are left out.
+    pub fn code(&self) -> String {
+        let mut code = String::new();
+        for cell in self.cells.iter().filter(|c| c.kind == CellKind::Code) {
+            if cell.source.trim_start().starts_with("%%") {
+                continue;
+            }
+            for line in cell.source.lines() {
+                if !line.trim_start().starts_with(['%', '!']) {
+                    code.push_str(line);
+                }
+                code.push('\n');
+            }
+        }
+        code
+    }
+}
+
+impl NotebookText {
+    /// The cell holding byte `offset` of [`NotebookText::text`], and the
+    /// offset within that cell's source.
+    pub fn locate(&self, offset: usize) -> (u32, usize) {
+        let at = self.starts.partition_point(|&(start, _)| start <= offset);
+        let (start, cell) = self.starts[at.saturating_sub(1)];
+        (cell, offset - start)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOTEBOOK: &str = r##"{
+      "cells": [
+        {"cell_type": "markdown", "source": "# Uses `summarize`"},
+        {"cell_type": "code", "source": ["%matplotlib inline\n", "import report\n", "report.summarize()"],
+         "outputs": [{"data": {"image/png": "iVBORw0KGgoAAAANSUhEUgsummarize"}}]},
+        {"cell_type": "raw", "source": "raw_only"},
+        {"cell_type": "code", "source": "%%bash\nimport nothing"}
+      ],
+      "nbformat": 4
+    }"##;
+
+    #[test]
+    fn test_parse_keeps_sources_and_drops_outputs() {
+        let notebook = Notebook::parse(NOTEBOOK.as_bytes()).unwrap();
+        let indexes: Vec<u32> = notebook.cells.iter().map(|c| c.index).collect();
+        assert_eq!(indexes, vec![0, 1, 3]);
+        assert_eq!(
+            notebook.cells[1].source,
+            "%matplotlib inline\nimport report\nreport.summarize()"
+        );
+
+        let text = notebook.text();
+        assert!(!text.text.contains("iVBOR"));
+        assert!(!text.text.contains("raw_only"));
+        let second = text.text.find("import report").unwrap();
+        assert_eq!(text.locate(second), (1, "%matplotlib inline\n".len()));
+        assert_eq!(text.locate(0), (0, 0));
+
+        assert_eq!(notebook.code(), "\nimport report\nreport.summarize()\n");
+        assert!(Notebook::parse(b"{\"not\": \"a notebook\"}").is_none());
+    }
+}
@@ -4,23 +4,22 @@
 //! which grammar is used. Python entities receive full heuristic classification; other
 //! languages receive name + location extraction only.
 
-use std::collections::HashSet;
-use std::fs::File;
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
 use std::sync::OnceLock;
 
-use memmap2::MmapOptions;
 use tree_sitter::{Language, Parser, Query, QueryCursor, StreamingIterator};
 
 use crate::path_util::normalize_path;
-use crate::{AnatomistError, Entity, EntityType, Heuristic};
-use forge::compute_structural_hash;
+use crate::{AnatomistError, Entity, EntityType, Heuristic, Protection, ProtectionDetail};
+use forge::{compute_fingerprint, compute_fingerprint_with};
 
 /// Pattern indices for the entity query.
 const PATTERN_FN: usize = 0; // Standalone function_definition
 const PATTERN_CLASS: usize = 1; // Standalone class_definition
 const PATTERN_DECORATED: usize = 2; // decorated_definition wrapping function or class
 const PATTERN_ASSIGNMENT: usize = 3; // Module-level assignments
+const PATTERN_TYPE_ALIAS: usize = 4; // PEP 695 `type X = ...` statements
 
 /// Static cache for the Python entity extraction query.
 static ENTITY_QUERY: OnceLock<Query> = OnceLock::new();
@@ -35,7 +34,7 @@ static TSX_QUERY: OnceLock<Query> = OnceLock::new();
 /// Static cache for the C++ entity extraction query.
 static CPP_QUERY: OnceLock<Query> = OnceLock::new();
 
-/// S-expression shared by JS, TS, and TSX grammars (all extend the JS grammar node shapes).
+/// S-expression for JavaScript grammar entity extraction.
 const JS_ENTITY_S_EXPR: &str = r#"
     (function_declaration
       name: (identifier) @fn.name) @fn.def
@@ -47,6 +46,21 @@ const JS_ENTITY_S_EXPR: &str = r#"
       name: (property_identifier) @method.name) @method.def
 "#;
 
+/// S-expression shared by TS and TSX grammars, where class names are `type_identifier`s.
+const TS_ENTITY_S_EXPR: &str = r#"
+    (function_declaration
+      name: (identifier) @fn.name) @fn.def
+
+    (class_declaration
+      name: (type_identifier) @class.name) @class.def
+
+    (method_definition
+      name: (property_identifier) @method.name) @method.def
+
+    (abstract_class_declaration
+      name: (type_identifier) @class.name) @class.def
+"#;
+
 /// S-expression for Rust grammar entity extraction.
 const RUST_ENTITY_S_EXPR: &str = r#"
     (function_item
@@ -72,12 +86,23 @@ const RUST_PATTERNS: &[(&str, &str, EntityType)] = &[
 
 /// S-expression for C++ grammar entity extraction.
 ///
-/// Captures simple (non-template, non-pointer) function definitions and class/struct specifiers.
+/// Captures simple (non-template, non-pointer) function definitions, method definitions
+/// (in-class and out-of-line `Foo::bar`), and class/struct specifiers.
 const CPP_ENTITY_S_EXPR: &str = r#"
     (function_definition
       declarator: (function_declarator
         declarator: (identifier) @fn.name)) @fn.def
 
+    (function_definition
+      declarator: (function_declarator
+        declarator: (field_identifier) @method.name)) @method.def
+
+    (function_definition
+      declarator: (function_declarator
+        declarator: (qualified_identifier
+          scope: (namespace_identifier) @method.scope
+          name: (identifier) @method.name))) @method.def
+
     (class_specifier
       name: (type_identifier) @class.name) @class.def
 
@@ -88,17 +113,27 @@ const CPP_ENTITY_S_EXPR: &str = r#"
 /// Pattern-index → (def_cap, name_cap, entity_type) mapping for C++ grammar.
 const CPP_PATTERNS: &[(&str, &str, EntityType)] = &[
     ("fn.def", "fn.name", EntityType::FunctionDefinition),
+    ("method.def", "method.name", EntityType::MethodDefinition),
+    ("method.def", "method.name", EntityType::MethodDefinition),
     ("class.def", "class.name", EntityType::ClassDefinition),
     ("struct.def", "struct.name", EntityType::ClassDefinition),
 ];
 
-/// Pattern-index → (def_cap, name_cap, entity_type) mapping for JS/TS grammars.
+/// Pattern-index → (def_cap, name_cap, entity_type) mapping for the JS grammar.
 const JS_PATTERNS: &[(&str, &str, EntityType)] = &[
     ("fn.def", "fn.name", EntityType::FunctionDefinition),
     ("class.def", "class.name", EntityType::ClassDefinition),
     ("method.def", "method.name", EntityType::MethodDefinition),
 ];
 
+/// Pattern-index → (def_cap, name_cap, entity_type) mapping for TS/TSX grammars.
+const TS_PATTERNS: &[(&str, &str, EntityType)] = &[
+    ("fn.def", "fn.name", EntityType::FunctionDefinition),
+    ("class.def", "class.name", EntityType::ClassDefinition),
+    ("method.def", "method.name", EntityType::MethodDefinition),
+    ("class.def", "class.name", EntityType::ClassDefinition),
+];
+
 fn get_rust_query() -> &'static Query {
     RUST_QUERY.get_or_init(|| {
         Query::new(&tree_sitter_rust::LANGUAGE.into(), RUST_ENTITY_S_EXPR).expect(
@@ -119,7 +154,7 @@ fn get_ts_query() -> &'static Query {
     TS_QUERY.get_or_init(|| {
         Query::new(
             &tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
-            JS_ENTITY_S_EXPR,
+            TS_ENTITY_S_EXPR,
         )
         .expect("TS entity query compilation failed — this is a bug in the hardcoded S-expression")
     })
@@ -129,7 +164,7 @@ fn get_tsx_query() -> &'static Query {
     TSX_QUERY.get_or_init(|| {
         Query::new(
             &tree_sitter_typescript::LANGUAGE_TSX.into(),
-            JS_ENTITY_S_EXPR,
+            TS_ENTITY_S_EXPR,
         )
         .expect("TSX entity query compilation failed — this is a bug in the hardcoded S-expression")
     })
@@ -149,7 +184,8 @@ fn get_cpp_query() -> &'static Query {
 /// - Pattern 0: Standalone `function_definition`
 /// - Pattern 1: Standalone `class_definition` (with optional superclasses)
 /// - Pattern 2: `decorated_definition` wrapping function or class
-/// - Pattern 3: Module-level assignments (e.g., `__all__ = [...]`)
+/// - Pattern 3: Assignments to a single name (filtered to module level on extraction)
+/// - Pattern 4: PEP 695 `type` statements
 ///
 /// # Panic
 /// Panics if the query S-expression is malformed. This is a compile-time bug,
@@ -178,10 +214,17 @@ fn get_entity_query() -> &'static Query {
                   name: (identifier) @decorated.name)
               ] @decorated.inner) @decorated.def
 
-            ; Pattern 3: Module-level assignments
+            ; Pattern 3: Assignments (module-level ones are kept on extraction)
             (assignment
               left: (identifier) @assign.name
               right: (_) @assign.value) @assign.stmt
+
+            ; Pattern 4: PEP 695 type aliases (`type Vector[T] = list[T]`)
+            (type_alias_statement
+              left: (type [
+                (identifier) @alias.name
+                (generic_type (identifier) @alias.name)
+              ])) @alias.stmt
             "#,
         )
         .expect("Entity query compilation failed — this is a bug in the hardcoded S-expression")
@@ -249,7 +292,38 @@ impl ParserHost {
         self.heuristics.push(heuristic);
     }
 
-    /// Extracts entities from a source file using memory-mapped I/O.
+    /// Returns the registered heuristic names, in order, joined with `,`.
+    ///
+    /// Cached entities are only valid for the heuristic set that produced them.
+    pub fn heuristic_fingerprint(&self) -> String {
+        self.heuristics
+            .iter()
+            .map(|h| h.name())
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// The first registered heuristic protecting `node`, named in the detail.
+    fn apply_heuristics(
+        &self,
+        source: &[u8],
+        node: &tree_sitter::Node<'_>,
+        file_path: &str,
+    ) -> (Option<Protection>, Option<ProtectionDetail>) {
+        self.heuristics
+            .iter()
+            .find_map(|h| {
+                let protection = h.apply(source, node, file_path)?;
+                let name = h.name().rsplit("::").next().unwrap_or_default();
+                Some((
+                    protection,
+                    ProtectionDetail::new(0, format!("parser heuristic `{}`", name)),
+                ))
+            })
+            .unzip()
+    }
+
+    /// Extracts entities from a source file read with [`common::source::read`].
     ///
     /// Dispatches to the appropriate grammar based on file extension:
     /// - `.py` (default): Full Python extraction with heuristic classification.
@@ -260,47 +334,82 @@ impl ParserHost {
     ///
     /// # Errors
     /// - `IoError`: File not found, permission denied, mmap failure
+    /// - `Source`: The file contains a NUL byte
     /// - `ByteRangeOverflow`: File larger than 4GB (tree-sitter u32 limit)
     /// - `ParseFailure`: Tree-sitter parse returned `None` (severe syntax errors)
     pub fn dissect(&mut self, path: &Path) -> Result<Vec<Entity>, AnatomistError> {
-        let file = File::open(path)?;
-        let metadata = file.metadata()?;
-        let file_len = metadata.len();
+        let source = common::source::read(path)?;
+        self.dissect_source(path, &source)
+    }
 
-        if file_len > u32::MAX as u64 {
+    /// Like [`Self::dissect`], for the already-read UTF-8 text of `path`.
+    ///
+    /// Entity byte offsets index `source`, which for a transcoded file differs
+    /// from the bytes on disk.
+    pub fn dissect_source(
+        &mut self,
+        path: &Path,
+        source: &[u8],
+    ) -> Result<Vec<Entity>, AnatomistError> {
+        if source.len() as u64 > u32::MAX as u64 {
             return Err(AnatomistError::ByteRangeOverflow);
         }
-        if file_len == 0 {
+        if source.is_empty() {
             return Ok(Vec::new());
         }
-
-        // SAFETY: The file handle is held for the duration of the mmap lifetime.
-        let mmap = unsafe { MmapOptions::new().map(&file)? };
-        let source = &mmap[..];
         let normalized_path = normalize_path(path)?;
 
         let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
         match ext {
             "rs" => Self::extract_rust_entities(source, &normalized_path),
             "js" | "jsx" => Self::extract_js_entities(source, &normalized_path),
-            "ts" => extract_named_entities(
-                source,
-                tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
-                get_ts_query(),
-                &normalized_path,
-                JS_PATTERNS,
-            ),
-            "tsx" => extract_named_entities(
-                source,
-                tree_sitter_typescript::LANGUAGE_TSX.into(),
-                get_tsx_query(),
-                &normalized_path,
-                JS_PATTERNS,
-            ),
+            "ts" => Self::extract_ts_entities(source, &normalized_path, false),
+            "tsx" => Self::extract_ts_entities(source, &normalized_path, true),
             "cpp" | "cxx" | "cc" | "h" | "hpp" => {
                 Self::extract_cpp_entities(source, &normalized_path)
             }
-            _ => self.dissect_impl(source, &normalized_path), // Python + unknown → Python pass
+            _ => Ok(self.dissect_impl(source, &normalized_path)?.0), // Python + unknown → Python pass
+        }
+    }
+
+    /// Like [`Self::dissect`] for a Python file, also returning the syntax
+    /// tree the entities came from, so callers need not parse it again.
+    pub fn dissect_python(
+        &mut self,
+        path: &Path,
+    ) -> Result<(Vec<Entity>, tree_sitter::Tree), AnatomistError> {
+        let source = common::source::read(path)?;
+        if source.len() as u64 > u32::MAX as u64 {
+            return Err(AnatomistError::ByteRangeOverflow);
+        }
+        self.dissect_impl(&source, &normalize_path(path)?)
+    }
+
+    /// Extracts `function`, `class`, and `method` entities from a TypeScript source buffer,
+    /// using the TSX grammar when `tsx` is set.
+    pub fn extract_ts_entities(
+        source: &[u8],
+        file_path: &str,
+        tsx: bool,
+    ) -> Result<Vec<Entity>, AnatomistError> {
+        if tsx {
+            extract_named_entities(
+                source,
+                tree_sitter_typescript::LANGUAGE_TSX.into(),
+                get_tsx_query(),
+                file_path,
+                TS_PATTERNS,
+                forge::JS_SKIP_KINDS,
+            )
+        } else {
+            extract_named_entities(
+                source,
+                tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+                get_ts_query(),
+                file_path,
+                TS_PATTERNS,
+                forge::JS_SKIP_KINDS,
+            )
         }
     }
 
@@ -318,6 +427,7 @@ impl ParserHost {
             get_rust_query(),
             file_path,
             RUST_PATTERNS,
+            forge::RUST_SKIP_KINDS,
         )
     }
 
@@ -335,14 +445,17 @@ impl ParserHost {
             get_js_query(),
             file_path,
             JS_PATTERNS,
+            forge::JS_SKIP_KINDS,
         )
     }
 
     /// Extracts `function_definition`, `class_specifier`, and `struct_specifier` entities
     /// from a C++ source buffer.
     ///
-    /// Only captures simple (non-template, non-pointer-returning) functions. `protected_by`
-    /// is `None` for all returned entities; protection is assigned by later pipeline stages.
+    /// Only captures simple (non-template, non-pointer-returning) functions. Methods are
+    /// qualified with their class (`Foo.bar`), whether defined in the class body or out of
+    /// line as `Foo::bar`; overloads get a `#n` suffix. `protected_by` is `None` for all
+    /// returned entities; protection is assigned by later pipeline stages.
     pub fn extract_cpp_entities(
         source: &[u8],
         file_path: &str,
@@ -353,15 +466,17 @@ impl ParserHost {
             get_cpp_query(),
             file_path,
             CPP_PATTERNS,
+            forge::CPP_SKIP_KINDS,
         )
     }
 
-    /// Internal implementation shared by `dissect()` and `dissect_bytes()`.
+    /// Internal implementation shared by `dissect()`, `dissect_python()` and
+    /// `dissect_bytes()`.
     fn dissect_impl(
         &mut self,
         source: &[u8],
         file_path: &str,
-    ) -> Result<Vec<Entity>, AnatomistError> {
+    ) -> Result<(Vec<Entity>, tree_sitter::Tree), AnatomistError> {
         // Parse source into CST
         let tree = self.parser.parse(source, None).ok_or_else(|| {
             AnatomistError::ParseFailure("Tree-sitter parse returned None".to_string())
@@ -391,6 +506,7 @@ impl ParserHost {
 
         // Pass 2: Extract entities, skipping duplicates
         let mut entities = Vec::new();
+        let mut assignments = Vec::new();
         cursor = QueryCursor::new(); // Reset cursor
         let mut matches = cursor.matches(query, root, source);
         while let Some(m) = matches.next() {
@@ -405,6 +521,19 @@ impl ParserHost {
                 }
             }
 
+            // Definitions local to a function are part of its body: the
+            // enclosing entity's range and fingerprint already cover them.
+            if let Some(def_capture) = m.captures.iter().find(|c| {
+                matches!(
+                    c.node.kind(),
+                    "function_definition" | "class_definition" | "decorated_definition"
+                )
+            }) {
+                if is_nested_in_function(&def_capture.node) {
+                    continue;
+                }
+            }
+
             match pattern_idx {
                 PATTERN_FN | PATTERN_CLASS | PATTERN_DECORATED => {
                     if let Some(entity) =
@@ -413,14 +542,125 @@ impl ParserHost {
                         entities.push(entity);
                     }
                 }
-                PATTERN_ASSIGNMENT => {
-                    // Module-level assignments (future work: extract __all__, etc.)
+                PATTERN_ASSIGNMENT | PATTERN_TYPE_ALIAS => {
+                    if let Some(entity) =
+                        self.extract_assignment(source, m, query, file_path, pattern_idx)?
+                    {
+                        assignments.push(entity);
+                    }
                 }
                 _ => {}
             }
         }
 
-        Ok(entities)
+        // A name bound more than once at module level (or also defined by a
+        // `def`/`class`) has no single statement to report or delete.
+        let mut bindings: HashMap<&str, usize> = HashMap::new();
+        for name in entities.iter().chain(&assignments).map(|e| e.name.as_str()) {
+            *bindings.entry(name).or_default() += 1;
+        }
+        let unique: HashSet<String> = bindings
+            .into_iter()
+            .filter(|(_, n)| *n == 1)
+            .map(|(name, _)| name.to_string())
+            .collect();
+        entities.extend(assignments.into_iter().filter(|e| unique.contains(&e.name)));
+        entities.sort_by_key(|e| e.start_byte);
+
+        // Conditional definitions (`if WIN: def f()` / `else: def f()`) share a
+        // qualified name; later ones get a `#n` suffix so symbol ids stay unique.
+        disambiguate_qualified_names(&mut entities);
+
+        Ok((entities, tree))
+    }
+
+    /// Extracts a module-level assignment target or type alias from a query match.
+    ///
+    /// Only statements directly in the module body qualify; assignments in
+    /// functions, classes, and compound statements are skipped, as is
+    /// `__all__`. Each target of `A = B = 1` becomes its own entity, and the
+    /// range of every entity covers the whole statement. `type X = ...` and
+    /// `X: TypeAlias = ...` yield [`EntityType::TypeAlias`].
+    fn extract_assignment(
+        &self,
+        source: &[u8],
+        m: &tree_sitter::QueryMatch<'_, '_>,
+        query: &Query,
+        file_path: &str,
+        pattern_idx: usize,
+    ) -> Result<Option<Entity>, AnatomistError> {
+        let capture_names = query.capture_names();
+        let (name_cap, stmt_cap) = if pattern_idx == PATTERN_TYPE_ALIAS {
+            ("alias.name", "alias.stmt")
+        } else {
+            ("assign.name", "assign.stmt")
+        };
+        let (Some(name_node), Some(stmt_node)) = (
+            m.captures
+                .iter()
+                .find(|c| capture_names[c.index as usize] == name_cap),
+            m.captures
+                .iter()
+                .find(|c| capture_names[c.index as usize] == stmt_cap),
+        ) else {
+            return Ok(None);
+        };
+
+        let entity_type = match stmt_node.node.child_by_field_name("type") {
+            _ if pattern_idx == PATTERN_TYPE_ALIAS => EntityType::TypeAlias,
+            Some(annotation) if is_type_alias_annotation(&source[annotation.byte_range()]) => {
+                EntityType::TypeAlias
+            }
+            _ => EntityType::Assignment,
+        };
+
+        // Chained targets nest as `right:` children: climb to the statement.
+        let mut statement = stmt_node.node;
+        while statement.kind() == "assignment" {
+            match statement.parent() {
+                Some(parent) => statement = parent,
+                None => break,
+            }
+        }
+        let at_module_level = matches!(
+            statement.kind(),
+            "expression_statement" | "type_alias_statement"
+        ) && statement.parent().is_some_and(|p| p.kind() == "module");
+        if !at_module_level {
+            return Ok(None);
+        }
+
+        let name = std::str::from_utf8(&source[name_node.node.byte_range()])
+            .map_err(|_| AnatomistError::ParseFailure("Non-UTF-8 identifier".to_string()))?
+            .to_string();
+        if name == "__all__" {
+            return Ok(None);
+        }
+
+        let (protected_by, protection_detail) =
+            self.apply_heuristics(source, &statement, file_path);
+
+        let (start_byte, end_byte, start_line, end_line) = node_span(&statement)?;
+        let entity = Entity {
+            qualified_name: name.clone(),
+            module_path: String::new(),
+            name,
+            entity_type,
+            file_path: file_path.to_string(),
+            start_byte,
+            end_byte,
+            start_line,
+            end_line,
+            parent_class: None,
+            base_classes: vec![],
+            decorators: vec![],
+            protected_by,
+            protection_detail,
+            structural_hash: None,
+            structural_nodes: 0,
+        };
+        entity.validate(source.len())?;
+        Ok(Some(entity))
     }
 
     /// Extracts a function or class entity from a query match.
@@ -538,19 +778,14 @@ impl ParserHost {
             };
 
         // Byte range and line numbers
-        let start_byte = primary_node.start_byte() as u32;
-        let end_byte = primary_node.end_byte() as u32;
-        let start_line = (primary_node.start_position().row + 1) as u32; // tree-sitter uses 0-based rows
-        let end_line = (primary_node.end_position().row + 1) as u32;
+        let (start_byte, end_byte, start_line, end_line) = node_span(&primary_node)?;
 
         // Apply heuristics
-        let protected_by = self
-            .heuristics
-            .iter()
-            .find_map(|h| h.apply(source, &primary_node, file_path));
+        let (protected_by, protection_detail) =
+            self.apply_heuristics(source, &primary_node, file_path);
 
-        // Compute structural hash for functions/methods (alpha-normalized BLAKE3 over body block).
-        let structural_hash = match entity_type {
+        // Compute structural fingerprint for functions/methods (alpha-normalized BLAKE3 over body block).
+        let fingerprint = match entity_type {
             EntityType::FunctionDefinition
             | EntityType::AsyncFunctionDefinition
             | EntityType::MethodDefinition => {
@@ -562,16 +797,15 @@ impl ParserHost {
                 } else {
                     primary_node
                 };
-                func_node
-                    .child_by_field_name("body")
-                    .map(|body| compute_structural_hash(body, source))
+                compute_fingerprint(func_node, source)
             }
             _ => None,
         };
 
-        Ok(Some(Entity {
+        let entity = Entity {
             name,
             qualified_name,
+            module_path: String::new(),
             entity_type,
             file_path: file_path.to_string(),
             start_byte,
@@ -582,8 +816,12 @@ impl ParserHost {
             decorators,
             base_classes,
             protected_by,
-            structural_hash,
-        }))
+            protection_detail,
+            structural_hash: fingerprint.map(|f| f.hash),
+            structural_nodes: fingerprint.map_or(0, |f| f.node_count),
+        };
+        entity.validate(source.len())?;
+        Ok(Some(entity))
     }
 
     /// Determines the specific entity type based on node kind and context.
@@ -631,14 +869,15 @@ impl ParserHost {
         source: &[u8],
         file_path: &str,
     ) -> Result<Vec<Entity>, AnatomistError> {
-        self.dissect_impl(source, file_path)
+        Ok(self.dissect_impl(source, file_path)?.0)
     }
 }
 
 /// Generic entity extractor for non-Python languages.
 ///
 /// Parses `source` with `language`, runs `query`, and maps pattern indices to entity
-/// metadata via `patterns: &[(def_cap, name_cap, entity_type)]`.
+/// metadata via `patterns: &[(def_cap, name_cap, entity_type)]`. Functions and methods
+/// are fingerprinted with the grammar's alpha-normalization `skip_kinds`.
 ///
 /// Creates a local `Parser` per call — avoids mutating the host's Python parser state.
 fn extract_named_entities(
@@ -647,6 +886,7 @@ fn extract_named_entities(
     query: &Query,
     file_path: &str,
     patterns: &[(&str, &str, EntityType)],
+    skip_kinds: &[&str],
 ) -> Result<Vec<Entity>, AnatomistError> {
     let mut parser = Parser::new();
     parser
@@ -689,27 +929,118 @@ fn extract_named_entities(
             Ok(n) => n.to_string(),
             Err(_) => continue,
         };
+        // C++ methods: `Foo::bar` names its class; in-class definitions sit in its body.
+        let parent_class = m
+            .captures
+            .iter()
+            .find(|c| capture_names[c.index as usize] == "method.scope")
+            .map(|c| c.node)
+            .or_else(|| enclosing_class(def_node))
+            .and_then(|n| n.utf8_text(source).ok())
+            .map(str::to_string);
+        let qualified_name = match &parent_class {
+            Some(class) => format!("{}.{}", class, name),
+            None => name.clone(),
+        };
+        let fingerprint = match entity_type {
+            EntityType::FunctionDefinition | EntityType::MethodDefinition => {
+                compute_fingerprint_with(def_node, source, skip_kinds)
+            }
+            _ => None,
+        };
 
-        entities.push(Entity {
-            name: name.clone(),
-            qualified_name: name,
+        let (start_byte, end_byte, start_line, end_line) = node_span(&def_node)?;
+        let entity = Entity {
+            name,
+            qualified_name,
+            module_path: String::new(),
             entity_type,
             file_path: file_path.to_string(),
-            start_byte: def_node.start_byte() as u32,
-            end_byte: def_node.end_byte() as u32,
-            start_line: (def_node.start_position().row + 1) as u32,
-            end_line: (def_node.end_position().row + 1) as u32,
-            parent_class: None,
+            start_byte,
+            end_byte,
+            start_line,
+            end_line,
+            parent_class,
             base_classes: vec![],
             decorators: vec![],
             protected_by: None,
-            structural_hash: None,
-        });
+            protection_detail: None,
+            structural_hash: fingerprint.map(|f| f.hash),
+            structural_nodes: fingerprint.map_or(0, |f| f.node_count),
+        };
+        entity.validate(source.len())?;
+        entities.push(entity);
     }
 
+    // Overloads and same-named methods of one class share a qualified name.
+    entities.sort_by_key(|e| e.start_byte);
+    disambiguate_qualified_names(&mut entities);
+
     Ok(entities)
 }
 
+/// Name node of the C++ class/struct or JS/TS class whose body directly contains `def_node`.
+fn enclosing_class(def_node: tree_sitter::Node<'_>) -> Option<tree_sitter::Node<'_>> {
+    let body = def_node
+        .parent()
+        .filter(|p| matches!(p.kind(), "field_declaration_list" | "class_body"))?;
+    body.parent()
+        .filter(|p| {
+            matches!(
+                p.kind(),
+                "class_specifier"
+                    | "struct_specifier"
+                    | "class_declaration"
+                    | "abstract_class_declaration"
+            )
+        })?
+        .child_by_field_name("name")
+}
+
+/// Appends `#n` to the n-th (n > 1) entity sharing a qualified name, in order.
+fn disambiguate_qualified_names(entities: &mut [Entity]) {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    for entity in entities {
+        let count = seen.entry(entity.qualified_name.clone()).or_default();
+        *count += 1;
+        if *count > 1 {
+            entity.qualified_name = format!("{}#{}", entity.qualified_name, count);
+        }
+    }
+}
+
+/// `(start_byte, end_byte, start_line, end_line)` of `node` as [`Entity`]
+/// stores them, lines 1-based.
+///
+/// # Errors
+/// `ByteRangeOverflow` when an offset does not fit in `u32`.
+fn node_span(node: &tree_sitter::Node) -> Result<(u32, u32, u32, u32), AnatomistError> {
+    let narrow = |value: usize| u32::try_from(value).map_err(|_| AnatomistError::ByteRangeOverflow);
+    Ok((
+        narrow(node.start_byte())?,
+        narrow(node.end_byte())?,
+        narrow(node.start_position().row + 1)?,
+        narrow(node.end_position().row + 1)?,
+    ))
+}
+
+/// `TypeAlias`, `typing.TypeAlias`, or `typing_extensions.TypeAlias`.
+fn is_type_alias_annotation(annotation: &[u8]) -> bool {
+    annotation == b"TypeAlias" || annotation.ends_with(b".TypeAlias")
+}
+
+/// Returns `true` if `node` lies inside a `function_definition`.
+fn is_nested_in_function(node: &tree_sitter::Node) -> bool {
+    let mut current = node.parent();
+    while let Some(parent) = current {
+        if parent.kind() == "function_definition" {
+            return true;
+        }
+        current = parent.parent();
+    }
+    false
+}
+
 /// Finds the enclosing class name for a given node by walking up the tree.
 ///
 /// # Returns
@@ -803,6 +1134,113 @@ mod tests {
         assert_eq!(entities[0].start_byte, 0);
     }
 
+    #[test]
+    fn test_module_level_assignments() {
+        let mut host = ParserHost::new().unwrap();
+        let source = b"import logging\n\nDEFAULT_TIMEOUT = 30\nlogger = logging.getLogger(__name__)\n__all__ = ['run']\nx: int = 5\nA = B = 1\n\ndef run():\n    local = 1\n\nclass C:\n    attr = 2\n\nif DEBUG:\n    nested = 3\n";
+        let entities = host.dissect_bytes(source, "test.py").unwrap();
+
+        let names: Vec<&str> = entities.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["DEFAULT_TIMEOUT", "logger", "x", "A", "B", "run", "C"]
+        );
+
+        let logger = &entities[1];
+        assert_eq!(logger.entity_type, EntityType::Assignment);
+        assert_eq!(logger.qualified_name, "logger");
+        assert_eq!((logger.start_line, logger.end_line), (4, 4));
+        assert_eq!(
+            &source[logger.start_byte as usize..logger.end_byte as usize],
+            b"logger = logging.getLogger(__name__)"
+        );
+        assert!(logger.structural_hash.is_none());
+
+        let annotated = &entities[2];
+        assert_eq!(
+            &source[annotated.start_byte as usize..annotated.end_byte as usize],
+            b"x: int = 5"
+        );
+
+        // Both targets of a chained assignment cover the whole statement.
+        let (a, b) = (&entities[3], &entities[4]);
+        assert_eq!((a.start_byte, a.end_byte), (b.start_byte, b.end_byte));
+        assert_eq!(
+            &source[a.start_byte as usize..a.end_byte as usize],
+            b"A = B = 1"
+        );
+        assert_eq!(b.entity_type, EntityType::Assignment);
+    }
+
+    #[test]
+    fn test_rebound_names_not_extracted_as_assignments() {
+        let mut host = ParserHost::new().unwrap();
+        let source =
+            b"MODE = 'fast'\nMODE = 'safe'\n\ndef handler():\n    pass\n\nhandler = wrap(handler)\n";
+        let entities = host.dissect_bytes(source, "test.py").unwrap();
+
+        assert_eq!(entities.len(), 1);
+        assert_eq!(entities[0].name, "handler");
+        assert_eq!(entities[0].entity_type, EntityType::FunctionDefinition);
+    }
+
+    #[test]
+    fn test_type_aliases() {
+        let mut host = ParserHost::new().unwrap();
+        let source = b"from typing import TypeAlias\nimport typing\n\ntype Vector[T] = list[T]\ntype Pair = tuple[int, int]\nUserId: TypeAlias = int\nOrderId: typing.TypeAlias = 'int'\nlimit: int = 5\n\nclass C:\n    type Inner = int\n";
+        let entities = host.dissect_bytes(source, "test.py").unwrap();
+
+        let kinds: Vec<(&str, EntityType)> = entities
+            .iter()
+            .map(|e| (e.name.as_str(), e.entity_type))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ("Vector", EntityType::TypeAlias),
+                ("Pair", EntityType::TypeAlias),
+                ("UserId", EntityType::TypeAlias),
+                ("OrderId", EntityType::TypeAlias),
+                ("limit", EntityType::Assignment),
+                ("C", EntityType::ClassDefinition),
+            ]
+        );
+        let vector = &entities[0];
+        assert_eq!(
+            &source[vector.start_byte as usize..vector.end_byte as usize],
+            b"type Vector[T] = list[T]"
+        );
+        assert_eq!(
+            (vector.start_line, vector.qualified_name.as_str()),
+            (4, "Vector")
+        );
+    }
+
+    #[test]
+    fn test_nested_definitions_not_extracted() {
+        let mut host = ParserHost::new().unwrap();
+        let source = b"def outer():\n    def middle():\n        @wraps(middle)\n        def inner():\n            return 1\n        return inner\n    class Local:\n        def method(self):\n            pass\n    return middle, Local\n\nclass Service:\n    def run(self):\n        def step():\n            pass\n        return step\n";
+        let entities = host.dissect_bytes(source, "test.py").unwrap();
+
+        let names: Vec<&str> = entities.iter().map(|e| e.qualified_name.as_str()).collect();
+        assert_eq!(names, vec!["outer", "Service", "Service.run"]);
+        // The enclosing function's range covers its local definitions.
+        assert!(source[..entities[0].end_byte as usize].ends_with(b"return middle, Local"));
+    }
+
+    #[test]
+    fn test_conditional_definitions_get_unique_names() {
+        let mut host = ParserHost::new().unwrap();
+        let source = b"import sys\n\nif sys.platform == \"win32\":\n    def open_port():\n        return 1\nelse:\n    def open_port():\n        return 2\n\nhandler = lambda evt: open_port()\n";
+        let entities = host.dissect_bytes(source, "ports.py").unwrap();
+
+        let names: Vec<&str> = entities.iter().map(|e| e.qualified_name.as_str()).collect();
+        assert_eq!(names, vec!["open_port", "open_port#2", "handler"]);
+        assert_eq!(entities[1].name, "open_port");
+        assert_ne!(entities[0].start_byte, entities[1].start_byte);
+        assert_eq!(entities[2].entity_type, EntityType::Assignment);
+    }
+
     #[test]
     fn test_empty_file() {
         let mut host = ParserHost::new().unwrap();
@@ -823,6 +1261,23 @@ mod tests {
         assert!(!entities.is_empty());
     }
 
+    #[test]
+    fn test_recovered_definitions_without_a_name_are_skipped() {
+        let mut host = ParserHost::new().unwrap();
+        for source in [
+            &b"def ():\n    pass\n\ndef valid():\n    pass\n"[..],
+            b"class :\n    pass\n\ndef valid():\n    pass\n",
+            b"def\n\ndef valid():\n    pass\n",
+        ] {
+            let entities = host.dissect_bytes(source, "broken.py").unwrap();
+            let names: Vec<&str> = entities.iter().map(|e| e.name.as_str()).collect();
+            assert!(names.contains(&"valid"), "{:?}", names);
+            for entity in &entities {
+                entity.validate(source.len()).unwrap();
+            }
+        }
+    }
+
     #[test]
     fn test_pytest_fixture_decorator() {
         let mut host = ParserHost::new().unwrap();
@@ -896,4 +1351,75 @@ mod tests {
         // u32 byte ranges must fit without overflow
         assert!(fn_entity.end_byte > fn_entity.start_byte);
     }
+
+    #[test]
+    fn test_cpp_method_extraction() {
+        let source = b"class Foo {\n    int get() { return 1; }\n    void set(int v);\n};\nvoid Foo::set(int v) {}\nvoid Foo::set(double v) {}\n";
+        let entities = ParserHost::extract_cpp_entities(source, "foo.cpp").unwrap();
+
+        let qnames: Vec<&str> = entities.iter().map(|e| e.qualified_name.as_str()).collect();
+        assert_eq!(qnames, vec!["Foo", "Foo.get", "Foo.set", "Foo.set#2"]);
+        let set = entities
+            .iter()
+            .find(|e| e.qualified_name == "Foo.set")
+            .unwrap();
+        assert_eq!(set.entity_type, EntityType::MethodDefinition);
+        assert_eq!(set.name, "set");
+        assert_eq!(set.parent_class.as_deref(), Some("Foo"));
+    }
+
+    #[test]
+    fn test_ts_class_methods_qualified() {
+        let source = b"export class Store {\n  load(): void {}\n}\nclass Cache {\n  load() {}\n}\nexport function open(): Store { return new Store(); }\n";
+        let entities = ParserHost::extract_ts_entities(source, "store.ts", false).unwrap();
+        let qnames: Vec<&str> = entities.iter().map(|e| e.qualified_name.as_str()).collect();
+        assert_eq!(
+            qnames,
+            vec!["Store", "Store.load", "Cache", "Cache.load", "open"]
+        );
+    }
+
+    #[test]
+    fn test_rust_structural_hash_alpha_normalized() {
+        let source = b"fn total(items: &[u32]) -> u32 {\n    let mut sum = 0;\n    for item in items {\n        sum += item;\n    }\n    sum\n}\n\nfn add_all(values: &[u32]) -> u32 {\n    let mut acc = 0;\n    for v in values {\n        acc += v;\n    }\n    acc\n}\n\nfn largest(values: &[u32]) -> u32 {\n    let mut best = 0;\n    for v in values {\n        if *v > best {\n            best = *v;\n        }\n    }\n    best\n}\n";
+        let entities = ParserHost::extract_rust_entities(source, "lib.rs").unwrap();
+        let hash = |name: &str| {
+            let entity = entities.iter().find(|e| e.name == name).unwrap();
+            assert!(entity.structural_nodes > 0);
+            entity.structural_hash.unwrap()
+        };
+
+        assert_eq!(hash("total"), hash("add_all"));
+        assert_ne!(hash("total"), hash("largest"));
+    }
+
+    #[test]
+    fn test_js_and_cpp_structural_hashes() {
+        let js = b"function a(x) { return x.map(v => v * 2); }\nfunction b(list) { return list.map(item => item * 2); }\nclass K { m(y) { return y + 1; } }\n";
+        let entities = ParserHost::extract_js_entities(js, "a.js").unwrap();
+        let hash = |name: &str| {
+            entities
+                .iter()
+                .find(|e| e.name == name)
+                .unwrap()
+                .structural_hash
+        };
+        assert!(hash("a").is_some());
+        assert_eq!(hash("a"), hash("b"));
+        assert_ne!(hash("a"), hash("m"));
+        assert_eq!(hash("K"), None);
+
+        let cpp = b"int f(int a) { return a * 2; }\nint g(int b) { return b * 2; }\nint h(int c, int d) { return c * 2; }\n";
+        let entities = ParserHost::extract_cpp_entities(cpp, "a.cpp").unwrap();
+        let hash = |name: &str| {
+            entities
+                .iter()
+                .find(|e| e.name == name)
+                .unwrap()
+                .structural_hash
+        };
+        assert_eq!(hash("f"), hash("g"));
+        // Parameters come from the `function_declarator`.
+        assert_ne!(hash("f"), hash("h"));
+    }
 }
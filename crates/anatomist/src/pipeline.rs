@@ -1,28 +1,53 @@
 //! 6-stage dead symbol detection pipeline ("The Funnel of Truth").
 //!
 //! Stages:
-//! - **Stage 0** — Directory filter: skip files in protected directories.
+//! - **Stage 0** — Directory filter: skip files in protected directories,
+//!   unless they carry `# janitor: analyze`. Files with `# janitor: skip-file`
+//!   leave the funnel before it, whatever else applies (see [`crate::pragma`]).
 //! - **Stage 1** — Reference graph: symbols with incoming edges survive.
 //! - **Stage 2+4** — Wisdom + PackageExport: single mmap pass per file via [`wisdom`].
 //! - **Stage 3** — Library mode: protect public symbols when `--library` is set.
-//! - **Stage 5** — Grep shield: Aho-Corasick scan of non-`.py` files via [`scan`].
+//! - **Stage 5** — Grep shield: Aho-Corasick scan of non-`.py` files via [`scan`],
+//!   then of the string literals of `.py` files (`Protection::ConfigReference`).
+//! - **Stage 5.5** — Test fingerprint: pytest-collected names and test-only helpers
+//!   ([`ScanOptions::test_evidence`]).
+//! - **Stage 6** — Runtime liveness: symbols seen in production logs ([`ScanOptions::live_ids`]).
 //!
 //! Only symbols that pass through all five stages without acquiring a `protected_by`
 //! reason are reported as dead.
 
-use crate::graph::build_reference_graph;
+use crate::cache::EntityCache;
+use crate::di::DiRules;
+use crate::graph::{
+    build_reference_graph_observed, file_key, EdgeInfo, EdgeKind, FileLinks, GraphOptions,
+    ReferenceGraph,
+};
+use crate::imports::is_script_path;
 use crate::parser::ParserHost;
-use crate::{scan, wisdom, Entity, Protection};
-use common::registry::symbol_hash;
+use crate::pragma::FilePragmas;
+use crate::progress::{self, Stage};
+use crate::unused_imports::UnusedImport;
+use crate::{scan, wisdom, Entity, EntityType, Protection, ProtectionDetail};
+use common::config::JanitorConfig;
+use common::diagnostics::{codes, Diagnostic, Diagnostics};
+use common::registry::{symbol_hash, SymbolEntry, SymbolRegistry};
+use common::wisdom::WisdomRegistry;
+use petgraph::graph::{DiGraph, EdgeReference, NodeIndex};
+use petgraph::visit::EdgeRef;
 use petgraph::Direction;
-use std::collections::{HashMap, HashSet};
-use std::path::Path;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+pub use crate::progress::PipelineEvent;
 
 /// Results of a full pipeline run.
-#[derive(Debug, Default)]
+#[derive(Debug, Default, serde::Serialize)]
 pub struct ScanResult {
     /// Symbols with no protection and no references — candidates for deletion.
     pub dead: Vec<Entity>,
+    /// Audit trail for each dead symbol; `evidence[i]` explains `dead[i]`.
+    pub evidence: Vec<DeadEvidence>,
     /// Symbols that survived at least one stage (with `protected_by` set).
     pub protected: Vec<Entity>,
     /// Total entities examined.
@@ -30,28 +55,104 @@ pub struct ScanResult {
     /// Per-stage survivor counts: `stage_counts[n]` = entities protected at stage n.
     /// Index 0 = directory, 1 = reference, 2 = wisdom/pkg-export, 3 = library, 5 = grep.
     pub stage_counts: [usize; 6],
-    /// Python files with zero incoming file-level dependencies (orphan files).
+    /// Python and JS/TS files with zero incoming file-level dependencies (orphan files).
     /// Entry points (`main.py`, `wsgi.py`, etc.) and `__init__.py` are excluded.
     pub orphan_files: Vec<String>,
+    /// Package directories nothing outside them depends on (see
+    /// [`ReferenceGraph::find_orphan_packages_with`]), minus packages holding a
+    /// symbol kept alive by evidence from outside the graph.
+    pub orphan_packages: Vec<String>,
+    /// Size in bytes of each of [`Self::orphan_packages`], counting the files
+    /// the project walk does not exclude.
+    #[serde(skip)]
+    pub orphan_package_bytes: BTreeMap<String, u64>,
+    /// Python files whose entities were loaded from `.janitor/cache/`.
+    pub cache_hits: usize,
+    /// Python files dissected during this run.
+    pub files_parsed: usize,
+    /// BLAKE3 hash of every file holding a dead symbol, of the bytes the scan
+    /// parsed (see [`ReferenceGraph::file_hashes`]). `janitor clean` compares it
+    /// against the file on disk to detect byte offsets gone stale between scan
+    /// and deletion.
+    #[serde(skip)]
+    pub file_hashes: HashMap<String, [u8; 32]>,
+    /// Symbols that survived stages 0-5 as dead but were observed in runtime
+    /// logs (see [`ScanOptions::live_ids`]).
+    pub runtime_rescued: usize,
+    /// References between dead symbols, keyed by [`symbol_hash`] of
+    /// [`Entity::symbol_id`]. Input to `oracle::deletion_plan`.
+    #[serde(skip)]
+    pub dead_graph: DiGraph<u64, ()>,
+    /// Symbols referenced only from protected (test/example) directories
+    /// (Stage 1) or collected test files (Stage 5.5): not dead, but only kept
+    /// alive by the test suite.
+    pub test_only: Vec<Entity>,
+    /// Import bindings no identifier in their module reads, outside protected directories.
+    pub unused_imports: Vec<UnusedImport>,
+    /// Symbols that survived every stage but live under a low-confidence
+    /// directory (`scripts/`, `bin/`; see [`LOW_CONFIDENCE_DIRS`]). Probably
+    /// dead, but run by hand rather than imported, so not on the kill list.
+    pub low_confidence: Vec<Entity>,
+    /// Files that were transcoded, skipped, parsed with errors or could not be
+    /// searched, in the order the stages met them.
+    pub diagnostics: Vec<Diagnostic>,
+    /// Files marked `# janitor: skip-file`, sorted. None of their symbols is
+    /// reported anywhere else in the result.
+    pub skipped_files: Vec<String>,
+    /// Totals of each `[workspace]` root, in configuration order; empty in
+    /// single-root mode.
+    pub roots: Vec<RootReport>,
+}
+
+/// The share of a [`ScanResult`] under one workspace root.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize)]
+pub struct RootReport {
+    /// Root directory relative to the project root, forward slashes.
+    pub root: String,
+    /// Entities examined under the root.
+    pub total: usize,
+    pub dead: usize,
+    /// Bytes spanned by the dead symbols.
+    pub dead_bytes: u64,
+    pub orphan_files: usize,
+}
+
+/// Why a symbol was judged dead: the facts each stage saw when it let the symbol through.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct DeadEvidence {
+    /// `Entity::symbol_id()` of the dead symbol.
+    pub symbol_id: String,
+    /// Incoming edges in the reference graph that Stage 1 counted.
+    pub incoming_edges: usize,
+    /// Incoming edges Stage 1 saw but did not count: self-references, and
+    /// `if TYPE_CHECKING:` references under `type_checking_keeps_alive = false`.
+    pub ignored_edges: usize,
+    /// `true` when the name was fed to the grep shield automaton (Stage 5).
+    pub grep_searched: bool,
+    /// Stages the symbol passed through without acquiring protection, in order.
+    pub stages_passed: Vec<&'static str>,
+    /// `true` when the containing file is also reported as an orphan.
+    pub orphan_file: bool,
 }
 
 /// Directory name segments that indicate protected/test/example code (Stage 0).
+/// Nothing in them is analyzed; every symbol is protected as `Directory`.
 const PROTECTED_DIRS: &[&str] = &[
     "tests",
     "test",
     "examples",
     "example",
-    "docs_src",
-    "docs",
     "sandbox",
-    "bin",
-    "scripts",
     "tutorial",
-    "benchmarks",
     "fixtures",
     "migrations",
 ];
 
+/// Directory name segments whose code is run by hand rather than imported.
+/// Their symbols go through every stage, but the dead ones are reported in
+/// [`ScanResult::low_confidence`] instead of on the kill list.
+pub const LOW_CONFIDENCE_DIRS: &[&str] = &["scripts", "bin", "docs_src", "docs", "benchmarks"];
+
 /// Runs the full 6-stage dead symbol detection pipeline against a project directory.
 ///
 /// # Arguments
@@ -69,61 +170,385 @@ pub fn run(
     host: &mut ParserHost,
     library_mode: bool,
 ) -> anyhow::Result<ScanResult> {
+    let options = ScanOptions {
+        library_mode,
+        ..Default::default()
+    };
+    run_with_options(project_root, host, &options)
+}
+
+/// Optional pipeline behaviour for [`run_with_options`].
+#[derive(Debug, Clone, Default)]
+pub struct ScanOptions {
+    /// Stage 3: protect all public top-level symbols.
+    pub library_mode: bool,
+    /// Serve unchanged files from the incremental entity cache in `.janitor/cache/`
+    /// (see [`crate::cache`]). Hits and re-parses are reported in
+    /// [`ScanResult::cache_hits`] and [`ScanResult::files_parsed`].
+    pub use_cache: bool,
+    /// Disable the conservative star-import linking (see [`GraphOptions::strict_star_imports`]).
+    pub strict_star_imports: bool,
+    /// Project overrides from `.janitor.toml`; the default reproduces the built-in tables.
+    pub config: JanitorConfig,
+    /// Stage 6: `symbol_hash(symbol_id)` of every symbol seen in runtime logs
+    /// (see [`liveness_registry`]). Matching symbols are protected with
+    /// [`Protection::RuntimeLiveness`] instead of being reported dead.
+    pub live_ids: HashSet<u64>,
+    /// Stage 5.5: the project's pytest-collected test surface, when
+    /// `--use-test-fingerprint` is set.
+    pub test_evidence: Option<TestEvidence>,
+    /// Stage 2: baked framework rules (`wisdom.rkyv`) evaluated after the
+    /// built-in ones.
+    pub wisdom: Option<Arc<WisdomRegistry>>,
+    /// Problems met while gathering these options (configuration, runtime and
+    /// test evidence). They lead [`ScanResult::diagnostics`].
+    pub diagnostics: Diagnostics,
+    /// Report only on these files (relative paths resolve against the project
+    /// root). The whole project is still indexed and linked, so references
+    /// from outside the scope keep its symbols alive; `total`, `stage_counts`,
+    /// orphans, unused imports and per-file diagnostics cover the scope only,
+    /// and no orphan packages are reported. `None` reports on every file.
+    pub scope: Option<Vec<PathBuf>>,
+}
+
+/// Test-suite facts for Stage 5.5, gathered by `pytest --collect-only`.
+#[derive(Debug, Clone, Default)]
+pub struct TestEvidence {
+    /// Class and function segments of the collected test node IDs.
+    pub names: HashSet<String>,
+    /// Collected test files.
+    pub files: Vec<PathBuf>,
+}
+
+/// Same as [`run`], with explicit [`ScanOptions`].
+pub fn run_with_options(
+    project_root: &Path,
+    host: &mut ParserHost,
+    options: &ScanOptions,
+) -> anyhow::Result<ScanResult> {
+    run_observed(project_root, host, options, &mut progress::silent)
+}
+
+/// Same as [`run_with_options`], reporting progress to `progress` as files
+/// are parsed and stages complete (see [`crate::progress`]).
+pub fn run_observed(
+    project_root: &Path,
+    host: &mut ParserHost,
+    options: &ScanOptions,
+    progress: &mut dyn FnMut(PipelineEvent),
+) -> anyhow::Result<ScanResult> {
+    Ok(run_with_graph(project_root, host, options, progress)?.0)
+}
+
+/// Runs the pipeline, also returning the reference graph it was built on.
+fn run_with_graph(
+    project_root: &Path,
+    host: &mut ParserHost,
+    options: &ScanOptions,
+    progress: &mut dyn FnMut(PipelineEvent),
+) -> anyhow::Result<(ScanResult, ReferenceGraph)> {
+    let ref_graph = build_graph(project_root, host, options, progress)?;
+    classify_graph(project_root, options, ref_graph, progress)
+}
+
+/// Builds the cross-file reference graph `options` describe (Pass 1: index,
+/// Pass 2: link edges), for [`run_on_built_graph`] or [`explain_on_graph`].
+///
+/// Splitting the build from the stages lets callers derive options from the
+/// parsed project, e.g. [`ScanOptions::live_ids`] from [`liveness_registry`],
+/// without parsing it twice.
+pub fn build_graph(
+    project_root: &Path,
+    host: &mut ParserHost,
+    options: &ScanOptions,
+    progress: &mut dyn FnMut(PipelineEvent),
+) -> anyhow::Result<ReferenceGraph> {
     let root = dunce::canonicalize(project_root)?;
+    let mut cache = if options.use_cache {
+        Some(EntityCache::open(&root, host)?)
+    } else {
+        None
+    };
+    Ok(build_reference_graph_observed(
+        &root,
+        host,
+        &graph_options(options),
+        cache.as_mut(),
+        progress,
+    )?)
+}
+
+/// Runs stages 0-6 on a graph from [`build_graph`], consuming it.
+pub fn run_on_built_graph(
+    project_root: &Path,
+    options: &ScanOptions,
+    ref_graph: ReferenceGraph,
+    progress: &mut dyn FnMut(PipelineEvent),
+) -> anyhow::Result<ScanResult> {
+    Ok(classify_graph(project_root, options, ref_graph, progress)?.0)
+}
+
+/// Stages 0-6 on an owned graph, handing the graph back for [`explain_on_graph`].
+fn classify_graph(
+    project_root: &Path,
+    options: &ScanOptions,
+    mut ref_graph: ReferenceGraph,
+    progress: &mut dyn FnMut(PipelineEvent),
+) -> anyhow::Result<(ScanResult, ReferenceGraph)> {
+    // The stages consume the entities; later steps only need the graph and its
+    // registry, so hand them over instead of keeping a second copy alive.
+    let entities = std::mem::take(&mut ref_graph.entities);
+    let result = classify(project_root, options, &ref_graph, entities, progress)?;
+    Ok((result, ref_graph))
+}
+
+/// The reference-graph settings `options` imply.
+pub fn graph_options(options: &ScanOptions) -> GraphOptions {
+    GraphOptions {
+        strict_star_imports: options.strict_star_imports,
+        source_roots: options.config.source_roots.clone(),
+        workspace_roots: options.config.workspace.roots.clone(),
+        di_rules: DiRules::from_config(&options.config.di),
+    }
+}
+
+/// Runs stages 0-6 on a reference graph that is already built, e.g. one kept
+/// up to date by [`crate::incremental::IncrementalGraph`].
+///
+/// The graph is borrowed, so its entities are copied; [`run_on_built_graph`]
+/// moves them out of a graph it owns.
+pub fn run_on_graph(
+    project_root: &Path,
+    options: &ScanOptions,
+    ref_graph: &ReferenceGraph,
+    progress: &mut dyn FnMut(PipelineEvent),
+) -> anyhow::Result<ScanResult> {
+    let entities = ref_graph.entities.clone();
+    classify(project_root, options, ref_graph, entities, progress)
+}
 
-    // Build cross-file reference graph (Pass 1: index, Pass 2: link edges).
-    let ref_graph = build_reference_graph(&root, host)?;
+/// Stages 0-6 over `entities`, the symbols of `ref_graph` (whose own
+/// `entities` may already have been taken).
+fn classify(
+    project_root: &Path,
+    options: &ScanOptions,
+    ref_graph: &ReferenceGraph,
+    entities: Vec<Entity>,
+    progress: &mut dyn FnMut(PipelineEvent),
+) -> anyhow::Result<ScanResult> {
+    let root = dunce::canonicalize(project_root)?;
+    let config = &options.config;
+    let library_mode = options.library_mode || config.library_mode == Some(true);
+    let protected_dirs: Vec<String> = config
+        .protected_dirs
+        .clone()
+        .unwrap_or_else(|| PROTECTED_DIRS.iter().map(|d| d.to_string()).collect());
+    let low_confidence_dirs: Vec<String> = config
+        .low_confidence_dirs
+        .clone()
+        .unwrap_or_else(|| LOW_CONFIDENCE_DIRS.iter().map(|d| d.to_string()).collect());
+    let plugin_dirs: Vec<String> = config
+        .plugin_dirs
+        .clone()
+        .unwrap_or_else(|| wisdom::PLUGIN_DIRS.iter().map(|d| d.to_string()).collect());
+    let protect_symbols: HashSet<&str> =
+        config.protect_symbols.iter().map(String::as_str).collect();
+    let root_prefix = root_prefix(&root);
+    let scope: Option<HashSet<String>> = options
+        .scope
+        .as_ref()
+        .map(|files| files.iter().map(|f| file_key(&root.join(f))).collect());
+    let in_scope = |file: &str| scope.as_ref().is_none_or(|scope| scope.contains(file));
+    // Directory rules match the path relative to the file's owning root.
+    let in_dirs =
+        |file: &str, dirs: &[String]| is_protected_path(ref_graph.root_relative(file), dirs);
 
     // Pre-compute raw orphan candidates (files with zero cross-file incoming edges).
     // These are refined post-pipeline: a file is only a TRUE orphan when none of its
     // entities survived any protection stage. Files in test dirs, library-mode modules,
     // and framework-managed dirs all acquire protection and drop out of the final list.
-    let raw_orphan_set: HashSet<String> = ref_graph.find_orphan_files().into_iter().collect();
+    let raw_orphan_set: HashSet<String> = ref_graph
+        .find_orphan_files_with(&config.extra_entry_points, &plugin_dirs)
+        .into_iter()
+        .filter(|f| in_scope(f))
+        .collect();
+    // A package verdict needs every file in it classified.
+    let raw_orphan_packages = match scope {
+        Some(_) => Vec::new(),
+        None => ref_graph.find_orphan_packages_with(&config.extra_entry_points, &plugin_dirs),
+    };
 
     let mut result = ScanResult {
-        total: ref_graph.entities.len(),
+        cache_hits: ref_graph.stats.cache_hits,
+        files_parsed: ref_graph.stats.files_parsed,
         ..Default::default()
     };
+    let mut diagnostics = options.diagnostics.clone();
+    diagnostics.extend(
+        ref_graph
+            .stats
+            .diagnostics
+            .iter()
+            .filter(|d| d.file.as_deref().is_none_or(&in_scope))
+            .cloned(),
+    );
 
-    // Stage 1 prep: collect symbol hashes with at least one incoming edge.
-    let referenced_ids: HashSet<u64> = ref_graph
+    // Stage 1 prep: counted and ignored incoming edges per symbol hash (absent =
+    // no edges). Self-edges do not count: a recursive function is not kept alive
+    // by itself, and `if TYPE_CHECKING:` references only when the config allows them.
+    let keeps_alive = config.type_checking_keeps_alive.unwrap_or(true);
+    let counts = |e: &EdgeReference<'_, EdgeInfo>, n: NodeIndex| {
+        e.source() != n && (keeps_alive || e.weight().kind != EdgeKind::TypeChecking)
+    };
+    let incoming_edges: HashMap<u64, (usize, usize)> = ref_graph
         .graph
         .node_indices()
-        .filter(|&n| {
-            ref_graph
+        .filter_map(|n| {
+            let (counted, ignored): (Vec<_>, Vec<_>) = ref_graph
                 .graph
                 .edges_directed(n, Direction::Incoming)
-                .count()
-                > 0
+                .partition(|e| counts(e, n));
+            let id = ref_graph.graph.node_weight(n)?;
+            (!counted.is_empty() || !ignored.is_empty())
+                .then_some((*id, (counted.len(), ignored.len())))
         })
-        .filter_map(|n| ref_graph.graph.node_weight(n))
-        .copied()
         .collect();
+    // What the stages saw of each symbol they let through, by symbol hash; the
+    // entries of the symbols left dead become `result.evidence`.
+    let mut evidence: HashMap<u64, DeadEvidence> = HashMap::new();
+
+    // Stage 1 prep: the protection detail of every referenced symbol, naming
+    // its first caller. Symbols whose every caller lives in a protected
+    // (test/example) directory are test-only, not production-referenced.
+    let caller_entries: HashMap<u64, &SymbolEntry> = ref_graph
+        .registry
+        .entries
+        .iter()
+        .map(|e| (e.id, e))
+        .collect();
+    let mut reference_details: HashMap<u64, ProtectionDetail> = HashMap::new();
+    let mut test_only_refs: HashSet<u64> = HashSet::new();
+    for n in ref_graph.graph.node_indices() {
+        let callers: Vec<Option<(&str, u32, &str)>> = ref_graph
+            .graph
+            .edges_directed(n, Direction::Incoming)
+            .filter(|e| counts(e, n))
+            .map(|e| {
+                let caller = caller_entries.get(&ref_graph.graph[e.source()])?;
+                Some((
+                    caller.file_path.as_str(),
+                    e.weight().line,
+                    caller.qualified_name.as_str(),
+                ))
+            })
+            .collect();
+        if callers.is_empty() {
+            continue;
+        }
+        let id = ref_graph.graph[n];
+        let test_only = callers
+            .iter()
+            .all(|c| c.is_some_and(|(file, ..)| in_dirs(file, &protected_dirs)));
+        if test_only {
+            test_only_refs.insert(id);
+        }
+        let others = match callers.len() - 1 {
+            0 => String::new(),
+            n => format!(" and {} other caller{}", n, if n == 1 { "" } else { "s" }),
+        };
+        let scope = if test_only { "only from tests, " } else { "" };
+        let detail = match callers.iter().flatten().min() {
+            Some((file, line, qname)) => {
+                ProtectionDetail::new(1, format!("referenced {}by `{}`{}", scope, qname, others))
+                    .at(format!("{}:{}", file, line))
+            }
+            None => ProtectionDetail::new(1, "referenced"),
+        };
+        reference_details.insert(id, detail);
+    }
+
+    // Project-wide class hierarchy: interface bases often live in other modules.
+    let hierarchy = wisdom::ClassHierarchy::build(&entities);
+
+    // Django: views named in any `urls.py` route table (one project-wide pre-scan).
+    let url_references = crate::heuristics::django::collect_url_references(&root);
 
     // Group entities by file for the wisdom pass (Stage 2+4).
     let mut file_groups: HashMap<String, Vec<Entity>> = HashMap::new();
-    for entity in ref_graph.entities {
-        file_groups
-            .entry(entity.file_path.clone())
-            .or_default()
-            .push(entity);
+    for entity in entities {
+        if in_scope(&entity.file_path) {
+            file_groups
+                .entry(entity.file_path.clone())
+                .or_default()
+                .push(entity);
+        }
+    }
+    result.total = file_groups.values().map(Vec::len).sum();
+    let mut root_totals: HashMap<String, usize> = HashMap::new();
+    for (file, entities) in &file_groups {
+        if let Some(owner) = ref_graph.owning_root(file) {
+            *root_totals.entry(owner.to_string()).or_default() += entities.len();
+        }
+    }
+
+    // File pragmas: `skip-file` drops a file before Stage 0, `analyze` lifts
+    // its directory protection and low-confidence demotion.
+    let mut analyzed: HashSet<String> = HashSet::new();
+    let python_files: BTreeSet<String> = file_groups
+        .keys()
+        .chain(&raw_orphan_set)
+        .filter(|f| f.ends_with(".py"))
+        .cloned()
+        .collect();
+    for file in python_files {
+        let pragmas = FilePragmas::read(Path::new(&file));
+        if pragmas.skip_file {
+            file_groups.remove(&file);
+            result.skipped_files.push(file);
+        } else if pragmas.analyze {
+            analyzed.insert(file);
+        }
     }
+    let skipped: HashSet<String> = result.skipped_files.iter().cloned().collect();
 
     // Per-file stage loop (Stages 0 → 1 → 2+4 → 3).
+    progress(PipelineEvent::StageStarted(Stage::Classify));
     let mut candidates: Vec<Entity> = Vec::new();
 
-    for (file_path, entities) in file_groups {
+    for (file_path, mut entities) in file_groups {
+        // Stage 0: explicit `protect_symbols` allowlist from `.janitor.toml`.
+        if !protect_symbols.is_empty() {
+            let module = dotted_module(ref_graph.root_relative(&file_path));
+            let (listed, rest): (Vec<Entity>, Vec<Entity>) = entities.into_iter().partition(|e| {
+                protect_symbols.contains(format!("{}.{}", module, e.qualified_name).as_str())
+            });
+            for mut e in listed {
+                let detail =
+                    ProtectionDetail::new(0, "listed in `protect_symbols`").at(".janitor.toml");
+                e.protect(Protection::UserConfig, detail);
+                result.stage_counts[0] += 1;
+                result.protected.push(e);
+            }
+            entities = rest;
+        }
+
         // Stage 0: Directory filter.
-        if is_protected_path(&file_path) {
+        if in_dirs(&file_path, &protected_dirs) && !analyzed.contains(&file_path) {
+            let dir = ref_graph
+                .root_relative(&file_path)
+                .split('/')
+                .find(|seg| protected_dirs.iter().any(|d| d == seg))
+                .unwrap_or_default();
+            let detail = ProtectionDetail::new(0, format!("in protected directory `{}/`", dir));
             for mut e in entities {
-                e.protected_by = Some(Protection::Directory);
+                e.protect(Protection::Directory, detail.clone());
                 result.stage_counts[0] += 1;
                 result.protected.push(e);
             }
             continue;
         }
 
-        // Stage 1: Reference check (cross-file edges in the graph).
+        // Stage 1: Reference check (incoming edges in the graph).
         let mut still_dead: Vec<Entity> = Vec::new();
         for mut entity in entities {
             if entity.protected_by.is_some() {
@@ -134,11 +559,48 @@ pub fn run(
 
             let sym_id = entity.symbol_id();
             let hash = symbol_hash(&sym_id);
-            if referenced_ids.contains(&hash) {
-                entity.protected_by = Some(Protection::Referenced);
+            if let Some(site) = ref_graph.di_registered.get(&hash) {
+                // Registered with a DI container or service registry.
+                let detail = ProtectionDetail::new(2, format!("registered by `{}`", site.call))
+                    .at(site.location());
+                entity.protect(Protection::ConfigReference, detail);
+                result.stage_counts[2] += 1;
+                result.protected.push(entity);
+            } else if test_only_refs.contains(&hash) {
+                let detail = reference_details[&hash].clone();
+                entity.protect(Protection::TestReference, detail);
                 result.stage_counts[1] += 1;
+                result.test_only.push(entity);
+            } else if let Some(detail) = reference_details.get(&hash) {
+                entity.protect(Protection::Referenced, detail.clone());
+                result.stage_counts[1] += 1;
+                result.protected.push(entity);
+            } else if let Some(site) = ref_graph.annotation_refs.get(&hash) {
+                let detail = ProtectionDetail::new(1, "named in a string annotation").at(site);
+                entity.protect(Protection::TypeAnnotation, detail);
+                result.stage_counts[1] += 1;
+                result.protected.push(entity);
+            } else if let Some(site) = ref_graph.dynamic_refs.get(&hash) {
+                // Named by a `getattr`/`setattr`/`hasattr` string somewhere.
+                let detail =
+                    ProtectionDetail::new(2, format!("accessed by name in `{}`", site.call))
+                        .at(site.location());
+                entity.protect(Protection::MetaprogrammingDanger, detail);
+                result.stage_counts[2] += 1;
                 result.protected.push(entity);
             } else {
+                let (counted, ignored) = incoming_edges.get(&hash).copied().unwrap_or_default();
+                evidence.insert(
+                    hash,
+                    DeadEvidence {
+                        symbol_id: sym_id,
+                        incoming_edges: counted,
+                        ignored_edges: ignored,
+                        grep_searched: false,
+                        stages_passed: vec!["directory", "reference"],
+                        orphan_file: false,
+                    },
+                );
                 still_dead.push(entity);
             }
         }
@@ -148,76 +610,277 @@ pub fn run(
         }
 
         // Stage 2+4: Wisdom + PackageExport (single mmap pass per file).
-        match std::fs::read(&file_path) {
+        match common::source::read(Path::new(&file_path)) {
             Ok(source) => {
-                wisdom::classify(&mut still_dead, &source, &file_path);
+                wisdom::classify_with_hierarchy(
+                    &mut still_dead,
+                    &source,
+                    ref_graph.root_relative(&file_path),
+                    &hierarchy,
+                    &plugin_dirs,
+                    options.wisdom.as_deref(),
+                );
             }
-            Err(_) => {
-                // Cannot read file — leave entities in still_dead for later stages.
+            Err(e) => {
+                // Leave entities in still_dead for later stages.
+                diagnostics.push(
+                    Diagnostic::error(codes::UNREADABLE_FILE, format!("not classified: {e}"))
+                        .at(file_path.clone()),
+                );
             }
         }
 
         for mut entity in still_dead {
+            if entity.protected_by.is_none()
+                && entity.parent_class.is_none()
+                && url_references.contains(&entity.name)
+            {
+                let detail = ProtectionDetail::new(2, "view routed from a `urls.py`");
+                entity.protect(Protection::DjangoFramework, detail);
+            }
+
             if entity.protected_by.is_some() {
                 result.stage_counts[2] += 1;
                 result.protected.push(entity);
             } else if library_mode && entity.parent_class.is_none() && !entity.is_private() {
                 // Stage 3: Library mode — protect all public top-level symbols.
-                entity.protected_by = Some(Protection::LibraryMode);
+                let detail = ProtectionDetail::new(3, "public symbol in library mode");
+                entity.protect(Protection::LibraryMode, detail);
                 result.stage_counts[3] += 1;
                 result.protected.push(entity);
             } else {
+                passed(&mut evidence, &entity, "wisdom");
+                if library_mode {
+                    passed(&mut evidence, &entity, "library");
+                }
                 candidates.push(entity);
             }
         }
     }
+    progress(PipelineEvent::StageFinished {
+        stage: Stage::Classify,
+        protected: result.protected.len(),
+    });
 
     if candidates.is_empty() {
         result.dead = candidates;
+        result.diagnostics = diagnostics.into_vec();
         return Ok(result);
     }
 
     // Stage 4.5: Bridge Shield — protect Python route handlers referenced by JS/TS API paths.
-    // Extracts path strings (e.g. "/users") from JS/TS files and cross-references them
-    // against each candidate entity's decorator text.
+    // Extracts `fetch`/`axios` calls and path templates from JS/TS files and matches them
+    // against the route each candidate's decorators declare.
+    progress(PipelineEvent::StageStarted(Stage::Bridge));
+    let protected_before = result.protected.len();
     let bridge_paths = scan::bridge_extract(&root).unwrap_or_default();
     if !bridge_paths.is_empty() {
         let mut remaining: Vec<Entity> = Vec::new();
         for mut entity in candidates {
-            let hit = entity
+            let routes: Vec<scan::ApiRoute> = entity
                 .decorators
                 .iter()
-                .any(|d| bridge_paths.iter().any(|bp| d.contains(bp.as_str())));
-            if hit {
-                entity.protected_by = Some(Protection::GrepShield);
+                .filter_map(|d| scan::decorator_route(d))
+                .collect();
+            let hit = bridge_paths
+                .iter()
+                .find(|call| routes.iter().any(|route| scan::route_matches(route, call)));
+            if let Some(call) = hit {
+                let detail = ProtectionDetail::new(
+                    5,
+                    format!("bridge shield: route `{}` called from JS/TS", call),
+                );
+                entity.protect(Protection::GrepShield, detail);
                 result.stage_counts[5] += 1;
                 result.protected.push(entity);
             } else {
+                passed(&mut evidence, &entity, "bridge");
                 remaining.push(entity);
             }
         }
         candidates = remaining;
     }
+    progress(PipelineEvent::StageFinished {
+        stage: Stage::Bridge,
+        protected: result.protected.len() - protected_before,
+    });
 
     if candidates.is_empty() {
         result.dead = candidates;
+        result.diagnostics = diagnostics.into_vec();
         return Ok(result);
     }
 
     // Stage 5: Grep Shield — only for symbols still dead after stages 0-4.5.
     let dead_names: Vec<String> = candidates.iter().map(|e| e.name.clone()).collect();
-    let grep_found = scan::grep_shield(&dead_names, &root)?;
+    progress(PipelineEvent::StageStarted(Stage::Grep));
+    let protected_before = result.protected.len();
+    let min_name_len = config
+        .grep_min_name_length
+        .unwrap_or(scan::GREP_MIN_NAME_LEN);
+    let grep_found = match &config.grep_extensions {
+        Some(extensions) => scan::grep_shield_observed(
+            &dead_names,
+            &root,
+            extensions,
+            min_name_len,
+            &mut diagnostics,
+            progress,
+        )?,
+        None => scan::grep_shield_observed(
+            &dead_names,
+            &root,
+            scan::GREP_EXTENSIONS,
+            min_name_len,
+            &mut diagnostics,
+            progress,
+        )?,
+    };
+
+    // JS/TS files are both parsed and grepped: a symbol's own definition is not a mention.
+    let script_names: Vec<String> = candidates
+        .iter()
+        .filter(|e| is_script_path(&e.file_path))
+        .map(|e| e.name.clone())
+        .collect();
+    let script_hits = match &config.grep_extensions {
+        Some(extensions) => {
+            scan::grep_shield_locate(&script_names, &root, extensions, min_name_len)?
+        }
+        None => {
+            scan::grep_shield_locate(&script_names, &root, scan::GREP_EXTENSIONS, min_name_len)?
+        }
+    };
 
+    let mut remaining: Vec<Entity> = Vec::new();
     for mut entity in candidates {
-        if grep_found.contains(&entity.name) {
-            entity.protected_by = Some(Protection::GrepShield);
+        let hits = if is_script_path(&entity.file_path) {
+            script_hits.get(&entity.name)
+        } else {
+            grep_found.get(&entity.name)
+        };
+        let shielded_by = hits.into_iter().flatten().find(|hit| {
+            let own_definition = hit.file.to_string_lossy().replace('\\', "/") == entity.file_path
+                && (entity.start_byte..entity.end_byte).contains(&(hit.byte_offset as u32));
+            !own_definition
+        });
+        if let Some(hit) = shielded_by {
+            let detail = ProtectionDetail::new(5, "grep shield: name found in a non-Python file")
+                .at(hit.location());
+            entity.protect(Protection::GrepShield, detail);
             result.stage_counts[5] += 1;
             result.protected.push(entity);
         } else {
+            passed(&mut evidence, &entity, "grep");
+            remaining.push(entity);
+        }
+    }
+
+    // Stage 5 (Python strings): registries, dotted paths and `"module:function"`
+    // strings in settings, task routes and entry points name symbols without a
+    // call edge; the link pass collected them. Strings in protected dirs
+    // (`mock.patch("app.x")` in tests) are left to 5.5.
+    let string_names = ref_graph.string_names(|f| !in_dirs(f, &protected_dirs));
+    let candidates = std::mem::take(&mut remaining);
+    for mut entity in candidates {
+        match string_names.get(entity.name.as_str()) {
+            Some((file, line)) if !is_script_path(&entity.file_path) => {
+                let detail = ProtectionDetail::new(5, "name found in a Python string literal")
+                    .at(format!("{}:{}", file, line));
+                entity.protect(Protection::ConfigReference, detail);
+                result.stage_counts[5] += 1;
+                result.protected.push(entity);
+            }
+            _ => remaining.push(entity),
+        }
+    }
+    progress(PipelineEvent::StageFinished {
+        stage: Stage::Grep,
+        protected: result.protected.len() - protected_before,
+    });
+
+    // Stage 5.5: Test fingerprint — collected test nodes are protected; symbols
+    // whose name appears in a collected test file other than their own are test-only.
+    if let Some(tests) = &options.test_evidence {
+        let names: Vec<String> = remaining.iter().map(|e| e.name.clone()).collect();
+        let mentions = scan::word_mentions(&names, &tests.files, &mut diagnostics)?;
+        let candidates = std::mem::take(&mut remaining);
+        for mut entity in candidates {
+            let own_file = Path::new(&entity.file_path);
+            let test_file = mentions
+                .get(&entity.name)
+                .and_then(|files| files.iter().filter(|f| *f != own_file).min());
+            if tests.names.contains(&entity.name) {
+                let detail = ProtectionDetail::new(5, "test fingerprint: collected test node");
+                entity.protect(Protection::TestReference, detail);
+                result.protected.push(entity);
+            } else if let Some(file) = test_file {
+                let detail = ProtectionDetail::new(5, "test fingerprint: mentioned in a test file")
+                    .at(file.to_string_lossy().replace('\\', "/"));
+                entity.protect(Protection::TestReference, detail);
+                result.test_only.push(entity);
+            } else {
+                passed(&mut evidence, &entity, "tests");
+                remaining.push(entity);
+            }
+        }
+    }
+
+    // Stage 6: Runtime liveness — the final word before the verdict.
+    for mut entity in remaining {
+        if options.live_ids.contains(&symbol_hash(&entity.symbol_id())) {
+            let detail = ProtectionDetail::new(6, "observed in runtime logs or coverage");
+            entity.protect(Protection::RuntimeLiveness, detail);
+            result.runtime_rescued += 1;
+            result.protected.push(entity);
+        } else {
+            if !options.live_ids.is_empty() {
+                passed(&mut evidence, &entity, "runtime");
+            }
             result.dead.push(entity);
         }
     }
 
+    // Targets of one statement (`A = B = 1`) share its byte range and are
+    // deleted together, so a single live target keeps the others alive too.
+    let live_statements: HashMap<(String, u32, u32), (Protection, Option<ProtectionDetail>)> =
+        result
+            .protected
+            .iter()
+            .chain(&result.test_only)
+            .filter(|e| e.entity_type == EntityType::Assignment)
+            .filter_map(|e| {
+                Some((
+                    (e.file_path.clone(), e.start_byte, e.end_byte),
+                    (e.protected_by?, e.protection_detail.clone()),
+                ))
+            })
+            .collect();
+    if !live_statements.is_empty() {
+        let (kept, dead): (Vec<Entity>, Vec<Entity>) =
+            std::mem::take(&mut result.dead).into_iter().partition(|e| {
+                live_statements.contains_key(&(e.file_path.clone(), e.start_byte, e.end_byte))
+            });
+        result.dead = dead;
+        for mut e in kept {
+            if let Some((by, detail)) =
+                live_statements.get(&(e.file_path.clone(), e.start_byte, e.end_byte))
+            {
+                e.protected_by = Some(*by);
+                e.protection_detail = detail.clone();
+            }
+            result.protected.push(e);
+        }
+    }
+
+    // Dead code under scripts/ and bin/ is reported, but needs a human to confirm.
+    let (low_confidence, dead): (Vec<Entity>, Vec<Entity>) =
+        std::mem::take(&mut result.dead).into_iter().partition(|e| {
+            in_dirs(&e.file_path, &low_confidence_dirs) && !analyzed.contains(&e.file_path)
+        });
+    result.dead = dead;
+    result.low_confidence = low_confidence;
+
     // Post-pipeline orphan refinement.
     //
     // A raw_orphan file is a TRUE dead orphan only when none of its entities
@@ -233,22 +896,377 @@ pub fn run(
     let protected_files: HashSet<&str> = result
         .protected
         .iter()
+        .chain(&result.test_only)
         .map(|e| e.file_path.as_str())
         .collect();
     result.orphan_files = raw_orphan_set
-        .into_iter()
-        .filter(|f| !protected_files.contains(f.as_str()))
+        .iter()
+        .filter(|f| !protected_files.contains(f.as_str()) && !skipped.contains(*f))
+        .filter(|f| !in_dirs(f, &low_confidence_dirs) || analyzed.contains(*f))
+        .cloned()
         .collect();
+    // Modules named in Python string literals (`INSTALLED_APPS`, `"app.main:create_app"`)
+    // are loaded by name, like `importlib.import_module` targets.
+    let module_of = |f: &str| dotted_module(ref_graph.root_relative(f));
+    result
+        .orphan_files
+        .retain(|f| !f.ends_with(".py") || !string_names.contains_key(module_of(f).as_str()));
     result.orphan_files.sort();
 
+    // Orphan packages: calls between their own modules do not count, but a
+    // directory rule, a string or grep hit, or an entry point does.
+    let externally_kept: Vec<&str> = result
+        .protected
+        .iter()
+        .chain(&result.test_only)
+        .filter(|e| e.protected_by.is_some_and(is_outside_evidence))
+        .map(|e| e.file_path.as_str())
+        .chain(skipped.iter().map(String::as_str))
+        .collect();
+    result.orphan_packages = raw_orphan_packages
+        .into_iter()
+        .filter(|package| {
+            let prefix = format!("{}/", package);
+            !in_dirs(&prefix, &protected_dirs)
+                && !in_dirs(&prefix, &low_confidence_dirs)
+                && !externally_kept.iter().any(|f| f.starts_with(&prefix))
+        })
+        .collect();
+    result.orphan_package_bytes = result
+        .orphan_packages
+        .iter()
+        .map(|package| (package.clone(), package_bytes(Path::new(package))))
+        .collect();
+
+    for entity in &result.dead {
+        if let Some(hash) = ref_graph.file_hashes.get(&entity.file_path) {
+            result.file_hashes.insert(entity.file_path.clone(), *hash);
+        }
+    }
+
+    let mut dead_nodes: HashMap<u64, NodeIndex> = HashMap::new();
+    for e in &result.dead {
+        let id = symbol_hash(&e.symbol_id());
+        dead_nodes
+            .entry(id)
+            .or_insert_with(|| result.dead_graph.add_node(id));
+    }
+    for edge in ref_graph.graph.edge_references() {
+        let from = dead_nodes.get(&ref_graph.graph[edge.source()]);
+        let to = dead_nodes.get(&ref_graph.graph[edge.target()]);
+        if let (Some(&from), Some(&to)) = (from, to) {
+            result.dead_graph.update_edge(from, to, ());
+        }
+    }
+
+    let orphans: HashSet<&str> = result.orphan_files.iter().map(String::as_str).collect();
+    result.evidence = result
+        .dead
+        .iter()
+        .map(|e| {
+            let mut evidence = evidence[&symbol_hash(&e.symbol_id())].clone();
+            evidence.orphan_file = orphans.contains(e.file_path.as_str());
+            evidence
+        })
+        .collect();
+
+    // Found by the graph pass on the trees it parsed.
+    let mut linked: Vec<(&String, &FileLinks)> = ref_graph.python_links.iter().collect();
+    linked.sort_by_key(|(file, _)| *file);
+    for (file_path, links) in linked {
+        let protected = in_dirs(file_path, &protected_dirs);
+        if (protected && !analyzed.contains(file_path))
+            || skipped.contains(file_path)
+            || !in_scope(file_path)
+            || ref_graph.owning_root(file_path).is_none()
+        {
+            continue;
+        }
+        result
+            .unused_imports
+            .extend(links.unused_imports.iter().cloned());
+    }
+
+    for dir in graph_options(options).workspace_dirs(&root)? {
+        let owner = file_key(&dir);
+        let owned = |file: &str| ref_graph.owning_root(file) == Some(owner.as_str());
+        let dead: Vec<&Entity> = result.dead.iter().filter(|e| owned(&e.file_path)).collect();
+        result.roots.push(RootReport {
+            root: owner
+                .strip_prefix(&root_prefix)
+                .unwrap_or(&owner)
+                .to_string(),
+            total: root_totals.get(&owner).copied().unwrap_or(0),
+            dead: dead.len(),
+            dead_bytes: dead
+                .iter()
+                .map(|e| u64::from(e.end_byte.saturating_sub(e.start_byte)))
+                .sum(),
+            orphan_files: result.orphan_files.iter().filter(|f| owned(f)).count(),
+        });
+    }
+
+    result.diagnostics = diagnostics.into_vec();
     Ok(result)
 }
 
+/// What `janitor why` reports about one symbol (see [`explain`]).
+#[derive(Debug, Clone)]
+pub struct Explanation {
+    /// The symbol, with `protected_by` as the pipeline left it.
+    pub entity: Entity,
+    /// Incoming reference-graph edges (Stage 1), ordered by file and line.
+    pub callers: Vec<Caller>,
+    /// Pipeline stage that assigned `entity.protected_by` (see [`protection_stage`]).
+    pub stage: Option<&'static str>,
+    /// Occurrences of the symbol's name in files searched by the grep shield (Stage 5).
+    pub grep_hits: Vec<scan::GrepHit>,
+    /// `true` when the symbol is on the kill list.
+    pub dead: bool,
+    /// `true` when the symbol would be dead but is in
+    /// [`ScanResult::low_confidence`].
+    pub low_confidence: bool,
+}
+
+/// The referencing end of an incoming reference-graph edge.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Caller {
+    pub file_path: String,
+    /// `"__MODULE__"` for module-level code.
+    pub qualified_name: String,
+    /// 1-based line of the call site, name read, or import.
+    pub line: u32,
+    pub kind: EdgeKind,
+}
+
+/// Runs the pipeline and explains its verdict on every symbol matching `symbol`:
+/// a qualified name (`Class.method`), or `file::qualified_name` where `file` is
+/// the path relative to `project_root` (or any trailing part of it).
+///
+/// Conditional variants (`name#2`) match their base name. Returns an empty list
+/// when nothing matches.
+pub fn explain(
+    project_root: &Path,
+    host: &mut ParserHost,
+    options: &ScanOptions,
+    symbol: &str,
+) -> anyhow::Result<Vec<Explanation>> {
+    let ref_graph = build_graph(project_root, host, options, &mut progress::silent)?;
+    explain_on_graph(project_root, options, ref_graph, symbol)
+}
+
+/// Same as [`explain`], on a graph from [`build_graph`].
+pub fn explain_on_graph(
+    project_root: &Path,
+    options: &ScanOptions,
+    ref_graph: ReferenceGraph,
+    symbol: &str,
+) -> anyhow::Result<Vec<Explanation>> {
+    let (result, ref_graph) =
+        classify_graph(project_root, options, ref_graph, &mut progress::silent)?;
+    let root = dunce::canonicalize(project_root)?;
+    let root_prefix = root_prefix(&root);
+
+    let (file_query, qname_query) = match symbol.rsplit_once("::") {
+        Some((file, qname)) => (Some(file.replace('\\', "/")), qname),
+        None => (None, symbol),
+    };
+    let matches = |e: &Entity| {
+        let qname = &e.qualified_name;
+        let name_ok = qname == qname_query
+            || qname
+                .split_once('#')
+                .is_some_and(|(base, _)| base == qname_query);
+        let file_ok = file_query.as_deref().is_none_or(|file| {
+            e.file_path.strip_prefix(&root_prefix) == Some(file)
+                || e.file_path == file
+                || e.file_path.ends_with(&format!("/{}", file))
+        });
+        name_ok && file_ok
+    };
+
+    let id_to_node: HashMap<u64, NodeIndex> = ref_graph
+        .graph
+        .node_indices()
+        .map(|n| (ref_graph.graph[n], n))
+        .collect();
+    let entries: HashMap<u64, &SymbolEntry> = ref_graph
+        .registry
+        .entries
+        .iter()
+        .map(|e| (e.id, e))
+        .collect();
+
+    let mut explanations = Vec::new();
+    let candidates = result
+        .dead
+        .iter()
+        .map(|e| (e, true))
+        .chain(result.protected.iter().map(|e| (e, false)))
+        .chain(result.test_only.iter().map(|e| (e, false)))
+        .chain(result.low_confidence.iter().map(|e| (e, false)));
+    let low_confidence: HashSet<(&str, u32)> = result
+        .low_confidence
+        .iter()
+        .map(|e| (e.file_path.as_str(), e.start_byte))
+        .collect();
+    for (entity, dead) in candidates.filter(|(e, _)| matches(e)) {
+        let mut callers: Vec<Caller> = id_to_node
+            .get(&symbol_hash(&entity.symbol_id()))
+            .into_iter()
+            .flat_map(|&n| ref_graph.graph.edges_directed(n, Direction::Incoming))
+            .filter_map(|edge| {
+                let caller = entries.get(&ref_graph.graph[edge.source()])?;
+                Some(Caller {
+                    file_path: caller.file_path.clone(),
+                    qualified_name: caller.qualified_name.clone(),
+                    line: edge.weight().line,
+                    kind: edge.weight().kind,
+                })
+            })
+            .collect();
+        callers.sort_by(|a, b| (&a.file_path, a.line).cmp(&(&b.file_path, b.line)));
+        callers.dedup();
+
+        let names = [entity.name.clone()];
+        let min_name_len = options
+            .config
+            .grep_min_name_length
+            .unwrap_or(scan::GREP_MIN_NAME_LEN);
+        let mut grep_hits = match &options.config.grep_extensions {
+            Some(extensions) => scan::grep_shield_locate(&names, &root, extensions, min_name_len)?,
+            None => scan::grep_shield_locate(&names, &root, scan::GREP_EXTENSIONS, min_name_len)?,
+        };
+        let grep_hits = grep_hits.remove(&entity.name).unwrap_or_default();
+
+        explanations.push(Explanation {
+            stage: entity.protected_by.map(protection_stage),
+            entity: entity.clone(),
+            callers,
+            grep_hits,
+            dead,
+            low_confidence: low_confidence
+                .contains(&(entity.file_path.as_str(), entity.start_byte)),
+        });
+    }
+    explanations.sort_by(|a, b| {
+        (&a.entity.file_path, a.entity.start_byte).cmp(&(&b.entity.file_path, b.entity.start_byte))
+    });
+    Ok(explanations)
+}
+
+/// The pipeline stage that assigns `protection`, named as in
+/// [`DeadEvidence::stages_passed`]. Heuristic protections assigned while parsing
+/// report `"parser"`.
+pub fn protection_stage(protection: Protection) -> &'static str {
+    match protection {
+        Protection::Directory | Protection::UserConfig => "directory",
+        Protection::Referenced | Protection::TypeAnnotation => "reference",
+        Protection::WisdomRule
+        | Protection::PackageExport
+        | Protection::ConfigReference
+        | Protection::MetaprogrammingDanger
+        | Protection::LifecycleMethod
+        | Protection::EntryPoint
+        | Protection::QtAutoSlot
+        | Protection::SqlAlchemyMeta
+        | Protection::OrmLifecycle
+        | Protection::PydanticAlias
+        | Protection::FastApiOverride
+        | Protection::InterfaceOverride
+        | Protection::DjangoFramework
+        | Protection::DispatchRegistration => "wisdom",
+        Protection::LibraryMode => "library",
+        Protection::PytestFixture => "parser",
+        // The bridge shield (Stage 4.5) reuses this protection for route handlers.
+        Protection::GrepShield => "grep",
+        Protection::RuntimeLiveness => "runtime",
+        Protection::TestReference => "test",
+    }
+}
+
+/// Indexes the Python symbols of `ref_graph` for runtime log matching.
+///
+/// Each entry carries its dotted module path relative to the project's source
+/// roots, so [`SymbolEntry::fully_qualified_name`] gives the form tracebacks and
+/// structured logs carry (`pkg.module.Class.method`). Its `id` is the
+/// `symbol_hash` the stages use, so IDs found by `lazarus` or
+/// `reaper::SimpleLogTracker` can be passed straight to [`ScanOptions::live_ids`].
+///
+/// Call it before the graph's entities are consumed, i.e. on a graph from
+/// [`build_graph`] before [`run_on_built_graph`].
+pub fn liveness_registry(ref_graph: &ReferenceGraph) -> SymbolRegistry {
+    let mut registry = SymbolRegistry::new();
+    for entity in ref_graph
+        .entities
+        .iter()
+        .filter(|e| e.file_path.ends_with(".py"))
+    {
+        registry.insert(SymbolEntry::from(entity));
+    }
+    registry
+}
+
+/// Records in `evidence` that `entity` passed `stage` without protection.
+/// The grep stage also marks its name as searched.
+fn passed(evidence: &mut HashMap<u64, DeadEvidence>, entity: &Entity, stage: &'static str) {
+    if let Some(evidence) = evidence.get_mut(&symbol_hash(&entity.symbol_id())) {
+        evidence.grep_searched |= stage == "grep";
+        evidence.stages_passed.push(stage);
+    }
+}
+
+/// `root` as a normalized path prefix with a trailing slash, for stripping
+/// entity file paths down to root-relative ones.
+fn root_prefix(root: &Path) -> String {
+    format!(
+        "{}/",
+        dunce::simplified(root).to_string_lossy().replace('\\', "/")
+    )
+}
+
 /// Returns `true` if any path segment matches a protected directory name.
-fn is_protected_path(file_path: &str) -> bool {
+fn is_protected_path(file_path: &str, protected_dirs: &[String]) -> bool {
     file_path
         .split('/')
-        .any(|seg| PROTECTED_DIRS.contains(&seg))
+        .any(|seg| protected_dirs.iter().any(|d| d == seg))
+}
+
+/// Protections that vouch for a symbol from outside its package's own code:
+/// everything but references (which may come from a sibling module) and
+/// structural rules (dunder methods, overrides, package exports).
+fn is_outside_evidence(protection: Protection) -> bool {
+    !matches!(
+        protection,
+        Protection::Referenced
+            | Protection::TypeAnnotation
+            | Protection::PackageExport
+            | Protection::LifecycleMethod
+            | Protection::InterfaceOverride
+            | Protection::SqlAlchemyMeta
+            | Protection::OrmLifecycle
+            | Protection::PydanticAlias
+            | Protection::QtAutoSlot
+            | Protection::DispatchRegistration
+    )
+}
+
+/// Total size of the files under `dir`, skipping what the project walk excludes.
+fn package_bytes(dir: &Path) -> u64 {
+    common::walk::walk(dir)
+        .flatten()
+        .filter_map(|e| e.metadata().ok())
+        .filter(|m| m.is_file())
+        .map(|m| m.len())
+        .sum()
+}
+
+/// Dotted module name of a root-relative file path (`pkg/mod.py` → `pkg.mod`,
+/// `pkg/__init__.py` → `pkg`).
+fn dotted_module(relative: &str) -> String {
+    let stem = relative.strip_suffix(".py").unwrap_or(relative);
+    let stem = stem.strip_suffix("/__init__").unwrap_or(stem);
+    stem.replace('/', ".")
 }
 
 #[cfg(test)]
@@ -275,6 +1293,101 @@ mod tests {
         fs::remove_dir_all(tmp).ok();
     }
 
+    #[test]
+    fn test_progress_events_in_order() {
+        let tmp = std::env::temp_dir().join("test_pipeline_progress");
+        fs::remove_dir_all(&tmp).ok();
+        fs::create_dir_all(&tmp).ok();
+        fs::write(tmp.join("a.py"), b"def unused():\n    pass\n").ok();
+        fs::write(tmp.join("b.py"), b"def documented():\n    pass\n").ok();
+        fs::write(tmp.join("README.md"), b"See `documented`.\n").ok();
+
+        let mut events = Vec::new();
+        let mut host = make_host();
+        let result = run_observed(&tmp, &mut host, &ScanOptions::default(), &mut |e| {
+            events.push(e)
+        })
+        .unwrap();
+        assert_eq!(result.dead.len(), 1);
+
+        let parsed: Vec<(usize, usize)> = events
+            .iter()
+            .filter_map(|e| match e {
+                PipelineEvent::FileParsed { index, total, .. } => Some((*index, *total)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(parsed, vec![(1, 2), (2, 2)]);
+
+        let stages: Vec<&PipelineEvent> = events
+            .iter()
+            .filter(|e| !matches!(e, PipelineEvent::FileParsed { .. }))
+            .collect();
+        let finished = |stage, protected| PipelineEvent::StageFinished { stage, protected };
+        assert_eq!(
+            stages,
+            vec![
+                &PipelineEvent::StageStarted(Stage::Parse),
+                &finished(Stage::Parse, 0),
+                &PipelineEvent::StageStarted(Stage::Link),
+                &finished(Stage::Link, 0),
+                &PipelineEvent::StageStarted(Stage::Classify),
+                &finished(Stage::Classify, 0),
+                &PipelineEvent::StageStarted(Stage::Bridge),
+                &finished(Stage::Bridge, 0),
+                &PipelineEvent::StageStarted(Stage::Grep),
+                &PipelineEvent::GrepFileScanned {
+                    path: dunce::canonicalize(&tmp).unwrap().join("README.md")
+                },
+                &finished(Stage::Grep, 1),
+            ]
+        );
+        // Per-file events sit inside the parse stage.
+        assert!(matches!(
+            events[1],
+            PipelineEvent::FileParsed { index: 1, .. }
+        ));
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_live_ids_rescue_dead_symbol() {
+        let tmp = std::env::temp_dir().join("test_pipeline_live_ids");
+        fs::remove_dir_all(&tmp).ok();
+        fs::create_dir_all(&tmp).ok();
+        fs::write(tmp.join("hooks.py"), b"def on_signal():\n    pass\n").ok();
+
+        let mut host = make_host();
+        let graph = build_graph(
+            &tmp,
+            &mut host,
+            &ScanOptions::default(),
+            &mut progress::silent,
+        )
+        .unwrap();
+        let registry = liveness_registry(&graph);
+        assert_eq!(registry.entries.len(), 1);
+        assert_eq!(
+            registry.entries[0].fully_qualified_name(),
+            "hooks.on_signal"
+        );
+
+        let options = ScanOptions {
+            live_ids: HashSet::from([registry.entries[0].id]),
+            ..Default::default()
+        };
+        let result = run_on_built_graph(&tmp, &options, graph, &mut progress::silent).unwrap();
+        assert!(result.dead.is_empty());
+        assert_eq!(result.runtime_rescued, 1);
+        assert_eq!(
+            result.protected[0].protected_by,
+            Some(Protection::RuntimeLiveness)
+        );
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
     #[test]
     fn test_referenced_symbol_survives() {
         let tmp = std::env::temp_dir().join("test_pipeline_ref");
@@ -297,53 +1410,529 @@ mod tests {
     }
 
     #[test]
-    fn test_unreferenced_symbol_is_dead() {
-        let tmp = std::env::temp_dir().join("test_pipeline_dead");
+    fn test_module_constants_dead_unless_read() {
+        let tmp = std::env::temp_dir().join("test_pipeline_constants");
+        fs::remove_dir_all(&tmp).ok();
         fs::create_dir_all(&tmp).ok();
-
-        fs::write(tmp.join("utils.py"), b"def dead_code():\n    pass\n").ok();
-        fs::write(tmp.join("main.py"), b"# nothing uses utils\n").ok();
+        fs::write(
+            tmp.join("settings.py"),
+            b"import logging\n\nDEFAULT_TIMEOUT = 30\nRETRIES: int = 3\nLEGACY_URL = 'http://old'\nPRIMARY = FALLBACK = 'db'\nlogger = logging.getLogger(__name__)\n\ndef connect():\n    logger.info('connecting')\n    return RETRIES\n",
+        )
+        .ok();
+        fs::write(
+            tmp.join("client.py"),
+            b"import settings\nfrom settings import DEFAULT_TIMEOUT, connect\n\ndef fetch(timeout=DEFAULT_TIMEOUT):\n    connect()\n    return settings.FALLBACK\n",
+        )
+        .ok();
 
         let mut host = make_host();
         let result = run(&tmp, &mut host, false).unwrap();
+        let mut dead: Vec<&str> = result.dead.iter().map(|e| e.name.as_str()).collect();
+        dead.sort();
+        // `fetch` is an unused function; `LEGACY_URL` an unused constant.
+        assert_eq!(dead, vec!["LEGACY_URL", "fetch"]);
 
-        assert!(result.dead.iter().any(|e| e.name == "dead_code"));
+        let protection = |name: &str| {
+            result
+                .protected
+                .iter()
+                .find(|e| e.name == name)
+                .and_then(|e| e.protected_by)
+        };
+        assert_eq!(protection("DEFAULT_TIMEOUT"), Some(Protection::Referenced));
+        assert_eq!(protection("RETRIES"), Some(Protection::Referenced));
+        assert_eq!(protection("logger"), Some(Protection::Referenced));
+        // Only `FALLBACK` is read, but the statement also binds `PRIMARY`.
+        assert_eq!(protection("FALLBACK"), Some(Protection::Referenced));
+        assert_eq!(protection("PRIMARY"), Some(Protection::Referenced));
 
         fs::remove_dir_all(tmp).ok();
     }
 
     #[test]
-    fn test_dunder_protected() {
-        let tmp = std::env::temp_dir().join("test_pipeline_dunder");
+    fn test_type_aliases_dead_unless_used() {
+        let tmp = std::env::temp_dir().join("test_pipeline_type_aliases");
+        fs::remove_dir_all(&tmp).ok();
         fs::create_dir_all(&tmp).ok();
-
         fs::write(
-            tmp.join("model.py"),
-            b"class Foo:\n    def __init__(self):\n        pass\n",
+            tmp.join("aliases.py"),
+            b"from typing import TypeAlias\n\n__all__ = ['Exported']\n\ntype Vector = list[float]\ntype Stale = dict[str, int]\nUserId: TypeAlias = int\nForward: TypeAlias = str\nExported: TypeAlias = bytes\n",
+        )
+        .ok();
+        fs::write(
+            tmp.join("geometry.py"),
+            b"from aliases import Forward, UserId, Vector\n\ndef norm(v: Vector, owner: 'Forward') -> float:\n    return 0.0\n\ndef main(uid: list[UserId]):\n    return norm\n\nif __name__ == '__main__':\n    main([])\n",
         )
         .ok();
 
         let mut host = make_host();
         let result = run(&tmp, &mut host, false).unwrap();
+        let dead: Vec<&str> = result
+            .dead
+            .iter()
+            .filter(|e| e.entity_type == EntityType::TypeAlias)
+            .map(|e| e.name.as_str())
+            .collect();
+        assert_eq!(dead, vec!["Stale"]);
 
-        assert!(!result.dead.iter().any(|e| e.name == "__init__"));
+        let protection = |name: &str| {
+            result
+                .protected
+                .iter()
+                .find(|e| e.name == name)
+                .and_then(|e| e.protected_by)
+        };
+        assert_eq!(protection("Vector"), Some(Protection::Referenced));
+        assert_eq!(protection("UserId"), Some(Protection::Referenced));
+        // Forward references in string annotations count as reads.
+        assert_eq!(protection("Forward"), Some(Protection::Referenced));
+        assert_eq!(protection("Exported"), Some(Protection::PackageExport));
 
         fs::remove_dir_all(tmp).ok();
     }
 
     #[test]
-    fn test_library_mode_protects_public() {
-        let tmp = std::env::temp_dir().join("test_pipeline_lib");
+    fn test_closure_from_factory_not_reported_dead() {
+        let tmp = std::env::temp_dir().join("test_pipeline_closures");
+        fs::remove_dir_all(&tmp).ok();
         fs::create_dir_all(&tmp).ok();
-
         fs::write(
-            tmp.join("api.py"),
-            b"def public_fn():\n    pass\ndef _private():\n    pass\n",
+            tmp.join("factory.py"),
+            b"def make_counter(start):\n    count = start\n    def increment(step=1):\n        nonlocal count\n        count += step\n        return count\n    return increment\n",
+        )
+        .ok();
+        fs::write(
+            tmp.join("main.py"),
+            b"from factory import make_counter\n\nif __name__ == '__main__':\n    make_counter(0)()\n",
         )
         .ok();
 
         let mut host = make_host();
-        let result = run(&tmp, &mut host, true).unwrap();
+        let result = run(&tmp, &mut host, false).unwrap();
+        assert!(result.dead.is_empty(), "dead: {:?}", result.dead);
+        assert_eq!(result.total, 1);
+        assert_eq!(result.protected[0].name, "make_counter");
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_unreferenced_symbol_is_dead() {
+        let tmp = std::env::temp_dir().join("test_pipeline_dead");
+        fs::create_dir_all(&tmp).ok();
+
+        fs::write(tmp.join("utils.py"), b"def dead_code():\n    pass\n").ok();
+        fs::write(tmp.join("main.py"), b"# nothing uses utils\n").ok();
+
+        let mut host = make_host();
+        let result = run(&tmp, &mut host, false).unwrap();
+
+        assert!(result.dead.iter().any(|e| e.name == "dead_code"));
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_dead_evidence() {
+        let tmp = std::env::temp_dir().join("test_pipeline_evidence");
+        fs::create_dir_all(&tmp).ok();
+
+        fs::write(
+            tmp.join("utils.py"),
+            b"def helper():\n    pass\ndef dead_code():\n    pass\n",
+        )
+        .ok();
+        fs::write(tmp.join("lonely.py"), b"def stranded():\n    pass\n").ok();
+        fs::write(
+            tmp.join("main.py"),
+            b"from utils import helper\ndef run():\n    helper()\n",
+        )
+        .ok();
+
+        let mut host = make_host();
+        let result = run(&tmp, &mut host, false).unwrap();
+        assert_eq!(result.evidence.len(), result.dead.len());
+        for e in &result.dead {
+            let bytes = fs::read(&e.file_path).unwrap();
+            assert_eq!(
+                result.file_hashes.get(&e.file_path),
+                Some(blake3::hash(&bytes).as_bytes())
+            );
+        }
+
+        let evidence_for = |name: &str| {
+            result
+                .dead
+                .iter()
+                .position(|e| e.name == name)
+                .map(|i| &result.evidence[i])
+        };
+
+        let dead = evidence_for("dead_code").expect("dead_code should be dead");
+        assert!(dead.symbol_id.ends_with("::dead_code"));
+        assert_eq!(dead.incoming_edges, 0);
+        assert!(dead.grep_searched);
+        assert_eq!(
+            dead.stages_passed,
+            vec!["directory", "reference", "wisdom", "grep"]
+        );
+        assert!(!dead.orphan_file);
+
+        let stranded = evidence_for("stranded").expect("stranded should be dead");
+        assert!(stranded.orphan_file);
+
+        // Referenced symbols are protected and carry no dead evidence.
+        assert!(evidence_for("helper").is_none());
+        assert!(result
+            .protected
+            .iter()
+            .any(|e| e.name == "helper" && e.protected_by == Some(Protection::Referenced)));
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_cache_hits_reported() {
+        let tmp = std::env::temp_dir().join("test_pipeline_cache");
+        fs::create_dir_all(&tmp).ok();
+        fs::write(tmp.join("a.py"), b"def f():\n    pass\n").ok();
+        fs::write(tmp.join("b.py"), b"def g():\n    pass\n").ok();
+
+        let mut host = make_host();
+        let options = ScanOptions {
+            use_cache: true,
+            ..Default::default()
+        };
+        let first = run_with_options(&tmp, &mut host, &options).unwrap();
+        assert_eq!((first.cache_hits, first.files_parsed), (0, 2));
+
+        fs::write(tmp.join("b.py"), b"def h():\n    pass\n").ok();
+        let second = run_with_options(&tmp, &mut host, &options).unwrap();
+        assert_eq!((second.cache_hits, second.files_parsed), (1, 1));
+        assert!(second.dead.iter().any(|e| e.name == "h"));
+        assert!(!second.dead.iter().any(|e| e.name == "g"));
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_package_reexport_not_dead() {
+        let tmp = std::env::temp_dir().join("test_pipeline_reexport");
+        fs::create_dir_all(tmp.join("pkg")).ok();
+
+        fs::write(
+            tmp.join("pkg/core.py"),
+            b"def process():\n    pass\n\ndef unused():\n    pass\n",
+        )
+        .ok();
+        fs::write(tmp.join("pkg/__init__.py"), b"from .core import process\n").ok();
+        fs::write(
+            tmp.join("main.py"),
+            b"from pkg import process\n\ndef run():\n    process()\n",
+        )
+        .ok();
+
+        let mut host = make_host();
+        let result = run(&tmp, &mut host, false).unwrap();
+
+        assert!(!result.dead.iter().any(|e| e.name == "process"));
+        assert!(result.dead.iter().any(|e| e.name == "unused"));
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_star_import_keeps_public_symbols() {
+        let tmp = std::env::temp_dir().join("test_pipeline_star_import");
+        fs::create_dir_all(&tmp).ok();
+
+        fs::write(
+            tmp.join("helpers.py"),
+            b"def helper():\n    pass\n\ndef _private():\n    pass\n",
+        )
+        .ok();
+        fs::write(tmp.join("main.py"), b"from helpers import *\n").ok();
+
+        let mut host = make_host();
+        let result = run(&tmp, &mut host, false).unwrap();
+        let helper = result.protected.iter().find(|e| e.name == "helper");
+        assert_eq!(
+            helper.and_then(|e| e.protected_by),
+            Some(Protection::Referenced)
+        );
+        assert!(result.dead.iter().any(|e| e.name == "_private"));
+
+        let strict = ScanOptions {
+            strict_star_imports: true,
+            ..Default::default()
+        };
+        let result = run_with_options(&tmp, &mut host, &strict).unwrap();
+        assert!(result.dead.iter().any(|e| e.name == "helper"));
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_reference_provenance_test_only() {
+        let tmp = std::env::temp_dir().join("test_pipeline_test_only");
+        fs::remove_dir_all(&tmp).ok();
+        fs::create_dir_all(tmp.join("tests")).ok();
+
+        fs::write(
+            tmp.join("helpers.py"),
+            b"def fixture_helper():\n    return 1\n\ndef shared():\n    return 2\n",
+        )
+        .ok();
+        fs::write(
+            tmp.join("main.py"),
+            b"from helpers import shared\n\nshared()\n",
+        )
+        .ok();
+        fs::write(
+            tmp.join("tests/test_x.py"),
+            b"from helpers import fixture_helper, shared\n\n\
+def test_helpers():\n    assert fixture_helper() + shared() == 3\n",
+        )
+        .ok();
+
+        let mut host = make_host();
+        let result = run(&tmp, &mut host, false).unwrap();
+
+        let test_only: Vec<&str> = result.test_only.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(test_only, vec!["fixture_helper"]);
+        assert_eq!(
+            result.test_only[0].protected_by,
+            Some(Protection::TestReference)
+        );
+        let shared = result.protected.iter().find(|e| e.name == "shared");
+        assert_eq!(
+            shared.and_then(|e| e.protected_by),
+            Some(Protection::Referenced)
+        );
+        assert!(!result.dead.iter().any(|e| e.name == "fixture_helper"));
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_protection_detail_names_evidence() {
+        let tmp = std::env::temp_dir().join("test_pipeline_protection_detail");
+        fs::remove_dir_all(&tmp).ok();
+        fs::create_dir_all(tmp.join("templates")).ok();
+
+        fs::write(
+            tmp.join("helpers.py"),
+            b"def helper():\n    return 1\n\ndef render_badge():\n    return 2\n",
+        )
+        .ok();
+        fs::write(
+            tmp.join("main.py"),
+            b"from helpers import helper\n\ndef main():\n    return helper()\n",
+        )
+        .ok();
+        fs::write(
+            tmp.join("templates/page.html"),
+            b"<div>{{ render_badge() }}</div>\n",
+        )
+        .ok();
+
+        let mut host = make_host();
+        let result = run(&tmp, &mut host, false).unwrap();
+        let root_prefix = root_prefix(&dunce::canonicalize(&tmp).unwrap());
+        let detail = |name: &str| {
+            result
+                .protected
+                .iter()
+                .find(|e| e.name == name)
+                .and_then(|e| e.protection_detail.clone())
+                .unwrap()
+        };
+
+        let helper = detail("helper");
+        assert_eq!(helper.stage, 1);
+        assert_eq!(helper.reason, "referenced by `main`");
+        assert_eq!(helper.location, Some(format!("{}main.py:4", root_prefix)));
+
+        let badge = detail("render_badge");
+        assert_eq!(badge.stage, 5);
+        assert_eq!(
+            badge.location,
+            Some(format!("{}templates/page.html:1", root_prefix))
+        );
+
+        let main = detail("main");
+        assert_eq!(main.stage, 2);
+        assert_eq!(main.reason, "wisdom rule: entry point `main`");
+        assert_eq!(main.location, None);
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_self_method_call_referenced() {
+        let tmp = std::env::temp_dir().join("test_pipeline_self_call");
+        fs::create_dir_all(&tmp).ok();
+
+        fs::write(
+            tmp.join("cart.py"),
+            b"class Cart:\n    def total(self):\n        return self._sum()
+\n    def _sum(self):\n        return 1\n\n    @classmethod\n    def build(cls):\n        return cls._defaults()\n\n    @classmethod\n    def _defaults(cls):\n        return {}\n\n    def _unused(self):\n        return 2\n",
+        )
+        .ok();
+
+        let mut host = make_host();
+        let result = run(&tmp, &mut host, false).unwrap();
+        let protection = |name: &str| {
+            result
+                .protected
+                .iter()
+                .find(|e| e.name == name)
+                .and_then(|e| e.protected_by)
+        };
+
+        assert_eq!(protection("_sum"), Some(Protection::Referenced));
+        assert_eq!(protection("_defaults"), Some(Protection::Referenced));
+        assert!(result.dead.iter().any(|e| e.name == "_unused"));
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_unittest_case_outside_tests_dir() {
+        let tmp = std::env::temp_dir().join("test_pipeline_unittest");
+        fs::create_dir_all(tmp.join("src")).ok();
+
+        fs::write(
+            tmp.join("src/checks.py"),
+            b"import unittest\n\nclass FooTests(unittest.TestCase):\n    def setUp(self):\n        pass\n\n    def test_adds(self):\n        pass\n",
+        )
+        .ok();
+
+        let mut host = make_host();
+        let result = run(&tmp, &mut host, false).unwrap();
+
+        for name in ["setUp", "test_adds"] {
+            let entity = result.protected.iter().find(|e| e.name == name);
+            assert_eq!(
+                entity.and_then(|e| e.protected_by),
+                Some(Protection::TestReference),
+                "{name} should be protected"
+            );
+        }
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_interface_override_across_files() {
+        let tmp = std::env::temp_dir().join("test_pipeline_interface");
+        fs::create_dir_all(&tmp).ok();
+
+        fs::write(
+            tmp.join("base.py"),
+            b"import abc\n\nclass StorageBackend(abc.ABC):\n    @abc.abstractmethod\n    def read(self, key):\n        ...\n",
+        )
+        .ok();
+        fs::write(
+            tmp.join("s3.py"),
+            b"from base import StorageBackend\n\nclass S3Storage(StorageBackend):\n    def read(self, key):\n        return key\n\n    def unused(self):\n        pass\n",
+        )
+        .ok();
+
+        let mut host = make_host();
+        let result = run(&tmp, &mut host, false).unwrap();
+
+        let read = result
+            .protected
+            .iter()
+            .find(|e| e.qualified_name == "S3Storage.read");
+        assert_eq!(
+            read.and_then(|e| e.protected_by),
+            Some(Protection::InterfaceOverride)
+        );
+        assert!(result
+            .dead
+            .iter()
+            .any(|e| e.qualified_name == "S3Storage.unused"));
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_django_app_layout() {
+        let tmp = std::env::temp_dir().join("test_pipeline_django");
+        fs::create_dir_all(tmp.join("shop")).ok();
+
+        fs::write(
+            tmp.join("shop/urls.py"),
+            b"from django.urls import path\nfrom . import views\n\nurlpatterns = [\n    path('', views.index),\n    path('items/', views.ItemList.as_view()),\n]\n",
+        )
+        .ok();
+        fs::write(
+            tmp.join("shop/views.py"),
+            b"def index(request):\n    pass\n\nclass ItemList:\n    pass\n\ndef orphan_view(request):\n    pass\n",
+        )
+        .ok();
+        fs::write(
+            tmp.join("shop/signals.py"),
+            b"from django.dispatch import receiver\n\n@receiver(post_save)\ndef on_item_saved(sender, **kwargs):\n    pass\n",
+        )
+        .ok();
+        fs::write(
+            tmp.join("shop/admin.py"),
+            b"from django.contrib import admin\n\nclass ItemAdmin(admin.ModelAdmin):\n    pass\n",
+        )
+        .ok();
+
+        let mut host = make_host();
+        host.register_heuristic(Box::new(crate::heuristics::django::DjangoHeuristic));
+        let result = run(&tmp, &mut host, false).unwrap();
+
+        for name in ["index", "ItemList", "on_item_saved", "ItemAdmin"] {
+            let entity = result.protected.iter().find(|e| e.name == name);
+            assert_eq!(
+                entity.and_then(|e| e.protected_by),
+                Some(Protection::DjangoFramework),
+                "{name} should be protected"
+            );
+        }
+        assert!(result.dead.iter().any(|e| e.name == "orphan_view"));
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_dunder_protected() {
+        let tmp = std::env::temp_dir().join("test_pipeline_dunder");
+        fs::create_dir_all(&tmp).ok();
+
+        fs::write(
+            tmp.join("model.py"),
+            b"class Foo:\n    def __init__(self):\n        pass\n",
+        )
+        .ok();
+
+        let mut host = make_host();
+        let result = run(&tmp, &mut host, false).unwrap();
+
+        assert!(!result.dead.iter().any(|e| e.name == "__init__"));
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_library_mode_protects_public() {
+        let tmp = std::env::temp_dir().join("test_pipeline_lib");
+        fs::create_dir_all(&tmp).ok();
+
+        fs::write(
+            tmp.join("api.py"),
+            b"def public_fn():\n    pass\ndef _private():\n    pass\n",
+        )
+        .ok();
+
+        let mut host = make_host();
+        let result = run(&tmp, &mut host, true).unwrap();
 
         assert!(!result.dead.iter().any(|e| e.name == "public_fn"));
         // _private is still a candidate (private even in library mode)
@@ -353,21 +1942,1005 @@ mod tests {
     }
 
     #[test]
-    fn test_protected_dir_skipped() {
-        let tmp = std::env::temp_dir().join("test_pipeline_dir");
-        fs::create_dir_all(tmp.join("tests")).ok();
+    fn test_config_overrides_protected_dirs() {
+        let tmp = std::env::temp_dir().join("test_pipeline_config_dirs");
+        fs::remove_dir_all(&tmp).ok();
+        fs::create_dir_all(tmp.join("examples")).ok();
+        fs::create_dir_all(tmp.join("vendor")).ok();
+        fs::write(
+            tmp.join("examples/demo.py"),
+            b"def example_fn():\n    pass\n",
+        )
+        .ok();
+        fs::write(tmp.join("vendor/lib.py"), b"def vendor_fn():\n    pass\n").ok();
+
+        let mut host = make_host();
+        let result = run(&tmp, &mut host, false).unwrap();
+        assert!(!result.dead.iter().any(|e| e.name == "example_fn"));
+        assert!(result.dead.iter().any(|e| e.name == "vendor_fn"));
+
+        // `protected_dirs` replaces the built-in list rather than extending it.
+        let options = ScanOptions {
+            config: JanitorConfig {
+                protected_dirs: Some(vec!["vendor".into()]),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let result = run_with_options(&tmp, &mut host, &options).unwrap();
+        assert!(result.dead.iter().any(|e| e.name == "example_fn"));
+        assert!(!result.dead.iter().any(|e| e.name == "vendor_fn"));
 
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_scripts_are_analyzed_with_low_confidence() {
+        let tmp = std::env::temp_dir().join("test_pipeline_low_confidence");
+        fs::remove_dir_all(&tmp).ok();
+        fs::create_dir_all(tmp.join("scripts")).ok();
+        fs::create_dir_all(tmp.join("tests")).ok();
         fs::write(
-            tmp.join("tests/test_foo.py"),
-            b"def test_something():\n    pass\n",
+            tmp.join("scripts/legacy_migration.py"),
+            b"def migrate():\n    copy_rows()\n\ndef copy_rows():\n    pass\n\nif __name__ == \"__main__\":\n    copy_rows()\n",
         )
         .ok();
+        fs::write(tmp.join("tests/test_app.py"), b"def helper():\n    pass\n").ok();
+        fs::write(tmp.join("app.py"), b"def unused():\n    pass\n").ok();
 
         let mut host = make_host();
         let result = run(&tmp, &mut host, false).unwrap();
 
-        // All symbols in tests/ should be Directory-protected, not dead.
-        assert!(result.dead.is_empty());
+        let names = |list: &[Entity]| list.iter().map(|e| e.name.clone()).collect::<Vec<_>>();
+        // scripts/ goes through the stages: the call keeps `copy_rows` alive...
+        let copy_rows = result
+            .protected
+            .iter()
+            .find(|e| e.name == "copy_rows")
+            .unwrap();
+        assert_eq!(copy_rows.protected_by, Some(Protection::Referenced));
+        // ...and the dead `migrate` is reported apart from the kill list.
+        assert_eq!(names(&result.low_confidence), vec!["migrate"]);
+        assert_eq!(names(&result.dead), vec!["unused"]);
+        assert_eq!(result.evidence.len(), 1);
+        assert!(!result.orphan_files.iter().any(|f| f.contains("/scripts/")));
+
+        // tests/ is still never analyzed.
+        let helper = result
+            .protected
+            .iter()
+            .find(|e| e.name == "helper")
+            .unwrap();
+        assert_eq!(helper.protected_by, Some(Protection::Directory));
+
+        // `low_confidence_dirs` replaces the built-in list.
+        let options = ScanOptions {
+            config: JanitorConfig {
+                low_confidence_dirs: Some(Vec::new()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let result = run_with_options(&tmp, &mut host, &options).unwrap();
+        assert!(result.low_confidence.is_empty());
+        assert!(result.dead.iter().any(|e| e.name == "migrate"));
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_config_protect_symbols_allowlist() {
+        let tmp = std::env::temp_dir().join("test_pipeline_config_allow");
+        fs::remove_dir_all(&tmp).ok();
+        fs::create_dir_all(tmp.join("billing")).ok();
+        fs::write(
+            tmp.join("billing/legacy.py"),
+            b"def migrate_v1():\n    pass\n\nclass Old:\n    def run_once(self):\n        pass\n\ndef unused():\n    pass\n",
+        )
+        .ok();
+
+        let options = ScanOptions {
+            config: JanitorConfig {
+                protect_symbols: vec![
+                    "billing.legacy.migrate_v1".into(),
+                    "billing.legacy.Old.run_once".into(),
+                ],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut host = make_host();
+        let result = run_with_options(&tmp, &mut host, &options).unwrap();
+
+        let protection = |name: &str| {
+            result
+                .protected
+                .iter()
+                .find(|e| e.name == name)
+                .and_then(|e| e.protected_by)
+        };
+        assert_eq!(protection("migrate_v1"), Some(Protection::UserConfig));
+        assert_eq!(protection("run_once"), Some(Protection::UserConfig));
+        assert!(result.dead.iter().any(|e| e.name == "unused"));
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_protected_dir_skipped() {
+        let tmp = std::env::temp_dir().join("test_pipeline_dir");
+        fs::create_dir_all(tmp.join("tests")).ok();
+
+        fs::write(
+            tmp.join("tests/test_foo.py"),
+            b"def test_something():\n    pass\n",
+        )
+        .ok();
+
+        let mut host = make_host();
+        let result = run(&tmp, &mut host, false).unwrap();
+
+        // All symbols in tests/ should be Directory-protected, not dead.
+        assert!(result.dead.is_empty());
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_analyze_pragma_lifts_directory_protection() {
+        let tmp = std::env::temp_dir().join("test_pipeline_pragma_analyze");
+        fs::remove_dir_all(&tmp).ok();
+        fs::create_dir_all(tmp.join("tests")).ok();
+        fs::create_dir_all(tmp.join("scripts")).ok();
+        fs::write(
+            tmp.join("tests/helpers.py"),
+            "# janitor: analyze\ndef stale_helper():\n    pass\n",
+        )
+        .ok();
+        fs::write(
+            tmp.join("tests/other.py"),
+            "def fixture_data():\n    pass\n",
+        )
+        .ok();
+        fs::write(
+            tmp.join("scripts/billing.py"),
+            "#!/usr/bin/env python\n# janitor: analyze\ndef stale_report():\n    pass\n",
+        )
+        .ok();
+        fs::write(tmp.join("scripts/tool.py"), "def adhoc():\n    pass\n").ok();
+
+        let mut host = make_host();
+        let result = run(&tmp, &mut host, false).unwrap();
+
+        let mut dead: Vec<&str> = result.dead.iter().map(|e| e.name.as_str()).collect();
+        dead.sort();
+        assert_eq!(dead, vec!["stale_helper", "stale_report"]);
+        let protected = result.protected.iter().find(|e| e.name == "fixture_data");
+        assert_eq!(protected.unwrap().protected_by, Some(Protection::Directory));
+        let low: Vec<&str> = result
+            .low_confidence
+            .iter()
+            .map(|e| e.name.as_str())
+            .collect();
+        assert_eq!(low, vec!["adhoc"]);
+        assert!(result.skipped_files.is_empty());
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_skip_file_pragma_hides_file_but_keeps_its_calls() {
+        let tmp = std::env::temp_dir().join("test_pipeline_pragma_skip");
+        fs::remove_dir_all(&tmp).ok();
+        fs::create_dir_all(tmp.join("tests")).ok();
+        fs::write(
+            tmp.join("lib.py"),
+            "def used_by_generated():\n    pass\n\ndef unused():\n    pass\n",
+        )
+        .ok();
+        fs::write(
+            tmp.join("generated.py"),
+            "# Code generated by protoc. DO NOT EDIT.\n# janitor: skip-file\nfrom lib import used_by_generated\n\ndef stub():\n    used_by_generated()\n",
+        )
+        .ok();
+        // skip-file wins over analyze.
+        fs::write(
+            tmp.join("tests/both.py"),
+            "# janitor: analyze\n# janitor: skip-file\ndef helper():\n    pass\n",
+        )
+        .ok();
+
+        let mut host = make_host();
+        let result = run(&tmp, &mut host, false).unwrap();
+
+        let dead: Vec<&str> = result.dead.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(dead, vec!["unused"]);
+        let reported = result
+            .protected
+            .iter()
+            .chain(&result.test_only)
+            .chain(&result.low_confidence);
+        assert!(!reported
+            .clone()
+            .any(|e| e.name == "stub" || e.name == "helper"));
+        assert!(reported.clone().any(|e| e.name == "used_by_generated"));
+        assert!(!result
+            .orphan_files
+            .iter()
+            .any(|f| f.ends_with("generated.py")));
+        let skipped: Vec<&str> = result
+            .skipped_files
+            .iter()
+            .map(|f| {
+                f.rsplit_once(tmp.file_name().unwrap().to_str().unwrap())
+                    .unwrap()
+                    .1
+            })
+            .collect();
+        assert_eq!(skipped, vec!["/generated.py", "/tests/both.py"]);
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_scope_reports_only_listed_files() {
+        let tmp = std::env::temp_dir().join("test_pipeline_scope");
+        fs::remove_dir_all(&tmp).ok();
+        fs::create_dir_all(&tmp).ok();
+        fs::write(
+            tmp.join("changed.py"),
+            "def called_elsewhere():\n    pass\n\ndef forgotten():\n    pass\n",
+        )
+        .ok();
+        fs::write(
+            tmp.join("main.py"),
+            "from changed import called_elsewhere\n\ncalled_elsewhere()\n\ndef untouched_dead():\n    pass\n",
+        )
+        .ok();
+        fs::write(tmp.join("stale.py"), "def old():\n    pass\n").ok();
+
+        let mut host = make_host();
+        let options = ScanOptions {
+            scope: Some(vec![PathBuf::from("changed.py")]),
+            ..Default::default()
+        };
+        let result = run_with_options(&tmp, &mut host, &options).unwrap();
+
+        let dead: Vec<&str> = result.dead.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(dead, vec!["forgotten"]);
+        let protected: Vec<&str> = result.protected.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(protected, vec!["called_elsewhere"]);
+        assert_eq!(result.total, 2);
+        assert_eq!(result.stage_counts, [0, 1, 0, 0, 0, 0]);
+        assert!(result.orphan_files.is_empty());
+
+        let unscoped = run_with_options(&tmp, &mut host, &ScanOptions::default()).unwrap();
+        assert_eq!(unscoped.dead.len(), 3);
+        assert!(unscoped
+            .orphan_files
+            .iter()
+            .any(|f| f.ends_with("stale.py")));
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_di_container_registrations_protected() {
+        let tmp = std::env::temp_dir().join("test_pipeline_di_container");
+        fs::create_dir_all(&tmp).ok();
+
+        fs::write(
+            tmp.join("services.py"),
+            b"class SmtpEmailSender:\n    pass\n\nclass SmsSender:\n    pass\n\nclass UnregisteredSender:\n    pass\n",
+        )
+        .ok();
+        fs::write(
+            tmp.join("containers.py"),
+            b"from dependency_injector import containers, providers\nfrom services import SmtpEmailSender\n\nclass Container(containers.DeclarativeContainer):\n    email = providers.Factory(SmtpEmailSender)\n    sms = providers.Singleton(\"services.SmsSender\")\n",
+        )
+        .ok();
+
+        let mut host = make_host();
+        let result = run(&tmp, &mut host, false).unwrap();
+
+        let protection = |name: &str| {
+            result
+                .protected
+                .iter()
+                .find(|e| e.name == name)
+                .and_then(|e| e.protected_by)
+        };
+        assert_eq!(
+            protection("SmtpEmailSender"),
+            Some(Protection::ConfigReference)
+        );
+        assert_eq!(protection("SmsSender"), Some(Protection::ConfigReference));
+        assert!(result.dead.iter().any(|e| e.name == "UnregisteredSender"));
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_dict_registry_protected() {
+        let tmp = std::env::temp_dir().join("test_pipeline_di_dict");
+        fs::create_dir_all(&tmp).ok();
+
+        fs::write(
+            tmp.join("senders.py"),
+            b"class SmtpEmailSender:\n    pass\n\nclass UnusedSender:\n    pass\n",
+        )
+        .ok();
+        fs::write(
+            tmp.join("registry.py"),
+            b"from senders import SmtpEmailSender\n\nSERVICES = {\"email\": SmtpEmailSender}\n",
+        )
+        .ok();
+
+        let mut host = make_host();
+        let result = run(&tmp, &mut host, false).unwrap();
+
+        assert!(!result.dead.iter().any(|e| e.name == "SmtpEmailSender"));
+        assert!(result.dead.iter().any(|e| e.name == "UnusedSender"));
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_getattr_string_protects_dispatched_methods() {
+        // The space in the path must survive into the protection's location.
+        let tmp = std::env::temp_dir().join("test_pipeline_getattr dispatch");
+        fs::remove_dir_all(&tmp).ok();
+        fs::create_dir_all(&tmp).ok();
+
+        fs::write(
+            tmp.join("orders.py"),
+            b"class Order:\n    def compute_total(self):\n        return 0\n\n    def on_paid(self):\n        pass\n\n    def on_refunded(self):\n        pass\n\n    def archive(self):\n        pass\n",
+        )
+        .ok();
+        fs::write(
+            tmp.join("dispatcher.py"),
+            b"from orders import Order\n\ndef total(order: Order):\n    return getattr(order, \"compute_total\")()\n\ndef notify(order: Order, event):\n    return getattr(order, f\"on_{event}\")()\n",
+        )
+        .ok();
+
+        let mut host = make_host();
+        let result = run(&tmp, &mut host, false).unwrap();
+
+        let protected = |name: &str| result.protected.iter().find(|e| e.name == name);
+        let total = protected("compute_total").expect("literal getattr protects");
+        assert_eq!(total.protected_by, Some(Protection::MetaprogrammingDanger));
+        let detail = total.protection_detail.as_ref().unwrap();
+        assert_eq!(
+            detail.reason,
+            "accessed by name in `getattr(…, \"compute_total\")`"
+        );
+        assert!(detail
+            .location
+            .as_deref()
+            .is_some_and(|l| l.ends_with("getattr dispatch/dispatcher.py:4")));
+        assert!(protected("on_paid").is_some());
+        assert!(protected("on_refunded").is_some());
+        assert!(result.dead.iter().any(|e| e.name == "archive"));
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_bridge_matches_routes_by_method_and_template() {
+        let tmp = std::env::temp_dir().join("test_pipeline_bridge_routes");
+        fs::remove_dir_all(&tmp).ok();
+        fs::create_dir_all(&tmp).ok();
+        fs::write(
+            tmp.join("views.py"),
+            b"from web import api\n\n\n@api.get(\"/users/{id}/audit\")\ndef user_audit(id):\n    pass\n\n\n@api.get(\"/users/{id}\")\ndef user_detail(id):\n    pass\n\n\n@api.delete(\"/orders/{id}\")\ndef delete_order(id):\n    pass\n",
+        )
+        .ok();
+        fs::write(tmp.join("web.py"), b"api = None\n").ok();
+        fs::write(
+            tmp.join("client.js"),
+            b"fetch(\"/users\");\naxios.get(`/users/${id}`);\naxios.post(\"/orders/\" + id);\n",
+        )
+        .ok();
+
+        let mut host = make_host();
+        let result = run(&tmp, &mut host, false).unwrap();
+
+        let detail = result
+            .protected
+            .iter()
+            .find(|e| e.name == "user_detail")
+            .and_then(|e| e.protection_detail.as_ref())
+            .expect("GET /users/${id} reaches the handler");
+        assert_eq!(
+            detail.reason,
+            "bridge shield: route `GET /users/${id}` called from JS/TS"
+        );
+        // `/users` is not `/users/{id}/audit`, and a POST does not reach a DELETE route.
+        let dead: Vec<&str> = result.dead.iter().map(|e| e.name.as_str()).collect();
+        assert!(dead.contains(&"user_audit"), "{:?}", dead);
+        assert!(dead.contains(&"delete_order"), "{:?}", dead);
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_module_named_in_string_is_not_orphan() {
+        let tmp = std::env::temp_dir().join("test_pipeline_string_module_orphan");
+        fs::remove_dir_all(&tmp).ok();
+        fs::create_dir_all(tmp.join("billing")).ok();
+        fs::write(tmp.join("billing/__init__.py"), b"").ok();
+        fs::write(
+            tmp.join("billing/signals.py"),
+            b"def on_save():\n    pass\n",
+        )
+        .ok();
+        fs::write(tmp.join("billing/old.py"), b"def stale():\n    pass\n").ok();
+        fs::write(
+            tmp.join("settings.py"),
+            b"INSTALLED_APPS = [\n    \"billing.signals\",\n]\n",
+        )
+        .ok();
+
+        let mut host = make_host();
+        let result = run(&tmp, &mut host, false).unwrap();
+
+        assert!(
+            !result
+                .orphan_files
+                .iter()
+                .any(|f| f.ends_with("signals.py")),
+            "{:?}",
+            result.orphan_files
+        );
+        assert!(result
+            .orphan_files
+            .iter()
+            .any(|f| f.ends_with("billing/old.py")));
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_orphan_packages_reported_unless_named_elsewhere() {
+        let tmp = std::env::temp_dir().join("test_pipeline_orphan_packages");
+        fs::remove_dir_all(&tmp).ok();
+        for dir in ["billing_v1", "reports", "tests/helpers"] {
+            fs::create_dir_all(tmp.join(dir)).ok();
+            fs::write(tmp.join(dir).join("__init__.py"), b"").ok();
+        }
+        fs::write(
+            tmp.join("billing_v1/invoice.py"),
+            b"from billing_v1.tax import rate\n\ndef total(x):\n    return x * rate()\n",
+        )
+        .ok();
+        fs::write(
+            tmp.join("billing_v1/tax.py"),
+            b"def rate():\n    return 1.2\n",
+        )
+        .ok();
+        // Named in a template: the grep shield vouches for the package.
+        fs::write(tmp.join("reports/pdf.py"), b"def render_pdf():\n    pass\n").ok();
+        fs::write(
+            tmp.join("page.html"),
+            b"<a href=\"{{ render_pdf }}\">PDF</a>\n",
+        )
+        .ok();
+        fs::write(
+            tmp.join("tests/helpers/util.py"),
+            b"def fake():\n    pass\n",
+        )
+        .ok();
+        fs::write(tmp.join("app.py"), b"print('hi')\n").ok();
+
+        let mut host = make_host();
+        let result = run(&tmp, &mut host, false).unwrap();
+
+        let root = dunce::canonicalize(&tmp).unwrap();
+        let billing = root.join("billing_v1").to_string_lossy().replace('\\', "/");
+        assert_eq!(result.orphan_packages, vec![billing.clone()]);
+        assert!(result.orphan_package_bytes[&billing] > 0);
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_settings_string_protects_task() {
+        let tmp = std::env::temp_dir().join("test_pipeline_settings_string");
+        fs::remove_dir_all(&tmp).ok();
+        fs::create_dir_all(tmp.join("app")).ok();
+
+        fs::write(tmp.join("app").join("__init__.py"), b"").ok();
+        fs::write(
+            tmp.join("app").join("tasks.py"),
+            b"def send_email(to):\n    pass\n\ndef send_sms(to):\n    pass\n",
+        )
+        .ok();
+        fs::write(
+            tmp.join("settings.py"),
+            b"\"\"\"Celery settings; send_sms is routed elsewhere.\"\"\"\n\nCELERY_TASK_ROUTES = {\n    \"app.tasks.send_email\": {\"queue\": \"mail\"},\n}\n",
+        )
+        .ok();
+
+        let mut host = make_host();
+        let result = run(&tmp, &mut host, false).unwrap();
+
+        let task = result
+            .protected
+            .iter()
+            .find(|e| e.name == "send_email")
+            .expect("dotted string in settings protects the task");
+        assert_eq!(task.protected_by, Some(Protection::ConfigReference));
+        let detail = task.protection_detail.as_ref().unwrap();
+        assert_eq!(detail.stage, 5);
+        assert!(detail
+            .location
+            .as_deref()
+            .is_some_and(|l| l.ends_with("settings.py:4")));
+        // Docstrings are prose, not configuration.
+        assert!(result.dead.iter().any(|e| e.name == "send_sms"));
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_explain_reports_callers_stage_and_grep_hits() {
+        let tmp = std::env::temp_dir().join("test_pipeline_explain");
+        fs::remove_dir_all(&tmp).ok();
+        fs::create_dir_all(tmp.join("pkg")).ok();
+        fs::write(tmp.join("pkg/__init__.py"), "").ok();
+        fs::write(
+            tmp.join("pkg/util.py"),
+            "def helper():\n    pass\n\ndef unused():\n    pass\n\ndef documented():\n    pass\n",
+        )
+        .ok();
+        fs::write(
+            tmp.join("main.py"),
+            "from pkg.util import helper\n\ndef main():\n    return helper()\n",
+        )
+        .ok();
+        fs::write(tmp.join("README.md"), "Call `documented()` from a shell.\n").ok();
+
+        let mut host = make_host();
+        let options = ScanOptions::default();
+
+        let helper = explain(&tmp, &mut host, &options, "helper").unwrap();
+        assert_eq!(helper.len(), 1);
+        assert!(!helper[0].dead);
+        assert_eq!(helper[0].stage, Some("reference"));
+        assert_eq!(helper[0].callers.len(), 1);
+        assert_eq!(helper[0].callers[0].qualified_name, "main");
+        assert!(helper[0].callers[0].file_path.ends_with("/main.py"));
+        assert_eq!(helper[0].callers[0].line, 4);
+
+        let unused = explain(&tmp, &mut host, &options, "pkg/util.py::unused").unwrap();
+        assert_eq!(unused.len(), 1);
+        assert!(unused[0].dead);
+        assert_eq!(unused[0].stage, None);
+        assert!(unused[0].callers.is_empty() && unused[0].grep_hits.is_empty());
+
+        let documented = explain(&tmp, &mut host, &options, "util.py::documented").unwrap();
+        assert_eq!(
+            documented[0].entity.protected_by,
+            Some(Protection::GrepShield)
+        );
+        assert_eq!(documented[0].stage, Some("grep"));
+        assert!(documented[0].grep_hits[0].file.ends_with("README.md"));
+        assert_eq!(documented[0].grep_hits[0].byte_offset, 6);
+        assert_eq!(documented[0].grep_hits[0].line, 1);
+        let detail = documented[0].entity.protection_detail.as_ref().unwrap();
+        assert!(detail
+            .location
+            .as_deref()
+            .is_some_and(|l| l.ends_with("README.md:1")));
+
+        assert!(explain(&tmp, &mut host, &options, "main.py::unused")
+            .unwrap()
+            .is_empty());
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_notebook_calls_and_imports_protect_symbols_and_files() {
+        let tmp = std::env::temp_dir().join("test_pipeline_notebook");
+        fs::remove_dir_all(&tmp).ok();
+        fs::create_dir_all(&tmp).ok();
+        fs::write(
+            tmp.join("report.py"),
+            "def summarize_sales():\n    pass\n\ndef legacy_export():\n    pass\n",
+        )
+        .ok();
+        // `legacy_export` only appears in an output, which is not searched.
+        fs::write(
+            tmp.join("analysis.ipynb"),
+            r##"{"cells": [
+                {"cell_type": "markdown", "source": ["# Sales\n"]},
+                {"cell_type": "code", "source": ["import report\n", "report.summarize_sales()\n"],
+                 "outputs": [{"output_type": "stream", "text": ["legacy_export\n"]}]}
+            ], "nbformat": 4}"##,
+        )
+        .ok();
+
+        let mut host = make_host();
+        let result = run(&tmp, &mut host, false).unwrap();
+
+        let summarize = result
+            .protected
+            .iter()
+            .find(|e| e.name == "summarize_sales")
+            .unwrap();
+        assert_eq!(summarize.protected_by, Some(Protection::GrepShield));
+        let detail = summarize.protection_detail.as_ref().unwrap();
+        assert!(
+            detail
+                .location
+                .as_deref()
+                .is_some_and(|l| l.ends_with("/analysis.ipynb[cell 1]:2")),
+            "{:?}",
+            detail.location
+        );
+        assert!(result.dead.iter().any(|e| e.name == "legacy_export"));
+        assert!(
+            !result
+                .orphan_files
+                .iter()
+                .any(|f| f.ends_with("/report.py")),
+            "{:?}",
+            result.orphan_files
+        );
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_type_checking_imports_and_string_annotations() {
+        let tmp = std::env::temp_dir().join("test_pipeline_type_checking");
+        fs::remove_dir_all(&tmp).ok();
+        fs::create_dir_all(tmp.join("app")).ok();
+        fs::write(tmp.join("app/__init__.py"), "").ok();
+        fs::write(
+            tmp.join("app/models.py"),
+            "class Order:\n    pass\n\nclass Ledger:\n    pass\n\nclass Invoice:\n    pass\n",
+        )
+        .ok();
+        // Same name elsewhere: `"app.models.Ledger"` must not reach it.
+        fs::write(tmp.join("app/legacy.py"), "class Ledger:\n    pass\n").ok();
+        fs::write(
+            tmp.join("app/service.py"),
+            "from __future__ import annotations\nfrom typing import TYPE_CHECKING, Literal\n\nif TYPE_CHECKING:\n    from app.models import Order\n\ndef handle(order: Order, ledger: \"app.models.Ledger\", kind: Literal[\"Invoice\"]) -> None:\n    pass\n\nhandle(None, None, None)\n",
+        )
+        .ok();
+
+        let mut host = make_host();
+        let graph = crate::graph::build_reference_graph(&tmp, &mut host).unwrap();
+        assert!(graph
+            .graph
+            .edge_weights()
+            .any(|e| e.kind == EdgeKind::TypeChecking));
+        let annotated: Vec<&str> = graph
+            .annotation_refs
+            .keys()
+            .filter_map(|id| graph.registry.entries.iter().find(|e| e.id == *id))
+            .map(|e| e.file_path.as_str())
+            .collect();
+        assert_eq!(annotated.len(), 1);
+        assert!(annotated[0].ends_with("app/models.py"));
+
+        let protection = |result: &ScanResult, name: &str| {
+            result
+                .protected
+                .iter()
+                .find(|e| e.name == name && e.file_path.ends_with("models.py"))
+                .and_then(|e| e.protected_by)
+        };
+        let result = run(&tmp, &mut host, false).unwrap();
+        assert_eq!(protection(&result, "Order"), Some(Protection::Referenced));
+        assert_eq!(
+            protection(&result, "Ledger"),
+            Some(Protection::TypeAnnotation)
+        );
+        let ledger = result
+            .protected
+            .iter()
+            .find(|e| e.name == "Ledger")
+            .unwrap();
+        let location = ledger.protection_detail.as_ref().unwrap().location.clone();
+        assert!(location.is_some_and(|l| l.ends_with("app/service.py:7")));
+        // A `Literal` value is data, not a forward reference.
+        assert_ne!(
+            protection(&result, "Invoice"),
+            Some(Protection::TypeAnnotation)
+        );
+
+        // Type-only references can be told not to keep symbols alive; the
+        // string annotation still protects `Ledger`.
+        let options = ScanOptions {
+            config: JanitorConfig {
+                type_checking_keeps_alive: Some(false),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let result = run_with_options(&tmp, &mut host, &options).unwrap();
+        assert!(result.dead.iter().any(|e| e.name == "Order"));
+        assert_eq!(
+            protection(&result, "Ledger"),
+            Some(Protection::TypeAnnotation)
+        );
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_self_recursive_function_still_dead() {
+        let tmp = std::env::temp_dir().join("test_pipeline_self_recursive");
+        fs::remove_dir_all(&tmp).ok();
+        fs::create_dir_all(&tmp).ok();
+        // `tree.walk()` through the module's own import is a self-edge.
+        fs::write(
+            tmp.join("tree.py"),
+            "import tree\n\ndef walk(n):\n    return tree.walk(n - 1)\n\ndef used():\n    pass\n",
+        )
+        .ok();
+        fs::write(tmp.join("main.py"), "from tree import used\n\nused()\n").ok();
+
+        let mut host = make_host();
+        let graph = crate::graph::build_reference_graph(&tmp, &mut host).unwrap();
+        assert!(graph
+            .graph
+            .edge_references()
+            .any(|e| e.source() == e.target()));
+
+        let result = run(&tmp, &mut host, false).unwrap();
+        assert!(result.dead.iter().any(|e| e.name == "walk"));
+        assert!(result.protected.iter().any(|e| e.name == "used"));
+        let i = result.dead.iter().position(|e| e.name == "walk").unwrap();
+        assert_eq!(result.evidence[i].incoming_edges, 0);
+        assert_eq!(result.evidence[i].ignored_edges, 1);
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_intra_file_calls_are_references() {
+        let tmp = std::env::temp_dir().join("test_pipeline_intra_file");
+        fs::remove_dir_all(&tmp).ok();
+        fs::create_dir_all(&tmp).ok();
+        fs::write(
+            tmp.join("script.py"),
+            "def helper():\n    return 1\n\ndef run():\n    return list(helper())\n\ndef stale():\n    return sorted([])\n\ndef sorted(items):\n    return items\n\nif __name__ == \"__main__\":\n    run()\n",
+        )
+        .ok();
+
+        let mut host = make_host();
+        let result = run(&tmp, &mut host, false).unwrap();
+        let dead: Vec<&str> = result.dead.iter().map(|e| e.name.as_str()).collect();
+        assert!(!dead.contains(&"helper") && !dead.contains(&"run"));
+        assert!(dead.contains(&"stale"));
+        // `sorted` is defined here, so the shadowing call links it.
+        assert!(!dead.contains(&"sorted"));
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_decorator_only_function_not_dead() {
+        let tmp = std::env::temp_dir().join("test_pipeline_decorator_only");
+        fs::remove_dir_all(&tmp).ok();
+        fs::create_dir_all(&tmp).ok();
+        fs::write(
+            tmp.join("service.py"),
+            "def audit(fn):\n    return fn\n\n@audit\ndef create():\n    pass\n\n@audit\ndef update():\n    pass\n\n@audit\ndef remove():\n    pass\n\ndef unused(fn):\n    return fn\n",
+        )
+        .ok();
+
+        let mut host = make_host();
+        let result = run(&tmp, &mut host, false).unwrap();
+        let dead: Vec<&str> = result.dead.iter().map(|e| e.name.as_str()).collect();
+        assert!(!dead.contains(&"audit"));
+        assert!(dead.contains(&"unused"));
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_src_layout_imports_resolve() {
+        let tmp = std::env::temp_dir().join("test_pipeline_src_layout");
+        fs::remove_dir_all(&tmp).ok();
+        fs::create_dir_all(tmp.join("src/mypkg")).ok();
+        fs::create_dir_all(tmp.join("src/nspkg")).ok();
+        fs::create_dir_all(tmp.join("scripts")).ok();
+        fs::write(tmp.join("src/mypkg/__init__.py"), "").ok();
+        fs::write(
+            tmp.join("src/mypkg/core.py"),
+            "def helper():\n    pass\n\ndef unused():\n    pass\n",
+        )
+        .ok();
+        // PEP 420 namespace package: no `__init__.py`.
+        fs::write(tmp.join("src/nspkg/tools.py"), "def tool():\n    pass\n").ok();
+        fs::write(
+            tmp.join("scripts/run.py"),
+            "from mypkg.core import helper\nfrom nspkg import tools\n\nhelper()\ntools.tool()\n",
+        )
+        .ok();
+
+        let mut host = make_host();
+        let result = run(&tmp, &mut host, false).unwrap();
+        let dead: Vec<&str> = result.dead.iter().map(|e| e.name.as_str()).collect();
+        assert!(!dead.contains(&"helper"));
+        assert!(!dead.contains(&"tool"));
+        assert!(dead.contains(&"unused"));
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_config_source_roots() {
+        let tmp = std::env::temp_dir().join("test_pipeline_config_source_roots");
+        fs::remove_dir_all(&tmp).ok();
+        fs::create_dir_all(tmp.join("code/app")).ok();
+        fs::write(tmp.join("code/app/__init__.py"), "").ok();
+        fs::write(tmp.join("code/app/util.py"), "def helper():\n    pass\n").ok();
+        fs::write(
+            tmp.join("main.py"),
+            "from app.util import helper\n\nhelper()\n",
+        )
+        .ok();
+
+        let mut host = make_host();
+        let without = run(&tmp, &mut host, false).unwrap();
+        assert!(without.dead.iter().any(|e| e.name == "helper"));
+
+        let options = ScanOptions {
+            config: JanitorConfig {
+                source_roots: Some(vec!["code".into()]),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let with = run_with_options(&tmp, &mut host, &options).unwrap();
+        assert!(!with.dead.iter().any(|e| e.name == "helper"));
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_workspace_roots_share_one_graph() {
+        // The monorepo lives under a `tasks/` directory, a plugin-dir name:
+        // directory rules must see paths relative to each root.
+        let tmp = std::env::temp_dir().join("test_pipeline_workspace_roots");
+        fs::remove_dir_all(&tmp).ok();
+        let mono = tmp.join("tasks/mono");
+        fs::create_dir_all(mono.join("services/a/app")).ok();
+        fs::create_dir_all(mono.join("libs/common/common")).ok();
+        fs::create_dir_all(mono.join("tools")).ok();
+        fs::write(mono.join("services/a/app/__init__.py"), "").ok();
+        fs::write(
+            mono.join("services/a/app/main.py"),
+            "from common.helpers import helper\n\nhelper()\n",
+        )
+        .ok();
+        fs::write(
+            mono.join("services/a/app/stale.py"),
+            "def forgotten():\n    pass\n",
+        )
+        .ok();
+        fs::write(mono.join("libs/common/common/__init__.py"), "").ok();
+        fs::write(
+            mono.join("libs/common/common/helpers.py"),
+            "def helper():\n    return 1\n\n\ndef unused():\n    return 2\n",
+        )
+        .ok();
+        fs::write(mono.join("tools/outside.py"), "def ignored():\n    pass\n").ok();
+
+        let mut host = make_host();
+        let without = run(&mono, &mut host, false).unwrap();
+        assert!(without.dead.iter().any(|e| e.name == "helper"));
+        assert!(without.roots.is_empty());
+
+        let options = ScanOptions {
+            config: JanitorConfig {
+                workspace: common::config::WorkspaceConfig {
+                    roots: vec!["services/a".into(), "libs/common".into()],
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let result = run_with_options(&mono, &mut host, &options).unwrap();
+        let dead: Vec<&str> = result.dead.iter().map(|e| e.name.as_str()).collect();
+        assert!(!dead.contains(&"helper"), "dead: {:?}", dead);
+        assert!(dead.contains(&"unused"));
+        assert!(dead.contains(&"forgotten"));
+        // Files outside every root are not scanned.
+        assert!(!result
+            .dead
+            .iter()
+            .chain(&result.protected)
+            .any(|e| e.name == "ignored"));
+        assert!(result
+            .orphan_files
+            .iter()
+            .any(|f| f.ends_with("app/stale.py")));
+
+        let per_root: Vec<(&str, usize, usize, usize)> = result
+            .roots
+            .iter()
+            .map(|r| (r.root.as_str(), r.total, r.dead, r.orphan_files))
+            .collect();
+        assert_eq!(
+            per_root,
+            vec![("services/a", 1, 1, 1), ("libs/common", 2, 1, 0)]
+        );
+        assert!(result.roots[1].dead_bytes > 0);
+
+        // A root that does not exist fails the scan rather than shrinking it.
+        let mut bogus = options.clone();
+        bogus.config.workspace.roots.push("libs/gone".into());
+        let err = run_with_options(&mono, &mut host, &bogus).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "workspace root `libs/gone` is not a directory"
+        );
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_js_ts_symbols_enter_pipeline() {
+        let tmp = std::env::temp_dir().join("test_pipeline_js_ts");
+        fs::remove_dir_all(&tmp).ok();
+        fs::create_dir_all(&tmp).ok();
+        fs::write(
+            tmp.join("util.ts"),
+            "export function used(): number { return 1; }\n\nexport function unused(): number { return 2; }\n\nexport class Widget {\n  constructor() {}\n}\n",
+        )
+        .ok();
+        fs::write(
+            tmp.join("index.ts"),
+            "import { used, Widget } from './util';\n\nused();\nnew Widget();\n",
+        )
+        .ok();
+        fs::write(tmp.join("stale.js"), "function forgotten() {}\n").ok();
+
+        let mut host = make_host();
+        let result = run(&tmp, &mut host, false).unwrap();
+        let dead: Vec<&str> = result.dead.iter().map(|e| e.name.as_str()).collect();
+        assert!(!dead.contains(&"used"));
+        assert!(!dead.contains(&"Widget"));
+        assert!(!dead.contains(&"constructor"));
+        // Its own definition in a grepped `.ts` file does not shield it.
+        assert!(dead.contains(&"unused"));
+        assert!(dead.contains(&"forgotten"));
+        assert!(result.orphan_files.iter().any(|f| f.ends_with("stale.js")));
+        assert!(!result.orphan_files.iter().any(|f| f.ends_with("index.ts")));
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_base_classes_and_annotation_types_not_dead() {
+        let tmp = std::env::temp_dir().join("test_pipeline_inherit_typeref");
+        fs::remove_dir_all(&tmp).ok();
+        fs::create_dir_all(&tmp).ok();
+        fs::write(
+            tmp.join("models.py"),
+            "from dataclasses import dataclass\n\nclass BaseRepo:\n    pass\n\n@dataclass\nclass Config:\n    name: str\n\nclass Unused:\n    pass\n",
+        )
+        .ok();
+        fs::write(
+            tmp.join("repos.py"),
+            "from models import BaseRepo, Config\n\nclass UserRepo(BaseRepo):\n    pass\n\ndef load(cfg: \"Config\") -> UserRepo:\n    pass\n",
+        )
+        .ok();
+
+        let mut host = make_host();
+        let result = run(&tmp, &mut host, false).unwrap();
+        let dead: Vec<&str> = result.dead.iter().map(|e| e.name.as_str()).collect();
+        assert!(!dead.contains(&"BaseRepo"));
+        assert!(!dead.contains(&"Config"));
+        assert!(!dead.contains(&"UserRepo"));
+        assert!(dead.contains(&"Unused"));
 
         fs::remove_dir_all(tmp).ok();
     }
@@ -0,0 +1,93 @@
+//! File-level pragmas, read from a comment in the first [`HEADER_LINES`] lines
+//! of a Python file:
+//!
+//! - `# janitor: skip-file` — none of the file's symbols is reported (dead,
+//!   protected or orphaned). The file is still parsed and linked, so its calls
+//!   keep other files alive. Listed in [`ScanResult::skipped_files`].
+//! - `# janitor: analyze` — the file is analyzed even inside a Stage 0
+//!   protected directory (`tests/`) or a low-confidence one (`scripts/`):
+//!   its dead symbols go on the kill list like any other.
+//!
+//! `skip-file` wins when both appear.
+//!
+//! [`ScanResult::skipped_files`]: crate::pipeline::ScanResult::skipped_files
+
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+/// Lines searched for pragmas.
+pub const HEADER_LINES: usize = 5;
+
+/// The pragmas a file declares.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FilePragmas {
+    /// `# janitor: skip-file`
+    pub skip_file: bool,
+    /// `# janitor: analyze`
+    pub analyze: bool,
+}
+
+impl FilePragmas {
+    /// Pragmas in the first [`HEADER_LINES`] lines of `source`.
+    pub fn parse(source: &[u8]) -> Self {
+        let mut pragmas = Self::default();
+        for line in source.split(|&b| b == b'\n').take(HEADER_LINES) {
+            match pragma(line) {
+                Some(b"skip-file") => pragmas.skip_file = true,
+                Some(b"analyze") => pragmas.analyze = true,
+                _ => {}
+            }
+        }
+        pragmas
+    }
+
+    /// Pragmas of the file at `path`, reading only its header. An unreadable
+    /// file declares none.
+    pub fn read(path: &Path) -> Self {
+        let Ok(file) = File::open(path) else {
+            return Self::default();
+        };
+        let mut reader = BufReader::new(file);
+        let mut header = Vec::new();
+        for _ in 0..HEADER_LINES {
+            match reader.read_until(b'\n', &mut header) {
+                Ok(0) | Err(_) => break,
+                Ok(_) => {}
+            }
+        }
+        Self::parse(&header)
+    }
+}
+
+/// The name in a `# janitor: <name>` comment line.
+fn pragma(line: &[u8]) -> Option<&[u8]> {
+    let comment = line.trim_ascii().strip_prefix(b"#")?;
+    let rest = comment.trim_ascii_start().strip_prefix(b"janitor:")?;
+    rest.trim_ascii().split(|b| b.is_ascii_whitespace()).next()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_header_only() {
+        let source = b"#!/usr/bin/env python\n# -*- coding: utf-8 -*-\n#janitor: skip-file\n";
+        assert_eq!(
+            FilePragmas::parse(source),
+            FilePragmas {
+                skip_file: true,
+                analyze: false
+            }
+        );
+        let late = b"\n\n\n\n\n# janitor: analyze\n";
+        assert_eq!(FilePragmas::parse(late), FilePragmas::default());
+        let trailing = b"# janitor: analyze  -- production helper\n";
+        assert!(FilePragmas::parse(trailing).analyze);
+        assert_eq!(
+            FilePragmas::parse(b"x = 1  # janitor: analyze\n"),
+            FilePragmas::default()
+        );
+    }
+}
@@ -0,0 +1,66 @@
+//! # Progress Events
+//!
+//! Observers passed to [`crate::pipeline::run_observed`] and
+//! [`crate::graph::build_reference_graph_observed`] receive a
+//! [`PipelineEvent`] as each file and stage completes. The library never
+//! prints; rendering is left to the caller.
+//!
+//! Ordering guarantees:
+//! - Every `StageStarted(s)` is followed by exactly one `StageFinished { stage: s, .. }`
+//!   before the next stage starts.
+//! - `FileParsed` is only emitted inside [`Stage::Parse`], with `index` counting
+//!   `1..=total`.
+//! - `GrepFileScanned` is only emitted inside [`Stage::Grep`].
+//! - Stages that have no work (bridge and grep when nothing is left dead) are
+//!   not reported at all.
+
+use std::path::PathBuf;
+
+/// A pipeline phase, in execution order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Stage {
+    /// Graph Pass 1: parse every Python file and index its symbols.
+    Parse,
+    /// Graph Pass 2: resolve imports and call sites into edges.
+    Link,
+    /// Stages 0–3: directory, reference, wisdom and library-mode checks.
+    Classify,
+    /// Stage 4.5: JS/TS API path bridge.
+    Bridge,
+    /// Stage 5: grep shield over non-Python files.
+    Grep,
+}
+
+impl Stage {
+    /// Short lowercase label for display.
+    pub fn label(self) -> &'static str {
+        match self {
+            Stage::Parse => "parse",
+            Stage::Link => "link",
+            Stage::Classify => "classify",
+            Stage::Bridge => "bridge",
+            Stage::Grep => "grep",
+        }
+    }
+}
+
+/// One progress notification.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PipelineEvent {
+    /// A stage began.
+    StageStarted(Stage),
+    /// A stage ended; `protected` is the number of symbols it protected
+    /// (always 0 for the graph stages).
+    StageFinished { stage: Stage, protected: usize },
+    /// A Python file finished Pass 1 (parsed or served from cache).
+    FileParsed {
+        path: PathBuf,
+        index: usize,
+        total: usize,
+    },
+    /// The grep shield finished searching one file.
+    GrepFileScanned { path: PathBuf },
+}
+
+/// Observer that ignores every event.
+pub fn silent(_: PipelineEvent) {}
@@ -2,58 +2,365 @@
 //!
 //! Scans non-Python files (HTML, JS, JSON, YAML, TOML, Markdown, etc.) for
 //! any of the given symbol names. Only symbols still dead after stages 0-4
-//! are passed to this stage, so the automaton is typically small.
+//! are passed to this stage, so the automaton is typically small. Names in
+//! `.py` string literals (`INSTALLED_APPS`, `"module:function"`) are collected
+//! by the link pass instead (see `ReferenceGraph::string_names`).
+//! Jupyter notebooks are searched in their cell sources only, never their
+//! outputs (see [`crate::notebook`]).
 //!
-//! **Memory model**: one mmap per file, zero heap allocation per match.
+//! Matches must be whole words, so `config` is not found in `app_config`;
+//! names shorter than [`GREP_MIN_NAME_LEN`] also need quotes or a colon
+//! around them. Every hit is reported with its file and line.
+//!
+//! **Memory model**: one mmap per file; matches are only copied out as hits.
 //! **Time complexity**: O(patterns·len + file_sizes) — single pass per file.
 
-use aho_corasick::{AhoCorasick, MatchKind};
+use crate::progress::{self, PipelineEvent};
+use aho_corasick::AhoCorasick;
+use common::diagnostics::{codes, Diagnostic, Diagnostics};
 use memmap2::Mmap;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
-use std::path::Path;
-use walkdir::WalkDir;
+use std::path::{Path, PathBuf};
 
 /// File extensions to scan for string references to Python symbols.
 ///
 /// Excludes `.py` files — those are already covered by the reference graph.
-const GREP_EXTENSIONS: &[&str] = &[
+pub const GREP_EXTENSIONS: &[&str] = &[
     // Web
     "html", "htm", "css", "scss", "js", "jsx", "ts", "tsx", "vue", "svelte", // Config
     "xml", "yaml", "yml", "toml", "json", "ini", "cfg", "env", "conf", // Templates
     "jinja", "j2", "mako", // Docs / Scripts
-    "md", "rst", "txt", "sh", "bash",
+    "md", "rst", "txt", "sh", "bash", // Notebooks (cell sources only)
+    "ipynb",
 ];
 
+/// Names shorter than this only count as grep shield hits in token context
+/// (quoted, or next to a colon); see [`is_shield_hit`].
+pub const GREP_MIN_NAME_LEN: usize = 4;
+
 /// Scans non-Python project files for occurrences of the given symbol names.
 ///
 /// Builds a single Aho-Corasick automaton from `dead_names` and runs it over
 /// every matching file in the project tree via `mmap`. Returns the subset of
-/// names that were found.
+/// names that were found as whole words (see [`is_shield_hit`]).
 ///
-/// Returns an empty set immediately if `dead_names` is empty (no automaton built).
+/// Empty and repeated names are dropped before the automaton is built (see
+/// [`unique_patterns`]); returns an empty set immediately if none is left.
 ///
 /// # Errors
 /// Returns an `anyhow::Error` only if automaton construction fails (malformed patterns).
-/// Individual file I/O errors are silently skipped.
+/// Files that cannot be read are skipped; [`grep_shield_observed`] reports them.
 pub fn grep_shield(dead_names: &[String], project_root: &Path) -> anyhow::Result<HashSet<String>> {
-    if dead_names.is_empty() {
-        return Ok(HashSet::new());
+    grep_shield_with_extensions(dead_names, project_root, GREP_EXTENSIONS)
+}
+
+/// Same as [`grep_shield`], searching files whose extension is in `extensions`
+/// instead of [`GREP_EXTENSIONS`].
+pub fn grep_shield_with_extensions<S: AsRef<str>>(
+    dead_names: &[String],
+    project_root: &Path,
+    extensions: &[S],
+) -> anyhow::Result<HashSet<String>> {
+    let found = grep_shield_observed(
+        dead_names,
+        project_root,
+        extensions,
+        GREP_MIN_NAME_LEN,
+        &mut Diagnostics::new(),
+        &mut progress::silent,
+    )?;
+    Ok(found.into_keys().collect())
+}
+
+/// Same as [`grep_shield_with_extensions`], emitting
+/// [`PipelineEvent::GrepFileScanned`] after each searched file, and mapping
+/// each found name to its hits. Names shorter than `min_name_len` only count
+/// in token context (see [`is_shield_hit`]). Files that cannot be read are
+/// recorded in `diagnostics`.
+///
+/// The walk stops after the first file in which every name has at least one
+/// hit, so later files' hits are not reported; use [`grep_shield_locate`] for
+/// all of them.
+pub fn grep_shield_observed<S: AsRef<str>>(
+    dead_names: &[String],
+    project_root: &Path,
+    extensions: &[S],
+    min_name_len: usize,
+    diagnostics: &mut Diagnostics,
+    progress: &mut dyn FnMut(PipelineEvent),
+) -> anyhow::Result<HashMap<String, Vec<GrepHit>>> {
+    let patterns = unique_patterns(dead_names);
+    if patterns.is_empty() {
+        return Ok(HashMap::new());
     }
 
-    // Build automaton once — O(sum of name lengths).
-    let ac = AhoCorasick::builder()
-        .match_kind(MatchKind::LeftmostFirst)
-        .build(dead_names)
-        .map_err(|e| anyhow::anyhow!("AhoCorasick build failed: {}", e))?;
+    let ac = build_automaton(&patterns)?;
+    let mut found: HashMap<String, Vec<GrepHit>> = HashMap::new();
+
+    search_files(
+        project_root,
+        extensions,
+        diagnostics,
+        &mut |path, contents| {
+            for (pattern, hit) in file_hits(&ac, min_name_len, path, contents) {
+                found
+                    .entry(patterns[pattern].to_string())
+                    .or_default()
+                    .push(hit);
+            }
+            progress(PipelineEvent::GrepFileScanned {
+                path: path.to_path_buf(),
+            });
+            // Early exit: every symbol has a location.
+            found.len() == patterns.len()
+        },
+    );
+
+    Ok(found)
+}
+
+/// A single grep shield match.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GrepHit {
+    /// File containing the match.
+    pub file: PathBuf,
+    /// 1-based line of the match within `file`, or within the cell.
+    pub line: u32,
+    /// Byte offset of the match within `file`, or within the cell's source.
+    pub byte_offset: usize,
+    /// 0-based index of the notebook cell holding the match; `None` outside
+    /// notebooks.
+    pub cell: Option<u32>,
+}
+
+impl GrepHit {
+    /// `file:line`, or `notebook.ipynb[cell 3]:line`, with forward slashes.
+    pub fn location(&self) -> String {
+        let file = self.file.to_string_lossy().replace('\\', "/");
+        match self.cell {
+            Some(cell) => format!("{}[cell {}]:{}", file, cell, self.line),
+            None => format!("{}:{}", file, self.line),
+        }
+    }
+}
+
+/// Same as [`grep_shield_observed`], but searches every file and returns
+/// every hit. Used by `janitor why`.
+pub fn grep_shield_locate<S: AsRef<str>>(
+    names: &[String],
+    project_root: &Path,
+    extensions: &[S],
+    min_name_len: usize,
+) -> anyhow::Result<HashMap<String, Vec<GrepHit>>> {
+    let patterns = unique_patterns(names);
+    if patterns.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let ac = build_automaton(&patterns)?;
+    let mut found: HashMap<String, Vec<GrepHit>> = HashMap::new();
+
+    search_files(
+        project_root,
+        extensions,
+        &mut Diagnostics::new(),
+        &mut |path, contents| {
+            for (pattern, hit) in file_hits(&ac, min_name_len, path, contents) {
+                found
+                    .entry(patterns[pattern].to_string())
+                    .or_default()
+                    .push(hit);
+            }
+            false
+        },
+    );
+
+    Ok(found)
+}
+
+/// Grep shield hits in one file, as `(pattern index, hit)` in offset order.
+///
+/// Overlapping matches are searched so `helper` is still found after
+/// `helper_factory`; hits that fail [`is_shield_hit`] are dropped. A `.ipynb`
+/// file is searched in its cell sources (see [`notebook_hits`]).
+fn file_hits(
+    ac: &AhoCorasick,
+    min_name_len: usize,
+    path: &Path,
+    contents: &[u8],
+) -> Vec<(usize, GrepHit)> {
+    if path.extension().is_some_and(|ext| ext == "ipynb") {
+        return notebook_hits(ac, min_name_len, path, contents);
+    }
+    let hits = shield_matches(ac, min_name_len, contents);
+    if hits.is_empty() {
+        return Vec::new();
+    }
 
-    let mut found: HashSet<String> = HashSet::new();
+    let newlines: Vec<usize> = contents
+        .iter()
+        .enumerate()
+        .filter_map(|(i, &b)| (b == b'\n').then_some(i))
+        .collect();
+    hits.into_iter()
+        .map(|(pattern, start)| {
+            let line = newlines.partition_point(|&n| n < start) as u32 + 1;
+            let hit = GrepHit {
+                file: path.to_path_buf(),
+                line,
+                byte_offset: start,
+                cell: None,
+            };
+            (pattern, hit)
+        })
+        .collect()
+}
 
-    for entry in WalkDir::new(project_root)
+/// [`file_hits`] for a notebook: only code and Markdown cell sources are
+/// searched, and each hit is located by cell, line and offset within it. A
+/// file that is not valid notebook JSON has no hits.
+fn notebook_hits(
+    ac: &AhoCorasick,
+    min_name_len: usize,
+    path: &Path,
+    contents: &[u8],
+) -> Vec<(usize, GrepHit)> {
+    let Some(notebook) = crate::notebook::Notebook::parse(contents) else {
+        return Vec::new();
+    };
+    let text = notebook.text();
+    let bytes = text.text.as_bytes();
+    shield_matches(ac, min_name_len, bytes)
         .into_iter()
-        .filter_entry(|e| !is_scan_excluded(e.path()))
-        .flatten()
-    {
+        .map(|(pattern, start)| {
+            let (cell, offset) = text.locate(start);
+            let cell_start = start - offset;
+            let line = bytes[cell_start..start]
+                .iter()
+                .filter(|&&b| b == b'\n')
+                .count() as u32
+                + 1;
+            let hit = GrepHit {
+                file: path.to_path_buf(),
+                line,
+                byte_offset: offset,
+                cell: Some(cell),
+            };
+            (pattern, hit)
+        })
+        .collect()
+}
+
+/// `(pattern index, start)` of the shield hits in `haystack`, by start.
+fn shield_matches(ac: &AhoCorasick, min_name_len: usize, haystack: &[u8]) -> Vec<(usize, usize)> {
+    let mut hits: Vec<(usize, usize)> = ac
+        .find_overlapping_iter(haystack)
+        .filter(|mat| is_shield_hit(haystack, mat.start(), mat.end(), min_name_len))
+        .map(|mat| (mat.pattern().as_usize(), mat.start()))
+        .collect();
+    hits.sort_by_key(|&(_, start)| start);
+    hits
+}
+
+/// Whether the match `haystack[start..end]` counts for the grep shield.
+///
+/// The match must be a whole word: `config` inside `app_config` or
+/// `configuration` is not a mention. A name shorter than `min_name_len`
+/// (`run`, `get`) is so common in prose that it also needs token context:
+/// quoted on both sides with the same quote (`"run"`, `` `run` ``), or next
+/// to a colon (`handler: run`, `"module:run"`, `run:`).
+fn is_shield_hit(haystack: &[u8], start: usize, end: usize, min_name_len: usize) -> bool {
+    if !common::text::is_word_match(haystack, start, end) {
+        return false;
+    }
+    if end - start >= min_name_len {
+        return true;
+    }
+    let before = start.checked_sub(1).map(|i| haystack[i]);
+    let after = haystack.get(end).copied();
+    if let (Some(open), Some(close)) = (before, after) {
+        if open == close && matches!(open, b'"' | b'\'' | b'`') {
+            return true;
+        }
+    }
+    let colon_before = haystack[..start]
+        .iter()
+        .rev()
+        .find(|&&b| b != b' ' && b != b'\t')
+        == Some(&b':');
+    colon_before || after == Some(b':')
+}
+
+/// Whole-word occurrences of `names` in `files` (the collected test files of
+/// the test-fingerprint stage), as name → files mentioning it. Overlapping
+/// matches are kept so `helper` is found next to `helper_factory`. Files that
+/// cannot be read are recorded in `diagnostics`.
+pub fn word_mentions(
+    names: &[String],
+    files: &[PathBuf],
+    diagnostics: &mut Diagnostics,
+) -> anyhow::Result<HashMap<String, HashSet<PathBuf>>> {
+    let mut mentions: HashMap<String, HashSet<PathBuf>> = HashMap::new();
+    let patterns = unique_patterns(names);
+    if patterns.is_empty() {
+        return Ok(mentions);
+    }
+
+    let ac = build_automaton(&patterns)?;
+    for path in files {
+        let contents = match std::fs::read(path) {
+            Ok(contents) => contents,
+            Err(e) => {
+                diagnostics.push(
+                    Diagnostic::warning(codes::UNREADABLE_FILE, format!("not searched: {e}"))
+                        .at(crate::graph::file_key(path)),
+                );
+                continue;
+            }
+        };
+        for mat in ac.find_overlapping_iter(&contents) {
+            if common::text::is_word_match(&contents, mat.start(), mat.end()) {
+                mentions
+                    .entry(patterns[mat.pattern().as_usize()].to_string())
+                    .or_default()
+                    .insert(path.clone());
+            }
+        }
+    }
+    Ok(mentions)
+}
+
+/// `names` without empty strings and repeats, in first-seen order.
+///
+/// Every search here reports by name, so one pattern per distinct name
+/// covers all the symbols sharing it (`save` in two modules). An empty
+/// pattern would match at every offset and shadow the real ones.
+fn unique_patterns(names: &[String]) -> Vec<&str> {
+    let mut seen = HashSet::new();
+    names
+        .iter()
+        .map(String::as_str)
+        .filter(|name| !name.is_empty() && seen.insert(*name))
+        .collect()
+}
+
+/// Builds the automaton once — O(sum of name lengths). Standard match
+/// semantics, so every search can iterate overlapping matches.
+fn build_automaton(names: &[&str]) -> anyhow::Result<AhoCorasick> {
+    AhoCorasick::new(names).map_err(|e| anyhow::anyhow!("AhoCorasick build failed: {}", e))
+}
+
+/// Calls `on_file(path, contents)` for every file under `project_root` whose
+/// extension is in `extensions`, recording files it cannot read in
+/// `diagnostics`. The walk stops once `on_file` returns `true`.
+fn search_files<S: AsRef<str>>(
+    project_root: &Path,
+    extensions: &[S],
+    diagnostics: &mut Diagnostics,
+    on_file: &mut dyn FnMut(&Path, &[u8]) -> bool,
+) {
+    for entry in common::walk::walk(project_root).flatten() {
         let path = entry.path();
         if !path.is_file() {
             continue;
@@ -63,129 +370,372 @@ pub fn grep_shield(dead_names: &[String], project_root: &Path) -> anyhow::Result
             .extension()
             .and_then(|s| s.to_str())
             .unwrap_or_default();
-        if !GREP_EXTENSIONS.contains(&ext) {
+        if !extensions.iter().any(|e| e.as_ref() == ext) {
             continue;
         }
 
-        let file = match File::open(path) {
-            Ok(f) => f,
-            Err(_) => continue,
-        };
         // SAFETY: mmap is read-only; the file handle outlives the mmap.
-        let mmap = match unsafe { Mmap::map(&file) } {
+        let mmap = match File::open(path).and_then(|file| unsafe { Mmap::map(&file) }) {
             Ok(m) => m,
-            Err(_) => continue,
+            Err(e) => {
+                diagnostics.push(
+                    Diagnostic::warning(codes::UNREADABLE_FILE, format!("not searched: {e}"))
+                        .at(crate::graph::file_key(path)),
+                );
+                continue;
+            }
         };
 
-        for mat in ac.find_iter(&*mmap) {
-            found.insert(dead_names[mat.pattern().as_usize()].clone());
-        }
-
-        // Early exit: all symbols accounted for.
-        if found.len() == dead_names.len() {
+        if on_file(path, &mmap) {
             break;
         }
     }
+}
 
-    Ok(found)
+/// An HTTP endpoint: a path and the methods it is used with.
+///
+/// On the JS/TS side ([`bridge_extract`]) `path` is what the frontend calls,
+/// possibly with `${…}` interpolations or an open tail (`"/users/" + id` is
+/// kept as `/users/`). On the Python side ([`decorator_route`]) it is the
+/// route template (`/users/{id}`). `methods` are upper-case; empty means
+/// unknown, which is compatible with any method.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ApiRoute {
+    pub methods: Vec<String>,
+    pub path: String,
 }
 
-/// Extracts REST API path strings from JavaScript and TypeScript files.
+impl std::fmt::Display for ApiRoute {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if !self.methods.is_empty() {
+            write!(f, "{} ", self.methods.join("|"))?;
+        }
+        f.write_str(&self.path)
+    }
+}
+
+/// Lower-case HTTP verbs, as decorator and `axios` method names.
+const HTTP_METHODS: &[&str] = &["get", "post", "put", "patch", "delete", "head", "options"];
+
+/// Extracts the API calls JavaScript and TypeScript files make.
 ///
-/// Scans for single- or double-quoted string literals that start with `/` in all
-/// `.js`, `.jsx`, `.ts`, and `.tsx` files under `project_root`. Returns the set of
-/// path strings found (e.g. `"/users"`, `"/api/v1/items"`).
+/// Searches `.js`, `.jsx`, `.ts` and `.tsx` files under `project_root` for
+/// the first argument of `fetch(…)`, `axios(…)` and `axios.<verb>(…)` calls,
+/// and for template literals starting with `/` (`` `/users/${id}` ``, also
+/// after a leading `${base}`). A string followed by `+` keeps an open tail.
+/// The method comes from the `axios` verb or a `method: "…"` option.
 ///
-/// These paths are used by the bridge shield in the pipeline: if a Python entity's
-/// decorator text references one of these paths, it is presumed to serve that endpoint
-/// and is therefore protected.
+/// These routes are used by the bridge shield in the pipeline: a Python
+/// entity whose route decorator [`route_matches`] one of them presumably
+/// serves that endpoint and is therefore protected.
 ///
 /// # Errors
 /// Individual file I/O errors are silently skipped.
-pub fn bridge_extract(project_root: &Path) -> anyhow::Result<HashSet<String>> {
-    let mut api_paths: HashSet<String> = HashSet::new();
+pub fn bridge_extract(project_root: &Path) -> anyhow::Result<HashSet<ApiRoute>> {
+    let mut routes: HashSet<ApiRoute> = HashSet::new();
 
-    for entry in WalkDir::new(project_root)
-        .into_iter()
-        .filter_entry(|e| !is_scan_excluded(e.path()))
-        .flatten()
-    {
-        let path = entry.path();
-        if !path.is_file() {
-            continue;
+    search_files(
+        project_root,
+        &["js", "jsx", "ts", "tsx"],
+        &mut Diagnostics::new(),
+        &mut |_, src| {
+            extract_api_calls(src, &mut routes);
+            false
+        },
+    );
+    Ok(routes)
+}
+
+/// [`bridge_extract`] over one file's source.
+fn extract_api_calls(src: &[u8], routes: &mut HashSet<ApiRoute>) {
+    let mut i = 0usize;
+    while i < src.len() {
+        match src[i] {
+            b'"' | b'\'' => i = skip_string(src, i),
+            b'`' => {
+                let end = skip_template(src, i);
+                let body = &src[i + 1..end.saturating_sub(1).max(i + 1)];
+                if let Some(path) = template_path(body).filter(|_| src[end - 1] == b'`') {
+                    routes.insert(ApiRoute {
+                        methods: Vec::new(),
+                        path,
+                    });
+                }
+                i = end;
+            }
+            b if b.is_ascii_alphabetic() && (i == 0 || !is_js_ident(src[i - 1])) => {
+                let start = i;
+                while i < src.len() && (is_js_ident(src[i]) || src[i] == b'.') {
+                    i += 1;
+                }
+                let callee = std::str::from_utf8(&src[start..i]).unwrap_or("");
+                let verb = match callee {
+                    "fetch" | "axios" | "axios.request" => None,
+                    _ => match callee.strip_prefix("axios.") {
+                        Some(verb) if HTTP_METHODS.contains(&verb) => Some(verb),
+                        _ => continue,
+                    },
+                };
+                let open = skip_space(src, i);
+                if src.get(open) != Some(&b'(') {
+                    continue;
+                }
+                i = open + 1;
+                if let Some((mut route, end)) = call_route(src, i) {
+                    if let Some(verb) = verb {
+                        route.methods = vec![verb.to_ascii_uppercase()];
+                    }
+                    routes.insert(route);
+                    // The argument is not also a bare template literal.
+                    i = end;
+                }
+            }
+            _ => i += 1,
         }
-        let ext = path
-            .extension()
-            .and_then(|s| s.to_str())
-            .unwrap_or_default();
-        if !matches!(ext, "js" | "jsx" | "ts" | "tsx") {
-            continue;
+    }
+}
+
+/// The route of a call whose argument list starts at `args`: the path of the
+/// first argument if it is a literal, and the `method` option if there is one.
+/// Also returns the index just past that literal.
+fn call_route(src: &[u8], args: usize) -> Option<(ApiRoute, usize)> {
+    let start = skip_space(src, args);
+    let end = match src.get(start)? {
+        b'"' | b'\'' => skip_string(src, start),
+        b'`' => skip_template(src, start),
+        _ => return None,
+    };
+    if end < start + 2 || src[end - 1] != src[start] {
+        return None;
+    }
+    let body = &src[start + 1..end - 1];
+    let mut path = if src[start] == b'`' {
+        template_path(body)?
+    } else {
+        let path = std::str::from_utf8(body).ok()?;
+        if path.len() < 2 || !path.starts_with('/') || !path.bytes().all(|b| b.is_ascii_graphic()) {
+            return None;
         }
+        path.to_string()
+    };
+    // `"/users/" + id`: more segments follow.
+    let concatenated = src.get(skip_space(src, end)) == Some(&b'+');
+    if concatenated && !path.contains('?') && !path.ends_with('/') {
+        path.push_str("${}/");
+    }
 
-        let file = match File::open(path) {
-            Ok(f) => f,
-            Err(_) => continue,
-        };
-        // SAFETY: mmap is read-only; the file handle outlives the mmap.
-        let mmap = match unsafe { Mmap::map(&file) } {
-            Ok(m) => m,
-            Err(_) => continue,
-        };
-        let src = &*mmap;
+    let close = matching_paren(src, args);
+    let methods = method_option(&src[end..close]).into_iter().collect();
+    Some((ApiRoute { methods, path }, end))
+}
 
-        // Scan for quoted strings starting with '/'
-        let mut i = 0usize;
-        while i < src.len() {
-            let q = src[i];
-            if q == b'"' || q == b'\'' {
-                i += 1;
-                if i < src.len() && src[i] == b'/' {
-                    let start = i;
-                    // Scan to closing quote (no multiline or escape sequences needed here)
-                    while i < src.len() && src[i] != q && src[i] != b'\n' {
-                        i += 1;
-                    }
-                    if i < src.len() && src[i] == q {
-                        if let Ok(s) = std::str::from_utf8(&src[start..i]) {
-                            let s = s.trim();
-                            // Only keep non-trivial paths with valid ASCII-printable chars
-                            if s.len() > 1
-                                && s.bytes()
-                                    .all(|b| b.is_ascii_graphic() && b != b'"' && b != b'\'')
-                            {
-                                api_paths.insert(s.to_string());
-                            }
-                        }
-                    }
-                    i = i.saturating_add(1); // skip closing quote
-                }
-            } else {
+/// The value of a `method: "post"` option in a call's remaining arguments.
+fn method_option(args: &[u8]) -> Option<String> {
+    let at = args.windows(6).position(|w| w == b"method")?;
+    let rest = &args[at + 6..];
+    let rest = rest
+        .strip_prefix(b"\"")
+        .or(rest.strip_prefix(b"'"))
+        .unwrap_or(rest);
+    let colon = skip_space(rest, 0);
+    if rest.get(colon) != Some(&b':') {
+        return None;
+    }
+    let quote = skip_space(rest, colon + 1);
+    let q = *rest.get(quote).filter(|q| matches!(q, b'"' | b'\''))?;
+    let value: Vec<u8> = rest[quote + 1..]
+        .iter()
+        .take_while(|&&b| b != q)
+        .copied()
+        .collect();
+    let value = String::from_utf8(value).ok()?;
+    HTTP_METHODS
+        .contains(&value.to_ascii_lowercase().as_str())
+        .then(|| value.to_ascii_uppercase())
+}
+
+/// The path in a template literal's `body`, when it starts with `/` (after an
+/// optional leading `${base}`).
+fn template_path(body: &[u8]) -> Option<String> {
+    let mut body = std::str::from_utf8(body).ok()?;
+    if body.starts_with("${") {
+        body = &body[body.find('}')? + 1..];
+    }
+    (body.len() > 1 && body.starts_with('/') && !body.contains(char::is_whitespace))
+        .then(|| body.to_string())
+}
+
+/// Index just past the string literal opening at `start`.
+fn skip_string(src: &[u8], start: usize) -> usize {
+    let quote = src[start];
+    let mut i = start + 1;
+    while i < src.len() && src[i] != quote && src[i] != b'\n' {
+        i += if src[i] == b'\\' { 2 } else { 1 };
+    }
+    (i + 1).min(src.len())
+}
+
+/// Index just past the template literal opening at `start`, stepping over
+/// `${…}` interpolations.
+fn skip_template(src: &[u8], start: usize) -> usize {
+    let mut i = start + 1;
+    let mut depth = 0usize;
+    while i < src.len() {
+        match src[i] {
+            b'\\' => i += 1,
+            b'$' if src.get(i + 1) == Some(&b'{') => {
+                depth += 1;
                 i += 1;
             }
+            b'}' if depth > 0 => depth -= 1,
+            b'`' if depth == 0 => return i + 1,
+            _ => {}
         }
+        i += 1;
     }
+    src.len()
+}
 
-    Ok(api_paths)
-}
-
-/// Returns `true` if the path should be excluded from grep scanning.
-fn is_scan_excluded(path: &Path) -> bool {
-    path.file_name()
-        .and_then(|s| s.to_str())
-        .map(|name| {
-            matches!(
-                name,
-                "__pycache__"
-                    | ".git"
-                    | ".janitor"
-                    | "venv"
-                    | ".venv"
-                    | "target"
-                    | "node_modules"
-                    | ".pytest_cache"
-            )
-        })
-        .unwrap_or(false)
+/// Index of the `)` closing the argument list that starts at `args`, or the
+/// end of `src`.
+fn matching_paren(src: &[u8], args: usize) -> usize {
+    let mut depth = 0usize;
+    let mut i = args;
+    while i < src.len() {
+        match src[i] {
+            b'"' | b'\'' => {
+                i = skip_string(src, i);
+                continue;
+            }
+            b'`' => {
+                i = skip_template(src, i);
+                continue;
+            }
+            b'(' => depth += 1,
+            b')' if depth == 0 => return i,
+            b')' => depth -= 1,
+            _ => {}
+        }
+        i += 1;
+    }
+    src.len()
+}
+
+fn skip_space(src: &[u8], mut i: usize) -> usize {
+    while i < src.len() && src[i].is_ascii_whitespace() {
+        i += 1;
+    }
+    i
+}
+
+fn is_js_ident(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_' || b == b'$'
+}
+
+/// The route a Python decorator declares: its first string argument starting
+/// with `/`, with the method from the decorator name (`app.get`,
+/// `router.post`) or a `methods=[…]` argument. `None` for decorators without
+/// a path (`staticmethod`, `app.task(name="x")`).
+pub fn decorator_route(decorator: &str) -> Option<ApiRoute> {
+    let (callee, args) = decorator.split_once('(')?;
+    let bytes = args.as_bytes();
+    let mut path = None;
+    let mut i = 0;
+    while i < bytes.len() {
+        if matches!(bytes[i], b'"' | b'\'') {
+            let end = skip_string(bytes, i);
+            let literal = &args[i + 1..end.saturating_sub(1).max(i + 1)];
+            if literal.starts_with('/') {
+                path = Some(literal.to_string());
+                break;
+            }
+            i = end;
+        } else {
+            i += 1;
+        }
+    }
+    let path = path?;
+
+    let verb = callee.rsplit('.').next().unwrap_or(callee).trim();
+    let methods = if HTTP_METHODS.contains(&verb) {
+        vec![verb.to_ascii_uppercase()]
+    } else {
+        args.split_once("methods")
+            .and_then(|(_, rest)| rest.split_once('['))
+            .and_then(|(_, rest)| rest.split_once(']'))
+            .map(|(list, _)| {
+                list.split(',')
+                    .map(|m| m.trim().trim_matches(|c| c == '"' || c == '\''))
+                    .filter(|m| !m.is_empty())
+                    .map(str::to_ascii_uppercase)
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+    Some(ApiRoute { methods, path })
+}
+
+/// Whether the frontend call `call` can reach the Python route `template`.
+///
+/// Methods must overlap unless either side is unknown. Paths are compared
+/// segment by segment, ignoring query strings and trailing slashes:
+/// `{id}`, `<int:id>` and `:id` in the template match any single segment, and
+/// `${…}` in the call any text within one. A call ending in `/` (`"/users/" + id`) is a prefix
+/// and also matches longer templates; any other call must have as many
+/// segments as the template, so `/users` does not reach `/users/{id}/audit`.
+pub fn route_matches(template: &ApiRoute, call: &ApiRoute) -> bool {
+    let methods_overlap = template.methods.is_empty()
+        || call.methods.is_empty()
+        || template.methods.iter().any(|m| call.methods.contains(m));
+    methods_overlap && path_matches(&template.path, &call.path)
+}
+
+/// The path half of [`route_matches`].
+pub fn path_matches(template: &str, call: &str) -> bool {
+    let (template, _) = route_segments(template);
+    let (call, open) = route_segments(call);
+    if call.is_empty() {
+        return template.is_empty() && !open;
+    }
+    let lengths_fit = if open {
+        call.len() <= template.len()
+    } else {
+        call.len() == template.len()
+    };
+    lengths_fit
+        && template
+            .iter()
+            .zip(&call)
+            .all(|(t, c)| segment_matches(t, c))
+}
+
+/// One segment of [`path_matches`]. A call segment with interpolations
+/// (`orders-${id}`) matches a template literal with the same text around them.
+fn segment_matches(template: &str, call: &str) -> bool {
+    if is_path_parameter(template) || template == call {
+        return true;
+    }
+    let (Some(first), Some(last)) = (call.find("${"), call.rfind('}')) else {
+        return false;
+    };
+    let (prefix, suffix) = (&call[..first], &call[last + 1..]);
+    template.len() > prefix.len() + suffix.len()
+        && template.starts_with(prefix)
+        && template.ends_with(suffix)
+}
+
+/// Non-empty segments of `path` before any `?` or `#`, and whether it ends
+/// with `/`.
+fn route_segments(path: &str) -> (Vec<&str>, bool) {
+    let path = path.split(['?', '#']).next().unwrap_or(path);
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    (segments, path.len() > 1 && path.ends_with('/'))
+}
+
+/// A template segment standing for any value: `{id}` (FastAPI), `<int:id>`
+/// (Flask) or `:id` (Express-style).
+fn is_path_parameter(segment: &str) -> bool {
+    segment.starts_with(':') || segment.contains('{') || segment.starts_with('<')
 }
 
 #[cfg(test)]
@@ -216,6 +766,20 @@ mod tests {
         fs::remove_dir_all(tmp).ok();
     }
 
+    #[test]
+    fn test_gitignored_file_not_searched() {
+        let tmp = std::env::temp_dir().join("test_grep_gitignore");
+        fs::create_dir_all(tmp.join("build")).ok();
+        fs::write(tmp.join(".gitignore"), b"build/\n").ok();
+        fs::write(tmp.join("build/routes.yaml"), b"handler: stale_fn").ok();
+
+        let names = vec!["stale_fn".to_string()];
+        let found = grep_shield(&names, &tmp).unwrap();
+        assert!(found.is_empty());
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
     #[test]
     fn test_symbol_not_found() {
         let tmp = std::env::temp_dir().join("test_grep_not_found");
@@ -249,6 +813,150 @@ mod tests {
         fs::remove_dir_all(tmp).ok();
     }
 
+    #[test]
+    fn test_locate_reports_file_and_offset() {
+        let tmp = std::env::temp_dir().join("test_grep_locate");
+        fs::remove_dir_all(&tmp).ok();
+        fs::create_dir_all(&tmp).ok();
+
+        fs::write(
+            tmp.join("routes.yaml"),
+            b"handler: process_request
+",
+        )
+        .ok();
+        fs::write(
+            tmp.join("skip.py"),
+            b"process_request()
+",
+        )
+        .ok();
+
+        let names = vec!["process_request".to_string()];
+        let found = grep_shield_locate(&names, &tmp, GREP_EXTENSIONS, GREP_MIN_NAME_LEN).unwrap();
+        assert_eq!(found.len(), 1);
+        let hits = &found["process_request"];
+        assert_eq!(hits.len(), 1);
+        assert!(hits[0].file.ends_with("routes.yaml"));
+        assert_eq!(hits[0].byte_offset, 9);
+        assert_eq!(hits[0].line, 1);
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_repeated_and_empty_names_are_searched_once() {
+        let tmp = std::env::temp_dir().join("test_grep_repeated_names");
+        fs::remove_dir_all(&tmp).ok();
+        fs::create_dir_all(&tmp).ok();
+        fs::write(tmp.join("README.md"), b"Call save() before exit.\n").ok();
+
+        // `save` is defined in two modules; `""` is what a nameless sentinel forwards.
+        let names: Vec<String> = ["save", "", "save", "load"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let found = grep_shield(&names, &tmp).unwrap();
+        assert_eq!(found, HashSet::from(["save".to_string()]));
+
+        let found = grep_shield_locate(&names, &tmp, GREP_EXTENSIONS, GREP_MIN_NAME_LEN).unwrap();
+        assert_eq!(found.len(), 1, "{:?}", found);
+        assert_eq!(found["save"][0].byte_offset, 5);
+
+        assert!(grep_shield(&["".to_string()], &tmp).unwrap().is_empty());
+        let mut diagnostics = Diagnostics::new();
+        let files = [tmp.join("README.md"), tmp.join("gone.md")];
+        let mentions = word_mentions(&names, &files, &mut diagnostics).unwrap();
+        assert_eq!(mentions.len(), 1);
+        let unreadable = diagnostics.iter().next().unwrap();
+        assert_eq!(unreadable.code, codes::UNREADABLE_FILE);
+        assert!(unreadable.file.as_deref().unwrap().ends_with("gone.md"));
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_interior_matches_do_not_shield() {
+        let tmp = std::env::temp_dir().join("test_grep_whole_word");
+        fs::remove_dir_all(&tmp).ok();
+        fs::create_dir_all(&tmp).ok();
+        fs::write(
+            tmp.join("settings.yaml"),
+            b"app_config:\n  configuration: prod\n  access_token: abc\n  tokens: 3\n",
+        )
+        .ok();
+
+        let names = vec!["config".to_string(), "token".to_string()];
+        assert!(grep_shield(&names, &tmp).unwrap().is_empty());
+
+        fs::write(tmp.join("deploy.yaml"), b"steps:\n  - config\n").ok();
+        let found = grep_shield(&names, &tmp).unwrap();
+        assert_eq!(found, HashSet::from(["config".to_string()]));
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_short_names_need_token_context() {
+        let tmp = std::env::temp_dir().join("test_grep_short_names");
+        fs::remove_dir_all(&tmp).ok();
+        fs::create_dir_all(&tmp).ok();
+        fs::write(tmp.join("README.md"), b"Run it and get the app going.\n").ok();
+        fs::write(
+            tmp.join("jobs.yaml"),
+            b"nightly:\n  handler: run\n  entry: \"tasks:get\"\n",
+        )
+        .ok();
+
+        let names: Vec<String> = ["run", "get", "app"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let found = grep_shield_locate(&names, &tmp, GREP_EXTENSIONS, GREP_MIN_NAME_LEN).unwrap();
+        let mut found_names: Vec<&String> = found.keys().collect();
+        found_names.sort();
+        assert_eq!(found_names, ["get", "run"]);
+        assert!(found["run"].iter().all(|h| h.file.ends_with("jobs.yaml")));
+
+        // With the knob at 1 every whole word counts.
+        let found = grep_shield_locate(&names, &tmp, GREP_EXTENSIONS, 1).unwrap();
+        assert!(found["app"][0].file.ends_with("README.md"));
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_hits_report_every_line() {
+        let tmp = std::env::temp_dir().join("test_grep_hit_lines");
+        fs::remove_dir_all(&tmp).ok();
+        fs::create_dir_all(&tmp).ok();
+        fs::write(
+            tmp.join("notes.md"),
+            b"# Helpers\n\nUse `helper_factory` or `helper`.\n\nSee helper.\n",
+        )
+        .ok();
+
+        let names = vec!["helper".to_string(), "helper_factory".to_string()];
+        let found = grep_shield_locate(&names, &tmp, GREP_EXTENSIONS, GREP_MIN_NAME_LEN).unwrap();
+        let lines = |name: &str| found[name].iter().map(|h| h.line).collect::<Vec<_>>();
+        assert_eq!(lines("helper"), vec![3, 5]);
+        assert_eq!(lines("helper_factory"), vec![3]);
+
+        let mut progress = progress::silent;
+        let observed = grep_shield_observed(
+            &names,
+            &tmp,
+            GREP_EXTENSIONS,
+            GREP_MIN_NAME_LEN,
+            &mut Diagnostics::new(),
+            &mut progress,
+        )
+        .unwrap();
+        assert_eq!(observed, found);
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
     #[test]
     fn test_bridge_extract_finds_api_paths() {
         let tmp = std::env::temp_dir().join("test_bridge_api");
@@ -260,7 +968,8 @@ mod tests {
         )
         .ok();
 
-        let paths = bridge_extract(&tmp).unwrap();
+        let routes = bridge_extract(&tmp).unwrap();
+        let paths: HashSet<&str> = routes.iter().map(|r| r.path.as_str()).collect();
         assert!(paths.contains("/users"), "should find /users");
         assert!(paths.contains("/items/123"), "should find /items/123");
         assert!(
@@ -271,6 +980,107 @@ mod tests {
         fs::remove_dir_all(tmp).ok();
     }
 
+    #[test]
+    fn test_bridge_extract_calls_and_templates_only() {
+        let tmp = std::env::temp_dir().join("test_bridge_calls");
+        fs::remove_dir_all(&tmp).ok();
+        fs::create_dir_all(&tmp).ok();
+        fs::write(
+            tmp.join("client.ts"),
+            concat!(
+                "const STATIC = \"/static\";\n",
+                "axios.post('/orders', body);\n",
+                "fetch(\"/users/\" + id, { method: \"DELETE\" });\n",
+                "const audit = `${API}/users/${id}/audit`;\n",
+                "fetch(`/reports/${year}`);\n",
+                "fetch(\"/search?q=\" + q);\n",
+            ),
+        )
+        .ok();
+
+        let routes = bridge_extract(&tmp).unwrap();
+        let mut found: Vec<String> = routes.iter().map(ApiRoute::to_string).collect();
+        found.sort();
+        assert_eq!(
+            found,
+            [
+                "/reports/${year}",
+                "/search?q=",
+                "/users/${id}/audit",
+                "DELETE /users/",
+                "POST /orders",
+            ]
+        );
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_path_matches_templates() {
+        let cases = [
+            // (Python template, JS call, matches)
+            ("/users", "/users", true),
+            ("/users/", "/users", true),
+            ("/users", "/users/", true),
+            ("/users/{id}", "/users/42", true),
+            ("/users/{id}", "/users/${id}", true),
+            ("/users/<int:id>", "/users/7", true),
+            ("/users/:id", "/users/7", true),
+            ("/users/{id}", "/users/", true),
+            ("/users/{id}/audit", "/users/", true),
+            ("/users/{id}/audit", "/users/${id}/audit", true),
+            ("/users/{id}", "/users/42?expand=1", true),
+            ("/users/{id}/audit", "/users", false),
+            ("/users/{id}", "/users", false),
+            ("/users", "/users/42", false),
+            ("/users/{id}/audit", "/users/${id}/history", false),
+            ("/static/{path}", "/static", false),
+            ("/assets/{path}", "/static/", false),
+            ("/orders/{id}", "/orders-${id}/", false),
+            ("/orders-{id}", "/orders-${id}", true),
+            ("/v1/orders-7", "/v1/orders-${id}", true),
+            ("/v1/orders", "/v1/items-${id}", false),
+            ("/", "/", true),
+            ("/users", "/", false),
+        ];
+        for (template, call, expected) in cases {
+            assert_eq!(
+                path_matches(template, call),
+                expected,
+                "{} vs {}",
+                template,
+                call
+            );
+        }
+    }
+
+    #[test]
+    fn test_decorator_route_and_methods() {
+        let route = |d: &str| decorator_route(d).map(|r| r.to_string());
+        assert_eq!(
+            route("app.get(\"/users/{id}\")").as_deref(),
+            Some("GET /users/{id}")
+        );
+        assert_eq!(
+            route("bp.route('/items', methods=['GET', 'POST'])").as_deref(),
+            Some("GET|POST /items")
+        );
+        assert_eq!(route("router.api_route(\"/x\")").as_deref(), Some("/x"));
+        assert_eq!(route("app.task(name=\"cleanup\")"), None);
+        assert_eq!(route("staticmethod"), None);
+
+        let call = |methods: &[&str], path: &str| ApiRoute {
+            methods: methods.iter().map(|m| m.to_string()).collect(),
+            path: path.to_string(),
+        };
+        let get = decorator_route("app.get(\"/users/{id}\")").unwrap();
+        assert!(route_matches(&get, &call(&[], "/users/1")));
+        assert!(route_matches(&get, &call(&["GET"], "/users/1")));
+        assert!(!route_matches(&get, &call(&["DELETE"], "/users/1")));
+        let any = decorator_route("app.route(\"/users/<id>\")").unwrap();
+        assert!(route_matches(&any, &call(&["DELETE"], "/users/")));
+    }
+
     #[test]
     fn test_bridge_extract_empty_dir() {
         let tmp = std::env::temp_dir().join("test_bridge_empty");
@@ -0,0 +1,358 @@
+//! # Unused Import Detection
+//!
+//! Finds Python import bindings that no identifier in the module ever reads.
+//!
+//! A name counts as used when it appears as an identifier anywhere outside an
+//! import statement, or as a whole word inside any string literal (string
+//! annotations, `__all__` entries, `getattr` targets). Star imports,
+//! `__future__` imports, `__init__.py` re-export hubs, and statements carrying
+//! a `# noqa` comment are never reported. Neither are imports kept for their
+//! side effects: a bare `import pkg.module`, or `from pkg import module` when
+//! the scan knows `module` is a submodule (plugin and signal registration).
+//!
+//! Removing the last statement of a block (a `try:` or `if TYPE_CHECKING:`
+//! body) leaves `pass` in its place.
+
+use crate::AnatomistError;
+use std::collections::HashSet;
+use tree_sitter::{Node, Parser};
+
+/// One unused binding introduced by an import statement.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct UnusedImport {
+    /// Path of the file holding the import.
+    pub file: String,
+    /// 1-based line of the import statement.
+    pub line: u32,
+    /// Local binding that is never read (`b` in `from a import b`, `np` in `import numpy as np`).
+    pub name: String,
+    /// Byte range of the whole import statement.
+    pub start_byte: u32,
+    pub end_byte: u32,
+    /// The statement rewritten without its unused names, or `None` when every
+    /// name it binds is unused and the statement should be deleted outright.
+    pub replacement: Option<String>,
+}
+
+/// One binding inside an import statement: its source text, the local name
+/// it binds, and whether it is kept for its side effects.
+struct Binding {
+    text: String,
+    local: String,
+    side_effect: bool,
+}
+
+/// Reports every unused import binding in a Python module.
+///
+/// Entries sharing a statement carry the same byte range and `replacement`.
+pub fn find_unused_imports(
+    source: &[u8],
+    file_path: &str,
+) -> Result<Vec<UnusedImport>, AnatomistError> {
+    let mut parser = Parser::new();
+    parser
+        .set_language(&tree_sitter_python::LANGUAGE.into())
+        .map_err(|e| AnatomistError::ParseFailure(e.to_string()))?;
+    let tree = parser
+        .parse(source, None)
+        .ok_or_else(|| AnatomistError::ParseFailure(file_path.to_string()))?;
+    Ok(unused_in_tree(
+        source,
+        tree.root_node(),
+        file_path,
+        &|_, _| false,
+    ))
+}
+
+/// [`find_unused_imports`] over an already parsed module. `binds_module(module,
+/// name)` tells whether `from module import name` imports a submodule, which is
+/// kept like a bare `import`. Statements past 4 GiB are never reported.
+pub(crate) fn unused_in_tree(
+    source: &[u8],
+    root: Node,
+    file_path: &str,
+    binds_module: &dyn Fn(&str, &str) -> bool,
+) -> Vec<UnusedImport> {
+    if file_path.ends_with("__init__.py") {
+        return Vec::new();
+    }
+
+    let mut statements = Vec::new();
+    let mut used = HashSet::new();
+    collect(root, source, &mut statements, &mut used);
+
+    // Each statement losing a binding, what it loses, and its rewrite.
+    let mut edits: Vec<(Node, Vec<String>, Option<String>)> = Vec::new();
+    for node in statements {
+        if has_noqa(source, node) {
+            continue;
+        }
+        let Some((prefix, bindings)) = import_bindings(node, source, binds_module) else {
+            continue;
+        };
+        let (kept, dropped): (Vec<&Binding>, Vec<&Binding>) = bindings
+            .iter()
+            .partition(|b| b.side_effect || used.contains(&b.local));
+        if dropped.is_empty() {
+            continue;
+        }
+        let replacement = (!kept.is_empty()).then(|| {
+            let names: Vec<&str> = kept.iter().map(|b| b.text.as_str()).collect();
+            format!("{}{}", prefix, names.join(", "))
+        });
+        let dropped = dropped.iter().map(|b| b.local.clone()).collect();
+        edits.push((node, dropped, replacement));
+    }
+
+    // A block whose every statement goes would be empty: its first keeps `pass`.
+    let deleted: HashSet<usize> = edits
+        .iter()
+        .filter(|(_, _, replacement)| replacement.is_none())
+        .map(|(node, _, _)| node.start_byte())
+        .collect();
+    for (node, _, replacement) in &mut edits {
+        let Some(block) = node.parent().filter(|p| p.kind() == "block") else {
+            continue;
+        };
+        let mut cursor = block.walk();
+        let mut statements = block
+            .named_children(&mut cursor)
+            .filter(|n| n.kind() != "comment");
+        let first = statements.next();
+        if replacement.is_none()
+            && first == Some(*node)
+            && statements.all(|n| deleted.contains(&n.start_byte()))
+        {
+            *replacement = Some("pass".to_string());
+        }
+    }
+
+    let mut unused = Vec::new();
+    for (node, dropped, replacement) in edits {
+        let (Ok(start_byte), Ok(end_byte)) = (
+            u32::try_from(node.start_byte()),
+            u32::try_from(node.end_byte()),
+        ) else {
+            continue;
+        };
+        for name in dropped {
+            unused.push(UnusedImport {
+                file: file_path.to_string(),
+                line: node.start_position().row as u32 + 1,
+                name,
+                start_byte,
+                end_byte,
+                replacement: replacement.clone(),
+            });
+        }
+    }
+    unused
+}
+
+/// Splits the tree into import statements and the set of names read elsewhere.
+fn collect<'t>(
+    node: Node<'t>,
+    source: &[u8],
+    imports: &mut Vec<Node<'t>>,
+    used: &mut HashSet<String>,
+) {
+    match node.kind() {
+        "import_statement" | "import_from_statement" => {
+            imports.push(node);
+            return;
+        }
+        "identifier" => {
+            if let Ok(name) = node.utf8_text(source) {
+                used.insert(name.to_string());
+            }
+        }
+        "string" => {
+            let text = node.utf8_text(source).unwrap_or_default();
+            used.extend(
+                text.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+                    .filter(|w| !w.is_empty())
+                    .map(str::to_string),
+            );
+        }
+        _ => {}
+    }
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        collect(child, source, imports, used);
+    }
+}
+
+/// Returns the statement text preceding its names (`"from a.b import "`,
+/// `"import "`) and its bindings, or `None` for star and `__future__` imports.
+fn import_bindings(
+    node: Node,
+    source: &[u8],
+    binds_module: &dyn Fn(&str, &str) -> bool,
+) -> Option<(String, Vec<Binding>)> {
+    let text = |n: Node| n.utf8_text(source).unwrap_or_default().to_string();
+    let from_module = if node.kind() == "import_from_statement" {
+        let module = text(node.child_by_field_name("module_name")?);
+        if module == "__future__" {
+            return None;
+        }
+        Some(module)
+    } else {
+        None
+    };
+    let prefix = match &from_module {
+        Some(module) => format!("from {} import ", module),
+        None => "import ".to_string(),
+    };
+
+    let mut cursor = node.walk();
+    if node
+        .children(&mut cursor)
+        .any(|c| c.kind() == "wildcard_import")
+    {
+        return None;
+    }
+
+    let mut cursor = node.walk();
+    let bindings = node
+        .children_by_field_name("name", &mut cursor)
+        .filter_map(|name| {
+            let aliased = name.kind() == "aliased_import";
+            let local = if aliased {
+                text(name.child_by_field_name("alias")?)
+            } else {
+                // `import a.b` binds `a`; `from m import a` binds `a`.
+                text(name).split('.').next()?.to_string()
+            };
+            let side_effect = match &from_module {
+                None => !aliased,
+                Some(module) => {
+                    let imported = if aliased {
+                        name.child_by_field_name("name")?
+                    } else {
+                        name
+                    };
+                    binds_module(module, &text(imported))
+                }
+            };
+            Some(Binding {
+                text: text(name),
+                local,
+                side_effect,
+            })
+        })
+        .collect::<Vec<_>>();
+    (!bindings.is_empty()).then_some((prefix, bindings))
+}
+
+/// `true` when any line the statement spans carries a `# noqa` comment.
+fn has_noqa(source: &[u8], node: Node) -> bool {
+    let line_end = source[node.end_byte()..]
+        .iter()
+        .position(|&b| b == b'\n')
+        .map_or(source.len(), |p| node.end_byte() + p);
+    String::from_utf8_lossy(&source[node.start_byte()..line_end]).contains("# noqa")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unused(source: &str) -> Vec<UnusedImport> {
+        find_unused_imports(source.as_bytes(), "mod.py").unwrap()
+    }
+
+    #[test]
+    fn test_single_unused_import() {
+        let found = unused("from os import path\nimport sys\n\nprint(sys.argv)\n");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].name, "path");
+        assert_eq!(found[0].line, 1);
+        assert_eq!((found[0].start_byte, found[0].end_byte), (0, 19));
+        assert_eq!(found[0].replacement, None);
+    }
+
+    #[test]
+    fn test_multi_name_import_is_split() {
+        let found = unused("from x import a, b\n\nb()\n");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].name, "a");
+        assert_eq!(found[0].replacement.as_deref(), Some("from x import b"));
+
+        let found = unused("from x import (\n    a,\n    b,\n    c,\n)\n\nc()\n");
+        let names: Vec<&str> = found.iter().map(|u| u.name.as_str()).collect();
+        assert_eq!(names, vec!["a", "b"]);
+        assert_eq!(found[0].replacement.as_deref(), Some("from x import c"));
+    }
+
+    #[test]
+    fn test_aliased_import_uses_alias() {
+        let found = unused(
+            "import numpy as np\nimport pandas as pd\nfrom m import f as g\n\nnp.zeros(1)\nf()\n",
+        );
+        let names: Vec<&str> = found.iter().map(|u| u.name.as_str()).collect();
+        assert_eq!(names, vec!["pd", "g"]);
+
+        let found = unused("import os.path, json as j\n\nos.path.join()\n");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].name, "j");
+        assert_eq!(found[0].replacement.as_deref(), Some("import os.path"));
+    }
+
+    #[test]
+    fn test_side_effect_imports_kept() {
+        // A bare `import` runs the module for its side effects.
+        assert!(unused("import app.signals\nimport os\n").is_empty());
+        let found = unused("import os, json as j\n");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].replacement.as_deref(), Some("import os"));
+
+        // So does `from pkg import module` once the scan knows it is one.
+        let source = b"from app import signals, helper\n";
+        let mut parser = Parser::new();
+        parser
+            .set_language(&tree_sitter_python::LANGUAGE.into())
+            .unwrap();
+        let tree = parser.parse(source, None).unwrap();
+        let found = unused_in_tree(source, tree.root_node(), "mod.py", &|module, name| {
+            module == "app" && name == "signals"
+        });
+        let names: Vec<&str> = found.iter().map(|u| u.name.as_str()).collect();
+        assert_eq!(names, vec!["helper"]);
+        assert_eq!(
+            found[0].replacement.as_deref(),
+            Some("from app import signals")
+        );
+    }
+
+    #[test]
+    fn test_emptied_block_keeps_pass() {
+        let found = unused(
+            "from typing import TYPE_CHECKING\n\nif TYPE_CHECKING:\n    # models\n    from m import A\n    from m import B\n\ntry:\n    from fast import dumps\nexcept ImportError:\n    dumps = None\n\nprint(TYPE_CHECKING, dumps)\n",
+        );
+        let edits: Vec<(&str, Option<&str>)> = found
+            .iter()
+            .map(|u| (u.name.as_str(), u.replacement.as_deref()))
+            .collect();
+        assert_eq!(edits, vec![("A", Some("pass")), ("B", None)]);
+    }
+
+    #[test]
+    fn test_star_and_future_imports_not_reported() {
+        assert!(unused("from os import *\nfrom __future__ import annotations\n").is_empty());
+    }
+
+    #[test]
+    fn test_exclusions() {
+        // String annotations and `__all__` entries count as uses.
+        assert!(
+            unused("from t import Foo, bar\n__all__ = [\"bar\"]\n\ndef f(x: \"Foo\"): pass\n")
+                .is_empty()
+        );
+        // `# noqa` opts a statement out.
+        assert!(unused("import os as o  # noqa: F401\n").is_empty());
+        // Package `__init__.py` files re-export by importing.
+        assert!(find_unused_imports(b"import os as o\n", "pkg/__init__.py")
+            .unwrap()
+            .is_empty());
+    }
+}
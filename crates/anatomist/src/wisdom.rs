@@ -9,8 +9,9 @@
 //! Both stages share pre-computed file-level flags (one linear pass each),
 //! then iterate entities once. Total cost: O(file_size + entity_count).
 
-use crate::{Entity, Protection};
-use std::collections::HashSet;
+use crate::{Entity, Protection, ProtectionDetail};
+use common::wisdom::WisdomRegistry;
+use std::collections::{HashMap, HashSet};
 
 // --- Directory-level protection ---
 
@@ -22,10 +23,24 @@ use std::collections::HashSet;
 ///
 /// `migrations/` is intentionally omitted here — it is already caught by Stage 0
 /// (`PROTECTED_DIRS` in `pipeline.rs`) which marks the entire directory as `Directory`.
-static PLUGIN_DIRS: &[&str] = &["spiders", "plugins", "commands", "handlers", "tasks"];
+pub const PLUGIN_DIRS: &[&str] = &["spiders", "plugins", "commands", "handlers", "tasks"];
 
 // --- Byte pattern tables (compile-time constants) ---
 
+/// JS/TS methods invoked by the runtime or a UI framework, never by name.
+static SCRIPT_LIFECYCLE: &[&str] = &[
+    "constructor",
+    "render",
+    "componentDidMount",
+    "componentDidUpdate",
+    "componentWillUnmount",
+    "shouldComponentUpdate",
+    "getDerivedStateFromProps",
+    "connectedCallback",
+    "disconnectedCallback",
+    "attributeChangedCallback",
+];
+
 /// FastAPI/Flask/Starlette route decorator patterns (without leading `@`).
 static ROUTE_DEC: &[&[u8]] = &[
     b"app.get",
@@ -47,13 +62,18 @@ static ROUTE_DEC: &[&[u8]] = &[
 /// FastAPI dependency injection patterns (file-level scan).
 static DI_PATTERNS: &[&[u8]] = &[b"Depends(", b"Security(", b"dependency_overrides"];
 
-/// CLI entry-point decorator patterns.
-static CLI_DEC: &[&[u8]] = &[
-    b"app.command",
-    b"app.callback",
-    b"cli.command",
-    b"click.command",
-    b"typer.command",
+/// CLI entry-point and task decorators, matched against the whole callee path.
+static CLI_DEC: &[&str] = &[
+    "app.command",
+    "app.callback",
+    "cli.command",
+    "click.command",
+    "typer.command",
+    "shared_task",
+    "celery.shared_task",
+    "app.task",
+    "celery.task",
+    "task",
 ];
 
 /// ORM base class patterns (file-level: indicates ORM usage).
@@ -94,6 +114,12 @@ static PYDANTIC_DEC: &[&[u8]] = &[
     b"root_validator",
 ];
 
+/// `unittest` base classes (final dotted segment) whose test methods are run by the loader.
+static TESTCASE_BASES: &[&str] = &["TestCase", "IsolatedAsyncioTestCase"];
+
+/// Method name prefixes collected or invoked by the `unittest` runner.
+static TESTCASE_METHOD_PREFIXES: &[&str] = &["test", "setUp", "tearDown"];
+
 /// Metaprogramming danger patterns (entity-level scan).
 static METAPROG: &[&[u8]] = &[
     b"getattr(",
@@ -110,6 +136,64 @@ static METAPROG: &[&[u8]] = &[
 
 // ---------------------------------------------------------------------------
 
+/// Class name → bases / method names, used to detect interface overrides (rule 2k).
+///
+/// Keyed by bare class name; bases are reduced to their final dotted segment
+/// (`abc.StorageBackend` → `StorageBackend`). Same-named classes are merged.
+#[derive(Debug, Default)]
+pub struct ClassHierarchy {
+    bases: HashMap<String, Vec<String>>,
+    methods: HashMap<String, HashSet<String>>,
+}
+
+impl ClassHierarchy {
+    /// Builds the hierarchy from every class and method in `entities`.
+    pub fn build(entities: &[Entity]) -> Self {
+        let mut hierarchy = Self::default();
+        for entity in entities {
+            if let Some(parent) = &entity.parent_class {
+                hierarchy
+                    .methods
+                    .entry(parent.clone())
+                    .or_default()
+                    .insert(entity.name.clone());
+            } else if !entity.base_classes.is_empty() {
+                let bases = hierarchy.bases.entry(entity.name.clone()).or_default();
+                for base in &entity.base_classes {
+                    bases.push(base.rsplit('.').next().unwrap_or(base).to_string());
+                }
+            }
+        }
+        hierarchy
+    }
+
+    /// Returns `true` if `method` is defined on any ancestor of `class`.
+    pub fn overrides(&self, class: &str, method: &str) -> bool {
+        let mut seen: HashSet<&str> = HashSet::new();
+        let mut stack: Vec<&str> = self
+            .bases
+            .get(class)
+            .map(|b| b.iter().map(String::as_str).collect())
+            .unwrap_or_default();
+        while let Some(ancestor) = stack.pop() {
+            if !seen.insert(ancestor) {
+                continue;
+            }
+            if self
+                .methods
+                .get(ancestor)
+                .is_some_and(|m| m.contains(method))
+            {
+                return true;
+            }
+            if let Some(bases) = self.bases.get(ancestor) {
+                stack.extend(bases.iter().map(String::as_str));
+            }
+        }
+        false
+    }
+}
+
 /// Classifies entities in-place using Stages 2 and 4 of the pipeline.
 ///
 /// Modifies `entity.protected_by` for each entity that matches a rule.
@@ -119,7 +203,26 @@ static METAPROG: &[&[u8]] = &[
 /// - `entities`: Mutable slice of entities belonging to a single file.
 /// - `source`: Raw bytes of that file (used for byte-level pattern scanning).
 /// - `file_path`: Normalized file path (UTF-8, forward slashes).
+///
+/// Interface overrides are resolved against classes in `entities` only; use
+/// [`classify_with_hierarchy`] to resolve bases defined in other modules.
 pub fn classify(entities: &mut [Entity], source: &[u8], file_path: &str) {
+    let hierarchy = ClassHierarchy::build(entities);
+    classify_with_hierarchy(entities, source, file_path, &hierarchy, PLUGIN_DIRS, None);
+}
+
+/// Same as [`classify`], resolving interface overrides against a project-wide
+/// [`ClassHierarchy`], treating `plugin_dirs` (default [`PLUGIN_DIRS`]) as
+/// framework-discovered directories, and protecting symbols matched by the
+/// baked `rules` (`wisdom.rkyv`) after every built-in Stage 2 rule.
+pub fn classify_with_hierarchy<S: AsRef<str>>(
+    entities: &mut [Entity],
+    source: &[u8],
+    file_path: &str,
+    hierarchy: &ClassHierarchy,
+    plugin_dirs: &[S],
+    rules: Option<&WisdomRegistry>,
+) {
     // Pre-compute file-level flags — one linear scan each, amortised over all entities.
     let has_di = any_in(source, DI_PATTERNS);
     let has_orm = any_in(source, ORM_BASE);
@@ -130,15 +233,28 @@ pub fn classify(entities: &mut [Entity], source: &[u8], file_path: &str) {
         || bytes_contain(source, b"QObject");
     let has_metaprog = any_in(source, METAPROG);
     let is_init = file_path.ends_with("__init__.py");
+    let is_script = crate::imports::is_script_path(file_path);
 
     // Plugin directory flag: file lives in a framework-managed directory.
-    let is_plugin_dir = PLUGIN_DIRS
+    let is_plugin_dir = plugin_dirs
         .iter()
-        .any(|d| file_path.split('/').any(|seg| seg == *d));
+        .any(|d| file_path.split('/').any(|seg| seg == d.as_ref()));
 
     // Stage 4: extract __all__ exports (single scan, result is &str slices into `source`).
     let all_exports = extract_all_exports(source);
 
+    // Pre-pass: classes deriving from unittest.TestCase (methods only know their parent's name).
+    let testcase_classes: HashSet<String> = entities
+        .iter()
+        .filter(|e| {
+            e.base_classes.iter().any(|b| {
+                let last = b.rsplit('.').next().unwrap_or(b);
+                TESTCASE_BASES.contains(&last)
+            })
+        })
+        .map(|e| e.name.clone())
+        .collect();
+
     for entity in entities.iter_mut() {
         // Already protected by a prior pass (e.g., PytestFixture from parser).
         if entity.protected_by.is_some() {
@@ -151,48 +267,77 @@ pub fn classify(entities: &mut [Entity], source: &[u8], file_path: &str) {
         // Spiders, task handlers, command modules, etc. are discovered dynamically —
         // they are never explicitly imported, so the reference graph has no edges to them.
         if is_plugin_dir && !entity.is_private() {
-            entity.protected_by = Some(Protection::EntryPoint);
+            entity.protect(
+                Protection::EntryPoint,
+                rule("public symbol in a plugin directory"),
+            );
             continue;
         }
 
-        // 2a. Dunder methods: always lifecycle-critical.
-        if entity.is_dunder() {
-            entity.protected_by = Some(Protection::LifecycleMethod);
+        // 2a. Dunder methods (and JS/TS constructors and component hooks): always
+        // lifecycle-critical.
+        if entity.is_dunder()
+            || (is_script
+                && entity.parent_class.is_some()
+                && SCRIPT_LIFECYCLE.contains(&entity.name.as_str()))
+        {
+            entity.protect(Protection::LifecycleMethod, rule("lifecycle method"));
             continue;
         }
 
         // 2b. Entry points: `main` function or CLI decorator.
-        if entity.name == "main"
-            || entity.decorators.iter().any(|d| {
-                let b = d.as_bytes();
-                CLI_DEC.iter().any(|p| bytes_contain(b, p))
-            })
+        if entity.name == "main" {
+            entity.protect(Protection::EntryPoint, rule("entry point `main`"));
+            continue;
+        }
+        if let Some(d) = entity
+            .decorators
+            .iter()
+            .find(|d| CLI_DEC.contains(&decorator_callee(d)))
         {
-            entity.protected_by = Some(Protection::EntryPoint);
+            let detail = rule(format!("CLI decorator `@{}`", decorator_callee(d)));
+            entity.protect(Protection::EntryPoint, detail);
             continue;
         }
 
         // 2c. FastAPI / Flask / Starlette route decorators.
-        if entity.decorators.iter().any(|d| {
-            let b = d.as_bytes();
+        if let Some(d) = entity.decorators.iter().find(|d| {
+            let b = decorator_callee(d).as_bytes();
             ROUTE_DEC.iter().any(|p| bytes_contain(b, p))
         }) {
-            entity.protected_by = Some(Protection::MetaprogrammingDanger);
+            let detail = rule(format!("route decorator `@{}`", decorator_callee(d)));
+            entity.protect(Protection::MetaprogrammingDanger, detail);
             continue;
         }
 
         // 2d. Pydantic validator decorators.
-        if entity.decorators.iter().any(|d| {
-            let b = d.as_bytes();
+        if let Some(d) = entity.decorators.iter().find(|d| {
+            let b = decorator_callee(d).as_bytes();
             PYDANTIC_DEC.iter().any(|p| bytes_contain(b, p))
         }) {
-            entity.protected_by = Some(Protection::PydanticAlias);
+            let detail = rule(format!("validator decorator `@{}`", decorator_callee(d)));
+            entity.protect(Protection::PydanticAlias, detail);
+            continue;
+        }
+
+        // 2d'. Dispatch registration: `@<name>.register` / `@<name>.register(<type>)`
+        // (functools.singledispatch and similar registries).
+        if let Some(d) = entity
+            .decorators
+            .iter()
+            .find(|d| decorator_callee(d).ends_with(".register"))
+        {
+            let detail = rule(format!("dispatch registration `@{}`", decorator_callee(d)));
+            entity.protect(Protection::DispatchRegistration, detail);
             continue;
         }
 
         // 2e. SQLAlchemy special attribute names.
         if SQLALCHEMY_NAMES.contains(&entity.name.as_str()) {
-            entity.protected_by = Some(Protection::SqlAlchemyMeta);
+            entity.protect(
+                Protection::SqlAlchemyMeta,
+                rule("SQLAlchemy attribute name"),
+            );
             continue;
         }
 
@@ -200,7 +345,7 @@ pub fn classify(entities: &mut [Entity], source: &[u8], file_path: &str) {
         if has_sqlalchemy {
             let es = entity_src(source, entity);
             if any_in(es, SQLALCHEMY_DEC) {
-                entity.protected_by = Some(Protection::SqlAlchemyMeta);
+                entity.protect(Protection::SqlAlchemyMeta, rule("SQLAlchemy decorator"));
                 continue;
             }
         }
@@ -210,7 +355,7 @@ pub fn classify(entities: &mut [Entity], source: &[u8], file_path: &str) {
             && entity.parent_class.is_some()
             && ORM_LIFECYCLE_NAMES.contains(&entity.name.as_str())
         {
-            entity.protected_by = Some(Protection::OrmLifecycle);
+            entity.protect(Protection::OrmLifecycle, rule("ORM lifecycle method"));
             continue;
         }
 
@@ -218,14 +363,27 @@ pub fn classify(entities: &mut [Entity], source: &[u8], file_path: &str) {
         if has_di {
             let es = entity_src(source, entity);
             if any_in(es, DI_PATTERNS) {
-                entity.protected_by = Some(Protection::FastApiOverride);
+                entity.protect(Protection::FastApiOverride, rule("dependency injection"));
                 continue;
             }
         }
 
+        // 2h'. unittest runner hooks: `test*`, `setUp*`, `tearDown*` on a TestCase subclass.
+        if entity
+            .parent_class
+            .as_ref()
+            .is_some_and(|p| testcase_classes.contains(p))
+            && TESTCASE_METHOD_PREFIXES
+                .iter()
+                .any(|p| entity.name.starts_with(p))
+        {
+            entity.protect(Protection::TestReference, rule("unittest.TestCase hook"));
+            continue;
+        }
+
         // 2i. Qt auto-connection slot: `on_<widget>_<signal>` in Qt-using file.
         if has_qt && is_qt_auto_slot(&entity.name) {
-            entity.protected_by = Some(Protection::QtAutoSlot);
+            entity.protect(Protection::QtAutoSlot, rule("Qt auto-connected slot"));
             continue;
         }
 
@@ -233,22 +391,45 @@ pub fn classify(entities: &mut [Entity], source: &[u8], file_path: &str) {
         if has_metaprog {
             let es = entity_src(source, entity);
             if any_in(es, METAPROG) {
-                entity.protected_by = Some(Protection::MetaprogrammingDanger);
+                entity.protect(Protection::MetaprogrammingDanger, rule("metaprogramming"));
                 continue;
             }
         }
 
+        // 2k. Interface override: an ancestor class defines the same method name,
+        // so callers may dispatch through the base type.
+        if entity
+            .parent_class
+            .as_ref()
+            .is_some_and(|p| hierarchy.overrides(p, &entity.name))
+        {
+            let detail = rule(format!(
+                "overrides an ancestor of `{}`",
+                entity.parent_class.as_deref().unwrap_or_default()
+            ));
+            entity.protect(Protection::InterfaceOverride, detail);
+            continue;
+        }
+
+        // 2l. Baked framework rules from `wisdom.rkyv`.
+        if let Some(matched) =
+            rules.and_then(|r| r.matches(&entity.name, &entity.decorators, &entity.base_classes))
+        {
+            entity.protect(Protection::WisdomRule, rule(matched));
+            continue;
+        }
+
         // --- Stage 4: Package Export ---
 
         // 4a. Symbol name appears in `__all__`.
         if !all_exports.is_empty() && all_exports.contains(entity.name.as_str()) {
-            entity.protected_by = Some(Protection::PackageExport);
+            entity.protect(Protection::PackageExport, export("listed in `__all__`"));
             continue;
         }
 
         // 4b. `__init__.py`: every non-private, non-dunder top-level symbol is an export.
         if is_init && entity.parent_class.is_none() && !entity.is_private() {
-            entity.protected_by = Some(Protection::PackageExport);
+            entity.protect(Protection::PackageExport, export("public in `__init__.py`"));
             continue;
         }
     }
@@ -258,13 +439,37 @@ pub fn classify(entities: &mut [Entity], source: &[u8], file_path: &str) {
 // Internal helpers
 // ---------------------------------------------------------------------------
 
+/// A Stage 2 wisdom-rule detail.
+fn rule(reason: impl Into<String>) -> ProtectionDetail {
+    ProtectionDetail::new(2, format!("wisdom rule: {}", reason.into()))
+}
+
+/// A Stage 4 package-export detail.
+fn export(reason: &str) -> ProtectionDetail {
+    ProtectionDetail::new(4, format!("package export: {}", reason))
+}
+
 /// Returns true if `name` matches Qt's `on_<widget>_<signal>` auto-slot convention.
 fn is_qt_auto_slot(name: &str) -> bool {
     name.starts_with("on_") && name.len() > 3 && name[3..].contains('_')
 }
 
+/// Returns the decorator's callee with any argument list stripped
+/// (`foo.register(int)` → `foo.register`), so rules match the decorator name only.
+fn decorator_callee(decorator: &str) -> &str {
+    let decorator = decorator.strip_prefix('@').unwrap_or(decorator);
+    decorator.split('(').next().unwrap_or(decorator).trim()
+}
+
 /// Returns the source bytes for an entity's byte range (clamped to file bounds).
 fn entity_src<'a>(source: &'a [u8], entity: &Entity) -> &'a [u8] {
+    debug_assert!(
+        entity.start_byte <= entity.end_byte,
+        "inverted byte range {}..{} for {}",
+        entity.start_byte,
+        entity.end_byte,
+        entity.symbol_id()
+    );
     let start = entity.start_byte as usize;
     let end = (entity.end_byte as usize).min(source.len());
     if start < end {
@@ -356,14 +561,27 @@ mod tests {
             end_line: 1,
             file_path: "src/mod.py".into(),
             qualified_name: name.into(),
+            module_path: String::new(),
             parent_class: parent,
             base_classes: vec![],
             protected_by: None,
+            protection_detail: None,
             decorators,
             structural_hash: None,
+            structural_nodes: 0,
         }
     }
 
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "inverted byte range 10..5")]
+    fn test_inverted_entity_range_panics_in_debug() {
+        let mut entity = make_entity("query", vec![], None);
+        entity.start_byte = 10;
+        entity.end_byte = 5;
+        classify(&mut [entity], b"import sqlalchemy\n", "src/mod.py");
+    }
+
     #[test]
     fn test_dunder_protected() {
         let mut entities = vec![make_entity("__init__", vec![], None)];
@@ -390,6 +608,13 @@ mod tests {
             entities[0].protected_by,
             Some(Protection::MetaprogrammingDanger)
         );
+        assert_eq!(
+            entities[0].protection_detail,
+            Some(ProtectionDetail::new(
+                2,
+                "wisdom rule: route decorator `@app.get`"
+            ))
+        );
     }
 
     #[test]
@@ -403,6 +628,118 @@ mod tests {
         assert_eq!(entities[0].protected_by, Some(Protection::PydanticAlias));
     }
 
+    #[test]
+    fn test_unittest_testcase_methods() {
+        let mut case = make_entity("FooTests", vec![], None);
+        case.base_classes = vec!["unittest.TestCase".into()];
+        let mut async_case = make_entity("BarTests", vec![], None);
+        async_case.base_classes = vec!["IsolatedAsyncioTestCase".into()];
+        let mut entities = vec![
+            case,
+            async_case,
+            make_entity("setUp", vec![], Some("FooTests".into())),
+            make_entity("tearDownClass", vec![], Some("FooTests".into())),
+            make_entity("test_adds", vec![], Some("FooTests".into())),
+            make_entity("test_async", vec![], Some("BarTests".into())),
+            make_entity("helper", vec![], Some("FooTests".into())),
+            make_entity("test_plain", vec![], Some("NotATestCase".into())),
+        ];
+        classify(&mut entities, b"", "src/checks.py");
+
+        let protection = |name: &str| {
+            entities
+                .iter()
+                .find(|e| e.name == name)
+                .and_then(|e| e.protected_by)
+        };
+        assert_eq!(protection("setUp"), Some(Protection::TestReference));
+        assert_eq!(protection("tearDownClass"), Some(Protection::TestReference));
+        assert_eq!(protection("test_adds"), Some(Protection::TestReference));
+        assert_eq!(protection("test_async"), Some(Protection::TestReference));
+        assert_eq!(protection("helper"), None);
+        assert_eq!(protection("test_plain"), None);
+    }
+
+    #[test]
+    fn test_interface_override() {
+        let mut base = make_entity("StorageBackend", vec![], None);
+        base.base_classes = vec!["abc.ABC".into()];
+        let mut mid = make_entity("CachedStorage", vec![], None);
+        mid.base_classes = vec!["StorageBackend".into()];
+        let mut leaf = make_entity("S3Storage", vec![], None);
+        leaf.base_classes = vec!["storage.CachedStorage".into()];
+        let mut entities = vec![
+            base,
+            mid,
+            leaf,
+            make_entity("read", vec![], Some("StorageBackend".into())),
+            make_entity("read", vec![], Some("S3Storage".into())),
+            make_entity("upload_part", vec![], Some("S3Storage".into())),
+        ];
+        classify(&mut entities, b"", "src/storage.py");
+
+        assert_eq!(
+            entities[4].protected_by,
+            Some(Protection::InterfaceOverride)
+        );
+        assert_eq!(entities[5].protected_by, None);
+        // The base's own method has no ancestor defining it.
+        assert_eq!(entities[3].protected_by, None);
+    }
+
+    #[test]
+    fn test_hierarchy_cycle_terminates() {
+        let mut a = make_entity("A", vec![], None);
+        a.base_classes = vec!["B".into()];
+        let mut b = make_entity("B", vec![], None);
+        b.base_classes = vec!["A".into()];
+        let hierarchy = ClassHierarchy::build(&[a, b]);
+        assert!(!hierarchy.overrides("A", "missing"));
+    }
+
+    #[test]
+    fn test_dispatch_registration() {
+        let mut entities = vec![
+            make_entity("_int", vec!["process.register(int)".into()], None),
+            make_entity("_str", vec!["process.register".into()], None),
+            // `register` only inside the argument list must not match.
+            make_entity(
+                "cached",
+                vec!["lru_cache(key=registry.register)".into()],
+                None,
+            ),
+        ];
+        classify(&mut entities, b"", "src/dispatch.py");
+        assert_eq!(
+            entities[0].protected_by,
+            Some(Protection::DispatchRegistration)
+        );
+        assert_eq!(
+            entities[1].protected_by,
+            Some(Protection::DispatchRegistration)
+        );
+        assert_eq!(entities[2].protected_by, None);
+    }
+
+    #[test]
+    fn test_celery_task_decorators() {
+        let mut entities = vec![
+            make_entity("a", vec!["shared_task".into()], None),
+            make_entity("b", vec!["app.task(bind=True)".into()], None),
+            make_entity("c", vec!["task".into()], None),
+            make_entity("d", vec!["taskify".into()], None),
+            make_entity("e", vec!["my_click.command_helper".into()], None),
+            make_entity("f", vec!["webapp.task".into()], None),
+        ];
+        classify(&mut entities, b"", "src/jobs.py");
+        assert_eq!(entities[0].protected_by, Some(Protection::EntryPoint));
+        assert_eq!(entities[1].protected_by, Some(Protection::EntryPoint));
+        assert_eq!(entities[2].protected_by, Some(Protection::EntryPoint));
+        assert_eq!(entities[3].protected_by, None);
+        assert_eq!(entities[4].protected_by, None);
+        assert_eq!(entities[5].protected_by, None);
+    }
+
     #[test]
     fn test_all_exports() {
         let source =
@@ -0,0 +1,56 @@
+//! The pipeline on generated projects, where the live set is known up front.
+
+use anatomist::pipeline;
+use anatomist::ParserHost;
+use std::collections::BTreeSet;
+use testkit::SyntheticProject;
+
+#[test]
+fn dead_symbols_are_exactly_the_uncalled_functions() {
+    let project = SyntheticProject::new(60)
+        .with_functions(5)
+        .with_imports(3)
+        .with_call_sites(4)
+        .with_modules_per_package(20);
+    let root = project.write_temp("test_synthetic_dead").unwrap();
+    let mut host = ParserHost::new().unwrap();
+
+    let result = pipeline::run(&root, &mut host, false).unwrap();
+    let dead: BTreeSet<String> = result.dead.iter().map(|e| e.name.clone()).collect();
+    let referenced = project.referenced();
+    let expected: BTreeSet<String> = project
+        .function_names()
+        .into_iter()
+        .filter(|name| !referenced.contains(name))
+        .collect();
+    assert_eq!(result.total, 60 * 5);
+    assert_eq!(dead, expected);
+
+    std::fs::remove_dir_all(root).ok();
+}
+
+#[test]
+fn config_files_shield_the_names_they_mention() {
+    let project = SyntheticProject::new(30)
+        .with_functions(4)
+        .with_call_sites(0)
+        .with_config_files(3);
+    let root = project.write_temp("test_synthetic_grep").unwrap();
+
+    let names = project.function_names();
+    let shielded = anatomist::scan::grep_shield(&names, &root).unwrap();
+    let mentioned: BTreeSet<String> = (0..3)
+        .flat_map(|n| {
+            let config = project.config_source(n);
+            names
+                .iter()
+                .filter(|name| config.contains(&format!(".{name}\"")))
+                .cloned()
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    assert!(!mentioned.is_empty());
+    assert_eq!(shielded.into_iter().collect::<BTreeSet<_>>(), mentioned);
+
+    std::fs::remove_dir_all(root).ok();
+}
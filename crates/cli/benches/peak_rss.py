@@ -0,0 +1,82 @@
+#!/usr/bin/env python3
+"""Peak RSS of `janitor scan` on a generated project.
+
+Generates a synthetic Python tree (FILES modules of FUNCS functions each, every
+module importing and calling into the previous one), runs the given `janitor`
+binary on it and prints the child's peak resident set size.
+
+    cargo build --release -p cli
+    python3 crates/cli/benches/peak_rss.py target/release/cli --files 20000
+
+The tree is deterministic, so numbers from two builds are comparable.
+"""
+
+import argparse
+import os
+import resource
+import shutil
+import subprocess
+import sys
+import tempfile
+import time
+
+
+def generate(root, files, funcs, per_package):
+    for i in range(files):
+        package = os.path.join(root, "app", f"pkg{i // per_package}")
+        if i % per_package == 0:
+            os.makedirs(package, exist_ok=True)
+            open(os.path.join(package, "__init__.py"), "w").close()
+        lines = []
+        if i > 0:
+            lines.append(f"from app.pkg{(i - 1) // per_package}.mod{i - 1} import func{i - 1}_0\n")
+        for f in range(funcs):
+            lines.append(f"\n\ndef func{i}_{f}(value, scale=2):\n")
+            lines.append("    total = 0\n")
+            lines.append("    for item in range(value):\n")
+            lines.append("        total += item * scale\n")
+            if f == 0 and i > 0:
+                lines.append(f"    total += func{i - 1}_0(value)\n")
+            lines.append("    return total\n")
+        lines.append(f"\n\nclass Model{i}:\n    def method(self):\n        return func{i}_0(3)\n")
+        with open(os.path.join(package, f"mod{i}.py"), "w") as out:
+            out.writelines(lines)
+    with open(os.path.join(root, "main.py"), "w") as out:
+        out.write(f"from app.pkg{(files - 1) // per_package}.mod{files - 1} import func{files - 1}_0\n")
+        out.write(f"\nfunc{files - 1}_0(10)\n")
+
+
+def main():
+    parser = argparse.ArgumentParser(description=__doc__.splitlines()[0])
+    parser.add_argument("janitor", help="path to the janitor binary")
+    parser.add_argument("--files", type=int, default=5000)
+    parser.add_argument("--funcs", type=int, default=20, help="functions per module")
+    parser.add_argument("--per-package", type=int, default=100, help="modules per package")
+    parser.add_argument("--keep", action="store_true", help="keep the generated tree")
+    args = parser.parse_args()
+
+    root = tempfile.mkdtemp(prefix="janitor-rss-")
+    try:
+        generate(root, args.files, args.funcs, args.per_package)
+        start = time.monotonic()
+        subprocess.run(
+            [args.janitor, "scan", root],
+            check=True,
+            stdout=subprocess.DEVNULL,
+            stderr=subprocess.DEVNULL,
+        )
+        elapsed = time.monotonic() - start
+        # Linux reports ru_maxrss in KiB, macOS in bytes.
+        peak = resource.getrusage(resource.RUSAGE_CHILDREN).ru_maxrss
+        peak_mib = peak / 1024 if sys.platform != "darwin" else peak / (1024 * 1024)
+        symbols = args.files * (args.funcs + 2)
+        print(f"{args.files} files, {symbols} symbols: peak RSS {peak_mib:.1f} MiB in {elapsed:.1f}s")
+    finally:
+        if args.keep:
+            print(root)
+        else:
+            shutil.rmtree(root)
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,128 @@
+//! `janitor hook`: a git pre-commit check that fails on dead code in the
+//! staged Python files, compared against the project's baseline.
+
+use anatomist::Entity;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// The pre-commit script written by [`install`], which runs `exe` (this
+/// binary, wherever it is installed and whatever it is called).
+pub fn hook_script(exe: &Path) -> String {
+    format!(
+        "#!/bin/sh
+# Installed by `janitor hook install`: fail the commit if the staged Python
+# files hold dead symbols missing from .janitor/baseline.json.
+exec {} hook run
+",
+        sh_quote(exe)
+    )
+}
+
+/// A `.pre-commit-config.yaml` entry for the pre-commit framework, which
+/// passes the staged Python files as arguments to `exe`.
+pub fn pre_commit_config(exe: &Path) -> String {
+    let entry = format!("{} hook run", sh_quote(exe));
+    format!(
+        "repos:
+  - repo: local
+    hooks:
+      - id: janitor
+        name: janitor (dead code)
+        entry: \"{}\"
+        language: system
+        types: [python]
+",
+        entry.replace('\\', "\\\\").replace('"', "\\\"")
+    )
+}
+
+/// `path` as one single-quoted `sh` word.
+fn sh_quote(path: &Path) -> String {
+    format!("'{}'", path.to_string_lossy().replace('\'', "'\\''"))
+}
+
+/// Writes [`hook_script`] for `exe` to the pre-commit hook of the git
+/// repository at `root`, refusing to replace an existing hook unless `force`
+/// is set. The hooks directory is git's own answer, so worktrees,
+/// submodules and `core.hooksPath` are honoured.
+pub fn install(root: &Path, exe: &Path, force: bool) -> anyhow::Result<PathBuf> {
+    let hooks_dir = hooks_dir(root)?;
+    let hook = hooks_dir.join("pre-commit");
+    if hook.exists() && !force {
+        anyhow::bail!(
+            "{} already exists; pass --force to replace it",
+            hook.display()
+        );
+    }
+    std::fs::create_dir_all(&hooks_dir)?;
+    std::fs::write(&hook, hook_script(exe))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&hook, std::fs::Permissions::from_mode(0o755))?;
+    }
+    Ok(hook)
+}
+
+/// `git rev-parse --git-path hooks` in `root`, resolved against `root`.
+fn hooks_dir(root: &Path) -> anyhow::Result<PathBuf> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--git-path", "hooks"])
+        .current_dir(root)
+        .output()
+        .map_err(|e| anyhow::anyhow!("could not run git: {}", e))?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "{}: not in a git repository: {}",
+            root.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    let dir = String::from_utf8_lossy(&output.stdout);
+    Ok(root.join(dir.trim_end_matches(['\r', '\n'])))
+}
+
+/// `git diff --cached --name-only` in `root`: staged paths, relative to the
+/// repository root, one per line. Deleted files are left out.
+pub fn staged_files(root: &Path) -> anyhow::Result<String> {
+    let output = Command::new("git")
+        .args(["diff", "--cached", "--name-only", "--diff-filter=ACMR"])
+        .current_dir(root)
+        .output()
+        .map_err(|e| anyhow::anyhow!("could not run git: {}", e))?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "git diff --cached failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// The Python files among `paths`.
+pub fn python_files(paths: impl IntoIterator<Item = PathBuf>) -> Vec<PathBuf> {
+    paths
+        .into_iter()
+        .filter(|p| p.extension().is_some_and(|ext| ext == "py"))
+        .collect()
+}
+
+/// One `file:line: dead symbol `name`` line per entity in `dead`, with files
+/// relative to `root` so editors and terminals can jump to them.
+pub fn write_findings(out: &mut impl Write, dead: &[Entity], root: &Path) -> std::io::Result<()> {
+    let root = dunce::canonicalize(root).unwrap_or_else(|_| root.to_path_buf());
+    let prefix = format!("{}/", root.to_string_lossy().replace('\\', "/"));
+    for entity in dead {
+        let file = entity
+            .file_path
+            .strip_prefix(&prefix)
+            .unwrap_or(&entity.file_path);
+        writeln!(
+            out,
+            "{}:{}: dead symbol `{}`",
+            file, entity.start_line, entity.qualified_name
+        )?;
+    }
+    Ok(())
+}
@@ -1,7 +1,19 @@
-use clap::{Parser, Subcommand};
-use std::collections::HashMap;
-use std::env;
+mod hook;
+mod progress;
+mod report;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use common::diagnostics::{has_warnings, Severity};
+use common::lock::LockMode;
+use janitor::baseline::Baseline;
+use janitor::plan::CleanPlan;
+use janitor::{CleanOptions, DedupOptions, Event, Janitor, RuntimeEvidence};
+use report::sarif::SarifLevel;
+use std::collections::HashSet;
+use std::io::{IsTerminal, Write};
 use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::Duration;
 
 #[derive(Parser)]
 #[command(name = "janitor")]
@@ -9,6 +21,10 @@ use std::path::{Path, PathBuf};
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+    /// Wait for another janitor process on the same project to finish
+    /// instead of failing.
+    #[arg(long, global = true)]
+    wait: bool,
 }
 
 #[derive(Subcommand)]
@@ -23,17 +39,99 @@ enum Commands {
         /// Also print protected symbols with their protection reason.
         #[arg(long)]
         verbose: bool,
+        /// Output format; `json` writes a machine-readable report to stdout.
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+        /// SARIF result level used with `--format sarif`.
+        #[arg(long, value_enum, default_value_t = SarifLevel::Note)]
+        sarif_level: SarifLevel,
+        /// Re-parse every file instead of reusing `.janitor/cache/`.
+        #[arg(long)]
+        no_cache: bool,
+        /// Do not treat `from m import *` as referencing every public symbol of `m`.
+        #[arg(long)]
+        strict_star_imports: bool,
+        /// Runtime logs; symbols named in them are never dead (`.json`/`.json.gz` = OTLP).
+        #[arg(long, num_args = 1..)]
+        logs: Vec<PathBuf>,
+        /// `coverage json` report; functions whose bodies executed are never dead.
+        #[arg(long)]
+        coverage: Option<PathBuf>,
+        /// Record the dead symbols found by this scan as accepted.
+        #[arg(long, value_name = "FILE")]
+        write_baseline: Option<PathBuf>,
+        /// Report dead symbols recorded in this baseline as a count only.
+        #[arg(long, value_name = "FILE")]
+        baseline: Option<PathBuf>,
+        /// Exit non-zero if any dead symbol is missing from `--baseline`.
+        #[arg(long, requires = "baseline")]
+        fail_on_new: bool,
+        /// Exit 1 if any dead symbol is found (`[ci] fail_on_dead`). Ignored
+        /// when a `--max-dead*` threshold is set: within it, the scan passes.
+        #[arg(long)]
+        fail_on_dead: bool,
+        /// Exit 2 if more than this many dead symbols are found (`[ci] max_dead`).
+        #[arg(long, value_name = "N")]
+        max_dead: Option<u64>,
+        /// Exit 2 if the dead symbols span more than this many bytes (`[ci] max_dead_bytes`).
+        #[arg(long, value_name = "N")]
+        max_dead_bytes: Option<u64>,
+        /// Count orphan files, whole, towards the dead totals (`[ci] count_orphans`).
+        #[arg(long)]
+        count_orphans: bool,
+        /// Protect symbols referenced by the pytest-collected test suite.
+        #[arg(long)]
+        use_test_fingerprint: bool,
+        #[command(flatten)]
+        rules: RulesArgs,
+        /// Exit non-zero if the scan reports any warning or error diagnostic.
+        #[arg(long)]
+        deny_warnings: bool,
+        /// Report only on symbols defined in this file (repeatable). The whole
+        /// project is still analysed, so references from other files count.
+        #[arg(long = "only", value_name = "FILE", conflicts_with = "write_baseline")]
+        only: Vec<PathBuf>,
+        /// Like `--only`, for each path read from stdin, one per line, relative
+        /// to the project root (`git diff --name-only | janitor scan . --changed`).
+        #[arg(long, conflicts_with = "write_baseline")]
+        changed: bool,
+        /// Scan this project and report only on `path`, a file in it:
+        /// `scan FILE --project-root DIR` is `scan DIR --only FILE`.
+        #[arg(long, value_name = "DIR", conflicts_with = "write_baseline")]
+        project_root: Option<PathBuf>,
+        #[command(flatten)]
+        roots: RootsArgs,
     },
     /// Detect (and optionally refactor) structurally-duplicate functions.
     Dedup {
         /// Python file or directory to analyse.
         path: PathBuf,
-        /// Rewrite duplicates using the Safe Proxy Pattern and verify with pytest.
+        /// Rewrite duplicates using the Safe Proxy Pattern and verify with the test suite.
         #[arg(long)]
         apply: bool,
         /// Ed25519 purge token (required with --apply).
         #[arg(long)]
         token: Option<String>,
+        /// Dotted module that receives the shared `_impl` (default: first file alphabetically).
+        #[arg(long)]
+        canonical_module: Option<String>,
+        /// With `--apply`, write the rewrite to FILE as a unified diff for
+        /// `git apply` instead of changing the project; no tests are run.
+        #[arg(long, value_name = "FILE", requires = "apply")]
+        patch: Option<PathBuf>,
+        /// Report near-duplicates instead of exact ones: bodies that differ
+        /// by a few statements (Python only).
+        #[arg(long, conflicts_with = "apply")]
+        near: bool,
+        /// Minimum similarity (0.0–1.0) for `--near`.
+        #[arg(long, default_value_t = 0.85, requires = "near")]
+        threshold: f32,
+        /// Ignore function bodies with fewer than N structural nodes
+        /// (default 8), so trivial stubs do not group together.
+        #[arg(long, value_name = "N", conflicts_with = "near")]
+        min_nodes: Option<u32>,
+        #[command(flatten)]
+        rules: RulesArgs,
     },
     /// Shadow tree management.
     Shadow {
@@ -44,15 +142,162 @@ enum Commands {
     Clean {
         /// Python project root.
         path: PathBuf,
-        /// Ed25519 purge token (required).
+        /// Ed25519 purge token (required unless `--dry-run`).
+        #[arg(long, required_unless_present = "dry_run")]
+        token: Option<String>,
+        /// Print the deletion plan and simulate it without modifying the project.
+        #[arg(long, conflicts_with_all = ["remove_imports", "bisect"])]
+        dry_run: bool,
+        /// With `--dry-run`, also write the plan (default `.janitor/plan.json`).
+        #[arg(long, value_name = "FILE", num_args = 0..=1, requires = "dry_run")]
+        write_plan: Option<Option<PathBuf>>,
+        /// Execute a plan written by `--dry-run --write-plan` verbatim.
+        #[arg(long, value_name = "FILE", conflicts_with_all = ["dry_run", "remove_imports", "bisect"])]
+        from_plan: Option<PathBuf>,
+        /// Leave orphan files in place; only delete dead symbols.
+        #[arg(long)]
+        skip_orphans: bool,
+        /// Runtime logs; symbols named in them are never deleted (`.json`/`.json.gz` = OTLP).
+        #[arg(long, num_args = 1..)]
+        logs: Vec<PathBuf>,
+        /// `coverage json` report; functions whose bodies executed are never deleted.
+        #[arg(long)]
+        coverage: Option<PathBuf>,
+        /// Also remove import bindings nothing in their module reads.
+        #[arg(long)]
+        remove_imports: bool,
+        /// When the shadow simulation fails, bisect for the files whose removal
+        /// breaks the tests, keep those, and clean the rest.
+        #[arg(long)]
+        bisect: bool,
+        /// Write the deletions to FILE as a unified diff for `git apply`
+        /// instead of changing the project; no tests are run.
+        #[arg(long, value_name = "FILE", conflicts_with_all = ["dry_run", "from_plan", "bisect"])]
+        patch: Option<PathBuf>,
+        /// Clean even when the test command is not installed, so nothing
+        /// verifies the deletions.
+        #[arg(long, conflicts_with_all = ["dry_run", "patch"])]
+        allow_unverified: bool,
+        #[command(flatten)]
+        rules: RulesArgs,
+        #[command(flatten)]
+        roots: RootsArgs,
+    },
+    /// List or resurrect files and symbol backups held in `.janitor/ghost`.
+    Restore {
+        /// Python project root.
+        path: PathBuf,
+        /// Enumerate ghosted files and symbol backups (default).
         #[arg(long)]
-        token: String,
+        list: bool,
+        /// Restore every entry of the most recent transaction.
+        #[arg(long, conflicts_with = "file")]
+        all: bool,
+        /// Restore one file, given relative to the project root.
+        #[arg(long)]
+        file: Option<PathBuf>,
     },
     /// Launch the Ratatui TUI dashboard from a saved symbol registry.
     Dashboard {
         /// Python project root (reads .janitor/symbols.rkyv).
         path: PathBuf,
     },
+    /// Summarise a saved symbol registry as text.
+    Report {
+        /// Python project root (reads .janitor/symbols.rkyv).
+        path: PathBuf,
+        /// Break dead code down by top-level directory.
+        #[arg(long)]
+        by_dir: bool,
+        /// Also write a self-contained HTML report to this file.
+        #[arg(long, value_name = "OUT")]
+        html: Option<PathBuf>,
+    },
+    /// Compare two saved symbol registries (e.g. main vs. a PR branch).
+    Diff {
+        /// Baseline `symbols.rkyv`.
+        old: PathBuf,
+        /// Updated `symbols.rkyv`.
+        new: PathBuf,
+        /// Print the diff as JSON instead of tables.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Explain the pipeline's verdict on one symbol: callers, protection, grep hits.
+    Why {
+        /// Python project root to analyse.
+        path: PathBuf,
+        /// `qualified_name` or `file::qualified_name` (file relative to `path`).
+        symbol: String,
+        /// Protect all public top-level symbols (library mode), as in `scan`.
+        #[arg(long)]
+        library: bool,
+        #[command(flatten)]
+        rules: RulesArgs,
+        #[command(flatten)]
+        roots: RootsArgs,
+    },
+    /// Re-scan incrementally on every file change; one JSON line per scan on stdout.
+    Watch {
+        /// Python project root to watch.
+        path: PathBuf,
+        /// Protect all public top-level symbols (library mode), as in `scan`.
+        #[arg(long)]
+        library: bool,
+        /// Quiet period that ends a burst of file events, in milliseconds.
+        #[arg(long, value_name = "MS", default_value_t = 200)]
+        debounce: u64,
+    },
+    /// Language server on stdio: dead-symbol diagnostics and protection hovers.
+    Lsp {
+        /// Protect all public top-level symbols (library mode), as in `scan`.
+        #[arg(long)]
+        library: bool,
+    },
+    /// Git pre-commit integration: fail commits that add dead code.
+    Hook {
+        #[command(subcommand)]
+        cmd: HookCmd,
+    },
+    /// Upgrade `.janitor/symbols.rkyv` written by an older janitor in place.
+    Migrate {
+        /// Python project root.
+        path: PathBuf,
+    },
+    /// Dump the reference graph for debugging pipeline decisions.
+    Graph {
+        /// Python project root to analyse.
+        path: PathBuf,
+        /// Export format written to stdout.
+        #[arg(long, value_enum, default_value_t = GraphFormat::Dot)]
+        format: GraphFormat,
+        /// Only export the neighbourhood of this qualified name.
+        #[arg(long)]
+        focus: Option<String>,
+        /// Hops kept around `--focus`.
+        #[arg(long, default_value_t = 2, requires = "focus")]
+        depth: usize,
+    },
+}
+
+/// Report format for `janitor scan`.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    /// ASCII tables on stdout.
+    Text,
+    /// JSON document on stdout; human-readable report on stderr.
+    Json,
+    /// SARIF 2.1.0 log on stdout (GitHub code scanning); report on stderr.
+    Sarif,
+}
+
+/// Export format for `janitor graph`.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum GraphFormat {
+    /// Graphviz DOT, one cluster per file.
+    Dot,
+    /// JSON adjacency list with registry metadata.
+    Json,
 }
 
 #[derive(Subcommand)]
@@ -62,172 +307,853 @@ enum ShadowCmd {
         /// Python project root.
         path: PathBuf,
     },
+    /// Bring an existing shadow tree up to date with the source tree.
+    Sync {
+        /// Python project root.
+        path: PathBuf,
+    },
+    /// List shadow links whose source file no longer exists.
+    Verify {
+        /// Python project root.
+        path: PathBuf,
+    },
+}
+
+impl Commands {
+    /// The project whose `.janitor/lock` the command takes, how, and the name
+    /// recorded for other processes. `diff` reads two registry files, `lsp`
+    /// learns its root from the client and `hook install` only writes to
+    /// `.git/`; none of them locks.
+    fn lock_target(&self) -> Option<(&Path, LockMode, &'static str)> {
+        use LockMode::{Exclusive, Shared};
+        Some(match self {
+            Commands::Scan {
+                path, project_root, ..
+            } => (project_root.as_deref().unwrap_or(path), Shared, "scan"),
+            Commands::Dedup {
+                path, apply, patch, ..
+            } => match (apply, patch) {
+                (true, None) => (path, Exclusive, "dedup --apply"),
+                (true, Some(_)) => (path, Shared, "dedup --apply --patch"),
+                (false, _) => (path, Shared, "dedup"),
+            },
+            Commands::Shadow { cmd } => match cmd {
+                ShadowCmd::Init { path } => (path, Exclusive, "shadow init"),
+                ShadowCmd::Sync { path } => (path, Exclusive, "shadow sync"),
+                ShadowCmd::Verify { path } => (path, Shared, "shadow verify"),
+            },
+            Commands::Clean {
+                path,
+                patch: Some(_),
+                ..
+            } => (path, Shared, "clean --patch"),
+            // Even a dry run rebuilds the shadow tree to simulate in.
+            Commands::Clean { path, .. } => (path, Exclusive, "clean"),
+            Commands::Restore {
+                path, all, file, ..
+            } => {
+                if *all || file.is_some() {
+                    (path, Exclusive, "restore")
+                } else {
+                    (path, Shared, "restore --list")
+                }
+            }
+            Commands::Dashboard { path } => (path, Shared, "dashboard"),
+            Commands::Report { path, .. } => (path, Shared, "report"),
+            Commands::Why { path, .. } => (path, Shared, "why"),
+            Commands::Watch { path, .. } => (path, Shared, "watch"),
+            Commands::Graph { path, .. } => (path, Shared, "graph"),
+            // Readers map the registry file; nobody may hold it while it is
+            // replaced by a different format.
+            Commands::Migrate { path } => (path, Exclusive, "migrate"),
+            Commands::Hook {
+                cmd: HookCmd::Run { root, .. },
+            } => (root, Shared, "hook run"),
+            Commands::Hook {
+                cmd: HookCmd::Install { .. },
+            }
+            | Commands::Diff { .. }
+            | Commands::Lsp { .. } => return None,
+        })
+    }
+}
+
+/// `--rules` files shared by every command that judges liveness.
+#[derive(Args)]
+struct RulesArgs {
+    /// JSON rules file merged into the baked wisdom rules (repeatable).
+    #[arg(long = "rules", value_name = "FILE")]
+    files: Vec<PathBuf>,
+    /// Fail on an invalid `--rules` file instead of skipping it.
+    #[arg(long)]
+    strict_rules: bool,
+}
+
+impl RulesArgs {
+    fn apply(&self, janitor: Janitor) -> anyhow::Result<Janitor> {
+        janitor.with_rules(&self.files, self.strict_rules)
+    }
+}
+
+/// `--root` monorepo roots, shared by the commands that build the graph of
+/// the whole project.
+#[derive(Args)]
+struct RootsArgs {
+    /// Monorepo root, relative to the project (repeatable; `[workspace]
+    /// roots`). Imports resolve against each root in order, and scan totals
+    /// are also reported per root.
+    #[arg(long = "root", value_name = "DIR")]
+    roots: Vec<String>,
+}
+
+impl RootsArgs {
+    fn apply(&self, janitor: Janitor) -> anyhow::Result<Janitor> {
+        if self.roots.is_empty() {
+            return Ok(janitor);
+        }
+        janitor.with_roots(self.roots.clone())
+    }
+}
+
+#[derive(Subcommand)]
+enum HookCmd {
+    /// Write the repository's pre-commit hook, which runs `hook run` with
+    /// this binary.
+    Install {
+        /// Git repository root.
+        #[arg(default_value = ".")]
+        path: PathBuf,
+        /// Replace an existing pre-commit hook.
+        #[arg(long)]
+        force: bool,
+        /// Print a `.pre-commit-config.yaml` entry instead of writing a hook.
+        #[arg(long)]
+        pre_commit_config: bool,
+    },
+    /// Report dead symbols in the staged Python files that the baseline does
+    /// not accept, one `file:line` per line; exit 1 if there are any.
+    Run {
+        /// Staged files (default: `git diff --cached --name-only`).
+        files: Vec<PathBuf>,
+        /// Project root; staged paths are relative to it.
+        #[arg(long, default_value = ".")]
+        root: PathBuf,
+        /// Read the staged paths from stdin, one per line.
+        #[arg(long, conflicts_with = "files")]
+        stdin: bool,
+        /// Baseline of accepted dead symbols, relative to the root; optional.
+        #[arg(long, value_name = "FILE", default_value = ".janitor/baseline.json")]
+        baseline: PathBuf,
+    },
 }
 
+/// Exit code of a scan that found dead symbols under `--fail-on-dead`, or
+/// failed `--fail-on-new`.
+const EXIT_DEAD: u8 = 1;
+/// Exit code of a scan over `--max-dead` or `--max-dead-bytes`.
+const EXIT_OVER_THRESHOLD: u8 = 2;
+/// Exit code of any other failure, including usage errors (clap's own code,
+/// 2, is taken by [`EXIT_OVER_THRESHOLD`]).
+const EXIT_INTERNAL: u8 = 3;
+/// Exit code of a scan that reported warnings under `--deny-warnings`.
+const EXIT_WARNINGS: u8 = 4;
+
+/// A command that ran to completion but failed a CI gate.
+#[derive(Debug)]
+struct GateFailure {
+    /// Process exit code: [`EXIT_DEAD`], [`EXIT_OVER_THRESHOLD`] or [`EXIT_WARNINGS`].
+    code: u8,
+    message: String,
+}
+
+impl std::fmt::Display for GateFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for GateFailure {}
+
 #[tokio::main]
-async fn main() -> anyhow::Result<()> {
+async fn main() -> ExitCode {
     if let Err(e) = dotenvy::dotenv() {
         eprintln!("warning: .env: {}", e);
     }
 
-    let _root = env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
-    let cli = Cli::parse();
+    let cli = match Cli::try_parse() {
+        Ok(cli) => cli,
+        Err(e) => {
+            e.print().ok();
+            return if e.use_stderr() {
+                ExitCode::from(EXIT_INTERNAL)
+            } else {
+                ExitCode::SUCCESS
+            };
+        }
+    };
+    match run(&cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => match e.downcast_ref::<GateFailure>() {
+            Some(failure) => {
+                eprintln!("{}", failure);
+                ExitCode::from(failure.code)
+            }
+            None => {
+                // A failed test run: show what the suite printed first.
+                let failure = e
+                    .chain()
+                    .find_map(|c| c.downcast_ref::<janitor::verification::VerificationFailure>());
+                for output in failure.iter().flat_map(|f| [&f.stdout, &f.stderr]) {
+                    if !output.trim().is_empty() {
+                        eprintln!("{}", output.trim_end());
+                    }
+                }
+                eprintln!("Error: {:?}", e);
+                ExitCode::from(EXIT_INTERNAL)
+            }
+        },
+    }
+}
 
+fn run(cli: &Cli) -> anyhow::Result<()> {
+    let _lock = match cli.command.lock_target() {
+        Some((path, mode, command)) => janitor::lock_project(path, mode, command, cli.wait)?,
+        None => None,
+    };
     match &cli.command {
         Commands::Scan {
             path,
             library,
             verbose,
-        } => cmd_scan(path, *library, *verbose)?,
-        Commands::Dedup { path, apply, token } => cmd_dedup(path, *apply, token.as_deref())?,
+            format,
+            sarif_level,
+            no_cache,
+            strict_star_imports,
+            logs,
+            coverage,
+            write_baseline,
+            baseline,
+            fail_on_new,
+            fail_on_dead,
+            max_dead,
+            max_dead_bytes,
+            count_orphans,
+            use_test_fingerprint,
+            rules,
+            deny_warnings,
+            only,
+            changed,
+            project_root,
+            roots,
+        } => {
+            let scope = scan_scope(path, only, *changed, project_root.is_some())?;
+            let janitor = rules
+                .apply(Janitor::open(project_root.as_deref().unwrap_or(path))?)?
+                .with_library_mode(*library)
+                .with_cache(!*no_cache)
+                .with_strict_star_imports(*strict_star_imports)
+                .with_test_fingerprint(*use_test_fingerprint)
+                .with_evidence(RuntimeEvidence {
+                    logs: logs.clone(),
+                    coverage: coverage.clone(),
+                });
+            let janitor = match scope {
+                Some(files) => janitor.with_scope(files),
+                None => janitor,
+            };
+            let janitor = roots.apply(janitor)?;
+            let gate = BaselineArgs {
+                write: write_baseline.as_deref(),
+                compare: baseline.as_deref(),
+                fail_on_new: *fail_on_new,
+                deny_warnings: *deny_warnings,
+                ci: CiGate::new(janitor.config().ci.clone())
+                    .fail_on_dead(*fail_on_dead)
+                    .max_dead(*max_dead)
+                    .max_dead_bytes(*max_dead_bytes)
+                    .count_orphans(*count_orphans),
+            };
+            cmd_scan(&janitor, &gate, *verbose, *format, *sarif_level)?
+        }
+        Commands::Dedup {
+            path,
+            apply,
+            token,
+            canonical_module,
+            patch,
+            near,
+            threshold,
+            min_nodes,
+            rules,
+        } => {
+            let options = DedupOptions {
+                python_only: *apply,
+                near: near.then_some(*threshold),
+                min_nodes: *min_nodes,
+            };
+            cmd_dedup(
+                path,
+                rules,
+                *apply,
+                token.as_deref(),
+                canonical_module.as_deref(),
+                patch.as_deref(),
+                &options,
+            )?
+        }
         Commands::Shadow { cmd } => match cmd {
             ShadowCmd::Init { path } => cmd_shadow_init(path)?,
+            ShadowCmd::Sync { path } => cmd_shadow_sync(path)?,
+            ShadowCmd::Verify { path } => cmd_shadow_verify(path)?,
         },
-        Commands::Clean { path, token } => cmd_clean(path, token)?,
+        Commands::Clean {
+            path,
+            token,
+            dry_run,
+            write_plan,
+            from_plan,
+            skip_orphans,
+            logs,
+            coverage,
+            remove_imports,
+            bisect,
+            patch,
+            allow_unverified,
+            rules,
+            roots,
+        } => {
+            let janitor = roots
+                .apply(rules.apply(Janitor::open(path)?)?)?
+                .with_evidence(RuntimeEvidence {
+                    logs: logs.clone(),
+                    coverage: coverage.clone(),
+                });
+            // The token is filled in once `require_token` has accepted it.
+            let modes = CleanOptions {
+                token: String::new(),
+                skip_orphans: *skip_orphans,
+                remove_imports: *remove_imports,
+                bisect: *bisect,
+                allow_unverified: *allow_unverified,
+            };
+            if let Some(plan) = from_plan {
+                cmd_clean_from_plan(&janitor, token.as_deref(), modes, plan)?
+            } else if let Some(out) = patch {
+                cmd_clean_patch(&janitor, token.as_deref(), modes, out)?
+            } else if *dry_run {
+                let write_plan = write_plan.as_ref().map(|p| {
+                    p.clone()
+                        .unwrap_or_else(|| path.join(".janitor").join("plan.json"))
+                });
+                cmd_clean_dry_run(&janitor, &modes, write_plan.as_deref())?
+            } else {
+                cmd_clean(&janitor, token.as_deref(), modes)?
+            }
+        }
+        Commands::Restore {
+            path,
+            list,
+            all,
+            file,
+        } => cmd_restore(path, *list, *all, file.as_deref())?,
         Commands::Dashboard { path } => cmd_dashboard(path)?,
+        Commands::Report { path, by_dir, html } => cmd_report(path, *by_dir, html.as_deref())?,
+        Commands::Diff { old, new, json } => cmd_diff(old, new, *json)?,
+        Commands::Why {
+            path,
+            symbol,
+            library,
+            rules,
+            roots,
+        } => cmd_why(path, symbol, *library, rules, roots)?,
+        Commands::Watch {
+            path,
+            library,
+            debounce,
+        } => {
+            let janitor = Janitor::open(path)?.with_library_mode(*library);
+            cmd_watch(&janitor, Duration::from_millis(*debounce))?
+        }
+        Commands::Lsp { library } => cmd_lsp(*library)?,
+        Commands::Migrate { path } => cmd_migrate(path)?,
+        Commands::Graph {
+            path,
+            format,
+            focus,
+            depth,
+        } => cmd_graph(path, *format, focus.as_deref(), *depth)?,
+        Commands::Hook { cmd } => match cmd {
+            HookCmd::Install {
+                path,
+                force,
+                pre_commit_config,
+            } => cmd_hook_install(path, *force, *pre_commit_config)?,
+            HookCmd::Run {
+                files,
+                root,
+                stdin,
+                baseline,
+            } => cmd_hook_run(root, files, *stdin, baseline)?,
+        },
     }
 
     Ok(())
 }
 
+/// Prints an operation's [`Event`]s: progress lines to stdout, warnings to
+/// stderr. Pipeline progress is not drawn.
+fn print_event(event: Event) {
+    match event {
+        Event::Info(text) => println!("{}", text),
+        Event::Warning(text) => eprintln!("{}", text),
+        Event::Pipeline(_) => {}
+    }
+}
+
+/// Prints the warnings met while opening the project to stderr.
+fn print_warnings(janitor: &Janitor) {
+    for w in janitor.warnings() {
+        eprintln!("warning: {}", w);
+    }
+}
+
 // ---------------------------------------------------------------------------
 // scan
 // ---------------------------------------------------------------------------
 
-fn cmd_scan(project_root: &Path, library: bool, verbose: bool) -> anyhow::Result<()> {
-    use anatomist::{heuristics::pytest::PytestFixtureHeuristic, parser::ParserHost, pipeline};
-    use common::registry::{symbol_hash, SymbolEntry, SymbolRegistry};
+fn cmd_scan(
+    janitor: &Janitor,
+    gate: &BaselineArgs,
+    verbose: bool,
+    format: OutputFormat,
+    sarif_level: SarifLevel,
+) -> anyhow::Result<()> {
+    let project_root = janitor.root();
+
+    // Live progress only when a human is watching stderr; stdout may carry
+    // the JSON or SARIF document, so every other notice goes to stderr too.
+    let mut line = std::io::stderr()
+        .is_terminal()
+        .then(|| progress::ProgressLine::new(std::io::stderr()));
+    let mut result = janitor.scan_observed(&mut |event| match event {
+        Event::Pipeline(e) => {
+            if let Some(line) = line.as_mut() {
+                line.handle(e);
+            }
+        }
+        Event::Info(text) | Event::Warning(text) => eprintln!("{}", text),
+    })?;
+
+    if let Some(path) = gate.write {
+        Baseline::from_dead(&result.dead, project_root).save(path)?;
+        eprintln!(
+            "Baseline of {} dead symbols written to {}",
+            result.dead.len(),
+            path.display()
+        );
+    }
+
+    // Baselined symbols leave `result.dead` so every output format reports
+    // only new ones; they are still persisted to the registry below.
+    let baselined = match gate.compare {
+        Some(path) => Baseline::load(path)?.hold_back(&mut result, project_root),
+        None => Vec::new(),
+    };
+    let baselined_count = gate.compare.map(|_| baselined.len());
 
-    let mut host = ParserHost::new()?;
-    host.register_heuristic(Box::new(PytestFixtureHeuristic));
+    match format {
+        OutputFormat::Text => {
+            print_scan_report(&mut std::io::stdout(), &result, baselined_count, verbose)?
+        }
+        OutputFormat::Json => {
+            // Stdout carries only the JSON document; the human report goes to stderr.
+            print_scan_report(&mut std::io::stderr(), &result, baselined_count, verbose)?;
+            println!("{}", scan_json(&result)?);
+        }
+        OutputFormat::Sarif => {
+            print_scan_report(&mut std::io::stderr(), &result, baselined_count, verbose)?;
+            let log = report::sarif::render(&result, project_root, sarif_level);
+            println!("{}", serde_json::to_string_pretty(&log)?);
+        }
+    }
 
-    let result = pipeline::run(project_root, &mut host, library)?;
+    // Persist the full registry to .janitor/symbols.rkyv for the dashboard;
+    // a scoped scan saw only part of the project and leaves it alone.
+    if janitor.scope().is_none() {
+        if let Err(e) = janitor.save_registry(&result, &baselined) {
+            eprintln!("warning: {}", e);
+        }
+    }
 
-    println!("+------------------------------------------+");
-    println!("| JANITOR SCAN                             |");
-    println!("+------------------------------------------+");
-    println!("| Total entities : {:>22} |", result.total);
-    println!("| Dead           : {:>22} |", result.dead.len());
-    println!("| Protected      : {:>22} |", result.protected.len());
-    println!("| Orphan files   : {:>22} |", result.orphan_files.len());
-    println!("+------------------------------------------+");
+    let failure = |code, message: String| GateFailure { code, message };
+    if gate.fail_on_new && !result.dead.is_empty() {
+        let message = format!("{} dead symbols are not in the baseline", result.dead.len());
+        return Err(failure(EXIT_DEAD, message).into());
+    }
+    if gate.deny_warnings && has_warnings(&result.diagnostics) {
+        let message = "scan reported warnings (--deny-warnings)".to_string();
+        return Err(failure(EXIT_WARNINGS, message).into());
+    }
+    match gate.ci.verdict(&result) {
+        Some(failure) => Err(failure.into()),
+        None => Ok(()),
+    }
+}
+
+/// The files a scan reports on: `--only` files and the positional `path` under
+/// `--project-root` (both relative to the working directory), plus `--changed`
+/// paths from stdin (relative to the project root). `None` when unscoped.
+fn scan_scope(
+    path: &Path,
+    only: &[PathBuf],
+    changed: bool,
+    path_is_file: bool,
+) -> anyhow::Result<Option<Vec<PathBuf>>> {
+    if only.is_empty() && !changed && !path_is_file {
+        return Ok(None);
+    }
+    let mut files = only
+        .iter()
+        .map(PathBuf::as_path)
+        .chain(path_is_file.then_some(path))
+        .map(std::path::absolute)
+        .collect::<std::io::Result<Vec<PathBuf>>>()?;
+    if changed {
+        let stdin = std::io::read_to_string(std::io::stdin())?;
+        files.extend(read_changed(&stdin));
+    }
+    Ok(Some(files))
+}
+
+/// Paths in `git diff --name-only` output, one per line; blank lines skipped.
+fn read_changed(text: &str) -> impl Iterator<Item = PathBuf> + '_ {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Baseline and exit-code flags of `scan`.
+#[derive(Default)]
+struct BaselineArgs<'a> {
+    /// `--write-baseline`: record this scan's dead symbols.
+    write: Option<&'a Path>,
+    /// `--baseline`: split dead symbols into new and accepted.
+    compare: Option<&'a Path>,
+    /// `--fail-on-new`: error out if any dead symbol is new.
+    fail_on_new: bool,
+    /// `--deny-warnings`: error out on any warning-level diagnostic.
+    deny_warnings: bool,
+    /// Dead-code thresholds.
+    ci: CiGate,
+}
+
+/// `--fail-on-dead`, `--max-dead`, `--max-dead-bytes` and `--count-orphans`
+/// over the `[ci]` table of `.janitor.toml`. Flags win over the file.
+#[derive(Default)]
+struct CiGate(common::config::CiConfig);
+
+impl CiGate {
+    fn new(config: common::config::CiConfig) -> Self {
+        Self(config)
+    }
+
+    fn fail_on_dead(mut self, enabled: bool) -> Self {
+        if enabled {
+            self.0.fail_on_dead = Some(true);
+        }
+        self
+    }
+
+    fn max_dead(mut self, max: Option<u64>) -> Self {
+        self.0.max_dead = max.or(self.0.max_dead);
+        self
+    }
+
+    fn max_dead_bytes(mut self, max: Option<u64>) -> Self {
+        self.0.max_dead_bytes = max.or(self.0.max_dead_bytes);
+        self
+    }
+
+    fn count_orphans(mut self, enabled: bool) -> Self {
+        if enabled {
+            self.0.count_orphans = Some(true);
+        }
+        self
+    }
+
+    /// The failure `result` earns, if any. Thresholds take precedence: with
+    /// one set, a scan within it passes even under `fail_on_dead`.
+    fn verdict(&self, result: &anatomist::pipeline::ScanResult) -> Option<GateFailure> {
+        let ci = &self.0;
+        let (count, bytes) = dead_totals(result, ci.count_orphans == Some(true));
+        let over = |what: &str, found: u64, max: u64| GateFailure {
+            code: EXIT_OVER_THRESHOLD,
+            message: format!("{} {} exceed the limit of {}", found, what, max),
+        };
+        if let Some(max) = ci.max_dead.filter(|max| count > *max) {
+            return Some(over("dead symbols", count, max));
+        }
+        if let Some(max) = ci.max_dead_bytes.filter(|max| bytes > *max) {
+            return Some(over("bytes of dead code", bytes, max));
+        }
+        let thresholds = ci.max_dead.is_some() || ci.max_dead_bytes.is_some();
+        (ci.fail_on_dead == Some(true) && !thresholds && count > 0).then(|| GateFailure {
+            code: EXIT_DEAD,
+            message: format!("{} dead symbols found (--fail-on-dead)", count),
+        })
+    }
+}
+
+/// Dead symbols and their bytes. With `count_orphans`, each orphan file
+/// counts once more and by its whole size, replacing its symbols' bytes.
+fn dead_totals(result: &anatomist::pipeline::ScanResult, count_orphans: bool) -> (u64, u64) {
+    let orphans: HashSet<&str> = if count_orphans {
+        result.orphan_files.iter().map(String::as_str).collect()
+    } else {
+        HashSet::new()
+    };
+    let symbol_bytes: u64 = result
+        .dead
+        .iter()
+        .filter(|e| !orphans.contains(e.file_path.as_str()))
+        .map(|e| u64::from(e.end_byte.saturating_sub(e.start_byte)))
+        .sum();
+    let orphan_bytes: u64 = orphans
+        .iter()
+        .filter_map(|path| std::fs::metadata(path).ok())
+        .map(|m| m.len())
+        .sum();
+    let count = result.dead.len() + orphans.len();
+    (count as u64, symbol_bytes + orphan_bytes)
+}
+
+/// Writes the human-readable scan report to `out`.
+///
+/// `baselined` is the number of dead symbols held back by `--baseline`, if one was given.
+fn print_scan_report(
+    out: &mut dyn Write,
+    result: &anatomist::pipeline::ScanResult,
+    baselined: Option<usize>,
+    verbose: bool,
+) -> std::io::Result<()> {
+    writeln!(out, "+------------------------------------------+")?;
+    writeln!(out, "| JANITOR SCAN                             |")?;
+    writeln!(out, "+------------------------------------------+")?;
+    writeln!(out, "| Total entities : {:>22} |", result.total)?;
+    writeln!(out, "| Files parsed   : {:>22} |", result.files_parsed)?;
+    writeln!(out, "| Cache hits     : {:>22} |", result.cache_hits)?;
+    writeln!(out, "| Dead           : {:>22} |", result.dead.len())?;
+    if let Some(baselined) = baselined {
+        writeln!(out, "| Baselined      : {:>22} |", baselined)?;
+    }
+    writeln!(out, "| Protected      : {:>22} |", result.protected.len())?;
+    writeln!(out, "| Runtime rescued: {:>22} |", result.runtime_rescued)?;
+    writeln!(out, "| Test-only      : {:>22} |", result.test_only.len())?;
+    writeln!(
+        out,
+        "| Low confidence : {:>22} |",
+        result.low_confidence.len()
+    )?;
+    writeln!(
+        out,
+        "| Orphan files   : {:>22} |",
+        result.orphan_files.len()
+    )?;
+    writeln!(out, "+------------------------------------------+")?;
 
     if result.dead.is_empty() {
-        println!("No dead symbols detected.");
+        writeln!(out, "No dead symbols detected.")?;
     } else {
-        println!("\nDEAD SYMBOLS:");
-        for entity in &result.dead {
-            println!(
+        writeln!(out, "\nDEAD SYMBOLS:")?;
+        for (i, entity) in result.dead.iter().enumerate() {
+            writeln!(
+                out,
                 "  {}:{} - {}",
                 entity.file_path, entity.start_line, entity.qualified_name
-            );
+            )?;
+            if let (true, Some(ev)) = (verbose, result.evidence.get(i)) {
+                writeln!(
+                    out,
+                    "      edges={} ignored_edges={} grep_searched={} orphan_file={} passed=[{}]",
+                    ev.incoming_edges,
+                    ev.ignored_edges,
+                    ev.grep_searched,
+                    ev.orphan_file,
+                    ev.stages_passed.join(", ")
+                )?;
+            }
         }
     }
 
-    println!("\n+------------------------------------------+");
-    println!("| DEAD FILES (ORPHANS)                     |");
-    println!("+------------------------------------------+");
-    println!("| Count          : {:>22} |", result.orphan_files.len());
-    println!("+------------------------------------------+");
+    writeln!(out, "\n+------------------------------------------+")?;
+    writeln!(out, "| DEAD FILES (ORPHANS)                     |")?;
+    writeln!(out, "+------------------------------------------+")?;
+    writeln!(
+        out,
+        "| Count          : {:>22} |",
+        result.orphan_files.len()
+    )?;
+    writeln!(out, "+------------------------------------------+")?;
     if result.orphan_files.is_empty() {
-        println!("No orphan files detected.");
+        writeln!(out, "No orphan files detected.")?;
     } else {
         for path in &result.orphan_files {
-            println!("  {path}");
+            writeln!(out, "  {path}")?;
+        }
+    }
+    if !result.orphan_packages.is_empty() {
+        writeln!(out, "\nDEAD PACKAGES (nothing outside imports them):")?;
+        for package in &result.orphan_packages {
+            writeln!(
+                out,
+                "  {}/  {}",
+                package,
+                dashboard::aggregate::format_bytes(result.orphan_package_bytes[package])
+            )?;
+        }
+    }
+
+    if !result.roots.is_empty() {
+        writeln!(out, "\nWORKSPACE ROOTS:")?;
+        for root in &result.roots {
+            writeln!(
+                out,
+                "  {}/  {} entities, {} dead ({}), {} orphan files",
+                root.root,
+                root.total,
+                root.dead,
+                dashboard::aggregate::format_bytes(root.dead_bytes),
+                root.orphan_files
+            )?;
+        }
+    }
+
+    if !result.test_only.is_empty() {
+        writeln!(out, "\nTEST-ONLY SYMBOLS (referenced only by tests):")?;
+        for entity in &result.test_only {
+            writeln!(
+                out,
+                "  {}:{} - {}",
+                entity.file_path, entity.start_line, entity.qualified_name
+            )?;
+            if let (true, Some(detail)) = (verbose, &entity.protection_detail) {
+                writeln!(out, "      {}", detail)?;
+            }
+        }
+    }
+
+    if !result.low_confidence.is_empty() {
+        writeln!(
+            out,
+            "\nLOW-CONFIDENCE DEAD SYMBOLS (in scripts/bin; not on the kill list):"
+        )?;
+        for entity in &result.low_confidence {
+            writeln!(
+                out,
+                "  {}:{} - {}",
+                entity.file_path, entity.start_line, entity.qualified_name
+            )?;
         }
     }
 
     if verbose {
-        println!("\nPROTECTED SYMBOLS:");
+        writeln!(out, "\nPROTECTED SYMBOLS:")?;
         for entity in &result.protected {
-            println!(
+            writeln!(
+                out,
                 "  {}:{} - {} [{:?}]",
                 entity.file_path, entity.start_line, entity.qualified_name, entity.protected_by
-            );
+            )?;
+            if let Some(detail) = &entity.protection_detail {
+                writeln!(out, "      {}", detail)?;
+            }
         }
     }
 
-    // Persist the full registry to .janitor/symbols.rkyv for the dashboard.
-    let rkyv_path = project_root.join(".janitor").join("symbols.rkyv");
-    let mut registry = SymbolRegistry::new();
-    for entity in result.dead.iter().chain(result.protected.iter()) {
-        registry.insert(SymbolEntry {
-            id: symbol_hash(&entity.symbol_id()),
-            name: entity.name.clone(),
-            qualified_name: entity.qualified_name.clone(),
-            file_path: entity.file_path.clone(),
-            entity_type: entity.entity_type as u8,
-            start_line: entity.start_line,
-            end_line: entity.end_line,
-            start_byte: entity.start_byte,
-            end_byte: entity.end_byte,
-            structural_hash: entity.structural_hash.unwrap_or(0),
-            protected_by: entity.protected_by,
-        });
-    }
-    if let Err(e) = registry.save(&rkyv_path) {
-        eprintln!("warning: could not save symbols.rkyv: {}", e);
+    if !result.skipped_files.is_empty() {
+        writeln!(out, "\nSKIPPED FILES (# janitor: skip-file):")?;
+        for file in &result.skipped_files {
+            writeln!(out, "  {}", file)?;
+        }
+    }
+
+    for severity in [Severity::Error, Severity::Warning, Severity::Info] {
+        let mut group = result
+            .diagnostics
+            .iter()
+            .filter(|d| d.severity == severity)
+            .peekable();
+        if group.peek().is_none() {
+            continue;
+        }
+        writeln!(out, "\nDIAGNOSTICS ({}):", severity)?;
+        for diagnostic in group {
+            writeln!(out, "  {}", diagnostic)?;
+        }
     }
 
     Ok(())
 }
 
+/// Serializes `result` as the stable `scan --format json` document.
+///
+/// Top-level keys: `dead`, `protected`, `test_only`, `low_confidence`, `total`,
+/// `stage_counts`, `orphan_files`, `orphan_packages`, `diagnostics`,
+/// `skipped_files`.
+/// `protected_by` is the [`common::Protection`] variant name (or `null`).
+/// Each entity also carries `fully_qualified_name`, its dotted
+/// `module_path.qualified_name` form.
+fn scan_json(result: &anatomist::pipeline::ScanResult) -> anyhow::Result<String> {
+    let mut doc = serde_json::to_value(result)?;
+    for (key, entities) in [
+        ("dead", &result.dead),
+        ("protected", &result.protected),
+        ("test_only", &result.test_only),
+        ("low_confidence", &result.low_confidence),
+    ] {
+        let Some(values) = doc[key].as_array_mut() else {
+            continue;
+        };
+        for (value, entity) in values.iter_mut().zip(entities) {
+            value["fully_qualified_name"] = entity.fully_qualified_name().into();
+        }
+    }
+    Ok(serde_json::to_string_pretty(&doc)?)
+}
+
 // ---------------------------------------------------------------------------
 // dedup
 // ---------------------------------------------------------------------------
 
-struct DupGroup {
-    hash: u64,
-    file_path: PathBuf,
-    members: Vec<anatomist::Entity>,
-}
-
-fn cmd_dedup(path: &Path, apply: bool, token: Option<&str>) -> anyhow::Result<()> {
-    use anatomist::{heuristics::pytest::PytestFixtureHeuristic, parser::ParserHost};
-
-    if apply {
-        require_token(token)?;
-    }
+/// `near` is the `--near` similarity threshold, if requested.
+fn cmd_dedup(
+    path: &Path,
+    rules: &RulesArgs,
+    apply: bool,
+    token: Option<&str>,
+    canonical_module: Option<&str>,
+    patch: Option<&Path>,
+    options: &DedupOptions,
+) -> anyhow::Result<()> {
+    let token = if apply {
+        require_token(token, path)?
+    } else {
+        ""
+    };
 
-    let py_files = collect_py_files(path)?;
-    if py_files.is_empty() {
-        println!("No Python files found at: {}", path.display());
+    let janitor = rules.apply(Janitor::open(path)?)?;
+    print_warnings(&janitor);
+    let found = janitor.dedup_observed(options, &mut print_event)?;
+    if found.files == 0 {
+        println!("No source files found at: {}", path.display());
         return Ok(());
     }
-
-    let mut host = ParserHost::new()?;
-    host.register_heuristic(Box::new(PytestFixtureHeuristic));
-
-    let mut all_groups: Vec<DupGroup> = Vec::new();
-
-    for file_path in &py_files {
-        let entities = match host.dissect(file_path) {
-            Ok(e) => e,
-            Err(e) => {
-                eprintln!("warning: skipping {}: {}", file_path.display(), e);
-                continue;
-            }
-        };
-
-        let mut hash_groups: HashMap<u64, Vec<anatomist::Entity>> = HashMap::new();
-        for entity in entities {
-            if let Some(hash) = entity.structural_hash {
-                hash_groups.entry(hash).or_default().push(entity);
-            }
-        }
-
-        for (hash, members) in hash_groups {
-            if members.len() >= 2 {
-                all_groups.push(DupGroup {
-                    hash,
-                    file_path: file_path.clone(),
-                    members,
-                });
-            }
-        }
+    if options.near.is_some() {
+        print_similar_groups(&mut std::io::stdout().lock(), &found.similar)?;
+        return Ok(());
     }
-
-    if all_groups.is_empty() {
+    if found.groups.is_empty() {
         println!("No duplicate functions found.");
         return Ok(());
     }
@@ -235,96 +1161,100 @@ fn cmd_dedup(path: &Path, apply: bool, token: Option<&str>) -> anyhow::Result<()
     println!("+------------------------------------------+");
     println!("| JANITOR DEDUP                            |");
     println!("+------------------------------------------+");
-    println!("| Duplicate groups : {:>20} |", all_groups.len());
+    println!("| Duplicate groups : {:>20} |", found.groups.len());
     println!("+------------------------------------------+");
 
-    for group in &all_groups {
-        println!("\n  Hash: {:016x}", group.hash);
-        for entity in &group.members {
+    for group in &found.groups {
+        let files: HashSet<&str> = group.members.iter().map(|m| m.0.as_str()).collect();
+        if files.len() > 1 {
             println!(
-                "    {}:{} - {}",
-                entity.file_path, entity.start_line, entity.qualified_name
+                "\n  Hash: {:016x} (across {} files)",
+                group.hash,
+                files.len()
             );
+        } else {
+            println!("\n  Hash: {:016x}", group.hash);
+        }
+        for (file_path, qualified_name, _, _) in &group.members {
+            let line = found.start_line(file_path, qualified_name);
+            println!("    {}:{} - {}", file_path, line, qualified_name);
         }
     }
 
-    if apply {
-        apply_dedup(&all_groups, path)?;
+    if let (true, Some(out)) = (apply, patch) {
+        let patch = janitor.patch_dedup_observed(
+            &found.groups,
+            canonical_module,
+            token,
+            &mut print_event,
+        )?;
+        if patch.is_empty() {
+            println!("No rewritable duplicates (candidates are in test code or were skipped).");
+        } else {
+            write_patch(&patch, out)?;
+        }
+    } else if apply {
+        let applied = janitor.apply_dedup_observed(
+            &found.groups,
+            canonical_module,
+            token,
+            &mut print_event,
+        )?;
+        if applied.is_empty() {
+            println!("No rewritable duplicates (candidates are in test code or were skipped).");
+        }
+        for file in &applied {
+            println!("APPLIED + VERIFIED: {}", file);
+        }
     }
 
     Ok(())
 }
 
-fn apply_dedup(groups: &[DupGroup], root_hint: &Path) -> anyhow::Result<()> {
-    use reaper::{ReplacementTarget, SafeDeleter};
-
-    let project_root = if root_hint.is_dir() {
-        root_hint.to_path_buf()
-    } else {
-        root_hint.parent().unwrap_or(root_hint).to_path_buf()
-    };
+/// Prints `dedup --near` results: one block per group with its score and,
+/// per member, the line ranges that have no counterpart in the others.
+fn print_similar_groups(
+    out: &mut impl std::io::Write,
+    groups: &[forge::similarity::SimilarGroup],
+) -> std::io::Result<()> {
+    if groups.is_empty() {
+        writeln!(out, "No near-duplicate functions found.")?;
+        return Ok(());
+    }
 
-    let mut by_file: HashMap<&Path, (Vec<ReplacementTarget>, Vec<String>)> = HashMap::new();
+    writeln!(out, "+------------------------------------------+")?;
+    writeln!(out, "| JANITOR DEDUP (NEAR)                     |")?;
+    writeln!(out, "+------------------------------------------+")?;
+    writeln!(out, "| Similar groups   : {:>20} |", groups.len())?;
+    writeln!(out, "+------------------------------------------+")?;
 
     for group in groups {
-        let file_path = group.file_path.as_path();
-        let source = std::fs::read(file_path)?;
-
-        let canon = &group.members[0];
-        let impl_name = format!("_{}_impl", canon.name);
-
-        let (body_start, params_str) = extract_function_parts(&source, canon)?;
-        let original_body =
-            std::str::from_utf8(&source[body_start as usize..canon.end_byte as usize])
-                .unwrap_or("    pass\n");
-
-        let impl_block = format!("\ndef {}({}):\n{}", impl_name, params_str, original_body);
-        let call_args = params_to_call_args(&params_str);
-        let proxy_body = if call_args.is_empty() {
-            format!("    return {}()\n", impl_name)
-        } else {
-            format!("    return {}({})\n", impl_name, call_args)
-        };
-
-        let entry = by_file.entry(file_path).or_default();
-        entry.1.push(impl_block);
-
-        for member in &group.members {
-            let (member_body_start, _) = extract_function_parts(&source, member)?;
-            entry.0.push(ReplacementTarget {
-                qualified_name: member.qualified_name.clone(),
-                start_byte: member_body_start,
-                end_byte: member.end_byte,
-                replacement: proxy_body.clone(),
-            });
+        writeln!(out, "\n  Similarity: {:.0}%", group.score * 100.0)?;
+        for m in &group.members {
+            write!(
+                out,
+                "    {}:{}-{} - {}",
+                m.file_path, m.start_line, m.end_line, m.qualified_name
+            )?;
+            if !m.differing_lines.is_empty() {
+                let ranges: Vec<String> = m
+                    .differing_lines
+                    .iter()
+                    .map(|&(a, b)| {
+                        if a == b {
+                            a.to_string()
+                        } else {
+                            format!("{}-{}", a, b)
+                        }
+                    })
+                    .collect();
+                write!(out, " (differs at {})", ranges.join(", "))?;
+            }
+            writeln!(out)?;
         }
     }
-
-    for (file_path, (mut replacements, impl_blocks)) in by_file {
-        let mut deleter = SafeDeleter::new(&project_root)?;
-        deleter.replace_symbols(file_path, &mut replacements)?;
-
-        let mut current = std::fs::read_to_string(file_path)?;
-        for block in &impl_blocks {
-            current.push_str(block);
-        }
-        std::fs::write(file_path, &current)?;
-
-        match run_pytest(&project_root) {
-            Ok(()) => {
-                deleter.commit()?;
-                println!("APPLIED + VERIFIED: {}", file_path.display());
-            }
-            Err(e) => {
-                eprintln!("PYTEST FAILED: {}. Rolling back...", e);
-                deleter.restore_all()?;
-                return Err(e);
-            }
-        }
-    }
-
-    Ok(())
-}
+    Ok(())
+}
 
 // ---------------------------------------------------------------------------
 // shadow
@@ -334,113 +1264,276 @@ fn cmd_shadow_init(project_root: &Path) -> anyhow::Result<()> {
     use shadow::ShadowManager;
 
     let shadow_path = project_root.join(".janitor").join("shadow_src");
+    if shadow_path.exists() {
+        let manager = ShadowManager::open(project_root, &shadow_path)?;
+        print_sync_report(&manager.sync()?);
+        warn_copy_strategy(&manager);
+        return Ok(());
+    }
     let manager = ShadowManager::initialize(project_root, &shadow_path)?;
     println!(
-        "Shadow tree initialised: {} -> {}",
+        "Shadow tree initialised: {} -> {} ({})",
         manager.source_root().display(),
-        manager.shadow_root().display()
+        manager.shadow_root().display(),
+        manager.strategy().as_str()
     );
+    warn_copy_strategy(&manager);
+    Ok(())
+}
+
+/// Warns that a copied shadow tree only sees source edits after a re-sync.
+fn warn_copy_strategy(manager: &shadow::ShadowManager) {
+    if manager.strategy() == shadow::LinkStrategy::Copy {
+        eprintln!(
+            "warning: symlinks and hard links are unavailable; the shadow tree holds copies \
+             that only pick up source edits on remap or `janitor shadow init`"
+        );
+    }
+}
+
+fn cmd_shadow_sync(project_root: &Path) -> anyhow::Result<()> {
+    let manager = open_shadow(project_root)?;
+    print_sync_report(&manager.sync()?);
+    warn_copy_strategy(&manager);
     Ok(())
 }
 
+fn cmd_shadow_verify(project_root: &Path) -> anyhow::Result<()> {
+    let manager = open_shadow(project_root)?;
+    let broken = manager.verify_integrity()?;
+    if broken.is_empty() {
+        println!("Shadow tree healthy: {}", manager.shadow_root().display());
+        return Ok(());
+    }
+    for link in &broken {
+        let rel = link.strip_prefix(manager.shadow_root()).unwrap_or(link);
+        println!("broken: {}", rel.display());
+    }
+    anyhow::bail!(
+        "{} broken shadow links; run `janitor shadow sync {}`",
+        broken.len(),
+        project_root.display()
+    )
+}
+
+/// Opens `{project_root}/.janitor/shadow_src`, which must already exist.
+fn open_shadow(project_root: &Path) -> anyhow::Result<shadow::ShadowManager> {
+    let shadow_path = project_root.join(".janitor").join("shadow_src");
+    if !shadow_path.exists() {
+        anyhow::bail!(
+            "no shadow tree at {}; run `janitor shadow init {}`",
+            shadow_path.display(),
+            project_root.display()
+        );
+    }
+    Ok(shadow::ShadowManager::open(project_root, &shadow_path)?)
+}
+
+/// Prints what a [`shadow::ShadowManager::sync`] pass changed.
+fn print_sync_report(report: &shadow::SyncReport) {
+    println!(
+        "Shadow tree synced: {} added, {} removed, {} kept",
+        report.added, report.removed, report.kept
+    );
+}
+
 // ---------------------------------------------------------------------------
 // clean
 // ---------------------------------------------------------------------------
 
-fn cmd_clean(project_root: &Path, token: &str) -> anyhow::Result<()> {
-    use anatomist::{heuristics::pytest::PytestFixtureHeuristic, parser::ParserHost, pipeline};
-    use reaper::{DeletionTarget, SafeDeleter};
-    use shadow::ShadowManager;
+fn cmd_clean(janitor: &Janitor, token: Option<&str>, modes: CleanOptions) -> anyhow::Result<()> {
+    let options = CleanOptions {
+        token: require_token(token, janitor.root())?.to_string(),
+        ..modes
+    };
+    print_warnings(janitor);
+    let report = janitor.clean_observed(&options, &mut print_event)?;
+    print_clean_report(janitor, &report);
+    Ok(())
+}
 
-    require_token(Some(token))?;
+/// `clean --patch`: writes what `clean` would delete as a unified diff,
+/// leaving the project untouched.
+fn cmd_clean_patch(
+    janitor: &Janitor,
+    token: Option<&str>,
+    modes: CleanOptions,
+    out: &Path,
+) -> anyhow::Result<()> {
+    let options = CleanOptions {
+        token: require_token(token, janitor.root())?.to_string(),
+        ..modes
+    };
+    print_warnings(janitor);
+    let patch = janitor.patch_clean_observed(&options, &mut print_event)?;
+    if patch.is_empty() {
+        println!("Nothing to clean.");
+    } else {
+        write_patch(&patch, out)?;
+    }
+    Ok(())
+}
 
-    // 1. Pipeline: get kill list.
-    let mut host = ParserHost::new()?;
-    host.register_heuristic(Box::new(PytestFixtureHeuristic));
-    let result = pipeline::run(project_root, &mut host, false)?;
+/// Writes `patch` to `out` and says how to apply it.
+fn write_patch(patch: &janitor::Patch, out: &Path) -> anyhow::Result<()> {
+    std::fs::write(out, patch.render()?)
+        .map_err(|e| anyhow::anyhow!("could not write {}: {}", out.display(), e))?;
+    println!(
+        "Patch for {} files written to {}; review it, then run: git apply {}",
+        patch.changed_files().len(),
+        out.display(),
+        out.display()
+    );
+    Ok(())
+}
 
-    if result.dead.is_empty() {
+/// `clean --dry-run`: plans what `clean` would delete, simulates the plan in
+/// the shadow tree, and prints it. The project itself is left untouched.
+fn cmd_clean_dry_run(
+    janitor: &Janitor,
+    modes: &CleanOptions,
+    write_plan: Option<&Path>,
+) -> anyhow::Result<()> {
+    print_warnings(janitor);
+    let plan = janitor.plan_clean_observed(modes, &mut print_event)?;
+    if plan.is_empty() {
         println!("Nothing to clean.");
         return Ok(());
     }
-    println!("{} dead symbols identified.", result.dead.len());
+    print!("{}", plan);
+    if let Some(path) = write_plan {
+        plan.save(path)?;
+        println!(
+            "Plan written to {}; execute with: janitor clean {} --from-plan {} --token <TOKEN>",
+            path.display(),
+            janitor.root().display(),
+            path.display()
+        );
+    }
+    Ok(())
+}
 
-    // 2. Initialise (or open existing) shadow tree.
-    let shadow_path = project_root.join(".janitor").join("shadow_src");
-    let manager = if shadow_path.exists() {
-        ShadowManager::open(project_root, &shadow_path)?
-    } else {
-        ShadowManager::initialize(project_root, &shadow_path)?
+/// `clean --from-plan`: executes a reviewed plan after the token check.
+fn cmd_clean_from_plan(
+    janitor: &Janitor,
+    token: Option<&str>,
+    modes: CleanOptions,
+    plan_path: &Path,
+) -> anyhow::Result<()> {
+    let options = CleanOptions {
+        token: require_token(token, janitor.root())?.to_string(),
+        ..modes
     };
+    print_warnings(janitor);
+    let plan = CleanPlan::load(plan_path)?;
+    let report = janitor.execute_plan_observed(&plan, &options, &mut print_event)?;
+    print_clean_report(janitor, &report);
+    Ok(())
+}
 
-    // 3. Collect unique files and unmap their symlinks.
-    let mut dead_files: Vec<PathBuf> = result
-        .dead
-        .iter()
-        .map(|e| PathBuf::from(&e.file_path))
-        .collect();
-    dead_files.sort();
-    dead_files.dedup();
-
-    let mut unmapped: Vec<PathBuf> = Vec::new();
-    for abs in &dead_files {
-        let rel = abs
-            .strip_prefix(manager.source_root())
-            .unwrap_or(abs.as_path());
-        match manager.unmap(rel) {
-            Ok(()) => unmapped.push(rel.to_path_buf()),
-            Err(e) => eprintln!("warning: unmap {}: {}", abs.display(), e),
+/// Closes a clean run: its summary, where the run log went, and how to undo
+/// it; or that there was nothing to do.
+fn print_clean_report(janitor: &Janitor, report: &janitor::CleanReport) {
+    if report.is_empty() {
+        println!("Nothing to clean.");
+    } else {
+        print!("{}", report);
+        if let Some(path) = &report.run_log {
+            println!("Run log: {}", path.display());
         }
+        println!(
+            "Undo with: janitor restore {} --all",
+            janitor.root().display()
+        );
     }
+}
 
-    // 4. Shadow simulation: run tests against the shadow tree.
-    println!("Shadow simulation in: {}", manager.shadow_root().display());
-    match run_pytest(manager.shadow_root()) {
-        Ok(()) => {
-            println!("Shadow tests PASSED. Executing physical deletion...");
+// ---------------------------------------------------------------------------
+// restore
+// ---------------------------------------------------------------------------
+
+fn cmd_restore(
+    project_root: &Path,
+    list: bool,
+    all: bool,
+    file: Option<&Path>,
+) -> anyhow::Result<()> {
+    use reaper::ghost::{GhostKind, GhostManifest, GhostRecord};
+
+    let project_root = std::fs::canonicalize(project_root)?;
+    let manifest = GhostManifest::open(&project_root);
+    let records = manifest.records()?;
+
+    if list || (!all && file.is_none()) {
+        if records.is_empty() {
+            println!("Ghost directory is empty.");
+            return Ok(());
         }
-        Err(e) => {
-            eprintln!("Shadow simulation FAILED: {}. Restoring symlinks...", e);
-            for rel in &unmapped {
-                manager.remap(rel).ok();
-            }
-            return Err(e);
+        println!(
+            "{:<12} {:<18} {:<7} ORIGINAL",
+            "TIMESTAMP", "TRANSACTION", "KIND"
+        );
+        for r in &records {
+            let kind = match r.kind {
+                GhostKind::Backup => "backup",
+                GhostKind::File => "file",
+            };
+            let original = r
+                .original
+                .strip_prefix(&project_root)
+                .unwrap_or(&r.original);
+            println!(
+                "{:<12} {:<18} {:<7} {}",
+                r.timestamp,
+                r.transaction,
+                kind,
+                original.display()
+            );
         }
+        return Ok(());
     }
 
-    // 5. Physical deletion via SafeDeleter.
-    let mut by_file: HashMap<&str, Vec<&anatomist::Entity>> = HashMap::new();
-    for entity in &result.dead {
-        by_file
-            .entry(entity.file_path.as_str())
-            .or_default()
-            .push(entity);
-    }
-
-    for (file_str, entities) in &by_file {
-        let file_path = Path::new(file_str);
-        let mut deleter = SafeDeleter::new(project_root)?;
-        let mut targets: Vec<DeletionTarget> = entities
-            .iter()
-            .map(|e| DeletionTarget {
-                qualified_name: e.qualified_name.clone(),
-                start_byte: e.start_byte,
-                end_byte: e.end_byte,
-            })
-            .collect();
+    let selected: Vec<GhostRecord> = if let Some(rel) = file {
+        let target = project_root.join(rel);
+        let latest = records
+            .into_iter()
+            .rev()
+            .find(|r| r.original == target)
+            .ok_or_else(|| anyhow::anyhow!("No ghost entry for {}", rel.display()))?;
+        vec![latest]
+    } else {
+        let Some(transaction) = manifest.latest_transaction()? else {
+            println!("Nothing to restore.");
+            return Ok(());
+        };
+        // Newest first, so a file touched twice ends at its oldest state.
+        records
+            .into_iter()
+            .rev()
+            .filter(|r| r.transaction == transaction)
+            .collect()
+    };
 
-        match deleter.delete_symbols(file_path, &mut targets) {
-            Ok(n) => {
-                deleter.commit()?;
-                println!("Deleted {} symbols from {}", n, file_str);
-            }
-            Err(e) => {
-                eprintln!("Deletion error in {}: {}. Restoring backup...", file_str, e);
-                deleter.restore_all()?;
+    let shadow_path = project_root.join(".janitor").join("shadow_src");
+    let shadow = shadow_path
+        .exists()
+        .then(|| shadow::ShadowManager::open(&project_root, &shadow_path).ok())
+        .flatten();
+
+    for record in &selected {
+        manifest.restore(record)?;
+        let rel = record
+            .original
+            .strip_prefix(&project_root)
+            .unwrap_or(&record.original);
+        if let Some(manager) = &shadow {
+            if let Err(e) = manager.remap(rel) {
+                eprintln!("warning: remap {}: {}", rel.display(), e);
             }
         }
+        println!("Restored {}", rel.display());
     }
-
+    println!("{} entries restored.", selected.len());
     Ok(())
 }
 
@@ -449,10 +1542,51 @@ fn cmd_clean(project_root: &Path, token: &str) -> anyhow::Result<()> {
 // ---------------------------------------------------------------------------
 
 fn cmd_dashboard(project_root: &Path) -> anyhow::Result<()> {
-    use common::registry::{MappedRegistry, SymbolRegistry};
+    let Some(registry) = load_registry(project_root)? else {
+        return Ok(());
+    };
+    dashboard::draw_dashboard(&registry).map_err(|e| anyhow::anyhow!("TUI error: {}", e))
+}
 
+/// Reads `.janitor/symbols.rkyv`, or prints a hint and returns `None` if no
+/// scan has saved one yet.
+fn load_registry(project_root: &Path) -> anyhow::Result<Option<common::registry::SymbolRegistry>> {
     let rkyv_path = project_root.join(".janitor").join("symbols.rkyv");
 
+    if !rkyv_path.exists() {
+        println!(
+            "No symbol registry found. Run `janitor scan {}` first.",
+            project_root.display()
+        );
+        return Ok(None);
+    }
+
+    let registry = common::registry::SymbolRegistry::load(&rkyv_path)
+        .map_err(|e| registry_error(&rkyv_path, e))?;
+    Ok(Some(registry))
+}
+
+/// Error for a registry at `path` that failed to load, spelling out the fix
+/// when it comes from another janitor release.
+fn registry_error(path: &Path, e: common::registry::RegistryError) -> anyhow::Error {
+    use common::registry::RegistryError;
+    match e {
+        RegistryError::VersionMismatch { found, expected } if found < expected => anyhow::anyhow!(
+            "{}: registry was produced by an older janitor — re-run scan",
+            path.display()
+        ),
+        RegistryError::VersionMismatch { .. } => anyhow::anyhow!(
+            "{}: registry was produced by a newer janitor — upgrade, or re-run scan",
+            path.display()
+        ),
+        e => anyhow::anyhow!("Failed to open {}: {}", path.display(), e),
+    }
+}
+
+/// Upgrades the project's registry to the current format. Opening a registry
+/// never migrates it, so this is the only writer besides `scan`.
+fn cmd_migrate(project_root: &Path) -> anyhow::Result<()> {
+    let rkyv_path = project_root.join(".janitor").join("symbols.rkyv");
     if !rkyv_path.exists() {
         println!(
             "No symbol registry found. Run `janitor scan {}` first.",
@@ -461,26 +1595,384 @@ fn cmd_dashboard(project_root: &Path) -> anyhow::Result<()> {
         return Ok(());
     }
 
-    let mapped = MappedRegistry::open(&rkyv_path)
-        .map_err(|e| anyhow::anyhow!("Failed to open symbols.rkyv: {}", e))?;
+    let migrated = common::registry::migrate(&rkyv_path, common::registry::MIGRATIONS)
+        .map_err(|e| registry_error(&rkyv_path, e))?;
+    if migrated {
+        println!(
+            "Upgraded {} to format {}.",
+            rkyv_path.display(),
+            common::registry::REGISTRY_FORMAT_VERSION
+        );
+    } else {
+        println!("{} is already current.", rkyv_path.display());
+    }
+    Ok(())
+}
 
-    let registry: SymbolRegistry = rkyv::deserialize::<_, rkyv::rancor::Error>(mapped.archived())
-        .map_err(|e| anyhow::anyhow!("Deserialization failed: {}", e))?;
+// ---------------------------------------------------------------------------
+// report
+// ---------------------------------------------------------------------------
 
-    dashboard::draw_dashboard(&registry).map_err(|e| anyhow::anyhow!("TUI error: {}", e))
+fn cmd_report(project_root: &Path, by_dir: bool, html: Option<&Path>) -> anyhow::Result<()> {
+    let Some(registry) = load_registry(project_root)? else {
+        return Ok(());
+    };
+    print_registry_report(&mut std::io::stdout(), &registry, by_dir)?;
+
+    if let Some(out) = html {
+        let project_root = std::fs::canonicalize(project_root)?;
+        // Written by the same scan as the registry; absent for registries from older scans.
+        let orphans: Vec<String> =
+            std::fs::read_to_string(project_root.join(".janitor").join("orphans.txt"))
+                .unwrap_or_default()
+                .lines()
+                .map(str::to_string)
+                .collect();
+        std::fs::write(
+            out,
+            report::html::render(&registry, &orphans, &project_root),
+        )
+        .map_err(|e| anyhow::anyhow!("{}: {}", out.display(), e))?;
+        println!("HTML report written to {}", out.display());
+    }
+    Ok(())
+}
+
+/// Writes registry totals and, with `by_dir`, one line per top-level
+/// directory: `legacy/  71% dead, 210KB (12 of 40 symbols)`.
+fn print_registry_report(
+    out: &mut dyn Write,
+    registry: &common::registry::SymbolRegistry,
+    by_dir: bool,
+) -> std::io::Result<()> {
+    use dashboard::aggregate::{self, format_bytes};
+
+    let dirs = aggregate::by_directory(registry);
+    let dead: usize = dirs.iter().map(|d| d.dead_symbols).sum();
+    let dead_bytes: u64 = dirs.iter().map(|d| d.dead_bytes).sum();
+    writeln!(out, "+------------------------------------------+")?;
+    writeln!(out, "| JANITOR REPORT                           |")?;
+    writeln!(out, "+------------------------------------------+")?;
+    writeln!(out, "| Total entities : {:>22} |", registry.len())?;
+    writeln!(out, "| Dead           : {:>22} |", dead)?;
+    writeln!(out, "| Dead bytes     : {:>22} |", format_bytes(dead_bytes))?;
+    writeln!(out, "+------------------------------------------+")?;
+
+    if by_dir {
+        let width = dirs.iter().map(|d| d.name.len()).max().unwrap_or(0);
+        writeln!(out, "\nDEAD CODE BY DIRECTORY:")?;
+        for d in &dirs {
+            writeln!(
+                out,
+                "  {:<width$}  {:>3.0}% dead, {:>6} ({} of {} symbols)",
+                d.name,
+                d.dead_percent(),
+                format_bytes(d.dead_bytes),
+                d.dead_symbols,
+                d.total_symbols,
+            )?;
+        }
+    }
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// diff
+// ---------------------------------------------------------------------------
+
+fn cmd_diff(old: &Path, new: &Path, json: bool) -> anyhow::Result<()> {
+    use common::registry::{self, SymbolRegistry};
+
+    let load = |path: &Path| SymbolRegistry::load(path).map_err(|e| registry_error(path, e));
+    let diff = registry::diff(&load(old)?, &load(new)?);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&diff)?);
+    } else {
+        print_diff_report(&mut std::io::stdout(), &diff)?;
+    }
+    Ok(())
+}
+
+/// Writes the human-readable registry diff to `out`.
+fn print_diff_report(
+    out: &mut dyn Write,
+    diff: &common::registry::RegistryDiff,
+) -> std::io::Result<()> {
+    writeln!(out, "+------------------------------------------+")?;
+    writeln!(out, "| JANITOR DIFF                             |")?;
+    writeln!(out, "+------------------------------------------+")?;
+    writeln!(out, "| Newly dead     : {:>22} |", diff.newly_dead.len())?;
+    writeln!(out, "| Resurrected    : {:>22} |", diff.resurrected.len())?;
+    writeln!(out, "| Added          : {:>22} |", diff.added.len())?;
+    writeln!(out, "| Removed        : {:>22} |", diff.removed.len())?;
+    writeln!(out, "| Renamed        : {:>22} |", diff.renamed.len())?;
+    writeln!(
+        out,
+        "| Dead bytes     : {:>22} |",
+        format!("{:+}", diff.dead_bytes_delta)
+    )?;
+    writeln!(out, "+------------------------------------------+")?;
+
+    for (title, entries) in [
+        ("NEWLY DEAD", &diff.newly_dead),
+        ("RESURRECTED", &diff.resurrected),
+        ("ADDED", &diff.added),
+        ("REMOVED", &diff.removed),
+    ] {
+        if entries.is_empty() {
+            continue;
+        }
+        writeln!(out, "\n{}:", title)?;
+        for e in entries {
+            writeln!(
+                out,
+                "  {}:{} - {}",
+                e.file_path, e.start_line, e.qualified_name
+            )?;
+        }
+    }
+    if !diff.renamed.is_empty() {
+        writeln!(out, "\nRENAMED:")?;
+        for (old, new) in &diff.renamed {
+            writeln!(
+                out,
+                "  {}:{} - {} -> {}:{} - {}",
+                old.file_path,
+                old.start_line,
+                old.qualified_name,
+                new.file_path,
+                new.start_line,
+                new.qualified_name
+            )?;
+        }
+    }
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// why
+// ---------------------------------------------------------------------------
+
+fn cmd_why(
+    project_root: &Path,
+    symbol: &str,
+    library: bool,
+    rules: &RulesArgs,
+    roots: &RootsArgs,
+) -> anyhow::Result<()> {
+    let janitor = roots
+        .apply(rules.apply(Janitor::open(project_root)?)?)?
+        .with_library_mode(library)
+        .with_cache(true);
+    print_warnings(&janitor);
+    let explanations = janitor.explain(symbol)?;
+    if explanations.is_empty() {
+        anyhow::bail!("no symbol `{}` under {}", symbol, project_root.display());
+    }
+    print_explanations(&mut std::io::stdout(), &explanations)?;
+    Ok(())
+}
+
+/// Grep shield hits listed per symbol before the rest are summarised.
+const WHY_MAX_GREP_HITS: usize = 5;
+
+fn print_explanations(
+    out: &mut dyn Write,
+    explanations: &[anatomist::pipeline::Explanation],
+) -> std::io::Result<()> {
+    for (i, ex) in explanations.iter().enumerate() {
+        if i > 0 {
+            writeln!(out)?;
+        }
+        let entity = &ex.entity;
+        writeln!(
+            out,
+            "{}::{} (lines {}-{})",
+            entity.file_path, entity.qualified_name, entity.start_line, entity.end_line
+        )?;
+
+        if ex.callers.is_empty() {
+            writeln!(out, "  Referenced by: nothing")?;
+        } else {
+            writeln!(out, "  Referenced by:")?;
+            for caller in &ex.callers {
+                let name = match caller.qualified_name.as_str() {
+                    "__MODULE__" => "<module>",
+                    qname => qname,
+                };
+                writeln!(
+                    out,
+                    "    {}:{} - {} [{}]",
+                    caller.file_path,
+                    caller.line,
+                    name,
+                    caller.kind.label()
+                )?;
+            }
+        }
+
+        match (entity.protected_by, ex.stage) {
+            (Some(protection), Some(stage)) => {
+                writeln!(out, "  Protection:    {:?} (stage: {})", protection, stage)?
+            }
+            _ => writeln!(out, "  Protection:    none")?,
+        }
+
+        if ex.grep_hits.is_empty() {
+            writeln!(out, "  Grep shield:   name not found")?;
+        } else {
+            writeln!(out, "  Grep shield:   name found")?;
+            for hit in ex.grep_hits.iter().take(WHY_MAX_GREP_HITS) {
+                writeln!(out, "    {}", hit.location())?;
+            }
+            if ex.grep_hits.len() > WHY_MAX_GREP_HITS {
+                writeln!(
+                    out,
+                    "    ... and {} more",
+                    ex.grep_hits.len() - WHY_MAX_GREP_HITS
+                )?;
+            }
+        }
+
+        let verdict = if ex.dead {
+            "DEAD - on the kill list"
+        } else if ex.low_confidence {
+            "probably dead - low-confidence directory, not on the kill list"
+        } else {
+            "alive - not on the kill list"
+        };
+        writeln!(out, "  Verdict:       {}", verdict)?;
+    }
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// watch
+// ---------------------------------------------------------------------------
+
+fn cmd_watch(janitor: &Janitor, debounce: Duration) -> anyhow::Result<()> {
+    print_warnings(janitor);
+    eprintln!("Watching {} (Ctrl-C to stop)", janitor.root().display());
+    let mut stdout = std::io::stdout();
+    janitor.watch(debounce, &mut |report| {
+        writeln!(stdout, "{}", serde_json::to_string(&report)?)?;
+        stdout.flush()?;
+        Ok(())
+    })
+}
+
+// ---------------------------------------------------------------------------
+// lsp
+// ---------------------------------------------------------------------------
+
+fn cmd_lsp(library: bool) -> anyhow::Result<()> {
+    let (connection, io_threads) = janitor::lsp::Connection::stdio();
+    janitor::lsp::serve(connection, &|root| {
+        Ok(Janitor::open(root)?.with_library_mode(library))
+    })?;
+    io_threads.join()?;
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// hook
+// ---------------------------------------------------------------------------
+
+fn cmd_hook_install(path: &Path, force: bool, pre_commit_config: bool) -> anyhow::Result<()> {
+    let exe = std::env::current_exe()?;
+    if pre_commit_config {
+        print!("{}", hook::pre_commit_config(&exe));
+        return Ok(());
+    }
+    let written = hook::install(path, &exe, force)?;
+    println!("Installed {}", written.display());
+    Ok(())
+}
+
+/// The pre-commit check: a scan scoped to the staged Python files, with the
+/// baseline's dead symbols held back. No token is needed; nothing is deleted.
+fn cmd_hook_run(
+    root: &Path,
+    files: &[PathBuf],
+    stdin: bool,
+    baseline: &Path,
+) -> anyhow::Result<()> {
+    let staged = if !files.is_empty() {
+        files.to_vec()
+    } else if stdin {
+        read_changed(&std::io::read_to_string(std::io::stdin())?).collect()
+    } else {
+        read_changed(&hook::staged_files(root)?).collect()
+    };
+    let staged = hook::python_files(staged);
+    if staged.is_empty() {
+        return Ok(());
+    }
+
+    let janitor = Janitor::open(root)?.with_cache(true).with_scope(staged);
+    let mut result = janitor.scan()?;
+    let baseline = root.join(baseline);
+    if baseline.exists() {
+        Baseline::load(&baseline)?.hold_back(&mut result, root);
+    }
+    hook::write_findings(&mut std::io::stdout().lock(), &result.dead, root)?;
+    if result.dead.is_empty() {
+        return Ok(());
+    }
+    Err(GateFailure {
+        code: EXIT_DEAD,
+        message: format!(
+            "janitor: {} dead symbol(s) in staged files; delete them or accept them in {}",
+            result.dead.len(),
+            baseline.display()
+        ),
+    }
+    .into())
+}
+
+// ---------------------------------------------------------------------------
+// graph
+// ---------------------------------------------------------------------------
+
+fn cmd_graph(
+    project_root: &Path,
+    format: GraphFormat,
+    focus: Option<&str>,
+    depth: usize,
+) -> anyhow::Result<()> {
+    use anatomist::graph::{build_reference_graph, ExportOptions};
+    use anatomist::parser::ParserHost;
+
+    let mut host = ParserHost::new()?;
+    let graph = build_reference_graph(project_root, &mut host)?;
+    let options = ExportOptions {
+        focus: focus.map(str::to_string),
+        depth,
+    };
+    let mut out = std::io::stdout().lock();
+    match format {
+        GraphFormat::Dot => graph.export_dot(&mut out, &options)?,
+        GraphFormat::Json => {
+            graph.export_json(&mut out, &options)?;
+            writeln!(out)?;
+        }
+    }
+    Ok(())
 }
 
 // ---------------------------------------------------------------------------
 // Token gate
 // ---------------------------------------------------------------------------
 
-/// Verifies the purge token; exits the process on failure.
-fn require_token(token: Option<&str>) -> anyhow::Result<()> {
+/// Verifies the purge token for `project_root` and returns it; exits the
+/// process on failure.
+fn require_token<'a>(token: Option<&'a str>, project_root: &Path) -> anyhow::Result<&'a str> {
     use vault::SigningOracle;
-    match token {
-        Some(t) if SigningOracle::verify_token(t) => Ok(()),
-        Some(_) => {
-            eprintln!("ACCESS DENIED. Purchase PQC/Ed25519 Token at thejanitor.app");
+    match token.map(|t| (t, SigningOracle::verify_token_v2(t, project_root))) {
+        Some((t, Ok(_))) => Ok(t),
+        Some((_, Err(e))) => {
+            eprintln!("ACCESS DENIED: {}", e);
+            eprintln!("Purchase PQC/Ed25519 Token at thejanitor.app");
             std::process::exit(1);
         }
         None => {
@@ -491,113 +1983,466 @@ fn require_token(token: Option<&str>) -> anyhow::Result<()> {
     }
 }
 
-// ---------------------------------------------------------------------------
-// Shared helpers
-// ---------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan_json_schema() {
+        use anatomist::{parser::ParserHost, pipeline};
+
+        let tmp = std::env::temp_dir().join("test_cli_scan_json");
+        std::fs::create_dir_all(&tmp).unwrap();
+        std::fs::write(
+            tmp.join("utils.py"),
+            "def used():\n    pass\n\ndef unused():\n    pass\n",
+        )
+        .unwrap();
+        std::fs::write(
+            tmp.join("main.py"),
+            "from utils import used\ndef run():\n    used()\n",
+        )
+        .unwrap();
+
+        let mut host = ParserHost::new().unwrap();
+        let result = pipeline::run(&tmp, &mut host, false).unwrap();
+        let doc: serde_json::Value = serde_json::from_str(&scan_json(&result).unwrap()).unwrap();
+
+        assert_eq!(doc["total"], result.total);
+        assert_eq!(doc["stage_counts"].as_array().unwrap().len(), 6);
+        assert!(doc["orphan_files"].is_array());
+        assert!(doc["test_only"].is_array());
+
+        let dead = doc["dead"].as_array().unwrap();
+        let unused = dead
+            .iter()
+            .find(|e| e["qualified_name"] == "unused")
+            .expect("unused should be dead");
+        assert_eq!(unused["entity_type"], "FunctionDefinition");
+        assert_eq!(unused["start_line"], 4);
+        assert_eq!(unused["end_line"], 5);
+        assert!(unused["file_path"].as_str().unwrap().ends_with("utils.py"));
+        assert!(unused["protected_by"].is_null());
+        assert_eq!(unused["module_path"], "utils");
+        assert_eq!(unused["fully_qualified_name"], "utils.unused");
+
+        let used = doc["protected"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|e| e["qualified_name"] == "used")
+            .expect("used should be protected");
+        assert_eq!(used["protected_by"], "Referenced");
 
-fn extract_function_parts(
-    source: &[u8],
-    entity: &anatomist::Entity,
-) -> anyhow::Result<(u32, String)> {
-    let start = entity.start_byte as usize;
-    let end = (entity.end_byte as usize).min(source.len());
-    let slice = &source[start..end];
+        std::fs::remove_dir_all(&tmp).ok();
+    }
 
-    let paren_open = slice
-        .iter()
-        .position(|&b| b == b'(')
-        .ok_or_else(|| anyhow::anyhow!("No `(` in signature of `{}`", entity.name))?;
-
-    let mut depth = 0i32;
-    let mut paren_close = paren_open;
-    for (i, &b) in slice[paren_open..].iter().enumerate() {
-        match b {
-            b'(' => depth += 1,
-            b')' => {
-                depth -= 1;
-                if depth == 0 {
-                    paren_close = paren_open + i;
-                    break;
-                }
-            }
-            _ => {}
-        }
+    #[test]
+    fn test_scan_report_lists_dead_packages_with_size() {
+        use anatomist::{parser::ParserHost, pipeline};
+
+        let tmp = std::env::temp_dir().join("test_cli_scan_dead_packages");
+        std::fs::remove_dir_all(&tmp).ok();
+        std::fs::create_dir_all(tmp.join("legacy")).unwrap();
+        std::fs::write(tmp.join("legacy/__init__.py"), "").unwrap();
+        std::fs::write(
+            tmp.join("legacy/a.py"),
+            "from legacy.b import g\n\ndef f():\n    return g()\n",
+        )
+        .unwrap();
+        std::fs::write(tmp.join("legacy/b.py"), "def g():\n    return 1\n").unwrap();
+        std::fs::write(tmp.join("main.py"), "print('hi')\n").unwrap();
+
+        let mut host = ParserHost::new().unwrap();
+        let result = pipeline::run(&tmp, &mut host, false).unwrap();
+        let mut out = Vec::new();
+        print_scan_report(&mut out, &result, None, false).unwrap();
+        let text = String::from_utf8(out).unwrap();
+
+        let size = dashboard::aggregate::format_bytes(70);
+        assert!(text.contains(&format!("legacy/  {}\n", size)), "{}", text);
+        let doc: serde_json::Value = serde_json::from_str(&scan_json(&result).unwrap()).unwrap();
+        assert_eq!(doc["orphan_packages"].as_array().unwrap().len(), 1);
+
+        std::fs::remove_dir_all(&tmp).ok();
     }
 
-    let params_str = std::str::from_utf8(&slice[paren_open + 1..paren_close])
-        .unwrap_or("")
-        .to_string();
+    #[test]
+    fn test_scan_report_lists_diagnostics() {
+        use anatomist::{parser::ParserHost, pipeline};
+
+        let tmp = std::env::temp_dir().join("test_cli_scan_diagnostics");
+        std::fs::remove_dir_all(&tmp).ok();
+        std::fs::create_dir_all(&tmp).unwrap();
+        std::fs::write(tmp.join("legacy.py"), b"# caf\xe9\ndef f():\n    pass\n").unwrap();
+        std::fs::write(tmp.join("broken.py"), "def g(\n").unwrap();
+
+        let mut host = ParserHost::new().unwrap();
+        let result = pipeline::run(&tmp, &mut host, false).unwrap();
+        let mut out = Vec::new();
+        print_scan_report(&mut out, &result, None, false).unwrap();
+        let text = String::from_utf8(out).unwrap();
+
+        let warning = text.find("DIAGNOSTICS (warning):").expect(&text);
+        let info = text.find("DIAGNOSTICS (info):").expect(&text);
+        assert!(warning < info, "{}", text);
+        assert!(
+            text.contains("legacy.py: not valid UTF-8; read as Latin-1 [non-utf8-source]"),
+            "{}",
+            text
+        );
+        assert!(
+            text.contains("broken.py: syntax error at line 1 [syntax-error]"),
+            "{}",
+            text
+        );
+        assert!(text.contains("legacy.py:2 - f"), "{}", text);
+        let doc: serde_json::Value = serde_json::from_str(&scan_json(&result).unwrap()).unwrap();
+        let codes: Vec<&str> = doc["diagnostics"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|d| d["code"].as_str().unwrap())
+            .collect();
+        assert_eq!(codes.len(), 2);
+        assert!(codes.contains(&"syntax-error"), "{:?}", codes);
+        assert!(has_warnings(&result.diagnostics));
+
+        std::fs::remove_dir_all(&tmp).ok();
+    }
 
-    let newline_offset = slice[paren_close..]
-        .iter()
-        .position(|&b| b == b'\n')
-        .map(|i| paren_close + i + 1)
-        .unwrap_or(slice.len());
+    #[test]
+    fn test_deny_warnings_fails_scan() {
+        let tmp = std::env::temp_dir().join("test_cli_deny_warnings");
+        std::fs::remove_dir_all(&tmp).ok();
+        std::fs::create_dir_all(&tmp).unwrap();
+        std::fs::write(tmp.join("ok.py"), "def f():\n    pass\n").unwrap();
+        let janitor = Janitor::open(&tmp).unwrap();
+        let gate = BaselineArgs {
+            deny_warnings: true,
+            ..Default::default()
+        };
+        let scan = || cmd_scan(&janitor, &gate, false, OutputFormat::Json, SarifLevel::Note);
 
-    Ok((entity.start_byte + newline_offset as u32, params_str))
-}
+        scan().unwrap();
+        std::fs::write(tmp.join("broken.py"), "def g(\n").unwrap();
+        let err = scan().unwrap_err();
+        assert_eq!(err.to_string(), "scan reported warnings (--deny-warnings)");
 
-fn params_to_call_args(params: &str) -> String {
-    if params.trim().is_empty() {
-        return String::new();
+        std::fs::remove_dir_all(tmp).ok();
     }
-    let args: Vec<String> = params
-        .split(',')
-        .filter_map(|p| {
-            let p = p.trim();
-            if p.is_empty() {
-                return None;
-            }
-            let name_part = p
-                .split(':')
-                .next()
-                .unwrap_or(p)
-                .split('=')
-                .next()
-                .unwrap_or(p)
-                .trim();
-            if name_part.is_empty() {
-                return None;
-            }
-            Some(name_part.to_string())
-        })
-        .collect();
-    args.join(", ")
-}
 
-fn collect_py_files(path: &Path) -> anyhow::Result<Vec<PathBuf>> {
-    use walkdir::WalkDir;
-    if path.is_file() {
-        return Ok(vec![path.to_path_buf()]);
+    #[test]
+    fn test_migrate_leaves_current_registry_and_rejects_unknown_format() {
+        let tmp = std::env::temp_dir().join("test_cli_migrate");
+        std::fs::remove_dir_all(&tmp).ok();
+        std::fs::create_dir_all(tmp.join(".janitor")).unwrap();
+        let path = tmp.join(".janitor/symbols.rkyv");
+        common::registry::SymbolRegistry::new().save(&path).unwrap();
+        let before = std::fs::read(&path).unwrap();
+
+        cmd_migrate(&tmp).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), before);
+
+        // No migration starts from a headerless file.
+        std::fs::write(&path, [0u8; 64]).unwrap();
+        let err = cmd_migrate(&tmp).unwrap_err().to_string();
+        assert!(err.ends_with("older janitor — re-run scan"), "{}", err);
+
+        std::fs::remove_dir_all(tmp).ok();
     }
-    let files = WalkDir::new(path)
-        .follow_links(false)
-        .into_iter()
-        .filter_map(|e| e.ok())
-        .filter(|e| {
-            e.file_type().is_file() && e.path().extension().and_then(|x| x.to_str()) == Some("py")
+
+    #[test]
+    fn test_report_by_dir_from_saved_registry() {
+        let tmp = std::env::temp_dir().join("test_cli_report_by_dir");
+        std::fs::remove_dir_all(&tmp).ok();
+        std::fs::create_dir_all(tmp.join("api")).unwrap();
+        std::fs::create_dir_all(tmp.join("legacy")).unwrap();
+        std::fs::write(
+            tmp.join("api/views.py"),
+            "def list_users():\n    return []\n",
+        )
+        .unwrap();
+        std::fs::write(
+            tmp.join("legacy/export.py"),
+            "def export_xml():\n    return '<xml/>'\n",
+        )
+        .unwrap();
+        std::fs::write(
+            tmp.join("main.py"),
+            "from api.views import list_users\n\nif __name__ == '__main__':\n    list_users()\n",
+        )
+        .unwrap();
+
+        let janitor = Janitor::open(&tmp).unwrap();
+        cmd_scan(
+            &janitor,
+            &BaselineArgs::default(),
+            false,
+            OutputFormat::Text,
+            SarifLevel::Note,
+        )
+        .unwrap();
+        let registry = load_registry(&tmp).unwrap().unwrap();
+
+        let mut out = Vec::new();
+        print_registry_report(&mut out, &registry, true).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.contains("DEAD CODE BY DIRECTORY:"), "{}", text);
+        let lines: Vec<&str> = text
+            .lines()
+            .skip_while(|l| !l.starts_with("DEAD"))
+            .collect();
+        assert!(lines[1].starts_with("  legacy/  100% dead,"), "{}", text);
+        assert!(lines[1].ends_with("(1 of 1 symbols)"), "{}", text);
+        assert!(text.contains("  api/       0% dead,"), "{}", text);
+
+        let mut out = Vec::new();
+        print_registry_report(&mut out, &registry, false).unwrap();
+        assert!(!String::from_utf8(out).unwrap().contains("BY DIRECTORY"));
+
+        let html_path = std::env::temp_dir().join("test_cli_report_by_dir.html");
+        cmd_report(&tmp, false, Some(&html_path)).unwrap();
+        let html = std::fs::read_to_string(&html_path).unwrap();
+        assert!(html.contains("<td>legacy/export.py</td>"));
+        assert!(html.contains("<code>export_xml</code>"));
+        assert!(html.contains("Orphan files ("));
+
+        std::fs::remove_file(html_path).ok();
+        std::fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_scan_fails_only_on_dead_symbols_missing_from_baseline() {
+        let tmp = std::env::temp_dir().join("test_cli_scan_baseline");
+        std::fs::remove_dir_all(&tmp).ok();
+        std::fs::create_dir_all(tmp.join("pkg")).unwrap();
+        std::fs::write(
+            tmp.join("pkg/legacy.py"),
+            "def export_xml():\n    return '<xml/>'\n",
+        )
+        .unwrap();
+        // Outside the project so the grep shield never reads it.
+        let baseline = std::env::temp_dir().join("test_cli_scan_baseline.json");
+        let janitor = Janitor::open(&tmp).unwrap();
+        let scan = |gate: &BaselineArgs| {
+            cmd_scan(&janitor, gate, false, OutputFormat::Json, SarifLevel::Note)
+        };
+
+        scan(&BaselineArgs {
+            write: Some(&baseline),
+            ..Default::default()
         })
-        .map(|e| e.path().to_path_buf())
-        .collect();
-    Ok(files)
-}
-
-fn run_pytest(dir: &Path) -> anyhow::Result<()> {
-    let status = std::process::Command::new("pytest")
-        .args(["--tb=short", "-q"])
-        .current_dir(dir)
-        .status();
-
-    match status {
-        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
-            eprintln!("note: pytest not found — skipping verification");
-            Ok(())
-        }
-        Err(e) => Err(anyhow::anyhow!("Failed to spawn pytest: {}", e)),
-        Ok(s) if s.success() => Ok(()),
-        Ok(s) => Err(anyhow::anyhow!(
-            "pytest exited with code {}",
-            s.code().unwrap_or(-1)
-        )),
+        .unwrap();
+        let gate = BaselineArgs {
+            compare: Some(&baseline),
+            fail_on_new: true,
+            ..Default::default()
+        };
+        scan(&gate).unwrap();
+
+        std::fs::write(
+            tmp.join("pkg/legacy.py"),
+            "def export_xml():\n    return '<xml/>'\n\ndef export_yaml():\n    return '---'\n",
+        )
+        .unwrap();
+        let err = scan(&gate).unwrap_err();
+        assert_eq!(err.to_string(), "1 dead symbols are not in the baseline");
+        // Baselined symbols are still saved for the dashboard.
+        let registry = load_registry(&tmp).unwrap().unwrap();
+        assert_eq!(registry.len(), 2);
+
+        std::fs::remove_dir_all(tmp).ok();
+        std::fs::remove_file(baseline).ok();
+    }
+
+    #[test]
+    fn test_diff_report_lists_new_dead_symbols() {
+        use common::registry::{SymbolEntry, SymbolRegistry};
+
+        let entry = |id: u64, name: &str, dead: bool| SymbolEntry {
+            id,
+            name: name.into(),
+            qualified_name: name.into(),
+            module_path: String::new(),
+            file_path: "/p/api.py".into(),
+            entity_type: 0,
+            start_line: id as u32,
+            end_line: id as u32 + 1,
+            start_byte: 0,
+            end_byte: 40,
+            structural_hash: 0,
+            protected_by: (!dead).then_some(common::Protection::Referenced),
+            protection_detail: None,
+        };
+        let tmp = std::env::temp_dir().join("test_cli_diff");
+        std::fs::remove_dir_all(&tmp).ok();
+        std::fs::create_dir_all(&tmp).unwrap();
+        let mut old = SymbolRegistry::new();
+        old.insert(entry(1, "handler", false));
+        old.save(&tmp.join("old.rkyv")).unwrap();
+        let mut new = SymbolRegistry::new();
+        new.insert(entry(1, "handler", true));
+        new.insert(entry(2, "helper", true));
+        new.save(&tmp.join("new.rkyv")).unwrap();
+
+        let diff = common::registry::diff(
+            &SymbolRegistry::load(&tmp.join("old.rkyv")).unwrap(),
+            &SymbolRegistry::load(&tmp.join("new.rkyv")).unwrap(),
+        );
+        let mut out = Vec::new();
+        print_diff_report(&mut out, &diff).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.contains("| Newly dead     :                      2 |"));
+        assert!(text.contains("| Dead bytes     :                    +80 |"));
+        assert!(text.contains("NEWLY DEAD:\n  /p/api.py:1 - handler\n  /p/api.py:2 - helper\n"));
+        assert!(text.contains("ADDED:\n  /p/api.py:2 - helper\n"));
+        assert!(!text.contains("REMOVED:"));
+
+        let json: serde_json::Value = serde_json::to_value(&diff).unwrap();
+        assert_eq!(json["newly_dead"][1]["qualified_name"], "helper");
+        assert_eq!(json["dead_bytes_delta"], 80);
+
+        std::fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_registry_from_older_janitor_asks_for_rescan() {
+        let tmp = std::env::temp_dir().join("test_cli_old_registry");
+        std::fs::remove_dir_all(&tmp).ok();
+        std::fs::create_dir_all(tmp.join(".janitor")).unwrap();
+        // Registries written before format versioning have no header.
+        std::fs::write(tmp.join(".janitor/symbols.rkyv"), [0u8; 64]).unwrap();
+
+        let err = load_registry(&tmp).unwrap_err().to_string();
+        assert!(
+            err.ends_with("registry was produced by an older janitor — re-run scan"),
+            "{}",
+            err
+        );
+
+        std::fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_near_dedup_reports_score_and_differing_lines() {
+        let tmp = std::env::temp_dir().join("test_cli_dedup_near");
+        std::fs::remove_dir_all(&tmp).ok();
+        std::fs::create_dir_all(&tmp).unwrap();
+        let body = "def total(rows, limit):\n    kept = []\n    for row in rows:\n        if row is None:\n            continue\n        if row > limit:\n            kept.append(row)\n    if not kept:\n        return 0\n    return sum(kept) / len(kept)\n";
+        std::fs::write(tmp.join("a.py"), body).unwrap();
+        std::fs::write(
+            tmp.join("b.py"),
+            body.replace(
+                "        if row > limit:",
+                "        log.debug(row)\n        if row > limit:",
+            ),
+        )
+        .unwrap();
+        let janitor = Janitor::open(&tmp).unwrap();
+        let exact = janitor.dedup(&DedupOptions::default()).unwrap();
+        assert!(exact.groups.is_empty());
+
+        let near = DedupOptions {
+            near: Some(0.85),
+            ..Default::default()
+        };
+        let groups = janitor.dedup(&near).unwrap().similar;
+        let mut out = Vec::new();
+        print_similar_groups(&mut out, &groups).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.contains("| Similar groups   :                    1 |"));
+        assert!(text.contains("a.py:1-10 - total\n"));
+        assert!(text.contains("b.py:1-11 - total (differs at 6)\n"));
+
+        std::fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_why_prints_callers_protection_and_verdict() {
+        let tmp = std::env::temp_dir().join("test_cli_why");
+        std::fs::remove_dir_all(&tmp).ok();
+        std::fs::create_dir_all(&tmp).unwrap();
+        std::fs::write(
+            tmp.join("util.py"),
+            "def helper():\n    pass\n\ndef unused():\n    pass\n",
+        )
+        .unwrap();
+        std::fs::write(tmp.join("app.py"), "from util import helper\n\nhelper()\n").unwrap();
+        std::fs::write(tmp.join("notes.txt"), "unused is kept for later\n").unwrap();
+
+        let mut host = anatomist::parser::ParserHost::new().unwrap();
+        let options = anatomist::pipeline::ScanOptions::default();
+        let mut explanations =
+            anatomist::pipeline::explain(&tmp, &mut host, &options, "util.py::helper").unwrap();
+        explanations
+            .extend(anatomist::pipeline::explain(&tmp, &mut host, &options, "unused").unwrap());
+        let mut out = Vec::new();
+        print_explanations(&mut out, &explanations).unwrap();
+        let text = String::from_utf8(out).unwrap();
+
+        assert!(text.contains("util.py::helper (lines 1-2)\n"));
+        assert!(text.contains("app.py:3 - <module> [call]\n"));
+        assert!(text.contains("  Protection:    Referenced (stage: reference)\n"));
+        assert!(text.contains("  Verdict:       alive - not on the kill list\n"));
+        assert!(text.contains("  Protection:    GrepShield (stage: grep)\n"));
+        assert!(text.contains("notes.txt:1\n"));
+
+        std::fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_restore_latest_transaction() {
+        use reaper::ghost::{GhostKind, GhostManifest, GhostRecord};
+
+        let tmp = std::env::temp_dir().join("test_cli_restore");
+        std::fs::remove_dir_all(&tmp).ok();
+        std::fs::create_dir_all(tmp.join(".janitor/ghost/pkg")).unwrap();
+        let tmp = std::fs::canonicalize(&tmp).unwrap();
+
+        // Older run: a symbol deletion that must survive `--all`.
+        std::fs::write(tmp.join("keep.py"), "x = 1\n").unwrap();
+        let mut old = reaper::SafeDeleter::with_transaction(&tmp, "a").unwrap();
+        old.ensure_backup(&tmp.join("keep.py")).unwrap();
+        old.seal();
+        std::fs::write(tmp.join("keep.py"), "").unwrap();
+
+        // Latest run: a symbol deletion and a ghosted orphan file.
+        std::fs::write(tmp.join("app.py"), "def f():\n    pass\n").unwrap();
+        let mut latest = reaper::SafeDeleter::with_transaction(&tmp, "b").unwrap();
+        latest.ensure_backup(&tmp.join("app.py")).unwrap();
+        latest.seal();
+        std::fs::write(tmp.join("app.py"), "").unwrap();
+        std::fs::write(tmp.join(".janitor/ghost/pkg/stale.py"), "y = 2\n").unwrap();
+        let manifest = GhostManifest::open(&tmp);
+        manifest
+            .append(&GhostRecord {
+                transaction: "b".into(),
+                kind: GhostKind::File,
+                timestamp: 0,
+                ghost_name: PathBuf::from("pkg/stale.py"),
+                original: tmp.join("pkg/stale.py"),
+            })
+            .unwrap();
+
+        cmd_restore(&tmp, false, true, None).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(tmp.join("app.py")).unwrap(),
+            "def f():\n    pass\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(tmp.join("pkg/stale.py")).unwrap(),
+            "y = 2\n"
+        );
+        assert_eq!(std::fs::read_to_string(tmp.join("keep.py")).unwrap(), "");
+
+        cmd_restore(&tmp, false, false, Some(Path::new("keep.py"))).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(tmp.join("keep.py")).unwrap(),
+            "x = 1\n"
+        );
+        assert!(manifest.records().unwrap().is_empty());
+
+        std::fs::remove_dir_all(tmp).ok();
     }
 }
@@ -0,0 +1,104 @@
+//! Live scan progress: one redrawn status line per pipeline stage.
+
+use anatomist::progress::{PipelineEvent, Stage};
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+/// Minimum interval between redraws of the same line.
+const REDRAW_INTERVAL: Duration = Duration::from_millis(50);
+const BAR_WIDTH: usize = 30;
+
+/// Renders [`PipelineEvent`]s as a carriage-return status line per stage.
+pub struct ProgressLine<W: Write> {
+    out: W,
+    started: Instant,
+    last_draw: Option<Instant>,
+    grep_files: usize,
+}
+
+impl<W: Write> ProgressLine<W> {
+    pub fn new(out: W) -> Self {
+        Self {
+            out,
+            started: Instant::now(),
+            last_draw: None,
+            grep_files: 0,
+        }
+    }
+
+    /// Handles one event. Write errors are ignored: progress is best-effort.
+    pub fn handle(&mut self, event: PipelineEvent) {
+        match event {
+            PipelineEvent::StageStarted(stage) => {
+                self.started = Instant::now();
+                self.grep_files = 0;
+                self.draw(stage, String::new(), true);
+            }
+            PipelineEvent::FileParsed { index, total, .. } => {
+                let filled = (index * BAR_WIDTH).checked_div(total).unwrap_or(BAR_WIDTH);
+                let bar = format!(
+                    "[{}{}] {}/{} files",
+                    "=".repeat(filled),
+                    " ".repeat(BAR_WIDTH - filled),
+                    index,
+                    total
+                );
+                self.draw(Stage::Parse, bar, index == total);
+            }
+            PipelineEvent::GrepFileScanned { .. } => {
+                self.grep_files += 1;
+                let text = format!("{} files searched", self.grep_files);
+                self.draw(Stage::Grep, text, false);
+            }
+            PipelineEvent::StageFinished { stage, protected } => {
+                let mut text = format!("done in {:.1?}", self.started.elapsed());
+                if protected > 0 {
+                    text.push_str(&format!(", {} protected", protected));
+                }
+                self.draw(stage, text, true);
+                let _ = writeln!(self.out);
+                self.last_draw = None;
+            }
+        }
+    }
+
+    fn draw(&mut self, stage: Stage, text: String, force: bool) {
+        let now = Instant::now();
+        if !force && self.last_draw.is_some_and(|t| now - t < REDRAW_INTERVAL) {
+            return;
+        }
+        self.last_draw = Some(now);
+        let _ = write!(self.out, "\r\x1b[2K  {:<9}{}", stage.label(), text);
+        let _ = self.out.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress_line_renders_each_stage() {
+        let mut line = ProgressLine::new(Vec::new());
+        line.handle(PipelineEvent::StageStarted(Stage::Parse));
+        line.handle(PipelineEvent::FileParsed {
+            path: "a.py".into(),
+            index: 2,
+            total: 2,
+        });
+        line.handle(PipelineEvent::StageFinished {
+            stage: Stage::Parse,
+            protected: 0,
+        });
+        line.handle(PipelineEvent::StageStarted(Stage::Grep));
+        line.handle(PipelineEvent::StageFinished {
+            stage: Stage::Grep,
+            protected: 3,
+        });
+
+        let text = String::from_utf8(line.out).unwrap();
+        assert!(text.contains(&format!("[{}] 2/2 files", "=".repeat(BAR_WIDTH))));
+        assert!(text.contains("3 protected"));
+        assert_eq!(text.matches('\n').count(), 2);
+    }
+}
@@ -0,0 +1,245 @@
+//! Self-contained HTML report rendered from a saved symbol registry.
+//!
+//! One file, no external assets: styles and the table-sorting script are
+//! inlined so the report can be mailed or attached to a CI run as-is.
+
+use common::registry::SymbolRegistry;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::Path;
+
+const STYLE: &str = r#"
+body { font-family: -apple-system, "Segoe UI", Helvetica, Arial, sans-serif; margin: 2em; color: #222; }
+h1 { font-size: 1.6em; } h2 { font-size: 1.2em; margin-top: 2em; }
+.stats { display: flex; gap: 1em; }
+.stat { border: 1px solid #ddd; border-radius: 6px; padding: 0.8em 1.2em; }
+.stat b { display: block; font-size: 1.5em; }
+table { border-collapse: collapse; width: 100%; font-size: 0.9em; }
+th, td { border-bottom: 1px solid #eee; padding: 4px 8px; text-align: left; }
+th { background: #f6f6f6; cursor: pointer; user-select: none; }
+td.num { text-align: right; font-variant-numeric: tabular-nums; }
+code { font-family: Menlo, Consolas, monospace; }
+"#;
+
+/// Sorts `table.sortable` by the clicked column; numeric cells carry `data-v`.
+const SCRIPT: &str = r#"
+document.querySelectorAll("table.sortable th").forEach(function (th, col) {
+  th.addEventListener("click", function () {
+    var body = th.closest("table").tBodies[0];
+    var asc = th.dataset.dir !== "asc";
+    th.dataset.dir = asc ? "asc" : "desc";
+    var key = function (row) {
+      var cell = row.cells[col];
+      return cell.dataset.v !== undefined ? Number(cell.dataset.v) : cell.textContent;
+    };
+    Array.from(body.rows)
+      .sort(function (a, b) {
+        var x = key(a), y = key(b);
+        return (x < y ? -1 : x > y ? 1 : 0) * (asc ? 1 : -1);
+      })
+      .forEach(function (row) { body.appendChild(row); });
+  });
+});
+"#;
+
+/// Renders the registry (plus the orphan files of the same scan) as an HTML page.
+///
+/// Paths are shown relative to `project_root` when they lie beneath it.
+pub fn render(registry: &SymbolRegistry, orphan_files: &[String], project_root: &Path) -> String {
+    let root = format!(
+        "{}/",
+        project_root
+            .to_string_lossy()
+            .replace('\\', "/")
+            .trim_end_matches('/')
+    );
+    let relative = |path: &str| path.strip_prefix(&root).unwrap_or(path).to_string();
+
+    let total = registry.entries.len();
+    let mut dead: Vec<_> = registry
+        .entries
+        .iter()
+        .filter(|e| e.protected_by.is_none())
+        .collect();
+    dead.sort_by(|a, b| (&a.file_path, a.start_line).cmp(&(&b.file_path, b.start_line)));
+    let mut reasons: BTreeMap<String, usize> = BTreeMap::new();
+    for p in registry.entries.iter().filter_map(|e| e.protected_by) {
+        *reasons.entry(format!("{:?}", p)).or_default() += 1;
+    }
+    let protected = total - dead.len();
+    let density = if total > 0 {
+        protected as f64 * 100.0 / total as f64
+    } else {
+        100.0
+    };
+
+    let mut html = String::new();
+    // `write!` into a String cannot fail.
+    let _ = write!(
+        html,
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>Janitor report: {title}</title>\n<style>{STYLE}</style>\n</head>\n<body>\n\
+         <h1>Janitor report: <code>{title}</code></h1>\n\
+         <div class=\"stats\">\n\
+         <div class=\"stat\">Total<b>{total}</b></div>\n\
+         <div class=\"stat\">Dead<b>{dead}</b></div>\n\
+         <div class=\"stat\">Protected<b>{protected}</b></div>\n\
+         <div class=\"stat\">Density<b>{density:.1}%</b></div>\n\
+         </div>\n",
+        title = escape(&project_root.display().to_string()),
+        dead = dead.len(),
+    );
+
+    let _ = write!(
+        html,
+        "<h2>Dead symbols ({})</h2>\n<table class=\"sortable\" id=\"dead\">\n\
+         <thead><tr><th>File</th><th>Line</th><th>Name</th><th>Size</th><th>Structural hash</th></tr></thead>\n<tbody>\n",
+        dead.len()
+    );
+    for e in &dead {
+        let size = e.end_byte.saturating_sub(e.start_byte);
+        let _ = writeln!(
+            html,
+            "<tr><td>{}</td><td class=\"num\" data-v=\"{line}\">{line}</td><td><code>{}</code></td>\
+             <td class=\"num\" data-v=\"{size}\">{size}</td><td><code>{:016x}</code></td></tr>",
+            escape(&relative(&e.file_path)),
+            escape(&e.qualified_name),
+            e.structural_hash,
+            line = e.start_line,
+        );
+    }
+    html.push_str("</tbody>\n</table>\n");
+
+    let _ = writeln!(html, "<h2>Orphan files ({})</h2>", orphan_files.len());
+    if orphan_files.is_empty() {
+        html.push_str("<p>No orphan files detected.</p>\n");
+    } else {
+        html.push_str("<ul id=\"orphans\">\n");
+        for path in orphan_files {
+            let _ = writeln!(html, "<li><code>{}</code></li>", escape(&relative(path)));
+        }
+        html.push_str("</ul>\n");
+    }
+
+    let _ = write!(
+        html,
+        "<h2>Protected symbols by reason ({protected})</h2>\n<table class=\"sortable\" id=\"reasons\">\n\
+         <thead><tr><th>Reason</th><th>Symbols</th></tr></thead>\n<tbody>\n"
+    );
+    for (reason, count) in &reasons {
+        let _ = writeln!(
+            html,
+            "<tr><td>{reason}</td><td class=\"num\" data-v=\"{count}\">{count}</td></tr>"
+        );
+    }
+    let _ = write!(
+        html,
+        "</tbody>\n</table>\n<script>{SCRIPT}</script>\n</body>\n</html>\n"
+    );
+    html
+}
+
+/// Escapes text for element content and double-quoted attribute values.
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::registry::SymbolEntry;
+    use common::Protection;
+
+    fn entry(id: u64, name: &str, file: &str, protected_by: Option<Protection>) -> SymbolEntry {
+        SymbolEntry {
+            id,
+            name: name.into(),
+            qualified_name: name.into(),
+            module_path: String::new(),
+            file_path: file.into(),
+            entity_type: 0,
+            start_line: 12,
+            end_line: 20,
+            start_byte: 100,
+            end_byte: 340,
+            structural_hash: 0xdead_beef,
+            protected_by,
+            protection_detail: None,
+        }
+    }
+
+    fn sample() -> SymbolRegistry {
+        let mut registry = SymbolRegistry::new();
+        registry.insert(entry(1, "export_xml", "/proj/pkg/reports.py", None));
+        registry.insert(entry(
+            2,
+            "main",
+            "/proj/cli.py",
+            Some(Protection::EntryPoint),
+        ));
+        registry.insert(entry(
+            3,
+            "handler",
+            "/proj/api.py",
+            Some(Protection::Referenced),
+        ));
+        registry.insert(entry(
+            4,
+            "view",
+            "/proj/api.py",
+            Some(Protection::Referenced),
+        ));
+        registry
+    }
+
+    #[test]
+    fn test_render_contains_summary_rows_and_sections() {
+        let html = render(
+            &sample(),
+            &["/proj/pkg/stale.py".to_string()],
+            Path::new("/proj"),
+        );
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("Total<b>4</b>"));
+        assert!(html.contains("Dead<b>1</b>"));
+        assert!(html.contains("Protected<b>3</b>"));
+        assert!(html.contains("Density<b>75.0%</b>"));
+        assert!(html.contains(
+            "<tr><td>pkg/reports.py</td><td class=\"num\" data-v=\"12\">12</td>\
+             <td><code>export_xml</code></td><td class=\"num\" data-v=\"240\">240</td>\
+             <td><code>00000000deadbeef</code></td></tr>"
+        ));
+        assert!(html.contains("<li><code>pkg/stale.py</code></li>"));
+        assert!(html.contains("<tr><td>EntryPoint</td><td class=\"num\" data-v=\"1\">1</td></tr>"));
+        assert!(html.contains("<tr><td>Referenced</td><td class=\"num\" data-v=\"2\">2</td></tr>"));
+        // Self-contained: no external stylesheets or scripts.
+        assert!(!html.contains("<link"));
+        assert!(!html.contains("src="));
+    }
+
+    #[test]
+    fn test_render_escapes_names_and_paths() {
+        let mut registry = SymbolRegistry::new();
+        registry.insert(entry(1, "<lambda>", "/proj/a&b.py", None));
+        registry.insert(entry(2, "cmp<T>&\"x\"", "/proj/a&b.py", None));
+        let html = render(&registry, &[], Path::new("/proj"));
+
+        assert!(html.contains("<td>a&amp;b.py</td>"));
+        assert!(html.contains("<code>&lt;lambda&gt;</code>"));
+        assert!(html.contains("<code>cmp&lt;T&gt;&amp;&quot;x&quot;</code>"));
+        assert!(!html.contains("<lambda>"));
+        assert!(html.contains("No orphan files detected."));
+    }
+}
@@ -0,0 +1,4 @@
+//! Scan report formats: SARIF for code scanning, static HTML for people.
+
+pub mod html;
+pub mod sarif;
@@ -0,0 +1,207 @@
+//! SARIF 2.1.0 rendering of scan results for GitHub code scanning.
+//!
+//! Each dead symbol becomes a `janitor/dead-symbol` result with a region
+//! covering the entity's line range; each orphan file becomes a file-level
+//! `janitor/orphan-file` result. Protected symbols are never emitted.
+
+use anatomist::pipeline::ScanResult;
+use clap::ValueEnum;
+use serde_json::{json, Value};
+use std::path::Path;
+
+const SCHEMA_URI: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const RULE_DEAD_SYMBOL: &str = "janitor/dead-symbol";
+const RULE_ORPHAN_FILE: &str = "janitor/orphan-file";
+
+/// SARIF `level` assigned to every emitted result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum SarifLevel {
+    #[default]
+    Note,
+    Warning,
+    Error,
+}
+
+impl SarifLevel {
+    fn as_str(self) -> &'static str {
+        match self {
+            SarifLevel::Note => "note",
+            SarifLevel::Warning => "warning",
+            SarifLevel::Error => "error",
+        }
+    }
+}
+
+/// Renders `result` as a SARIF 2.1.0 log.
+///
+/// File URIs are made relative to `project_root` (with `uriBaseId` `%SRCROOT%`)
+/// so GitHub can map them onto the checked-out repository.
+pub fn render(result: &ScanResult, project_root: &Path, level: SarifLevel) -> Value {
+    let level = level.as_str();
+    let mut results = Vec::with_capacity(result.dead.len() + result.orphan_files.len());
+
+    for entity in &result.dead {
+        let name = entity.fully_qualified_name();
+        results.push(json!({
+            "ruleId": RULE_DEAD_SYMBOL,
+            "level": level,
+            "message": {
+                "text": format!("`{name}` is never referenced."),
+            },
+            "locations": [{
+                "physicalLocation": {
+                    "artifactLocation": artifact_location(&entity.file_path, project_root),
+                    "region": {
+                        "startLine": entity.start_line,
+                        "endLine": entity.end_line,
+                    },
+                },
+                "logicalLocations": [{
+                    "fullyQualifiedName": name,
+                }],
+            }],
+        }));
+    }
+
+    for file in &result.orphan_files {
+        results.push(json!({
+            "ruleId": RULE_ORPHAN_FILE,
+            "level": level,
+            "message": { "text": "File is never imported." },
+            "locations": [{
+                "physicalLocation": {
+                    "artifactLocation": artifact_location(file, project_root),
+                },
+            }],
+        }));
+    }
+
+    json!({
+        "$schema": SCHEMA_URI,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "janitor",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": "https://github.com/GhrammR/the-janitor",
+                    "rules": [
+                        {
+                            "id": RULE_DEAD_SYMBOL,
+                            "shortDescription": { "text": "Unreferenced symbol" },
+                            "defaultConfiguration": { "level": level },
+                        },
+                        {
+                            "id": RULE_ORPHAN_FILE,
+                            "shortDescription": { "text": "Unimported file" },
+                            "defaultConfiguration": { "level": level },
+                        },
+                    ],
+                },
+            },
+            "results": results,
+        }],
+    })
+}
+
+/// Builds an `artifactLocation`, relative to `project_root` when possible.
+fn artifact_location(file_path: &str, project_root: &Path) -> Value {
+    match Path::new(file_path).strip_prefix(project_root) {
+        Ok(rel) => json!({
+            "uri": rel.to_string_lossy().replace('\\', "/"),
+            "uriBaseId": "%SRCROOT%",
+        }),
+        Err(_) => json!({ "uri": file_path }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anatomist::{Entity, EntityType, Protection};
+
+    fn entity(name: &str, protected_by: Option<Protection>) -> Entity {
+        Entity {
+            name: name.to_string(),
+            entity_type: EntityType::FunctionDefinition,
+            start_byte: 0,
+            end_byte: 10,
+            start_line: 3,
+            end_line: 7,
+            file_path: "/proj/pkg/mod.py".to_string(),
+            qualified_name: name.to_string(),
+            module_path: "pkg.mod".to_string(),
+            parent_class: None,
+            base_classes: Vec::new(),
+            protected_by,
+            protection_detail: None,
+            decorators: Vec::new(),
+            structural_hash: None,
+            structural_nodes: 0,
+        }
+    }
+
+    fn sample() -> ScanResult {
+        ScanResult {
+            dead: vec![entity("unused", None)],
+            evidence: Vec::new(),
+            protected: vec![entity("used", Some(Protection::Referenced))],
+            total: 2,
+            stage_counts: [0, 1, 0, 0, 0, 0],
+            orphan_files: vec!["/proj/pkg/orphan.py".to_string()],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_sarif_structure() {
+        let log = render(&sample(), Path::new("/proj"), SarifLevel::default());
+
+        assert_eq!(log["version"], "2.1.0");
+        assert_eq!(log["$schema"], SCHEMA_URI);
+        let run = &log["runs"][0];
+        assert_eq!(run["tool"]["driver"]["name"], "janitor");
+        assert_eq!(run["tool"]["driver"]["rules"].as_array().unwrap().len(), 2);
+
+        let results = run["results"].as_array().unwrap();
+        assert_eq!(results.len(), 2, "protected symbols must not be emitted");
+
+        let dead = &results[0];
+        assert_eq!(dead["ruleId"], RULE_DEAD_SYMBOL);
+        assert_eq!(dead["level"], "note");
+        assert_eq!(
+            dead["message"]["text"],
+            "`pkg.mod.unused` is never referenced."
+        );
+        let loc = &dead["locations"][0]["physicalLocation"];
+        assert_eq!(loc["artifactLocation"]["uri"], "pkg/mod.py");
+        assert_eq!(loc["artifactLocation"]["uriBaseId"], "%SRCROOT%");
+        assert_eq!(loc["region"]["startLine"], 3);
+        assert_eq!(loc["region"]["endLine"], 7);
+        assert_eq!(
+            dead["locations"][0]["logicalLocations"][0]["fullyQualifiedName"],
+            "pkg.mod.unused"
+        );
+
+        let orphan = &results[1];
+        assert_eq!(orphan["ruleId"], RULE_ORPHAN_FILE);
+        let loc = &orphan["locations"][0]["physicalLocation"];
+        assert_eq!(loc["artifactLocation"]["uri"], "pkg/orphan.py");
+        assert!(loc.get("region").is_none());
+    }
+
+    #[test]
+    fn test_sarif_level_configurable() {
+        let log = render(&sample(), Path::new("/proj"), SarifLevel::Error);
+        for result in log["runs"][0]["results"].as_array().unwrap() {
+            assert_eq!(result["level"], "error");
+        }
+    }
+
+    #[test]
+    fn test_uri_outside_root_kept_verbatim() {
+        let loc = artifact_location("/elsewhere/x.py", Path::new("/proj"));
+        assert_eq!(loc["uri"], "/elsewhere/x.py");
+        assert!(loc.get("uriBaseId").is_none());
+    }
+}
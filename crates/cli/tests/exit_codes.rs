@@ -0,0 +1,129 @@
+//! The `janitor scan` exit-code contract, checked against the built binary:
+//! 0 = pass, 1 = `--fail-on-dead` found dead symbols, 2 = over a
+//! `--max-dead*` threshold, 3 = internal or usage error, 4 = warnings under
+//! `--deny-warnings`.
+
+use assert_cmd::Command;
+use std::path::PathBuf;
+
+/// A project with one live function, one dead function and an orphan module
+/// holding a second dead function.
+fn project(name: &str, config: &str) -> PathBuf {
+    let tmp = std::env::temp_dir().join(name);
+    std::fs::remove_dir_all(&tmp).ok();
+    std::fs::create_dir_all(tmp.join("pkg")).unwrap();
+    std::fs::write(tmp.join(".janitor.toml"), config).unwrap();
+    std::fs::write(
+        tmp.join("main.py"),
+        "from pkg.billing import keep\n\nkeep()\n",
+    )
+    .unwrap();
+    std::fs::write(tmp.join("pkg/__init__.py"), "").unwrap();
+    std::fs::write(
+        tmp.join("pkg/billing.py"),
+        "def keep():\n    return 1\n\n\ndef legacy():\n    x = 1\n    return x\n",
+    )
+    .unwrap();
+    std::fs::write(tmp.join("pkg/stale.py"), "def old():\n    pass\n").unwrap();
+    tmp
+}
+
+/// Exit code of `janitor scan <root> <args>`.
+fn scan(root: &PathBuf, args: &[&str]) -> i32 {
+    let output = Command::new(env!("CARGO_BIN_EXE_cli"))
+        .arg("scan")
+        .arg(root)
+        .args(["--no-cache", "--format", "json"])
+        .args(args)
+        .output()
+        .unwrap();
+    output.status.code().unwrap()
+}
+
+#[test]
+fn dead_symbols_only_fail_when_asked() {
+    let root = project("test_exit_fail_on_dead", "");
+    assert_eq!(scan(&root, &[]), 0);
+    assert_eq!(scan(&root, &["--fail-on-dead"]), 1);
+    // Within a threshold, the scan passes even under --fail-on-dead.
+    assert_eq!(scan(&root, &["--fail-on-dead", "--max-dead", "5"]), 0);
+
+    let clean = project("test_exit_no_dead", "");
+    std::fs::write(clean.join("pkg/billing.py"), "def keep():\n    return 1\n").unwrap();
+    std::fs::remove_file(clean.join("pkg/stale.py")).unwrap();
+    assert_eq!(scan(&clean, &["--fail-on-dead"]), 0);
+
+    std::fs::remove_dir_all(root).ok();
+    std::fs::remove_dir_all(clean).ok();
+}
+
+#[test]
+fn thresholds_exit_two() {
+    let root = project("test_exit_thresholds", "");
+    assert_eq!(scan(&root, &["--max-dead", "1"]), 2);
+    assert_eq!(scan(&root, &["--max-dead", "2"]), 0);
+    assert_eq!(scan(&root, &["--max-dead-bytes", "10"]), 2);
+    assert_eq!(scan(&root, &["--max-dead-bytes", "10000"]), 0);
+    // The orphan module counts once more, by its whole size.
+    assert_eq!(scan(&root, &["--max-dead", "2", "--count-orphans"]), 2);
+
+    std::fs::remove_dir_all(root).ok();
+}
+
+#[test]
+fn ci_table_sets_defaults_and_flags_override() {
+    let root = project("test_exit_ci_table", "[ci]\nmax_dead = 0\n");
+    assert_eq!(scan(&root, &[]), 2);
+    assert_eq!(scan(&root, &["--max-dead", "10"]), 0);
+
+    let orphans = project(
+        "test_exit_ci_orphans",
+        "[ci]\nmax_dead = 2\ncount_orphans = true\n",
+    );
+    assert_eq!(scan(&orphans, &[]), 2);
+
+    std::fs::remove_dir_all(root).ok();
+    std::fs::remove_dir_all(orphans).ok();
+}
+
+#[test]
+fn denied_warnings_exit_four() {
+    let root = project("test_exit_deny_warnings", "");
+    assert_eq!(scan(&root, &["--deny-warnings"]), 0);
+    std::fs::write(root.join("broken.py"), "def g(\n").unwrap();
+    assert_eq!(scan(&root, &[]), 0);
+    assert_eq!(scan(&root, &["--deny-warnings"]), 4);
+
+    std::fs::remove_dir_all(root).ok();
+}
+
+#[test]
+fn errors_exit_three() {
+    let missing = std::env::temp_dir().join("test_exit_no_such_project");
+    std::fs::remove_dir_all(&missing).ok();
+    assert_eq!(scan(&missing, &[]), 3);
+
+    let root = project("test_exit_usage", "");
+    assert_eq!(scan(&root, &["--no-such-flag"]), 3);
+    assert_eq!(scan(&root, &["--max-dead", "many"]), 3);
+
+    // `why` shares the scan's `--rules` flags.
+    std::fs::write(root.join("bad.json"), "[]").unwrap();
+    let why = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_cli"))
+            .arg("why")
+            .arg(&root)
+            .arg("keep")
+            .args(["--rules", root.join("bad.json").to_str().unwrap()])
+            .args(args)
+            .output()
+            .unwrap()
+            .status
+            .code()
+            .unwrap()
+    };
+    assert_eq!(why(&[]), 0);
+    assert_eq!(why(&["--strict-rules"]), 3);
+
+    std::fs::remove_dir_all(root).ok();
+}
@@ -0,0 +1,110 @@
+//! `janitor hook`: installing the pre-commit script and running the check,
+//! with the staged-file list passed on stdin instead of asking git.
+
+use assert_cmd::Command;
+use std::path::{Path, PathBuf};
+
+fn project(name: &str) -> PathBuf {
+    let tmp = std::env::temp_dir().join(name);
+    std::fs::remove_dir_all(&tmp).ok();
+    std::fs::create_dir_all(tmp.join(".git")).unwrap();
+    std::fs::write(
+        tmp.join("changed.py"),
+        "def called_elsewhere():\n    pass\n\ndef forgotten():\n    pass\n",
+    )
+    .unwrap();
+    std::fs::write(
+        tmp.join("main.py"),
+        "from changed import called_elsewhere\n\ncalled_elsewhere()\n\ndef untouched_dead():\n    pass\n",
+    )
+    .unwrap();
+    tmp
+}
+
+fn janitor(dir: &Path) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_cli"));
+    command.current_dir(dir);
+    command
+}
+
+#[test]
+fn install_writes_an_executable_hook_once() {
+    let root = project("test_cli_hook_install");
+    // The hooks directory comes from git itself.
+    std::fs::remove_dir_all(root.join(".git")).unwrap();
+    let init = std::process::Command::new("git")
+        .args(["init", "-q"])
+        .current_dir(&root)
+        .status();
+    if !init.is_ok_and(|s| s.success()) {
+        return;
+    }
+    janitor(&root).args(["hook", "install"]).assert().success();
+    let hook = root.join(".git/hooks/pre-commit");
+    let script = std::fs::read_to_string(&hook).unwrap();
+    assert!(script.starts_with("#!/bin/sh\n"));
+    // The hook runs this very binary, not whatever `janitor` is on PATH.
+    let exe = dunce::canonicalize(env!("CARGO_BIN_EXE_cli")).unwrap();
+    assert!(
+        script.ends_with(&format!("exec '{}' hook run\n", exe.display())),
+        "{}",
+        script
+    );
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(&hook).unwrap().permissions().mode();
+        assert_eq!(mode & 0o111, 0o111);
+    }
+
+    janitor(&root).args(["hook", "install"]).assert().code(3);
+    janitor(&root)
+        .args(["hook", "install", "--force"])
+        .assert()
+        .success();
+    let yaml = janitor(&root)
+        .args(["hook", "install", "--pre-commit-config"])
+        .output()
+        .unwrap();
+    assert!(String::from_utf8_lossy(&yaml.stdout)
+        .contains(&format!("entry: \"'{}' hook run\"", exe.display())));
+
+    std::fs::remove_dir_all(root).ok();
+}
+
+#[test]
+fn run_reports_staged_dead_symbols_not_in_the_baseline() {
+    let root = project("test_cli_hook_run");
+
+    // Nothing staged in Python: pass without scanning.
+    janitor(&root)
+        .args(["hook", "run", "--stdin"])
+        .write_stdin("README.md\n")
+        .assert()
+        .success()
+        .stdout("");
+
+    let output = janitor(&root)
+        .args(["hook", "run", "--stdin"])
+        .write_stdin("changed.py\n")
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "changed.py:4: dead symbol `forgotten`\n"
+    );
+
+    // Accepted in the baseline: the commit goes through.
+    janitor(&root)
+        .args(["scan", ".", "--write-baseline", ".janitor/baseline.json"])
+        .assert()
+        .success();
+    janitor(&root)
+        .args(["hook", "run", "changed.py"])
+        .assert()
+        .success()
+        .stdout("");
+
+    std::fs::remove_dir_all(root).ok();
+}
@@ -0,0 +1,44 @@
+//! Two janitor processes on one project: the second fails fast, naming the
+//! first, or queues behind it with `--wait`.
+
+use assert_cmd::Command;
+use common::lock::{LockMode, ProjectLock};
+use std::time::Duration;
+
+#[test]
+fn held_lock_fails_fast_or_waits() {
+    let root = std::env::temp_dir().join("test_cli_lock");
+    std::fs::remove_dir_all(&root).ok();
+    std::fs::create_dir_all(&root).unwrap();
+    std::fs::write(root.join("main.py"), "def f():\n    pass\n").unwrap();
+    let scan = || {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_cli"));
+        command.arg("scan").arg(&root).arg("--no-cache");
+        command
+    };
+
+    let held =
+        ProjectLock::acquire(&root.join(".janitor"), LockMode::Exclusive, "clean", false).unwrap();
+    let output = scan().output().unwrap();
+    assert_eq!(output.status.code(), Some(3));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let holder = format!(
+        "another janitor process (pid {}, janitor clean",
+        std::process::id()
+    );
+    assert!(stderr.contains(&holder), "{}", stderr);
+
+    let release = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(300));
+        drop(held);
+    });
+    scan().arg("--wait").assert().success();
+    release.join().unwrap();
+
+    // Shared holders do not exclude each other.
+    let _reader =
+        ProjectLock::acquire(&root.join(".janitor"), LockMode::Shared, "report", false).unwrap();
+    scan().assert().success();
+
+    std::fs::remove_dir_all(root).ok();
+}
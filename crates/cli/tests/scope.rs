@@ -0,0 +1,79 @@
+//! Scoped scans (`--only`, `--changed`, `--project-root`), as a pre-commit
+//! hook runs them: the whole project is analysed, one file is reported.
+
+use assert_cmd::Command;
+use std::path::{Path, PathBuf};
+
+fn project(name: &str) -> PathBuf {
+    let tmp = std::env::temp_dir().join(name);
+    std::fs::remove_dir_all(&tmp).ok();
+    std::fs::create_dir_all(&tmp).unwrap();
+    std::fs::write(
+        tmp.join("changed.py"),
+        "def called_elsewhere():\n    pass\n\ndef forgotten():\n    pass\n",
+    )
+    .unwrap();
+    std::fs::write(
+        tmp.join("main.py"),
+        "from changed import called_elsewhere\n\ncalled_elsewhere()\n\ndef untouched_dead():\n    pass\n",
+    )
+    .unwrap();
+    tmp
+}
+
+/// Qualified names of the dead symbols in a `scan --format json` document.
+fn dead_names(stdout: &[u8]) -> Vec<String> {
+    let doc: serde_json::Value = serde_json::from_slice(stdout).unwrap();
+    doc["dead"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| e["qualified_name"].as_str().unwrap().to_string())
+        .collect()
+}
+
+fn scan(dir: &Path) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_cli"));
+    command.current_dir(dir).arg("scan");
+    command
+}
+
+#[test]
+fn changed_files_from_stdin_limit_the_report() {
+    let root = project("test_cli_scope_changed");
+    let output = scan(&root)
+        .args([".", "--changed", "--format", "json"])
+        .write_stdin("changed.py\ndeleted.py\n\n")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(dead_names(&output.stdout), vec!["forgotten"]);
+    // A partial scan does not overwrite the saved registry.
+    assert!(!root.join(".janitor/symbols.rkyv").exists());
+
+    std::fs::remove_dir_all(root).ok();
+}
+
+#[test]
+fn only_and_project_root_name_files_from_the_working_directory() {
+    let root = project("test_cli_scope_only");
+    let only = scan(&root)
+        .args([".", "--only", "./changed.py", "--format", "json"])
+        .output()
+        .unwrap();
+    assert_eq!(dead_names(&only.stdout), vec!["forgotten"]);
+
+    let file = scan(&root)
+        .args(["changed.py", "--project-root", ".", "--format", "json"])
+        .output()
+        .unwrap();
+    assert_eq!(dead_names(&file.stdout), vec!["forgotten"]);
+
+    let full = scan(&root)
+        .args([".", "--format", "json"])
+        .output()
+        .unwrap();
+    assert_eq!(dead_names(&full.stdout).len(), 2);
+
+    std::fs::remove_dir_all(root).ok();
+}
@@ -0,0 +1,139 @@
+//! Monorepo scans (`--root`, `[workspace] roots`): one reference graph over
+//! several Python roots, with totals reported per root.
+
+use assert_cmd::Command;
+use std::path::PathBuf;
+
+fn monorepo(name: &str) -> PathBuf {
+    let tmp = std::env::temp_dir().join(name);
+    std::fs::remove_dir_all(&tmp).ok();
+    for dir in ["services/a/app", "services/b/worker", "libs/common/common"] {
+        std::fs::create_dir_all(tmp.join(dir)).unwrap();
+    }
+    std::fs::write(
+        tmp.join("services/a/app/main.py"),
+        "from common.helpers import helper\n\nhelper()\n",
+    )
+    .unwrap();
+    std::fs::write(
+        tmp.join("services/b/worker/main.py"),
+        "def idle():\n    pass\n",
+    )
+    .unwrap();
+    std::fs::write(tmp.join("libs/common/common/__init__.py"), "").unwrap();
+    std::fs::write(
+        tmp.join("libs/common/common/helpers.py"),
+        "def helper():\n    return 1\n\n\ndef unused():\n    return 2\n",
+    )
+    .unwrap();
+    tmp
+}
+
+fn scan(root: &PathBuf, args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_cli"))
+        .current_dir(root)
+        .args(["scan", "."])
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn roots_share_one_graph_and_report_per_root() {
+    let root = monorepo("test_cli_workspace_roots");
+    let output = scan(
+        &root,
+        &[
+            "--root",
+            "services/a",
+            "--root",
+            "services/b",
+            "--root",
+            "libs/common",
+            "--format",
+            "json",
+        ],
+    );
+    assert!(output.status.success());
+    let doc: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let dead: Vec<&str> = doc["dead"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| e["qualified_name"].as_str().unwrap())
+        .collect();
+    assert!(dead.contains(&"unused"));
+    assert!(dead.contains(&"idle"));
+    assert!(!dead.contains(&"helper"));
+
+    let roots: Vec<(&str, u64)> = doc["roots"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| (r["root"].as_str().unwrap(), r["dead"].as_u64().unwrap()))
+        .collect();
+    assert_eq!(
+        roots,
+        vec![("services/a", 0), ("services/b", 1), ("libs/common", 1)]
+    );
+    let report = String::from_utf8_lossy(&output.stderr);
+    assert!(report.contains("WORKSPACE ROOTS:"));
+    assert!(report.contains("  libs/common/  2 entities, 1 dead"));
+
+    std::fs::remove_dir_all(root).ok();
+}
+
+#[test]
+fn config_roots_apply_and_missing_roots_fail() {
+    let root = monorepo("test_cli_workspace_config");
+    std::fs::write(
+        root.join(".janitor.toml"),
+        "[workspace]\nroots = [\"services/a\", \"libs/common\"]\n",
+    )
+    .unwrap();
+    let output = scan(&root, &["--format", "json"]);
+    assert!(output.status.success());
+    let doc: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(doc["roots"].as_array().unwrap().len(), 2);
+
+    let missing = scan(&root, &["--root", "services/c"]);
+    assert!(!missing.status.success());
+    assert!(String::from_utf8_lossy(&missing.stderr).contains("`services/c` is not a directory"));
+
+    // A bad configured root fails too, instead of being dropped.
+    std::fs::write(
+        root.join(".janitor.toml"),
+        "[workspace]\nroots = [\"services/a\", \"libs/gone\"]\n",
+    )
+    .unwrap();
+    let missing = scan(&root, &[]);
+    assert!(!missing.status.success());
+    assert!(String::from_utf8_lossy(&missing.stderr).contains("`libs/gone` is not a directory"));
+
+    std::fs::remove_dir_all(root).ok();
+}
+
+#[test]
+fn why_takes_the_scan_roots() {
+    let root = monorepo("test_cli_workspace_why");
+    let why = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_cli"))
+            .current_dir(&root)
+            .args(["why", ".", "helper"])
+            .args(args)
+            .output()
+            .unwrap()
+    };
+    // Only with the library root is `common.helpers` importable from app code.
+    let rooted = why(&["--root", "services/a", "--root", "libs/common"]);
+    assert!(rooted.status.success());
+    let text = String::from_utf8_lossy(&rooted.stdout);
+    assert!(text.contains("Verdict:       alive"), "{}", text);
+    let unrooted = String::from_utf8_lossy(&why(&[]).stdout).into_owned();
+    assert!(unrooted.contains("Verdict:       DEAD"), "{}", unrooted);
+
+    let missing = why(&["--root", "services/c"]);
+    assert!(!missing.status.success());
+
+    std::fs::remove_dir_all(root).ok();
+}
@@ -0,0 +1,316 @@
+//! # Project Configuration: `.janitor.toml`
+//!
+//! Optional per-project overrides for the pipeline's built-in tables. Every
+//! list that replaces a built-in default is an `Option`: `None` means "use the
+//! built-in constant", `Some(list)` replaces it outright.
+//!
+//! ```toml
+//! protected_dirs = ["tests", "migrations"]
+//! low_confidence_dirs = ["scripts", "bin"]
+//! extra_entry_points = ["cli.py"]
+//! plugin_dirs = ["spiders", "jobs"]
+//! grep_extensions = ["html", "yaml"]
+//! grep_min_name_length = 4
+//! library_mode = false
+//! exclude = ["build/", "generated/**"]
+//! protect_symbols = ["billing.legacy.migrate_v1"]
+//! source_roots = ["src", "lib"]
+//! type_checking_keeps_alive = true
+//! test_command = "pytest -x"
+//! test_timeout = 600
+//! test_env = ["DJANGO_SETTINGS_MODULE=app.settings.test"]
+//!
+//! [ci]
+//! max_dead = 0
+//! max_dead_bytes = 20_000
+//! fail_on_dead = true
+//! count_orphans = true
+//!
+//! [workspace]
+//! roots = ["services/a", "services/b", "libs/common"]
+//!
+//! [di]
+//! registration_calls = ["register", "provide", "Factory"]
+//! dict_registries = true
+//! ```
+//!
+//! Unknown keys and tables produce warnings, not errors; a value of the wrong
+//! type is an error pointing at its line.
+
+use serde::{Deserialize, Deserializer};
+use std::path::Path;
+
+/// Parsed `.janitor.toml`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, Deserialize)]
+#[serde(default)]
+pub struct JanitorConfig {
+    /// Stage 0 directory names; replaces the built-in protected directories.
+    pub protected_dirs: Option<Vec<String>>,
+    /// Directory names whose dead symbols are reported as low confidence
+    /// rather than dead; replaces the built-in list.
+    pub low_confidence_dirs: Option<Vec<String>>,
+    /// File names never reported as orphans, in addition to the built-in entry points.
+    pub extra_entry_points: Vec<String>,
+    /// Framework-discovered directories; replaces the built-in plugin directories.
+    pub plugin_dirs: Option<Vec<String>>,
+    /// File extensions searched by the grep shield; replaces the built-in list.
+    pub grep_extensions: Option<Vec<String>>,
+    /// Names shorter than this need quotes or a colon around them to count as
+    /// grep shield hits (default 4).
+    pub grep_min_name_length: Option<usize>,
+    /// Protect all public symbols (Stage 3), as if `--library` were passed.
+    pub library_mode: Option<bool>,
+    /// Gitignore-syntax patterns excluded from every project walk (see [`crate::walk`]).
+    pub exclude: Vec<String>,
+    /// Dotted symbol paths (`pkg.module.Class.method`) that are never dead.
+    pub protect_symbols: Vec<String>,
+    /// Directories absolute imports resolve against; replaces auto-detection
+    /// (`src/`, `pyproject.toml` hints). The project root is always searched last.
+    pub source_roots: Option<Vec<String>>,
+    /// Whether references through names imported only under
+    /// `if TYPE_CHECKING:` keep their targets alive (default `true`).
+    pub type_checking_keeps_alive: Option<bool>,
+    /// Command verifying a simulated deletion (default `pytest --tb=short -q`).
+    #[serde(deserialize_with = "non_empty_command")]
+    pub test_command: Option<String>,
+    /// Seconds the verification command may run before it is killed and the
+    /// simulation counts as failed.
+    pub test_timeout: Option<u64>,
+    /// `KEY=VALUE` variables set for the verification command.
+    #[serde(deserialize_with = "key_value_pairs")]
+    pub test_env: Vec<String>,
+    /// `[ci]`: exit-code gates of `janitor scan`.
+    pub ci: CiConfig,
+    /// `[workspace]`: the Python roots of a monorepo.
+    pub workspace: WorkspaceConfig,
+    /// `[di]`: how dependency-injection registrations are recognised.
+    pub di: DiConfig,
+}
+
+/// The `[ci]` table: defaults for the `scan` flags of the same names.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, Deserialize)]
+#[serde(default)]
+pub struct CiConfig {
+    /// Most dead symbols a scan may report before failing.
+    pub max_dead: Option<u64>,
+    /// Most bytes of dead code a scan may report before failing.
+    pub max_dead_bytes: Option<u64>,
+    /// Fail when any dead symbol is found.
+    pub fail_on_dead: Option<bool>,
+    /// Count orphan files towards the dead totals.
+    pub count_orphans: Option<bool>,
+}
+
+/// The `[workspace]` table: several Python roots scanned as one project.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, Deserialize)]
+#[serde(default)]
+pub struct WorkspaceConfig {
+    /// Root directories relative to the project root, searched in this order
+    /// by import resolution; the default for `janitor scan --root`. Empty
+    /// means the project root is the only root.
+    pub roots: Vec<String>,
+}
+
+/// The `[di]` table: overrides of the built-in DI registration rules.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, Deserialize)]
+#[serde(default)]
+pub struct DiConfig {
+    /// Callee names whose arguments are registered implementations
+    /// (`container.register(Base, Impl)`); replaces the built-in list.
+    pub registration_calls: Option<Vec<String>>,
+    /// Whether string-keyed dicts assigned to registry-like names
+    /// (`SERVICES = {"email": Smtp}`) count as registrations (default `true`).
+    pub dict_registries: Option<bool>,
+}
+
+/// Errors from loading `.janitor.toml`.
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error(".janitor.toml line {line}: {message}")]
+    Parse { line: usize, message: String },
+}
+
+impl JanitorConfig {
+    /// Name of the configuration file at the project root.
+    pub const FILE_NAME: &'static str = ".janitor.toml";
+
+    /// Loads `{project_root}/.janitor.toml`, returning the defaults when absent.
+    ///
+    /// The second element lists non-fatal warnings (unknown keys, tables).
+    pub fn load(project_root: &Path) -> Result<(Self, Vec<String>), ConfigError> {
+        match std::fs::read_to_string(project_root.join(Self::FILE_NAME)) {
+            Ok(text) => Self::parse(&text),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok((Self::default(), Vec::new())),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Parses configuration text.
+    pub fn parse(text: &str) -> Result<(Self, Vec<String>), ConfigError> {
+        let mut warnings = Vec::new();
+        let config = serde_ignored::deserialize(toml::Deserializer::new(text), |path| {
+            warnings.push(format!("unknown key `{}` ignored", path))
+        })
+        .map_err(|e| ConfigError::Parse {
+            line: e
+                .span()
+                .map_or(1, |span| text[..span.start].matches('\n').count() + 1),
+            message: e.message().to_string(),
+        })?;
+        Ok((config, warnings))
+    }
+}
+
+/// `test_command`: a command line, which must not be blank.
+fn non_empty_command<'de, D: Deserializer<'de>>(de: D) -> Result<Option<String>, D::Error> {
+    let command = String::deserialize(de)?;
+    if command.trim().is_empty() {
+        return Err(serde::de::Error::custom(
+            "`test_command` must be a non-empty string",
+        ));
+    }
+    Ok(Some(command))
+}
+
+/// `test_env`: `KEY=VALUE` entries.
+fn key_value_pairs<'de, D: Deserializer<'de>>(de: D) -> Result<Vec<String>, D::Error> {
+    let vars = Vec::<String>::deserialize(de)?;
+    if let Some(bad) = vars.iter().find(|v| !v.contains('=')) {
+        return Err(serde::de::Error::custom(format!(
+            "`test_env` entry `{}` is not KEY=VALUE",
+            bad
+        )));
+    }
+    Ok(vars)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_all_keys() {
+        let text = r#"
+# project settings
+protected_dirs = ["tests", "qa"]
+low_confidence_dirs = ["tools"]
+extra_entry_points = ['cli.py']
+plugin_dirs = [
+    "jobs",   # discovered by the scheduler
+    "hooks",
+]
+grep_extensions = ["html"]
+grep_min_name_length = 3
+library_mode = true
+exclude = ["build/", "gen#erated/**"]
+protect_symbols = ["billing.legacy.migrate_v1"]
+source_roots = ["src"]
+type_checking_keeps_alive = false
+test_command = "python -m unittest"
+test_timeout = 1_200
+test_env = ["DJANGO_SETTINGS_MODULE=app.test"]
+"#;
+        let (config, warnings) = JanitorConfig::parse(text).unwrap();
+        assert!(warnings.is_empty());
+        assert_eq!(
+            config.protected_dirs,
+            Some(vec!["tests".to_string(), "qa".to_string()])
+        );
+        assert_eq!(config.low_confidence_dirs, Some(vec!["tools".to_string()]));
+        assert_eq!(config.extra_entry_points, vec!["cli.py"]);
+        assert_eq!(
+            config.plugin_dirs,
+            Some(vec!["jobs".to_string(), "hooks".to_string()])
+        );
+        assert_eq!(config.grep_extensions, Some(vec!["html".to_string()]));
+        assert_eq!(config.grep_min_name_length, Some(3));
+        assert_eq!(config.library_mode, Some(true));
+        assert_eq!(config.exclude, vec!["build/", "gen#erated/**"]);
+        assert_eq!(config.protect_symbols, vec!["billing.legacy.migrate_v1"]);
+        assert_eq!(config.source_roots, Some(vec!["src".to_string()]));
+        assert_eq!(config.type_checking_keeps_alive, Some(false));
+        assert_eq!(config.test_command.as_deref(), Some("python -m unittest"));
+        assert_eq!(config.test_timeout, Some(1200));
+        assert_eq!(config.test_env, vec!["DJANGO_SETTINGS_MODULE=app.test"]);
+    }
+
+    #[test]
+    fn test_unknown_keys_and_tables_warn() {
+        let text = "colour = \"blue\"\n[tool.other]\nexclude = [\"x\"]\n";
+        let (config, warnings) = JanitorConfig::parse(text).unwrap();
+        assert_eq!(config, JanitorConfig::default());
+        assert_eq!(
+            warnings,
+            vec!["unknown key `colour` ignored", "unknown key `tool` ignored"]
+        );
+    }
+
+    #[test]
+    fn test_ci_table() {
+        let text = "library_mode = true\n\n[ci]\nmax_dead = 0\nmax_dead_bytes = 20_000\nfail_on_dead = true\ncount_orphans = false\nflaky = 1\n";
+        let (config, warnings) = JanitorConfig::parse(text).unwrap();
+        assert_eq!(config.library_mode, Some(true));
+        assert_eq!(
+            config.ci,
+            CiConfig {
+                max_dead: Some(0),
+                max_dead_bytes: Some(20_000),
+                fail_on_dead: Some(true),
+                count_orphans: Some(false),
+            }
+        );
+        assert_eq!(warnings, vec!["unknown key `ci.flaky` ignored"]);
+        assert!(JanitorConfig::parse("[ci]\nmax_dead = \"5\"\n").is_err());
+        assert!(JanitorConfig::parse("[ci]\nfail_on_dead = 1\n").is_err());
+    }
+
+    #[test]
+    fn test_workspace_table() {
+        let text =
+            "[workspace]\nroots = [\n  \"services/a\",\n  \"libs/common\",\n]\nmembers = []\n";
+        let (config, warnings) = JanitorConfig::parse(text).unwrap();
+        assert_eq!(config.workspace.roots, vec!["services/a", "libs/common"]);
+        assert_eq!(warnings, vec!["unknown key `workspace.members` ignored"]);
+        assert!(JanitorConfig::parse("[workspace]\nroots = \"services/a\"\n").is_err());
+    }
+
+    #[test]
+    fn test_di_table() {
+        let text = "[di]\nregistration_calls = [\"provide\"]\ndict_registries = false\n";
+        let (config, warnings) = JanitorConfig::parse(text).unwrap();
+        assert!(warnings.is_empty());
+        assert_eq!(
+            config.di,
+            DiConfig {
+                registration_calls: Some(vec!["provide".to_string()]),
+                dict_registries: Some(false),
+            }
+        );
+        assert!(JanitorConfig::parse("[di]\nregistration_calls = \"provide\"\n").is_err());
+    }
+
+    #[test]
+    fn test_type_errors_are_fatal() {
+        let err = JanitorConfig::parse("library_mode = \"yes\"\n").unwrap_err();
+        assert!(matches!(err, ConfigError::Parse { line: 1, .. }));
+        assert!(JanitorConfig::parse("exclude = \"build\"\n").is_err());
+        assert!(JanitorConfig::parse("test_command = \"\"\n").is_err());
+        assert!(JanitorConfig::parse("test_timeout = \"60\"\n").is_err());
+        assert!(JanitorConfig::parse("test_env = [\"DEBUG\"]\n").is_err());
+        let err = JanitorConfig::parse("\ngrep_min_name_length = -1\n").unwrap_err();
+        assert!(matches!(err, ConfigError::Parse { line: 2, .. }), "{}", err);
+        assert!(JanitorConfig::parse("[ci]\nmax_dead = -5\n").is_err());
+    }
+
+    #[test]
+    fn test_missing_file_is_default() {
+        let tmp = std::env::temp_dir().join("test_config_missing");
+        std::fs::create_dir_all(&tmp).ok();
+        let (config, warnings) = JanitorConfig::load(&tmp).unwrap();
+        assert_eq!(config, JanitorConfig::default());
+        assert!(warnings.is_empty());
+        std::fs::remove_dir_all(tmp).ok();
+    }
+}
@@ -0,0 +1,208 @@
+//! # Diagnostics: What a Run Skipped or Degraded
+//!
+//! The library never prints. A file it could not read, a parse that failed,
+//! a log record it could not decode — each becomes a [`Diagnostic`] in a
+//! [`Diagnostics`] collector that travels with the result, so the CLI can
+//! list them, `--format json` can carry them and CI can fail on them.
+//!
+//! Codes are stable kebab-case strings (see [`codes`]); messages are for
+//! humans and may change.
+
+use std::fmt;
+
+/// How much a diagnostic undermines the result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Nothing was lost, but the input was unusual.
+    Info,
+    /// Part of a file or evidence source was not understood.
+    Warning,
+    /// A whole file or evidence source was left out.
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        })
+    }
+}
+
+/// Stable diagnostic codes.
+pub mod codes {
+    /// A file could not be opened or read; its symbols are missing.
+    pub const UNREADABLE_FILE: &str = "unreadable-file";
+    /// A source file is not valid UTF-8 and was read as Latin-1.
+    pub const NON_UTF8_SOURCE: &str = "non-utf8-source";
+    /// A source file contains a NUL byte and was skipped.
+    pub const NUL_BYTE: &str = "nul-byte";
+    /// The parser gave up on a file; its symbols are missing.
+    pub const PARSE_FAILURE: &str = "parse-failure";
+    /// A file parsed with errors; definitions and references inside the
+    /// broken region may be missing.
+    pub const SYNTAX_ERROR: &str = "syntax-error";
+    /// Records of a runtime log could not be decoded and were skipped.
+    pub const MALFORMED_LOG: &str = "malformed-log";
+    /// `.janitor.toml` or a rules file had a problem that was ignored.
+    pub const CONFIG: &str = "config";
+    /// An optional evidence source (pytest collection) was unavailable.
+    pub const EVIDENCE_UNAVAILABLE: &str = "evidence-unavailable";
+}
+
+/// One problem found during a run.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// One of [`codes`].
+    pub code: &'static str,
+    /// Normalized path of the file concerned, if any.
+    pub file: Option<String>,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn new(severity: Severity, code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            severity,
+            code,
+            file: None,
+            message: message.into(),
+        }
+    }
+
+    pub fn warning(code: &'static str, message: impl Into<String>) -> Self {
+        Self::new(Severity::Warning, code, message)
+    }
+
+    pub fn error(code: &'static str, message: impl Into<String>) -> Self {
+        Self::new(Severity::Error, code, message)
+    }
+
+    /// Sets the file the diagnostic is about.
+    pub fn at(mut self, file: impl Into<String>) -> Self {
+        self.file = Some(file.into());
+        self
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.file {
+            Some(file) => write!(f, "{}: {} [{}]", file, self.message, self.code),
+            None => write!(f, "{} [{}]", self.message, self.code),
+        }
+    }
+}
+
+/// Diagnostics collected during a run, in the order they were found.
+///
+/// Several passes may trip over the same file; only the first diagnostic
+/// per code and file is kept.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize)]
+#[serde(transparent)]
+pub struct Diagnostics(Vec<Diagnostic>);
+
+impl Diagnostics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records `diagnostic` unless one with the same code and file is already
+    /// recorded (file-less diagnostics are compared by message).
+    pub fn push(&mut self, diagnostic: Diagnostic) {
+        let seen = self.0.iter().any(|d| {
+            d.code == diagnostic.code
+                && d.file == diagnostic.file
+                && (d.file.is_some() || d.message == diagnostic.message)
+        });
+        if !seen {
+            self.0.push(diagnostic);
+        }
+    }
+
+    /// Drops the diagnostics `keep` rejects.
+    pub fn retain(&mut self, keep: impl FnMut(&Diagnostic) -> bool) {
+        self.0.retain(keep);
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Diagnostic> {
+        self.0.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn into_vec(self) -> Vec<Diagnostic> {
+        self.0
+    }
+}
+
+impl Extend<Diagnostic> for Diagnostics {
+    fn extend<I: IntoIterator<Item = Diagnostic>>(&mut self, iter: I) {
+        for diagnostic in iter {
+            self.push(diagnostic);
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a Diagnostics {
+    type Item = &'a Diagnostic;
+    type IntoIter = std::slice::Iter<'a, Diagnostic>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+/// `true` when any of `diagnostics` is a warning or worse.
+pub fn has_warnings<'a>(diagnostics: impl IntoIterator<Item = &'a Diagnostic>) -> bool {
+    diagnostics
+        .into_iter()
+        .any(|d| d.severity >= Severity::Warning)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_keeps_first_per_code_and_file() {
+        let mut diagnostics = Diagnostics::new();
+        diagnostics.push(Diagnostic::error(codes::UNREADABLE_FILE, "denied").at("a.py"));
+        diagnostics.push(Diagnostic::error(codes::UNREADABLE_FILE, "again").at("a.py"));
+        diagnostics.push(Diagnostic::warning(codes::SYNTAX_ERROR, "line 3").at("a.py"));
+        diagnostics.push(Diagnostic::error(codes::UNREADABLE_FILE, "denied").at("b.py"));
+        assert_eq!(diagnostics.len(), 3);
+        assert_eq!(diagnostics.iter().next().unwrap().message, "denied");
+    }
+
+    #[test]
+    fn test_severity_order_and_json() {
+        assert!(!has_warnings(&[Diagnostic::new(
+            Severity::Info,
+            codes::NON_UTF8_SOURCE,
+            "read as Latin-1"
+        )]));
+        let d = Diagnostic::warning(codes::SYNTAX_ERROR, "syntax error at line 3").at("a.py");
+        assert!(has_warnings([&d]));
+        assert_eq!(
+            serde_json::to_value(&d).unwrap(),
+            serde_json::json!({
+                "severity": "warning",
+                "code": "syntax-error",
+                "file": "a.py",
+                "message": "syntax error at line 3",
+            })
+        );
+        assert_eq!(d.to_string(), "a.py: syntax error at line 3 [syntax-error]");
+    }
+}
@@ -1,4 +1,10 @@
+pub mod config;
+pub mod diagnostics;
+pub mod lock;
 pub mod registry;
+pub mod source;
+pub mod text;
+pub mod walk;
 pub mod wisdom;
 
 use rkyv::bytecheck::CheckBytes;
@@ -10,7 +16,20 @@ use uuid::Uuid;
 ///
 /// Stored in `SymbolEntry::protected_by` in the disk-backed registry so that
 /// downstream tools (dashboard, oracle) can reason about protection rationale.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Archive, Deserialize, Serialize, CheckBytes)]
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    Archive,
+    Deserialize,
+    Serialize,
+    CheckBytes,
+    serde::Serialize,
+    serde::Deserialize,
+)]
 #[rkyv(derive(Debug))]
 #[repr(u8)]
 pub enum Protection {
@@ -48,6 +67,69 @@ pub enum Protection {
     GrepShield = 15,
     /// Post-pipeline: symbol is directly referenced by a test node ID.
     TestReference = 16,
+    /// Stage 2: method overrides a method defined on one of its class's ancestors
+    /// (abstract interface / protocol implementation called through the base type).
+    InterfaceOverride = 17,
+    /// Stage 2: Django convention — view routed from `urls.py`, `@receiver` signal
+    /// handler, admin registration, or model/form `Meta` inner class.
+    DjangoFramework = 18,
+    /// Stage 2: implementation registered through a dispatch decorator
+    /// (`@fn.register`, `@fn.register(int)` — e.g. `functools.singledispatch`).
+    DispatchRegistration = 19,
+    /// Stage 0: listed in `protect_symbols` of the project's `.janitor.toml`.
+    UserConfig = 20,
+    /// Stage 6: qualified name observed in runtime logs passed via `--logs`.
+    RuntimeLiveness = 21,
+    /// Stage 1: named in a string annotation or forward reference
+    /// (`def f(c: "Config")`) that no import or definition resolves.
+    TypeAnnotation = 22,
+}
+
+/// The evidence behind a [`Protection`]: which stage assigned it and why.
+///
+/// Carried next to `protected_by` on entities and in `SymbolEntry` so `scan
+/// --verbose` and the dashboard can show, e.g., which file tripped the grep
+/// shield or which caller kept a symbol referenced.
+#[derive(
+    Debug, Clone, PartialEq, Eq, Hash, Archive, Deserialize, Serialize, CheckBytes, serde::Serialize,
+)]
+#[rkyv(derive(Debug))]
+#[repr(C)]
+pub struct ProtectionDetail {
+    /// Pipeline stage that decided (0-6; parser heuristics count as 0, the
+    /// Stage 5.5 test fingerprint as 5).
+    pub stage: u8,
+    /// Human-readable rule or evidence, e.g. ``"wisdom rule: CLI decorator `@click.command`"``.
+    pub reason: String,
+    /// Where the evidence lives (`file` or `file:line`), when there is one.
+    pub location: Option<String>,
+}
+
+impl ProtectionDetail {
+    /// A detail without a source location.
+    pub fn new(stage: u8, reason: impl Into<String>) -> Self {
+        Self {
+            stage,
+            reason: reason.into(),
+            location: None,
+        }
+    }
+
+    /// Attaches the location of the evidence.
+    pub fn at(mut self, location: impl Into<String>) -> Self {
+        self.location = Some(location.into());
+        self
+    }
+}
+
+impl std::fmt::Display for ProtectionDetail {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "stage {}: {}", self.stage, self.reason)?;
+        if let Some(location) = &self.location {
+            write!(f, " ({})", location)?;
+        }
+        Ok(())
+    }
 }
 
 // THE ATOM: CLR FACT
@@ -0,0 +1,260 @@
+//! # Project Lock: One Writer per `.janitor/`
+//!
+//! Two janitor processes on one project must not interleave their writes to
+//! `.janitor/`: a `clean` deleting by a registry a concurrent `scan` is
+//! rewriting, or both backing files up into `ghost/`. [`ProjectLock`] is an
+//! advisory lock on `.janitor/lock`. Commands that read or regenerate state
+//! take it [`Shared`](LockMode::Shared); commands that edit the project take it
+//! [`Exclusive`](LockMode::Exclusive).
+//!
+//! The operating system drops the lock when its holder exits, crashed or not,
+//! so a lock file left behind never blocks anyone. The file only records the
+//! pid, start time and command of the last holder, for the "held by" message;
+//! a recorded pid that is no longer running is not blamed.
+
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::{self, Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// How a command uses `.janitor/`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockMode {
+    /// Reads, or rewrites files without tearing them for readers: the
+    /// registry is renamed into place whole, and cache entries are
+    /// validated when read.
+    Shared,
+    /// Edits the project, its shadow tree or its backups.
+    Exclusive,
+}
+
+/// Errors from taking the project lock.
+#[derive(Debug, thiserror::Error)]
+pub enum LockError {
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    #[error("another janitor process ({holder}) holds {}; pass --wait to block until it finishes", path.display())]
+    Held { path: PathBuf, holder: String },
+}
+
+/// A held lock on `.janitor/lock`, released on drop.
+#[derive(Debug)]
+pub struct ProjectLock {
+    _file: File,
+}
+
+impl ProjectLock {
+    /// Name of the lock file inside `.janitor/`.
+    pub const FILE_NAME: &'static str = "lock";
+
+    /// Locks `{janitor_dir}/lock` in `mode` on behalf of `command`, creating
+    /// `janitor_dir` (but not its parent) if needed. With `wait`, blocks until
+    /// the lock is free; otherwise fails with [`LockError::Held`].
+    pub fn acquire(
+        janitor_dir: &Path,
+        mode: LockMode,
+        command: &str,
+        wait: bool,
+    ) -> Result<Self, LockError> {
+        match std::fs::create_dir(janitor_dir) {
+            Err(e) if e.kind() != io::ErrorKind::AlreadyExists => return Err(e.into()),
+            _ => {}
+        }
+        let path = janitor_dir.join(Self::FILE_NAME);
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)?;
+        let attempt = match (mode, wait) {
+            (LockMode::Shared, true) => Ok(file.lock_shared()?),
+            (LockMode::Exclusive, true) => Ok(file.lock()?),
+            (LockMode::Shared, false) => file.try_lock_shared(),
+            (LockMode::Exclusive, false) => file.try_lock(),
+        };
+        match attempt {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                let holder = describe(Holder::parse(&read_record(&mut file)?));
+                return Err(LockError::Held { path, holder });
+            }
+            Err(TryLockError::Error(e)) => return Err(e.into()),
+        }
+        let record = Holder {
+            pid: std::process::id(),
+            started: now_secs(),
+            command: command.to_string(),
+        };
+        file.set_len(0)?;
+        file.rewind()?;
+        file.write_all(record.to_string().as_bytes())?;
+        Ok(Self { _file: file })
+    }
+}
+
+fn read_record(file: &mut File) -> io::Result<String> {
+    let mut record = String::new();
+    file.rewind()?;
+    file.read_to_string(&mut record)?;
+    Ok(record)
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// The last process recorded in the lock file.
+#[derive(Debug, PartialEq, Eq)]
+struct Holder {
+    pid: u32,
+    /// Seconds since the Unix epoch.
+    started: u64,
+    command: String,
+}
+
+impl Holder {
+    /// Parses `pid=… started=… command=…`; anything else yields `None`.
+    fn parse(record: &str) -> Option<Self> {
+        let mut fields = record.trim().splitn(3, ' ');
+        let mut field = |key: &str| fields.next()?.strip_prefix(key).map(str::to_string);
+        Some(Self {
+            pid: field("pid=")?.parse().ok()?,
+            started: field("started=")?.parse().ok()?,
+            command: field("command=")?,
+        })
+    }
+}
+
+impl std::fmt::Display for Holder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "pid={} started={} command={}",
+            self.pid, self.started, self.command
+        )
+    }
+}
+
+/// `pid 123, janitor clean, started 42s ago`, or `pid unknown` when the record
+/// is missing or its process has exited (another shared holder outlived it).
+fn describe(holder: Option<Holder>) -> String {
+    match holder {
+        Some(holder) if pid_alive(holder.pid) => format!(
+            "pid {}, janitor {}, started {} ago",
+            holder.pid,
+            holder.command,
+            age(now_secs().saturating_sub(holder.started))
+        ),
+        _ => "pid unknown".to_string(),
+    }
+}
+
+fn age(secs: u64) -> String {
+    match secs {
+        0..60 => format!("{}s", secs),
+        60..3600 => format!("{}m", secs / 60),
+        _ => format!("{}h", secs / 3600),
+    }
+}
+
+/// `true` unless `pid` is known not to be running. Only Linux can tell
+/// (through `/proc`); elsewhere every pid counts as alive.
+fn pid_alive(pid: u32) -> bool {
+    if cfg!(target_os = "linux") {
+        Path::new("/proc").join(pid.to_string()).exists()
+    } else {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::time::{Duration, Instant};
+
+    fn janitor_dir(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(name);
+        std::fs::remove_dir_all(&root).ok();
+        std::fs::create_dir_all(&root).unwrap();
+        root.join(".janitor")
+    }
+
+    #[test]
+    fn test_shared_holders_block_exclusive() {
+        let dir = janitor_dir("test_lock_shared");
+        let a = ProjectLock::acquire(&dir, LockMode::Shared, "scan", false).unwrap();
+        let b = ProjectLock::acquire(&dir, LockMode::Shared, "dashboard", false).unwrap();
+
+        let err = ProjectLock::acquire(&dir, LockMode::Exclusive, "clean", false).unwrap_err();
+        let message = err.to_string();
+        let pid = format!(
+            "pid {}, janitor dashboard, started 0s ago",
+            std::process::id()
+        );
+        assert!(message.contains(&pid), "{}", message);
+        assert!(message.ends_with("pass --wait to block until it finishes"));
+
+        drop((a, b));
+        let held = ProjectLock::acquire(&dir, LockMode::Exclusive, "clean", false).unwrap();
+        assert!(ProjectLock::acquire(&dir, LockMode::Shared, "scan", false).is_err());
+        drop(held);
+        std::fs::remove_dir_all(dir.parent().unwrap()).ok();
+    }
+
+    #[test]
+    fn test_wait_blocks_until_released() {
+        let dir = janitor_dir("test_lock_wait");
+        let (locked, ready) = mpsc::channel();
+        let holder = {
+            let dir = dir.clone();
+            std::thread::spawn(move || {
+                let lock = ProjectLock::acquire(&dir, LockMode::Exclusive, "clean", false);
+                locked.send(()).unwrap();
+                std::thread::sleep(Duration::from_millis(300));
+                drop(lock);
+            })
+        };
+        ready.recv().unwrap();
+
+        let start = Instant::now();
+        assert!(ProjectLock::acquire(&dir, LockMode::Shared, "scan", false).is_err());
+        ProjectLock::acquire(&dir, LockMode::Shared, "scan", true).unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(200));
+        holder.join().unwrap();
+        std::fs::remove_dir_all(dir.parent().unwrap()).ok();
+    }
+
+    #[test]
+    fn test_record_of_crashed_holder_is_ignored() {
+        let dir = janitor_dir("test_lock_stale");
+        std::fs::create_dir_all(&dir).unwrap();
+        let dead = u32::MAX - 1;
+        let record = format!("pid={} started=0 command=clean\n", dead);
+        std::fs::write(dir.join(ProjectLock::FILE_NAME), &record).unwrap();
+
+        // The crashed holder's lock died with it; only its record is left.
+        let lock = ProjectLock::acquire(&dir, LockMode::Exclusive, "clean", false).unwrap();
+        let rewritten = std::fs::read_to_string(dir.join(ProjectLock::FILE_NAME)).unwrap();
+        let holder = Holder::parse(&rewritten).unwrap();
+        assert_eq!(holder.pid, std::process::id());
+        assert_eq!(holder.command, "clean");
+        drop(lock);
+
+        assert_eq!(describe(Holder::parse(&record)), "pid unknown");
+        std::fs::remove_dir_all(dir.parent().unwrap()).ok();
+    }
+
+    #[test]
+    fn test_missing_project_is_not_created() {
+        let root = std::env::temp_dir().join("test_lock_no_project");
+        std::fs::remove_dir_all(&root).ok();
+        let err = ProjectLock::acquire(&root.join(".janitor"), LockMode::Shared, "scan", false);
+        assert!(matches!(err, Err(LockError::Io(_))));
+        assert!(!root.exists());
+    }
+}
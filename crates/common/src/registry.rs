@@ -2,15 +2,32 @@
 //!
 //! Stores cross-file symbol references via `rkyv` zero-copy serialization.
 //! Enables fast mmap-based lookups for reference graph construction.
+//!
+//! A registry file is a 32-byte header followed by the archive:
+//!
+//! | bytes  | content                                      |
+//! |--------|----------------------------------------------|
+//! | 0..8   | magic `JNTRSYMS`                             |
+//! | 8..12  | [`REGISTRY_FORMAT_VERSION`], little-endian   |
+//! | 16..24 | archive length in bytes, little-endian `u64` |
+//!
+//! The remaining bytes are zero. A file from another format version is
+//! rejected with [`RegistryError::VersionMismatch`] rather than misread;
+//! [`migrate`] upgrades an older one in place when [`MIGRATIONS`] reach the
+//! current version.
 
-use crate::Protection;
+use crate::{Protection, ProtectionDetail};
 use memmap2::Mmap;
 use rkyv::bytecheck::CheckBytes;
-use rkyv::{Archive, Deserialize, Serialize};
+use rkyv::rancor::Fallible;
+use rkyv::ser::writer::IoWriter;
+use rkyv::ser::{Allocator, Writer};
+use rkyv::vec::{ArchivedVec, VecResolver};
+use rkyv::{Archive, Deserialize, Place, Serialize};
+use std::collections::HashMap;
 use std::fs::File;
-use std::hash::{Hash, Hasher};
-use std::io::Write;
-use std::path::Path;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
 
 /// Errors from registry operations.
 #[derive(Debug, thiserror::Error)]
@@ -19,9 +36,135 @@ pub enum RegistryError {
     IoError(#[from] std::io::Error),
     #[error("Deserialization error: {0}")]
     DeserializeError(String),
+    /// The file comes from another release; `found` is `0` for a file that
+    /// predates format versioning.
+    #[error(
+        "registry format {found} is not supported (this janitor reads format {expected}); re-run `janitor scan` to rebuild it"
+    )]
+    VersionMismatch { found: u32, expected: u32 },
+    /// The archive is not as long as the header says.
+    #[error(
+        "registry archive is {found} bytes but its header says {expected}; the file is damaged"
+    )]
+    Truncated { found: u64, expected: u64 },
+}
+
+/// Version of the registry file layout, including how [`symbol_hash`] derives
+/// IDs. Bump it whenever either changes, and add a [`Migration`] from the old
+/// version when the old archive can be converted.
+pub const REGISTRY_FORMAT_VERSION: u32 = 3;
+
+/// Magic bytes opening every registry file.
+const MAGIC: &[u8; 8] = b"JNTRSYMS";
+
+/// Header length; a multiple of the archive's alignment so the mmapped archive
+/// stays aligned.
+const HEADER_LEN: usize = 32;
+
+/// Offset of the archive length in the header.
+const LENGTH_OFFSET: usize = 16;
+
+/// The header of a format-`version` file whose archive is `len` bytes.
+fn header_for(version: u32, len: u64) -> [u8; HEADER_LEN] {
+    let mut header = [0u8; HEADER_LEN];
+    header[..8].copy_from_slice(MAGIC);
+    header[8..12].copy_from_slice(&version.to_le_bytes());
+    header[LENGTH_OFFSET..LENGTH_OFFSET + 8].copy_from_slice(&len.to_le_bytes());
+    header
+}
+
+/// The header written in front of an archive of `len` bytes.
+fn header(len: u64) -> [u8; HEADER_LEN] {
+    header_for(REGISTRY_FORMAT_VERSION, len)
+}
+
+/// Format version of registry file `bytes`; `0` when it has no header.
+fn format_version(bytes: &[u8]) -> u32 {
+    match bytes.get(..12) {
+        Some(head) if &head[..8] == MAGIC => {
+            u32::from_le_bytes(head[8..12].try_into().expect("4-byte slice"))
+        }
+        _ => 0,
+    }
+}
+
+/// Checks the header of registry file `bytes`, returning the archive after it.
+fn archive_bytes(bytes: &[u8]) -> Result<&[u8], RegistryError> {
+    let version = format_version(bytes);
+    if version != REGISTRY_FORMAT_VERSION || bytes.len() < HEADER_LEN {
+        return Err(RegistryError::VersionMismatch {
+            found: version,
+            expected: REGISTRY_FORMAT_VERSION,
+        });
+    }
+    let (header, archive) = bytes.split_at(HEADER_LEN);
+    let expected = u64::from_le_bytes(
+        header[LENGTH_OFFSET..LENGTH_OFFSET + 8]
+            .try_into()
+            .expect("8-byte slice"),
+    );
+    let found = archive.len() as u64;
+    if found != expected {
+        return Err(RegistryError::Truncated { found, expected });
+    }
+    Ok(archive)
+}
+
+/// Upgrades registry files from one format version to the next, so a release
+/// can keep a registry its predecessor wrote instead of asking for a re-scan.
+/// Registered in [`MIGRATIONS`].
+pub trait Migration: Sync {
+    /// The format version this migration reads.
+    fn source_version(&self) -> u32;
+
+    /// Converts the whole registry file `file`, of [`Migration::source_version`],
+    /// into a whole file of the next version, header included.
+    fn migrate(&self, file: &[u8]) -> Result<Vec<u8>, RegistryError>;
+}
+
+/// Migrations [`migrate`] applies to an older registry file. None yet: every
+/// earlier format predates a release.
+pub static MIGRATIONS: &[&dyn Migration] = &[];
+
+/// Upgrades the registry file at `path` to [`REGISTRY_FORMAT_VERSION`] through
+/// `migrations`, replacing it atomically. Returns `false` when the file is
+/// already current, and [`RegistryError::VersionMismatch`] when no chain of
+/// migrations leads from its version to the current one. Readers may map the
+/// file, so callers hold the project's exclusive lock.
+pub fn migrate(path: &Path, migrations: &[&dyn Migration]) -> Result<bool, RegistryError> {
+    let mut file = std::fs::read(path)?;
+    let found = format_version(&file);
+    if found == REGISTRY_FORMAT_VERSION {
+        return Ok(false);
+    }
+    let mut version = found;
+    while version < REGISTRY_FORMAT_VERSION {
+        let step = migrations
+            .iter()
+            .find(|m| m.source_version() == version)
+            .ok_or(RegistryError::VersionMismatch {
+                found,
+                expected: REGISTRY_FORMAT_VERSION,
+            })?;
+        file = step.migrate(&file)?;
+        version += 1;
+    }
+    if version != REGISTRY_FORMAT_VERSION {
+        return Err(RegistryError::VersionMismatch {
+            found,
+            expected: REGISTRY_FORMAT_VERSION,
+        });
+    }
+    archive_bytes(&file)?;
+    let tmp = path.with_extension("rkyv.migrating");
+    std::fs::write(&tmp, &file)?;
+    std::fs::rename(&tmp, path)?;
+    Ok(true)
 }
 
-/// SipHash of symbol ID strings. Deterministic within a Rust version.
+/// Stable 64-bit hash of symbol ID strings: the first 8 bytes (LE) of their
+/// BLAKE3 digest. Independent of the Rust version and platform, so registries
+/// written on different machines compare by ID.
 ///
 /// # Examples
 /// ```
@@ -31,19 +174,21 @@ pub enum RegistryError {
 /// assert_eq!(h1, h2);
 /// ```
 pub fn symbol_hash(s: &str) -> u64 {
-    let mut hasher = std::collections::hash_map::DefaultHasher::new();
-    s.hash(&mut hasher);
-    hasher.finish()
+    let digest = blake3::hash(s.as_bytes());
+    u64::from_le_bytes(digest.as_bytes()[..8].try_into().expect("blake3 ≥ 8 bytes"))
 }
 
 /// Single symbol entry in the registry.
-#[derive(Debug, Clone, Archive, Deserialize, Serialize, CheckBytes)]
+#[derive(Debug, Clone, Archive, Deserialize, Serialize, CheckBytes, serde::Serialize)]
 #[rkyv(derive(Debug))]
 #[repr(C)]
 pub struct SymbolEntry {
     pub id: u64,
     pub name: String,
     pub qualified_name: String,
+    /// Dotted module of `file_path` relative to its source root
+    /// (`app.api.handlers`); empty when unknown.
+    pub module_path: String,
     pub file_path: String,
     pub entity_type: u8,
     pub start_line: u32,
@@ -54,6 +199,20 @@ pub struct SymbolEntry {
     pub structural_hash: u64,
     /// Protection reason (if entity survived the pipeline). `None` = candidate for deletion.
     pub protected_by: Option<Protection>,
+    /// Stage and evidence behind `protected_by`.
+    pub protection_detail: Option<ProtectionDetail>,
+}
+
+impl SymbolEntry {
+    /// `module_path.qualified_name`, or just the qualified name when the
+    /// module is unknown.
+    pub fn fully_qualified_name(&self) -> String {
+        if self.module_path.is_empty() {
+            self.qualified_name.clone()
+        } else {
+            format!("{}.{}", self.module_path, self.qualified_name)
+        }
+    }
 }
 
 /// In-memory symbol registry, serializable to disk.
@@ -87,26 +246,136 @@ impl SymbolRegistry {
         self.entries.is_empty()
     }
 
-    /// Sorts entries by ID and serializes the registry to bytes using `rkyv`.
+    /// Sorts entries by ID and serializes the registry to bytes using `rkyv`,
+    /// header included (the contents of a [`SymbolRegistry::save`] file).
     pub fn to_bytes(&mut self) -> Result<Vec<u8>, RegistryError> {
         self.entries.sort_by_key(|e| e.id);
         let aligned = rkyv::to_bytes::<rkyv::rancor::Error>(self)
             .map_err(|e| RegistryError::DeserializeError(e.to_string()))?;
-        Ok(aligned.to_vec())
+        let mut bytes = header(aligned.len() as u64).to_vec();
+        bytes.extend_from_slice(&aligned);
+        Ok(bytes)
+    }
+
+    /// Reads a registry file written by [`SymbolRegistry::save`] into memory.
+    pub fn load(path: &Path) -> Result<Self, RegistryError> {
+        let mapped = MappedRegistry::open(path)?;
+        rkyv::deserialize::<_, rkyv::rancor::Error>(mapped.archived())
+            .map_err(|e| RegistryError::DeserializeError(e.to_string()))
     }
 
     /// Saves the registry to a file (sorts by ID before writing).
+    ///
+    /// The archive is streamed to a temporary file that then replaces `path`,
+    /// so no serialized copy is held in memory and a reader that has the old
+    /// file mapped keeps reading it intact.
     pub fn save(&mut self, path: &Path) -> Result<(), RegistryError> {
-        if let Some(parent) = path.parent() {
-            std::fs::create_dir_all(parent)?;
-        }
-        let bytes = self.to_bytes()?;
-        let mut file = File::create(path)?;
-        file.write_all(&bytes)?;
+        self.entries.sort_by_key(|e| e.id);
+        let mut pending = create(path)?;
+        rkyv::api::high::to_bytes_in::<_, rkyv::rancor::Error>(
+            self,
+            IoWriter::new(&mut pending.out),
+        )
+        .map_err(|e| RegistryError::DeserializeError(e.to_string()))?;
+        pending.finish()
+    }
+
+    /// Writes `entries` to `path` in the [`SymbolRegistry::save`] format
+    /// without collecting them into a registry first: each entry is built,
+    /// archived and dropped in turn. The iterator is walked twice, so it should
+    /// be cheap to clone (e.g. a mapped slice iterator).
+    ///
+    /// `entries` must come sorted by ID, as [`MappedRegistry::find_by_id`]
+    /// expects; an unsorted stream still opens, through the slower side table.
+    pub fn save_entries<I>(path: &Path, entries: I) -> Result<(), RegistryError>
+    where
+        I: ExactSizeIterator<Item = SymbolEntry> + Clone,
+    {
+        let mut pending = create(path)?;
+        let stream = EntryStream(entries);
+        rkyv::api::high::to_bytes_in::<_, rkyv::rancor::Error>(
+            &stream,
+            IoWriter::new(&mut pending.out),
+        )
+        .map_err(|e| RegistryError::DeserializeError(e.to_string()))?;
+        pending.finish()
+    }
+}
+
+/// A registry file being written next to its destination, under a name
+/// unique to this process. [`Pending::finish`] renames it over the old file;
+/// dropped unfinished, it is deleted.
+struct Pending {
+    out: BufWriter<File>,
+    tmp: PathBuf,
+    path: PathBuf,
+}
+
+/// Starts writing the registry file `path` (creating its parent directory):
+/// the header, with the archive length left for [`Pending::finish`].
+fn create(path: &Path) -> Result<Pending, RegistryError> {
+    let parent = path.parent().unwrap_or(Path::new("."));
+    std::fs::create_dir_all(parent)?;
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let tmp = parent.join(format!(".{}.{}.tmp", name, std::process::id()));
+    let mut out = BufWriter::new(File::create(&tmp)?);
+    out.write_all(&header(0))?;
+    Ok(Pending {
+        out,
+        tmp,
+        path: path.to_path_buf(),
+    })
+}
+
+impl Pending {
+    /// Records the length of the archive streamed after the header, then
+    /// replaces the destination with the complete file.
+    fn finish(mut self) -> Result<(), RegistryError> {
+        self.out.flush()?;
+        let file = self.out.get_mut();
+        let len = file.stream_position()? - HEADER_LEN as u64;
+        file.seek(SeekFrom::Start(LENGTH_OFFSET as u64))?;
+        file.write_all(&len.to_le_bytes())?;
+        file.sync_all()?;
+        std::fs::rename(&self.tmp, &self.path)?;
         Ok(())
     }
 }
 
+impl Drop for Pending {
+    fn drop(&mut self) {
+        // Gone already when `finish` renamed it.
+        let _ = std::fs::remove_file(&self.tmp);
+    }
+}
+
+/// Entries archived as a [`SymbolRegistry`] straight from an iterator.
+struct EntryStream<I>(I);
+
+impl<I: ExactSizeIterator<Item = SymbolEntry> + Clone> Archive for EntryStream<I> {
+    type Archived = ArchivedSymbolRegistry;
+    type Resolver = VecResolver;
+
+    fn resolve(&self, resolver: Self::Resolver, out: Place<Self::Archived>) {
+        rkyv::munge::munge!(let ArchivedSymbolRegistry { entries } = out);
+        ArchivedVec::resolve_from_len(self.0.len(), resolver, entries);
+    }
+}
+
+impl<I, S> Serialize<S> for EntryStream<I>
+where
+    I: ExactSizeIterator<Item = SymbolEntry> + Clone,
+    S: Fallible + Allocator + Writer + ?Sized,
+    SymbolEntry: Serialize<S>,
+{
+    fn serialize(&self, serializer: &mut S) -> Result<Self::Resolver, S::Error> {
+        ArchivedVec::<ArchivedSymbolEntry>::serialize_from_iter::<SymbolEntry, _, _>(
+            self.0.clone(),
+            serializer,
+        )
+    }
+}
+
 impl Default for SymbolRegistry {
     fn default() -> Self {
         Self::new()
@@ -114,8 +383,15 @@ impl Default for SymbolRegistry {
 }
 
 /// Memory-mapped read-only registry handle.
+///
+/// [`SymbolRegistry::save`] writes entries sorted by ID, which lets
+/// [`MappedRegistry::find_by_id`] binary-search. Archives produced any other
+/// way (e.g. plain `rkyv::to_bytes`) may be unsorted; `open` detects that and
+/// builds an ID → index table instead.
 pub struct MappedRegistry {
     _mmap: Mmap,
+    /// `None` when the archive is sorted by ID.
+    id_index: Option<HashMap<u64, usize>>,
 }
 
 impl MappedRegistry {
@@ -125,26 +401,72 @@ impl MappedRegistry {
         let mmap = unsafe { Mmap::map(&file)? };
 
         // Validate the archive
-        rkyv::access::<ArchivedSymbolRegistry, rkyv::rancor::Error>(&mmap)
-            .map_err(|e| RegistryError::DeserializeError(e.to_string()))?;
+        let archived =
+            rkyv::access::<ArchivedSymbolRegistry, rkyv::rancor::Error>(archive_bytes(&mmap)?)
+                .map_err(|e| RegistryError::DeserializeError(e.to_string()))?;
+
+        let ids = archived.entries.iter().map(|e| u64::from(e.id));
+        let sorted = ids.clone().zip(ids.skip(1)).all(|(a, b)| a <= b);
+        let id_index = (!sorted).then(|| {
+            let mut index = HashMap::with_capacity(archived.entries.len());
+            for (i, e) in archived.entries.iter().enumerate() {
+                // First occurrence wins, as with a binary search over duplicates.
+                index.entry(e.id.into()).or_insert(i);
+            }
+            index
+        });
 
-        Ok(Self { _mmap: mmap })
+        Ok(Self {
+            _mmap: mmap,
+            id_index,
+        })
     }
 
     /// Returns a reference to the archived registry (zero-copy).
     pub fn archived(&self) -> &ArchivedSymbolRegistry {
         // SAFETY: We validated the archive in `open()` via rkyv::access.
         // The mmap is held for the lifetime of self, so the reference is valid.
-        unsafe { rkyv::access_unchecked::<ArchivedSymbolRegistry>(&self._mmap[..]) }
+        unsafe { rkyv::access_unchecked::<ArchivedSymbolRegistry>(&self._mmap[HEADER_LEN..]) }
     }
 
-    /// Finds an entry by symbol ID (binary search; requires sorted registry).
+    /// Finds an entry by symbol ID (binary search, or the side table for
+    /// unsorted archives).
     pub fn find_by_id(&self, id: u64) -> Option<&ArchivedSymbolEntry> {
         let entries = &self.archived().entries;
-        let idx = entries.binary_search_by_key(&id, |e| e.id.into()).ok()?;
+        let idx = match &self.id_index {
+            Some(index) => *index.get(&id)?,
+            None => entries.binary_search_by_key(&id, |e| e.id.into()).ok()?,
+        };
         Some(&entries[idx])
     }
 
+    /// Whether the archive is sorted by ID (as written by [`SymbolRegistry::save`]).
+    pub fn is_sorted(&self) -> bool {
+        self.id_index.is_none()
+    }
+
+    /// Entries with the given qualified name, one per file that defines it (linear scan).
+    pub fn find_by_qualified_name<'a>(
+        &'a self,
+        qualified_name: &'a str,
+    ) -> impl Iterator<Item = &'a ArchivedSymbolEntry> + 'a {
+        self.archived()
+            .entries
+            .iter()
+            .filter(move |e| e.qualified_name.as_str() == qualified_name)
+    }
+
+    /// Entries defined in `file_path`, in archive order (linear scan).
+    pub fn iter_by_file<'a>(
+        &'a self,
+        file_path: &'a str,
+    ) -> impl Iterator<Item = &'a ArchivedSymbolEntry> + 'a {
+        self.archived()
+            .entries
+            .iter()
+            .filter(move |e| e.file_path.as_str() == file_path)
+    }
+
     /// Returns the number of symbols.
     pub fn len(&self) -> usize {
         self.archived().entries.len()
@@ -156,6 +478,128 @@ impl MappedRegistry {
     }
 }
 
+// ---------------------------------------------------------------------------
+// Diffing
+// ---------------------------------------------------------------------------
+
+/// Changes between two registry snapshots of the same project.
+///
+/// Categories overlap by design: a symbol added already dead is in both
+/// `added` and `newly_dead`; a dead symbol that was deleted is in both
+/// `removed` and `resurrected`.
+#[derive(Debug, Default, serde::Serialize)]
+pub struct RegistryDiff {
+    /// Dead in `new`, and either protected or absent in `old`.
+    pub newly_dead: Vec<SymbolEntry>,
+    /// Dead in `old`, and either protected or absent in `new`.
+    pub resurrected: Vec<SymbolEntry>,
+    /// In `new` with no counterpart in `old`.
+    pub added: Vec<SymbolEntry>,
+    /// In `old` with no counterpart in `new`.
+    pub removed: Vec<SymbolEntry>,
+    /// `(old, new)` pairs matched only by structural hash.
+    pub renamed: Vec<(SymbolEntry, SymbolEntry)>,
+    /// Dead bytes in `new` minus dead bytes in `old`.
+    pub dead_bytes_delta: i64,
+}
+
+/// Compares two registries.
+///
+/// Symbols are matched by `(file_path, qualified_name)`, which survives a
+/// change of hasher where [`SymbolEntry::id`] does not. Leftovers are then
+/// paired by non-zero `structural_hash` when exactly one old and one new
+/// symbol share it, catching renamed or moved functions with identical bodies.
+pub fn diff(old: &SymbolRegistry, new: &SymbolRegistry) -> RegistryDiff {
+    let key = |e: &SymbolEntry| (e.file_path.clone(), e.qualified_name.clone());
+    let old_by_key: HashMap<(String, String), usize> = old
+        .entries
+        .iter()
+        .enumerate()
+        .map(|(i, e)| (key(e), i))
+        .collect();
+
+    let mut pairs: Vec<(Option<usize>, Option<usize>)> = Vec::new();
+    let mut old_matched = vec![false; old.entries.len()];
+    let mut unmatched_new = Vec::new();
+    for (j, e) in new.entries.iter().enumerate() {
+        match old_by_key.get(&key(e)) {
+            Some(&i) if !old_matched[i] => {
+                old_matched[i] = true;
+                pairs.push((Some(i), Some(j)));
+            }
+            _ => unmatched_new.push(j),
+        }
+    }
+
+    // Structural fallback: only unambiguous hashes.
+    let mut old_by_hash: HashMap<u64, Vec<usize>> = HashMap::new();
+    for (i, e) in old.entries.iter().enumerate() {
+        if !old_matched[i] && e.structural_hash != 0 {
+            old_by_hash.entry(e.structural_hash).or_default().push(i);
+        }
+    }
+    let mut new_hash_count: HashMap<u64, usize> = HashMap::new();
+    for &j in &unmatched_new {
+        *new_hash_count
+            .entry(new.entries[j].structural_hash)
+            .or_default() += 1;
+    }
+    let mut diff = RegistryDiff::default();
+    for j in unmatched_new {
+        let e = &new.entries[j];
+        let renamed_from = match old_by_hash.get(&e.structural_hash) {
+            Some(olds) if olds.len() == 1 && new_hash_count[&e.structural_hash] == 1 => {
+                Some(olds[0])
+            }
+            _ => None,
+        };
+        if let Some(i) = renamed_from {
+            old_matched[i] = true;
+            diff.renamed.push((old.entries[i].clone(), e.clone()));
+        } else {
+            diff.added.push(e.clone());
+        }
+        pairs.push((renamed_from, Some(j)));
+    }
+    for (i, matched) in old_matched.iter().enumerate() {
+        if !matched {
+            diff.removed.push(old.entries[i].clone());
+            pairs.push((Some(i), None));
+        }
+    }
+
+    let is_dead = |e: Option<&SymbolEntry>| e.is_some_and(|e| e.protected_by.is_none());
+    for (i, j) in pairs {
+        let before = i.map(|i| &old.entries[i]);
+        let after = j.map(|j| &new.entries[j]);
+        match (is_dead(before), is_dead(after)) {
+            (false, true) => diff.newly_dead.extend(after.cloned()),
+            (true, false) => diff.resurrected.extend(before.cloned()),
+            _ => {}
+        }
+    }
+
+    let dead_bytes = |r: &SymbolRegistry| -> i64 {
+        r.entries
+            .iter()
+            .filter(|e| e.protected_by.is_none())
+            .map(|e| i64::from(e.end_byte.saturating_sub(e.start_byte)))
+            .sum()
+    };
+    diff.dead_bytes_delta = dead_bytes(new) - dead_bytes(old);
+
+    let order = |e: &SymbolEntry| (e.file_path.clone(), e.start_line);
+    for list in [
+        &mut diff.newly_dead,
+        &mut diff.resurrected,
+        &mut diff.added,
+        &mut diff.removed,
+    ] {
+        list.sort_by_key(order);
+    }
+    diff
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -167,6 +611,111 @@ mod tests {
         assert_eq!(h1, h2);
     }
 
+    #[test]
+    fn test_hash_is_pinned() {
+        // Registry IDs must not change across Rust versions or machines; a
+        // change here needs a `REGISTRY_FORMAT_VERSION` bump.
+        assert_eq!(symbol_hash("src/api.py::foo"), 1486879007955557318);
+        assert_eq!(symbol_hash("pkg/mod.py::Class.method"), 1537924669038683055);
+        // BLAKE3("") starts af 13 49 b9 f5 f9 a1 a6.
+        assert_eq!(symbol_hash(""), 0xa6a1_f9f5_b949_13af);
+    }
+
+    #[test]
+    fn test_rejects_other_format_versions() {
+        let tmp_path = std::env::temp_dir().join("test_registry_format.db");
+        let mut registry = SymbolRegistry::new();
+        registry.insert(entry(1, "pkg.a", "pkg/m.py"));
+
+        // Before versioning the file was the bare archive.
+        let archive = rkyv::to_bytes::<rkyv::rancor::Error>(&registry).unwrap();
+        std::fs::write(&tmp_path, &archive).unwrap();
+        let err = SymbolRegistry::load(&tmp_path).unwrap_err();
+        assert!(
+            matches!(err, RegistryError::VersionMismatch { found: 0, .. }),
+            "{err}"
+        );
+        assert!(err.to_string().contains("re-run `janitor scan`"), "{err}");
+
+        let mut bytes = registry.to_bytes().unwrap();
+        bytes[8..12].copy_from_slice(&(REGISTRY_FORMAT_VERSION + 1).to_le_bytes());
+        std::fs::write(&tmp_path, &bytes).unwrap();
+        assert!(matches!(
+            MappedRegistry::open(&tmp_path),
+            Err(RegistryError::VersionMismatch { found, expected })
+                if found == REGISTRY_FORMAT_VERSION + 1 && expected == REGISTRY_FORMAT_VERSION
+        ));
+        // A newer file is left as it was.
+        assert_eq!(std::fs::read(&tmp_path).unwrap(), bytes);
+
+        registry.save(&tmp_path).unwrap();
+        assert_eq!(
+            std::fs::read(&tmp_path).unwrap(),
+            registry.to_bytes().unwrap()
+        );
+        assert_eq!(SymbolRegistry::load(&tmp_path).unwrap().len(), 1);
+
+        let mut cut = registry.to_bytes().unwrap();
+        cut.truncate(cut.len() - 8);
+        std::fs::write(&tmp_path, &cut).unwrap();
+        assert!(matches!(
+            MappedRegistry::open(&tmp_path),
+            Err(RegistryError::Truncated { .. })
+        ));
+
+        std::fs::remove_file(tmp_path).ok();
+    }
+
+    /// A format that differed from the current one only in its version field.
+    struct Relabel;
+
+    impl Migration for Relabel {
+        fn source_version(&self) -> u32 {
+            REGISTRY_FORMAT_VERSION - 1
+        }
+
+        fn migrate(&self, file: &[u8]) -> Result<Vec<u8>, RegistryError> {
+            let mut upgraded = file.to_vec();
+            upgraded[8..12].copy_from_slice(&REGISTRY_FORMAT_VERSION.to_le_bytes());
+            Ok(upgraded)
+        }
+    }
+
+    #[test]
+    fn test_migrate_upgrades_older_files_in_place() {
+        let tmp_path = std::env::temp_dir().join("test_registry_migrate.db");
+        let mut registry = SymbolRegistry::new();
+        for (id, name) in [(30, "pkg.b"), (10, "pkg.a")] {
+            registry.insert(entry(id, name, "pkg/m.py"));
+        }
+        let current = registry.to_bytes().unwrap();
+        let mut old = current.clone();
+        old[8..12].copy_from_slice(&(REGISTRY_FORMAT_VERSION - 1).to_le_bytes());
+        std::fs::write(&tmp_path, &old).unwrap();
+
+        // Opening never rewrites the file.
+        assert!(matches!(
+            MappedRegistry::open(&tmp_path),
+            Err(RegistryError::VersionMismatch { found, .. }) if found == REGISTRY_FORMAT_VERSION - 1
+        ));
+        assert_eq!(std::fs::read(&tmp_path).unwrap(), old);
+
+        // Without a migration from its version, an old file stays rejected.
+        assert!(matches!(
+            migrate(&tmp_path, MIGRATIONS),
+            Err(RegistryError::VersionMismatch { .. })
+        ));
+        assert_eq!(std::fs::read(&tmp_path).unwrap(), old);
+
+        assert!(migrate(&tmp_path, &[&Relabel]).unwrap());
+        assert_eq!(std::fs::read(&tmp_path).unwrap(), current);
+        let mapped = MappedRegistry::open(&tmp_path).unwrap();
+        assert_eq!(mapped.find_by_id(30).unwrap().name.as_str(), "b");
+        assert!(!migrate(&tmp_path, &[&Relabel]).unwrap());
+
+        std::fs::remove_file(tmp_path).ok();
+    }
+
     #[test]
     fn test_hash_uniqueness() {
         let h1 = symbol_hash("src/api.py::foo");
@@ -181,6 +730,7 @@ mod tests {
             id: 12345,
             name: "foo".into(),
             qualified_name: "module.foo".into(),
+            module_path: String::new(),
             file_path: "src/test.py".into(),
             entity_type: 0,
             start_line: 10,
@@ -189,6 +739,7 @@ mod tests {
             end_byte: 200,
             structural_hash: 0,
             protected_by: None,
+            protection_detail: None,
         });
 
         let bytes = registry.to_bytes().unwrap();
@@ -205,6 +756,7 @@ mod tests {
             id: 999,
             name: "bar".into(),
             qualified_name: "pkg.bar".into(),
+            module_path: String::new(),
             file_path: "pkg/mod.py".into(),
             entity_type: 1,
             start_line: 5,
@@ -213,6 +765,7 @@ mod tests {
             end_byte: 150,
             structural_hash: 0,
             protected_by: Some(Protection::LifecycleMethod),
+            protection_detail: None,
         });
 
         let tmp_path = std::env::temp_dir().join("test_registry.db");
@@ -222,6 +775,54 @@ mod tests {
         assert_eq!(mapped.len(), 1);
         assert_eq!(mapped.archived().entries[0].id, 999);
 
+        // Saving again replaces the file; the open mapping keeps the old one.
+        registry.insert(entry(1, "pkg.a", "pkg/m.py"));
+        registry.save(&tmp_path).unwrap();
+        assert_eq!(mapped.len(), 1);
+        assert_eq!(mapped.archived().entries[0].id, 999);
+        assert_eq!(MappedRegistry::open(&tmp_path).unwrap().len(), 2);
+        let dir = tmp_path.parent().unwrap();
+        let leftovers = std::fs::read_dir(dir).unwrap().flatten().any(|e| {
+            e.file_name()
+                .to_string_lossy()
+                .starts_with(".test_registry.db.")
+        });
+        assert!(!leftovers);
+
+        std::fs::remove_file(tmp_path).ok();
+    }
+
+    #[test]
+    fn test_protection_detail_roundtrip() {
+        let mut registry = SymbolRegistry::new();
+        let mut e = entry(7, "pkg.render", "pkg/views.py");
+        e.protected_by = Some(Protection::GrepShield);
+        e.protection_detail = Some(
+            ProtectionDetail::new(5, "grep shield: name found in a non-Python file")
+                .at("templates/index.html"),
+        );
+        registry.insert(e.clone());
+        registry.insert(entry(8, "pkg.unused", "pkg/views.py"));
+
+        let tmp_path = std::env::temp_dir().join("test_registry_detail.db");
+        registry.save(&tmp_path).unwrap();
+        let loaded = SymbolRegistry::load(&tmp_path).unwrap();
+        assert_eq!(loaded.entries[0].protection_detail, e.protection_detail);
+        assert_eq!(loaded.entries[1].protection_detail, None);
+
+        let mapped = MappedRegistry::open(&tmp_path).unwrap();
+        let archived = mapped.find_by_id(7).unwrap();
+        let detail = archived.protection_detail.as_ref().unwrap();
+        assert_eq!(detail.stage, 5);
+        assert_eq!(
+            detail.location.as_ref().map(|l| l.as_str()),
+            Some("templates/index.html")
+        );
+        assert_eq!(
+            e.protection_detail.unwrap().to_string(),
+            "stage 5: grep shield: name found in a non-Python file (templates/index.html)"
+        );
+
         std::fs::remove_file(tmp_path).ok();
     }
 
@@ -239,6 +840,7 @@ mod tests {
             id: 100,
             name: "test".into(),
             qualified_name: "test".into(),
+            module_path: String::new(),
             file_path: "test.py".into(),
             entity_type: 0,
             start_line: 1,
@@ -247,6 +849,7 @@ mod tests {
             end_byte: 10,
             structural_hash: 0,
             protected_by: None,
+            protection_detail: None,
         });
 
         let tmp_path = std::env::temp_dir().join("test_find_by_id.db");
@@ -257,4 +860,174 @@ mod tests {
 
         std::fs::remove_file(tmp_path).ok();
     }
+
+    fn entry(id: u64, qualified_name: &str, file_path: &str) -> SymbolEntry {
+        SymbolEntry {
+            id,
+            name: qualified_name.rsplit('.').next().unwrap().into(),
+            qualified_name: qualified_name.into(),
+            module_path: String::new(),
+            file_path: file_path.into(),
+            entity_type: 0,
+            start_line: 1,
+            end_line: 2,
+            start_byte: 0,
+            end_byte: 10,
+            structural_hash: 0,
+            protected_by: None,
+            protection_detail: None,
+        }
+    }
+
+    #[test]
+    fn test_find_by_id_in_unsorted_archive() {
+        let mut registry = SymbolRegistry::new();
+        for (id, name) in [(50, "c"), (10, "a"), (90, "e"), (30, "b"), (70, "d")] {
+            registry.insert(entry(id, name, "m.py"));
+        }
+        // Plain rkyv serialization skips the sort done by `to_bytes`.
+        let archive = rkyv::to_bytes::<rkyv::rancor::Error>(&registry).unwrap();
+        let tmp_path = std::env::temp_dir().join("test_find_by_id_unsorted.db");
+        std::fs::write(
+            &tmp_path,
+            [&header(archive.len() as u64)[..], &archive].concat(),
+        )
+        .unwrap();
+
+        let mapped = MappedRegistry::open(&tmp_path).unwrap();
+        assert!(!mapped.is_sorted());
+        for (id, name) in [(10, "a"), (30, "b"), (50, "c"), (70, "d"), (90, "e")] {
+            assert_eq!(mapped.find_by_id(id).unwrap().name.as_str(), name);
+        }
+        assert!(mapped.find_by_id(40).is_none());
+
+        registry.save(&tmp_path).unwrap();
+        let mapped = MappedRegistry::open(&tmp_path).unwrap();
+        assert!(mapped.is_sorted());
+        assert_eq!(mapped.find_by_id(90).unwrap().name.as_str(), "e");
+
+        std::fs::remove_file(tmp_path).ok();
+    }
+
+    #[test]
+    fn test_save_entries_matches_save() {
+        let names = [(10, "pkg.a"), (30, "pkg.b"), (50, "pkg.c")];
+        let mut registry = SymbolRegistry::new();
+        for (id, name) in names {
+            registry.insert(entry(id, name, "pkg/m.py"));
+        }
+        registry.entries[1].protected_by = Some(Protection::Referenced);
+        registry.entries[1].protection_detail = Some(ProtectionDetail::new(1, "referenced"));
+
+        let saved = std::env::temp_dir().join("test_save_entries_saved.db");
+        let streamed = std::env::temp_dir().join("test_save_entries_streamed.db");
+        registry.save(&saved).unwrap();
+        SymbolRegistry::save_entries(&streamed, registry.entries.iter().cloned()).unwrap();
+        assert_eq!(
+            std::fs::read(&saved).unwrap(),
+            std::fs::read(&streamed).unwrap()
+        );
+
+        let mapped = MappedRegistry::open(&streamed).unwrap();
+        assert!(mapped.is_sorted());
+        assert_eq!(mapped.find_by_id(30).unwrap().name.as_str(), "b");
+        let loaded = SymbolRegistry::load(&streamed).unwrap();
+        assert_eq!(
+            loaded.entries[1].protection_detail,
+            registry.entries[1].protection_detail
+        );
+
+        SymbolRegistry::save_entries(&streamed, std::iter::empty::<SymbolEntry>()).unwrap();
+        assert!(MappedRegistry::open(&streamed).unwrap().is_empty());
+
+        std::fs::remove_file(saved).ok();
+        std::fs::remove_file(streamed).ok();
+    }
+
+    fn with(mut e: SymbolEntry, protected_by: Option<Protection>, hash: u64) -> SymbolEntry {
+        e.protected_by = protected_by;
+        e.structural_hash = hash;
+        e
+    }
+
+    #[test]
+    fn test_diff_categorises_every_change() {
+        let p = Some(Protection::Referenced);
+        let mut old = SymbolRegistry::new();
+        old.insert(with(entry(1, "stable", "a.py"), p, 0));
+        old.insert(with(entry(2, "rotting", "a.py"), p, 0));
+        old.insert(with(entry(3, "revived", "a.py"), None, 0));
+        old.insert(with(entry(4, "deleted_dead", "b.py"), None, 0));
+        old.insert(with(entry(5, "deleted_live", "b.py"), p, 0));
+        old.insert(with(entry(6, "old_name", "b.py"), None, 0xfeed));
+
+        // Different ids throughout: matching must not rely on them.
+        let mut new = SymbolRegistry::new();
+        new.insert(with(entry(11, "stable", "a.py"), p, 0));
+        new.insert(with(entry(12, "rotting", "a.py"), None, 0));
+        new.insert(with(entry(13, "revived", "a.py"), p, 0));
+        new.insert(with(entry(16, "new_name", "c.py"), None, 0xfeed));
+        new.insert(with(entry(17, "fresh_dead", "c.py"), None, 0));
+        new.insert(with(entry(18, "fresh_live", "c.py"), p, 0));
+
+        let d = diff(&old, &new);
+        let names = |v: &[SymbolEntry]| -> Vec<String> {
+            v.iter().map(|e| e.qualified_name.clone()).collect()
+        };
+        assert_eq!(names(&d.newly_dead), vec!["rotting", "fresh_dead"]);
+        assert_eq!(names(&d.resurrected), vec!["revived", "deleted_dead"]);
+        assert_eq!(names(&d.added), vec!["fresh_dead", "fresh_live"]);
+        assert_eq!(names(&d.removed), vec!["deleted_dead", "deleted_live"]);
+        assert_eq!(d.renamed.len(), 1);
+        assert_eq!(d.renamed[0].0.qualified_name, "old_name");
+        assert_eq!(d.renamed[0].1.qualified_name, "new_name");
+        // Dead before: revived, deleted_dead, old_name (3 × 10 bytes).
+        // Dead after: rotting, new_name, fresh_dead (3 × 10 bytes).
+        assert_eq!(d.dead_bytes_delta, 0);
+    }
+
+    #[test]
+    fn test_diff_ambiguous_structural_hash_is_not_a_rename() {
+        let mut old = SymbolRegistry::new();
+        old.insert(with(entry(1, "a", "x.py"), None, 0xabc));
+        let mut new = SymbolRegistry::new();
+        new.insert(with(entry(2, "b", "x.py"), None, 0xabc));
+        new.insert(with(entry(3, "c", "x.py"), None, 0xabc));
+
+        let d = diff(&old, &new);
+        assert!(d.renamed.is_empty());
+        assert_eq!(d.added.len(), 2);
+        assert_eq!(d.removed.len(), 1);
+        assert_eq!(d.newly_dead.len(), 2);
+        assert_eq!(d.dead_bytes_delta, 10);
+    }
+
+    #[test]
+    fn test_find_by_qualified_name_and_iter_by_file() {
+        let mut registry = SymbolRegistry::new();
+        registry.insert(entry(1, "Invoice.total", "pkg/billing.py"));
+        registry.insert(entry(2, "charge", "pkg/billing.py"));
+        registry.insert(entry(3, "charge", "pkg/legacy.py"));
+        registry.insert(entry(4, "Invoice", "pkg/billing.py"));
+        let tmp_path = std::env::temp_dir().join("test_registry_accessors.db");
+        registry.save(&tmp_path).unwrap();
+
+        let mapped = MappedRegistry::open(&tmp_path).unwrap();
+        let charges: Vec<&str> = mapped
+            .find_by_qualified_name("charge")
+            .map(|e| e.file_path.as_str())
+            .collect();
+        assert_eq!(charges, vec!["pkg/billing.py", "pkg/legacy.py"]);
+        assert_eq!(mapped.find_by_qualified_name("Invoice.total").count(), 1);
+        assert_eq!(mapped.find_by_qualified_name("missing").count(), 0);
+
+        let billing: Vec<u64> = mapped
+            .iter_by_file("pkg/billing.py")
+            .map(|e| e.id.into())
+            .collect();
+        assert_eq!(billing, vec![1, 2, 4]);
+        assert_eq!(mapped.iter_by_file("pkg/other.py").count(), 0);
+
+        std::fs::remove_file(tmp_path).ok();
+    }
 }
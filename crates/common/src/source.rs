@@ -0,0 +1,180 @@
+//! # Source Text: One Byte Buffer per File
+//!
+//! The parser, the link passes and the deleter all address a file by byte
+//! offset, so they must read the same bytes. [`read`] is the single reader
+//! they share:
+//!
+//! 1. Valid UTF-8 is memory-mapped as-is.
+//! 2. Anything else is transcoded from Latin-1 into an owned UTF-8 buffer.
+//!    Every byte sequence is valid Latin-1, so this never fails, and
+//!    [`Encoding::encode`] maps an edited buffer back to the original bytes.
+//! 3. A NUL byte is an error: CPython refuses such files ("source code cannot
+//!    contain null bytes"), and tree-sitter turns them into garbage nodes.
+
+use memmap2::Mmap;
+use std::borrow::Cow;
+use std::fs::File;
+use std::ops::Deref;
+use std::path::Path;
+
+/// Errors from reading or writing back source text.
+#[derive(Debug, thiserror::Error)]
+pub enum SourceError {
+    #[error("I/O error: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("contains a NUL byte at offset {0}; not a Python source file")]
+    NulByte(usize),
+    /// An edit inserted a character the file's encoding cannot hold.
+    #[error("`{0}` cannot be written back as Latin-1")]
+    Unencodable(char),
+}
+
+/// On-disk encoding of a source file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Utf8,
+    /// Not valid UTF-8; read byte-per-character as ISO-8859-1.
+    Latin1,
+}
+
+impl Encoding {
+    /// Converts UTF-8 `text` back to this encoding, for writing to disk.
+    pub fn encode(self, text: &[u8]) -> Result<Cow<'_, [u8]>, SourceError> {
+        match self {
+            Encoding::Utf8 => Ok(Cow::Borrowed(text)),
+            Encoding::Latin1 => String::from_utf8_lossy(text)
+                .chars()
+                .map(|c| u8::try_from(c).map_err(|_| SourceError::Unencodable(c)))
+                .collect::<Result<Vec<u8>, _>>()
+                .map(Cow::Owned),
+        }
+    }
+}
+
+/// The UTF-8 text of a source file; dereferences to its bytes.
+pub struct Source {
+    text: Text,
+    encoding: Encoding,
+}
+
+enum Text {
+    Mapped(Mmap),
+    Owned(Vec<u8>),
+}
+
+impl Source {
+    /// Encoding of the file on disk.
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    /// `true` when the text had to be transcoded.
+    pub fn is_transcoded(&self) -> bool {
+        self.encoding != Encoding::Utf8
+    }
+
+    /// The text as an owned buffer, for editing.
+    pub fn into_vec(self) -> Vec<u8> {
+        match self.text {
+            Text::Mapped(mmap) => mmap.to_vec(),
+            Text::Owned(bytes) => bytes,
+        }
+    }
+}
+
+impl Deref for Source {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match &self.text {
+            Text::Mapped(mmap) => mmap,
+            Text::Owned(bytes) => bytes,
+        }
+    }
+}
+
+impl AsRef<[u8]> for Source {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+/// Reads `path` as UTF-8 text (see the [module docs](self)).
+pub fn read(path: &Path) -> Result<Source, SourceError> {
+    let file = File::open(path)?;
+    if file.metadata()?.len() == 0 {
+        return Ok(Source {
+            text: Text::Owned(Vec::new()),
+            encoding: Encoding::Utf8,
+        });
+    }
+    // SAFETY: read-only map; the file handle is held until the map is built.
+    let mmap = unsafe { Mmap::map(&file)? };
+    if let Some(offset) = mmap.iter().position(|&b| b == 0) {
+        return Err(SourceError::NulByte(offset));
+    }
+    if std::str::from_utf8(&mmap).is_ok() {
+        return Ok(Source {
+            text: Text::Mapped(mmap),
+            encoding: Encoding::Utf8,
+        });
+    }
+    Ok(Source {
+        text: Text::Owned(latin1_to_utf8(&mmap)),
+        encoding: Encoding::Latin1,
+    })
+}
+
+fn latin1_to_utf8(bytes: &[u8]) -> Vec<u8> {
+    bytes
+        .iter()
+        .map(|&b| char::from(b))
+        .collect::<String>()
+        .into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_temp(name: &str, bytes: &[u8]) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(name);
+        std::fs::write(&path, bytes).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_utf8_is_read_unchanged() {
+        let path = write_temp("test_source_utf8.py", "x = 'café'\n".as_bytes());
+        let source = read(&path).unwrap();
+        assert_eq!(source.encoding(), Encoding::Utf8);
+        assert_eq!(&*source, "x = 'café'\n".as_bytes());
+        std::fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn test_latin1_round_trips() {
+        let original = b"# caf\xe9\ndef f():\n    return '\xe9t\xe9'\n";
+        let path = write_temp("test_source_latin1.py", original);
+        let source = read(&path).unwrap();
+        assert!(source.is_transcoded());
+        assert_eq!(&*source, "# café\ndef f():\n    return 'été'\n".as_bytes());
+        assert_eq!(source.encoding().encode(&source).unwrap(), &original[..]);
+        std::fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn test_nul_byte_is_an_error() {
+        let path = write_temp("test_source_nul.py", b"def f():\n\0   pass\n");
+        assert!(matches!(read(&path), Err(SourceError::NulByte(9))));
+        std::fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn test_latin1_rejects_wider_characters() {
+        assert!(matches!(
+            Encoding::Latin1.encode("π".as_bytes()),
+            Err(SourceError::Unencodable('π'))
+        ));
+    }
+}
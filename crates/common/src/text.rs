@@ -0,0 +1,31 @@
+//! Identifier-aware text matching shared by the log liveness trackers.
+
+/// Returns `true` for bytes that can continue a Python identifier: `[A-Za-z0-9_]`.
+pub fn is_identifier_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_'
+}
+
+/// Returns `true` when `haystack[start..end]` is not glued to a neighbouring
+/// identifier character, so `run` matches `run(` and `.run` but not `running`.
+pub fn is_word_match(haystack: &[u8], start: usize, end: usize) -> bool {
+    let before = start
+        .checked_sub(1)
+        .and_then(|i| haystack.get(i))
+        .is_some_and(|&b| is_identifier_byte(b));
+    let after = haystack.get(end).is_some_and(|&b| is_identifier_byte(b));
+    !before && !after
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_word_match() {
+        let text = b"running run foo.bar(";
+        assert!(!is_word_match(text, 0, 3)); // "run" in "running"
+        assert!(is_word_match(text, 8, 11)); // standalone "run"
+        assert!(is_word_match(text, 12, 19)); // "foo.bar" before "("
+        assert!(is_word_match(b"foo.bar", 0, 7)); // whole haystack
+    }
+}
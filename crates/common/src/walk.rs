@@ -0,0 +1,224 @@
+//! # Project Walk: One File Set for Every Stage
+//!
+//! The graph builder, the grep shield, the bridge scanner and the shadow tree
+//! must all agree on which files belong to the project. [`walk`] is the single
+//! directory walker they share. A path is skipped when any of these match:
+//!
+//! 1. [`DEFAULT_EXCLUDES`] — tool caches and virtualenvs, by directory name.
+//! 2. `exclude = [...]` in `{root}/.janitor.toml` — gitignore-syntax patterns
+//!    anchored at the project root.
+//! 3. `.gitignore` files at the root and in every descended directory.
+//!
+//! The walk itself and all pattern matching are the `ignore` crate's (the
+//! engine behind ripgrep), so the full gitignore syntax applies.
+
+use crate::config::JanitorConfig;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::{DirEntry, Match, WalkBuilder};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Directory names never descended into.
+pub const DEFAULT_EXCLUDES: &[&str] = &[
+    "__pycache__",
+    ".git",
+    ".janitor",
+    "venv",
+    ".venv",
+    "target",
+    "node_modules",
+    ".pytest_cache",
+];
+
+/// Walks `root` depth-first, skipping excluded files and directories.
+///
+/// Symlinks are not followed. Errors are yielded as-is so callers keep their
+/// own error policy.
+pub fn walk(root: &Path) -> impl Iterator<Item = Result<DirEntry, ignore::Error>> {
+    let config = config_excludes(root);
+    WalkBuilder::new(root)
+        // Only `.gitignore` files inside the project count, git repo or not.
+        .standard_filters(false)
+        .git_ignore(true)
+        .require_git(false)
+        .filter_entry(move |e| {
+            let is_dir = e.file_type().is_some_and(|t| t.is_dir());
+            e.depth() == 0
+                || !(is_dir && is_default_exclude(e.path())
+                    || config.matched(e.path(), is_dir).is_ignore())
+        })
+        .build()
+}
+
+/// Decides exclusion for paths under one project root, exactly as [`walk`]
+/// does, for paths that are not being walked.
+pub struct WalkFilter {
+    root: PathBuf,
+    config: Gitignore,
+    /// Parsed `.gitignore` per directory (empty when the directory has none).
+    gitignores: HashMap<PathBuf, Gitignore>,
+}
+
+impl WalkFilter {
+    /// Loads `.janitor.toml` excludes for `root`. `.gitignore` files are read
+    /// lazily as directories are visited.
+    pub fn new(root: &Path) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            config: config_excludes(root),
+            gitignores: HashMap::new(),
+        }
+    }
+
+    /// Returns `true` if `path` (a directory when `is_dir`) must be skipped.
+    pub fn is_excluded(&mut self, path: &Path, is_dir: bool) -> bool {
+        let Ok(rel) = path.strip_prefix(&self.root) else {
+            return false;
+        };
+        if is_dir && is_default_exclude(path) {
+            return true;
+        }
+        if self.config.matched(path, is_dir).is_ignore() {
+            return true;
+        }
+
+        // The deepest `.gitignore` with a matching rule decides.
+        let mut excluded = false;
+        let mut dir = self.root.clone();
+        let parents: Vec<_> = rel
+            .parent()
+            .into_iter()
+            .flat_map(|p| p.components())
+            .collect();
+        for i in 0..=parents.len() {
+            if i > 0 {
+                dir.push(parents[i - 1]);
+            }
+            match self.gitignore(&dir).matched(path, is_dir) {
+                Match::Ignore(_) => excluded = true,
+                Match::Whitelist(_) => excluded = false,
+                Match::None => {}
+            }
+        }
+        excluded
+    }
+
+    /// Returns `true` if [`walk`] would yield `path`: it lies under the root
+    /// and neither it nor any directory above it is excluded. `path` need not
+    /// exist (a file that was just deleted is judged by its name).
+    pub fn is_walked(&mut self, path: &Path) -> bool {
+        let Ok(rel) = path.strip_prefix(&self.root) else {
+            return false;
+        };
+        let mut dir = self.root.clone();
+        let mut components = rel.components().peekable();
+        while let Some(component) = components.next() {
+            dir.push(component);
+            let is_dir = components.peek().is_some() || dir.is_dir();
+            if self.is_excluded(&dir, is_dir) {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn gitignore(&mut self, dir: &Path) -> &Gitignore {
+        self.gitignores
+            .entry(dir.to_path_buf())
+            .or_insert_with(|| Gitignore::new(dir.join(".gitignore")).0)
+    }
+}
+
+fn is_default_exclude(dir: &Path) -> bool {
+    dir.file_name()
+        .and_then(|n| n.to_str())
+        .is_some_and(|n| DEFAULT_EXCLUDES.contains(&n))
+}
+
+/// `exclude = [...]` of `{root}/.janitor.toml`, anchored at `root`. Patterns
+/// that are not valid globs are dropped.
+fn config_excludes(root: &Path) -> Gitignore {
+    let mut builder = GitignoreBuilder::new(root);
+    for pattern in JanitorConfig::load(root)
+        .map(|(c, _)| c.exclude)
+        .unwrap_or_default()
+    {
+        builder.add_line(None, &pattern).ok();
+    }
+    builder.build().unwrap_or_else(|_| Gitignore::empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_walk_honours_gitignore_and_config() {
+        let tmp = std::env::temp_dir().join("test_common_walk");
+        fs::remove_dir_all(&tmp).ok();
+        for dir in ["src/vendor", "build", "generated", "pkg", "node_modules"] {
+            fs::create_dir_all(tmp.join(dir)).ok();
+        }
+        fs::write(tmp.join(".gitignore"), "vendor/\n*.log\n!keep.log\nbuild\n").ok();
+        fs::write(tmp.join("pkg/.gitignore"), "secret.py\n").ok();
+        fs::write(tmp.join(".janitor.toml"), "exclude = [\"generated\"]\n").ok();
+        for file in [
+            "src/app.py",
+            "src/vendor/lib.py",
+            "build/out.py",
+            "generated/g.py",
+            "pkg/secret.py",
+            "pkg/ok.py",
+            "debug.log",
+            "keep.log",
+            "node_modules/x.js",
+        ] {
+            fs::write(tmp.join(file), "").ok();
+        }
+
+        let mut files: Vec<String> = walk(&tmp)
+            .flatten()
+            .filter(|e| e.file_type().is_some_and(|t| t.is_file()))
+            .map(|e| {
+                e.path()
+                    .strip_prefix(&tmp)
+                    .unwrap()
+                    .to_string_lossy()
+                    .replace('\\', "/")
+            })
+            .collect();
+        files.sort();
+        assert_eq!(
+            files,
+            vec![
+                ".gitignore",
+                ".janitor.toml",
+                "keep.log",
+                "pkg/.gitignore",
+                "pkg/ok.py",
+                "src/app.py"
+            ]
+        );
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_is_walked_checks_every_parent() {
+        let tmp = std::env::temp_dir().join("test_common_is_walked");
+        fs::remove_dir_all(&tmp).ok();
+        fs::create_dir_all(tmp.join("src")).ok();
+        fs::write(tmp.join(".gitignore"), "build/\n").ok();
+
+        let mut filter = WalkFilter::new(&tmp);
+        assert!(filter.is_walked(&tmp.join("src/app.py")));
+        assert!(filter.is_walked(&tmp.join("src/deleted.py")));
+        assert!(!filter.is_walked(&tmp.join(".janitor/symbols.rkyv")));
+        assert!(!filter.is_walked(&tmp.join("venv/lib/site.py")));
+        assert!(!filter.is_walked(&tmp.join("build/out.py")));
+        assert!(!filter.is_walked(Path::new("/elsewhere/app.py")));
+
+        fs::remove_dir_all(tmp).ok();
+    }
+}
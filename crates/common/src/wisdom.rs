@@ -1,6 +1,42 @@
+//! # Wisdom Rules: Baked Framework Knowledge
+//!
+//! `wisdom-bake` compiles the JSON rules under `rules/` into a [`WisdomSet`]
+//! archived as `wisdom.rkyv`; [`WisdomRegistry`] maps that file back in at scan
+//! time so Stage 2 can protect symbols matching user-supplied framework rules.
+//! [`WisdomSet::from_rules_file`] reads the same JSON directly, letting
+//! `janitor scan --rules` merge ad-hoc rules without a bake step.
+
+use memmap2::Mmap;
+use rkyv::util::AlignedVec;
 use rkyv::{Archive, Deserialize, Serialize};
 use serde::{Deserialize as SerdeDeserialize, Serialize as SerdeSerialize};
+use std::fs::File;
+use std::path::Path;
+
+/// Errors from baking or loading a wisdom archive.
+#[derive(Debug, thiserror::Error)]
+pub enum WisdomError {
+    #[error("I/O error: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("Invalid wisdom archive: {0}")]
+    InvalidArchive(String),
+    #[error("{file}: {message}")]
+    InvalidRulesFile { file: String, message: String },
+    #[error("{file}: rule {index}: {message}")]
+    InvalidRule {
+        file: String,
+        index: usize,
+        message: String,
+    },
+}
 
+/// Keeps alive symbols of one framework matching any of `patterns`.
+///
+/// `rule_type` selects what a pattern is compared with: `"decorator"` (the
+/// decorator text contains the pattern), `"base_class"` / `"inheritance"` (a
+/// base class, or its last dotted segment, equals it), anything else the
+/// symbol name. A leading or trailing `*` in a name pattern matches any
+/// prefix or suffix.
 #[derive(
     Debug,
     Clone,
@@ -93,6 +129,246 @@ impl WisdomSet {
         self.immortality_rules.sort();
         self.meta_patterns.sort();
     }
+
+    /// Appends the rules and meta patterns of `other`.
+    pub fn merge(&mut self, other: WisdomSet) {
+        self.immortality_rules.extend(other.immortality_rules);
+        self.meta_patterns.merge(other.meta_patterns);
+    }
+
+    /// Sorts the set for a deterministic archive and writes it to `path` as
+    /// `wisdom.rkyv` bytes.
+    pub fn save(&mut self, path: &Path) -> Result<(), WisdomError> {
+        let bytes = self.archive_bytes()?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, &bytes)?;
+        Ok(())
+    }
+
+    fn archive_bytes(&mut self) -> Result<AlignedVec, WisdomError> {
+        self.sort();
+        rkyv::to_bytes::<rkyv::rancor::Error>(self)
+            .map_err(|e| WisdomError::InvalidArchive(e.to_string()))
+    }
+
+    /// Reads one JSON rules file in either format `wisdom-bake` accepts: an
+    /// `{"immortality_rules": [...]}` wrapper or a [`MetaPattern`] object.
+    ///
+    /// Every immortality rule must name a framework and at least one
+    /// non-empty pattern; errors name the file and the offending rule index.
+    pub fn from_rules_file(path: &Path) -> Result<Self, WisdomError> {
+        let file = path.display().to_string();
+        let invalid = |message: String| WisdomError::InvalidRulesFile {
+            file: file.clone(),
+            message,
+        };
+        if path.extension().and_then(|e| e.to_str()) != Some("json") {
+            return Err(invalid(
+                "only JSON rule files are supported (bake other formats with wisdom-bake)"
+                    .to_string(),
+            ));
+        }
+        let text = std::fs::read_to_string(path)?;
+        let value: serde_json::Value =
+            serde_json::from_str(&text).map_err(|e| invalid(e.to_string()))?;
+        let serde_json::Value::Object(mut object) = value else {
+            return Err(invalid("expected a JSON object".to_string()));
+        };
+
+        let mut set = WisdomSet::default();
+        if let Some(rules) = object.remove("immortality_rules") {
+            let serde_json::Value::Array(rules) = rules else {
+                return Err(invalid("`immortality_rules` must be an array".to_string()));
+            };
+            for (index, rule) in rules.into_iter().enumerate() {
+                let rule_error = |message: String| WisdomError::InvalidRule {
+                    file: file.clone(),
+                    index,
+                    message,
+                };
+                let rule: ImmortalityRule =
+                    serde_json::from_value(rule).map_err(|e| rule_error(e.to_string()))?;
+                if rule.framework.trim().is_empty() {
+                    return Err(rule_error("`framework` is empty".to_string()));
+                }
+                if rule.patterns.is_empty() || rule.patterns.iter().any(|p| p.trim().is_empty()) {
+                    return Err(rule_error(
+                        "`patterns` must list at least one non-empty pattern".to_string(),
+                    ));
+                }
+                set.immortality_rules.push(rule);
+            }
+            if object.is_empty() {
+                return Ok(set);
+            }
+        }
+
+        const META_KEYS: [&str; 4] = [
+            "exact_matches",
+            "suffix_matches",
+            "prefix_matches",
+            "syntax_markers",
+        ];
+        if let Some(key) = object.keys().find(|k| !META_KEYS.contains(&k.as_str())) {
+            return Err(invalid(format!("unknown key `{}`", key)));
+        }
+        set.meta_patterns = serde_json::from_value(serde_json::Value::Object(object))
+            .map_err(|e| invalid(e.to_string()))?;
+        let meta = &set.meta_patterns;
+        if meta
+            .exact_matches
+            .iter()
+            .chain(&meta.suffix_matches)
+            .chain(&meta.prefix_matches)
+            .chain(&meta.syntax_markers)
+            .any(|p| p.trim().is_empty())
+        {
+            return Err(invalid(
+                "meta patterns must not be empty strings".to_string(),
+            ));
+        }
+        Ok(set)
+    }
+}
+
+/// Backing bytes of a [`WisdomRegistry`].
+enum Storage {
+    Mapped(Mmap),
+    /// Copied so rkyv sees properly aligned data (`include_bytes!` is not).
+    Owned(AlignedVec),
+}
+
+/// A validated, zero-copy view of a baked [`WisdomSet`].
+pub struct WisdomRegistry {
+    storage: Storage,
+}
+
+impl std::fmt::Debug for WisdomRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let set = self.archived();
+        f.debug_struct("WisdomRegistry")
+            .field("immortality_rules", &set.immortality_rules.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl WisdomRegistry {
+    /// Maps a `wisdom.rkyv` file and validates it.
+    pub fn load(path: &Path) -> Result<Self, WisdomError> {
+        let file = File::open(path)?;
+        let mmap = unsafe { Mmap::map(&file)? };
+        Self::validated(Storage::Mapped(mmap))
+    }
+
+    /// Validates archive bytes held in memory (e.g. an embedded default).
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, WisdomError> {
+        let mut aligned = AlignedVec::with_capacity(bytes.len());
+        aligned.extend_from_slice(bytes);
+        Self::validated(Storage::Owned(aligned))
+    }
+
+    /// Archives `set` in memory, e.g. a baked set merged with `--rules` files.
+    pub fn from_set(mut set: WisdomSet) -> Result<Self, WisdomError> {
+        Self::validated(Storage::Owned(set.archive_bytes()?))
+    }
+
+    /// Deserializes the archived rules back into an owned, mergeable set.
+    pub fn to_set(&self) -> Result<WisdomSet, WisdomError> {
+        rkyv::deserialize::<WisdomSet, rkyv::rancor::Error>(self.archived())
+            .map_err(|e| WisdomError::InvalidArchive(e.to_string()))
+    }
+
+    fn validated(storage: Storage) -> Result<Self, WisdomError> {
+        let registry = Self { storage };
+        rkyv::access::<ArchivedWisdomSet, rkyv::rancor::Error>(registry.bytes())
+            .map_err(|e| WisdomError::InvalidArchive(e.to_string()))?;
+        Ok(registry)
+    }
+
+    fn bytes(&self) -> &[u8] {
+        match &self.storage {
+            Storage::Mapped(mmap) => mmap,
+            Storage::Owned(bytes) => bytes,
+        }
+    }
+
+    /// Returns a reference to the archived rule set (zero-copy).
+    pub fn archived(&self) -> &ArchivedWisdomSet {
+        // SAFETY: validated by `rkyv::access` on construction; the bytes live
+        // as long as `self`.
+        unsafe { rkyv::access_unchecked::<ArchivedWisdomSet>(self.bytes()) }
+    }
+
+    /// The first rule keeping a symbol alive, described for its protection
+    /// detail, or `None` when no rule matches.
+    ///
+    /// Meta patterns compare exact, prefix and suffix matches with `name`;
+    /// syntax markers are searched for in the decorators and base classes.
+    pub fn matches(
+        &self,
+        name: &str,
+        decorators: &[String],
+        base_classes: &[String],
+    ) -> Option<String> {
+        let set = self.archived();
+        for rule in set.immortality_rules.iter() {
+            let hit = rule.patterns.iter().find(|p| {
+                let p = p.as_str();
+                match rule.rule_type.as_str() {
+                    "decorator" => decorators.iter().any(|d| d.contains(p)),
+                    "base_class" | "inheritance" => base_classes
+                        .iter()
+                        .any(|b| b == p || b.rsplit('.').next() == Some(p)),
+                    _ => name_matches(name, p),
+                }
+            });
+            if let Some(pattern) = hit {
+                return Some(format!(
+                    "{} {} rule `{}`",
+                    rule.framework, rule.rule_type, pattern
+                ));
+            }
+        }
+
+        let meta = &set.meta_patterns;
+        if let Some(p) = meta.exact_matches.iter().find(|p| p.as_str() == name) {
+            return Some(format!("exact match `{}`", p));
+        }
+        if let Some(p) = meta
+            .prefix_matches
+            .iter()
+            .find(|p| name.starts_with(p.as_str()))
+        {
+            return Some(format!("prefix match `{}`", p));
+        }
+        if let Some(p) = meta
+            .suffix_matches
+            .iter()
+            .find(|p| name.ends_with(p.as_str()))
+        {
+            return Some(format!("suffix match `{}`", p));
+        }
+        meta.syntax_markers
+            .iter()
+            .find(|m| {
+                decorators
+                    .iter()
+                    .chain(base_classes)
+                    .any(|text| text.contains(m.as_str()))
+            })
+            .map(|m| format!("syntax marker `{}`", m))
+    }
+}
+
+/// `name` equals `pattern`, with a leading/trailing `*` matching any prefix/suffix.
+fn name_matches(name: &str, pattern: &str) -> bool {
+    match (pattern.strip_prefix('*'), pattern.strip_suffix('*')) {
+        (Some(suffix), _) if !suffix.ends_with('*') => name.ends_with(suffix),
+        (_, Some(prefix)) => name.starts_with(prefix),
+        _ => name == pattern,
+    }
 }
 
 // Helper for JSON deserialization of files like immortality_rules.json
@@ -100,3 +376,133 @@ impl WisdomSet {
 pub struct ImmortalityRulesWrapper {
     pub immortality_rules: Vec<ImmortalityRule>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> WisdomSet {
+        WisdomSet {
+            immortality_rules: vec![
+                ImmortalityRule {
+                    framework: "celery".into(),
+                    patterns: vec!["shared_task".into()],
+                    rule_type: "decorator".into(),
+                    action: None,
+                },
+                ImmortalityRule {
+                    framework: "django".into(),
+                    patterns: vec!["BaseCommand".into()],
+                    rule_type: "base_class".into(),
+                    action: None,
+                },
+                ImmortalityRule {
+                    framework: "twisted".into(),
+                    patterns: vec!["*Received".into()],
+                    rule_type: "name".into(),
+                    action: None,
+                },
+            ],
+            meta_patterns: MetaPattern {
+                exact_matches: vec!["process_item".into()],
+                suffix_matches: vec!["_handler".into()],
+                prefix_matches: vec!["hook_".into()],
+                syntax_markers: vec!["register_plugin".into()],
+            },
+        }
+    }
+
+    #[test]
+    fn test_save_load_and_match() {
+        let tmp = std::env::temp_dir().join("test_wisdom_registry");
+        std::fs::remove_dir_all(&tmp).ok();
+        let path = tmp.join("wisdom.rkyv");
+        sample().save(&path).unwrap();
+
+        let registry = WisdomRegistry::load(&path).unwrap();
+        let matches = |name: &str, decorators: &[&str], bases: &[&str]| {
+            let decorators: Vec<String> = decorators.iter().map(|d| d.to_string()).collect();
+            let bases: Vec<String> = bases.iter().map(|b| b.to_string()).collect();
+            registry.matches(name, &decorators, &bases)
+        };
+
+        assert_eq!(
+            matches("send", &["shared_task(bind=True)"], &[]).as_deref(),
+            Some("celery decorator rule `shared_task`")
+        );
+        assert_eq!(
+            matches("Command", &[], &["management.BaseCommand"]).as_deref(),
+            Some("django base_class rule `BaseCommand`")
+        );
+        assert!(matches("dataReceived", &[], &[]).is_some());
+        assert_eq!(
+            matches("on_payment_handler", &[], &[]).as_deref(),
+            Some("suffix match `_handler`")
+        );
+        assert!(matches("hook_start", &[], &[]).is_some());
+        assert!(matches("process_item", &[], &[]).is_some());
+        assert!(matches("setup", &["app.register_plugin"], &[]).is_some());
+        assert_eq!(matches("handler_factory", &[], &[]), None);
+
+        // In-memory bytes (the embedded default) need not be aligned.
+        let bytes = std::fs::read(&path).unwrap();
+        let mut shifted = vec![0u8];
+        shifted.extend_from_slice(&bytes);
+        let registry = WisdomRegistry::from_bytes(&shifted[1..]).unwrap();
+        assert_eq!(registry.archived().immortality_rules.len(), 3);
+
+        assert!(WisdomRegistry::from_bytes(b"not an archive").is_err());
+
+        std::fs::remove_dir_all(&tmp).ok();
+    }
+
+    #[test]
+    fn test_rules_file_merges_and_reports_rule_index() {
+        let tmp = std::env::temp_dir().join("test_wisdom_rules_file");
+        std::fs::remove_dir_all(&tmp).ok();
+        std::fs::create_dir_all(&tmp).unwrap();
+
+        let meta = tmp.join("plugins.json");
+        std::fs::write(&meta, r#"{"suffix_matches": ["_plugin"]}"#).unwrap();
+        let rules = tmp.join("rules.json");
+        std::fs::write(
+            &rules,
+            r#"{"immortality_rules": [{"framework": "pluggy", "patterns": ["hookimpl"], "type": "decorator", "action": null}]}"#,
+        )
+        .unwrap();
+
+        let mut set = sample();
+        set.merge(WisdomSet::from_rules_file(&meta).unwrap());
+        set.merge(WisdomSet::from_rules_file(&rules).unwrap());
+        let registry = WisdomRegistry::from_set(set).unwrap();
+        assert_eq!(
+            registry.matches("auth_plugin", &[], &[]).as_deref(),
+            Some("suffix match `_plugin`")
+        );
+        assert!(registry
+            .matches("setup", &["pm.hookimpl".to_string()], &[])
+            .is_some());
+        // The baked rules survive the round trip.
+        assert_eq!(registry.to_set().unwrap().immortality_rules.len(), 4);
+
+        let bad = tmp.join("bad.json");
+        std::fs::write(
+            &bad,
+            r#"{"immortality_rules": [{"framework": "ok", "patterns": ["x"], "type": "name", "action": null}, {"framework": "", "patterns": ["y"], "type": "name", "action": null}]}"#,
+        )
+        .unwrap();
+        let err = WisdomSet::from_rules_file(&bad).unwrap_err().to_string();
+        assert!(
+            err.contains("bad.json: rule 1: `framework` is empty"),
+            "{}",
+            err
+        );
+
+        let typo = tmp.join("typo.json");
+        std::fs::write(&typo, r#"{"sufix_matches": ["_plugin"]}"#).unwrap();
+        let err = WisdomSet::from_rules_file(&typo).unwrap_err().to_string();
+        assert!(err.contains("unknown key `sufix_matches`"), "{}", err);
+
+        std::fs::remove_dir_all(&tmp).ok();
+    }
+}
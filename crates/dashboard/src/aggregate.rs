@@ -0,0 +1,243 @@
+//! Dead-code density per file and per top-level directory.
+//!
+//! Paths are grouped relative to the deepest directory containing every file
+//! in the registry, which for a scan is the project root or the single package
+//! beneath it. Byte counts are the union of symbol spans, so a dead class and
+//! its dead methods are counted once.
+
+use common::registry::SymbolRegistry;
+use std::collections::BTreeMap;
+
+/// Dead-code totals for one source file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileStats {
+    /// Path relative to the registry's common root, forward slashes.
+    pub path: String,
+    pub total_symbols: usize,
+    pub dead_symbols: usize,
+    pub total_bytes: u64,
+    pub dead_bytes: u64,
+}
+
+/// Dead-code totals for one top-level directory and the files inside it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirStats {
+    /// Directory name with a trailing slash; `./` for files at the root.
+    pub name: String,
+    pub total_symbols: usize,
+    pub dead_symbols: usize,
+    pub total_bytes: u64,
+    pub dead_bytes: u64,
+    /// Files in this directory, most dead bytes first.
+    pub files: Vec<FileStats>,
+}
+
+impl FileStats {
+    /// Share of symbol bytes that are dead, in percent.
+    pub fn dead_percent(&self) -> f64 {
+        percent(self.dead_bytes, self.total_bytes)
+    }
+}
+
+impl DirStats {
+    /// Share of symbol bytes that are dead, in percent.
+    pub fn dead_percent(&self) -> f64 {
+        percent(self.dead_bytes, self.total_bytes)
+    }
+}
+
+/// Aggregates `registry` by top-level directory, most dead bytes first.
+pub fn by_directory(registry: &SymbolRegistry) -> Vec<DirStats> {
+    // file path → (all spans, dead spans, total symbols, dead symbols)
+    type Spans = (Vec<(u32, u32)>, Vec<(u32, u32)>, usize, usize);
+    let mut files: BTreeMap<&str, Spans> = BTreeMap::new();
+    for e in &registry.entries {
+        let slot = files.entry(e.file_path.as_str()).or_default();
+        slot.0.push((e.start_byte, e.end_byte));
+        slot.2 += 1;
+        if e.protected_by.is_none() {
+            slot.1.push((e.start_byte, e.end_byte));
+            slot.3 += 1;
+        }
+    }
+
+    let root = common_dir(files.keys().copied());
+    let mut dirs: BTreeMap<String, DirStats> = BTreeMap::new();
+    for (path, (all, dead, total_symbols, dead_symbols)) in files {
+        let rel = path[root.len()..].trim_start_matches('/');
+        let name = match rel.split_once('/') {
+            Some((top, _)) => format!("{}/", top),
+            None => "./".to_string(),
+        };
+        let file = FileStats {
+            path: rel.to_string(),
+            total_symbols,
+            dead_symbols,
+            total_bytes: union_len(all),
+            dead_bytes: union_len(dead),
+        };
+        let dir = dirs.entry(name.clone()).or_insert_with(|| DirStats {
+            name,
+            total_symbols: 0,
+            dead_symbols: 0,
+            total_bytes: 0,
+            dead_bytes: 0,
+            files: Vec::new(),
+        });
+        dir.total_symbols += file.total_symbols;
+        dir.dead_symbols += file.dead_symbols;
+        dir.total_bytes += file.total_bytes;
+        dir.dead_bytes += file.dead_bytes;
+        dir.files.push(file);
+    }
+
+    let mut dirs: Vec<DirStats> = dirs.into_values().collect();
+    for dir in &mut dirs {
+        // Stable sort: ties keep path order.
+        dir.files.sort_by_key(|f| std::cmp::Reverse(f.dead_bytes));
+    }
+    dirs.sort_by_key(|d| std::cmp::Reverse(d.dead_bytes));
+    dirs
+}
+
+/// Human-readable size: `512B`, `18KB`, `3.4MB`.
+pub fn format_bytes(bytes: u64) -> String {
+    const KB: u64 = 1024;
+    const MB: u64 = 1024 * KB;
+    if bytes >= MB {
+        format!("{:.1}MB", bytes as f64 / MB as f64)
+    } else if bytes >= KB {
+        format!("{}KB", (bytes + KB / 2) / KB)
+    } else {
+        format!("{}B", bytes)
+    }
+}
+
+fn percent(part: u64, whole: u64) -> f64 {
+    if whole == 0 {
+        0.0
+    } else {
+        part as f64 * 100.0 / whole as f64
+    }
+}
+
+/// Total length covered by a set of possibly nested or overlapping spans.
+fn union_len(mut spans: Vec<(u32, u32)>) -> u64 {
+    spans.sort_unstable();
+    let mut total = 0u64;
+    let mut current: Option<(u32, u32)> = None;
+    for (start, end) in spans {
+        match current {
+            Some((s, e)) if start <= e => current = Some((s, e.max(end))),
+            _ => {
+                if let Some((s, e)) = current {
+                    total += u64::from(e.saturating_sub(s));
+                }
+                current = Some((start, end));
+            }
+        }
+    }
+    if let Some((s, e)) = current {
+        total += u64::from(e.saturating_sub(s));
+    }
+    total
+}
+
+/// Deepest directory (no trailing slash) that contains every path.
+fn common_dir<'a>(paths: impl Iterator<Item = &'a str>) -> String {
+    let mut common: Option<Vec<&str>> = None;
+    for path in paths {
+        let mut parts: Vec<&str> = path.split('/').collect();
+        parts.pop(); // file name
+        common = Some(match common {
+            None => parts,
+            Some(prefix) => prefix
+                .iter()
+                .zip(&parts)
+                .take_while(|(a, b)| a == b)
+                .map(|(a, _)| *a)
+                .collect(),
+        });
+    }
+    common.unwrap_or_default().join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::registry::SymbolEntry;
+    use common::Protection;
+
+    fn registry(entries: &[(&str, u32, u32, bool)]) -> SymbolRegistry {
+        let mut registry = SymbolRegistry::new();
+        for (i, &(file, start, end, dead)) in entries.iter().enumerate() {
+            registry.insert(SymbolEntry {
+                id: i as u64,
+                name: format!("sym{}", i),
+                qualified_name: format!("sym{}", i),
+                module_path: String::new(),
+                file_path: file.into(),
+                entity_type: 0,
+                start_line: 1,
+                end_line: 1,
+                start_byte: start,
+                end_byte: end,
+                structural_hash: 0,
+                protected_by: (!dead).then_some(Protection::Referenced),
+                protection_detail: None,
+            });
+        }
+        registry
+    }
+
+    #[test]
+    fn test_groups_by_top_level_directory_sorted_by_dead_bytes() {
+        let registry = registry(&[
+            ("/p/api/views.py", 0, 100, false),
+            ("/p/api/views.py", 100, 400, true),
+            ("/p/legacy/old.py", 0, 700, true),
+            ("/p/legacy/sub/older.py", 0, 300, false),
+            ("/p/setup.py", 0, 50, true),
+        ]);
+        let dirs = by_directory(&registry);
+
+        let names: Vec<&str> = dirs.iter().map(|d| d.name.as_str()).collect();
+        assert_eq!(names, vec!["legacy/", "api/", "./"]);
+
+        let legacy = &dirs[0];
+        assert_eq!((legacy.total_symbols, legacy.dead_symbols), (2, 1));
+        assert_eq!((legacy.total_bytes, legacy.dead_bytes), (1000, 700));
+        assert_eq!(legacy.dead_percent(), 70.0);
+        let paths: Vec<&str> = legacy.files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, vec!["legacy/old.py", "legacy/sub/older.py"]);
+
+        assert_eq!(dirs[1].dead_percent(), 75.0);
+        assert_eq!(dirs[2].files[0].path, "setup.py");
+    }
+
+    #[test]
+    fn test_nested_spans_are_counted_once() {
+        // A dead class (0..500) containing a dead method and a live method.
+        let registry = registry(&[
+            ("/p/pkg/models.py", 0, 500, true),
+            ("/p/pkg/models.py", 50, 150, true),
+            ("/p/pkg/models.py", 200, 300, false),
+            ("/p/pkg/other.py", 0, 10, false),
+        ]);
+        let dirs = by_directory(&registry);
+        assert_eq!(dirs.len(), 1);
+        // The common root is `/p/pkg`, so both files sit at its top level.
+        assert_eq!(dirs[0].name, "./");
+        let models = &dirs[0].files[0];
+        assert_eq!((models.total_bytes, models.dead_bytes), (500, 500));
+        assert_eq!((models.total_symbols, models.dead_symbols), (3, 2));
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(512), "512B");
+        assert_eq!(format_bytes(18 * 1024 + 100), "18KB");
+        assert_eq!(format_bytes(3 * 1024 * 1024 + 400 * 1024), "3.4MB");
+        assert!(by_directory(&SymbolRegistry::new()).is_empty());
+    }
+}
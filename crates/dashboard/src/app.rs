@@ -0,0 +1,424 @@
+//! Dashboard state: which symbols are listed, which one is selected, and the
+//! keyboard bindings that change them. Holds no terminal handle.
+
+use crate::aggregate::{self, DirStats, FileStats};
+use common::registry::{SymbolEntry, SymbolRegistry};
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::widgets::ListState;
+
+/// Which half of the registry the list shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum View {
+    /// Symbols with no protection reason.
+    Dead,
+    /// Symbols protected by some pipeline stage.
+    Protected,
+    /// Dead-code density per top-level directory, or per file once drilled into.
+    Directories,
+}
+
+/// Whether keystrokes edit the search filter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Normal,
+    Search,
+}
+
+/// Interactive dashboard state over a loaded registry.
+pub struct App<'a> {
+    registry: &'a SymbolRegistry,
+    view: View,
+    mode: Mode,
+    filter: String,
+    /// Indices matching the view and filter: into `registry.entries` for the
+    /// symbol views (largest first), into `dirs` or the drilled directory's files
+    /// for [`View::Directories`].
+    visible: Vec<usize>,
+    dirs: Vec<DirStats>,
+    /// Directory whose files are listed, as an index into `dirs`.
+    drilled: Option<usize>,
+    pub(crate) list_state: ListState,
+    /// List rows that fit on screen; updated on every draw, used by PageUp/PageDown.
+    pub(crate) page_size: usize,
+    dead_count: usize,
+    quit: bool,
+}
+
+impl<'a> App<'a> {
+    pub fn new(registry: &'a SymbolRegistry) -> Self {
+        let mut app = Self {
+            registry,
+            view: View::Dead,
+            mode: Mode::Normal,
+            filter: String::new(),
+            visible: Vec::new(),
+            dirs: aggregate::by_directory(registry),
+            drilled: None,
+            list_state: ListState::default(),
+            page_size: 10,
+            dead_count: registry
+                .entries
+                .iter()
+                .filter(|e| e.protected_by.is_none())
+                .count(),
+            quit: false,
+        };
+        app.refresh();
+        app
+    }
+
+    pub fn registry(&self) -> &SymbolRegistry {
+        self.registry
+    }
+
+    pub fn view(&self) -> View {
+        self.view
+    }
+
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    pub fn filter(&self) -> &str {
+        &self.filter
+    }
+
+    pub fn should_quit(&self) -> bool {
+        self.quit
+    }
+
+    /// Entries currently listed, in display order. Empty in [`View::Directories`].
+    pub fn visible(&self) -> impl Iterator<Item = &SymbolEntry> {
+        let rows: &[usize] = if self.view == View::Directories {
+            &[]
+        } else {
+            &self.visible
+        };
+        rows.iter().map(|&i| &self.registry.entries[i])
+    }
+
+    /// Per-directory totals for the whole registry.
+    pub fn dirs(&self) -> &[DirStats] {
+        &self.dirs
+    }
+
+    /// The directory whose files are listed, if the user drilled into one.
+    pub fn drilled(&self) -> Option<&DirStats> {
+        self.drilled.map(|d| &self.dirs[d])
+    }
+
+    /// Directories currently listed. Empty outside the top level of [`View::Directories`].
+    pub fn visible_dirs(&self) -> impl Iterator<Item = &DirStats> {
+        let rows: &[usize] = if self.view == View::Directories && self.drilled.is_none() {
+            &self.visible
+        } else {
+            &[]
+        };
+        rows.iter().map(|&i| &self.dirs[i])
+    }
+
+    /// Files currently listed. Empty unless drilled into a directory.
+    pub fn visible_files(&self) -> impl Iterator<Item = &FileStats> {
+        let files: &[FileStats] = match self.drilled() {
+            Some(dir) if self.view == View::Directories => &dir.files,
+            _ => &[],
+        };
+        let rows: &[usize] = if files.is_empty() { &[] } else { &self.visible };
+        rows.iter().map(move |&i| &files[i])
+    }
+
+    pub fn visible_len(&self) -> usize {
+        self.visible.len()
+    }
+
+    /// The highlighted entry, if a non-empty symbol list is shown.
+    pub fn selected(&self) -> Option<&SymbolEntry> {
+        self.visible().nth(self.list_state.selected()?)
+    }
+
+    /// The highlighted directory, at the top level of [`View::Directories`].
+    pub fn selected_dir(&self) -> Option<&DirStats> {
+        self.visible_dirs().nth(self.list_state.selected()?)
+    }
+
+    /// The highlighted file, inside a drilled-into directory.
+    pub fn selected_file(&self) -> Option<&FileStats> {
+        self.visible_files().nth(self.list_state.selected()?)
+    }
+
+    /// Number of symbols without a protection reason.
+    pub fn dead_count(&self) -> usize {
+        self.dead_count
+    }
+
+    /// Applies one key press.
+    pub fn handle_key(&mut self, key: KeyEvent) {
+        match self.mode {
+            Mode::Search => match key.code {
+                KeyCode::Esc => {
+                    self.mode = Mode::Normal;
+                    self.set_filter(String::new());
+                }
+                KeyCode::Enter => self.mode = Mode::Normal,
+                KeyCode::Backspace => {
+                    let mut filter = self.filter.clone();
+                    filter.pop();
+                    self.set_filter(filter);
+                }
+                KeyCode::Char(c) => {
+                    let filter = format!("{}{}", self.filter, c);
+                    self.set_filter(filter);
+                }
+                KeyCode::Down => self.move_by(1),
+                KeyCode::Up => self.move_by(-1),
+                _ => {}
+            },
+            Mode::Normal => match key.code {
+                KeyCode::Char('q') => self.quit = true,
+                KeyCode::Char('/') => self.mode = Mode::Search,
+                KeyCode::Esc => self.set_filter(String::new()),
+                KeyCode::Tab | KeyCode::Char('t') => self.toggle_view(),
+                KeyCode::Char('d') => self.toggle_directories(),
+                KeyCode::Enter | KeyCode::Right | KeyCode::Char('l') => self.drill_in(),
+                KeyCode::Backspace | KeyCode::Left | KeyCode::Char('h') => self.drill_out(),
+                KeyCode::Down | KeyCode::Char('j') => self.move_by(1),
+                KeyCode::Up | KeyCode::Char('k') => self.move_by(-1),
+                KeyCode::PageDown => self.move_by(self.page_size.max(1) as isize),
+                KeyCode::PageUp => self.move_by(-(self.page_size.max(1) as isize)),
+                KeyCode::Home | KeyCode::Char('g') => self.select(0),
+                KeyCode::End | KeyCode::Char('G') => {
+                    self.select(self.visible.len().saturating_sub(1))
+                }
+                _ => {}
+            },
+        }
+    }
+
+    fn toggle_view(&mut self) {
+        self.view = match self.view {
+            View::Dead => View::Protected,
+            View::Protected | View::Directories => View::Dead,
+        };
+        self.refresh();
+    }
+
+    fn toggle_directories(&mut self) {
+        self.view = match self.view {
+            View::Directories => View::Dead,
+            View::Dead | View::Protected => View::Directories,
+        };
+        self.drilled = None;
+        self.refresh();
+    }
+
+    fn drill_in(&mut self) {
+        if self.view != View::Directories || self.drilled.is_some() {
+            return;
+        }
+        let Some(&dir) = self.list_state.selected().and_then(|i| self.visible.get(i)) else {
+            return;
+        };
+        self.drilled = Some(dir);
+        self.filter.clear();
+        self.refresh();
+    }
+
+    fn drill_out(&mut self) {
+        let Some(dir) = self.drilled.take() else {
+            return;
+        };
+        self.filter.clear();
+        self.refresh();
+        // Re-select the directory we came from.
+        if let Some(row) = self.visible.iter().position(|&i| i == dir) {
+            self.list_state.select(Some(row));
+        }
+    }
+
+    fn set_filter(&mut self, filter: String) {
+        self.filter = filter;
+        self.refresh();
+    }
+
+    /// Recomputes the visible list and resets the selection to the top.
+    fn refresh(&mut self) {
+        let needle = self.filter.to_lowercase();
+        if self.view == View::Directories {
+            let names: Vec<&str> = match self.drilled {
+                Some(d) => self.dirs[d].files.iter().map(|f| f.path.as_str()).collect(),
+                None => self.dirs.iter().map(|d| d.name.as_str()).collect(),
+            };
+            // Already ordered by dead bytes.
+            self.visible = (0..names.len())
+                .filter(|&i| needle.is_empty() || names[i].to_lowercase().contains(&needle))
+                .collect();
+            self.list_state
+                .select((!self.visible.is_empty()).then_some(0));
+            return;
+        }
+        let want_dead = self.view == View::Dead;
+        let entries = &self.registry.entries;
+        self.visible = (0..entries.len())
+            .filter(|&i| {
+                let e = &entries[i];
+                e.protected_by.is_none() == want_dead
+                    && (needle.is_empty()
+                        || e.name.to_lowercase().contains(&needle)
+                        || e.file_path.to_lowercase().contains(&needle))
+            })
+            .collect();
+        self.visible.sort_by_key(|&i| {
+            let e = &entries[i];
+            std::cmp::Reverse(e.end_byte.saturating_sub(e.start_byte))
+        });
+        self.list_state
+            .select((!self.visible.is_empty()).then_some(0));
+    }
+
+    fn move_by(&mut self, delta: isize) {
+        let current = self.list_state.selected().unwrap_or(0) as isize;
+        let target = (current + delta).max(0) as usize;
+        self.select(target);
+    }
+
+    fn select(&mut self, index: usize) {
+        if self.visible.is_empty() {
+            return;
+        }
+        self.list_state
+            .select(Some(index.min(self.visible.len() - 1)));
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use common::{Protection, ProtectionDetail};
+    use crossterm::event::KeyModifiers;
+
+    pub(crate) fn sample_registry() -> SymbolRegistry {
+        let mut registry = SymbolRegistry::new();
+        for (id, name, file, size, protection) in [
+            (1, "export_csv", "/p/reports.py", 400, None),
+            (2, "export_xml", "/p/reports.py", 900, None),
+            (3, "legacy_sync", "/p/sync.py", 100, None),
+            (4, "main", "/p/cli.py", 50, Some(Protection::EntryPoint)),
+        ] {
+            registry.insert(SymbolEntry {
+                id,
+                name: name.into(),
+                qualified_name: name.into(),
+                module_path: String::new(),
+                file_path: file.into(),
+                entity_type: 0,
+                start_line: 10,
+                end_line: 20,
+                start_byte: 1000,
+                end_byte: 1000 + size,
+                structural_hash: 0xabc,
+                protected_by: protection,
+                protection_detail: protection
+                    .map(|_| ProtectionDetail::new(2, "wisdom rule: entry point `main`")),
+            });
+        }
+        registry
+    }
+
+    fn press(app: &mut App, code: KeyCode) {
+        app.handle_key(KeyEvent::new(code, KeyModifiers::NONE));
+    }
+
+    fn names(app: &App) -> Vec<String> {
+        app.visible().map(|e| e.name.clone()).collect()
+    }
+
+    #[test]
+    fn test_lists_all_dead_largest_first() {
+        let registry = sample_registry();
+        let app = App::new(&registry);
+        assert_eq!(names(&app), vec!["export_xml", "export_csv", "legacy_sync"]);
+        assert_eq!(app.selected().map(|e| e.id), Some(2));
+    }
+
+    #[test]
+    fn test_search_filters_by_name_or_path_and_esc_clears() {
+        let registry = sample_registry();
+        let mut app = App::new(&registry);
+        press(&mut app, KeyCode::Char('/'));
+        for c in "sync".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        assert_eq!(names(&app), vec!["legacy_sync"]);
+        press(&mut app, KeyCode::Backspace);
+        press(&mut app, KeyCode::Backspace);
+        press(&mut app, KeyCode::Backspace);
+        press(&mut app, KeyCode::Backspace);
+        for c in "REPORTS".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.mode(), Mode::Normal);
+        assert_eq!(names(&app), vec!["export_xml", "export_csv"]);
+
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(app.filter(), "");
+        assert_eq!(app.visible_len(), 3);
+    }
+
+    #[test]
+    fn test_navigation_clamps_and_toggle_switches_view() {
+        let registry = sample_registry();
+        let mut app = App::new(&registry);
+        press(&mut app, KeyCode::Up);
+        assert_eq!(app.list_state.selected(), Some(0));
+        press(&mut app, KeyCode::PageDown);
+        assert_eq!(app.list_state.selected(), Some(2));
+        press(&mut app, KeyCode::Down);
+        assert_eq!(app.list_state.selected(), Some(2));
+        press(&mut app, KeyCode::Home);
+        assert_eq!(app.selected().map(|e| e.id), Some(2));
+
+        press(&mut app, KeyCode::Tab);
+        assert_eq!(app.view(), View::Protected);
+        assert_eq!(names(&app), vec!["main"]);
+
+        press(&mut app, KeyCode::Char('q'));
+        assert!(app.should_quit());
+    }
+
+    #[test]
+    fn test_directory_view_drills_into_files_and_back() {
+        let mut registry = sample_registry();
+        for (id, file) in [(5, "/p/legacy/old.py"), (6, "/p/legacy/older.py")] {
+            let mut entry = registry.entries[0].clone();
+            entry.id = id;
+            entry.file_path = file.into();
+            registry.insert(entry);
+        }
+        let mut app = App::new(&registry);
+        press(&mut app, KeyCode::Char('d'));
+        assert_eq!(app.view(), View::Directories);
+        assert_eq!(app.visible().count(), 0);
+        let dirs: Vec<&str> = app.visible_dirs().map(|d| d.name.as_str()).collect();
+        assert_eq!(dirs, vec!["./", "legacy/"]);
+
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.drilled().map(|d| d.name.as_str()), Some("legacy/"));
+        assert_eq!(app.visible_dirs().count(), 0);
+        let files: Vec<&str> = app.visible_files().map(|f| f.path.as_str()).collect();
+        assert_eq!(files, vec!["legacy/old.py", "legacy/older.py"]);
+        assert_eq!(
+            app.selected_file().map(|f| f.path.as_str()),
+            Some("legacy/old.py")
+        );
+
+        press(&mut app, KeyCode::Backspace);
+        assert!(app.drilled().is_none());
+        assert_eq!(app.selected_dir().map(|d| d.name.as_str()), Some("legacy/"));
+
+        press(&mut app, KeyCode::Char('d'));
+        assert_eq!(app.view(), View::Dead);
+        assert_eq!(app.visible_len(), 5);
+    }
+}
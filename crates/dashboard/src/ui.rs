@@ -0,0 +1,295 @@
+//! Dashboard layout. Draws an [`App`] into any ratatui frame, so rendering can
+//! be checked against a `TestBackend` buffer.
+
+use crate::aggregate::format_bytes;
+use crate::app::{App, Mode, View};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{BarChart, Block, Borders, List, ListItem, Paragraph, Wrap},
+    Frame,
+};
+
+/// Registry share that must be alive for the "SOVEREIGN" status.
+const SOVEREIGN_DENSITY: f64 = 90.0;
+
+/// Cells in a directory or file density bar.
+const BAR_WIDTH: usize = 20;
+
+/// Draws the whole dashboard: status bar, symbol list, detail pane, overview
+/// chart and footer.
+pub fn draw(f: &mut Frame, app: &mut App) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .split(f.size());
+
+    let main_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+        .split(chunks[1]);
+
+    let side_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(11), Constraint::Min(0)])
+        .split(main_chunks[1]);
+
+    draw_status(f, app, chunks[0]);
+    draw_list(f, app, main_chunks[0]);
+    draw_detail(f, app, side_chunks[0]);
+    draw_overview(f, app, side_chunks[1]);
+    draw_footer(f, app, chunks[2]);
+}
+
+fn draw_status(f: &mut Frame, app: &App, area: Rect) {
+    let total = app.registry().len();
+    let dead = app.dead_count();
+    let density = if total > 0 {
+        ((total - dead) as f64 / total as f64) * 100.0
+    } else {
+        100.0
+    };
+    let (text, color) = if density > SOVEREIGN_DENSITY {
+        ("SOVEREIGN", Color::Green)
+    } else {
+        ("VULNERABLE", Color::Red)
+    };
+
+    let status = Paragraph::new(Line::from(vec![
+        Span::raw("Sovereign Status: "),
+        Span::styled(
+            format!("{} ({:.1}%)", text, density),
+            Style::default().fg(color).add_modifier(Modifier::BOLD),
+        ),
+        Span::raw(format!("   {} symbols, {} dead", total, dead)),
+    ]))
+    .block(Block::default().borders(Borders::ALL).title("Status"));
+    f.render_widget(status, area);
+}
+
+fn draw_list(f: &mut Frame, app: &mut App, area: Rect) {
+    // Borders take two rows.
+    app.page_size = area.height.saturating_sub(2) as usize;
+
+    let items: Vec<ListItem> = match (app.view(), app.drilled()) {
+        (View::Directories, None) => app
+            .visible_dirs()
+            .map(|d| density_row(&d.name, d.dead_percent(), d.dead_bytes))
+            .collect(),
+        (View::Directories, Some(_)) => app
+            .visible_files()
+            .map(|f| density_row(&f.path, f.dead_percent(), f.dead_bytes))
+            .collect(),
+        _ => app
+            .visible()
+            .map(|e| {
+                let size = e.end_byte.saturating_sub(e.start_byte);
+                ListItem::new(format!("{} ({} bytes) - {}", e.name, size, e.file_path))
+            })
+            .collect(),
+    };
+
+    let label = match (app.view(), app.drilled()) {
+        (View::Dead, _) => "Dead symbols".to_string(),
+        (View::Protected, _) => "Protected symbols".to_string(),
+        (View::Directories, None) => "Directories".to_string(),
+        (View::Directories, Some(dir)) => format!("Files in {}", dir.name),
+    };
+    let mut title = format!("{} ({})", label, app.visible_len());
+    if !app.filter().is_empty() {
+        title.push_str(&format!(" matching \"{}\"", app.filter()));
+    }
+
+    let list = List::new(items)
+        .block(Block::default().title(title).borders(Borders::ALL))
+        .style(Style::default().fg(Color::White))
+        .highlight_style(
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        )
+        .highlight_symbol("> ");
+    f.render_stateful_widget(list, area, &mut app.list_state);
+}
+
+/// `name  ██████░░░░  34% dead, 18KB`
+fn density_row(name: &str, percent: f64, dead_bytes: u64) -> ListItem<'static> {
+    let filled = ((percent / 100.0) * BAR_WIDTH as f64).round() as usize;
+    ListItem::new(Line::from(vec![
+        Span::raw(format!("{:<24} ", name)),
+        Span::styled("█".repeat(filled), Style::default().fg(Color::Red)),
+        Span::styled(
+            "░".repeat(BAR_WIDTH - filled.min(BAR_WIDTH)),
+            Style::default().fg(Color::DarkGray),
+        ),
+        Span::raw(format!(
+            " {:>3.0}% dead, {}",
+            percent,
+            format_bytes(dead_bytes)
+        )),
+    ]))
+}
+
+fn draw_detail(f: &mut Frame, app: &App, area: Rect) {
+    let totals = |name: &str, symbols: (usize, usize), bytes: (u64, u64), percent: f64| {
+        vec![
+            Line::from(name.to_string()),
+            Line::from(format!("Symbols: {} dead of {}", symbols.1, symbols.0)),
+            Line::from(format!(
+                "Bytes  : {} dead of {}",
+                format_bytes(bytes.1),
+                format_bytes(bytes.0)
+            )),
+            Line::from(format!("Density: {:.1}% dead", percent)),
+        ]
+    };
+    let lines = if let Some(d) = app.selected_dir() {
+        let mut lines = totals(
+            &format!("Dir    : {}", d.name),
+            (d.total_symbols, d.dead_symbols),
+            (d.total_bytes, d.dead_bytes),
+            d.dead_percent(),
+        );
+        lines.push(Line::from(format!("Files  : {}", d.files.len())));
+        lines
+    } else if let Some(file) = app.selected_file() {
+        totals(
+            &format!("File   : {}", file.path),
+            (file.total_symbols, file.dead_symbols),
+            (file.total_bytes, file.dead_bytes),
+            file.dead_percent(),
+        )
+    } else {
+        match app.selected() {
+            Some(e) => {
+                let mut lines = vec![
+                    Line::from(format!("Symbol : {}", e.qualified_name)),
+                    Line::from(format!("File   : {}", e.file_path)),
+                    Line::from(format!("Lines  : {}-{}", e.start_line, e.end_line)),
+                    Line::from(format!(
+                        "Size   : {} bytes",
+                        e.end_byte.saturating_sub(e.start_byte)
+                    )),
+                    Line::from(format!("Hash   : {:016x}", e.structural_hash)),
+                    Line::from(format!(
+                        "Reason : {}",
+                        e.protected_by
+                            .map(|p| format!("{:?}", p))
+                            .unwrap_or_else(|| "none (dead)".to_string())
+                    )),
+                ];
+                if let Some(detail) = &e.protection_detail {
+                    lines.push(Line::from(format!("Why    : {}", detail)));
+                }
+                lines
+            }
+            None => vec![Line::from("Nothing selected")],
+        }
+    };
+    let detail = Paragraph::new(lines)
+        .wrap(Wrap { trim: true })
+        .block(Block::default().title("Detail").borders(Borders::ALL));
+    f.render_widget(detail, area);
+}
+
+fn draw_overview(f: &mut Frame, app: &App, area: Rect) {
+    let bar_data = [
+        ("Total", app.registry().len() as u64),
+        ("Dead", app.dead_count() as u64),
+    ];
+    let barchart = BarChart::default()
+        .block(Block::default().title("Overview").borders(Borders::ALL))
+        .data(&bar_data)
+        .bar_width(10)
+        .bar_style(Style::default().fg(Color::Yellow))
+        .value_style(Style::default().fg(Color::Black).bg(Color::Yellow));
+    f.render_widget(barchart, area);
+}
+
+fn draw_footer(f: &mut Frame, app: &App, area: Rect) {
+    let footer = match app.mode() {
+        Mode::Search => {
+            Paragraph::new(format!("/{}", app.filter())).style(Style::default().fg(Color::Yellow))
+        }
+        Mode::Normal => Paragraph::new(
+            "q quit  / search  Esc clear  Tab dead/protected  d directories  Enter open  ⌫ up  ↑↓ PgUp PgDn move",
+        )
+        .style(Style::default().fg(Color::DarkGray)),
+    };
+    f.render_widget(footer, area);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use ratatui::{backend::TestBackend, Terminal};
+
+    fn render(app: &mut App, width: u16, height: u16) -> Vec<String> {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal.draw(|f| draw(f, app)).unwrap();
+        let buffer = terminal.backend().buffer();
+        (0..height)
+            .map(|y| (0..width).map(|x| buffer.get(x, y).symbol()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_render_lists_every_dead_symbol_with_detail() {
+        let registry = crate::app::tests::sample_registry();
+        let mut app = App::new(&registry);
+        let screen = render(&mut app, 120, 20).join("\n");
+
+        assert!(screen.contains("Dead symbols (3)"));
+        assert!(screen.contains("> export_xml (900 bytes)"));
+        assert!(screen.contains("legacy_sync (100 bytes)"));
+        assert!(screen.contains("Lines  : 10-20"));
+        assert!(screen.contains("Hash   : 0000000000000abc"));
+        assert!(screen.contains("Reason : none (dead)"));
+        // 20 rows - status (3) - footer (1) - list borders (2).
+        assert_eq!(app.page_size, 14);
+    }
+
+    #[test]
+    fn test_render_search_prompt_and_protected_reason() {
+        let registry = crate::app::tests::sample_registry();
+        let mut app = App::new(&registry);
+        for code in [KeyCode::Tab, KeyCode::Char('/'), KeyCode::Char('m')] {
+            app.handle_key(KeyEvent::new(code, KeyModifiers::NONE));
+        }
+        let screen = render(&mut app, 120, 20);
+
+        assert!(screen[19].starts_with("/m"));
+        let screen = screen.join("\n");
+        assert!(screen.contains("Protected symbols (1) matching \"m\""));
+        assert!(screen.contains("Reason : EntryPoint"));
+        // The detail wraps inside the pane.
+        assert!(screen.contains("Why    : stage 2: wisdom rule: entry point"));
+        assert!(screen.contains("│`main`"));
+    }
+
+    #[test]
+    fn test_render_directory_density_bars() {
+        let registry = crate::app::tests::sample_registry();
+        let mut app = App::new(&registry);
+        app.handle_key(KeyEvent::new(KeyCode::Char('d'), KeyModifiers::NONE));
+        let screen = render(&mut app, 140, 20).join("\n");
+
+        assert!(screen.contains("Directories (1)"));
+        // 1000 of 1050 symbol bytes are dead: 95% of 20 cells rounds to 19.
+        let bar = format!("{}{}  95% dead, 1000B", "█".repeat(19), "░");
+        assert!(screen.contains(&format!("> ./{}{}", " ".repeat(23), bar)));
+        assert!(screen.contains("Symbols: 3 dead of 4"));
+        assert!(screen.contains("Files  : 3"));
+
+        app.handle_key(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        let screen = render(&mut app, 140, 20).join("\n");
+        assert!(screen.contains("Files in ./ (3)"));
+        assert!(screen.contains("File   : reports.py"));
+    }
+}
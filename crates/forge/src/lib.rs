@@ -1,19 +1,27 @@
 //! # The Forge: Structural Identity Engine
 //!
-//! Computes a deterministic structural hash of Python AST nodes using
+//! Computes a deterministic structural hash of tree-sitter AST nodes using
 //! **alpha-normalization** — identifier names, string contents, and comments
 //! are erased so that two functions with identical logic but different naming
 //! produce the same `u64` hash.
 //!
 //! ## Alpha-Normalization Rule
-//! The following node kinds are **skipped** (not hashed):
+//! The following Python node kinds are **skipped** (not hashed):
 //! - `identifier`           — variable/function/parameter names
 //! - `string` / `string_content` / `string_start` / `string_end` — literal text
 //! - `comment`              — source comments
 //!
+//! Other grammars name the same things differently; the `*_with` functions take
+//! a skip list such as [`RUST_SKIP_KINDS`], [`JS_SKIP_KINDS`] or [`CPP_SKIP_KINDS`].
+//!
 //! Everything else (operator tokens, control-flow keywords, block structure,
 //! `kind_id` sequence) **is** hashed, preserving the structural skeleton.
 //!
+//! A function's [`StructuralFingerprint`] also mixes in its parameter count and
+//! records how many structural nodes the body has, so trivial bodies (`pass`,
+//! `raise NotImplementedError`, `return self._x`) can be told apart from real
+//! duplicated logic.
+//!
 //! ## Example
 //! ```ignore
 //! // def add(a, b): return a + b
@@ -21,11 +29,14 @@
 //! // → same structural hash
 //! ```
 
+pub mod similarity;
+
+use std::collections::BTreeMap;
 use tree_sitter::Node;
 
-/// Node kinds that carry only naming information and must be erased
-/// during alpha-normalization.
-const SKIP_KINDS: &[&str] = &[
+/// Python node kinds that carry only naming information and must be erased
+/// during alpha-normalization. The default skip list.
+pub const SKIP_KINDS: &[&str] = &[
     "identifier",
     "string",
     "string_content",
@@ -36,6 +47,48 @@ const SKIP_KINDS: &[&str] = &[
     "type_comment",
 ];
 
+/// Rust counterpart of [`SKIP_KINDS`].
+pub const RUST_SKIP_KINDS: &[&str] = &[
+    "identifier",
+    "field_identifier",
+    "shorthand_field_identifier",
+    "type_identifier",
+    "string_literal",
+    "raw_string_literal",
+    "char_literal",
+    "string_content",
+    "escape_sequence",
+    "line_comment",
+    "block_comment",
+];
+
+/// JavaScript / TypeScript counterpart of [`SKIP_KINDS`].
+pub const JS_SKIP_KINDS: &[&str] = &[
+    "identifier",
+    "property_identifier",
+    "shorthand_property_identifier",
+    "private_property_identifier",
+    "type_identifier",
+    "string",
+    "string_fragment",
+    "escape_sequence",
+    "comment",
+];
+
+/// C++ counterpart of [`SKIP_KINDS`].
+pub const CPP_SKIP_KINDS: &[&str] = &[
+    "identifier",
+    "field_identifier",
+    "type_identifier",
+    "namespace_identifier",
+    "string_literal",
+    "raw_string_literal",
+    "char_literal",
+    "string_content",
+    "escape_sequence",
+    "comment",
+];
+
 /// Computes a deterministic structural hash for the given AST node.
 ///
 /// The hash encodes the **shape** of the syntax tree — the sequence of
@@ -54,8 +107,73 @@ const SKIP_KINDS: &[&str] = &[
 /// shape and operator structure will produce identical values regardless of
 /// variable naming.
 pub fn compute_structural_hash(node: Node<'_>, source: &[u8]) -> u64 {
+    compute_structural_hash_with(node, source, SKIP_KINDS)
+}
+
+/// Same as [`compute_structural_hash`], erasing `skip_kinds` instead of the
+/// Python [`SKIP_KINDS`].
+pub fn compute_structural_hash_with(node: Node<'_>, source: &[u8], skip_kinds: &[&str]) -> u64 {
     let mut hasher = blake3::Hasher::new();
-    hash_node_recursive(&mut hasher, node, source);
+    hash_node_recursive(&mut hasher, node, source, skip_kinds);
+    truncate(hasher)
+}
+
+/// Structural identity of a function: body shape, arity and size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct StructuralFingerprint {
+    /// Alpha-normalized hash of the body, mixed with `param_count`.
+    pub hash: u64,
+    /// Structural nodes in the body (the nodes that feed the hash).
+    pub node_count: u32,
+    /// Declared parameters, including `self`, `*args` and `**kwargs`.
+    pub param_count: u8,
+}
+
+/// Computes the fingerprint of a `function_definition` node.
+///
+/// Returns `None` when the node has no `body` field.
+pub fn compute_fingerprint(function: Node<'_>, source: &[u8]) -> Option<StructuralFingerprint> {
+    compute_fingerprint_with(function, source, SKIP_KINDS)
+}
+
+/// Same as [`compute_fingerprint`] for any grammar whose function node has a
+/// `body` field, erasing `skip_kinds`. Parameters are read from the node's
+/// `parameters` field, or from its `declarator` (C/C++ `function_declarator`).
+pub fn compute_fingerprint_with(
+    function: Node<'_>,
+    source: &[u8],
+    skip_kinds: &[&str],
+) -> Option<StructuralFingerprint> {
+    let body = function.child_by_field_name("body")?;
+    let param_count = function
+        .child_by_field_name("parameters")
+        .or_else(|| {
+            function
+                .child_by_field_name("declarator")?
+                .child_by_field_name("parameters")
+        })
+        .map(|params| {
+            let mut cursor = params.walk();
+            let count = params
+                .named_children(&mut cursor)
+                .filter(|p| p.kind() != "comment")
+                .count();
+            count.min(u8::MAX as usize) as u8
+        })
+        .unwrap_or(0);
+
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(&[param_count]);
+    let node_count = hash_node_recursive(&mut hasher, body, source, skip_kinds);
+    Some(StructuralFingerprint {
+        hash: truncate(hasher),
+        node_count,
+        param_count,
+    })
+}
+
+/// First 8 bytes (LE) of the BLAKE3 digest.
+fn truncate(hasher: blake3::Hasher) -> u64 {
     let digest = hasher.finalize();
     u64::from_le_bytes(digest.as_bytes()[..8].try_into().expect("blake3 ≥ 8 bytes"))
 }
@@ -81,48 +199,171 @@ impl DuplicateGroup {
     }
 }
 
+/// A symbol that carries a structural fingerprint and a source location.
+///
+/// Implemented by `anatomist::Entity`; kept as a trait so `forge` stays
+/// independent of the parser crate.
+pub trait StructuralSymbol {
+    /// Alpha-normalized structural hash, or `None` for classes/assignments.
+    fn structural_hash(&self) -> Option<u64>;
+    /// Structural nodes in the body ([`StructuralFingerprint::node_count`]).
+    fn structural_nodes(&self) -> u32;
+    /// Normalized file path (UTF-8, forward slashes).
+    fn file_path(&self) -> &str;
+    /// Qualified name (e.g. `"ClassName.method_name"`).
+    fn qualified_name(&self) -> &str;
+    /// Byte offset of the first character of the definition (inclusive).
+    fn start_byte(&self) -> u32;
+    /// Byte offset just past the definition (exclusive).
+    fn end_byte(&self) -> u32;
+    /// Number of source lines spanned by the definition.
+    fn line_count(&self) -> u32;
+}
+
+/// Filtering options for [`find_duplicate_groups`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DedupOptions {
+    /// Symbols spanning fewer lines than this are ignored.
+    ///
+    /// Trivial stubs (`def f():\n    pass`) share one structural hash; without
+    /// this floor every empty stub in a project collapses into one giant group.
+    pub min_lines: u32,
+    /// Symbols whose body has fewer structural nodes than this are ignored.
+    ///
+    /// Catches stubs that pass `min_lines` only because of a docstring:
+    /// `raise NotImplementedError` is 3 nodes, `return self._x` 5.
+    pub min_nodes: u32,
+}
+
+impl Default for DedupOptions {
+    fn default() -> Self {
+        Self {
+            min_lines: 3,
+            min_nodes: 8,
+        }
+    }
+}
+
+/// Buckets symbols by structural hash and node count and returns every group
+/// with ≥ 2 members.
+///
+/// Symbols without a hash, shorter than [`DedupOptions::min_lines`], or
+/// smaller than [`DedupOptions::min_nodes`] are skipped.
+/// Output is deterministic: groups are sorted by hash, members by
+/// `(file_path, qualified_name, start_byte)`.
+pub fn find_duplicate_groups<T: StructuralSymbol>(
+    symbols: &[T],
+    options: &DedupOptions,
+) -> Vec<DuplicateGroup> {
+    // (hash, node_count) → members.
+    type Member = (String, String, u32, u32);
+    let mut buckets: BTreeMap<(u64, u32), Vec<Member>> = BTreeMap::new();
+
+    for symbol in symbols {
+        let Some(hash) = symbol.structural_hash() else {
+            continue;
+        };
+        if symbol.line_count() < options.min_lines || symbol.structural_nodes() < options.min_nodes
+        {
+            continue;
+        }
+        buckets
+            .entry((hash, symbol.structural_nodes()))
+            .or_default()
+            .push((
+                symbol.file_path().to_string(),
+                symbol.qualified_name().to_string(),
+                symbol.start_byte(),
+                symbol.end_byte(),
+            ));
+    }
+
+    buckets
+        .into_iter()
+        .filter(|(_, members)| members.len() >= 2)
+        .map(|((hash, _), mut members)| {
+            members.sort();
+            DuplicateGroup { hash, members }
+        })
+        .collect()
+}
+
 // ---------------------------------------------------------------------------
 // Internal recursive walker
 // ---------------------------------------------------------------------------
 
-/// Returns `true` if `node` (or any of its descendants) will contribute to the hash.
+#[cfg(test)]
+thread_local! {
+    /// Nodes entered by [`walk_structural`] on this thread, for the linear-walk test.
+    static VISITS: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+/// Calls `emit` on every node of `node`'s subtree that contributes to the hash,
+/// in depth-first pre-order; returns how many there were.
 ///
-/// A node contributes when it is NOT in `SKIP_KINDS` AND either:
+/// A node contributes when it is NOT in `skip_kinds` AND either:
 /// - it is a leaf node, OR
 /// - at least one of its children contributes.
 ///
-/// This pre-check lets us skip container nodes whose entire subtree is
-/// alpha-normalized away — most importantly `expression_statement` nodes
-/// that wrap docstring literals at the top of a function body.
-fn has_structural_content(node: Node<'_>) -> bool {
-    if SKIP_KINDS.contains(&node.kind()) {
-        return false;
-    }
-    if node.child_count() == 0 {
-        return true; // Non-skipped leaf — contributes its kind_id.
-    }
-    let mut cursor = node.walk();
-    let result = node
-        .children(&mut cursor)
-        .any(|child| has_structural_content(child));
-    result
+/// This drops container nodes whose entire subtree is alpha-normalized away —
+/// most importantly `expression_statement` nodes that wrap docstring literals
+/// at the top of a function body. A container waits on a pending stack until
+/// its first contributing leaf turns up, so every node is visited once.
+pub(crate) fn walk_structural<'t>(
+    node: Node<'t>,
+    skip_kinds: &[&str],
+    emit: &mut dyn FnMut(Node<'t>),
+) -> u32 {
+    walk(node, skip_kinds, &mut Vec::new(), emit)
 }
 
-fn hash_node_recursive(hasher: &mut blake3::Hasher, node: Node<'_>, _source: &[u8]) {
-    // Skip nodes that are either alpha-normalized away or have no structural
-    // descendants (e.g., a docstring `expression_statement`).
-    if !has_structural_content(node) {
-        return;
-    }
+fn walk<'t>(
+    node: Node<'t>,
+    skip_kinds: &[&str],
+    pending: &mut Vec<Node<'t>>,
+    emit: &mut dyn FnMut(Node<'t>),
+) -> u32 {
+    #[cfg(test)]
+    VISITS.with(|v| v.set(v.get() + 1));
 
-    // Hash the structural kind_id (u16 → 2 bytes).
-    hasher.update(&node.kind_id().to_le_bytes());
+    if skip_kinds.contains(&node.kind()) {
+        return 0;
+    }
+    if node.child_count() == 0 {
+        // Non-skipped leaf: it and every ancestor still pending contribute.
+        let count = pending.len() as u32 + 1;
+        for ancestor in pending.drain(..) {
+            emit(ancestor);
+        }
+        emit(node);
+        return count;
+    }
 
-    // Recurse into children (depth-first pre-order).
+    pending.push(node);
+    let depth = pending.len();
+    let mut count = 0;
     let mut cursor = node.walk();
     for child in node.children(&mut cursor) {
-        hash_node_recursive(hasher, child, _source);
+        count += walk(child, skip_kinds, pending, emit);
+    }
+    // Still pending: nothing below contributed, so neither does `node`.
+    if pending.len() == depth {
+        pending.pop();
     }
+    count
+}
+
+/// Feeds `node`'s structural subtree to `hasher`; returns the number of nodes hashed.
+fn hash_node_recursive(
+    hasher: &mut blake3::Hasher,
+    node: Node<'_>,
+    _source: &[u8],
+    skip_kinds: &[&str],
+) -> u32 {
+    // Each structural kind_id is hashed as 2 bytes (u16 LE).
+    walk_structural(node, skip_kinds, &mut |n| {
+        hasher.update(&n.kind_id().to_le_bytes());
+    })
 }
 
 #[cfg(test)]
@@ -191,4 +432,212 @@ mod tests {
         let h2 = body_hash("def foo(x):\n    return x * 2\n");
         assert_eq!(h1, h2);
     }
+
+    struct Sym {
+        hash: Option<u64>,
+        file: &'static str,
+        name: &'static str,
+        lines: u32,
+        nodes: u32,
+    }
+
+    impl StructuralSymbol for Sym {
+        fn structural_hash(&self) -> Option<u64> {
+            self.hash
+        }
+        fn structural_nodes(&self) -> u32 {
+            self.nodes
+        }
+        fn file_path(&self) -> &str {
+            self.file
+        }
+        fn qualified_name(&self) -> &str {
+            self.name
+        }
+        fn start_byte(&self) -> u32 {
+            0
+        }
+        fn end_byte(&self) -> u32 {
+            10
+        }
+        fn line_count(&self) -> u32 {
+            self.lines
+        }
+    }
+
+    fn sym(hash: Option<u64>, file: &'static str, name: &'static str, lines: u32) -> Sym {
+        Sym {
+            hash,
+            file,
+            name,
+            lines,
+            nodes: 20,
+        }
+    }
+
+    /// Fingerprint of the first function in `src`.
+    fn fingerprint(src: &str) -> StructuralFingerprint {
+        let (tree, bytes) = parse_and_get_body(src);
+        let mut node = tree.root_node().child(0).unwrap();
+        if node.kind() == "decorated_definition" {
+            node = node.child_by_field_name("definition").unwrap();
+        }
+        compute_fingerprint(node, &bytes).unwrap()
+    }
+
+    /// A symbol built from real source, as the parser would report it.
+    fn parsed(file: &'static str, name: &'static str, src: &str) -> Sym {
+        let fp = fingerprint(src);
+        Sym {
+            hash: Some(fp.hash),
+            file,
+            name,
+            lines: src.trim_end().lines().count() as u32,
+            nodes: fp.node_count,
+        }
+    }
+
+    #[test]
+    fn test_cross_file_duplicates_grouped() {
+        let symbols = vec![
+            sym(Some(7), "b.py", "copy", 5),
+            sym(Some(7), "a.py", "original", 5),
+            sym(Some(9), "a.py", "unique", 5),
+            sym(None, "a.py", "SomeClass", 5),
+        ];
+        let groups = find_duplicate_groups(&symbols, &DedupOptions::default());
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].hash, 7);
+        assert_eq!(groups[0].len(), 2);
+        assert_eq!(groups[0].members[0].0, "a.py");
+        assert_eq!(groups[0].members[1].0, "b.py");
+    }
+
+    #[test]
+    fn test_trivial_bodies_filtered() {
+        let symbols = vec![
+            sym(Some(1), "a.py", "stub_a", 2),
+            sym(Some(1), "b.py", "stub_b", 2),
+        ];
+        assert!(find_duplicate_groups(&symbols, &DedupOptions::default()).is_empty());
+        let groups = find_duplicate_groups(
+            &symbols,
+            &DedupOptions {
+                min_lines: 0,
+                ..Default::default()
+            },
+        );
+        assert_eq!(groups.len(), 1);
+    }
+
+    #[test]
+    fn test_fingerprint_counts_params_and_nodes() {
+        let fp = fingerprint("def f(self, a, *args, b=1, **kw):\n    pass\n");
+        assert_eq!(fp.param_count, 5);
+        // block → pass_statement → `pass`
+        assert_eq!(fp.node_count, 3);
+
+        // Same body, different arity: different hash.
+        let one = fingerprint("def f(a):\n    return a + 1\n");
+        let two = fingerprint("def f(a, b):\n    return a + 1\n");
+        assert_ne!(one.hash, two.hash);
+        assert_eq!(one.node_count, two.node_count);
+    }
+
+    #[test]
+    fn test_documented_stubs_are_not_duplicates() {
+        let stub_a = "def load(self):\n    \"\"\"Load the thing.\n\n    Subclasses override.\n    \"\"\"\n    raise NotImplementedError\n";
+        let stub_b = "def save(self):\n    \"\"\"Save the thing.\n\n    Subclasses override.\n    \"\"\"\n    raise NotImplementedError\n";
+        let symbols = vec![
+            parsed("a.py", "load", stub_a),
+            parsed("b.py", "save", stub_b),
+        ];
+        assert_eq!(symbols[0].hash, symbols[1].hash);
+        assert!(symbols[0].lines >= DedupOptions::default().min_lines);
+        assert!(find_duplicate_groups(&symbols, &DedupOptions::default()).is_empty());
+    }
+
+    #[test]
+    fn test_real_identical_functions_still_grouped() {
+        let body = |name: &str, var: &str| {
+            format!(
+                "def {name}(rows, threshold):\n    {var} = []\n    total = 0\n    for row in rows:\n        if row is None:\n            continue\n        value = row.get('amount', 0)\n        if value > threshold:\n            {var}.append(value)\n            total += value\n        elif value < 0:\n            raise ValueError(row)\n    if not {var}:\n        return None\n    return total / len({var})\n"
+            )
+        };
+        let a = body("mean_over", "kept");
+        let b = body("average_above", "selected");
+        assert_eq!(a.lines().count(), 15);
+        let symbols = vec![
+            parsed("reports.py", "mean_over", &a),
+            parsed("legacy/stats.py", "average_above", &b),
+        ];
+        let groups = find_duplicate_groups(&symbols, &DedupOptions::default());
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].len(), 2);
+    }
+
+    #[test]
+    fn test_deterministic_ordering() {
+        let symbols = vec![
+            sym(Some(30), "z.py", "b", 4),
+            sym(Some(10), "m.py", "y", 4),
+            sym(Some(30), "a.py", "a", 4),
+            sym(Some(10), "m.py", "x", 4),
+        ];
+        let groups = find_duplicate_groups(&symbols, &DedupOptions::default());
+        let hashes: Vec<u64> = groups.iter().map(|g| g.hash).collect();
+        assert_eq!(hashes, vec![10, 30]);
+        let names: Vec<&str> = groups[0].members.iter().map(|m| m.1.as_str()).collect();
+        assert_eq!(names, vec!["x", "y"]);
+        assert_eq!(groups[1].members[0].0, "a.py");
+    }
+
+    /// Every node in `node`'s subtree, `node` included.
+    fn subtree_size(node: Node<'_>) -> usize {
+        let mut cursor = node.walk();
+        let children: usize = node.children(&mut cursor).map(subtree_size).sum();
+        1 + children
+    }
+
+    /// The contributing nodes by the definition, checking each subtree afresh.
+    fn contributing_kinds(node: Node<'_>, out: &mut Vec<u16>) {
+        fn contributes(node: Node<'_>) -> bool {
+            let mut cursor = node.walk();
+            !SKIP_KINDS.contains(&node.kind())
+                && (node.child_count() == 0 || node.children(&mut cursor).any(contributes))
+        }
+        if contributes(node) {
+            out.push(node.kind_id());
+            let mut cursor = node.walk();
+            for child in node.children(&mut cursor) {
+                contributing_kinds(child, out);
+            }
+        }
+    }
+
+    #[test]
+    fn test_walk_emits_contributing_nodes_in_preorder() {
+        let src = "def f(a):\n    \"\"\"Docstring.\"\"\"\n    'note'\n    if a:\n        return [x for x in a if x]\n    return {'k': a}\n";
+        let (tree, _) = parse_and_get_body(src);
+        let mut expected = Vec::new();
+        contributing_kinds(tree.root_node(), &mut expected);
+        let mut emitted = Vec::new();
+        let count = walk_structural(tree.root_node(), SKIP_KINDS, &mut |n| {
+            emitted.push(n.kind_id())
+        });
+        assert_eq!(emitted, expected);
+        assert_eq!(count as usize, expected.len());
+    }
+
+    #[test]
+    fn test_walk_visits_each_node_once() {
+        let src = testkit::deep_function(300);
+        let (tree, bytes) = parse_and_get_body(&src);
+        let function = tree.root_node().named_child(0).unwrap();
+        let body = function.child_by_field_name("body").unwrap();
+
+        VISITS.with(|v| v.set(0));
+        compute_structural_hash(body, &bytes);
+        assert_eq!(VISITS.with(|v| v.get()), subtree_size(body));
+    }
 }
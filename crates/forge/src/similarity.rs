@@ -0,0 +1,519 @@
+//! # Near-Duplicate Detection
+//!
+//! Exact structural hashing misses copies that gained a logging line or had
+//! one condition tweaked. This module scores how much of two function bodies'
+//! structure they share.
+//!
+//! ## Fingerprint
+//! A body is reduced to the same alpha-normalized token stream the structural
+//! hash uses (node `kind_id`s, pre-order, identifiers/strings/comments erased).
+//! Overlapping 4-grams of that stream ("shingles") form a set; two bodies are
+//! compared by the Jaccard index of their shingle sets.
+//!
+//! ## Candidate search
+//! Comparing every pair is quadratic. Each shingle set is summarised by a
+//! 64-value MinHash signature split into 16 bands of 4; only functions that
+//! agree on an entire band are scored. A pair with similarity 0.85 shares a
+//! band with probability > 0.999, a pair at 0.3 with about 0.12.
+
+use crate::StructuralSymbol;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use tree_sitter::{Node, Tree};
+
+/// Tokens per shingle.
+const SHINGLE: usize = 4;
+/// MinHash signature length.
+const NUM_HASHES: usize = 64;
+/// Signature rows per LSH band (`NUM_HASHES / ROWS` bands).
+const ROWS: usize = 4;
+/// Bodies with fewer structural tokens than this are not compared: stubs are
+/// all alike and say nothing about copied logic.
+pub const MIN_TOKENS: usize = 16;
+
+/// One member of a [`SimilarGroup`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimilarMember {
+    pub file_path: String,
+    pub qualified_name: String,
+    /// 1-based, inclusive line range of the definition.
+    pub start_line: u32,
+    pub end_line: u32,
+    /// 1-based, inclusive line ranges of this member's body with no
+    /// counterpart in the other members. Empty for structurally equal copies.
+    pub differing_lines: Vec<(u32, u32)>,
+}
+
+/// Functions whose bodies are pairwise at least `threshold` similar.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimilarGroup {
+    /// Lowest pairwise similarity within the group, in `[0, 1]`.
+    pub score: f32,
+    /// Sorted by `(file_path, start_line)`.
+    pub members: Vec<SimilarMember>,
+}
+
+/// Comparable summary of one function body.
+#[derive(Debug, Clone)]
+pub struct BodyFingerprint {
+    /// Sorted, de-duplicated shingle hashes.
+    shingles: Vec<u64>,
+    /// `(line, hash of the tokens starting on it)` for every line with structure.
+    lines: Vec<(u32, u64)>,
+    token_count: usize,
+}
+
+impl BodyFingerprint {
+    /// Fingerprints a function body (`block`) node. Line numbers are 1-based.
+    pub fn of_body(body: Node<'_>) -> Self {
+        let mut tokens = Vec::new();
+        collect_tokens(body, &mut tokens);
+
+        let kinds: Vec<u64> = tokens.iter().map(|&(k, _)| u64::from(k)).collect();
+        let mut shingles: Vec<u64> = kinds
+            .windows(SHINGLE.min(kinds.len()).max(1))
+            .map(|w| w.iter().fold(0x9e37_79b9_7f4a_7c15, |h, &k| mix(h ^ k)))
+            .collect();
+        shingles.sort_unstable();
+        shingles.dedup();
+
+        let mut lines: Vec<(u32, u64)> = Vec::new();
+        for &(kind, row) in &tokens {
+            match lines.last_mut() {
+                Some((line, h)) if *line == row => *h = mix(*h ^ u64::from(kind)),
+                _ => lines.push((row, mix(u64::from(kind)))),
+            }
+        }
+
+        Self {
+            shingles,
+            lines,
+            token_count: tokens.len(),
+        }
+    }
+
+    /// Jaccard index of the two shingle sets.
+    pub fn similarity(&self, other: &Self) -> f32 {
+        let (a, b) = (&self.shingles, &other.shingles);
+        if a.is_empty() && b.is_empty() {
+            return 1.0;
+        }
+        let (mut i, mut j, mut shared) = (0, 0, 0usize);
+        while i < a.len() && j < b.len() {
+            match a[i].cmp(&b[j]) {
+                std::cmp::Ordering::Less => i += 1,
+                std::cmp::Ordering::Greater => j += 1,
+                std::cmp::Ordering::Equal => {
+                    shared += 1;
+                    i += 1;
+                    j += 1;
+                }
+            }
+        }
+        shared as f32 / (a.len() + b.len() - shared) as f32
+    }
+
+    fn signature(&self) -> [u64; NUM_HASHES] {
+        let mut sig = [u64::MAX; NUM_HASHES];
+        for &s in &self.shingles {
+            for (seed, slot) in sig.iter_mut().enumerate() {
+                *slot = (*slot).min(mix(s ^ SEEDS[seed]));
+            }
+        }
+        sig
+    }
+
+    /// Lines of `self` not aligned with a line of `other` (longest common subsequence).
+    fn unmatched_lines(&self, other: &Self) -> Vec<u32> {
+        let (a, b) = (&self.lines, &other.lines);
+        let mut lcs = vec![vec![0u32; b.len() + 1]; a.len() + 1];
+        for i in (0..a.len()).rev() {
+            for j in (0..b.len()).rev() {
+                lcs[i][j] = if a[i].1 == b[j].1 {
+                    lcs[i + 1][j + 1] + 1
+                } else {
+                    lcs[i + 1][j].max(lcs[i][j + 1])
+                };
+            }
+        }
+        let (mut i, mut j, mut unmatched) = (0, 0, Vec::new());
+        while i < a.len() {
+            if j < b.len() && a[i].1 == b[j].1 {
+                i += 1;
+                j += 1;
+            } else if j < b.len() && lcs[i][j + 1] >= lcs[i + 1][j] {
+                j += 1;
+            } else {
+                unmatched.push(a[i].0);
+                i += 1;
+            }
+        }
+        unmatched
+    }
+}
+
+/// Groups functions whose bodies are at least `threshold` similar.
+///
+/// `tree_of` returns the Python syntax tree of a file given a symbol's
+/// [`StructuralSymbol::file_path`]: the one the symbols were extracted from,
+/// so nothing is parsed twice. Symbols without a structural hash (classes, assignments) and bodies under
+/// [`MIN_TOKENS`] tokens are skipped. Every pair within a returned group
+/// scores ≥ `threshold`. Groups are sorted by descending score, then by
+/// their first member.
+pub fn find_similar_groups<'t, T, F>(
+    symbols: &[T],
+    mut tree_of: F,
+    threshold: f32,
+) -> Vec<SimilarGroup>
+where
+    T: StructuralSymbol,
+    F: FnMut(&str) -> Option<&'t Tree>,
+{
+    let mut by_file: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+    for (i, s) in symbols.iter().enumerate() {
+        if s.structural_hash().is_some() {
+            by_file.entry(s.file_path()).or_default().push(i);
+        }
+    }
+    let mut fingerprints: Vec<(usize, u32, u32, BodyFingerprint)> = Vec::new();
+    for (file, indices) in by_file {
+        let Some(tree) = tree_of(file) else {
+            continue;
+        };
+        for i in indices {
+            let Some((def, body)) = function_at(tree, symbols[i].start_byte() as usize) else {
+                continue;
+            };
+            let fp = BodyFingerprint::of_body(body);
+            if fp.token_count < MIN_TOKENS {
+                continue;
+            }
+            let start = def.start_position().row as u32 + 1;
+            let end = def.end_position().row as u32 + 1;
+            fingerprints.push((i, start, end, fp));
+        }
+    }
+
+    // LSH banding: candidate pairs share at least one whole band.
+    let mut buckets: HashMap<(usize, [u64; ROWS]), Vec<usize>> = HashMap::new();
+    for (f, (_, _, _, fp)) in fingerprints.iter().enumerate() {
+        let sig = fp.signature();
+        for (band, rows) in sig.chunks_exact(ROWS).enumerate() {
+            let key: [u64; ROWS] = rows.try_into().expect("chunk of ROWS");
+            buckets.entry((band, key)).or_default().push(f);
+        }
+    }
+    let mut candidates: HashSet<(usize, usize)> = HashSet::new();
+    for members in buckets.values() {
+        for (x, &a) in members.iter().enumerate() {
+            for &b in &members[x + 1..] {
+                candidates.insert((a.min(b), a.max(b)));
+            }
+        }
+    }
+
+    let mut scores: HashMap<(usize, usize), f32> = HashMap::new();
+    let mut pairs: Vec<(f32, usize, usize)> = Vec::new();
+    for (a, b) in candidates {
+        let score = fingerprints[a].3.similarity(&fingerprints[b].3);
+        if score >= threshold {
+            scores.insert((a, b), score);
+            pairs.push((score, a, b));
+        }
+    }
+    // Strongest pairs first; ties broken by index for determinism.
+    pairs.sort_by(|x, y| y.0.total_cmp(&x.0).then((x.1, x.2).cmp(&(y.1, y.2))));
+
+    // Complete linkage: clusters merge only if every cross pair passed.
+    let mut cluster_of: Vec<usize> = (0..fingerprints.len()).collect();
+    let mut clusters: Vec<Vec<usize>> = (0..fingerprints.len()).map(|f| vec![f]).collect();
+    let score_of = |a: usize, b: usize| scores.get(&(a.min(b), a.max(b))).copied();
+    for (_, a, b) in pairs {
+        let (ca, cb) = (cluster_of[a], cluster_of[b]);
+        if ca == cb {
+            continue;
+        }
+        let linked = clusters[ca]
+            .iter()
+            .all(|&x| clusters[cb].iter().all(|&y| score_of(x, y).is_some()));
+        if linked {
+            let moved = std::mem::take(&mut clusters[cb]);
+            for &m in &moved {
+                cluster_of[m] = ca;
+            }
+            clusters[ca].extend(moved);
+        }
+    }
+
+    let mut groups: Vec<SimilarGroup> = clusters
+        .into_iter()
+        .filter(|c| c.len() >= 2)
+        .map(|mut cluster| {
+            cluster.sort_by(|&x, &y| {
+                let (sx, sy) = (&symbols[fingerprints[x].0], &symbols[fingerprints[y].0]);
+                (sx.file_path(), fingerprints[x].1).cmp(&(sy.file_path(), fingerprints[y].1))
+            });
+            let mut score = 1.0f32;
+            for (n, &x) in cluster.iter().enumerate() {
+                for &y in &cluster[n + 1..] {
+                    score = score.min(score_of(x, y).unwrap_or(0.0));
+                }
+            }
+            let members = cluster
+                .iter()
+                .map(|&x| {
+                    let (i, start_line, end_line, fp) = &fingerprints[x];
+                    let mut differing: Vec<u32> = cluster
+                        .iter()
+                        .filter(|&&y| y != x)
+                        .flat_map(|&y| fp.unmatched_lines(&fingerprints[y].3))
+                        .collect();
+                    differing.sort_unstable();
+                    differing.dedup();
+                    SimilarMember {
+                        file_path: symbols[*i].file_path().to_string(),
+                        qualified_name: symbols[*i].qualified_name().to_string(),
+                        start_line: *start_line,
+                        end_line: *end_line,
+                        differing_lines: ranges(&differing),
+                    }
+                })
+                .collect();
+            SimilarGroup { score, members }
+        })
+        .collect();
+    groups.sort_by(|x, y| {
+        y.score.total_cmp(&x.score).then_with(|| {
+            let (a, b) = (&x.members[0], &y.members[0]);
+            (&a.file_path, a.start_line).cmp(&(&b.file_path, b.start_line))
+        })
+    });
+    groups
+}
+
+/// The function definition starting at `byte` and its body, looking through
+/// a `decorated_definition` wrapper.
+fn function_at(tree: &Tree, byte: usize) -> Option<(Node<'_>, Node<'_>)> {
+    let mut node = tree.root_node().descendant_for_byte_range(byte, byte)?;
+    while node.start_byte() == byte {
+        match node.kind() {
+            "decorated_definition" => {
+                let def = node.child_by_field_name("definition")?;
+                return Some((def, def.child_by_field_name("body")?));
+            }
+            "function_definition" => return Some((node, node.child_by_field_name("body")?)),
+            _ => node = node.parent()?,
+        }
+    }
+    None
+}
+
+/// `(kind_id, 1-based line)` of every node that feeds the structural hash.
+fn collect_tokens(node: Node<'_>, out: &mut Vec<(u16, u32)>) {
+    crate::walk_structural(node, crate::SKIP_KINDS, &mut |n| {
+        out.push((n.kind_id(), n.start_position().row as u32 + 1));
+    });
+}
+
+/// Collapses sorted line numbers into inclusive ranges.
+fn ranges(lines: &[u32]) -> Vec<(u32, u32)> {
+    let mut out: Vec<(u32, u32)> = Vec::new();
+    for &line in lines {
+        match out.last_mut() {
+            Some((_, end)) if *end + 1 == line => *end = line,
+            _ => out.push((line, line)),
+        }
+    }
+    out
+}
+
+/// SplitMix64 finaliser.
+fn mix(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+/// One seed per MinHash permutation.
+const SEEDS: [u64; NUM_HASHES] = {
+    let mut seeds = [0u64; NUM_HASHES];
+    let mut i = 0;
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    while i < NUM_HASHES {
+        // xorshift64
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        seeds[i] = state;
+        i += 1;
+    }
+    seeds
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Sym {
+        file: &'static str,
+        name: &'static str,
+        start: u32,
+    }
+
+    impl StructuralSymbol for Sym {
+        fn structural_hash(&self) -> Option<u64> {
+            Some(0)
+        }
+        fn structural_nodes(&self) -> u32 {
+            0
+        }
+        fn file_path(&self) -> &str {
+            self.file
+        }
+        fn qualified_name(&self) -> &str {
+            self.name
+        }
+        fn start_byte(&self) -> u32 {
+            self.start
+        }
+        fn end_byte(&self) -> u32 {
+            0
+        }
+        fn line_count(&self) -> u32 {
+            0
+        }
+    }
+
+    const ORIGINAL: &str = "def mean_over(rows, threshold):
+    kept = []
+    total = 0
+    for row in rows:
+        if row is None:
+            continue
+        value = row.get('amount', 0)
+        if value > threshold:
+            kept.append(value)
+            total += value
+    if not kept:
+        return None
+    return total / len(kept)
+";
+
+    const WITH_LOGGING: &str = "def average_above(rows, limit):
+    selected = []
+    total = 0
+    for row in rows:
+        if row is None:
+            continue
+        value = row.get('amount', 0)
+        logger.debug('row %s', value)
+        if value > limit:
+            selected.append(value)
+            total += value
+    if not selected:
+        return None
+    return total / len(selected)
+";
+
+    const UNRELATED: &str = "def render(template, context):
+    with open(template) as fh:
+        text = fh.read()
+    for key, value in context.items():
+        text = text.replace('{' + key + '}', str(value))
+    try:
+        return text.format(**context)
+    except KeyError as exc:
+        raise ValueError(exc)
+";
+
+    fn parse(src: &str) -> Tree {
+        let mut parser = tree_sitter::Parser::new();
+        parser
+            .set_language(&tree_sitter_python::LANGUAGE.into())
+            .unwrap();
+        parser.parse(src, None).unwrap()
+    }
+
+    fn body(src: &str) -> BodyFingerprint {
+        let tree = parse(src);
+        let (_, body) = function_at(&tree, 0).unwrap();
+        BodyFingerprint::of_body(body)
+    }
+
+    #[test]
+    fn test_inserted_statement_scores_high() {
+        let score = body(ORIGINAL).similarity(&body(WITH_LOGGING));
+        assert!(score >= 0.85, "score {}", score);
+        assert_eq!(body(ORIGINAL).similarity(&body(ORIGINAL)), 1.0);
+    }
+
+    #[test]
+    fn test_unrelated_functions_score_low() {
+        let score = body(ORIGINAL).similarity(&body(UNRELATED));
+        assert!(score < 0.3, "score {}", score);
+    }
+
+    #[test]
+    fn test_groups_near_copies_with_differing_lines() {
+        let files: HashMap<&str, Tree> = HashMap::from([
+            ("a.py", parse(&format!("import logging\n\n{}", ORIGINAL))),
+            ("b.py", parse(WITH_LOGGING)),
+            ("c.py", parse(UNRELATED)),
+        ]);
+        let symbols = vec![
+            Sym {
+                file: "c.py",
+                name: "render",
+                start: 0,
+            },
+            Sym {
+                file: "b.py",
+                name: "average_above",
+                start: 0,
+            },
+            Sym {
+                file: "a.py",
+                name: "mean_over",
+                start: "import logging\n\n".len() as u32,
+            },
+        ];
+        let groups = find_similar_groups(&symbols, |f| files.get(f), 0.85);
+
+        assert_eq!(groups.len(), 1);
+        let group = &groups[0];
+        assert!(group.score >= 0.85 && group.score < 1.0);
+        let names: Vec<&str> = group
+            .members
+            .iter()
+            .map(|m| m.qualified_name.as_str())
+            .collect();
+        assert_eq!(names, vec!["mean_over", "average_above"]);
+        assert_eq!(
+            (group.members[0].start_line, group.members[0].end_line),
+            (3, 15)
+        );
+        // Only the inserted logging call has no counterpart.
+        assert!(group.members[0].differing_lines.is_empty());
+        assert_eq!(group.members[1].differing_lines, vec![(8, 8)]);
+    }
+
+    #[test]
+    fn test_stubs_and_missing_sources_are_skipped() {
+        let symbols = vec![
+            Sym {
+                file: "stub.py",
+                name: "a",
+                start: 0,
+            },
+            Sym {
+                file: "missing.py",
+                name: "b",
+                start: 0,
+            },
+        ];
+        let stub = parse("def a():\n    raise NotImplementedError\n");
+        let groups = find_similar_groups(&symbols, |f| (f == "stub.py").then_some(&stub), 0.0);
+        assert!(groups.is_empty());
+        assert_eq!(ranges(&[2, 3, 4, 7, 9, 10]), vec![(2, 4), (7, 7), (9, 10)]);
+    }
+}
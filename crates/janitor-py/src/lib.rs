@@ -0,0 +1,266 @@
+//! # janitor-py
+//!
+//! Python bindings for [`janitor::Janitor`], built with maturin:
+//!
+//! ```python
+//! import janitor_py
+//!
+//! result = janitor_py.scan("path/to/project", library=False, rules=["my-rules.json"])
+//! for entity in result.dead:
+//!     print(entity.file_path, entity.start_line, entity.qualified_name)
+//! ```
+//!
+//! Results are plain Python objects copied out of the Rust types; nothing
+//! archived (`rkyv`) crosses the boundary. The GIL is released while the
+//! pipeline runs.
+//!
+//! Everything is behind the `python` feature so the workspace builds without
+//! a Python toolchain.
+
+#![cfg(feature = "python")]
+
+use pyo3::create_exception;
+use pyo3::exceptions::PyException;
+use pyo3::prelude::*;
+use std::path::PathBuf;
+
+create_exception!(
+    janitor_py,
+    JanitorError,
+    PyException,
+    "A scan or dedup run failed."
+);
+
+fn to_py_err(e: anyhow::Error) -> PyErr {
+    JanitorError::new_err(format!("{:#}", e))
+}
+
+/// One symbol and the pipeline's verdict on it.
+#[pyclass(module = "janitor_py", frozen, get_all)]
+#[derive(Clone)]
+struct Entity {
+    name: String,
+    qualified_name: String,
+    /// Dotted module relative to the source root, e.g. `"app.api.handlers"`.
+    module_path: String,
+    /// `module_path.qualified_name`.
+    fully_qualified_name: String,
+    /// `EntityType` variant name, e.g. `"FunctionDefinition"`.
+    entity_type: String,
+    file_path: String,
+    start_line: u32,
+    end_line: u32,
+    start_byte: u32,
+    end_byte: u32,
+    parent_class: Option<String>,
+    decorators: Vec<String>,
+    /// `Protection` variant name, e.g. `"Referenced"`; `None` when dead.
+    protected_by: Option<String>,
+    /// Rule or evidence behind `protected_by`.
+    protection_reason: Option<String>,
+    /// Where that evidence lives (`file` or `file:line`), when known.
+    protection_location: Option<String>,
+}
+
+#[pymethods]
+impl Entity {
+    fn __repr__(&self) -> String {
+        format!(
+            "Entity(qualified_name={:?}, file_path={:?}, start_line={}, protected_by={})",
+            self.qualified_name,
+            self.file_path,
+            self.start_line,
+            self.protected_by
+                .as_deref()
+                .map_or("None".to_string(), |p| format!("{:?}", p))
+        )
+    }
+}
+
+impl From<&anatomist::Entity> for Entity {
+    fn from(e: &anatomist::Entity) -> Self {
+        Self {
+            name: e.name.clone(),
+            qualified_name: e.qualified_name.clone(),
+            module_path: e.module_path.clone(),
+            fully_qualified_name: e.fully_qualified_name(),
+            entity_type: format!("{:?}", e.entity_type),
+            file_path: e.file_path.clone(),
+            start_line: e.start_line,
+            end_line: e.end_line,
+            start_byte: e.start_byte,
+            end_byte: e.end_byte,
+            parent_class: e.parent_class.clone(),
+            decorators: e.decorators.clone(),
+            protected_by: e.protected_by.map(|p| format!("{:?}", p)),
+            protection_reason: e.protection_detail.as_ref().map(|d| d.reason.clone()),
+            protection_location: e
+                .protection_detail
+                .as_ref()
+                .and_then(|d| d.location.clone()),
+        }
+    }
+}
+
+/// What `scan` found.
+#[pyclass(module = "janitor_py", frozen, get_all)]
+struct ScanResult {
+    dead: Vec<Entity>,
+    protected: Vec<Entity>,
+    /// Symbols referenced only by tests.
+    test_only: Vec<Entity>,
+    /// Unreferenced symbols in `scripts/`, `bin/` and similar directories.
+    low_confidence: Vec<Entity>,
+    /// Files nothing imports.
+    orphan_files: Vec<String>,
+    /// Package directories nothing outside them imports.
+    orphan_packages: Vec<String>,
+    /// Files marked `# janitor: skip-file`.
+    skipped_files: Vec<String>,
+    /// Entities seen by the pipeline.
+    total: usize,
+    /// Symbols protected by each of the six stages.
+    stage_counts: Vec<usize>,
+    /// `(severity, code, file_path, message)` for each problem the scan met;
+    /// `file_path` is `None` for project-wide ones.
+    diagnostics: Vec<(String, String, Option<String>, String)>,
+}
+
+#[pymethods]
+impl ScanResult {
+    fn __repr__(&self) -> String {
+        format!(
+            "ScanResult(total={}, dead={}, protected={}, orphan_files={})",
+            self.total,
+            self.dead.len(),
+            self.protected.len(),
+            self.orphan_files.len()
+        )
+    }
+}
+
+impl From<&janitor::ScanResult> for ScanResult {
+    fn from(r: &janitor::ScanResult) -> Self {
+        let entities = |list: &[anatomist::Entity]| list.iter().map(Entity::from).collect();
+        Self {
+            dead: entities(&r.dead),
+            protected: entities(&r.protected),
+            test_only: entities(&r.test_only),
+            low_confidence: entities(&r.low_confidence),
+            orphan_files: r.orphan_files.clone(),
+            orphan_packages: r.orphan_packages.clone(),
+            skipped_files: r.skipped_files.clone(),
+            total: r.total,
+            stage_counts: r.stage_counts.to_vec(),
+            diagnostics: r
+                .diagnostics
+                .iter()
+                .map(|d| {
+                    let severity = d.severity.to_string();
+                    (
+                        severity,
+                        d.code.to_string(),
+                        d.file.clone(),
+                        d.message.clone(),
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+/// One copy of a duplicated function.
+#[pyclass(module = "janitor_py", frozen, get_all)]
+#[derive(Clone)]
+struct DuplicateMember {
+    file_path: String,
+    qualified_name: String,
+    start_line: u32,
+}
+
+#[pymethods]
+impl DuplicateMember {
+    fn __repr__(&self) -> String {
+        format!(
+            "DuplicateMember(file_path={:?}, qualified_name={:?}, start_line={})",
+            self.file_path, self.qualified_name, self.start_line
+        )
+    }
+}
+
+/// Functions sharing one structural hash.
+#[pyclass(module = "janitor_py", frozen, get_all)]
+struct DuplicateGroup {
+    hash: u64,
+    members: Vec<DuplicateMember>,
+}
+
+#[pymethods]
+impl DuplicateGroup {
+    fn __repr__(&self) -> String {
+        format!(
+            "DuplicateGroup(hash={:016x}, members={})",
+            self.hash,
+            self.members.len()
+        )
+    }
+}
+
+/// Runs the 6-stage dead-symbol pipeline on `path`.
+///
+/// `rules` are JSON rules files merged into the baked framework rules; an
+/// invalid file raises `JanitorError`.
+#[pyfunction]
+#[pyo3(signature = (path, library = false, rules = None))]
+fn scan(
+    py: Python<'_>,
+    path: PathBuf,
+    library: bool,
+    rules: Option<Vec<PathBuf>>,
+) -> PyResult<ScanResult> {
+    let result = py
+        .allow_threads(|| {
+            janitor::Janitor::open(path)?
+                .with_rules(&rules.unwrap_or_default(), true)?
+                .with_library_mode(library)
+                .scan()
+        })
+        .map_err(to_py_err)?;
+    Ok(ScanResult::from(&result))
+}
+
+/// Groups the structurally duplicate functions under `path`.
+#[pyfunction]
+fn find_duplicates(py: Python<'_>, path: PathBuf) -> PyResult<Vec<DuplicateGroup>> {
+    let report = py
+        .allow_threads(|| janitor::Janitor::open(path)?.dedup(&janitor::DedupOptions::default()))
+        .map_err(to_py_err)?;
+    Ok(report
+        .groups
+        .iter()
+        .map(|g| DuplicateGroup {
+            hash: g.hash,
+            members: g
+                .members
+                .iter()
+                .map(|(file_path, qualified_name, _, _)| DuplicateMember {
+                    file_path: file_path.clone(),
+                    qualified_name: qualified_name.clone(),
+                    start_line: report.start_line(file_path, qualified_name),
+                })
+                .collect(),
+        })
+        .collect())
+}
+
+#[pymodule]
+fn janitor_py(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(scan, m)?)?;
+    m.add_function(wrap_pyfunction!(find_duplicates, m)?)?;
+    m.add_class::<Entity>()?;
+    m.add_class::<ScanResult>()?;
+    m.add_class::<DuplicateGroup>()?;
+    m.add_class::<DuplicateMember>()?;
+    m.add("JanitorError", m.py().get_type_bound::<JanitorError>())?;
+    Ok(())
+}
@@ -0,0 +1,3 @@
+from pkg.util import used
+
+used()
@@ -0,0 +1,16 @@
+def used():
+    return 1
+
+
+def unused():
+    return 2
+
+
+def total(rows):
+    kept = [r for r in rows if r]
+    return sum(kept)
+
+
+def summed(rows):
+    kept = [r for r in rows if r]
+    return sum(kept)
@@ -0,0 +1,74 @@
+"""Exercises the bindings against tests/fixture.
+
+Build first: ``maturin develop -m crates/janitor-py/Cargo.toml``.
+"""
+
+from pathlib import Path
+
+import pytest
+
+import janitor_py
+
+FIXTURE = Path(__file__).parent / "fixture"
+
+
+def names(entities):
+    return sorted(e.qualified_name for e in entities)
+
+
+def test_scan_reports_dead_and_protected_symbols():
+    result = janitor_py.scan(FIXTURE)
+
+    assert names(result.dead) == ["summed", "total", "unused"]
+    used = next(e for e in result.protected if e.qualified_name == "used")
+    assert used.protected_by == "Referenced"
+    assert used.file_path.endswith("pkg/util.py")
+    assert used.fully_qualified_name == "pkg.util.used"
+    assert used.entity_type == "FunctionDefinition"
+    assert (used.start_line, used.end_line) == (1, 2)
+    assert len(result.stage_counts) == 6
+    assert result.total >= 4
+
+
+def test_scan_accepts_str_and_pathlike():
+    assert names(janitor_py.scan(str(FIXTURE)).dead) == names(
+        janitor_py.scan(FIXTURE).dead
+    )
+
+
+def test_library_mode_protects_public_symbols():
+    result = janitor_py.scan(FIXTURE, library=True)
+
+    assert result.dead == []
+
+
+def test_rules_files_are_merged(tmp_path):
+    rules = tmp_path / "rules.json"
+    rules.write_text('{"suffix_matches": ["nused"]}')
+
+    result = janitor_py.scan(FIXTURE, rules=[rules])
+
+    assert names(result.dead) == ["summed", "total"]
+    unused = next(e for e in result.protected if e.qualified_name == "unused")
+    assert unused.protected_by == "WisdomRule"
+    assert unused.protection_reason == "wisdom rule: suffix match `nused`"
+
+
+def test_invalid_rules_file_raises(tmp_path):
+    rules = tmp_path / "bad.json"
+    rules.write_text('{"immortality_rules": [{"patterns": []}]}')
+
+    with pytest.raises(janitor_py.JanitorError, match="invalid rules file"):
+        janitor_py.scan(FIXTURE, rules=[rules])
+
+
+def test_find_duplicates_groups_identical_bodies():
+    groups = janitor_py.find_duplicates(FIXTURE)
+
+    assert len(groups) == 1
+    members = sorted(groups[0].members, key=lambda m: m.start_line)
+    assert [(m.qualified_name, m.start_line) for m in members] == [
+        ("total", 9),
+        ("summed", 14),
+    ]
+    assert isinstance(groups[0].hash, int)
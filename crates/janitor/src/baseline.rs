@@ -0,0 +1,263 @@
+//! Accepted dead symbols (`scan --write-baseline` / `scan --baseline`).
+//!
+//! The file is committed alongside the code, so it holds nothing that drifts
+//! with unrelated edits: entries are keyed by project-relative path and
+//! qualified name, never by line or byte offset. The structural hash lets a
+//! function that moved to another file keep its baselined status.
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "symbols": [
+//!     {"file": "pkg/billing.py", "qualified_name": "Invoice.legacy_total", "structural_hash": "9f0c1e2d3b4a5968"}
+//!   ]
+//! }
+//! ```
+
+use anatomist::Entity;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+const FORMAT_VERSION: u32 = 1;
+
+/// A set of accepted dead symbols.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Baseline {
+    pub version: u32,
+    /// Sorted by file, then qualified name, so regenerated baselines diff cleanly.
+    pub symbols: Vec<BaselineEntry>,
+}
+
+/// One accepted dead symbol.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct BaselineEntry {
+    /// Path relative to the project root, forward slashes.
+    pub file: String,
+    pub qualified_name: String,
+    /// 16-digit hex; absent for classes and assignments, which have none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub structural_hash: Option<String>,
+}
+
+impl Baseline {
+    /// Records `dead` as the accepted state of the project at `project_root`.
+    pub fn from_dead(dead: &[Entity], project_root: &Path) -> Self {
+        let root = root_prefix(project_root);
+        let mut symbols: Vec<BaselineEntry> = dead
+            .iter()
+            .map(|e| BaselineEntry {
+                file: relative(&e.file_path, &root),
+                qualified_name: e.qualified_name.clone(),
+                structural_hash: e.structural_hash.map(|h| format!("{:016x}", h)),
+            })
+            .collect();
+        symbols.sort();
+        symbols.dedup();
+        Self {
+            version: FORMAT_VERSION,
+            symbols,
+        }
+    }
+
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("{}: {}", path.display(), e))?;
+        let baseline: Self = serde_json::from_str(&text)
+            .map_err(|e| anyhow::anyhow!("{}: invalid baseline: {}", path.display(), e))?;
+        if baseline.version != FORMAT_VERSION {
+            anyhow::bail!(
+                "{}: unsupported baseline version {} (expected {})",
+                path.display(),
+                baseline.version,
+                FORMAT_VERSION
+            );
+        }
+        Ok(baseline)
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)? + "\n")?;
+        Ok(())
+    }
+
+    /// For each of `dead`, whether the baseline accepts it.
+    ///
+    /// A symbol matches an entry with the same file and qualified name. Failing
+    /// that, it matches by structural hash an entry whose own file and name no
+    /// longer name a dead symbol — the function moved or was renamed. Each
+    /// entry absorbs at most one symbol, so a copy of a baselined function is
+    /// still reported.
+    pub fn matches(&self, dead: &[Entity], project_root: &Path) -> Vec<bool> {
+        let root = root_prefix(project_root);
+        let keys: Vec<(String, &str)> = dead
+            .iter()
+            .map(|e| (relative(&e.file_path, &root), e.qualified_name.as_str()))
+            .collect();
+
+        let mut by_key: HashMap<(&str, &str), usize> = HashMap::new();
+        for (i, entry) in self.symbols.iter().enumerate() {
+            by_key.insert((&entry.file, &entry.qualified_name), i);
+        }
+        let mut used: HashSet<usize> = HashSet::new();
+        let mut matched = vec![false; dead.len()];
+        for (m, (file, name)) in matched.iter_mut().zip(&keys) {
+            if let Some(&i) = by_key.get(&(file.as_str(), *name)) {
+                *m = used.insert(i);
+            }
+        }
+
+        let mut by_hash: HashMap<&str, Vec<usize>> = HashMap::new();
+        for (i, entry) in self.symbols.iter().enumerate() {
+            if let (false, Some(hash)) = (used.contains(&i), &entry.structural_hash) {
+                by_hash.entry(hash).or_default().push(i);
+            }
+        }
+        for (m, e) in matched.iter_mut().zip(dead) {
+            if *m {
+                continue;
+            }
+            let Some(hash) = e.structural_hash.map(|h| format!("{:016x}", h)) else {
+                continue;
+            };
+            if let Some(i) = by_hash.get_mut(hash.as_str()).and_then(Vec::pop) {
+                *m = true;
+                used.insert(i);
+            }
+        }
+        matched
+    }
+
+    /// Moves the dead symbols the baseline accepts out of `result.dead` (and
+    /// their evidence out of `result.evidence`) and returns them, so every
+    /// report of `result` lists only new ones.
+    pub fn hold_back(
+        &self,
+        result: &mut anatomist::pipeline::ScanResult,
+        project_root: &Path,
+    ) -> Vec<Entity> {
+        let known = self.matches(&result.dead, project_root);
+        let dead = std::mem::take(&mut result.dead);
+        let evidence = std::mem::take(&mut result.evidence);
+        let mut baselined = Vec::new();
+        for ((entity, ev), known) in dead.into_iter().zip(evidence).zip(known) {
+            if known {
+                baselined.push(entity);
+            } else {
+                result.dead.push(entity);
+                result.evidence.push(ev);
+            }
+        }
+        baselined
+    }
+}
+
+/// `project_root` as an absolute, forward-slash prefix ending in `/`.
+fn root_prefix(project_root: &Path) -> String {
+    let root = dunce::canonicalize(project_root).unwrap_or_else(|_| project_root.into());
+    format!(
+        "{}/",
+        root.to_string_lossy()
+            .replace('\\', "/")
+            .trim_end_matches('/')
+    )
+}
+
+fn relative(path: &str, root: &str) -> String {
+    path.strip_prefix(root).unwrap_or(path).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anatomist::parser::ParserHost;
+
+    fn dead_in(host: &mut ParserHost, root: &Path, files: &[&str]) -> Vec<Entity> {
+        let mut dead = Vec::new();
+        for f in files {
+            dead.extend(host.dissect(&root.join(f)).unwrap());
+        }
+        dead
+    }
+
+    #[test]
+    fn test_baseline_suppresses_known_and_reports_new() {
+        let tmp = std::env::temp_dir().join("test_cli_baseline");
+        std::fs::remove_dir_all(&tmp).ok();
+        std::fs::create_dir_all(tmp.join("pkg")).unwrap();
+        std::fs::write(
+            tmp.join("pkg/legacy.py"),
+            "def export_xml():\n    return '<xml/>'\n",
+        )
+        .unwrap();
+        let mut host = ParserHost::new().unwrap();
+        let before = dead_in(&mut host, &tmp, &["pkg/legacy.py"]);
+        let path = tmp.join(".janitor/baseline.json");
+        Baseline::from_dead(&before, &tmp).save(&path).unwrap();
+
+        let saved = std::fs::read_to_string(&path).unwrap();
+        assert!(saved.contains("\"file\": \"pkg/legacy.py\""));
+        assert!(!saved.contains("line"));
+
+        // Lines drift above the old symbol, and a new dead symbol appears.
+        std::fs::write(
+            tmp.join("pkg/legacy.py"),
+            "import os\n\n\ndef export_csv():\n    return os.sep\n\ndef export_xml():\n    return '<xml/>'\n",
+        )
+        .unwrap();
+        let after = dead_in(&mut host, &tmp, &["pkg/legacy.py"]);
+        let baseline = Baseline::load(&path).unwrap();
+        let matched = baseline.matches(&after, &tmp);
+        let new: Vec<&str> = after
+            .iter()
+            .zip(&matched)
+            .filter(|(_, m)| !**m)
+            .map(|(e, _)| e.name.as_str())
+            .collect();
+        assert_eq!(new, vec!["export_csv"]);
+
+        std::fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_moved_function_matches_by_structural_hash() {
+        let tmp = std::env::temp_dir().join("test_cli_baseline_moved");
+        std::fs::remove_dir_all(&tmp).ok();
+        std::fs::create_dir_all(tmp.join("pkg")).unwrap();
+        let body = "def reconcile(ledger):\n    total = 0\n    for row in ledger:\n        total += row\n    return total\n";
+        std::fs::write(tmp.join("pkg/billing.py"), body).unwrap();
+        let mut host = ParserHost::new().unwrap();
+        let before = dead_in(&mut host, &tmp, &["pkg/billing.py"]);
+        let baseline = Baseline::from_dead(&before, &tmp);
+
+        // Moved to another module and renamed: same structure.
+        std::fs::remove_file(tmp.join("pkg/billing.py")).unwrap();
+        std::fs::write(
+            tmp.join("pkg/ledger.py"),
+            body.replace("reconcile", "reconcile_old"),
+        )
+        .unwrap();
+        let moved = dead_in(&mut host, &tmp, &["pkg/ledger.py"]);
+        assert_eq!(baseline.matches(&moved, &tmp), vec![true]);
+
+        // A second copy is new: one baseline entry absorbs one symbol.
+        std::fs::write(tmp.join("pkg/copy.py"), body).unwrap();
+        let both = dead_in(&mut host, &tmp, &["pkg/copy.py", "pkg/ledger.py"]);
+        let matched = baseline.matches(&both, &tmp);
+        assert_eq!(matched.iter().filter(|m| **m).count(), 1);
+
+        std::fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_unsupported_version_is_rejected() {
+        let path = std::env::temp_dir().join("test_cli_baseline_version.json");
+        std::fs::write(&path, r#"{"version": 99, "symbols": []}"#).unwrap();
+        let err = Baseline::load(&path).unwrap_err();
+        assert!(err.to_string().contains("unsupported baseline version 99"));
+        std::fs::remove_file(path).ok();
+    }
+}
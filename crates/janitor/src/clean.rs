@@ -0,0 +1,1511 @@
+//! `clean`: shadow-simulate the kill list, then delete it.
+//!
+//! Dead symbols are removed file by file with `reaper::SafeDeleter`, orphan
+//! files are ghosted into `.janitor/ghost/`, and every change of one run shares
+//! a transaction ID so `janitor restore --all` can undo it.
+
+use crate::event::Event;
+use crate::plan::{self, CleanPlan};
+use crate::report::{CleanReport, CleanedFile, RemovedSymbol, RescuedSymbol, Verification};
+use crate::verification::{Outcome, Verifier};
+use common::Protection;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Options of [`crate::Janitor::clean`].
+#[derive(Debug, Clone, Default)]
+pub struct CleanOptions {
+    /// Ed25519 purge token; required by `clean` and `execute_plan`, ignored
+    /// when only planning.
+    pub token: String,
+    /// Leave orphan files in place.
+    pub skip_orphans: bool,
+    /// Also remove unused imports.
+    pub remove_imports: bool,
+    /// Bisect a failed shadow simulation instead of giving up.
+    pub bisect: bool,
+    /// Proceed when the test command is not installed, so nothing verifies
+    /// the deletions. Without it such a run is refused.
+    pub allow_unverified: bool,
+}
+
+/// The pipeline run behind `clean`: the parser host (reused to re-locate
+/// symbols) and the scan result.
+pub(crate) type CleanScan = (
+    anatomist::parser::ParserHost,
+    anatomist::pipeline::ScanResult,
+);
+
+/// Deletes the kill list of `scan` under the shadow simulation of `verifier`.
+///
+/// Records what it changed in `report` as it goes, so a run that fails
+/// part-way still accounts for the edits it left in the project.
+pub(crate) fn clean(
+    project_root: &Path,
+    (mut host, result): CleanScan,
+    verifier: &Verifier,
+    options: &CleanOptions,
+    report: &mut CleanReport,
+    notify: &mut dyn FnMut(Event),
+) -> anyhow::Result<()> {
+    use reaper::SafeDeleter;
+
+    let (orphan_files, mut dead) = kill_list(&result, options.skip_orphans);
+    let import_files = import_files(&result, &orphan_files, options.remove_imports);
+
+    if dead.is_empty() && orphan_files.is_empty() && import_files.is_empty() {
+        return Ok(());
+    }
+    notify(Event::Info(format!(
+        "{} dead symbols, {} orphan files identified.",
+        dead.len(),
+        orphan_files.len()
+    )));
+    let mode = verification_mode(verifier, options.allow_unverified)?;
+
+    // Initialise the shadow tree, or bring a previous run's up to date.
+    let manager = prepare_shadow(project_root, notify)?;
+    let transaction = reaper::ghost::new_transaction_id();
+    report.transaction = Some(transaction.clone());
+    // One deleter for symbols and imports, so a file edited by both is backed
+    // up once and a rollback restores its pre-run contents.
+    let mut deleter = SafeDeleter::with_transaction(project_root, &transaction)?;
+    let relative = |abs: &Path| -> PathBuf {
+        abs.strip_prefix(manager.source_root())
+            .unwrap_or(abs)
+            .to_path_buf()
+    };
+
+    if !dead.is_empty() {
+        // Collect unique files and unmap their symlinks.
+        let mut dead_files: Vec<PathBuf> =
+            dead.iter().map(|e| PathBuf::from(&e.file_path)).collect();
+        dead_files.sort();
+        dead_files.dedup();
+
+        let mut unmapped: Vec<PathBuf> = Vec::new();
+        for abs in &dead_files {
+            let rel = relative(abs);
+            match manager.unmap(&rel) {
+                Ok(()) => unmapped.push(rel),
+                Err(e) => notify(Event::Warning(format!(
+                    "warning: unmap {}: {}",
+                    abs.display(),
+                    e
+                ))),
+            }
+        }
+
+        // Shadow simulation: run tests against the shadow tree.
+        notify(Event::Info(format!(
+            "Shadow simulation in: {}",
+            manager.shadow_root().display()
+        )));
+        match verifier.check(manager.shadow_root()) {
+            Ok(Outcome::Passed) => {
+                report.verification = Some(mode.clone());
+                notify(Event::Info(
+                    "Shadow tests PASSED. Executing physical deletion...".to_string(),
+                ));
+            }
+            Ok(Outcome::Skipped { reason }) => {
+                report.verification = Some(mode.clone());
+                notify(Event::Warning(format!(
+                    "Shadow tests SKIPPED: {}. Executing physical deletion unverified...",
+                    reason
+                )));
+            }
+            Err(e) if options.bisect => {
+                notify(Event::Warning(format!(
+                    "Shadow simulation FAILED: {}. Bisecting...",
+                    e
+                )));
+                let verify = |dir: &Path| verifier.check(dir).map(drop);
+                let offenders: HashSet<PathBuf> = bisect_unmapped(&manager, &unmapped, &verify)?
+                    .into_iter()
+                    .collect();
+                unmapped.retain(|rel| !offenders.contains(rel));
+                report.verification = Some(Verification::Bisected {
+                    files: offenders.len(),
+                });
+
+                // Symbols in the offending files are kept alive by test evidence.
+                let (rescued, rest): (Vec<&anatomist::Entity>, Vec<&anatomist::Entity>) = dead
+                    .into_iter()
+                    .partition(|e| offenders.contains(&relative(Path::new(e.file_path.as_str()))));
+                dead = rest;
+                notify(Event::Info(format!(
+                    "Rescued by test evidence: {} symbols in {} files",
+                    rescued.len(),
+                    offenders.len()
+                )));
+                for entity in &rescued {
+                    let symbol = RescuedSymbol {
+                        file: relative(Path::new(entity.file_path.as_str())),
+                        qualified_name: entity.qualified_name.clone(),
+                        protected_by: Protection::TestReference,
+                    };
+                    notify(Event::Info(format!(
+                        "  {}::{} ({:?})",
+                        symbol.file.display(),
+                        symbol.qualified_name,
+                        symbol.protected_by
+                    )));
+                    report.rescued.push(symbol);
+                }
+                if !dead.is_empty() {
+                    notify(Event::Info(
+                        "Remaining set PASSED. Executing physical deletion...".to_string(),
+                    ));
+                }
+            }
+            Err(e) => {
+                notify(Event::Warning(format!(
+                    "Shadow simulation FAILED: {}. Restoring symlinks...",
+                    e
+                )));
+                for rel in &unmapped {
+                    manager.remap(rel).ok();
+                }
+                return Err(e);
+            }
+        }
+
+        // Physical deletion via SafeDeleter, in dependency order.
+        let by_file = deletion_order(&dead, &result.dead_graph);
+        for (file_str, entities) in &by_file {
+            let file_path = Path::new(file_str);
+            let mut targets = match fresh_targets(
+                &mut host,
+                file_path,
+                entities,
+                result.file_hashes.get(*file_str),
+                notify,
+            ) {
+                Ok(t) if !t.is_empty() => t,
+                Ok(_) => {
+                    notify(Event::Warning(format!(
+                        "warning: skipping {}: no dead symbol left",
+                        file_str
+                    )));
+                    continue;
+                }
+                Err(e) => {
+                    notify(Event::Warning(format!(
+                        "warning: skipping {}: {}",
+                        file_str, e
+                    )));
+                    continue;
+                }
+            };
+            match delete_targets(&mut deleter, file_path, &mut targets, notify) {
+                Ok(symbols) => {
+                    notify(Event::Info(format!(
+                        "Deleted {} symbols from {}",
+                        symbols.len(),
+                        file_str
+                    )));
+                    report.deleted.push(CleanedFile {
+                        file: relative(file_path).to_string_lossy().replace('\\', "/"),
+                        symbols,
+                    });
+                }
+                Err(e) => {
+                    // Rolls back every file of this run, not just `file_str`.
+                    notify(Event::Warning(format!(
+                        "Deletion error in {}: {}. Restoring all backups...",
+                        file_str, e
+                    )));
+                    deleter.restore_all()?;
+                    report.deleted.clear();
+                    for rel in &unmapped {
+                        manager.remap(rel).ok();
+                    }
+                    return Err(e);
+                }
+            }
+        }
+
+        // The edited files stay in the project: put them back in the shadow
+        // tree so the orphan simulation sees the post-deletion sources.
+        for rel in &unmapped {
+            manager.remap(rel).ok();
+        }
+    }
+
+    // Unused imports: re-analysed on the post-deletion sources, then verified
+    // against the shadow tree, which links back to the edited files.
+    if !import_files.is_empty() {
+        let mut removed = 0usize;
+        for (file_str, names) in &import_files {
+            match remove_unused_imports(&mut deleter, Path::new(file_str), names) {
+                Ok(n) => {
+                    removed += n;
+                    report.imports_removed = removed;
+                    report.import_files += 1;
+                }
+                Err(e) => notify(Event::Warning(format!(
+                    "warning: skipping imports in {}: {}",
+                    file_str, e
+                ))),
+            }
+        }
+        // Copied shadow trees do not see the rewrites until re-synced.
+        manager.sync()?;
+        if let Err(e) = verifier.check(manager.shadow_root()) {
+            report.imports_removed = 0;
+            report.import_files = 0;
+            // Rolls back the symbol deletions of this run as well.
+            notify(Event::Warning(format!(
+                "Shadow simulation FAILED after import removal: {}. Restoring all backups...",
+                e
+            )));
+            deleter.restore_all()?;
+            report.deleted.clear();
+            return Err(e);
+        }
+        report.verification.get_or_insert_with(|| mode.clone());
+        notify(Event::Info(format!(
+            "Removed {} unused imports from {} files",
+            removed,
+            import_files.len()
+        )));
+    }
+    // Keep the backups so `janitor restore` can undo this run.
+    deleter.seal();
+
+    // Orphan files: simulate their removal, then ghost them.
+    if !orphan_files.is_empty() {
+        let orphans: Vec<PathBuf> = orphan_files
+            .iter()
+            .map(|f| relative(Path::new(f.as_str())))
+            .collect();
+        let verify = |dir: &Path| verifier.check(dir).map(drop);
+        ghost_orphans(
+            project_root,
+            &manager,
+            &transaction,
+            &orphans,
+            &verify,
+            report,
+            notify,
+        )?;
+        if !report.ghosted.is_empty() {
+            report.verification.get_or_insert(mode);
+        }
+    }
+
+    Ok(())
+}
+
+/// Plans what [`clean`] would delete and simulates the plan in the shadow
+/// tree. The project itself is left untouched.
+pub(crate) fn plan_clean(
+    project_root: &Path,
+    (mut host, result): CleanScan,
+    verifier: &Verifier,
+    options: &CleanOptions,
+    notify: &mut dyn FnMut(Event),
+) -> anyhow::Result<CleanPlan> {
+    let (orphan_files, dead) = kill_list(&result, options.skip_orphans);
+
+    let mut files: Vec<(&str, Vec<reaper::DeletionTarget>)> = Vec::new();
+    for (file_str, entities) in deletion_order(&dead, &result.dead_graph) {
+        let scanned_hash = result.file_hashes.get(file_str);
+        match fresh_targets(
+            &mut host,
+            Path::new(file_str),
+            &entities,
+            scanned_hash,
+            notify,
+        ) {
+            Ok(targets) if !targets.is_empty() => files.push((file_str, targets)),
+            Ok(_) => {}
+            Err(e) => notify(Event::Warning(format!(
+                "warning: skipping {}: {}",
+                file_str, e
+            ))),
+        }
+    }
+    let mut plan = CleanPlan::new(project_root, &files, &orphan_files)?;
+    if !plan.is_empty() {
+        plan.simulation = simulate_plan(project_root, &plan, verifier, notify)?;
+    }
+    Ok(plan)
+}
+
+/// The edits [`clean`] would make, as a patch: dead symbols and, when asked,
+/// unused imports removed, orphan files deleted. Nothing is written and no
+/// test runs, so there is nothing to bisect.
+pub(crate) fn patch(
+    project_root: &Path,
+    (mut host, result): CleanScan,
+    options: &CleanOptions,
+    notify: &mut dyn FnMut(Event),
+) -> anyhow::Result<reaper::patch::Patch> {
+    let (orphan_files, dead) = kill_list(&result, options.skip_orphans);
+    let mut patch = reaper::patch::Patch::new(project_root)?;
+
+    for (file_str, entities) in deletion_order(&dead, &result.dead_graph) {
+        let file_path = Path::new(file_str);
+        let scanned_hash = result.file_hashes.get(file_str);
+        let mut targets = match fresh_targets(&mut host, file_path, &entities, scanned_hash, notify)
+        {
+            Ok(targets) => targets,
+            Err(e) => {
+                notify(Event::Warning(format!(
+                    "warning: skipping {}: {}",
+                    file_str, e
+                )));
+                continue;
+            }
+        };
+        let outcomes = patch.delete_symbols(file_path, &mut targets)?;
+        removed_symbols(file_path, &targets, &outcomes, notify)?;
+    }
+
+    for (file_str, names) in import_files(&result, &orphan_files, options.remove_imports) {
+        let edited = patch.edit(Path::new(file_str), |content| {
+            strip_unused_imports(content, file_str, &names)
+        });
+        if let Err(e) = edited.map_err(anyhow::Error::from).and_then(|n| n) {
+            notify(Event::Warning(format!(
+                "warning: skipping imports in {}: {}",
+                file_str, e
+            )));
+        }
+    }
+
+    for file in &orphan_files {
+        patch.delete_file(Path::new(file.as_str()))?;
+    }
+    Ok(patch)
+}
+
+/// Files whose unused imports `clean` removes, with the bindings the scan
+/// reported in each: none unless `remove_imports`. Orphans are ghosted whole,
+/// so their imports are left alone too.
+fn import_files<'a>(
+    result: &'a anatomist::pipeline::ScanResult,
+    orphan_files: &[&String],
+    remove_imports: bool,
+) -> BTreeMap<&'a str, BTreeSet<&'a str>> {
+    let mut files: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+    if !remove_imports {
+        return files;
+    }
+    let orphans: HashSet<&String> = orphan_files.iter().copied().collect();
+    for unused in &result.unused_imports {
+        if !orphans.contains(&unused.file) {
+            files
+                .entry(unused.file.as_str())
+                .or_default()
+                .insert(unused.name.as_str());
+        }
+    }
+    files
+}
+
+/// Orphan files to ghost and dead symbols to delete from the remaining files.
+///
+/// Orphan files are ghosted whole, so their symbols skip the symbol-level pass.
+fn kill_list(
+    result: &anatomist::pipeline::ScanResult,
+    skip_orphans: bool,
+) -> (Vec<&String>, Vec<&anatomist::Entity>) {
+    let orphan_files: Vec<&String> = if skip_orphans {
+        Vec::new()
+    } else {
+        result.orphan_files.iter().collect()
+    };
+    let orphans: HashSet<&String> = orphan_files.iter().copied().collect();
+    let dead = result
+        .dead
+        .iter()
+        .filter(|e| !orphans.contains(&e.file_path))
+        .collect();
+    (orphan_files, dead)
+}
+
+/// Opens the shadow tree and brings it up to date, or initialises it.
+pub(crate) fn prepare_shadow(
+    project_root: &Path,
+    notify: &mut dyn FnMut(Event),
+) -> anyhow::Result<shadow::ShadowManager> {
+    let shadow_path = project_root.join(".janitor").join("shadow_src");
+    let manager = if shadow_path.exists() {
+        let manager = shadow::ShadowManager::open(project_root, &shadow_path)?;
+        let report = manager.sync()?;
+        notify(Event::Info(format!(
+            "Shadow tree synced: {} added, {} removed, {} kept",
+            report.added, report.removed, report.kept
+        )));
+        manager
+    } else {
+        shadow::ShadowManager::initialize(project_root, &shadow_path)?
+    };
+    if manager.strategy() == shadow::LinkStrategy::Copy {
+        notify(Event::Warning(
+            "warning: symlinks and hard links are unavailable; the shadow tree holds copies \
+             that only pick up source edits on remap or `janitor shadow init`"
+                .to_string(),
+        ));
+    }
+    Ok(manager)
+}
+
+/// Runs the test suite against the shadow tree with every planned file
+/// unmapped, as `clean` does before deleting, and maps them back afterwards.
+fn simulate_plan(
+    project_root: &Path,
+    plan: &CleanPlan,
+    verifier: &Verifier,
+    notify: &mut dyn FnMut(Event),
+) -> anyhow::Result<plan::Simulation> {
+    if let Some(program) = verifier.missing_program() {
+        return Ok(plan::Simulation::Skipped {
+            reason: format!("`{}` not found", program),
+        });
+    }
+    let manager = prepare_shadow(project_root, notify)?;
+    let mut unmapped: Vec<PathBuf> = Vec::new();
+    for file in plan
+        .files
+        .iter()
+        .map(|f| &f.file)
+        .chain(plan.orphans.iter().map(|o| &o.file))
+    {
+        let rel = PathBuf::from(file);
+        match manager.unmap(&rel) {
+            Ok(()) => unmapped.push(rel),
+            Err(e) => notify(Event::Warning(format!("warning: unmap {}: {}", file, e))),
+        }
+    }
+    notify(Event::Info(format!(
+        "Shadow simulation in: {}",
+        manager.shadow_root().display()
+    )));
+    let outcome = verifier.run(manager.shadow_root());
+    for rel in &unmapped {
+        manager.remap(rel).ok();
+    }
+    Ok(match outcome {
+        Ok(Outcome::Passed) => plan::Simulation::Passed,
+        Ok(Outcome::Skipped { reason }) => plan::Simulation::Skipped { reason },
+        Err(failure) => plan::Simulation::Failed {
+            reason: failure.reason,
+        },
+    })
+}
+
+/// Deletes exactly the byte ranges of `plan` and ghosts its orphans, with the
+/// same shadow simulation and rollback as [`clean`].
+///
+/// Refuses to touch anything when a planned file no longer has the contents
+/// the plan was made for. Records what it changed in `report` as it goes.
+pub(crate) fn execute_plan(
+    project_root: &Path,
+    plan: &CleanPlan,
+    verifier: &Verifier,
+    allow_unverified: bool,
+    report: &mut CleanReport,
+    notify: &mut dyn FnMut(Event),
+) -> anyhow::Result<()> {
+    use reaper::SafeDeleter;
+
+    let stale = plan.stale_files(project_root);
+    if !stale.is_empty() {
+        anyhow::bail!(
+            "plan is stale: {} changed since it was written; re-run `janitor clean --dry-run`",
+            stale.join(", ")
+        );
+    }
+    if plan.is_empty() {
+        return Ok(());
+    }
+    let mode = verification_mode(verifier, allow_unverified)?;
+    let verify = &|dir: &Path| verifier.check(dir).map(drop);
+
+    let manager = prepare_shadow(project_root, notify)?;
+    let transaction = reaper::ghost::new_transaction_id();
+    report.transaction = Some(transaction.clone());
+
+    if !plan.files.is_empty() {
+        let mut unmapped: Vec<PathBuf> = Vec::new();
+        for file in &plan.files {
+            let rel = PathBuf::from(&file.file);
+            match manager.unmap(&rel) {
+                Ok(()) => unmapped.push(rel),
+                Err(e) => notify(Event::Warning(format!(
+                    "warning: unmap {}: {}",
+                    file.file, e
+                ))),
+            }
+        }
+        notify(Event::Info(format!(
+            "Shadow simulation in: {}",
+            manager.shadow_root().display()
+        )));
+        if let Err(e) = verify(manager.shadow_root()) {
+            notify(Event::Warning(format!(
+                "Shadow simulation FAILED: {}. Restoring symlinks...",
+                e
+            )));
+            for rel in &unmapped {
+                manager.remap(rel).ok();
+            }
+            return Err(e);
+        }
+        report.verification = Some(mode.clone());
+        notify(Event::Info(
+            "Shadow tests PASSED. Executing plan...".to_string(),
+        ));
+
+        let mut deleter = SafeDeleter::with_transaction(project_root, &transaction)?;
+        for file in &plan.files {
+            let path = manager.source_root().join(&file.file);
+            let mut targets: Vec<reaper::DeletionTarget> =
+                file.symbols.iter().map(plan::PlanSymbol::target).collect();
+            match delete_targets(&mut deleter, &path, &mut targets, notify) {
+                Ok(symbols) => {
+                    notify(Event::Info(format!(
+                        "Deleted {} symbols from {}",
+                        symbols.len(),
+                        file.file
+                    )));
+                    report.deleted.push(CleanedFile {
+                        file: file.file.clone(),
+                        symbols,
+                    });
+                }
+                Err(e) => {
+                    notify(Event::Warning(format!(
+                        "Deletion error in {}: {}. Restoring all backups...",
+                        file.file, e
+                    )));
+                    deleter.restore_all()?;
+                    report.deleted.clear();
+                    for rel in &unmapped {
+                        manager.remap(rel).ok();
+                    }
+                    return Err(e);
+                }
+            }
+        }
+        deleter.seal();
+        for rel in &unmapped {
+            manager.remap(rel).ok();
+        }
+    }
+
+    if !plan.orphans.is_empty() {
+        let orphans: Vec<PathBuf> = plan
+            .orphans
+            .iter()
+            .map(|o| PathBuf::from(&o.file))
+            .collect();
+        ghost_orphans(
+            project_root,
+            &manager,
+            &transaction,
+            &orphans,
+            verify,
+            report,
+            notify,
+        )?;
+        if !report.ghosted.is_empty() {
+            report.verification.get_or_insert(mode);
+        }
+    }
+    Ok(())
+}
+
+/// Deletes `targets` from `file_path`, warning about skipped targets.
+/// Returns the symbols removed, in file order.
+fn delete_targets(
+    deleter: &mut reaper::SafeDeleter,
+    file_path: &Path,
+    targets: &mut [reaper::DeletionTarget],
+    notify: &mut dyn FnMut(Event),
+) -> anyhow::Result<Vec<RemovedSymbol>> {
+    let outcomes = deleter.delete_symbols(file_path, targets)?;
+    removed_symbols(file_path, targets, &outcomes, notify)
+}
+
+/// Warns about the targets of `file_path` that were skipped and returns the
+/// removed ones, in file order. `outcomes` pair up with `targets`.
+///
+/// Targets out of range mean the file no longer matches the byte ranges, so
+/// they fail the file (and the run) instead of being skipped.
+fn removed_symbols(
+    file_path: &Path,
+    targets: &[reaper::DeletionTarget],
+    outcomes: &[reaper::DeletionOutcome],
+    notify: &mut dyn FnMut(Event),
+) -> anyhow::Result<Vec<RemovedSymbol>> {
+    use reaper::DeletionStatus;
+
+    let file_str = file_path.display();
+    let mut stale = 0usize;
+    for o in outcomes {
+        match o.status {
+            DeletionStatus::Removed => {}
+            DeletionStatus::SkippedOverlap => notify(Event::Warning(format!(
+                "warning: {} in {} lies inside another deleted symbol",
+                o.qualified_name, file_str
+            ))),
+            DeletionStatus::SkippedOutOfRange => {
+                stale += 1;
+                notify(Event::Warning(format!(
+                    "warning: {} in {} is out of range (stale registry?)",
+                    o.qualified_name, file_str
+                )));
+            }
+        }
+    }
+    if stale > 0 {
+        anyhow::bail!(
+            "{} stale targets in {}; re-run `janitor scan`",
+            stale,
+            file_str
+        );
+    }
+    // Targets are left sorted by descending start byte.
+    Ok(targets
+        .iter()
+        .zip(outcomes)
+        .rev()
+        .filter(|(_, o)| o.status == DeletionStatus::Removed)
+        .map(|(t, o)| RemovedSymbol {
+            qualified_name: o.qualified_name.clone(),
+            bytes: t.end_byte.saturating_sub(t.start_byte),
+        })
+        .collect())
+}
+
+/// How a run will be verified: by the test suite, or, when its command is not
+/// installed, not at all — which needs `allow_unverified`. Recorded in the
+/// report once a shadow simulation passes.
+fn verification_mode(verifier: &Verifier, allow_unverified: bool) -> anyhow::Result<Verification> {
+    match verifier.missing_program() {
+        None => Ok(Verification::Passed),
+        Some(program) if allow_unverified => Ok(Verification::Skipped {
+            reason: format!("`{}` not found", program),
+        }),
+        Some(program) => anyhow::bail!(
+            "`{}` not found, so no test run can verify the deletions; install it, set \
+             `test_command` in .janitor.toml, or pass --allow-unverified",
+            program
+        ),
+    }
+}
+
+/// Simulates removing `orphans` (relative paths), ghosts them if the tests
+/// still pass, and records each ghost under `transaction`.
+///
+/// The ghosted paths and the retained count go into `report`.
+fn ghost_orphans(
+    project_root: &Path,
+    manager: &shadow::ShadowManager,
+    transaction: &str,
+    orphans: &[PathBuf],
+    verify: &dyn Fn(&Path) -> anyhow::Result<()>,
+    report: &mut CleanReport,
+    notify: &mut dyn FnMut(Event),
+) -> anyhow::Result<()> {
+    let (ghosted, retained) = clean_orphans(manager, orphans, verify, notify)?;
+    report.ghosted = ghosted.iter().map(|(rel, _)| rel.clone()).collect();
+    report.orphans_retained = retained;
+    let ghost_manifest = reaper::ghost::GhostManifest::open(project_root);
+    for (rel, ghost_name) in &ghosted {
+        ghost_manifest.append(&reaper::ghost::GhostRecord {
+            transaction: transaction.to_string(),
+            kind: reaper::ghost::GhostKind::File,
+            timestamp: reaper::ghost::unix_now(),
+            ghost_name: ghost_name.clone(),
+            original: manager.source_root().join(rel),
+        })?;
+    }
+    notify(Event::Info(format!(
+        "Orphans: {} ghosted, {} retained",
+        ghosted.len(),
+        retained
+    )));
+    Ok(())
+}
+
+/// Groups `dead` by file, ordering files so dead callers go before their callees.
+///
+/// Each file is rewritten in one step, so it is scheduled at the latest
+/// [`oracle::deletion_plan`] batch holding one of its symbols: every dead
+/// caller of anything in it goes first. Ties keep path order.
+fn deletion_order<'a>(
+    dead: &[&'a anatomist::Entity],
+    dead_graph: &petgraph::graph::DiGraph<u64, ()>,
+) -> Vec<(&'a str, Vec<&'a anatomist::Entity>)> {
+    use common::registry::symbol_hash;
+
+    let ids: Vec<u64> = dead.iter().map(|e| symbol_hash(&e.symbol_id())).collect();
+    let batch_of: HashMap<u64, usize> = oracle::deletion_plan(dead_graph, &ids)
+        .into_iter()
+        .enumerate()
+        .flat_map(|(b, batch)| batch.into_iter().map(move |id| (id, b)))
+        .collect();
+
+    let mut by_file: BTreeMap<&str, (usize, Vec<&anatomist::Entity>)> = BTreeMap::new();
+    for (entity, id) in dead.iter().zip(&ids) {
+        let slot = by_file
+            .entry(entity.file_path.as_str())
+            .or_insert((0, Vec::new()));
+        slot.0 = slot.0.max(batch_of[id]);
+        slot.1.push(entity);
+    }
+    let mut files: Vec<(usize, &str, Vec<&anatomist::Entity>)> = by_file
+        .into_iter()
+        .map(|(file, (batch, entities))| (batch, file, entities))
+        .collect();
+    files.sort_by_key(|(batch, file, _)| (*batch, *file));
+    files.into_iter().map(|(_, file, e)| (file, e)).collect()
+}
+
+/// Builds deletion targets for `entities`, all from `file_path`.
+///
+/// When the file's BLAKE3 hash still equals `scanned_hash` the scan-time byte
+/// ranges are used as-is. Otherwise the file changed after the scan (formatter,
+/// editor, a hook run by the test suite): it is re-dissected and each symbol is
+/// re-located by qualified name and type. Symbols that no longer exist are
+/// dropped with a warning.
+fn fresh_targets(
+    host: &mut anatomist::parser::ParserHost,
+    file_path: &Path,
+    entities: &[&anatomist::Entity],
+    scanned_hash: Option<&[u8; 32]>,
+    notify: &mut dyn FnMut(Event),
+) -> anyhow::Result<Vec<reaper::DeletionTarget>> {
+    let target = |e: &anatomist::Entity| reaper::DeletionTarget {
+        qualified_name: e.qualified_name.clone(),
+        start_byte: e.start_byte,
+        end_byte: e.end_byte,
+    };
+
+    let bytes = std::fs::read(file_path)?;
+    if scanned_hash == Some(blake3::hash(&bytes).as_bytes()) {
+        return Ok(entities.iter().map(|e| target(e)).collect());
+    }
+
+    notify(Event::Warning(format!(
+        "note: {} changed since the scan; re-locating symbols",
+        file_path.display()
+    )));
+    let current = host.dissect(file_path)?;
+    let mut targets = Vec::new();
+    for e in entities {
+        match current
+            .iter()
+            .find(|c| c.qualified_name == e.qualified_name && c.entity_type == e.entity_type)
+        {
+            Some(c) => targets.push(target(c)),
+            None => notify(Event::Warning(format!(
+                "warning: {} no longer found in {}; skipping",
+                e.qualified_name,
+                file_path.display()
+            ))),
+        }
+    }
+    Ok(targets)
+}
+
+/// Removes the unused import bindings `names` from `file_path`, as found in
+/// its current contents. Returns the number of bindings removed.
+fn remove_unused_imports(
+    deleter: &mut reaper::SafeDeleter,
+    file_path: &Path,
+    names: &BTreeSet<&str>,
+) -> anyhow::Result<usize> {
+    let file_str = file_path.to_string_lossy();
+    deleter.edit(file_path, |content| {
+        strip_unused_imports(content, &file_str, names)
+    })?
+}
+
+/// Removes the unused import bindings `names` from `content`, the decoded
+/// text of `file_str`. Only bindings the scan reported are candidates: it
+/// alone knows which `from pkg import module` lines are kept for their side
+/// effects. Returns the number of bindings removed.
+///
+/// Statements whose names are all unused are deleted first; the text is then
+/// re-analysed so the partially used ones are rewritten at fresh offsets.
+fn strip_unused_imports(
+    content: &mut Vec<u8>,
+    file_str: &str,
+    names: &BTreeSet<&str>,
+) -> anyhow::Result<usize> {
+    let find = |content: &[u8]| -> anyhow::Result<Vec<anatomist::unused_imports::UnusedImport>> {
+        let mut found = anatomist::unused_imports::find_unused_imports(content, file_str)?;
+        // A statement also binding a name kept here is rewritten, not deleted.
+        let kept_in: BTreeSet<u32> = found
+            .iter()
+            .filter(|u| !names.contains(u.name.as_str()))
+            .map(|u| u.start_byte)
+            .collect();
+        found.retain(|u| names.contains(u.name.as_str()) && !kept_in.contains(&u.start_byte));
+        Ok(found)
+    };
+
+    let found = find(content)?;
+    let mut statements: Vec<reaper::DeletionTarget> = found
+        .iter()
+        .filter(|u| u.replacement.is_none())
+        .map(|u| reaper::DeletionTarget {
+            qualified_name: format!("import@{}", u.line),
+            start_byte: u.start_byte,
+            end_byte: u.end_byte,
+        })
+        .collect();
+    statements.dedup_by_key(|t| t.start_byte);
+    let mut removed = found.iter().filter(|u| u.replacement.is_none()).count();
+    reaper::delete_ranges(content, &mut statements);
+
+    let found = find(content)?;
+    let mut rewrites: Vec<reaper::ReplacementTarget> = found
+        .iter()
+        .filter_map(|u| {
+            Some(reaper::ReplacementTarget {
+                qualified_name: format!("import@{}", u.line),
+                start_byte: u.start_byte,
+                end_byte: u.end_byte,
+                replacement: u.replacement.clone()?,
+            })
+        })
+        .collect();
+    rewrites.dedup_by_key(|t| t.start_byte);
+    removed += found.iter().filter(|u| u.replacement.is_some()).count();
+    reaper::replace_ranges(content, &mut rewrites);
+    Ok(removed)
+}
+
+/// Finds the files in `unmapped` whose absence from the shadow tree makes
+/// `verify` fail, given that unmapping all of them together does.
+///
+/// Repeatedly binary-searches for the shortest failing prefix of the files not
+/// yet classified: its last file is an offender and the files before it join
+/// the safe set. Each offender costs `O(log n)` runs, so `k` offenders take
+/// `O(k log n)`. On return exactly the non-offending files are unmapped.
+fn bisect_unmapped(
+    manager: &shadow::ShadowManager,
+    unmapped: &[PathBuf],
+    verify: &dyn Fn(&Path) -> anyhow::Result<()>,
+) -> anyhow::Result<Vec<PathBuf>> {
+    // Unmaps exactly `safe` plus `extra`, remapping every other candidate.
+    let simulate = |safe: &HashSet<&PathBuf>, extra: &[PathBuf]| -> anyhow::Result<bool> {
+        let extra: HashSet<&PathBuf> = extra.iter().collect();
+        for rel in unmapped {
+            if safe.contains(rel) || extra.contains(rel) {
+                manager.unmap(rel)?;
+            } else {
+                manager.remap(rel)?;
+            }
+        }
+        Ok(verify(manager.shadow_root()).is_ok())
+    };
+
+    let mut safe: HashSet<&PathBuf> = HashSet::new();
+    let mut offenders: Vec<PathBuf> = Vec::new();
+    let mut rest: &[PathBuf] = unmapped;
+    // The caller already saw `safe ∪ rest` fail on the first round.
+    let mut known_failing = true;
+    while !rest.is_empty() {
+        if !known_failing && simulate(&safe, rest)? {
+            safe.extend(rest);
+            break;
+        }
+        // Invariant: `safe ∪ rest[..lo]` passes, `safe ∪ rest[..hi]` fails.
+        let (mut lo, mut hi) = (0, rest.len());
+        while hi - lo > 1 {
+            let mid = (lo + hi) / 2;
+            if simulate(&safe, &rest[..mid])? {
+                lo = mid;
+            } else {
+                hi = mid;
+            }
+        }
+        safe.extend(&rest[..hi - 1]);
+        offenders.push(rest[hi - 1].clone());
+        rest = &rest[hi..];
+        known_failing = false;
+    }
+
+    for rel in unmapped {
+        if safe.contains(rel) {
+            manager.unmap(rel)?;
+        } else {
+            manager.remap(rel)?;
+        }
+    }
+    Ok(offenders)
+}
+
+/// Unmaps `orphans` from the shadow tree, runs `verify` against it, and on
+/// success moves every orphan to `.janitor/ghost/`.
+///
+/// A failed verification remaps the orphans and retains them all. A failed
+/// move rolls back the files already ghosted (see [`shadow::ShadowManager::ghost_all`])
+/// and returns the error. Returns the ghosted paths paired with their ghost
+/// names, and the retained count.
+fn clean_orphans(
+    manager: &shadow::ShadowManager,
+    orphans: &[PathBuf],
+    verify: &dyn Fn(&Path) -> anyhow::Result<()>,
+    notify: &mut dyn FnMut(Event),
+) -> anyhow::Result<(Vec<(PathBuf, PathBuf)>, usize)> {
+    let mut unmapped: Vec<PathBuf> = Vec::new();
+    for rel in orphans {
+        match manager.unmap(rel) {
+            Ok(()) => unmapped.push(rel.clone()),
+            Err(e) => notify(Event::Warning(format!(
+                "warning: unmap {}: {}",
+                rel.display(),
+                e
+            ))),
+        }
+    }
+    let retained = orphans.len() - unmapped.len();
+
+    notify(Event::Info(format!(
+        "Orphan simulation in: {}",
+        manager.shadow_root().display()
+    )));
+    if let Err(e) = verify(manager.shadow_root()) {
+        notify(Event::Warning(format!(
+            "Orphan simulation FAILED: {}. Retaining orphan files...",
+            e
+        )));
+        for rel in &unmapped {
+            manager.remap(rel).ok();
+        }
+        return Ok((Vec::new(), orphans.len()));
+    }
+
+    let ghost_names = manager.ghost_all(&unmapped)?;
+    for rel in &unmapped {
+        notify(Event::Info(format!("Ghosted {}", rel.display())));
+    }
+    Ok((unmapped.into_iter().zip(ghost_names).collect(), retained))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event;
+    use std::collections::HashSet;
+
+    /// Scans `project_root` the way `clean` does.
+    fn clean_scan(project_root: &Path) -> CleanScan {
+        let janitor = crate::Janitor::open(project_root).unwrap();
+        janitor.clean_scan(&mut event::silent).unwrap()
+    }
+
+    #[test]
+    fn test_remove_unused_imports_splits_and_deletes() {
+        let tmp = std::env::temp_dir().join("test_cli_remove_unused_imports");
+        std::fs::remove_dir_all(&tmp).ok();
+        std::fs::create_dir_all(&tmp).unwrap();
+        let file = tmp.join("mod.py");
+        std::fs::write(
+            &file,
+            "from os import path\nfrom x import a, b\nfrom y import c\nimport numpy as np\n\nprint(b, np)\n",
+        )
+        .unwrap();
+
+        // `c` was not reported by the scan, so it stays.
+        let names = BTreeSet::from(["path", "a"]);
+        let mut deleter = reaper::SafeDeleter::new(&tmp).unwrap();
+        let removed = remove_unused_imports(&mut deleter, &file, &names).unwrap();
+        assert_eq!(removed, 2);
+        assert_eq!(
+            std::fs::read_to_string(&file).unwrap(),
+            "from x import b\nfrom y import c\nimport numpy as np\n\nprint(b, np)\n"
+        );
+
+        std::fs::remove_dir_all(&tmp).ok();
+    }
+
+    #[test]
+    fn test_patch_holds_the_clean_without_writing() {
+        let tmp = std::env::temp_dir().join("test_clean_patch");
+        std::fs::remove_dir_all(&tmp).ok();
+        std::fs::create_dir_all(tmp.join("pkg")).unwrap();
+        std::fs::write(
+            tmp.join("main.py"),
+            "from pkg.billing import keep\n\nkeep()\n",
+        )
+        .unwrap();
+        std::fs::write(tmp.join("pkg/__init__.py"), "").unwrap();
+        let billing =
+            "from os import path\n\n\ndef keep():\n    return 1\n\n\ndef legacy():\n    return 2\n";
+        std::fs::write(tmp.join("pkg/billing.py"), billing).unwrap();
+        std::fs::write(tmp.join("pkg/stale.py"), "def old():\n    pass\n").unwrap();
+
+        let options = CleanOptions {
+            remove_imports: true,
+            ..Default::default()
+        };
+        let patch = patch(&tmp, clean_scan(&tmp), &options, &mut event::silent).unwrap();
+        assert_eq!(
+            patch.changed_files(),
+            vec!["pkg/billing.py", "pkg/stale.py"]
+        );
+        assert_eq!(
+            std::fs::read_to_string(tmp.join("pkg/billing.py")).unwrap(),
+            billing
+        );
+        assert!(!tmp.join(".janitor/ghost").exists());
+
+        let text = String::from_utf8(patch.render().unwrap()).unwrap();
+        assert!(text.contains("-def legacy():\n"));
+        assert!(text.contains(
+            "diff --git a/pkg/stale.py b/pkg/stale.py\ndeleted file mode 100644\n\
+             --- a/pkg/stale.py\n+++ /dev/null\n@@ -1,2 +0,0 @@\n"
+        ));
+
+        // `git apply` accepts it as written, when git is around.
+        std::fs::write(tmp.join("out.diff"), &text).unwrap();
+        let applied = std::process::Command::new("git")
+            .args(["apply", "out.diff"])
+            .current_dir(&tmp)
+            .status();
+        if let Ok(status) = applied {
+            assert!(status.success());
+            let billing = std::fs::read_to_string(tmp.join("pkg/billing.py")).unwrap();
+            assert!(billing.contains("def keep():\n    return 1\n"));
+            assert!(!billing.contains("import path") && !billing.contains("legacy"));
+            assert!(!tmp.join("pkg/stale.py").exists());
+        }
+
+        std::fs::remove_dir_all(&tmp).ok();
+    }
+
+    #[cfg(unix)]
+    fn orphan_project(name: &str) -> (PathBuf, shadow::ShadowManager) {
+        let tmp = std::env::temp_dir().join(name);
+        std::fs::remove_dir_all(&tmp).ok();
+        std::fs::create_dir_all(tmp.join("pkg")).unwrap();
+        std::fs::write(tmp.join("main.py"), "print('hi')\n").unwrap();
+        std::fs::write(tmp.join("pkg/stale.py"), "def old():\n    pass\n").unwrap();
+        let manager =
+            shadow::ShadowManager::initialize(&tmp, &tmp.join(".janitor/shadow_src")).unwrap();
+        (tmp, manager)
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_clean_orphans_ghosts_on_pass() {
+        let (tmp, manager) = orphan_project("test_clean_orphans_pass");
+        let orphans = vec![PathBuf::from("pkg/stale.py")];
+
+        let saw_orphan = std::cell::Cell::new(true);
+        let verify = |shadow: &Path| {
+            saw_orphan.set(shadow.join("pkg/stale.py").exists());
+            Ok(())
+        };
+        let (ghosted, retained) =
+            clean_orphans(&manager, &orphans, &verify, &mut event::silent).unwrap();
+
+        let stale = PathBuf::from("pkg/stale.py");
+        assert_eq!((ghosted, retained), (vec![(stale.clone(), stale)], 0));
+        assert!(
+            !saw_orphan.get(),
+            "orphan must be unmapped during simulation"
+        );
+        assert!(!tmp.join("pkg/stale.py").exists());
+        assert!(tmp.join(".janitor/ghost/pkg/stale.py").exists());
+        assert!(tmp.join("main.py").exists());
+
+        std::fs::remove_dir_all(tmp).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_bisect_finds_offending_files() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let tmp = std::env::temp_dir().join("test_cli_bisect");
+        std::fs::remove_dir_all(&tmp).ok();
+        std::fs::create_dir_all(tmp.join("pkg")).unwrap();
+        let files: Vec<PathBuf> = (0..16)
+            .map(|i| PathBuf::from(format!("pkg/m{:02}.py", i)))
+            .collect();
+        for rel in &files {
+            std::fs::write(tmp.join(rel), "x = 1\n").unwrap();
+        }
+        // The fake suite needs m03 and m11 to be importable from the shadow tree.
+        let script = tmp.join("fake_tests.sh");
+        std::fs::write(
+            &script,
+            "#!/bin/sh\ntest -e pkg/m03.py && test -e pkg/m11.py\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let manager =
+            shadow::ShadowManager::initialize(&tmp, &tmp.join(".janitor/shadow_src")).unwrap();
+        for rel in &files {
+            manager.unmap(rel).unwrap();
+        }
+
+        let verifier = Verifier::new(&script.to_string_lossy()).unwrap();
+        let runs = std::cell::Cell::new(0);
+        let verify = |dir: &Path| {
+            runs.set(runs.get() + 1);
+            verifier.check(dir).map(drop)
+        };
+        let offenders = bisect_unmapped(&manager, &files, &verify).unwrap();
+
+        assert_eq!(
+            offenders,
+            vec![PathBuf::from("pkg/m03.py"), PathBuf::from("pkg/m11.py")]
+        );
+        // k = 2 offenders in n = 16 files: 2 * (1 + log2 16) runs at most.
+        assert!(runs.get() <= 10, "{} verification runs", runs.get());
+        let shadow = manager.shadow_root();
+        for rel in &files {
+            assert_eq!(shadow.join(rel).exists(), offenders.contains(rel));
+        }
+        assert!(verifier.run(shadow).is_ok());
+
+        std::fs::remove_dir_all(tmp).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_clean_orphans_retains_on_failure() {
+        let (tmp, manager) = orphan_project("test_clean_orphans_fail");
+        let orphans = vec![PathBuf::from("pkg/stale.py")];
+
+        let verify = |_: &Path| Err(anyhow::anyhow!("pytest exited with code 1"));
+        let (ghosted, retained) =
+            clean_orphans(&manager, &orphans, &verify, &mut event::silent).unwrap();
+
+        assert_eq!((ghosted.len(), retained), (0, 1));
+        assert!(tmp.join("pkg/stale.py").exists());
+        assert!(tmp.join(".janitor/shadow_src/pkg/stale.py").is_symlink());
+        assert!(!tmp.join(".janitor/ghost/pkg/stale.py").exists());
+
+        std::fs::remove_dir_all(tmp).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_dry_run_plan_executes_verbatim_and_refuses_when_stale() {
+        let tmp = std::env::temp_dir().join("test_cli_clean_plan");
+        std::fs::remove_dir_all(&tmp).ok();
+        std::fs::create_dir_all(tmp.join("pkg")).unwrap();
+        std::fs::write(
+            tmp.join(".janitor.toml"),
+            "test_command = \"janitor-no-such-test-runner\"\n",
+        )
+        .unwrap();
+        std::fs::write(
+            tmp.join("main.py"),
+            "from pkg.billing import keep\n\nkeep()\n",
+        )
+        .unwrap();
+        std::fs::write(tmp.join("pkg/__init__.py"), "").unwrap();
+        std::fs::write(
+            tmp.join("pkg/billing.py"),
+            "def keep():\n    return 1\n\n\ndef legacy():\n    x = 1\n    return x\n",
+        )
+        .unwrap();
+        std::fs::write(tmp.join("pkg/stale.py"), "def old():\n    pass\n").unwrap();
+
+        let scan = clean_scan(&tmp);
+        let verifier = Verifier::new("janitor-no-such-test-runner").unwrap();
+        let plan = plan_clean(
+            &tmp,
+            scan,
+            &verifier,
+            &CleanOptions::default(),
+            &mut event::silent,
+        )
+        .unwrap();
+        let plan_path = tmp.join(".janitor/plan.json");
+        plan.save(&plan_path).unwrap();
+
+        // The dry run modifies nothing.
+        assert!(std::fs::read_to_string(tmp.join("pkg/billing.py"))
+            .unwrap()
+            .contains("def legacy"));
+        assert!(tmp.join("pkg/stale.py").exists());
+
+        let plan = CleanPlan::load(&plan_path).unwrap();
+        assert_eq!(plan.files.len(), 1);
+        assert_eq!(plan.files[0].file, "pkg/billing.py");
+        let names: Vec<&str> = plan.files[0]
+            .symbols
+            .iter()
+            .map(|s| s.qualified_name.as_str())
+            .collect();
+        assert_eq!(names, vec!["legacy"]);
+        assert_eq!(plan.orphans[0].file, "pkg/stale.py");
+        assert_eq!(
+            plan.simulation,
+            plan::Simulation::Skipped {
+                reason: "`janitor-no-such-test-runner` not found".to_string()
+            }
+        );
+
+        // A file edited after planning makes the whole plan stale.
+        let original = std::fs::read_to_string(tmp.join("pkg/billing.py")).unwrap();
+        std::fs::write(
+            tmp.join("pkg/billing.py"),
+            format!("# edited\n{}", original),
+        )
+        .unwrap();
+        let passing = Verifier::new("true").unwrap();
+        let err = execute_plan(
+            &tmp,
+            &plan,
+            &passing,
+            false,
+            &mut CleanReport::default(),
+            &mut event::silent,
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(err.contains("plan is stale: pkg/billing.py"), "{}", err);
+        assert!(tmp.join("pkg/stale.py").exists());
+        std::fs::write(tmp.join("pkg/billing.py"), &original).unwrap();
+
+        // No test runner: refused unless explicitly allowed.
+        let err = execute_plan(
+            &tmp,
+            &plan,
+            &verifier,
+            false,
+            &mut CleanReport::default(),
+            &mut event::silent,
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(err.contains("pass --allow-unverified"), "{}", err);
+        assert!(tmp.join("pkg/stale.py").exists());
+        assert!(!tmp.join(".janitor/ghost").exists());
+
+        let mut report = CleanReport::default();
+        execute_plan(
+            &tmp,
+            &plan,
+            &passing,
+            false,
+            &mut report,
+            &mut event::silent,
+        )
+        .unwrap();
+        assert_eq!(report.verification, Some(Verification::Passed));
+        assert_eq!(
+            report.deleted,
+            vec![CleanedFile {
+                file: "pkg/billing.py".to_string(),
+                symbols: vec![RemovedSymbol {
+                    qualified_name: "legacy".to_string(),
+                    bytes: 36,
+                }],
+            }]
+        );
+        assert_eq!(report.ghosted, vec![PathBuf::from("pkg/stale.py")]);
+        let cleaned = std::fs::read_to_string(tmp.join("pkg/billing.py")).unwrap();
+        assert!(cleaned.contains("def keep"));
+        assert!(!cleaned.contains("legacy"));
+        assert!(!tmp.join("pkg/stale.py").exists());
+        assert!(tmp.join(".janitor/ghost/pkg/stale.py").exists());
+
+        std::fs::remove_dir_all(tmp).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_failed_import_simulation_restores_the_original_source() {
+        let tmp = std::env::temp_dir().join("test_clean_import_rollback");
+        std::fs::remove_dir_all(&tmp).ok();
+        std::fs::create_dir_all(tmp.join("pkg")).unwrap();
+        std::fs::write(
+            tmp.join("main.py"),
+            "from pkg.billing import keep\n\nkeep()\n",
+        )
+        .unwrap();
+        std::fs::write(tmp.join("pkg/__init__.py"), "").unwrap();
+        let billing =
+            "from os import path\n\n\ndef keep():\n    return 1\n\n\ndef legacy():\n    return 2\n";
+        std::fs::write(tmp.join("pkg/billing.py"), billing).unwrap();
+        let options = CleanOptions {
+            remove_imports: true,
+            skip_orphans: true,
+            ..Default::default()
+        };
+
+        // A simulation that fails outright verifies nothing.
+        let failing = Verifier::new("false").unwrap();
+        let mut report = CleanReport::default();
+        let scan = clean_scan(&tmp);
+        clean(
+            &tmp,
+            scan,
+            &failing,
+            &options,
+            &mut report,
+            &mut event::silent,
+        )
+        .unwrap_err();
+        assert_eq!(report.verification, None);
+        assert_eq!(
+            std::fs::read_to_string(tmp.join("pkg/billing.py")).unwrap(),
+            billing
+        );
+
+        // Passes while `billing.py` is unmapped, fails once its import is gone.
+        let verifier = Verifier::new(
+            "sh -c \"test ! -e pkg/billing.py || grep -q 'import path' pkg/billing.py\"",
+        )
+        .unwrap();
+        let mut report = CleanReport::default();
+        let scan = clean_scan(&tmp);
+        let mut warnings = Vec::new();
+        clean(&tmp, scan, &verifier, &options, &mut report, &mut |e| {
+            if let Event::Warning(w) = e {
+                warnings.push(w);
+            }
+        })
+        .unwrap_err();
+        assert!(warnings
+            .iter()
+            .any(|w| w.contains("FAILED after import removal")));
+        assert!(report.deleted.is_empty());
+        assert_eq!(report.imports_removed, 0);
+        assert_eq!(
+            std::fs::read_to_string(tmp.join("pkg/billing.py")).unwrap(),
+            billing
+        );
+
+        std::fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_fresh_targets_relocates_after_edit() {
+        let tmp = std::env::temp_dir().join("test_cli_fresh_targets");
+        std::fs::remove_dir_all(&tmp).ok();
+        std::fs::create_dir_all(&tmp).unwrap();
+        let file = tmp.join("mod.py");
+        let original = "def dead():\n    pass\n\ndef gone():\n    pass\n";
+        std::fs::write(&file, original).unwrap();
+
+        let mut host = anatomist::parser::ParserHost::new().unwrap();
+        let scanned = host.dissect(&file).unwrap();
+        let hash = *blake3::hash(original.as_bytes()).as_bytes();
+        let doomed: Vec<&anatomist::Entity> = scanned.iter().collect();
+
+        // Unchanged file: scan-time ranges are trusted.
+        let targets =
+            fresh_targets(&mut host, &file, &doomed, Some(&hash), &mut event::silent).unwrap();
+        assert_eq!(targets[0].start_byte, scanned[0].start_byte);
+
+        // A formatter prepends a header and `gone` is removed by hand.
+        let edited = "\"\"\"Module docstring.\"\"\"\n\n\ndef dead():\n    pass\n";
+        std::fs::write(&file, edited).unwrap();
+        let mut notices = Vec::new();
+        let targets = fresh_targets(&mut host, &file, &doomed, Some(&hash), &mut |e| {
+            notices.push(e)
+        })
+        .unwrap();
+
+        assert_eq!(targets.len(), 1);
+        assert_eq!(targets[0].qualified_name, "dead");
+        let start = targets[0].start_byte as usize;
+        assert!(edited[start..].starts_with("def dead():"));
+        assert_eq!(notices.len(), 2, "{:?}", notices);
+
+        std::fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_deletion_order_puts_dead_callers_first() {
+        use common::registry::symbol_hash;
+
+        let tmp = std::env::temp_dir().join("test_cli_deletion_order");
+        std::fs::remove_dir_all(&tmp).ok();
+        std::fs::create_dir_all(&tmp).unwrap();
+        // `stray` has no dead caller, but `helper` does: the file still waits.
+        std::fs::write(
+            tmp.join("a_helpers.py"),
+            "def stray():\n    pass\n\ndef helper():\n    pass\n",
+        )
+        .unwrap();
+        std::fs::write(tmp.join("b_views.py"), "def old_view():\n    helper()\n").unwrap();
+        std::fs::write(tmp.join("c_misc.py"), "def unused():\n    pass\n").unwrap();
+
+        let mut host = anatomist::parser::ParserHost::new().unwrap();
+        let mut entities = Vec::new();
+        for f in ["a_helpers.py", "b_views.py", "c_misc.py"] {
+            entities.extend(host.dissect(&tmp.join(f)).unwrap());
+        }
+        let dead: Vec<&anatomist::Entity> = entities.iter().collect();
+        let id = |name: &str| {
+            let e = dead.iter().find(|e| e.name == name).unwrap();
+            symbol_hash(&e.symbol_id())
+        };
+
+        // old_view -> helper: b_views.py must be rewritten before a_helpers.py.
+        let mut graph = petgraph::graph::DiGraph::<u64, ()>::new();
+        let caller = graph.add_node(id("old_view"));
+        let callee = graph.add_node(id("helper"));
+        graph.add_edge(caller, callee, ());
+
+        let files: Vec<String> = deletion_order(&dead, &graph)
+            .into_iter()
+            .map(|(f, _)| f.rsplit('/').next().unwrap().to_string())
+            .collect();
+        assert_eq!(files, vec!["b_views.py", "c_misc.py", "a_helpers.py"]);
+
+        std::fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_single_file_script_kill_list_from_entry_point() {
+        let tmp = std::env::temp_dir().join("test_cli_single_file_kill_list");
+        std::fs::remove_dir_all(&tmp).ok();
+        std::fs::create_dir_all(&tmp).unwrap();
+        std::fs::write(
+            tmp.join("script.py"),
+            "def helper():\n    pass\n\ndef main():\n    helper()\n\ndef old_report():\n    format_row()\n\ndef format_row():\n    pass\n\nif __name__ == \"__main__\":\n    main()\n",
+        )
+        .unwrap();
+
+        let mut host = anatomist::parser::ParserHost::new().unwrap();
+        let graph = anatomist::graph::build_reference_graph(&tmp, &mut host).unwrap();
+        let id = |qname: &str| {
+            graph
+                .registry
+                .entries
+                .iter()
+                .find(|e| e.qualified_name == qname)
+                .unwrap()
+                .id
+        };
+        let plain = graph.graph.map(|_, n| *n, |_, _| ());
+        let kill_list = oracle::SymbolOracle::compute_kill_list(
+            &plain,
+            &[id("__MODULE__")],
+            &HashSet::new(),
+            &HashSet::new(),
+        );
+        let mut killed = kill_list.clone();
+        killed.sort();
+        let mut expected = vec![id("old_report"), id("format_row")];
+        expected.sort();
+        assert_eq!(killed, expected);
+
+        // Dead caller before its dead callee.
+        let plan = oracle::deletion_plan(&plain, &kill_list);
+        assert_eq!(plan, vec![vec![id("old_report")], vec![id("format_row")]]);
+
+        std::fs::remove_dir_all(tmp).ok();
+    }
+}
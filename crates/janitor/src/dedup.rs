@@ -0,0 +1,580 @@
+//! `dedup`: find structurally duplicate functions and rewrite them with the
+//! Safe Proxy Pattern.
+//!
+//! Every member of a duplicate group keeps its signature but its body becomes
+//! a call to one module-level `_impl` in the canonical file. All rewrites are
+//! verified by one test run through the shadow tree and rolled back together.
+
+use crate::event::Event;
+use crate::verification::{Outcome, Verifier};
+use forge::DuplicateGroup;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+/// Source extensions `dedup` reports on: everything the parser fingerprints.
+const DEDUP_EXTENSIONS: &[&str] = &[
+    "py", "rs", "js", "jsx", "ts", "tsx", "cpp", "cxx", "cc", "h", "hpp",
+];
+
+/// Options of [`crate::Janitor::dedup`].
+#[derive(Debug, Clone, Default)]
+pub struct DedupOptions {
+    /// Only search Python files, the language [`crate::Janitor::apply_dedup`]
+    /// can rewrite.
+    pub python_only: bool,
+    /// Report near-duplicates at this similarity (0.0–1.0) instead of exact
+    /// duplicates. Python-only.
+    pub near: Option<f32>,
+    /// Ignore bodies with fewer structural nodes than this when grouping exact
+    /// duplicates; `None` keeps [`forge::DedupOptions::min_nodes`]' default.
+    pub min_nodes: Option<u32>,
+}
+
+/// What `dedup` found.
+#[derive(Debug, Default)]
+pub struct DedupReport {
+    /// Source files searched.
+    pub files: usize,
+    /// Exact duplicate groups (empty with [`DedupOptions::near`]).
+    pub groups: Vec<DuplicateGroup>,
+    /// Start line of every member of `groups`, keyed by `(file, qualified name)`.
+    pub start_lines: HashMap<(String, String), u32>,
+    /// Near-duplicate groups (only with [`DedupOptions::near`]).
+    pub similar: Vec<forge::similarity::SimilarGroup>,
+}
+
+impl DedupReport {
+    /// Start line of a group member, or 0 if unknown.
+    pub fn start_line(&self, file: &str, qualified_name: &str) -> u32 {
+        self.start_lines
+            .get(&(file.to_string(), qualified_name.to_string()))
+            .copied()
+            .unwrap_or(0)
+    }
+}
+
+/// Finds duplicate (or, with `near`, similar) functions under `path`.
+pub(crate) fn find(
+    path: &Path,
+    options: &DedupOptions,
+    notify: &mut dyn FnMut(Event),
+) -> anyhow::Result<DedupReport> {
+    use anatomist::{heuristics::pytest::PytestFixtureHeuristic, parser::ParserHost};
+
+    // Exact-hash reports cover every parsed language; near-duplicate scoring and
+    // Safe Proxy rewrites are Python-only.
+    let extensions = if options.python_only || options.near.is_some() {
+        &["py"][..]
+    } else {
+        DEDUP_EXTENSIONS
+    };
+    let files = collect_source_files(path, extensions);
+    let mut report = DedupReport {
+        files: files.len(),
+        ..Default::default()
+    };
+    if files.is_empty() {
+        return Ok(report);
+    }
+
+    let mut host = ParserHost::new()?;
+    host.register_heuristic(Box::new(PytestFixtureHeuristic));
+
+    if let Some(threshold) = options.near {
+        if !(0.0..=1.0).contains(&threshold) {
+            anyhow::bail!("--threshold must be between 0.0 and 1.0, got {}", threshold);
+        }
+        // Bodies are fingerprinted on the trees the entities came from.
+        let mut all_entities = Vec::new();
+        let mut trees = HashMap::new();
+        for file_path in &files {
+            match host.dissect_python(file_path) {
+                Ok((entities, tree)) => {
+                    if let Some(first) = entities.first() {
+                        trees.insert(first.file_path.clone(), tree);
+                    }
+                    all_entities.extend(entities);
+                }
+                Err(e) => skipped(notify, file_path, e),
+            }
+        }
+        report.similar =
+            forge::similarity::find_similar_groups(&all_entities, |f| trees.get(f), threshold);
+        return Ok(report);
+    }
+
+    // Hashes are accumulated project-wide so copies in different files group together.
+    let mut all_entities = Vec::new();
+    for file_path in &files {
+        match host.dissect(file_path) {
+            Ok(e) => all_entities.extend(e),
+            Err(e) => skipped(notify, file_path, e),
+        }
+    }
+
+    let mut grouping = forge::DedupOptions::default();
+    if let Some(min_nodes) = options.min_nodes {
+        grouping.min_nodes = min_nodes;
+    }
+    report.groups = forge::find_duplicate_groups(&all_entities, &grouping);
+    report.start_lines = all_entities
+        .into_iter()
+        .map(|e| ((e.file_path, e.qualified_name), e.start_line))
+        .collect();
+    Ok(report)
+}
+
+/// Warns that `file_path` is left out of the report.
+fn skipped(notify: &mut dyn FnMut(Event), file_path: &Path, e: anatomist::AnatomistError) {
+    notify(Event::Warning(format!(
+        "warning: skipping {}: {}",
+        file_path.display(),
+        e
+    )));
+}
+
+fn collect_source_files(path: &Path, extensions: &[&str]) -> Vec<PathBuf> {
+    if path.is_file() {
+        return vec![path.to_path_buf()];
+    }
+    common::walk::walk(path)
+        .filter_map(|e| e.ok())
+        .filter(|e| {
+            e.file_type().is_some_and(|t| t.is_file())
+                && e.path()
+                    .extension()
+                    .and_then(|x| x.to_str())
+                    .is_some_and(|x| extensions.contains(&x))
+        })
+        .map(|e| e.path().to_path_buf())
+        .collect()
+}
+
+/// Pending edits for one file during `apply`.
+#[derive(Default)]
+struct FileEdits {
+    replacements: Vec<reaper::ReplacementTarget>,
+    impl_blocks: Vec<String>,
+    imports: Vec<String>,
+}
+
+/// Rewrites `groups` with the Safe Proxy Pattern and verifies the result with
+/// `verifier` through the shadow tree. Returns the rewritten files; nothing is
+/// kept if the tests fail.
+pub(crate) fn apply(
+    groups: &[DuplicateGroup],
+    project_root: &Path,
+    canonical_module: Option<&str>,
+    verifier: &Verifier,
+    notify: &mut dyn FnMut(Event),
+) -> anyhow::Result<Vec<String>> {
+    use reaper::SafeDeleter;
+
+    let mut by_file = plan_edits(groups, project_root, canonical_module, notify)?;
+    if by_file.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    // Verify through the shadow tree so the suite never writes into the project.
+    let shadow_path = project_root.join(".janitor").join("shadow_src");
+    let manager = shadow::ShadowManager::initialize(project_root, &shadow_path)?;
+
+    // One transaction across every touched file: a single test run decides.
+    let mut deleter = SafeDeleter::new(project_root)?;
+    for (file, edits) in by_file.iter_mut() {
+        deleter.edit(Path::new(file.as_str()), |content| rewrite(content, edits))??;
+    }
+
+    // Copied shadow trees do not see the rewrites until re-synced.
+    manager.sync()?;
+    match verifier.check(manager.shadow_root()) {
+        Ok(outcome) => {
+            if let Outcome::Skipped { reason } = outcome {
+                notify(Event::Warning(format!(
+                    "note: {} — skipping verification",
+                    reason
+                )));
+            }
+            deleter.commit()?;
+            Ok(by_file.into_keys().collect())
+        }
+        Err(e) => {
+            notify(Event::Warning(format!(
+                "TESTS FAILED: {}. Rolling back...",
+                e
+            )));
+            deleter.restore_all()?;
+            Err(e)
+        }
+    }
+}
+
+/// The rewrite [`apply`] would make, as a patch: nothing is written and no
+/// test runs.
+pub(crate) fn patch(
+    groups: &[DuplicateGroup],
+    project_root: &Path,
+    canonical_module: Option<&str>,
+    notify: &mut dyn FnMut(Event),
+) -> anyhow::Result<reaper::patch::Patch> {
+    let mut patch = reaper::patch::Patch::new(project_root)?;
+    for (file, edits) in plan_edits(groups, project_root, canonical_module, notify)?.iter_mut() {
+        patch.edit(Path::new(file.as_str()), |content| rewrite(content, edits))??;
+    }
+    Ok(patch)
+}
+
+/// Plans the proxies of every group, by file. A group with a member that
+/// cannot be proxied is skipped with a warning.
+fn plan_edits(
+    groups: &[DuplicateGroup],
+    project_root: &Path,
+    canonical_module: Option<&str>,
+    notify: &mut dyn FnMut(Event),
+) -> anyhow::Result<BTreeMap<String, FileEdits>> {
+    let canonical_root = std::fs::canonicalize(project_root)?;
+    let module_of = |file: &str| {
+        std::fs::canonicalize(file)
+            .ok()
+            .and_then(|abs| module_name(&canonical_root, &abs))
+    };
+
+    let mut sources: HashMap<String, Vec<u8>> = HashMap::new();
+    let mut by_file: BTreeMap<String, FileEdits> = BTreeMap::new();
+
+    for group in groups {
+        // Test code is never rewritten.
+        let members: Vec<&(String, String, u32, u32)> = group
+            .members
+            .iter()
+            .filter(|(file, _, _, _)| {
+                let file = std::fs::canonicalize(file).unwrap_or_else(|_| file.into());
+                !is_test_path(&canonical_root, &file)
+            })
+            .collect();
+        if members.len() < 2 {
+            continue;
+        }
+
+        // Canonical location: the requested module if it holds a member, else the
+        // first file alphabetically (members are sorted by file path).
+        let canon = canonical_module
+            .and_then(|module| {
+                members
+                    .iter()
+                    .copied()
+                    .find(|(file, _, _, _)| module_of(file).as_deref() == Some(module))
+            })
+            .unwrap_or(members[0]);
+        let (canon_file, canon_qname, _, _) = canon;
+        let impl_name = format!("_{}_impl", canon_qname.replace('.', "_"));
+        let canon_module = module_of(canon_file)
+            .ok_or_else(|| anyhow::anyhow!("Cannot derive module name for {}", canon_file))?;
+
+        // Every member must be proxyable, or the group is left untouched.
+        let plan = match plan_proxies(&mut sources, canon, &members, &impl_name) {
+            Ok(plan) => plan,
+            Err(e) => {
+                notify(Event::Warning(format!(
+                    "skipping duplicates of {}: {}",
+                    canon_qname, e
+                )));
+                continue;
+            }
+        };
+
+        by_file
+            .entry(canon_file.clone())
+            .or_default()
+            .impl_blocks
+            .push(plan.impl_block);
+        for (file, replacement) in plan.replacements {
+            let edits = by_file.entry(file.to_string()).or_default();
+            edits.replacements.push(replacement);
+            let import = format!("from {} import {}\n", canon_module, impl_name);
+            if file != canon_file && !edits.imports.contains(&import) {
+                edits.imports.push(import);
+            }
+        }
+    }
+
+    Ok(by_file)
+}
+
+/// Applies `edits` to the decoded `content` of one file: proxies first, then
+/// the `_impl` blocks at the end and the imports at the top.
+fn rewrite(content: &mut Vec<u8>, edits: &mut FileEdits) -> anyhow::Result<()> {
+    reaper::replace_ranges(content, &mut edits.replacements);
+    let mut current = String::from_utf8(std::mem::take(content))?;
+    for block in &edits.impl_blocks {
+        current.push_str(block);
+    }
+    if !edits.imports.is_empty() {
+        current = insert_imports(&current, &edits.imports.concat());
+    }
+    *content = current.into_bytes();
+    Ok(())
+}
+
+/// The rewrite of one duplicate group.
+#[derive(Debug)]
+struct ProxyPlan<'a> {
+    /// Module-level `_impl`, appended to the canonical file.
+    impl_block: String,
+    /// `(file, proxy body)` per member.
+    replacements: Vec<(&'a str, reaper::ReplacementTarget)>,
+}
+
+/// Builds the canonical `_impl` definition and one proxy replacement per member.
+fn plan_proxies<'a>(
+    sources: &mut HashMap<String, Vec<u8>>,
+    canon: &(String, String, u32, u32),
+    members: &[&'a (String, String, u32, u32)],
+    impl_name: &str,
+) -> Result<ProxyPlan<'a>, reaper::ReaperError> {
+    use reaper::proxy::ProxyTarget;
+
+    for (file, _, _, _) in members.iter().copied().chain([canon]) {
+        if !sources.contains_key(file) {
+            sources.insert(
+                file.clone(),
+                common::source::read(Path::new(file))?.into_vec(),
+            );
+        }
+    }
+    let canonical = ProxyTarget::parse(&sources[&canon.0], canon.2)?;
+    let mut planned = Vec::with_capacity(members.len());
+    for (file, qualified_name, start_byte, _) in members.iter().copied() {
+        let member = ProxyTarget::parse(&sources[file], *start_byte)?;
+        planned.push((
+            file.as_str(),
+            reaper::ReplacementTarget {
+                qualified_name: qualified_name.clone(),
+                start_byte: member.body_start,
+                end_byte: member.body_end,
+                replacement: member.proxy_body(impl_name, &canonical)?,
+            },
+        ));
+    }
+    Ok(ProxyPlan {
+        impl_block: canonical.impl_definition(impl_name),
+        replacements: planned,
+    })
+}
+
+/// Returns `true` if `file` lives under a `tests/` or `test/` directory of
+/// the project at `root`; directories above the root (a checkout in
+/// `/home/ci/test/`) do not count.
+fn is_test_path(root: &Path, file: &Path) -> bool {
+    file.strip_prefix(root)
+        .unwrap_or(file)
+        .components()
+        .any(|c| c.as_os_str() == "tests" || c.as_os_str() == "test")
+}
+
+/// Derives the dotted module name of `file` relative to `root` (`pkg/util.py` → `pkg.util`).
+fn module_name(root: &Path, file: &Path) -> Option<String> {
+    let rel = file.strip_prefix(root).ok()?;
+    let mut parts: Vec<String> = rel
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().into_owned())
+        .collect();
+    let last = parts.pop()?;
+    let stem = last.strip_suffix(".py")?;
+    if stem != "__init__" {
+        parts.push(stem.to_string());
+    }
+    if parts.is_empty() {
+        return None;
+    }
+    Some(parts.join("."))
+}
+
+/// Inserts `imports` after any shebang, encoding cookie, module docstring, and
+/// `from __future__` lines at the top of `content`.
+fn insert_imports(content: &str, imports: &str) -> String {
+    let mut offset = 0usize;
+    let mut in_docstring: Option<&str> = None;
+    let mut seen_docstring = false;
+
+    for line in content.split_inclusive('\n') {
+        let trimmed = line.trim();
+        if let Some(quote) = in_docstring {
+            offset += line.len();
+            if trimmed.ends_with(quote) {
+                in_docstring = None;
+            }
+            continue;
+        }
+        let is_header = trimmed.starts_with("#!")
+            || trimmed.starts_with("# -*-")
+            || trimmed.starts_with("from __future__");
+        if is_header {
+            offset += line.len();
+            continue;
+        }
+        let quote = ["\"\"\"", "'''"]
+            .into_iter()
+            .find(|q| trimmed.starts_with(q));
+        if let (Some(quote), false) = (quote, seen_docstring) {
+            seen_docstring = true;
+            offset += line.len();
+            let closed = trimmed.len() >= 6 && trimmed[3..].ends_with(quote);
+            if !closed {
+                in_docstring = Some(quote);
+            }
+            continue;
+        }
+        break;
+    }
+
+    format!("{}{}{}", &content[..offset], imports, &content[offset..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_module_name() {
+        let root = Path::new("/proj");
+        assert_eq!(
+            module_name(root, Path::new("/proj/pkg/util.py")).as_deref(),
+            Some("pkg.util")
+        );
+        assert_eq!(
+            module_name(root, Path::new("/proj/pkg/__init__.py")).as_deref(),
+            Some("pkg")
+        );
+        assert_eq!(module_name(root, Path::new("/other/x.py")), None);
+    }
+
+    #[test]
+    fn test_insert_imports_after_header() {
+        let src = "#!/usr/bin/env python\n\"\"\"Doc\nstring.\"\"\"\nfrom __future__ import annotations\nimport os\n";
+        let out = insert_imports(src, "from a import _f_impl\n");
+        assert_eq!(
+            out,
+            "#!/usr/bin/env python\n\"\"\"Doc\nstring.\"\"\"\nfrom __future__ import annotations\nfrom a import _f_impl\nimport os\n"
+        );
+        assert_eq!(insert_imports("x = 1\n", "import y\n"), "import y\nx = 1\n");
+    }
+
+    #[test]
+    fn test_plan_proxies_decorated_async_members() {
+        let tmp = std::env::temp_dir().join("test_cli_plan_proxies");
+        std::fs::remove_dir_all(&tmp).ok();
+        std::fs::create_dir_all(&tmp).unwrap();
+        let a = "@retry(times=3, delay=(1, 2))\nasync def fetch(url, *, timeout=10):\n    resp = await get(url, timeout)\n    return resp.json()\n";
+        let b = "class Client:\n    async def load(self_url, *, wait=5):\n        resp = await get(self_url, wait)\n        return resp.json()\n";
+        std::fs::write(tmp.join("a.py"), a).unwrap();
+        std::fs::write(tmp.join("b.py"), b).unwrap();
+        let file = |name: &str| tmp.join(name).to_string_lossy().into_owned();
+        let canon = (file("a.py"), "fetch".to_string(), 0, a.len() as u32);
+        let member = (
+            file("b.py"),
+            "Client.load".to_string(),
+            b.find("async def").unwrap() as u32,
+            b.len() as u32,
+        );
+
+        let mut sources = HashMap::new();
+        let plan = plan_proxies(&mut sources, &canon, &[&canon, &member], "_fetch_impl").unwrap();
+        assert_eq!(
+            plan.impl_block,
+            "\n\nasync def _fetch_impl(url, *, timeout):\n    resp = await get(url, timeout)\n    return resp.json()\n"
+        );
+        let bodies: Vec<&str> = plan
+            .replacements
+            .iter()
+            .map(|(_, r)| r.replacement.as_str())
+            .collect();
+        assert_eq!(
+            bodies,
+            vec![
+                "    return await _fetch_impl(url, timeout=timeout)",
+                "        return await _fetch_impl(self_url, timeout=wait)",
+            ]
+        );
+
+        // A nested duplicate makes the whole group unproxyable.
+        let c = "def outer():\n    async def fetch(url, *, timeout=10):\n        return await get(url, timeout)\n    return fetch\n";
+        std::fs::write(tmp.join("c.py"), c).unwrap();
+        let nested = (
+            file("c.py"),
+            "fetch".to_string(),
+            c.find("async def").unwrap() as u32,
+            c.len() as u32,
+        );
+        let err = plan_proxies(
+            &mut HashMap::new(),
+            &canon,
+            &[&canon, &nested],
+            "_fetch_impl",
+        )
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Cannot proxy `fetch`: it is nested"));
+
+        std::fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_patch_leaves_the_project_untouched() {
+        let tmp = std::env::temp_dir().join("test_dedup_patch");
+        std::fs::remove_dir_all(&tmp).ok();
+        std::fs::create_dir_all(&tmp).unwrap();
+        let body = "def total(rows):\n    kept = [r for r in rows if r]\n    return sum(kept)\n";
+        let b = format!("import os\n\n\n{}", body.replace("total", "summed"));
+        std::fs::write(tmp.join("a.py"), body).unwrap();
+        std::fs::write(tmp.join("b.py"), &b).unwrap();
+
+        let options = DedupOptions {
+            python_only: true,
+            ..Default::default()
+        };
+        let found = find(&tmp, &options, &mut crate::event::silent).unwrap();
+        let patch = patch(&found.groups, &tmp, None, &mut crate::event::silent).unwrap();
+        assert_eq!(patch.changed_files(), vec!["a.py", "b.py"]);
+        assert_eq!(std::fs::read_to_string(tmp.join("b.py")).unwrap(), b);
+
+        let text = String::from_utf8(patch.render().unwrap()).unwrap();
+        assert!(text.contains(
+            "--- a/b.py\n+++ b/b.py\n@@ -1,6 +1,6 @@\n+from a import _total_impl\n import os\n"
+        ));
+        assert!(text.contains("+def _total_impl(rows):\n"));
+
+        // `git apply` accepts it as written, when git is around.
+        let git_apply = |check: bool| {
+            let mut git = std::process::Command::new("git");
+            git.arg("apply").current_dir(&tmp);
+            if check {
+                git.arg("--check");
+            }
+            git.arg(tmp.join("out.diff")).status()
+        };
+        std::fs::write(tmp.join("out.diff"), &text).unwrap();
+        if let Ok(status) = git_apply(true) {
+            assert!(status.success());
+            assert!(git_apply(false).unwrap().success());
+            let b = std::fs::read_to_string(tmp.join("b.py")).unwrap();
+            assert!(b.starts_with("from a import _total_impl\nimport os\n"));
+            assert!(b.ends_with("def summed(rows):\n    return _total_impl(rows)\n"));
+        }
+
+        std::fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_is_test_path() {
+        let root = Path::new("/ci/test/proj");
+        assert!(is_test_path(
+            root,
+            Path::new("/ci/test/proj/tests/test_a.py")
+        ));
+        assert!(is_test_path(root, Path::new("pkg/test/helpers.py")));
+        assert!(!is_test_path(root, Path::new("/ci/test/proj/pkg/util.py")));
+        assert!(!is_test_path(root, Path::new("pkg/testing.py")));
+    }
+}
@@ -0,0 +1,24 @@
+//! # Events
+//!
+//! Observers passed to the `*_observed` methods of [`crate::Janitor`] receive
+//! an [`Event`] as the operation progresses. The library never prints; the
+//! CLI routes [`Event::Info`] to stdout, [`Event::Warning`] to stderr, and
+//! draws [`Event::Pipeline`] as a status line. A failed test run carries the
+//! output of the test command in its
+//! [`VerificationFailure`](crate::verification::VerificationFailure) error.
+
+use anatomist::progress::PipelineEvent;
+
+/// One notification from a [`crate::Janitor`] operation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    /// Scan progress from the 6-stage pipeline.
+    Pipeline(PipelineEvent),
+    /// A progress line: what was simulated, deleted, or ghosted.
+    Info(String),
+    /// A skipped file, a degraded stage, or a failure about to be rolled back.
+    Warning(String),
+}
+
+/// Observer that ignores every event.
+pub fn silent(_: Event) {}
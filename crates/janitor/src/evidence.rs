@@ -0,0 +1,299 @@
+//! Runtime and test-suite evidence fed into the pipeline's last stages.
+
+use crate::event::Event;
+use common::diagnostics::{codes, Diagnostic, Diagnostics};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// Runtime liveness sources (`--logs`, `--coverage`).
+#[derive(Debug, Clone, Default)]
+pub struct RuntimeEvidence {
+    /// Runtime logs; `.json`, `.json.gz` and `.json.zst` are OTLP log streams.
+    pub logs: Vec<PathBuf>,
+    /// `coverage json` report.
+    pub coverage: Option<PathBuf>,
+}
+
+/// Collects the IDs of symbols seen at runtime, for [`ScanOptions::live_ids`].
+///
+/// `.json`, `.json.gz` and `.json.zst` logs are read as OTLP log streams by
+/// `lazarus`; any other log is scanned line by line with
+/// `reaper::SimpleLogTracker`. A coverage report adds every function whose
+/// body executed (see `reaper::coverage`). Malformed OTLP records are
+/// skipped and reported in `diagnostics`.
+///
+/// Symbols are looked up in `graph`, the one the scan runs on, before its
+/// entities are consumed.
+///
+/// [`ScanOptions::live_ids`]: anatomist::pipeline::ScanOptions::live_ids
+pub(crate) fn runtime_live_ids(
+    project_root: &Path,
+    graph: &anatomist::graph::ReferenceGraph,
+    evidence: &RuntimeEvidence,
+    diagnostics: &mut Diagnostics,
+    notify: &mut dyn FnMut(Event),
+) -> anyhow::Result<HashSet<u64>> {
+    use reaper::{coverage::CoverageReport, LivenessTracker, SimpleLogTracker};
+
+    let mut live = HashSet::new();
+    if evidence.logs.is_empty() && evidence.coverage.is_none() {
+        return Ok(live);
+    }
+    let registry = anatomist::pipeline::liveness_registry(graph);
+
+    if let Some(report) = &evidence.coverage {
+        let covered = CoverageReport::load(report, project_root)
+            .and_then(|coverage| coverage.alive_ids(&registry))
+            .map_err(|e| anyhow::anyhow!("{}: {}", report.display(), e))?;
+        notify(Event::Info(format!(
+            "Coverage evidence: {} symbols executed.",
+            covered.len()
+        )));
+        live.extend(covered);
+    }
+
+    if evidence.logs.is_empty() {
+        return Ok(live);
+    }
+    let mut tracker = SimpleLogTracker::new(
+        registry
+            .entries
+            .iter()
+            .map(|e| (e.id, e.fully_qualified_name())),
+    )?;
+    let mut logged = HashSet::new();
+    for log in &evidence.logs {
+        let name = log.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+        if [".json", ".json.gz", ".json.zst"]
+            .iter()
+            .any(|ext| name.ends_with(ext))
+        {
+            logged.extend(lazarus::ingest_otlp_logs(log, &registry, diagnostics)?);
+        } else {
+            tracker
+                .ingest_log(log)
+                .map_err(|e| anyhow::anyhow!("{}: {}", log.display(), e))?;
+        }
+    }
+    logged.extend(tracker.alive_set());
+    notify(Event::Info(format!(
+        "Runtime evidence: {} symbols seen in {} log file(s).",
+        logged.len(),
+        evidence.logs.len()
+    )));
+    live.extend(logged);
+    Ok(live)
+}
+
+/// Runs `pytest --collect-only` for the test-fingerprint stage.
+///
+/// A missing pytest or a collection error only costs the test-fingerprint
+/// stage: the scan goes on without it, with a warning in `diagnostics`.
+pub(crate) fn test_evidence(
+    project_root: &Path,
+    diagnostics: &mut Diagnostics,
+) -> Option<anatomist::pipeline::TestEvidence> {
+    use reaper::{test_fingerprint, ReaperError};
+
+    match test_fingerprint::collect_test_surface(project_root) {
+        Ok(surface) => Some(anatomist::pipeline::TestEvidence {
+            names: surface.names,
+            files: surface.files,
+        }),
+        Err(e) => {
+            let message = match e {
+                ReaperError::IoError(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    "pytest not found — skipping test fingerprint".to_string()
+                }
+                e => format!("{} — skipping test fingerprint", e),
+            };
+            diagnostics.push(Diagnostic::warning(codes::EVIDENCE_UNAVAILABLE, message));
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event;
+    use anatomist::progress;
+
+    #[test]
+    fn test_runtime_logs_rescue_dead_symbols() {
+        use anatomist::{parser::ParserHost, pipeline};
+
+        let tmp = std::env::temp_dir().join("test_cli_runtime_logs");
+        std::fs::remove_dir_all(&tmp).ok();
+        std::fs::create_dir_all(tmp.join("pkg")).unwrap();
+        std::fs::write(tmp.join("pkg/__init__.py"), "").unwrap();
+        std::fs::write(
+            tmp.join("pkg/jobs.py"),
+            "def nightly_sync():\n    pass\n\ndef webhook_retry():\n    pass\n\ndef never_called():\n    pass\n",
+        )
+        .unwrap();
+        // Logs live outside the project so the grep shield never sees them.
+        let log_dir = std::env::temp_dir().join("test_cli_runtime_logs_fixtures");
+        std::fs::create_dir_all(&log_dir).unwrap();
+        let logs = vec![log_dir.join("app.log"), log_dir.join("otlp.json")];
+        std::fs::write(&logs[0], "2026-10-01 INFO pkg.jobs.nightly_sync finished\n").unwrap();
+        std::fs::write(
+            &logs[1],
+            "{\"body\": \"retrying pkg.jobs.webhook_retry(3)\"}\n{\"body\": \"pkg.jobs.never_called_v2\"}\n",
+        )
+        .unwrap();
+
+        let mut host = ParserHost::new().unwrap();
+        let graph =
+            pipeline::build_graph(&tmp, &mut host, &Default::default(), &mut progress::silent)
+                .unwrap();
+        let evidence = RuntimeEvidence {
+            logs: logs.clone(),
+            coverage: None,
+        };
+        let options = pipeline::ScanOptions {
+            live_ids: runtime_live_ids(
+                &tmp,
+                &graph,
+                &evidence,
+                &mut Diagnostics::new(),
+                &mut event::silent,
+            )
+            .unwrap(),
+            ..Default::default()
+        };
+        let result =
+            pipeline::run_on_built_graph(&tmp, &options, graph, &mut progress::silent).unwrap();
+
+        assert_eq!(result.runtime_rescued, 2);
+        let dead: Vec<&str> = result.dead.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(dead, vec!["never_called"]);
+        let rescued = result
+            .protected
+            .iter()
+            .filter(|e| e.protected_by == Some(common::Protection::RuntimeLiveness))
+            .count();
+        assert_eq!(rescued, 2);
+
+        std::fs::remove_dir_all(tmp).ok();
+        std::fs::remove_dir_all(log_dir).ok();
+    }
+
+    #[test]
+    fn test_coverage_report_rescues_executed_functions() {
+        use anatomist::{parser::ParserHost, pipeline};
+
+        let tmp = std::env::temp_dir().join("test_cli_coverage");
+        std::fs::remove_dir_all(&tmp).ok();
+        std::fs::create_dir_all(tmp.join("pkg")).unwrap();
+        std::fs::write(
+            tmp.join("pkg/exports.py"),
+            "def build_csv():\n    return 1\n",
+        )
+        .unwrap();
+        std::fs::write(
+            tmp.join("pkg/legacy.py"),
+            "def build_xml():\n    return 2\n",
+        )
+        .unwrap();
+        // The report lives outside the project so the grep shield never reads it.
+        let report = std::env::temp_dir().join("test_cli_coverage.json");
+        std::fs::write(
+            &report,
+            r#"{"files": {
+                "pkg/exports.py": {"executed_lines": [1, 2]},
+                "pkg/legacy.py": {"executed_lines": [1]}
+            }}"#,
+        )
+        .unwrap();
+
+        let mut host = ParserHost::new().unwrap();
+        let graph =
+            pipeline::build_graph(&tmp, &mut host, &Default::default(), &mut progress::silent)
+                .unwrap();
+        let evidence = RuntimeEvidence {
+            logs: Vec::new(),
+            coverage: Some(report.clone()),
+        };
+        let mut notices = Vec::new();
+        let options = pipeline::ScanOptions {
+            live_ids: runtime_live_ids(
+                &tmp,
+                &graph,
+                &evidence,
+                &mut Diagnostics::new(),
+                &mut |e| notices.push(e),
+            )
+            .unwrap(),
+            ..Default::default()
+        };
+        let result =
+            pipeline::run_on_built_graph(&tmp, &options, graph, &mut progress::silent).unwrap();
+
+        assert_eq!(result.runtime_rescued, 1);
+        let dead: Vec<&str> = result.dead.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(dead, vec!["build_xml"]);
+        assert!(
+            matches!(&notices[..], [Event::Info(text)] if text.starts_with("Coverage evidence:")),
+            "{:?}",
+            notices
+        );
+
+        std::fs::remove_dir_all(tmp).ok();
+        std::fs::remove_file(report).ok();
+    }
+
+    #[test]
+    fn test_scan_with_test_fingerprint() {
+        use anatomist::{parser::ParserHost, pipeline};
+
+        let tmp = std::env::temp_dir().join("test_cli_test_fingerprint");
+        std::fs::remove_dir_all(&tmp).ok();
+        std::fs::create_dir_all(tmp.join("app")).unwrap();
+        std::fs::create_dir_all(tmp.join("tests")).unwrap();
+        std::fs::write(
+            tmp.join("app/util.py"),
+            "def total(xs):\n    return sum(xs)\n\n\
+def helper():\n    return 1\n\n\
+def forgotten():\n    return 2\n\n\
+class SmokeSuite:\n    pass\n",
+        )
+        .unwrap();
+        std::fs::write(
+            tmp.join("tests/test_util.py"),
+            "from unittest import mock\n\n\
+def test_check_total():\n    with mock.patch(\"app.util.helper\"):\n        assert True\n",
+        )
+        .unwrap();
+        // What `pytest --collect-only -q` prints for this project, plus a
+        // collected class whose name matches an application symbol.
+        let collected = b"tests/test_util.py::test_check_total\n\
+tests/test_util.py::SmokeSuite::test_runs[fast]\n\
+\n2 tests collected in 0.01s\n";
+        let surface = reaper::test_fingerprint::parse_test_surface(collected, &tmp);
+
+        let mut host = ParserHost::new().unwrap();
+        let options = pipeline::ScanOptions {
+            test_evidence: Some(pipeline::TestEvidence {
+                names: surface.names,
+                files: surface.files,
+            }),
+            ..Default::default()
+        };
+        let result = pipeline::run_with_options(&tmp, &mut host, &options).unwrap();
+
+        let suite = result
+            .protected
+            .iter()
+            .find(|e| e.name == "SmokeSuite")
+            .expect("collected class should be protected");
+        assert_eq!(suite.protected_by, Some(common::Protection::TestReference));
+        let test_only: Vec<&str> = result.test_only.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(test_only, vec!["helper"]);
+        let mut dead: Vec<&str> = result.dead.iter().map(|e| e.name.as_str()).collect();
+        dead.sort();
+        assert_eq!(dead, vec!["forgotten", "total"]);
+
+        std::fs::remove_dir_all(tmp).ok();
+    }
+}
@@ -0,0 +1,747 @@
+//! # The Janitor
+//!
+//! Library facade over the scan → plan → clean pipeline behind the `janitor`
+//! CLI, for tools that embed it (editors, CI services, bindings).
+//!
+//! ```no_run
+//! use janitor::{CleanOptions, Janitor};
+//!
+//! let janitor = Janitor::open("path/to/project")?.with_cache(true);
+//! let result = janitor.scan()?;
+//! println!("{} dead symbols", result.dead.len());
+//!
+//! // Simulated in the shadow tree; the project is left untouched.
+//! let plan = janitor.plan_clean(&CleanOptions::default())?;
+//! print!("{}", plan);
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+//!
+//! Nothing here prints: each operation has an `*_observed` variant that
+//! reports progress and warnings as [`Event`]s (see [`event`]).
+
+pub mod baseline;
+mod clean;
+mod dedup;
+pub mod event;
+mod evidence;
+pub mod lsp;
+pub mod plan;
+pub mod report;
+pub mod verification;
+pub mod watch;
+
+pub use anatomist::pipeline::{Explanation, RootReport, ScanResult};
+pub use clean::CleanOptions;
+pub use dedup::{DedupOptions, DedupReport};
+pub use event::Event;
+pub use evidence::RuntimeEvidence;
+pub use reaper::patch::Patch;
+pub use report::CleanReport;
+pub use watch::{WatchReport, WatchSession};
+
+use anyhow::Context;
+use common::config::JanitorConfig;
+use common::diagnostics::{codes, Diagnostic, Diagnostics};
+use common::lock::{LockError, LockMode, ProjectLock};
+use common::wisdom::WisdomRegistry;
+use plan::CleanPlan;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use verification::Verifier;
+
+/// Framework rules baked from `rules/` by `wisdom-bake` at release time.
+const DEFAULT_WISDOM: &[u8] = include_bytes!("../assets/wisdom.rkyv");
+
+/// One project and the settings every operation on it shares.
+///
+/// Built with [`Janitor::open`] and the consuming `with_*` methods.
+pub struct Janitor {
+    root: PathBuf,
+    config: JanitorConfig,
+    wisdom: Arc<WisdomRegistry>,
+    library_mode: bool,
+    use_cache: bool,
+    strict_star_imports: bool,
+    test_fingerprint: bool,
+    evidence: RuntimeEvidence,
+    scope: Option<Vec<PathBuf>>,
+    warnings: Vec<String>,
+}
+
+impl Janitor {
+    /// Opens the project at `root` (a directory, or a single-file script),
+    /// loading its `.janitor.toml` and `.janitor/wisdom.rkyv`, or the embedded
+    /// default rules when it has none.
+    ///
+    /// Non-fatal configuration problems are kept in [`Janitor::warnings`].
+    pub fn open(root: impl Into<PathBuf>) -> anyhow::Result<Self> {
+        let root = root.into();
+        let dir = project_dir(&root);
+        let (config, config_warnings) = JanitorConfig::load(&dir)?;
+        check_roots(&dir, &config.workspace.roots)
+            .with_context(|| format!("{}: [workspace] roots", JanitorConfig::FILE_NAME))?;
+        let warnings = config_warnings
+            .into_iter()
+            .map(|w| format!("{}: {}", JanitorConfig::FILE_NAME, w))
+            .collect();
+        Ok(Self {
+            wisdom: load_wisdom(&dir)?,
+            root,
+            config,
+            library_mode: false,
+            use_cache: false,
+            strict_star_imports: false,
+            test_fingerprint: false,
+            evidence: RuntimeEvidence::default(),
+            scope: None,
+            warnings,
+        })
+    }
+
+    /// Replaces the configuration loaded from `.janitor.toml`. Fails, like
+    /// [`Janitor::open`], on a `[workspace] roots` entry that is not a
+    /// directory.
+    pub fn with_config(mut self, config: JanitorConfig) -> anyhow::Result<Self> {
+        check_roots(&self.root, &config.workspace.roots)?;
+        self.config = config;
+        Ok(self)
+    }
+
+    /// Replaces the framework rules loaded by [`Janitor::open`].
+    pub fn with_wisdom(mut self, wisdom: Arc<WisdomRegistry>) -> Self {
+        self.wisdom = wisdom;
+        self
+    }
+
+    /// Merges JSON rules files (`scan --rules`) into the framework rules.
+    ///
+    /// An invalid file is skipped with a warning unless `strict` is set.
+    pub fn with_rules(mut self, rule_files: &[PathBuf], strict: bool) -> anyhow::Result<Self> {
+        use common::wisdom::WisdomSet;
+
+        if rule_files.is_empty() {
+            return Ok(self);
+        }
+        let mut set = self.wisdom.to_set()?;
+        for file in rule_files {
+            match WisdomSet::from_rules_file(file) {
+                Ok(rules) => set.merge(rules),
+                Err(e) if strict => anyhow::bail!("invalid rules file: {}", e),
+                Err(e) => self.warnings.push(format!("skipping rules file: {}", e)),
+            }
+        }
+        self.wisdom = Arc::new(WisdomRegistry::from_set(set)?);
+        Ok(self)
+    }
+
+    /// Protects all public top-level symbols (`--library`).
+    pub fn with_library_mode(mut self, library_mode: bool) -> Self {
+        self.library_mode = library_mode;
+        self
+    }
+
+    /// Reuses unchanged files from `.janitor/cache/` (off by default).
+    pub fn with_cache(mut self, use_cache: bool) -> Self {
+        self.use_cache = use_cache;
+        self
+    }
+
+    /// Stops treating `from m import *` as a reference to every public symbol of `m`.
+    pub fn with_strict_star_imports(mut self, strict: bool) -> Self {
+        self.strict_star_imports = strict;
+        self
+    }
+
+    /// Protects symbols the pytest-collected test suite refers to.
+    pub fn with_test_fingerprint(mut self, enabled: bool) -> Self {
+        self.test_fingerprint = enabled;
+        self
+    }
+
+    /// Symbols named in these logs or executed in this coverage report are
+    /// never dead.
+    pub fn with_evidence(mut self, evidence: RuntimeEvidence) -> Self {
+        self.evidence = evidence;
+        self
+    }
+
+    /// Reports only on symbols defined in `files` (relative to the root),
+    /// while still indexing the whole project so references from elsewhere
+    /// count (see [`anatomist::pipeline::ScanOptions::scope`]).
+    pub fn with_scope(mut self, files: Vec<PathBuf>) -> Self {
+        self.scope = Some(files);
+        self
+    }
+
+    /// Scans `roots` (relative to the project root) as one monorepo,
+    /// replacing `[workspace] roots` from `.janitor.toml`: imports resolve
+    /// against each root in order and [`ScanResult::roots`] breaks the
+    /// totals down per root.
+    pub fn with_roots(mut self, roots: Vec<String>) -> anyhow::Result<Self> {
+        check_roots(&self.root, &roots)?;
+        self.config.workspace.roots = roots;
+        Ok(self)
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn config(&self) -> &JanitorConfig {
+        &self.config
+    }
+
+    /// Files set by [`Janitor::with_scope`].
+    pub fn scope(&self) -> Option<&[PathBuf]> {
+        self.scope.as_deref()
+    }
+
+    /// Non-fatal problems met while opening the project or loading rules.
+    /// Scans also report them as [`codes::CONFIG`] diagnostics.
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    /// Runs the 6-stage pipeline.
+    pub fn scan(&self) -> anyhow::Result<ScanResult> {
+        self.scan_observed(&mut event::silent)
+    }
+
+    /// Same as [`Janitor::scan`], reporting to `notify`.
+    pub fn scan_observed(&self, notify: &mut dyn FnMut(Event)) -> anyhow::Result<ScanResult> {
+        Ok(self.clean_scan(notify)?.1)
+    }
+
+    /// Explains the pipeline's verdict on `symbol` (`qualified_name` or
+    /// `file::qualified_name`); one entry per matching symbol.
+    pub fn explain(&self, symbol: &str) -> anyhow::Result<Vec<Explanation>> {
+        let (mut host, mut options) = self.pipeline()?;
+        let graph = anatomist::pipeline::build_graph(
+            &self.root,
+            &mut host,
+            &options,
+            &mut anatomist::progress::silent,
+        )?;
+        self.add_runtime_evidence(&mut options, &graph, &mut event::silent)?;
+        anatomist::pipeline::explain_on_graph(&self.root, &options, graph, symbol)
+    }
+
+    /// Saves `result` to `.janitor/symbols.rkyv` (with the dead symbols
+    /// `baselined` held back by [`baseline::Baseline::hold_back`]) and its
+    /// orphan files to `.janitor/orphans.txt`, for `report` and `dashboard`.
+    pub fn save_registry(
+        &self,
+        result: &ScanResult,
+        baselined: &[anatomist::Entity],
+    ) -> anyhow::Result<()> {
+        use common::registry::{symbol_hash, SymbolEntry, SymbolRegistry};
+
+        let dir = self.root.join(".janitor");
+        // Entries are built one at a time while the archive is written, so the
+        // registry never holds a second copy of every symbol.
+        let mut entities: Vec<(u64, &anatomist::Entity)> = result
+            .dead
+            .iter()
+            .chain(baselined)
+            .chain(result.protected.iter())
+            .chain(result.test_only.iter())
+            .chain(result.low_confidence.iter())
+            .map(|e| (symbol_hash(&e.symbol_id()), e))
+            .collect();
+        entities.sort_by_key(|(id, _)| *id);
+        let entries = entities.iter().map(|(_, e)| SymbolEntry::from(*e));
+        SymbolRegistry::save_entries(&dir.join("symbols.rkyv"), entries)
+            .map_err(|e| anyhow::anyhow!("could not save symbols.rkyv: {}", e))?;
+        // Orphans are file-level and not part of the registry; `report --html` reads them back.
+        let orphans: String = result
+            .orphan_files
+            .iter()
+            .map(|p| format!("{p}\n"))
+            .collect();
+        std::fs::write(dir.join("orphans.txt"), orphans)
+            .map_err(|e| anyhow::anyhow!("could not save orphans.txt: {}", e))?;
+        Ok(())
+    }
+
+    /// Shadow-simulates the kill list, then deletes dead symbols and ghosts
+    /// orphan files. Requires a purge token for this project.
+    pub fn clean(&self, options: &CleanOptions) -> anyhow::Result<CleanReport> {
+        self.clean_observed(options, &mut event::silent)
+    }
+
+    /// Same as [`Janitor::clean`], reporting to `notify`.
+    pub fn clean_observed(
+        &self,
+        options: &CleanOptions,
+        notify: &mut dyn FnMut(Event),
+    ) -> anyhow::Result<CleanReport> {
+        self.authorize(&options.token)?;
+        let started = reaper::ghost::unix_now();
+        let scan = self.clean_scan(notify)?;
+        let mut report = CleanReport::default();
+        let outcome = clean::clean(
+            &self.root,
+            scan,
+            &self.verifier()?,
+            options,
+            &mut report,
+            notify,
+        );
+        self.log_run(report, outcome, started, &options.token)
+    }
+
+    /// The edits [`Janitor::clean`] would make, as a unified-diff
+    /// [`Patch`] (`clean --patch`); nothing is written and no test runs.
+    /// Requires a purge token for this project.
+    pub fn patch_clean(&self, options: &CleanOptions) -> anyhow::Result<Patch> {
+        self.patch_clean_observed(options, &mut event::silent)
+    }
+
+    /// Same as [`Janitor::patch_clean`], reporting to `notify`.
+    pub fn patch_clean_observed(
+        &self,
+        options: &CleanOptions,
+        notify: &mut dyn FnMut(Event),
+    ) -> anyhow::Result<Patch> {
+        self.authorize(&options.token)?;
+        let scan = self.clean_scan(notify)?;
+        clean::patch(&self.root, scan, options, notify)
+    }
+
+    /// Plans what [`Janitor::clean`] would delete and simulates it in the
+    /// shadow tree (`clean --dry-run`). Needs no token; the project itself is
+    /// left untouched.
+    pub fn plan_clean(&self, options: &CleanOptions) -> anyhow::Result<CleanPlan> {
+        self.plan_clean_observed(options, &mut event::silent)
+    }
+
+    /// Same as [`Janitor::plan_clean`], reporting to `notify`.
+    pub fn plan_clean_observed(
+        &self,
+        options: &CleanOptions,
+        notify: &mut dyn FnMut(Event),
+    ) -> anyhow::Result<CleanPlan> {
+        let scan = self.clean_scan(notify)?;
+        clean::plan_clean(&self.root, scan, &self.verifier()?, options, notify)
+    }
+
+    /// Executes a reviewed plan verbatim (`clean --from-plan`). Refuses a plan
+    /// whose files changed since it was written. Only `token` and
+    /// `allow_unverified` of `options` apply; the plan fixes everything else.
+    pub fn execute_plan(
+        &self,
+        plan: &CleanPlan,
+        options: &CleanOptions,
+    ) -> anyhow::Result<CleanReport> {
+        self.execute_plan_observed(plan, options, &mut event::silent)
+    }
+
+    /// Same as [`Janitor::execute_plan`], reporting to `notify`.
+    pub fn execute_plan_observed(
+        &self,
+        plan: &CleanPlan,
+        options: &CleanOptions,
+        notify: &mut dyn FnMut(Event),
+    ) -> anyhow::Result<CleanReport> {
+        self.authorize(&options.token)?;
+        let started = reaper::ghost::unix_now();
+        let mut report = CleanReport::default();
+        let outcome = clean::execute_plan(
+            &self.root,
+            plan,
+            &self.verifier()?,
+            options.allow_unverified,
+            &mut report,
+            notify,
+        );
+        self.log_run(report, outcome, started, &options.token)
+    }
+
+    /// Stamps a clean and, if it changed anything, saves it under
+    /// `.janitor/runs/`. A run that failed after editing the project is saved
+    /// too, with its error, before the error is returned.
+    fn log_run(
+        &self,
+        mut report: CleanReport,
+        outcome: anyhow::Result<()>,
+        started: u64,
+        token: &str,
+    ) -> anyhow::Result<CleanReport> {
+        report.stamp(started, token);
+        if let Err(e) = outcome {
+            if !report.changed_files() {
+                return Err(e);
+            }
+            report.error = Some(format!("{:#}", e));
+            return Err(match report.save(&project_dir(&self.root)) {
+                Ok(path) => e.context(format!("partial run logged to {}", path.display())),
+                Err(save) => e.context(format!("partial run not logged: {}", save)),
+            });
+        }
+        if !report.is_empty() {
+            report.save(&project_dir(&self.root))?;
+        }
+        Ok(report)
+    }
+
+    /// Finds structurally duplicate functions.
+    pub fn dedup(&self, options: &DedupOptions) -> anyhow::Result<DedupReport> {
+        self.dedup_observed(options, &mut event::silent)
+    }
+
+    /// Same as [`Janitor::dedup`], reporting to `notify`.
+    pub fn dedup_observed(
+        &self,
+        options: &DedupOptions,
+        notify: &mut dyn FnMut(Event),
+    ) -> anyhow::Result<DedupReport> {
+        dedup::find(&self.root, options, notify)
+    }
+
+    /// Rewrites duplicate `groups` with the Safe Proxy Pattern, verified by
+    /// the test suite. Returns the rewritten files; empty when no group could
+    /// be rewritten. Requires a purge token for this project.
+    ///
+    /// The shared `_impl` goes into `canonical_module` when it holds a member,
+    /// else into the first file alphabetically.
+    pub fn apply_dedup(
+        &self,
+        groups: &[forge::DuplicateGroup],
+        canonical_module: Option<&str>,
+        token: &str,
+    ) -> anyhow::Result<Vec<String>> {
+        self.apply_dedup_observed(groups, canonical_module, token, &mut event::silent)
+    }
+
+    /// Same as [`Janitor::apply_dedup`], reporting to `notify`.
+    pub fn apply_dedup_observed(
+        &self,
+        groups: &[forge::DuplicateGroup],
+        canonical_module: Option<&str>,
+        token: &str,
+        notify: &mut dyn FnMut(Event),
+    ) -> anyhow::Result<Vec<String>> {
+        self.authorize(token)?;
+        dedup::apply(
+            groups,
+            &project_dir(&self.root),
+            canonical_module,
+            &self.verifier()?,
+            notify,
+        )
+    }
+
+    /// The rewrite [`Janitor::apply_dedup`] would make, as a unified-diff
+    /// [`Patch`] (`dedup --apply --patch`); nothing is written and no test
+    /// runs. Requires a purge token for this project.
+    pub fn patch_dedup(
+        &self,
+        groups: &[forge::DuplicateGroup],
+        canonical_module: Option<&str>,
+        token: &str,
+    ) -> anyhow::Result<Patch> {
+        self.patch_dedup_observed(groups, canonical_module, token, &mut event::silent)
+    }
+
+    /// Same as [`Janitor::patch_dedup`], reporting to `notify`.
+    pub fn patch_dedup_observed(
+        &self,
+        groups: &[forge::DuplicateGroup],
+        canonical_module: Option<&str>,
+        token: &str,
+        notify: &mut dyn FnMut(Event),
+    ) -> anyhow::Result<Patch> {
+        self.authorize(token)?;
+        dedup::patch(groups, &project_dir(&self.root), canonical_module, notify)
+    }
+
+    /// Starts a [`WatchSession`]: builds the reference graph, scans once, and
+    /// saves the registry. The returned report is the `"ready"` scan.
+    pub fn watch_session(&self) -> anyhow::Result<(WatchSession<'_>, WatchReport)> {
+        self.watch_session_observed(&mut event::silent)
+    }
+
+    /// Same as [`Janitor::watch_session`], reporting to `notify`.
+    pub fn watch_session_observed(
+        &self,
+        notify: &mut dyn FnMut(Event),
+    ) -> anyhow::Result<(WatchSession<'_>, WatchReport)> {
+        WatchSession::start(self, notify)
+    }
+
+    /// Watches the project (`janitor watch`): hands the initial scan and one
+    /// report per burst of file changes to `on_report`, until the watcher
+    /// stops or `on_report` fails. A burst ends after `debounce` without events.
+    pub fn watch(
+        &self,
+        debounce: Duration,
+        on_report: &mut dyn FnMut(WatchReport) -> anyhow::Result<()>,
+    ) -> anyhow::Result<()> {
+        let (mut session, ready) = self.watch_session()?;
+        on_report(ready)?;
+        watch::run(&mut session, debounce, on_report)
+    }
+
+    /// Runs the pipeline, keeping the parser host for re-locating symbols.
+    pub(crate) fn clean_scan(
+        &self,
+        notify: &mut dyn FnMut(Event),
+    ) -> anyhow::Result<clean::CleanScan> {
+        let (mut host, mut options) = self.pipeline()?;
+        let graph = anatomist::pipeline::build_graph(&self.root, &mut host, &options, &mut |e| {
+            notify(Event::Pipeline(e))
+        })?;
+        self.add_runtime_evidence(&mut options, &graph, notify)?;
+        let result =
+            anatomist::pipeline::run_on_built_graph(&self.root, &options, graph, &mut |e| {
+                notify(Event::Pipeline(e))
+            })?;
+        Ok((host, result))
+    }
+
+    /// Fills `options.live_ids` from the `--logs` / `--coverage` evidence,
+    /// matched against the symbols of `graph` (built but not yet scanned).
+    pub(crate) fn add_runtime_evidence(
+        &self,
+        options: &mut anatomist::pipeline::ScanOptions,
+        graph: &anatomist::graph::ReferenceGraph,
+        notify: &mut dyn FnMut(Event),
+    ) -> anyhow::Result<()> {
+        options.live_ids = evidence::runtime_live_ids(
+            &self.root,
+            graph,
+            &self.evidence,
+            &mut options.diagnostics,
+            notify,
+        )?;
+        Ok(())
+    }
+
+    /// The parser host and pipeline options every scan of this project uses.
+    pub(crate) fn pipeline(
+        &self,
+    ) -> anyhow::Result<(
+        anatomist::parser::ParserHost,
+        anatomist::pipeline::ScanOptions,
+    )> {
+        use anatomist::heuristics::{django::DjangoHeuristic, pytest::PytestFixtureHeuristic};
+
+        let mut host = anatomist::parser::ParserHost::new()?;
+        host.register_heuristic(Box::new(PytestFixtureHeuristic));
+        host.register_heuristic(Box::new(DjangoHeuristic));
+        let mut diagnostics = Diagnostics::new();
+        diagnostics.extend(
+            self.warnings
+                .iter()
+                .map(|w| Diagnostic::warning(codes::CONFIG, w.clone())),
+        );
+        let test_evidence = if self.test_fingerprint {
+            evidence::test_evidence(&self.root, &mut diagnostics)
+        } else {
+            None
+        };
+        let options = anatomist::pipeline::ScanOptions {
+            library_mode: self.library_mode,
+            use_cache: self.use_cache,
+            strict_star_imports: self.strict_star_imports,
+            config: self.config.clone(),
+            live_ids: Default::default(),
+            test_evidence,
+            wisdom: Some(self.wisdom.clone()),
+            diagnostics,
+            scope: self.scope.clone(),
+        };
+        Ok((host, options))
+    }
+
+    fn verifier(&self) -> anyhow::Result<Verifier> {
+        Verifier::from_config(&project_dir(&self.root), &self.config)
+    }
+
+    /// Checks `token` is a valid purge token for this project.
+    fn authorize(&self, token: &str) -> anyhow::Result<()> {
+        vault::SigningOracle::verify_token_v2(token, &self.root)
+            .map(|_| ())
+            .map_err(|e| anyhow::anyhow!("purge token rejected: {}", e))
+    }
+}
+
+/// The directory holding the project's `.janitor.toml` and `.janitor/`: `root`
+/// itself, or the parent of a single-file script.
+fn project_dir(root: &Path) -> PathBuf {
+    if root.is_file() {
+        root.parent().unwrap_or(root).to_path_buf()
+    } else {
+        root.to_path_buf()
+    }
+}
+
+/// Fails on the first of `roots` (workspace roots, relative to `root`) that
+/// is not a directory.
+fn check_roots(root: &Path, roots: &[String]) -> anyhow::Result<()> {
+    match roots.iter().find(|r| !root.join(r).is_dir()) {
+        Some(missing) => anyhow::bail!("workspace root `{}` is not a directory", missing),
+        None => Ok(()),
+    }
+}
+
+/// Takes `.janitor/lock` of the project at `root` for `command` (see
+/// [`common::lock`]). A project that does not exist is not locked, so the
+/// command itself gets to report it.
+pub fn lock_project(
+    root: &Path,
+    mode: LockMode,
+    command: &str,
+    wait: bool,
+) -> Result<Option<ProjectLock>, LockError> {
+    let dir = project_dir(root);
+    if !dir.is_dir() {
+        return Ok(None);
+    }
+    ProjectLock::acquire(&dir.join(".janitor"), mode, command, wait).map(Some)
+}
+
+/// Loads the project's `.janitor/wisdom.rkyv`, or the embedded default rules
+/// when the project has none.
+fn load_wisdom(project_dir: &Path) -> anyhow::Result<Arc<WisdomRegistry>> {
+    let path = project_dir.join(".janitor").join("wisdom.rkyv");
+    let registry = if path.is_file() {
+        WisdomRegistry::load(&path).map_err(|e| anyhow::anyhow!("{}: {}", path.display(), e))?
+    } else {
+        WisdomRegistry::from_bytes(DEFAULT_WISDOM)?
+    };
+    Ok(Arc::new(registry))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rules_file_changes_verdict_and_bad_file_is_skipped() {
+        let tmp = std::env::temp_dir().join("test_cli_rules_file");
+        std::fs::remove_dir_all(&tmp).ok();
+        std::fs::create_dir_all(&tmp).unwrap();
+        std::fs::write(
+            tmp.join("plugins.py"),
+            "def auth_plugin():\n    return 1\n\ndef leftover():\n    return 2\n",
+        )
+        .unwrap();
+        let rules = tmp.join("rules.json");
+        std::fs::write(&rules, r#"{"suffix_matches": ["_plugin"]}"#).unwrap();
+        let malformed = tmp.join("malformed.json");
+        std::fs::write(&malformed, r#"{"immortality_rules": [{"patterns": []}]}"#).unwrap();
+
+        let open = |rule_files: &[PathBuf]| {
+            Janitor::open(&tmp)
+                .unwrap()
+                .with_rules(rule_files, false)
+                .unwrap()
+        };
+        let scan_dead = |janitor: &Janitor| {
+            let result = janitor.scan().unwrap();
+            let mut dead: Vec<String> = result.dead.into_iter().map(|e| e.name).collect();
+            dead.sort();
+            dead
+        };
+
+        assert_eq!(scan_dead(&open(&[])), vec!["auth_plugin", "leftover"]);
+        // The malformed file is skipped; the valid one still applies.
+        let janitor = open(&[malformed.clone(), rules.clone()]);
+        assert_eq!(scan_dead(&janitor), vec!["leftover"]);
+        assert_eq!(janitor.warnings().len(), 1);
+
+        let err = Janitor::open(&tmp)
+            .unwrap()
+            .with_rules(&[malformed], true)
+            .err()
+            .unwrap()
+            .to_string();
+        assert!(err.contains("malformed.json: rule 0:"), "{}", err);
+
+        std::fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_custom_wisdom_rule_protects_matching_symbol() {
+        use common::wisdom::{MetaPattern, WisdomSet};
+
+        let tmp = std::env::temp_dir().join("test_cli_custom_wisdom");
+        std::fs::remove_dir_all(&tmp).ok();
+        std::fs::create_dir_all(&tmp).unwrap();
+        std::fs::write(
+            tmp.join("events.py"),
+            "def on_payment_handler(event):\n    return event\n\ndef leftover():\n    return 1\n",
+        )
+        .unwrap();
+
+        // Without a project rule set the embedded default applies.
+        let default = load_wisdom(&tmp).unwrap();
+        assert!(default.matches("on_payment_handler", &[], &[]).is_none());
+
+        let mut rules = WisdomSet {
+            meta_patterns: MetaPattern {
+                suffix_matches: vec!["_handler".into()],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        rules
+            .save(&tmp.join(".janitor").join("wisdom.rkyv"))
+            .unwrap();
+
+        let result = Janitor::open(&tmp).unwrap().scan().unwrap();
+
+        let handler = result
+            .protected
+            .iter()
+            .find(|e| e.name == "on_payment_handler")
+            .expect("suffix rule should protect the handler");
+        assert_eq!(handler.protected_by, Some(common::Protection::WisdomRule));
+        assert_eq!(
+            handler
+                .protection_detail
+                .as_ref()
+                .map(|d| d.reason.as_str()),
+            Some("wisdom rule: suffix match `_handler`")
+        );
+        let dead: Vec<&str> = result.dead.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(dead, vec!["leftover"]);
+
+        std::fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_failed_run_is_logged_only_when_it_changed_files() {
+        let tmp = std::env::temp_dir().join("test_janitor_failed_run_log");
+        std::fs::remove_dir_all(&tmp).ok();
+        std::fs::create_dir_all(&tmp).unwrap();
+        let janitor = Janitor::open(&tmp).unwrap();
+        let runs = tmp.join(".janitor").join("runs");
+
+        let failed = || Err(anyhow::anyhow!("disk full"));
+        let err = janitor
+            .log_run(CleanReport::default(), failed(), 1, "token")
+            .unwrap_err();
+        assert_eq!(err.to_string(), "disk full");
+        assert!(!runs.exists());
+
+        let partial = CleanReport {
+            transaction: Some("t1".to_string()),
+            ghosted: vec![PathBuf::from("pkg/stale.py")],
+            ..Default::default()
+        };
+        let err = janitor.log_run(partial, failed(), 1, "token").unwrap_err();
+        assert!(
+            err.to_string().starts_with("partial run logged to "),
+            "{}",
+            err
+        );
+        let logged = CleanReport::load(&runs.join("1.json")).unwrap();
+        assert_eq!(logged.error.as_deref(), Some("disk full"));
+        assert_eq!(logged.ghosted, vec![PathBuf::from("pkg/stale.py")]);
+
+        std::fs::remove_dir_all(tmp).ok();
+    }
+}
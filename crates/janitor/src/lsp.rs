@@ -0,0 +1,301 @@
+//! # Language Server
+//!
+//! `janitor lsp`: dead-code diagnostics and "why is this alive" hovers for
+//! editors. On `initialize` the server reads the workspace root's
+//! `.janitor/symbols.rkyv` (scanning once when there is none) and publishes a
+//! hint on the definition of every dead symbol. The first `didSave` starts a
+//! [`WatchSession`]; later saves patch its graph, and only files whose
+//! diagnostics changed are published again. Hovering a protected symbol shows
+//! its [`Protection`](common::Protection) and the evidence behind it.
+
+use crate::{Janitor, WatchSession};
+use common::registry::{SymbolEntry, SymbolRegistry};
+use lsp_server::{ErrorCode, Message, Notification, Request, Response};
+use lsp_types::notification::{
+    DidSaveTextDocument, LogMessage, Notification as _, PublishDiagnostics,
+};
+use lsp_types::request::{HoverRequest, Request as _};
+use lsp_types::{
+    Diagnostic, DiagnosticSeverity, DiagnosticTag, Hover, HoverContents, HoverParams,
+    HoverProviderCapability, InitializeParams, LogMessageParams, MarkupContent, MarkupKind,
+    MessageType, Position, PublishDiagnosticsParams, Range, SaveOptions, ServerCapabilities,
+    TextDocumentSyncCapability, TextDocumentSyncOptions, TextDocumentSyncSaveOptions, Url,
+};
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+
+pub use lsp_server::Connection;
+
+/// Serves one client on `connection` until it shuts down. `open` opens the
+/// workspace root named in `initialize`, with whatever settings the caller
+/// wants (`--library`, rules files).
+pub fn serve(
+    connection: Connection,
+    open: &dyn Fn(&Path) -> anyhow::Result<Janitor>,
+) -> anyhow::Result<()> {
+    let (id, params) = connection.initialize_start()?;
+    let params: InitializeParams = serde_json::from_value(params)?;
+    let root = workspace_root(&params)
+        .ok_or_else(|| anyhow::anyhow!("initialize names no file:// workspace root"))?;
+    connection.initialize_finish(
+        id,
+        serde_json::json!({
+            "capabilities": capabilities(),
+            "serverInfo": { "name": "janitor", "version": env!("CARGO_PKG_VERSION") },
+        }),
+    )?;
+
+    let janitor = open(&root)?;
+    let mut server = Server {
+        connection: &connection,
+        janitor: &janitor,
+        session: None,
+        symbols: HashMap::new(),
+        published: HashMap::new(),
+    };
+    server.start()?;
+
+    for message in &connection.receiver {
+        match message {
+            Message::Request(request) => {
+                if connection.handle_shutdown(&request)? {
+                    return Ok(());
+                }
+                let response = server.handle(request);
+                connection.sender.send(response.into())?;
+            }
+            Message::Notification(notification)
+                if notification.method == DidSaveTextDocument::METHOD =>
+            {
+                let params: lsp_types::DidSaveTextDocumentParams =
+                    serde_json::from_value(notification.params)?;
+                if let Err(e) = server.saved(&params.text_document.uri) {
+                    server.log(
+                        MessageType::ERROR,
+                        format!("janitor: rescan failed: {:#}", e),
+                    )?;
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn capabilities() -> ServerCapabilities {
+    ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Options(
+            TextDocumentSyncOptions {
+                save: Some(TextDocumentSyncSaveOptions::SaveOptions(SaveOptions {
+                    include_text: Some(false),
+                })),
+                ..Default::default()
+            },
+        )),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        ..Default::default()
+    }
+}
+
+/// The first workspace folder, or the deprecated `rootUri`.
+#[allow(deprecated)]
+fn workspace_root(params: &InitializeParams) -> Option<PathBuf> {
+    let folder = params.workspace_folders.as_ref().and_then(|f| f.first());
+    folder
+        .map(|f| &f.uri)
+        .or(params.root_uri.as_ref())?
+        .to_file_path()
+        .ok()
+}
+
+struct Server<'a> {
+    connection: &'a Connection,
+    janitor: &'a Janitor,
+    /// Started by the first save; the registry on disk serves until then.
+    session: Option<WatchSession<'a>>,
+    /// Every symbol of the last scan, by file path.
+    symbols: HashMap<String, Vec<SymbolEntry>>,
+    /// Diagnostics last published, by file path; files without any are absent.
+    published: HashMap<String, Vec<Diagnostic>>,
+}
+
+impl<'a> Server<'a> {
+    /// Publishes the saved registry, or scans when there is none.
+    fn start(&mut self) -> anyhow::Result<()> {
+        let registry = self.janitor.root().join(".janitor").join("symbols.rkyv");
+        match SymbolRegistry::load(&registry) {
+            Ok(registry) => self.set_symbols(registry.entries),
+            Err(_) => self.start_session()?,
+        }
+        self.publish()
+    }
+
+    fn start_session(&mut self) -> anyhow::Result<()> {
+        let (session, _) = self.janitor.watch_session()?;
+        self.session = Some(session);
+        self.refresh();
+        Ok(())
+    }
+
+    /// Re-scans after `uri` was saved and republishes the files it affected.
+    fn saved(&mut self, uri: &Url) -> anyhow::Result<()> {
+        match self.session.as_mut() {
+            // The new session's scan already sees the saved file.
+            None => self.start_session()?,
+            Some(session) => {
+                let Ok(path) = uri.to_file_path() else {
+                    return Ok(());
+                };
+                if session.update(&[path])?.is_none() {
+                    return Ok(());
+                }
+                self.refresh();
+            }
+        }
+        self.publish()
+    }
+
+    /// Takes the symbols of the session's last scan.
+    fn refresh(&mut self) {
+        if let Some(session) = &self.session {
+            let result = session.result();
+            let entries = result
+                .dead
+                .iter()
+                .chain(&result.protected)
+                .chain(&result.test_only)
+                .chain(&result.low_confidence)
+                .map(SymbolEntry::from)
+                .collect();
+            self.set_symbols(entries);
+        }
+    }
+
+    fn set_symbols(&mut self, entries: Vec<SymbolEntry>) {
+        self.symbols.clear();
+        for entry in entries {
+            self.symbols
+                .entry(entry.file_path.clone())
+                .or_default()
+                .push(entry);
+        }
+    }
+
+    /// Sends `publishDiagnostics` for every file whose dead symbols changed,
+    /// including files that no longer have any.
+    fn publish(&mut self) -> anyhow::Result<()> {
+        let mut diagnostics: HashMap<String, Vec<Diagnostic>> = HashMap::new();
+        for (file, entries) in &self.symbols {
+            let mut dead: Vec<Diagnostic> = entries
+                .iter()
+                .filter(|e| e.protected_by.is_none())
+                .map(dead_diagnostic)
+                .collect();
+            if !dead.is_empty() {
+                dead.sort_by_key(|d| d.range.start.line);
+                diagnostics.insert(file.clone(), dead);
+            }
+        }
+
+        let files: BTreeSet<&String> = diagnostics.keys().chain(self.published.keys()).collect();
+        for file in files {
+            let now = diagnostics.get(file).map_or(&[][..], Vec::as_slice);
+            let before = self.published.get(file).map_or(&[][..], Vec::as_slice);
+            if now == before {
+                continue;
+            }
+            let Ok(uri) = Url::from_file_path(file) else {
+                continue;
+            };
+            let params = PublishDiagnosticsParams::new(uri, now.to_vec(), None);
+            self.notify(PublishDiagnostics::METHOD, params)?;
+        }
+        self.published = diagnostics;
+        Ok(())
+    }
+
+    fn handle(&self, request: Request) -> Response {
+        if request.method != HoverRequest::METHOD {
+            return Response::new_err(
+                request.id,
+                ErrorCode::MethodNotFound as i32,
+                format!("unsupported request `{}`", request.method),
+            );
+        }
+        match serde_json::from_value::<HoverParams>(request.params) {
+            Ok(params) => Response::new_ok(request.id, self.hover(&params)),
+            Err(e) => Response::new_err(request.id, ErrorCode::InvalidParams as i32, e.to_string()),
+        }
+    }
+
+    /// The verdict on the innermost protected symbol defined at the cursor.
+    fn hover(&self, params: &HoverParams) -> Option<Hover> {
+        let position = &params.text_document_position_params;
+        let file = file_key(&position.text_document.uri)?;
+        let line = position.position.line + 1;
+        let entry = self
+            .symbols
+            .get(&file)?
+            .iter()
+            .filter(|e| e.start_line <= line && line <= e.end_line)
+            .min_by_key(|e| e.end_line - e.start_line)?;
+        let protection = entry.protected_by?;
+
+        let mut value = format!(
+            "**`{}`** is alive: `{:?}`",
+            entry.qualified_name, protection
+        );
+        if let Some(detail) = &entry.protection_detail {
+            value.push_str(&format!(" (stage {})\n\n{}", detail.stage, detail.reason));
+            if let Some(location) = &detail.location {
+                value.push_str(&format!("\n\nat `{}`", location));
+            }
+        }
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value,
+            }),
+            range: Some(definition_range(entry)),
+        })
+    }
+
+    fn log(&self, typ: MessageType, message: String) -> anyhow::Result<()> {
+        self.notify(LogMessage::METHOD, LogMessageParams { typ, message })
+    }
+
+    fn notify(&self, method: &str, params: impl serde::Serialize) -> anyhow::Result<()> {
+        let notification = Notification::new(method.to_string(), params);
+        self.connection.sender.send(notification.into())?;
+        Ok(())
+    }
+}
+
+fn dead_diagnostic(entry: &SymbolEntry) -> Diagnostic {
+    Diagnostic {
+        range: definition_range(entry),
+        severity: Some(DiagnosticSeverity::HINT),
+        source: Some("janitor".to_string()),
+        message: format!(
+            "dead symbol `{}`: nothing live refers to it",
+            entry.qualified_name
+        ),
+        tags: Some(vec![DiagnosticTag::UNNECESSARY]),
+        ..Default::default()
+    }
+}
+
+/// Whole lines `start_line..=end_line` (1-based) of the definition.
+fn definition_range(entry: &SymbolEntry) -> Range {
+    Range::new(
+        Position::new(entry.start_line.saturating_sub(1), 0),
+        Position::new(entry.end_line, 0),
+    )
+}
+
+/// The registry's `file_path` for `uri`: canonical, with `/` separators.
+fn file_key(uri: &Url) -> Option<String> {
+    let path = uri.to_file_path().ok()?;
+    let path = dunce::canonicalize(&path).unwrap_or(path);
+    Some(path.to_string_lossy().replace('\\', "/"))
+}
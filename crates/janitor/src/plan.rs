@@ -0,0 +1,341 @@
+//! Reviewable deletion plans (`clean --dry-run` / `clean --from-plan`).
+//!
+//! A dry run records exactly what `clean` would remove, so the plan can be
+//! reviewed in a PR and executed later without re-running the pipeline. Every
+//! file carries the BLAKE3 hash it had when planned: byte ranges are only
+//! meaningful for those exact contents, so execution refuses a stale plan.
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "files": [
+//!     {"file": "pkg/billing.py", "blake3": "5d41…", "symbols": [
+//!       {"qualified_name": "legacy_total", "start_byte": 120, "end_byte": 188, "lines": 3}
+//!     ]}
+//!   ],
+//!   "orphans": [{"file": "pkg/old.py", "blake3": "7c21…", "lines": 40, "bytes": 1200}],
+//!   "simulation": {"outcome": "passed"}
+//! }
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+const FORMAT_VERSION: u32 = 1;
+
+/// Everything one `janitor clean` run would delete.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CleanPlan {
+    pub version: u32,
+    /// Files losing symbols, in deletion order (dead callers first).
+    pub files: Vec<PlanFile>,
+    /// Orphan files to ghost whole.
+    pub orphans: Vec<PlanOrphan>,
+    pub simulation: Simulation,
+}
+
+/// Symbol deletions in one file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlanFile {
+    /// Path relative to the project root, forward slashes.
+    pub file: String,
+    /// Hex BLAKE3 hash of the contents the byte ranges refer to.
+    pub blake3: String,
+    /// Sorted by start byte.
+    pub symbols: Vec<PlanSymbol>,
+}
+
+/// One symbol definition to remove.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlanSymbol {
+    pub qualified_name: String,
+    pub start_byte: u32,
+    pub end_byte: u32,
+    /// Source lines the definition spans.
+    pub lines: u32,
+}
+
+/// An orphan file to ghost.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlanOrphan {
+    /// Path relative to the project root, forward slashes.
+    pub file: String,
+    pub blake3: String,
+    pub lines: u32,
+    pub bytes: u64,
+}
+
+/// Outcome of the shadow simulation run while planning.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum Simulation {
+    Passed,
+    Failed {
+        reason: String,
+    },
+    /// The test command is not installed.
+    Skipped {
+        reason: String,
+    },
+}
+
+impl PlanSymbol {
+    pub fn bytes(&self) -> u32 {
+        self.end_byte - self.start_byte
+    }
+
+    pub fn target(&self) -> reaper::DeletionTarget {
+        reaper::DeletionTarget {
+            qualified_name: self.qualified_name.clone(),
+            start_byte: self.start_byte,
+            end_byte: self.end_byte,
+        }
+    }
+}
+
+impl CleanPlan {
+    /// Plans deleting `files` (absolute path and targets, in deletion order)
+    /// and ghosting `orphans` (absolute paths), hashing their current contents.
+    pub fn new(
+        project_root: &Path,
+        files: &[(&str, Vec<reaper::DeletionTarget>)],
+        orphans: &[&String],
+    ) -> anyhow::Result<Self> {
+        let root = root_prefix(project_root);
+        let read = |path: &str| std::fs::read(path).map_err(|e| anyhow::anyhow!("{}: {}", path, e));
+
+        let mut planned = Vec::new();
+        for (path, targets) in files {
+            let bytes = read(path)?;
+            let mut symbols: Vec<PlanSymbol> = targets
+                .iter()
+                .map(|t| {
+                    let range = bytes
+                        .get(t.start_byte as usize..t.end_byte as usize)
+                        .ok_or_else(|| {
+                            anyhow::anyhow!(
+                                "{}: `{}` spans bytes {}..{} of a {}-byte file",
+                                path,
+                                t.qualified_name,
+                                t.start_byte,
+                                t.end_byte,
+                                bytes.len()
+                            )
+                        })?;
+                    Ok(PlanSymbol {
+                        qualified_name: t.qualified_name.clone(),
+                        start_byte: t.start_byte,
+                        end_byte: t.end_byte,
+                        lines: line_count(range),
+                    })
+                })
+                .collect::<anyhow::Result<_>>()?;
+            symbols.sort_by_key(|s| s.start_byte);
+            planned.push(PlanFile {
+                file: relative(path, &root),
+                blake3: blake3::hash(&bytes).to_hex().to_string(),
+                symbols,
+            });
+        }
+
+        let mut orphan_plan = Vec::new();
+        for path in orphans {
+            let bytes = read(path)?;
+            orphan_plan.push(PlanOrphan {
+                file: relative(path, &root),
+                blake3: blake3::hash(&bytes).to_hex().to_string(),
+                lines: line_count(&bytes),
+                bytes: bytes.len() as u64,
+            });
+        }
+        orphan_plan.sort_by(|a, b| a.file.cmp(&b.file));
+
+        Ok(Self {
+            version: FORMAT_VERSION,
+            files: planned,
+            orphans: orphan_plan,
+            simulation: Simulation::Skipped {
+                reason: "not run".to_string(),
+            },
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty() && self.orphans.is_empty()
+    }
+
+    /// Planned files (symbol edits and orphans) whose contents no longer hash
+    /// as planned, or that are gone.
+    pub fn stale_files(&self, project_root: &Path) -> Vec<String> {
+        let root = project_root_path(project_root);
+        self.files
+            .iter()
+            .map(|f| (&f.file, &f.blake3))
+            .chain(self.orphans.iter().map(|o| (&o.file, &o.blake3)))
+            .filter(|(file, hash)| {
+                std::fs::read(root.join(file))
+                    .map(|bytes| blake3::hash(&bytes).to_hex().as_str() != hash.as_str())
+                    .unwrap_or(true)
+            })
+            .map(|(file, _)| file.clone())
+            .collect()
+    }
+
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("{}: {}", path.display(), e))?;
+        let plan: Self = serde_json::from_str(&text)
+            .map_err(|e| anyhow::anyhow!("{}: invalid plan: {}", path.display(), e))?;
+        if plan.version != FORMAT_VERSION {
+            anyhow::bail!(
+                "{}: unsupported plan version {} (expected {})",
+                path.display(),
+                plan.version,
+                FORMAT_VERSION
+            );
+        }
+        Ok(plan)
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)? + "\n")?;
+        Ok(())
+    }
+}
+
+impl fmt::Display for CleanPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (mut lines, mut bytes, mut symbols) = (0u64, 0u64, 0usize);
+        for file in &self.files {
+            writeln!(f, "{}", file.file)?;
+            for s in &file.symbols {
+                writeln!(
+                    f,
+                    "  - {} [bytes {}..{}] {} lines, {} bytes",
+                    s.qualified_name,
+                    s.start_byte,
+                    s.end_byte,
+                    s.lines,
+                    s.bytes()
+                )?;
+                lines += u64::from(s.lines);
+                bytes += u64::from(s.bytes());
+            }
+            symbols += file.symbols.len();
+        }
+        for orphan in &self.orphans {
+            writeln!(
+                f,
+                "{} (orphan, ghosted) {} lines, {} bytes",
+                orphan.file, orphan.lines, orphan.bytes
+            )?;
+            lines += u64::from(orphan.lines);
+            bytes += orphan.bytes;
+        }
+        writeln!(
+            f,
+            "Plan: {} symbols in {} files, {} orphan files — {} lines, {} bytes",
+            symbols,
+            self.files.len(),
+            self.orphans.len(),
+            lines,
+            bytes
+        )?;
+        match &self.simulation {
+            Simulation::Passed => writeln!(f, "Shadow simulation: PASSED"),
+            Simulation::Failed { reason } => writeln!(f, "Shadow simulation: FAILED ({})", reason),
+            Simulation::Skipped { reason } => {
+                writeln!(f, "Shadow simulation: skipped ({})", reason)
+            }
+        }
+    }
+}
+
+/// Lines touched by `bytes`, counting a final line without a newline.
+fn line_count(bytes: &[u8]) -> u32 {
+    let newlines = bytes.iter().filter(|&&b| b == b'\n').count();
+    let trailing = usize::from(!bytes.is_empty() && !bytes.ends_with(b"\n"));
+    (newlines + trailing) as u32
+}
+
+/// `project_root` canonicalised, as the pipeline reports file paths.
+pub fn project_root_path(project_root: &Path) -> PathBuf {
+    dunce::canonicalize(project_root).unwrap_or_else(|_| project_root.into())
+}
+
+/// `project_root` as an absolute, forward-slash prefix ending in `/`.
+fn root_prefix(project_root: &Path) -> String {
+    format!(
+        "{}/",
+        project_root_path(project_root)
+            .to_string_lossy()
+            .replace('\\', "/")
+            .trim_end_matches('/')
+    )
+}
+
+fn relative(path: &str, root: &str) -> String {
+    path.strip_prefix(root).unwrap_or(path).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_counts_and_round_trips() {
+        let tmp = std::env::temp_dir().join("test_cli_plan_counts");
+        std::fs::remove_dir_all(&tmp).ok();
+        std::fs::create_dir_all(tmp.join("pkg")).unwrap();
+        let source = "def keep():\n    return 1\n\ndef legacy():\n    x = 1\n    return x\n";
+        std::fs::write(tmp.join("pkg/billing.py"), source).unwrap();
+        std::fs::write(tmp.join("pkg/old.py"), "A = 1\nB = 2").unwrap();
+
+        let root = project_root_path(&tmp);
+        let billing = root.join("pkg/billing.py").to_string_lossy().to_string();
+        let old = root.join("pkg/old.py").to_string_lossy().to_string();
+        let start = source.find("def legacy").unwrap() as u32;
+        let targets = vec![reaper::DeletionTarget {
+            qualified_name: "legacy".into(),
+            start_byte: start,
+            end_byte: source.len() as u32,
+        }];
+        let plan = CleanPlan::new(&tmp, &[(billing.as_str(), targets)], &[&old]).unwrap();
+
+        assert_eq!(plan.files[0].file, "pkg/billing.py");
+        assert_eq!(plan.files[0].symbols[0].lines, 3);
+        assert_eq!(plan.orphans[0].file, "pkg/old.py");
+        assert_eq!((plan.orphans[0].lines, plan.orphans[0].bytes), (2, 11));
+        let text = plan.to_string();
+        assert!(text.contains("Plan: 1 symbols in 1 files, 1 orphan files — 5 lines"));
+
+        let path = tmp.join(".janitor/plan.json");
+        plan.save(&path).unwrap();
+        assert_eq!(CleanPlan::load(&path).unwrap(), plan);
+        assert!(plan.stale_files(&tmp).is_empty());
+
+        std::fs::write(tmp.join("pkg/old.py"), "A = 1\n").unwrap();
+        assert_eq!(plan.stale_files(&tmp), vec!["pkg/old.py"]);
+
+        // A range past the end of the file is an error, not a 0-line symbol.
+        let past_end = vec![reaper::DeletionTarget {
+            qualified_name: "legacy".into(),
+            start_byte: start,
+            end_byte: source.len() as u32 + 10,
+        }];
+        let err = CleanPlan::new(&tmp, &[(billing.as_str(), past_end)], &[])
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.ends_with("`legacy` spans bytes 26..73 of a 63-byte file"),
+            "{}",
+            err
+        );
+
+        std::fs::remove_dir_all(tmp).ok();
+    }
+}
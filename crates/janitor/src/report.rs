@@ -0,0 +1,295 @@
+//! Run logs of destructive `clean` runs.
+//!
+//! Every clean that changes the project returns a [`CleanReport`] and saves
+//! it as `.janitor/runs/{started}.json`, so there is a record of what a run
+//! did after its terminal output is gone: the files it touched and the bytes
+//! excised from each, the orphans ghosted, whether the test suite verified
+//! it, and a fingerprint of the token that authorized it (never the token).
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "transaction": "17c9e0b3a4f2",
+//!   "started": 1760000000,
+//!   "finished": 1760000004,
+//!   "token_fingerprint": "4f1c9a0e2b7d5c31",
+//!   "verification": {"mode": "passed"},
+//!   "deleted": [
+//!     {"file": "pkg/billing.py", "symbols": [{"qualified_name": "legacy_total", "bytes": 68}]}
+//!   ],
+//!   "rescued": [],
+//!   "imports_removed": 0,
+//!   "import_files": 0,
+//!   "ghosted": ["pkg/old.py"],
+//!   "orphans_retained": 0
+//! }
+//! ```
+//!
+//! A run that fails after changing the project is saved too, with an
+//! `"error"` key; its lists hold only the changes left in place.
+
+use common::Protection;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+const FORMAT_VERSION: u32 = 1;
+
+/// What a clean run changed.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CleanReport {
+    pub version: u32,
+    /// Transaction every change was recorded under (see `janitor restore`);
+    /// `None` when there was nothing to clean.
+    pub transaction: Option<String>,
+    /// Unix time the run started and finished, in seconds.
+    pub started: u64,
+    pub finished: u64,
+    /// First 16 hex digits of the BLAKE3 hash of the purge token.
+    pub token_fingerprint: String,
+    /// How the deletions were checked; `None` when there was nothing to clean.
+    pub verification: Option<Verification>,
+    /// Files that lost symbols, in deletion order.
+    pub deleted: Vec<CleanedFile>,
+    /// Dead symbols kept because bisection found their file needed by the
+    /// test suite.
+    pub rescued: Vec<RescuedSymbol>,
+    /// Unused import bindings removed.
+    pub imports_removed: usize,
+    /// Files the import bindings were removed from.
+    pub import_files: usize,
+    /// Orphan files moved to `.janitor/ghost/`, relative to the project root.
+    pub ghosted: Vec<PathBuf>,
+    /// Orphan files left in place.
+    pub orphans_retained: usize,
+    /// Why the run stopped part-way; the fields above list the changes it
+    /// left in the project. `None` for a run that finished.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Where [`CleanReport::save`] wrote the report; not part of it.
+    #[serde(skip)]
+    pub run_log: Option<PathBuf>,
+}
+
+/// Symbols removed from one file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CleanedFile {
+    /// Path relative to the project root, forward slashes.
+    pub file: String,
+    /// In file order.
+    pub symbols: Vec<RemovedSymbol>,
+}
+
+/// One excised definition.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RemovedSymbol {
+    pub qualified_name: String,
+    /// Size of the definition's byte range.
+    pub bytes: u32,
+}
+
+/// A dead symbol the run kept.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RescuedSymbol {
+    /// Path relative to the project root.
+    pub file: PathBuf,
+    pub qualified_name: String,
+    /// The evidence that kept it.
+    pub protected_by: Protection,
+}
+
+/// How a run's deletions were checked against the test suite.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum Verification {
+    /// The shadow simulation passed.
+    Passed,
+    /// The simulation failed; bisection kept the symbols of `files` files and
+    /// the rest passed.
+    Bisected { files: usize },
+    /// The test command is not installed and the run was allowed to proceed
+    /// unverified.
+    Skipped { reason: String },
+}
+
+impl CleanedFile {
+    /// Bytes excised from the file.
+    pub fn bytes(&self) -> u64 {
+        self.symbols.iter().map(|s| u64::from(s.bytes)).sum()
+    }
+}
+
+impl CleanReport {
+    /// `true` when the run found nothing to clean.
+    pub fn is_empty(&self) -> bool {
+        self.transaction.is_none()
+    }
+
+    /// `true` when the run left edited, or ghosted, files in the project.
+    pub fn changed_files(&self) -> bool {
+        !self.deleted.is_empty() || self.imports_removed > 0 || !self.ghosted.is_empty()
+    }
+
+    /// Stamps the run with its start time, `finished` set to now, and the
+    /// fingerprint of `token`.
+    pub(crate) fn stamp(&mut self, started: u64, token: &str) {
+        self.version = FORMAT_VERSION;
+        self.started = started;
+        self.finished = reaper::ghost::unix_now();
+        self.token_fingerprint = blake3::hash(token.as_bytes()).to_hex()[..16].to_string();
+    }
+
+    /// Writes the report to `.janitor/runs/{started}.json` under
+    /// `project_root` (`{started}-2.json`, ... when runs share a second) and
+    /// records the path in [`CleanReport::run_log`].
+    pub fn save(&mut self, project_root: &Path) -> anyhow::Result<PathBuf> {
+        let dir = project_root.join(".janitor").join("runs");
+        std::fs::create_dir_all(&dir)?;
+        let text = serde_json::to_string_pretty(self)? + "\n";
+        let mut n = 1;
+        let path = loop {
+            let name = match n {
+                1 => format!("{}.json", self.started),
+                _ => format!("{}-{}.json", self.started, n),
+            };
+            let path = dir.join(name);
+            match std::fs::File::create_new(&path) {
+                Ok(mut file) => {
+                    std::io::Write::write_all(&mut file, text.as_bytes())?;
+                    break path;
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => n += 1,
+                Err(e) => return Err(anyhow::anyhow!("{}: {}", path.display(), e)),
+            }
+        };
+        self.run_log = Some(path.clone());
+        Ok(path)
+    }
+
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("{}: {}", path.display(), e))?;
+        let mut report: Self = serde_json::from_str(&text)
+            .map_err(|e| anyhow::anyhow!("{}: invalid run log: {}", path.display(), e))?;
+        report.run_log = Some(path.to_path_buf());
+        Ok(report)
+    }
+}
+
+impl fmt::Display for CleanReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for file in &self.deleted {
+            let names: Vec<&str> = file
+                .symbols
+                .iter()
+                .map(|s| s.qualified_name.as_str())
+                .collect();
+            writeln!(
+                f,
+                "{}: {} symbols, {} bytes ({})",
+                file.file,
+                file.symbols.len(),
+                file.bytes(),
+                names.join(", ")
+            )?;
+        }
+        let symbols: usize = self.deleted.iter().map(|d| d.symbols.len()).sum();
+        let bytes: u64 = self.deleted.iter().map(CleanedFile::bytes).sum();
+        writeln!(
+            f,
+            "Removed {} symbols ({} bytes) from {} files, {} unused imports from {} files",
+            symbols,
+            bytes,
+            self.deleted.len(),
+            self.imports_removed,
+            self.import_files
+        )?;
+        writeln!(
+            f,
+            "Orphans: {} ghosted, {} retained",
+            self.ghosted.len(),
+            self.orphans_retained
+        )?;
+        if !self.rescued.is_empty() {
+            writeln!(
+                f,
+                "Rescued by test evidence: {} symbols",
+                self.rescued.len()
+            )?;
+        }
+        match &self.verification {
+            Some(Verification::Passed) => writeln!(f, "Verification: shadow tests PASSED"),
+            Some(Verification::Bisected { files }) => writeln!(
+                f,
+                "Verification: shadow tests PASSED after bisection ({} files kept)",
+                files
+            ),
+            Some(Verification::Skipped { reason }) => {
+                writeln!(
+                    f,
+                    "Verification: SKIPPED ({}); the run is unverified",
+                    reason
+                )
+            }
+            None => writeln!(f, "Verification: not run"),
+        }?;
+        if let Some(error) = &self.error {
+            writeln!(f, "FAILED part-way: {}", error)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_saves_without_overwriting_and_round_trips() {
+        let tmp = std::env::temp_dir().join("test_clean_report_save");
+        std::fs::remove_dir_all(&tmp).ok();
+        std::fs::create_dir_all(&tmp).unwrap();
+
+        let mut report = CleanReport {
+            transaction: Some("abc".to_string()),
+            verification: Some(Verification::Skipped {
+                reason: "`pytest` not found".to_string(),
+            }),
+            deleted: vec![CleanedFile {
+                file: "pkg/billing.py".to_string(),
+                symbols: vec![
+                    RemovedSymbol {
+                        qualified_name: "legacy".to_string(),
+                        bytes: 30,
+                    },
+                    RemovedSymbol {
+                        qualified_name: "older".to_string(),
+                        bytes: 12,
+                    },
+                ],
+            }],
+            ghosted: vec![PathBuf::from("pkg/stale.py")],
+            ..Default::default()
+        };
+        report.stamp(1_700_000_000, "secret-token");
+        assert_eq!(report.token_fingerprint.len(), 16);
+        assert!(!report.token_fingerprint.contains("secret"));
+        assert!(report.finished >= report.started);
+
+        let first = report.save(&tmp).unwrap();
+        let second = report.clone().save(&tmp).unwrap();
+        assert_eq!(first, tmp.join(".janitor/runs/1700000000.json"));
+        assert_eq!(second, tmp.join(".janitor/runs/1700000000-2.json"));
+        assert_eq!(CleanReport::load(&first).unwrap(), report);
+        let json = std::fs::read_to_string(&first).unwrap();
+        assert!(json.contains("\"mode\": \"skipped\""));
+        assert!(!json.contains("secret-token"));
+
+        let text = report.to_string();
+        assert!(text.contains("pkg/billing.py: 2 symbols, 42 bytes (legacy, older)"));
+        assert!(text.contains("Orphans: 1 ghosted, 0 retained"));
+        assert!(text.contains("Verification: SKIPPED (`pytest` not found)"));
+
+        std::fs::remove_dir_all(tmp).ok();
+    }
+}
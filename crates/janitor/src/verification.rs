@@ -0,0 +1,406 @@
+//! Test-suite verification for shadow simulations (`clean`, `dedup --apply`).
+//!
+//! The command comes from `test_command` in `.janitor.toml` (default
+//! `pytest --tb=short -q`) and runs with the simulated tree as its working
+//! directory. `PYTHONPATH` is replaced, not extended, by that tree and its
+//! source roots, so modules missing from it (ghosted or unmapped files) are
+//! genuinely unimportable. Bytecode writing is disabled so a run leaves no
+//! `__pycache__` behind. On Unix the command leads its own process group, and
+//! a timeout kills the whole group, workers and subprocesses included.
+
+use common::config::JanitorConfig;
+use std::ffi::OsString;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// Used when `.janitor.toml` sets no `test_command`.
+pub const DEFAULT_TEST_COMMAND: &str = "pytest --tb=short -q";
+
+/// How often a running test command is polled for exit or timeout.
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Runs the project's test command against a simulated tree.
+#[derive(Debug, Clone)]
+pub struct Verifier {
+    /// Program followed by its arguments (the command split with shell quoting).
+    argv: Vec<String>,
+    timeout: Option<Duration>,
+    /// Source roots relative to the project root; prepended to `PYTHONPATH`
+    /// after the working directory itself.
+    source_roots: Vec<PathBuf>,
+    env: Vec<(String, OsString)>,
+}
+
+/// How a verification that did not fail ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[must_use]
+pub enum Outcome {
+    /// The command exited successfully.
+    Passed,
+    /// The command is not installed, so nothing was verified.
+    Skipped { reason: String },
+}
+
+/// A failed verification with everything the command printed.
+#[derive(Debug)]
+pub struct VerificationFailure {
+    pub reason: String,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl std::fmt::Display for VerificationFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.reason)
+    }
+}
+
+impl std::error::Error for VerificationFailure {}
+
+impl Verifier {
+    /// A verifier running `command`, split into words as a POSIX shell would
+    /// (quotes and backslash escapes; no expansion). Fails on unbalanced quotes.
+    pub fn new(command: &str) -> anyhow::Result<Self> {
+        let argv = shell_words::split(command)
+            .map_err(|e| anyhow::anyhow!("test command `{}`: {}", command, e))?;
+        Ok(Verifier {
+            argv,
+            timeout: None,
+            source_roots: Vec::new(),
+            env: Vec::new(),
+        })
+    }
+
+    /// The verifier `.janitor.toml` describes for `project_root`: its
+    /// `test_command`, `test_timeout`, `test_env`, and the project's import
+    /// source roots.
+    pub fn from_config(project_root: &Path, config: &JanitorConfig) -> anyhow::Result<Self> {
+        let root = dunce::canonicalize(project_root).unwrap_or_else(|_| project_root.into());
+        let source_roots = anatomist::imports::source_roots(&root, config.source_roots.as_deref())
+            .into_iter()
+            .filter_map(|r| r.strip_prefix(&root).ok().map(Path::to_path_buf))
+            .filter(|r| !r.as_os_str().is_empty())
+            .collect();
+        let mut verifier = Verifier {
+            source_roots,
+            ..Verifier::new(
+                config
+                    .test_command
+                    .as_deref()
+                    .unwrap_or(DEFAULT_TEST_COMMAND),
+            )?
+        }
+        .with_timeout(config.test_timeout.map(Duration::from_secs));
+        for entry in &config.test_env {
+            if let Some((key, value)) = entry.split_once('=') {
+                verifier = verifier.with_env(key.trim(), value);
+            }
+        }
+        Ok(verifier)
+    }
+
+    /// Kills the command and fails the verification after `timeout`.
+    pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sets an extra environment variable for the command.
+    pub fn with_env(mut self, key: &str, value: impl Into<OsString>) -> Self {
+        self.env.push((key.to_string(), value.into()));
+        self
+    }
+
+    /// The command's program when it is not installed (neither an existing
+    /// path nor found on `PATH`), in which case [`run`](Self::run) skips.
+    pub fn missing_program(&self) -> Option<&str> {
+        let program = self.argv.first()?;
+        let path = Path::new(program);
+        let found = if path.components().count() > 1 {
+            path.exists()
+        } else {
+            std::env::var_os("PATH").is_some_and(|paths| {
+                std::env::split_paths(&paths).any(|dir| {
+                    let candidate = dir.join(program);
+                    candidate.is_file()
+                        || candidate
+                            .with_extension(std::env::consts::EXE_EXTENSION)
+                            .is_file()
+                })
+            })
+        };
+        (!found).then_some(program.as_str())
+    }
+
+    /// Runs the command in `dir`.
+    ///
+    /// A command that is not installed skips verification, as a project
+    /// without a test suite has nothing to verify.
+    pub fn run(&self, dir: &Path) -> Result<Outcome, VerificationFailure> {
+        let failure = |reason: String| VerificationFailure {
+            reason,
+            stdout: String::new(),
+            stderr: String::new(),
+        };
+        let Some((program, args)) = self.argv.split_first() else {
+            return Err(failure("empty test command".to_string()));
+        };
+
+        let python_path = std::iter::once(dir.to_path_buf())
+            .chain(self.source_roots.iter().map(|r| dir.join(r)))
+            .collect::<Vec<_>>();
+        let mut command = Command::new(program);
+        command
+            .args(args)
+            .current_dir(dir)
+            .env(
+                "PYTHONPATH",
+                std::env::join_paths(python_path).map_err(|e| failure(e.to_string()))?,
+            )
+            .env("PYTHONDONTWRITEBYTECODE", "1")
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        for (key, value) in &self.env {
+            command.env(key, value);
+        }
+        #[cfg(unix)]
+        std::os::unix::process::CommandExt::process_group(&mut command, 0);
+
+        let mut child = match command.spawn() {
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(Outcome::Skipped {
+                    reason: format!("`{}` not found", program),
+                });
+            }
+            Err(e) => return Err(failure(format!("Failed to spawn {}: {}", program, e))),
+            Ok(child) => child,
+        };
+
+        // Drain both pipes concurrently so a chatty suite cannot block on a full pipe.
+        let capture = |pipe: Option<Box<dyn Read + Send>>| {
+            std::thread::spawn(move || {
+                let mut text = String::new();
+                if let Some(mut pipe) = pipe {
+                    pipe.read_to_string(&mut text).ok();
+                }
+                text
+            })
+        };
+        let stdout = capture(child.stdout.take().map(|p| Box::new(p) as _));
+        let stderr = capture(child.stderr.take().map(|p| Box::new(p) as _));
+
+        let started = Instant::now();
+        let status = loop {
+            match child.try_wait() {
+                Ok(Some(status)) => break Ok(status),
+                Ok(None) if self.timeout.is_some_and(|t| started.elapsed() >= t) => {
+                    kill_group(&mut child);
+                    child.wait().ok();
+                    break Err(format!(
+                        "{} timed out after {}s",
+                        program,
+                        self.timeout.unwrap_or_default().as_secs_f32()
+                    ));
+                }
+                Ok(None) => std::thread::sleep(POLL_INTERVAL),
+                Err(e) => break Err(format!("Failed to wait for {}: {}", program, e)),
+            }
+        };
+        let stdout = stdout.join().unwrap_or_default();
+        let stderr = stderr.join().unwrap_or_default();
+
+        let reason = match status {
+            Ok(s) if s.success() => return Ok(Outcome::Passed),
+            Ok(s) => format!("{} exited with code {}", program, s.code().unwrap_or(-1)),
+            Err(reason) => reason,
+        };
+        Err(VerificationFailure {
+            reason,
+            stdout,
+            stderr,
+        })
+    }
+
+    /// [`run`](Self::run) as an `anyhow` result; a failure keeps the captured
+    /// output in its [`VerificationFailure`].
+    pub fn check(&self, dir: &Path) -> anyhow::Result<Outcome> {
+        Ok(self.run(dir)?)
+    }
+}
+
+/// Kills `child` and, on Unix, every process in the group it leads: a test
+/// runner's workers would otherwise outlive it and hold its output pipes open.
+fn kill_group(child: &mut Child) {
+    #[cfg(unix)]
+    if let Ok(pid) = libc::pid_t::try_from(child.id()) {
+        // SAFETY: kill(2) only sends a signal; a negative pid addresses the
+        // process group the child was spawned to lead.
+        unsafe {
+            libc::kill(-pid, libc::SIGKILL);
+        }
+    }
+    child.kill().ok();
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    fn stub(dir: &Path, body: &str) -> String {
+        let script = dir.join("stub_tests.sh");
+        std::fs::write(&script, format!("#!/bin/sh\n{}\n", body)).unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+        script.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn test_verifier_sets_cwd_and_python_path() {
+        let tmp = std::env::temp_dir().join("test_verifier_env");
+        std::fs::remove_dir_all(&tmp).ok();
+        std::fs::create_dir_all(tmp.join("tree")).unwrap();
+        let tree = dunce::canonicalize(tmp.join("tree")).unwrap();
+        let script = stub(
+            &tmp,
+            "printf '%s\\n%s\\n%s\\n%s\\n' \"$PWD\" \"$PYTHONPATH\" \"$PYTHONDONTWRITEBYTECODE\" \"$EXTRA\" > seen.txt",
+        );
+
+        let mut verifier = Verifier::new(&script)
+            .unwrap()
+            .with_env("EXTRA", "injected");
+        verifier.source_roots = vec![PathBuf::from("src")];
+        assert_eq!(verifier.run(&tree).unwrap(), Outcome::Passed);
+
+        let seen = std::fs::read_to_string(tree.join("seen.txt")).unwrap();
+        let lines: Vec<&str> = seen.lines().collect();
+        let python_path = format!("{}:{}", tree.display(), tree.join("src").display());
+        assert_eq!(
+            lines,
+            vec![
+                tree.to_str().unwrap(),
+                python_path.as_str(),
+                "1",
+                "injected"
+            ]
+        );
+
+        std::fs::remove_dir_all(&tmp).ok();
+    }
+
+    #[test]
+    fn test_verifier_failure_captures_output() {
+        let tmp = std::env::temp_dir().join("test_verifier_failure");
+        std::fs::remove_dir_all(&tmp).ok();
+        std::fs::create_dir_all(&tmp).unwrap();
+        let script = stub(
+            &tmp,
+            "echo '1 passed'\necho 'E  ImportError: gone' >&2\nexit 3",
+        );
+
+        let failure = Verifier::new(&format!("{} -x", script))
+            .unwrap()
+            .run(&tmp)
+            .unwrap_err();
+        assert!(failure.reason.ends_with("exited with code 3"));
+        assert_eq!(failure.stdout, "1 passed\n");
+        assert_eq!(failure.stderr, "E  ImportError: gone\n");
+
+        std::fs::remove_dir_all(&tmp).ok();
+    }
+
+    #[test]
+    fn test_verifier_timeout_and_missing_command() {
+        let tmp = std::env::temp_dir().join("test_verifier_timeout");
+        std::fs::remove_dir_all(&tmp).ok();
+        std::fs::create_dir_all(&tmp).unwrap();
+        let script = stub(&tmp, "exec sleep 5");
+
+        let started = Instant::now();
+        let failure = Verifier::new(&script)
+            .unwrap()
+            .with_timeout(Some(Duration::from_millis(100)))
+            .run(&tmp)
+            .unwrap_err();
+        assert!(failure.reason.contains("timed out"));
+        assert!(started.elapsed() < Duration::from_secs(4));
+
+        let missing = Verifier::new("janitor-no-such-test-runner").unwrap();
+        assert_eq!(
+            missing.run(&tmp).unwrap(),
+            Outcome::Skipped {
+                reason: "`janitor-no-such-test-runner` not found".to_string()
+            }
+        );
+        assert_eq!(
+            missing.missing_program(),
+            Some("janitor-no-such-test-runner")
+        );
+        assert_eq!(Verifier::new(&script).unwrap().missing_program(), None);
+        assert_eq!(Verifier::new("sh -c true").unwrap().missing_program(), None);
+
+        std::fs::remove_dir_all(&tmp).ok();
+    }
+
+    #[test]
+    fn test_verifier_timeout_kills_subprocesses() {
+        let tmp = std::env::temp_dir().join("test_verifier_timeout_group");
+        std::fs::remove_dir_all(&tmp).ok();
+        std::fs::create_dir_all(&tmp).unwrap();
+        // The shell forks `sleep`, which inherits the output pipes; killing
+        // only the shell would leave them open until it exits.
+        let script = stub(&tmp, "sleep 30\necho done");
+
+        let started = Instant::now();
+        let failure = Verifier::new(&script)
+            .unwrap()
+            .with_timeout(Some(Duration::from_millis(100)))
+            .run(&tmp)
+            .unwrap_err();
+        assert!(failure.reason.contains("timed out"));
+        assert!(started.elapsed() < Duration::from_secs(10));
+
+        std::fs::remove_dir_all(&tmp).ok();
+    }
+
+    #[test]
+    fn test_verifier_quoted_arguments() {
+        let verifier = Verifier::new(r#"pytest -k "not slow" 'tests/a b.py'"#).unwrap();
+        assert_eq!(
+            verifier.argv,
+            vec!["pytest", "-k", "not slow", "tests/a b.py"]
+        );
+        assert!(Verifier::new("pytest -k \"unterminated").is_err());
+    }
+
+    #[test]
+    fn test_verifier_from_config() {
+        let tmp = std::env::temp_dir().join("test_verifier_config");
+        std::fs::remove_dir_all(&tmp).ok();
+        std::fs::create_dir_all(tmp.join("src/pkg")).unwrap();
+        std::fs::write(tmp.join("src/pkg/__init__.py"), "").unwrap();
+
+        let default = Verifier::from_config(&tmp, &JanitorConfig::default()).unwrap();
+        assert_eq!(default.argv, vec!["pytest", "--tb=short", "-q"]);
+        assert_eq!(default.source_roots, vec![PathBuf::from("src")]);
+        assert_eq!(default.timeout, None);
+
+        let config = JanitorConfig {
+            test_command: Some("python -m unittest".into()),
+            test_timeout: Some(30),
+            test_env: vec!["DJANGO_SETTINGS_MODULE=app.test".into()],
+            ..Default::default()
+        };
+        let verifier = Verifier::from_config(&tmp, &config).unwrap();
+        assert_eq!(verifier.argv, vec!["python", "-m", "unittest"]);
+        assert_eq!(verifier.timeout, Some(Duration::from_secs(30)));
+        assert_eq!(
+            verifier.env,
+            vec![("DJANGO_SETTINGS_MODULE".to_string(), "app.test".into())]
+        );
+
+        std::fs::remove_dir_all(&tmp).ok();
+    }
+}
@@ -0,0 +1,248 @@
+//! # Watch Mode
+//!
+//! A resident scan for editor integration (`janitor watch`). A
+//! [`WatchSession`] builds the reference graph once and patches it as files
+//! change (see [`anatomist::incremental`]). Each update re-runs the pipeline
+//! stages on the patched graph, rewrites `.janitor/symbols.rkyv`, and returns
+//! a [`WatchReport`]. [`crate::Janitor::watch`] drives a session from a
+//! filesystem watcher, collapsing each burst of events into one update.
+
+use crate::{Event, Janitor};
+use anatomist::incremental::{GraphUpdate, IncrementalGraph};
+use anatomist::parser::ParserHost;
+use anatomist::pipeline::{graph_options, run_on_graph, ScanOptions, ScanResult};
+use anatomist::progress;
+use notify::{EventKind, RecursiveMode, Watcher};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+/// One scan of a watch session; `janitor watch` prints each as a JSON line.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct WatchReport {
+    /// `"ready"` for the initial scan, `"scan"` after a change.
+    pub event: &'static str,
+    /// Paths that triggered the scan, relative to the project root.
+    pub changed: Vec<String>,
+    /// Dead symbols now.
+    pub dead: usize,
+    /// `dead` minus the previous report's.
+    pub dead_delta: i64,
+    /// Symbols dead now but not before, as `file::qualified_name`.
+    pub new_dead: Vec<String>,
+    /// Symbols dead before but not now, as `file::qualified_name`.
+    pub resolved: Vec<String>,
+    pub orphan_files: usize,
+    #[serde(flatten)]
+    pub graph: GraphUpdate,
+    pub elapsed_ms: u64,
+}
+
+/// A reference graph kept current across changes, and the dead set of the
+/// last scan.
+pub struct WatchSession<'a> {
+    janitor: &'a Janitor,
+    host: ParserHost,
+    options: ScanOptions,
+    graph: IncrementalGraph,
+    /// `file::qualified_name` of every dead symbol in the last report.
+    dead: BTreeSet<String>,
+    /// The last scan.
+    result: ScanResult,
+}
+
+impl<'a> WatchSession<'a> {
+    /// Builds the graph, scans, and saves the registry.
+    pub(crate) fn start(
+        janitor: &'a Janitor,
+        notify: &mut dyn FnMut(Event),
+    ) -> anyhow::Result<(Self, WatchReport)> {
+        let started = Instant::now();
+        let (mut host, mut options) = janitor.pipeline()?;
+        let graph = IncrementalGraph::build(janitor.root(), &mut host, graph_options(&options))?;
+        janitor.add_runtime_evidence(&mut options, graph.graph(), notify)?;
+        // Every Python file is parsed and linked once.
+        let built = GraphUpdate {
+            files_parsed: graph.graph().stats.files_parsed,
+            files_linked: graph.graph().stats.files_parsed,
+            files_removed: 0,
+            rebuilt: true,
+        };
+        let mut session = Self {
+            janitor,
+            host,
+            options,
+            graph,
+            dead: BTreeSet::new(),
+            result: ScanResult::default(),
+        };
+        let report = session.rescan("ready", &[], built, started)?;
+        Ok((session, report))
+    }
+
+    /// Applies a batch of changed paths (created, modified, deleted, or both
+    /// sides of a rename) and scans again. Returns `None` when none of them
+    /// belongs to the project, e.g. writes under `.janitor/`.
+    pub fn update(&mut self, paths: &[PathBuf]) -> anyhow::Result<Option<WatchReport>> {
+        let started = Instant::now();
+        let paths = self.graph.project_paths(paths);
+        if paths.is_empty() {
+            return Ok(None);
+        }
+        let update = self.graph.update(&mut self.host, &paths)?;
+        self.rescan("scan", &paths, update, started).map(Some)
+    }
+
+    fn rescan(
+        &mut self,
+        event: &'static str,
+        changed: &[PathBuf],
+        graph: GraphUpdate,
+        started: Instant,
+    ) -> anyhow::Result<WatchReport> {
+        let root = self.graph.root().to_path_buf();
+        let result = run_on_graph(
+            &root,
+            &self.options,
+            self.graph.graph(),
+            &mut progress::silent,
+        )?;
+        self.janitor.save_registry(&result, &[])?;
+
+        let prefix = format!("{}/", root.to_string_lossy().replace('\\', "/"));
+        let relative = |path: &str| path.strip_prefix(&prefix).unwrap_or(path).to_string();
+        let dead: BTreeSet<String> = result
+            .dead
+            .iter()
+            .map(|e| format!("{}::{}", relative(&e.file_path), e.qualified_name))
+            .collect();
+        let report = WatchReport {
+            event,
+            changed: changed
+                .iter()
+                .map(|p| relative(&p.to_string_lossy().replace('\\', "/")))
+                .collect(),
+            dead: dead.len(),
+            dead_delta: dead.len() as i64 - self.dead.len() as i64,
+            new_dead: dead.difference(&self.dead).cloned().collect(),
+            resolved: self.dead.difference(&dead).cloned().collect(),
+            orphan_files: result.orphan_files.len(),
+            graph,
+            elapsed_ms: started.elapsed().as_millis() as u64,
+        };
+        self.dead = dead;
+        self.result = result;
+        Ok(report)
+    }
+
+    /// Every symbol's verdict in the last scan.
+    pub fn result(&self) -> &ScanResult {
+        &self.result
+    }
+
+    /// The canonical project root being watched.
+    pub fn root(&self) -> &Path {
+        self.graph.root()
+    }
+}
+
+/// Feeds `session` the filesystem events under its root until the watcher
+/// shuts down or `on_report` fails. Events are batched until `debounce`
+/// passes without a new one.
+pub(crate) fn run(
+    session: &mut WatchSession,
+    debounce: Duration,
+    on_report: &mut dyn FnMut(WatchReport) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    watcher.watch(session.root(), RecursiveMode::Recursive)?;
+
+    while let Ok(first) = rx.recv() {
+        let mut paths: BTreeSet<PathBuf> = BTreeSet::new();
+        collect(first, &mut paths)?;
+        while let Ok(event) = rx.recv_timeout(debounce) {
+            collect(event, &mut paths)?;
+        }
+        let paths: Vec<PathBuf> = paths.into_iter().collect();
+        if let Some(report) = session.update(&paths)? {
+            on_report(report)?;
+        }
+    }
+    Ok(())
+}
+
+/// Adds the paths an event touched; reads and opens change nothing.
+fn collect(
+    event: notify::Result<notify::Event>,
+    paths: &mut BTreeSet<PathBuf>,
+) -> anyhow::Result<()> {
+    let event = event.map_err(|e| anyhow::anyhow!("file watcher failed: {}", e))?;
+    if !matches!(event.kind, EventKind::Access(_)) {
+        paths.extend(event.paths);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn project(name: &str) -> PathBuf {
+        let tmp = std::env::temp_dir().join(name);
+        fs::remove_dir_all(&tmp).ok();
+        fs::create_dir_all(&tmp).unwrap();
+        fs::write(tmp.join("util.py"), "def helper():\n    pass\n").unwrap();
+        fs::write(tmp.join("main.py"), "from util import helper\n\nhelper()\n").unwrap();
+        tmp
+    }
+
+    #[test]
+    fn test_session_reports_dead_delta() {
+        let root = project("test_watch_delta");
+        let janitor = Janitor::open(&root).unwrap();
+        let (mut session, ready) =
+            WatchSession::start(&janitor, &mut crate::event::silent).unwrap();
+        assert_eq!(ready.event, "ready");
+        assert_eq!(ready.dead, 0);
+        assert!(root.join(".janitor/symbols.rkyv").is_file());
+
+        fs::write(root.join("main.py"), "print('done')\n").unwrap();
+        let report = session.update(&[root.join("main.py")]).unwrap().unwrap();
+        assert_eq!(report.event, "scan");
+        assert_eq!(report.changed, vec!["main.py"]);
+        assert_eq!(report.dead_delta, 1);
+        assert_eq!(report.new_dead, vec!["util.py::helper"]);
+        assert_eq!(report.graph.files_parsed, 1);
+
+        fs::write(
+            root.join("main.py"),
+            "from util import helper\n\nhelper()\n",
+        )
+        .unwrap();
+        let report = session.update(&[root.join("main.py")]).unwrap().unwrap();
+        assert_eq!(report.dead_delta, -1);
+        assert_eq!(report.resolved, vec!["util.py::helper"]);
+
+        let json = serde_json::to_string(&report).unwrap();
+        assert!(json.starts_with("{\"event\":\"scan\""), "{}", json);
+        assert!(json.contains("\"dead_delta\":-1"), "{}", json);
+        assert!(json.contains("\"files_parsed\":1"), "{}", json);
+
+        fs::remove_dir_all(root).ok();
+    }
+
+    #[test]
+    fn test_own_writes_are_ignored() {
+        let root = project("test_watch_ignored");
+        let janitor = Janitor::open(&root).unwrap();
+        let (mut session, _) = WatchSession::start(&janitor, &mut crate::event::silent).unwrap();
+
+        let registry = root.join(".janitor/symbols.rkyv");
+        assert!(session.update(&[registry]).unwrap().is_none());
+
+        fs::remove_dir_all(root).ok();
+    }
+}
@@ -0,0 +1,201 @@
+//! The public `Janitor` API, driven the way an embedding tool would.
+
+use janitor::{CleanOptions, DedupOptions, Event, Janitor};
+use std::path::{Path, PathBuf};
+
+/// A project with one live and one dead function, plus an orphan module.
+fn project(name: &str) -> PathBuf {
+    let tmp = std::env::temp_dir().join(name);
+    std::fs::remove_dir_all(&tmp).ok();
+    std::fs::create_dir_all(tmp.join("pkg")).unwrap();
+    // A missing test runner skips the shadow simulation instead of running pytest.
+    std::fs::write(
+        tmp.join(".janitor.toml"),
+        "test_command = \"janitor-no-such-test-runner\"\n",
+    )
+    .unwrap();
+    std::fs::write(
+        tmp.join("main.py"),
+        "from pkg.billing import keep\n\nkeep()\n",
+    )
+    .unwrap();
+    std::fs::write(tmp.join("pkg/__init__.py"), "").unwrap();
+    std::fs::write(
+        tmp.join("pkg/billing.py"),
+        "def keep():\n    return 1\n\n\ndef legacy():\n    x = 1\n    return x\n",
+    )
+    .unwrap();
+    std::fs::write(tmp.join("pkg/stale.py"), "def old():\n    pass\n").unwrap();
+    tmp
+}
+
+fn read(root: &Path, file: &str) -> String {
+    std::fs::read_to_string(root.join(file)).unwrap()
+}
+
+#[test]
+fn scan_returns_structured_result_and_reports_progress() {
+    let root = project("test_facade_scan");
+    let janitor = Janitor::open(&root).unwrap();
+    assert!(janitor.warnings().is_empty());
+
+    let mut events = Vec::new();
+    let result = janitor.scan_observed(&mut |e| events.push(e)).unwrap();
+
+    let dead: Vec<&str> = result.dead.iter().map(|e| e.name.as_str()).collect();
+    assert!(dead.contains(&"legacy"), "{:?}", dead);
+    assert!(!dead.contains(&"keep"), "{:?}", dead);
+    assert!(result.orphan_files.iter().any(|f| f.ends_with("stale.py")));
+    assert!(events.iter().any(|e| matches!(e, Event::Pipeline(_))));
+
+    janitor.save_registry(&result, &[]).unwrap();
+    assert!(root.join(".janitor/symbols.rkyv").is_file());
+    assert!(read(&root, ".janitor/orphans.txt").contains("stale.py"));
+
+    std::fs::remove_dir_all(root).ok();
+}
+
+#[test]
+fn config_warnings_are_returned_not_printed() {
+    let root = project("test_facade_warnings");
+    std::fs::write(root.join(".janitor.toml"), "no_such_key = 1\n").unwrap();
+
+    let janitor = Janitor::open(&root).unwrap();
+    assert_eq!(janitor.warnings().len(), 1);
+    assert!(janitor.warnings()[0].starts_with(".janitor.toml: "));
+
+    std::fs::remove_dir_all(root).ok();
+}
+
+#[test]
+fn plan_clean_leaves_the_project_untouched() {
+    let root = project("test_facade_plan");
+    let janitor = Janitor::open(&root).unwrap();
+
+    let plan = janitor.plan_clean(&CleanOptions::default()).unwrap();
+
+    assert!(!plan.is_empty());
+    assert!(plan.files.iter().any(|f| f.file == "pkg/billing.py"));
+    assert!(plan.orphans.iter().any(|o| o.file == "pkg/stale.py"));
+    assert!(matches!(
+        plan.simulation,
+        janitor::plan::Simulation::Skipped { .. }
+    ));
+    assert!(read(&root, "pkg/billing.py").contains("def legacy"));
+    assert!(root.join("pkg/stale.py").exists());
+
+    std::fs::remove_dir_all(root).ok();
+}
+
+#[test]
+fn clean_and_execute_plan_refuse_an_invalid_token() {
+    let root = project("test_facade_token");
+    let janitor = Janitor::open(&root).unwrap();
+    let before = read(&root, "pkg/billing.py");
+
+    let options = CleanOptions {
+        token: "not-a-token".to_string(),
+        ..Default::default()
+    };
+    let err = janitor.clean(&options).unwrap_err().to_string();
+    assert!(err.starts_with("purge token rejected: "), "{}", err);
+    let err = janitor.patch_clean(&options).err().unwrap().to_string();
+    assert!(err.starts_with("purge token rejected: "), "{}", err);
+
+    let plan = janitor.plan_clean(&CleanOptions::default()).unwrap();
+    let err = janitor
+        .execute_plan(&plan, &options)
+        .unwrap_err()
+        .to_string();
+    assert!(err.starts_with("purge token rejected: "), "{}", err);
+
+    assert_eq!(read(&root, "pkg/billing.py"), before);
+    assert!(root.join("pkg/stale.py").exists());
+    assert!(!root.join(".janitor/ghost").exists());
+    assert!(!root.join(".janitor/runs").exists());
+
+    std::fs::remove_dir_all(root).ok();
+}
+
+#[test]
+fn dedup_groups_copies_across_files() {
+    let root = std::env::temp_dir().join("test_facade_dedup");
+    std::fs::remove_dir_all(&root).ok();
+    std::fs::create_dir_all(&root).unwrap();
+    let body = "def total(rows):\n    kept = [r for r in rows if r]\n    return sum(kept)\n";
+    std::fs::write(root.join("a.py"), body).unwrap();
+    std::fs::write(root.join("b.py"), body.replace("total", "summed")).unwrap();
+
+    let janitor = Janitor::open(&root).unwrap();
+    let report = janitor
+        .dedup(&DedupOptions {
+            python_only: true,
+            ..Default::default()
+        })
+        .unwrap();
+
+    assert_eq!(report.files, 2);
+    assert_eq!(report.groups.len(), 1);
+    let members = &report.groups[0].members;
+    assert_eq!(members.len(), 2);
+    assert_eq!(report.start_line(&members[0].0, &members[0].1), 1);
+
+    let strict = janitor
+        .dedup(&DedupOptions {
+            python_only: true,
+            min_nodes: Some(1000),
+            ..Default::default()
+        })
+        .unwrap();
+    assert!(strict.groups.is_empty());
+
+    let err = janitor
+        .dedup(&DedupOptions {
+            python_only: true,
+            near: Some(1.5),
+            ..Default::default()
+        })
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "--threshold must be between 0.0 and 1.0, got 1.5"
+    );
+
+    std::fs::remove_dir_all(root).ok();
+}
+
+#[test]
+fn di_registration_calls_come_from_the_config() {
+    let root = std::env::temp_dir().join("test_facade_di_config");
+    std::fs::remove_dir_all(&root).ok();
+    std::fs::create_dir_all(&root).unwrap();
+    std::fs::write(
+        root.join("services.py"),
+        "class SmtpSender:\n    pass\n\n\nclass UnusedSender:\n    pass\n",
+    )
+    .unwrap();
+    std::fs::write(
+        root.join("wiring.py"),
+        "from services import SmtpSender\n\ncontainer.provide(SmtpSender)\n",
+    )
+    .unwrap();
+    let dead = |root: &Path| -> Vec<String> {
+        let result = Janitor::open(root).unwrap().scan().unwrap();
+        result.dead.into_iter().map(|e| e.name).collect()
+    };
+
+    // `provide` is not a built-in registration call.
+    let before = dead(&root);
+    assert!(before.contains(&"SmtpSender".to_string()), "{:?}", before);
+
+    std::fs::write(
+        root.join(".janitor.toml"),
+        "[di]\nregistration_calls = [\"provide\"]\n",
+    )
+    .unwrap();
+    let after = dead(&root);
+    assert!(!after.contains(&"SmtpSender".to_string()), "{:?}", after);
+    assert!(after.contains(&"UnusedSender".to_string()), "{:?}", after);
+
+    std::fs::remove_dir_all(root).ok();
+}
@@ -0,0 +1,204 @@
+//! `janitor lsp` driven over an in-memory connection, as an editor would.
+
+use janitor::lsp::{self, Connection};
+use janitor::Janitor;
+use lsp_server::{Message, Notification, Request, RequestId};
+use lsp_types::notification::{
+    DidSaveTextDocument, Exit, Initialized, Notification as _, PublishDiagnostics,
+};
+use lsp_types::request::{HoverRequest, Initialize, Request as _, Shutdown};
+use lsp_types::{
+    DiagnosticSeverity, DidSaveTextDocumentParams, Hover, HoverContents, HoverParams,
+    InitializeResult, Position, PublishDiagnosticsParams, TextDocumentIdentifier,
+    TextDocumentPositionParams, Url,
+};
+use std::path::{Path, PathBuf};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// `helper` is called from `main.py`; `unused` is dead.
+fn project(name: &str) -> PathBuf {
+    let tmp = std::env::temp_dir().join(name);
+    std::fs::remove_dir_all(&tmp).ok();
+    std::fs::create_dir_all(&tmp).unwrap();
+    std::fs::write(
+        tmp.join("util.py"),
+        "def helper():\n    return 1\n\n\ndef unused():\n    return 2\n",
+    )
+    .unwrap();
+    std::fs::write(tmp.join("main.py"), "from util import helper\n\nhelper()\n").unwrap();
+    dunce::canonicalize(&tmp).unwrap()
+}
+
+fn uri(path: &Path) -> Url {
+    Url::from_file_path(path).unwrap()
+}
+
+struct Client {
+    connection: Connection,
+    server: JoinHandle<anyhow::Result<()>>,
+    next_id: i32,
+}
+
+impl Client {
+    /// Starts a server and completes the `initialize` handshake for `root`.
+    fn start(root: &Path) -> Self {
+        let (server, connection) = Connection::memory();
+        let server = std::thread::spawn(move || lsp::serve(server, &|root| Janitor::open(root)));
+        let mut client = Client {
+            connection,
+            server,
+            next_id: 0,
+        };
+        let result = client.request(
+            Initialize::METHOD,
+            serde_json::json!({ "capabilities": {}, "rootUri": uri(root) }),
+        );
+        let result: InitializeResult = serde_json::from_value(result).unwrap();
+        assert!(result.capabilities.hover_provider.is_some());
+        client.notify(Initialized::METHOD, serde_json::json!({}));
+        client
+    }
+
+    fn recv(&self) -> Message {
+        self.connection
+            .receiver
+            .recv_timeout(Duration::from_secs(60))
+            .expect("server went quiet")
+    }
+
+    fn request(&mut self, method: &str, params: serde_json::Value) -> serde_json::Value {
+        self.next_id += 1;
+        let id = RequestId::from(self.next_id);
+        let request = Request::new(id.clone(), method.to_string(), params);
+        self.connection.sender.send(request.into()).unwrap();
+        loop {
+            if let Message::Response(response) = self.recv() {
+                assert_eq!(response.id, id);
+                assert!(response.error.is_none(), "{:?}", response.error);
+                return response.result.unwrap_or_default();
+            }
+        }
+    }
+
+    fn notify(&self, method: &str, params: impl serde::Serialize) {
+        let notification = Notification::new(method.to_string(), params);
+        self.connection.sender.send(notification.into()).unwrap();
+    }
+
+    /// The next `publishDiagnostics`, skipping log messages.
+    fn diagnostics(&self) -> PublishDiagnosticsParams {
+        loop {
+            if let Message::Notification(n) = self.recv() {
+                if n.method == PublishDiagnostics::METHOD {
+                    return serde_json::from_value(n.params).unwrap();
+                }
+            }
+        }
+    }
+
+    fn save(&self, path: &Path) {
+        let params = DidSaveTextDocumentParams {
+            text_document: TextDocumentIdentifier::new(uri(path)),
+            text: None,
+        };
+        self.notify(DidSaveTextDocument::METHOD, params);
+    }
+
+    fn hover(&mut self, path: &Path, line: u32, character: u32) -> Option<String> {
+        let params = HoverParams {
+            text_document_position_params: TextDocumentPositionParams::new(
+                TextDocumentIdentifier::new(uri(path)),
+                Position::new(line, character),
+            ),
+            work_done_progress_params: Default::default(),
+        };
+        let result = self.request(HoverRequest::METHOD, serde_json::to_value(params).unwrap());
+        let hover: Option<Hover> = serde_json::from_value(result).unwrap();
+        hover.map(|h| match h.contents {
+            HoverContents::Markup(markup) => markup.value,
+            other => panic!("unexpected hover contents {:?}", other),
+        })
+    }
+
+    fn shutdown(mut self) {
+        self.request(Shutdown::METHOD, serde_json::Value::Null);
+        self.notify(Exit::METHOD, serde_json::Value::Null);
+        self.server.join().unwrap().unwrap();
+    }
+}
+
+/// `(first line, message)` of each diagnostic, lines 0-based.
+fn dead_lines(params: &PublishDiagnosticsParams) -> Vec<(u32, &str)> {
+    params
+        .diagnostics
+        .iter()
+        .map(|d| {
+            assert_eq!(d.severity, Some(DiagnosticSeverity::HINT));
+            (d.range.start.line, d.message.as_str())
+        })
+        .collect()
+}
+
+#[test]
+fn scans_on_initialize_and_explains_protected_symbols() {
+    let root = project("test_lsp_initialize");
+    let mut client = Client::start(&root);
+
+    let published = client.diagnostics();
+    assert_eq!(published.uri, uri(&root.join("util.py")));
+    assert_eq!(
+        dead_lines(&published),
+        vec![(4, "dead symbol `unused`: nothing live refers to it")]
+    );
+    // Without a registry the server scans once and saves one.
+    assert!(root.join(".janitor/symbols.rkyv").is_file());
+
+    let hover = client.hover(&root.join("util.py"), 1, 4).unwrap();
+    assert!(
+        hover.starts_with("**`helper`** is alive: `Referenced`"),
+        "{}",
+        hover
+    );
+    assert!(hover.contains("main.py"), "{}", hover);
+    // Dead symbols have no "why alive" hover; the diagnostic says it.
+    assert_eq!(client.hover(&root.join("util.py"), 4, 4), None);
+
+    client.shutdown();
+    std::fs::remove_dir_all(root).ok();
+}
+
+#[test]
+fn starts_from_saved_registry_and_refreshes_on_save() {
+    let root = project("test_lsp_registry");
+    let janitor = Janitor::open(&root).unwrap();
+    janitor
+        .save_registry(&janitor.scan().unwrap(), &[])
+        .unwrap();
+    // Stale on purpose: a rescan at startup would already report `helper`.
+    std::fs::write(root.join("main.py"), "print('done')\n").unwrap();
+
+    let client = Client::start(&root);
+    let published = client.diagnostics();
+    assert_eq!(dead_lines(&published).len(), 1, "{:?}", published);
+
+    client.save(&root.join("main.py"));
+    let published = client.diagnostics();
+    assert_eq!(published.uri, uri(&root.join("util.py")));
+    let lines: Vec<u32> = dead_lines(&published).iter().map(|d| d.0).collect();
+    assert_eq!(lines, vec![0, 4]);
+
+    // Restoring the call clears `helper` again, through the incremental graph.
+    std::fs::write(
+        root.join("main.py"),
+        "from util import helper\n\nhelper()\n",
+    )
+    .unwrap();
+    client.save(&root.join("main.py"));
+    let published = client.diagnostics();
+    let lines: Vec<u32> = dead_lines(&published).iter().map(|d| d.0).collect();
+    assert_eq!(lines, vec![4]);
+
+    client.shutdown();
+    std::fs::remove_dir_all(root).ok();
+}
@@ -1,72 +1,348 @@
 use aho_corasick::AhoCorasick;
 use anyhow::{Context, Result};
+use common::diagnostics::{codes, Diagnostic, Diagnostics};
 use common::registry::SymbolRegistry;
+use common::text::is_word_match;
 use flate2::read::GzDecoder;
 use serde_json::Value;
 use std::collections::HashSet;
 use std::fs::File;
-use std::io::{BufReader, Read};
+use std::io::{BufRead, BufReader, Read};
 use std::path::Path;
 
-/// Ingests OTLP logs from a file (JSON or JSON.gz) and identifies referenced symbols.
+/// Ingests OTLP logs from a file (JSON, optionally `.gz` or `.zst` compressed) and
+/// identifies referenced symbols.
+///
+/// Documents shaped like an OTLP export (`resourceLogs[].scopeLogs[].logRecords[]`,
+/// or the `resource_logs`/`scope_logs`/`log_records` protobuf field names) are
+/// walked record by record; see [`extract_record_text`]. So are bare records
+/// with an OTLP `attributes` array. Any other JSON value is flattened to its strings.
+///
+/// Newline-delimited files take a fast path that scans raw line bytes without
+/// building a JSON tree (see [`ingest_with`]).
 ///
 /// # Arguments
 /// * `path` - Path to the log file.
 /// * `registry` - The symbol registry containing symbols to search for.
+/// * `diagnostics` - Receives one [`codes::MALFORMED_LOG`] warning when records
+///   could not be decoded; those records are skipped.
 ///
 /// # Returns
 /// A `HashSet` of symbol IDs that were found in the logs.
-pub fn ingest_otlp_logs(path: &Path, registry: &SymbolRegistry) -> Result<HashSet<u64>> {
-    // 1. Prepare Aho-Corasick automaton
-    let mut patterns = Vec::new();
-    let mut ids = Vec::new();
+///
+/// A qualified name only counts when it is not glued to a neighbouring
+/// identifier character (see [`common::text::is_word_match`]); use
+/// [`ingest_otlp_logs_substring`] for raw substring matching.
+pub fn ingest_otlp_logs(
+    path: &Path,
+    registry: &SymbolRegistry,
+    diagnostics: &mut Diagnostics,
+) -> Result<HashSet<u64>> {
+    ingest(path, registry, true, diagnostics)
+}
+
+/// Same as [`ingest_otlp_logs`], but any substring occurrence counts.
+pub fn ingest_otlp_logs_substring(
+    path: &Path,
+    registry: &SymbolRegistry,
+    diagnostics: &mut Diagnostics,
+) -> Result<HashSet<u64>> {
+    ingest(path, registry, false, diagnostics)
+}
+
+fn ingest(
+    path: &Path,
+    registry: &SymbolRegistry,
+    word_boundary: bool,
+    diagnostics: &mut Diagnostics,
+) -> Result<HashSet<u64>> {
+    let mut malformed = Malformed::default();
+    let found = ingest_with(path, registry, word_boundary, true, &mut malformed)?;
+    diagnostics.extend(malformed.diagnostic(path));
+    Ok(found)
+}
+
+/// Log records that could not be decoded.
+#[derive(Default)]
+struct Malformed {
+    count: usize,
+    first: Option<String>,
+}
+
+impl Malformed {
+    fn record(&mut self, error: serde_json::Error) {
+        self.count += 1;
+        self.first.get_or_insert_with(|| error.to_string());
+    }
 
-    for entry in &registry.entries {
-        // Use qualified name as the pattern
-        if !entry.qualified_name.is_empty() {
-            patterns.push(entry.qualified_name.as_str());
-            ids.push(entry.id);
+    fn diagnostic(&self, path: &Path) -> Option<Diagnostic> {
+        let first = self.first.as_ref()?;
+        let message = format!(
+            "{} malformed JSON record(s) skipped; first: {}",
+            self.count, first
+        );
+        Some(Diagnostic::warning(codes::MALFORMED_LOG, message).at(path.to_string_lossy()))
+    }
+}
+
+/// Attribute key whose records need a parse to join `code.namespace` + `code.function`.
+const CODE_FUNCTION_KEY: &[u8] = b"code.function";
+
+/// Aho-Corasick automaton over the registry's fully qualified names.
+struct Matcher {
+    ac: AhoCorasick,
+    ids: Vec<u64>,
+    word_boundary: bool,
+}
+
+impl Matcher {
+    fn new(registry: &SymbolRegistry, word_boundary: bool) -> Result<Self> {
+        let mut patterns = Vec::new();
+        let mut ids = Vec::new();
+        for entry in &registry.entries {
+            // Module-qualified, so `billing.models.save` and `auth.models.save`
+            // stay apart.
+            if !entry.qualified_name.is_empty() {
+                patterns.push(entry.fully_qualified_name());
+                ids.push(entry.id);
+            }
         }
+        let ac = AhoCorasick::new(&patterns).context("Failed to build Aho-Corasick automaton")?;
+        Ok(Self {
+            ac,
+            ids,
+            word_boundary,
+        })
     }
 
-    let ac = AhoCorasick::new(&patterns).context("Failed to build Aho-Corasick automaton")?;
-    let mut found_ids = HashSet::new();
+    fn scan(&self, haystack: &[u8], found_ids: &mut HashSet<u64>) {
+        if self.word_boundary {
+            // Overlapping search: a rejected `foo` must not hide `foo.bar`.
+            for mat in self.ac.find_overlapping_iter(haystack) {
+                if !is_word_match(haystack, mat.start(), mat.end()) {
+                    continue;
+                }
+                if let Some(&id) = self.ids.get(mat.pattern().as_usize()) {
+                    found_ids.insert(id);
+                }
+            }
+        } else {
+            for mat in self.ac.find_iter(haystack) {
+                if let Some(&id) = self.ids.get(mat.pattern().as_usize()) {
+                    found_ids.insert(id);
+                }
+            }
+        }
+    }
+
+    /// Extracts the searchable text of a parsed document and scans it.
+    fn scan_value(&self, value: &Value, found_ids: &mut HashSet<u64>) {
+        let mut buffer = String::new();
+        extract_log_text(value, &mut buffer);
+        self.scan(buffer.as_bytes(), found_ids);
+    }
+}
 
-    // 2. Open file and setup decoder
+/// Opens `path`, decompressing `.gz` and `.zst` in-process (streamed, never
+/// buffered whole).
+fn open_log(path: &Path) -> Result<Box<dyn Read>> {
     let file = File::open(path).with_context(|| format!("Failed to open log file: {:?}", path))?;
-    let reader: Box<dyn Read> = if path.extension().and_then(|ext| ext.to_str()) == Some("gz") {
-        Box::new(GzDecoder::new(file))
-    } else {
-        Box::new(file)
+    Ok(match path.extension().and_then(|ext| ext.to_str()) {
+        Some("gz") => Box::new(GzDecoder::new(file)),
+        Some("zst") => Box::new(
+            zstd::Decoder::new(file)
+                .with_context(|| format!("Failed to start zstd decoding of {:?}", path))?,
+        ),
+        _ => Box::new(file),
+    })
+}
+
+/// Returns `true` when `line` is plausibly one whole JSON object.
+fn is_json_unit(line: &[u8]) -> bool {
+    line.first() == Some(&b'{') && line.last() == Some(&b'}')
+}
+
+/// Ingestion core. With `fast_path`, newline-delimited JSON is scanned as raw
+/// line bytes through one reused buffer — no `Value` tree per record. Lines
+/// that carry `code.function` (which must be joined to `code.namespace`) or do
+/// not look like one complete object are parsed individually instead. A file
+/// whose first record spans several lines is streamed through the parser.
+///
+/// The raw scan also sees object keys; symbols are dotted qualified names, so
+/// in practice this only matters for registries of bare key-like names.
+fn ingest_with(
+    path: &Path,
+    registry: &SymbolRegistry,
+    word_boundary: bool,
+    fast_path: bool,
+    malformed: &mut Malformed,
+) -> Result<HashSet<u64>> {
+    let matcher = Matcher::new(registry, word_boundary)?;
+    let mut found_ids = HashSet::new();
+    let mut reader = BufReader::with_capacity(1 << 16, open_log(path)?);
+
+    // Sniff the first non-blank line to decide between NDJSON and a JSON stream.
+    let mut line = Vec::new();
+    let mut consumed = Vec::new();
+    while reader.read_until(b'\n', &mut line)? > 0 {
+        consumed.extend_from_slice(&line);
+        if !line.trim_ascii().is_empty() {
+            break;
+        }
+        line.clear();
+    }
+    let first = line.trim_ascii();
+    let ndjson = fast_path
+        && is_json_unit(first)
+        && serde_json::from_slice::<serde::de::IgnoredAny>(first).is_ok();
+
+    if !ndjson {
+        let stream = serde_json::Deserializer::from_reader(consumed.as_slice().chain(reader))
+            .into_iter::<Value>();
+        for result in stream {
+            match result {
+                Ok(value) => matcher.scan_value(&value, &mut found_ids),
+                Err(e) => malformed.record(e),
+            }
+        }
+        return Ok(found_ids);
+    }
+
+    let finder = memchr::memmem::Finder::new(CODE_FUNCTION_KEY);
+    loop {
+        let record = line.trim_ascii();
+        if is_json_unit(record) && finder.find(record).is_none() {
+            matcher.scan(record, &mut found_ids);
+        } else if !record.is_empty() {
+            match serde_json::from_slice::<Value>(record) {
+                Ok(value) => matcher.scan_value(&value, &mut found_ids),
+                Err(e) => malformed.record(e),
+            }
+        }
+        line.clear();
+        if reader.read_until(b'\n', &mut line)? == 0 {
+            break;
+        }
+    }
+
+    Ok(found_ids)
+}
+
+/// Reads `camel` from an object, falling back to the protobuf field name `snake`.
+fn field<'a>(value: &'a Value, camel: &str, snake: &str) -> Option<&'a Value> {
+    value.get(camel).or_else(|| value.get(snake))
+}
+
+/// Appends the searchable text of one JSON document to `buffer`.
+fn extract_log_text(value: &Value, buffer: &mut String) {
+    let Some(resource_logs) =
+        field(value, "resourceLogs", "resource_logs").and_then(Value::as_array)
+    else {
+        // One record per line, as file exporters write them.
+        return if is_otlp_record(value) {
+            extract_record_text(value, buffer)
+        } else {
+            flatten_json_value(value, buffer)
+        };
     };
-    let buf_reader = BufReader::new(reader);
-
-    // 3. Stream Parsing
-    let stream = serde_json::Deserializer::from_reader(buf_reader).into_iter::<Value>();
-
-    for result in stream {
-        match result {
-            Ok(value) => {
-                // 4. Extraction & Matching
-                let mut buffer = String::new();
-                flatten_json_value(&value, &mut buffer);
-
-                for mat in ac.find_iter(&buffer) {
-                    let pattern_index = mat.pattern().as_usize();
-                    if let Some(&id) = ids.get(pattern_index) {
-                        found_ids.insert(id);
-                    }
+    for resource in resource_logs {
+        let scopes = field(resource, "scopeLogs", "scope_logs")
+            .or_else(|| {
+                field(
+                    resource,
+                    "instrumentationLibraryLogs",
+                    "instrumentation_library_logs",
+                )
+            })
+            .and_then(Value::as_array);
+        for scope in scopes.into_iter().flatten() {
+            let records = field(scope, "logRecords", "log_records").and_then(Value::as_array);
+            for record in records.into_iter().flatten() {
+                extract_record_text(record, buffer);
+            }
+        }
+    }
+}
+
+/// Returns `true` for a bare OTLP log record: `attributes` is a `[{key, value}]` array.
+fn is_otlp_record(value: &Value) -> bool {
+    value
+        .get("attributes")
+        .and_then(Value::as_array)
+        .is_some_and(|pairs| !pairs.is_empty() && pairs.iter().all(|p| p.get("key").is_some()))
+}
+
+/// Appends the body and attribute values of one OTLP log record to `buffer`.
+///
+/// Attributes may be the OTLP `[{key, value: AnyValue}]` array or a plain
+/// object. When `code.function` is present it is also emitted joined to
+/// `code.namespace` (`pkg.module.func`), since exporters split the two.
+fn extract_record_text(record: &Value, buffer: &mut String) {
+    if let Some(body) = record.get("body") {
+        any_value_text(body, buffer);
+    }
+
+    let mut namespace = None;
+    let mut function = None;
+    let mut visit = |key: &str, value: &Value, buffer: &mut String| {
+        let start = buffer.len();
+        any_value_text(value, buffer);
+        let text = buffer[start..].trim_end().to_string();
+        match key {
+            "code.namespace" => namespace = Some(text),
+            "code.function" => function = Some(text),
+            _ => {}
+        }
+    };
+    match record.get("attributes") {
+        Some(Value::Array(pairs)) => {
+            for pair in pairs {
+                if let (Some(key), Some(value)) =
+                    (pair.get("key").and_then(Value::as_str), pair.get("value"))
+                {
+                    visit(key, value, buffer);
                 }
             }
-            Err(e) => {
-                // Resilience: Log warning and continue
-                eprintln!("Warning: Malformed JSON object in log stream: {}", e);
-                continue;
+        }
+        Some(Value::Object(map)) => {
+            for (key, value) in map {
+                visit(key, value, buffer);
             }
         }
+        _ => {}
     }
 
-    Ok(found_ids)
+    if let (Some(namespace), Some(function)) = (namespace, function) {
+        buffer.push_str(&namespace);
+        buffer.push('.');
+        buffer.push_str(&function);
+        buffer.push(' ');
+    }
+}
+
+/// Appends the strings held by an OTLP `AnyValue` (`stringValue`, `arrayValue`,
+/// `kvlistValue`, or their protobuf field names). Bare JSON is flattened.
+fn any_value_text(value: &Value, buffer: &mut String) {
+    if let Some(s) = field(value, "stringValue", "string_value").and_then(Value::as_str) {
+        buffer.push_str(s);
+        buffer.push(' ');
+    } else if let Some(values) = field(value, "arrayValue", "array_value")
+        .and_then(|a| a.get("values"))
+        .and_then(Value::as_array)
+    {
+        for v in values {
+            any_value_text(v, buffer);
+        }
+    } else if let Some(pairs) = field(value, "kvlistValue", "kvlist_value")
+        .and_then(|kv| kv.get("values"))
+        .and_then(Value::as_array)
+    {
+        for v in pairs.iter().filter_map(|p| p.get("value")) {
+            any_value_text(v, buffer);
+        }
+    } else {
+        flatten_json_value(value, buffer);
+    }
 }
 
 /// Helper to flatten JSON values into a single string buffer.
@@ -108,6 +384,7 @@ mod tests {
             id: 101,
             name: "test_func".into(),
             qualified_name: "my_module.test_func".into(),
+            module_path: String::new(),
             file_path: "src/main.rs".into(),
             entity_type: 0,
             start_line: 1,
@@ -116,11 +393,13 @@ mod tests {
             end_byte: 100,
             structural_hash: 0,
             protected_by: None,
+            protection_detail: None,
         });
         registry.insert(SymbolEntry {
             id: 202,
             name: "unused".into(),
             qualified_name: "my_module.unused".into(),
+            module_path: String::new(),
             file_path: "src/lib.rs".into(),
             entity_type: 0,
             start_line: 1,
@@ -129,6 +408,7 @@ mod tests {
             end_byte: 100,
             structural_hash: 0,
             protected_by: None,
+            protection_detail: None,
         });
 
         // 2. Create a temporary .json.gz file with OTLP-like logs
@@ -165,12 +445,12 @@ mod tests {
             // Write a newline or nothing? serde_json::Deserializer::from_reader can handle stream of JSON values.
             // But usually NDJSON has newlines. serde_json::Deserializer handles adjacent values too.
             // Let's add whitespace just in case.
-            write!(encoder, "\n")?;
+            writeln!(encoder)?;
         }
         encoder.finish()?;
 
         // 3. Run ingestor
-        let found_ids = ingest_otlp_logs(&file_path, &registry)?;
+        let found_ids = ingest_otlp_logs(&file_path, &registry, &mut Diagnostics::new())?;
 
         // 4. Verify
         assert!(
@@ -184,4 +464,227 @@ mod tests {
 
         Ok(())
     }
+
+    fn billing_registry() -> SymbolRegistry {
+        let mut registry = SymbolRegistry::new();
+        for (id, qualified_name) in [
+            (1, "billing.tasks.close_period"),
+            (2, "billing.tasks.send_reminders"),
+            (3, "billing.webhooks.retry_delivery"),
+            (4, "billing.webhooks.verify_signature"),
+            (5, "billing.tasks.unused"),
+        ] {
+            let (module, name) = qualified_name.rsplit_once('.').unwrap();
+            registry.insert(SymbolEntry {
+                id,
+                name: name.into(),
+                qualified_name: name.into(),
+                module_path: module.into(),
+                file_path: format!("{}.py", module.replace('.', "/")),
+                entity_type: 0,
+                start_line: 1,
+                end_line: 2,
+                start_byte: 0,
+                end_byte: 10,
+                structural_hash: 0,
+                protected_by: None,
+                protection_detail: None,
+            });
+        }
+        registry
+    }
+
+    fn fixture(name: &str) -> std::path::PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("fixtures")
+            .join(name)
+    }
+
+    #[test]
+    fn test_otlp_export_joins_code_attributes() -> Result<()> {
+        let found = ingest_otlp_logs(
+            &fixture("otlp_export.json"),
+            &billing_registry(),
+            &mut Diagnostics::new(),
+        )?;
+        assert_eq!(found, HashSet::from([1, 2]));
+        Ok(())
+    }
+
+    #[test]
+    fn test_otlp_protobuf_field_names() -> Result<()> {
+        let found = ingest_otlp_logs(
+            &fixture("otlp_proto_fields.json"),
+            &billing_registry(),
+            &mut Diagnostics::new(),
+        )?;
+        assert_eq!(found, HashSet::from([3, 4]));
+        Ok(())
+    }
+
+    /// Writes a synthetic NDJSON export of `lines` records mixing plain bodies,
+    /// split `code.*` attributes and one malformed line.
+    fn write_synthetic_ndjson(out: &mut dyn Write, lines: usize) -> std::io::Result<()> {
+        for i in 0..lines {
+            let record = match i % 1000 {
+                0 => serde_json::json!({
+                    "body": {"stringValue": "tick"},
+                    "attributes": [
+                        {"key": "code.namespace", "value": {"stringValue": "billing.webhooks"}},
+                        {"key": "code.function", "value": {"stringValue": "retry_delivery"}}
+                    ]
+                }),
+                500 => {
+                    serde_json::json!({"body": {"stringValue": "sent billing.tasks.send_reminders"}})
+                }
+                _ => {
+                    serde_json::json!({"body": {"stringValue": format!("heartbeat {}", i)}, "severityText": "DEBUG"})
+                }
+            };
+            serde_json::to_writer(&mut *out, &record)?;
+            writeln!(out)?;
+        }
+        writeln!(out, "{{\"body\": truncated")
+    }
+
+    #[test]
+    fn test_ndjson_fast_path_matches_slow_path() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let plain = temp_dir.path().join("export.json");
+        let gz = temp_dir.path().join("export.json.gz");
+        let mut file = std::io::BufWriter::new(File::create(&plain)?);
+        write_synthetic_ndjson(&mut file, 50_000)?;
+        file.flush()?;
+        drop(file);
+        let mut encoder = GzEncoder::new(File::create(&gz)?, Compression::fast());
+        write_synthetic_ndjson(&mut encoder, 50_000)?;
+        encoder.finish()?;
+
+        let registry = billing_registry();
+        let expected = HashSet::from([2, 3]);
+        for path in [&plain, &gz] {
+            for fast_path in [true, false] {
+                let mut malformed = Malformed::default();
+                let found = ingest_with(path, &registry, true, fast_path, &mut malformed)?;
+                assert_eq!(found, expected);
+                assert_eq!(malformed.count, 1);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_zst_logs_are_decompressed() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let file = File::create(temp_dir.path().join("export.json.zst"))?;
+        let mut encoder = zstd::Encoder::new(file, 0)?;
+        write_synthetic_ndjson(&mut encoder, 2_000)?;
+        encoder.finish()?;
+
+        let mut diagnostics = Diagnostics::new();
+        let found = ingest_otlp_logs(
+            &temp_dir.path().join("export.json.zst"),
+            &billing_registry(),
+            &mut diagnostics,
+        )?;
+        assert_eq!(found, HashSet::from([2, 3]));
+        let malformed = diagnostics.iter().next().unwrap();
+        assert_eq!(malformed.code, codes::MALFORMED_LOG);
+        assert!(malformed
+            .message
+            .starts_with("1 malformed JSON record(s) skipped"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_record_text_joins_namespace_and_function() {
+        let record = serde_json::json!({
+            "body": {"stringValue": "done"},
+            "attributes": [
+                {"key": "code.function", "value": {"stringValue": "close_period"}},
+                {"key": "code.namespace", "value": {"stringValue": "billing.tasks"}}
+            ]
+        });
+        let mut buffer = String::new();
+        extract_record_text(&record, &mut buffer);
+        assert!(buffer.contains("billing.tasks.close_period"));
+    }
+
+    #[test]
+    fn test_ingest_otlp_logs_word_boundary() -> Result<()> {
+        let mut registry = SymbolRegistry::new();
+        for (id, qualified_name) in [(1, "jobs.run"), (2, "api.get"), (3, "api.post")] {
+            registry.insert(SymbolEntry {
+                id,
+                name: qualified_name.rsplit('.').next().unwrap().into(),
+                qualified_name: qualified_name.into(),
+                module_path: String::new(),
+                file_path: "src/app.py".into(),
+                entity_type: 0,
+                start_line: 1,
+                end_line: 2,
+                start_byte: 0,
+                end_byte: 10,
+                structural_hash: 0,
+                protected_by: None,
+                protection_detail: None,
+            });
+        }
+
+        let temp_dir = tempfile::tempdir()?;
+        let file_path = temp_dir.path().join("logs.json");
+        let mut file = File::create(&file_path)?;
+        writeln!(
+            file,
+            "{}",
+            serde_json::json!({"body": "jobs.running started; api.get(42) ok"})
+        )?;
+        drop(file);
+
+        let found = ingest_otlp_logs(&file_path, &registry, &mut Diagnostics::new())?;
+        assert_eq!(found, HashSet::from([2]));
+
+        let substring = ingest_otlp_logs_substring(&file_path, &registry, &mut Diagnostics::new())?;
+        assert_eq!(substring, HashSet::from([1, 2]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_same_name_in_two_modules_matches_by_module() -> Result<()> {
+        let mut registry = SymbolRegistry::new();
+        for (id, module) in [(1, "billing.models"), (2, "auth.models")] {
+            registry.insert(SymbolEntry {
+                id,
+                name: "save".into(),
+                qualified_name: "save".into(),
+                module_path: module.into(),
+                file_path: format!("{}.py", module.replace('.', "/")),
+                entity_type: 0,
+                start_line: 1,
+                end_line: 2,
+                start_byte: 0,
+                end_byte: 10,
+                structural_hash: 0,
+                protected_by: None,
+                protection_detail: None,
+            });
+        }
+
+        let temp_dir = tempfile::tempdir()?;
+        let file_path = temp_dir.path().join("logs.json");
+        let mut file = File::create(&file_path)?;
+        writeln!(
+            file,
+            "{}",
+            serde_json::json!({"body": "retrying billing.models.save after timeout"})
+        )?;
+        drop(file);
+
+        assert_eq!(
+            ingest_otlp_logs(&file_path, &registry, &mut Diagnostics::new())?,
+            HashSet::from([1])
+        );
+        Ok(())
+    }
 }
@@ -1,29 +1,67 @@
-use petgraph::graph::DiGraph;
+use petgraph::algo::tarjan_scc;
+use petgraph::graph::{DiGraph, NodeIndex};
 use petgraph::visit::EdgeRef;
 use petgraph::Direction;
-use std::collections::{HashSet, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
 
 pub struct SymbolOracle;
 
+/// How runtime evidence (`live_ids`) propagates through the graph.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LiveMode {
+    /// Live symbols seed the reachability search alongside entry points, so
+    /// everything they call survives too.
+    #[default]
+    Transitive,
+    /// Only the live symbols themselves survive; their callees must be
+    /// reachable from an entry point.
+    Strict,
+}
+
 impl SymbolOracle {
+    /// Computes the list of "dead" symbol IDs with [`LiveMode::Transitive`].
+    ///
+    /// See [`SymbolOracle::compute_kill_list_with`].
+    pub fn compute_kill_list(
+        graph: &DiGraph<u64, ()>,
+        entry_points: &[u64],
+        live_ids: &HashSet<u64>,
+        wisdom_protected: &HashSet<u64>,
+    ) -> Vec<u64> {
+        Self::compute_kill_list_with(
+            graph,
+            entry_points,
+            live_ids,
+            wisdom_protected,
+            LiveMode::Transitive,
+        )
+    }
+
     /// Computes the list of "dead" symbol IDs.
     ///
     /// # Algorithm
     /// 1. **The Universe**: Start with a list of ALL node IDs in the graph.
-    /// 2. **The Living**: Identify all nodes reachable from `entry_points`.
+    /// 2. **The Living**: Identify all nodes reachable from `entry_points` and,
+    ///    in [`LiveMode::Transitive`], from `live_ids`.
     /// 3. **The Evidence**: Union the "Reachable" set with `live_ids` (from Lazarus) and `wisdom_protected` (from Heuristics).
     /// 4. **The Verdict**: Any node NOT in the "Living/Evidence" set is **DEAD**.
     ///
+    /// A handler seen in production logs runs its helpers too, so transitive
+    /// mode keeps them. Strict mode keeps only the logged symbol and condemns
+    /// any callee not otherwise reachable from an entry point.
+    ///
     /// # Arguments
     /// * `graph` - The dependency graph where nodes are Symbol IDs (u64).
     /// * `entry_points` - List of symbol IDs that are considered roots (e.g., main functions, API endpoints).
     /// * `live_ids` - Set of symbol IDs found in runtime logs (Lazarus).
     /// * `wisdom_protected` - Set of symbol IDs protected by static analysis heuristics.
-    pub fn compute_kill_list(
+    /// * `mode` - Whether `live_ids` also act as reachability roots.
+    pub fn compute_kill_list_with(
         graph: &DiGraph<u64, ()>,
         entry_points: &[u64],
         live_ids: &HashSet<u64>,
         wisdom_protected: &HashSet<u64>,
+        mode: LiveMode,
     ) -> Vec<u64> {
         let node_count = graph.node_count();
         if node_count == 0 {
@@ -38,7 +76,9 @@ impl SymbolOracle {
 
         for idx in graph.node_indices() {
             let id = graph[idx];
-            if entry_point_set.contains(&id) {
+            let is_root = entry_point_set.contains(&id)
+                || (mode == LiveMode::Transitive && live_ids.contains(&id));
+            if is_root {
                 visited_indices.insert(idx.index());
                 queue.push_back(idx);
             }
@@ -74,6 +114,61 @@ impl SymbolOracle {
     }
 }
 
+/// Orders `kill_list` into deletion batches so no symbol is removed while a
+/// dead symbol that references it still exists.
+///
+/// Only edges between two killed symbols matter. Mutually-recursive symbols
+/// (a strongly connected component) always share a batch and must be deleted
+/// together. Batch `n` holds every component whose longest chain of dead
+/// callers has length `n`: callers come first, callees after. Symbols absent
+/// from `graph` land in the first batch. IDs within a batch are sorted.
+pub fn deletion_plan(graph: &DiGraph<u64, ()>, kill_list: &[u64]) -> Vec<Vec<u64>> {
+    let killed: HashSet<u64> = kill_list.iter().copied().collect();
+
+    // Subgraph induced by the kill list.
+    let mut sub = DiGraph::<u64, ()>::new();
+    let mut sub_index: HashMap<u64, NodeIndex> = HashMap::new();
+    for &id in kill_list {
+        sub_index.entry(id).or_insert_with(|| sub.add_node(id));
+    }
+    for edge in graph.edge_references() {
+        let (from, to) = (graph[edge.source()], graph[edge.target()]);
+        if killed.contains(&from) && killed.contains(&to) {
+            sub.update_edge(sub_index[&from], sub_index[&to], ());
+        }
+    }
+
+    // Tarjan yields components callees-first; walk them callers-first.
+    let components = tarjan_scc(&sub);
+    let mut component_of = vec![0usize; sub.node_count()];
+    for (c, nodes) in components.iter().enumerate() {
+        for n in nodes {
+            component_of[n.index()] = c;
+        }
+    }
+    let mut level = vec![0usize; components.len()];
+    for (c, nodes) in components.iter().enumerate().rev() {
+        for &n in nodes {
+            for edge in sub.edges_directed(n, Direction::Outgoing) {
+                let d = component_of[edge.target().index()];
+                if d != c {
+                    level[d] = level[d].max(level[c] + 1);
+                }
+            }
+        }
+    }
+
+    let depth = level.iter().max().map_or(0, |l| l + 1);
+    let mut batches = vec![Vec::new(); depth];
+    for (c, nodes) in components.iter().enumerate() {
+        batches[level[c]].extend(nodes.iter().map(|&n| sub[n]));
+    }
+    for batch in &mut batches {
+        batch.sort_unstable();
+    }
+    batches
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -87,12 +182,7 @@ mod tests {
         // 3: Dead (Isolated)
         // 4: Logged (Live in logs)
         // 5: Protected (Heuristic)
-        // 6: Called by 4 (Dependency of Live - SHOULD BE DEAD per strict algo?)
-        //    Wait, the prompt says "Reachable from entry_points".
-        //    It does NOT say "Reachable from live_ids".
-        //    "The Verdict: Any node NOT in 'Living/Evidence' set is DEAD."
-        //    So 6 is NOT in Living (reachable from 1), NOT in Live (4 is live, 6 is not), NOT in Protected.
-        //    So 6 is DEAD. This confirms strict adherence to the prompt.
+        // 6: Called by 4 (Dependency of Live - alive transitively, dead in strict mode)
 
         let n1 = graph.add_node(1);
         let n2 = graph.add_node(2);
@@ -120,11 +210,85 @@ mod tests {
         // 3: Isolated -> Dead
         // 4: In live_ids -> Alive
         // 5: In wisdom_protected -> Alive
-        // 6: Reachable from 4, but 4 is not entry point. Not in live_ids. Not protected. -> Dead.
+        // 6: Reachable from live 4 -> Alive
 
-        // Expected Dead: [3, 6]
+        // Expected Dead: [3]
         let mut sorted_kill = kill_list.clone();
         sorted_kill.sort();
-        assert_eq!(sorted_kill, vec![3, 6]);
+        assert_eq!(sorted_kill, vec![3]);
+
+        // Strict: 6 is reachable only from 4, which is not an entry point.
+        let mut strict = SymbolOracle::compute_kill_list_with(
+            &graph,
+            &entry_points,
+            &live_ids,
+            &wisdom_protected,
+            LiveMode::Strict,
+        );
+        strict.sort();
+        assert_eq!(strict, vec![3, 6]);
+    }
+
+    #[test]
+    fn test_live_chain_survives_transitively() {
+        let mut graph = DiGraph::<u64, ()>::new();
+
+        // 10 (logged handler) -> 11 -> 12; 13 calls 10 but nothing reaches 13.
+        let live = graph.add_node(10);
+        let a = graph.add_node(11);
+        let b = graph.add_node(12);
+        let caller = graph.add_node(13);
+        graph.add_edge(live, a, ());
+        graph.add_edge(a, b, ());
+        graph.add_edge(caller, live, ());
+
+        let live_ids = HashSet::from([10]);
+        let none = HashSet::new();
+
+        let kill_list = SymbolOracle::compute_kill_list(&graph, &[], &live_ids, &none);
+        // Liveness flows to callees only, never back to callers.
+        assert_eq!(kill_list, vec![13]);
+
+        let mut strict =
+            SymbolOracle::compute_kill_list_with(&graph, &[], &live_ids, &none, LiveMode::Strict);
+        strict.sort();
+        assert_eq!(strict, vec![11, 12, 13]);
+    }
+
+    #[test]
+    fn test_deletion_plan_orders_dead_chain_callers_first() {
+        let mut graph = DiGraph::<u64, ()>::new();
+        // live 1 -> 4; dead chain 10 -> 11 -> 12, with 12 also called by live 1.
+        let live = graph.add_node(1);
+        let kept = graph.add_node(4);
+        let a = graph.add_node(10);
+        let b = graph.add_node(11);
+        let c = graph.add_node(12);
+        graph.add_node(20);
+        graph.add_edge(live, kept, ());
+        graph.add_edge(a, b, ());
+        graph.add_edge(b, c, ());
+        graph.add_edge(a, c, ());
+        graph.add_edge(live, c, ());
+
+        let plan = deletion_plan(&graph, &[12, 20, 11, 10, 99]);
+        assert_eq!(plan, vec![vec![10, 20, 99], vec![11], vec![12]]);
+    }
+
+    #[test]
+    fn test_deletion_plan_keeps_cycles_in_one_batch() {
+        let mut graph = DiGraph::<u64, ()>::new();
+        // 1 -> {2 <-> 3} -> 4, and a self-recursive 5.
+        let n: Vec<NodeIndex> = (1..=5).map(|id| graph.add_node(id)).collect();
+        graph.add_edge(n[0], n[1], ());
+        graph.add_edge(n[1], n[2], ());
+        graph.add_edge(n[2], n[1], ());
+        graph.add_edge(n[2], n[3], ());
+        graph.add_edge(n[4], n[4], ());
+
+        let plan = deletion_plan(&graph, &[1, 2, 3, 4, 5]);
+        assert_eq!(plan, vec![vec![1, 5], vec![2, 3], vec![4]]);
+
+        assert!(deletion_plan(&graph, &[]).is_empty());
     }
 }
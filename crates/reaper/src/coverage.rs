@@ -0,0 +1,275 @@
+//! Coverage liveness: symbols executed under `coverage.py`.
+//!
+//! Reads the report written by `coverage json`:
+//!
+//! ```json
+//! {"files": {"pkg/billing.py": {"executed_lines": [1, 2, 5, 6]}}}
+//! ```
+//!
+//! A `def` line runs when its module is imported, so a function only counts as
+//! alive when a line of its *body* was executed. Class bodies also run at
+//! import time; a class is alive when one of its methods is. Module-level
+//! assignments always execute and are never proven alive by coverage.
+
+use crate::ReaperError;
+use common::registry::SymbolRegistry;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+/// Executed lines per source file from one coverage report.
+#[derive(Debug, Default)]
+pub struct CoverageReport {
+    /// Normalized absolute path (forward slashes) → executed line numbers.
+    files: HashMap<String, HashSet<u32>>,
+    /// Report keys as written (forward slashes), for suffix matching when the
+    /// report was produced from a different working directory.
+    relative: Vec<(String, String)>,
+}
+
+impl CoverageReport {
+    /// Loads a `coverage json` report, resolving relative paths against `project_root`.
+    pub fn load(report: &Path, project_root: &Path) -> Result<Self, ReaperError> {
+        Self::parse(&std::fs::read_to_string(report)?, project_root)
+    }
+
+    /// Parses report text. See [`CoverageReport::load`].
+    pub fn parse(json: &str, project_root: &Path) -> Result<Self, ReaperError> {
+        let value: serde_json::Value = serde_json::from_str(json)
+            .map_err(|e| ReaperError::ParseError(format!("coverage report: {}", e)))?;
+        let files = value
+            .get("files")
+            .and_then(|f| f.as_object())
+            .ok_or_else(|| ReaperError::ParseError("coverage report has no `files`".into()))?;
+
+        let root = dunce::canonicalize(project_root).unwrap_or_else(|_| project_root.into());
+        let mut report = Self::default();
+        for (name, data) in files {
+            let lines: HashSet<u32> = data
+                .get("executed_lines")
+                .and_then(|l| l.as_array())
+                .into_iter()
+                .flatten()
+                .filter_map(|n| n.as_u64())
+                .map(|n| n as u32)
+                .collect();
+            let path = root.join(name);
+            let key = normalize(&dunce::canonicalize(&path).unwrap_or(path));
+            report.relative.push((name.replace('\\', "/"), key.clone()));
+            report.files.entry(key).or_default().extend(lines);
+        }
+        Ok(report)
+    }
+
+    /// Executed lines recorded for `file_path` (a registry path), if any.
+    fn lines_for(&self, file_path: &str) -> Option<&HashSet<u32>> {
+        self.files.get(file_path).or_else(|| {
+            self.relative
+                .iter()
+                .find(|(rel, _)| file_path.ends_with(&format!("/{}", rel.trim_start_matches("./"))))
+                .and_then(|(_, key)| self.files.get(key))
+        })
+    }
+
+    /// IDs of registry symbols whose bodies executed.
+    ///
+    /// Each covered file is read and parsed again to find its function bodies;
+    /// a file that cannot be is an error.
+    pub fn alive_ids(&self, registry: &SymbolRegistry) -> Result<HashSet<u64>, ReaperError> {
+        let mut by_file: HashMap<&str, Vec<usize>> = HashMap::new();
+        for (i, entry) in registry.entries.iter().enumerate() {
+            by_file.entry(entry.file_path.as_str()).or_default().push(i);
+        }
+
+        let mut parser = tree_sitter::Parser::new();
+        parser
+            .set_language(&tree_sitter_python::LANGUAGE.into())
+            .map_err(|e| ReaperError::ParseError(e.to_string()))?;
+
+        let mut alive = HashSet::new();
+        for (file_path, indices) in by_file {
+            let Some(executed) = self.lines_for(file_path).filter(|l| !l.is_empty()) else {
+                continue;
+            };
+            let source = std::fs::read(file_path)?;
+            let tree = parser.parse(&source, None).ok_or_else(|| {
+                ReaperError::ParseError(format!("{}: tree-sitter returned no tree", file_path))
+            })?;
+
+            // (id, byte range) of every class, checked once its methods are known.
+            let mut classes: Vec<(u64, u32, u32)> = Vec::new();
+            let mut alive_functions: Vec<u32> = Vec::new();
+            for i in indices {
+                let entry = &registry.entries[i];
+                let Some(def) = definition_at(tree.root_node(), entry.start_byte as usize) else {
+                    continue;
+                };
+                if def.kind() == "class_definition" {
+                    classes.push((entry.id, entry.start_byte, entry.end_byte));
+                    continue;
+                }
+                let Some(body) = def.child_by_field_name("body") else {
+                    continue;
+                };
+                let header = def.start_position().row;
+                let first = body.start_position().row;
+                if first == header {
+                    // `def f(): return 1` — body and header share the import-time line.
+                    continue;
+                }
+                let last = def.end_position().row;
+                if (first..=last).any(|row| executed.contains(&(row as u32 + 1))) {
+                    alive.insert(entry.id);
+                    alive_functions.push(entry.start_byte);
+                }
+            }
+            for (id, start, end) in classes {
+                if alive_functions.iter().any(|b| start < *b && *b < end) {
+                    alive.insert(id);
+                }
+            }
+        }
+        Ok(alive)
+    }
+}
+
+/// The `function_definition` or `class_definition` that starts at `byte`,
+/// looking through a `decorated_definition` wrapper. `None` for anything else
+/// (assignments, or a byte that is merely inside a definition).
+fn definition_at(root: tree_sitter::Node<'_>, byte: usize) -> Option<tree_sitter::Node<'_>> {
+    let mut node = root.descendant_for_byte_range(byte, byte)?;
+    while node.start_byte() == byte {
+        match node.kind() {
+            "decorated_definition" => return node.child_by_field_name("definition"),
+            "function_definition" | "class_definition" => {
+                return Some(node);
+            }
+            _ => node = node.parent()?,
+        }
+    }
+    None
+}
+
+fn normalize(path: &Path) -> String {
+    dunce::simplified(path).to_string_lossy().replace('\\', "/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::registry::SymbolEntry;
+    use std::fs;
+
+    fn entry(id: u64, file: &Path, source: &str, name: &str, entity_type: u8) -> SymbolEntry {
+        let needle = if entity_type == 2 {
+            format!("class {}", name)
+        } else {
+            format!("def {}", name)
+        };
+        let start = source.find(&needle).unwrap();
+        let end = source[start..]
+            .find("\n\n")
+            .map_or(source.len(), |e| start + e);
+        SymbolEntry {
+            id,
+            name: name.into(),
+            qualified_name: name.into(),
+            module_path: String::new(),
+            file_path: normalize(&dunce::canonicalize(file).unwrap()),
+            entity_type,
+            start_line: source[..start].lines().count() as u32 + 1,
+            end_line: source[..end].lines().count() as u32,
+            start_byte: start as u32,
+            end_byte: end as u32,
+            structural_hash: 0,
+            protected_by: None,
+            protection_detail: None,
+        }
+    }
+
+    #[test]
+    fn test_executed_bodies_are_alive() {
+        let tmp = std::env::temp_dir().join("test_reaper_coverage");
+        fs::remove_dir_all(&tmp).ok();
+        fs::create_dir_all(tmp.join("pkg")).unwrap();
+        let billing = "def charge():\n    return 1\n\ndef refund():\n    return 2\n\ndef one_liner(): return 3\n";
+        let models = "class Invoice:\n    def total(self):\n        return 0\n\nclass Ledger:\n    def post(self):\n        return 0\n";
+        fs::write(tmp.join("pkg/billing.py"), billing).unwrap();
+        fs::write(tmp.join("pkg/models.py"), models).unwrap();
+
+        let mut registry = SymbolRegistry::new();
+        let billing_path = tmp.join("pkg/billing.py");
+        let models_path = tmp.join("pkg/models.py");
+        registry.insert(entry(1, &billing_path, billing, "charge", 0));
+        registry.insert(entry(2, &billing_path, billing, "refund", 0));
+        registry.insert(entry(3, &billing_path, billing, "one_liner", 0));
+        registry.insert(entry(4, &models_path, models, "Invoice", 2));
+        registry.insert(entry(5, &models_path, models, "total", 3));
+        registry.insert(entry(6, &models_path, models, "Ledger", 2));
+        registry.insert(entry(7, &models_path, models, "post", 3));
+
+        // Import-time lines (def/class headers) plus the bodies of charge and total.
+        // One path is relative, the other absolute with the report's own spelling.
+        let report = format!(
+            r#"{{"files": {{
+                "pkg/billing.py": {{"executed_lines": [1, 2, 4, 7]}},
+                "{}": {{"executed_lines": [1, 2, 3, 5, 6]}}
+            }}}}"#,
+            tmp.join("pkg/models.py").display()
+        );
+        let coverage = CoverageReport::parse(&report, &tmp).unwrap();
+        let alive = coverage.alive_ids(&registry).unwrap();
+        assert_eq!(alive, HashSet::from([1, 4, 5]));
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_report_from_parent_directory_matches_by_suffix() {
+        let tmp = std::env::temp_dir().join("test_reaper_coverage_suffix");
+        fs::remove_dir_all(&tmp).ok();
+        fs::create_dir_all(tmp.join("service")).unwrap();
+        let source = "def handler():\n    return 1\n";
+        fs::write(tmp.join("service/api.py"), source).unwrap();
+
+        let mut registry = SymbolRegistry::new();
+        registry.insert(entry(1, &tmp.join("service/api.py"), source, "handler", 0));
+
+        // Coverage ran from `tmp`, but the project root is `tmp/service`:
+        // `service/api.py` does not resolve under the root and is matched by suffix.
+        let report = r#"{"files": {"service\\api.py": {"executed_lines": [1, 2]}}}"#;
+        let coverage = CoverageReport::parse(report, &tmp.join("service")).unwrap();
+        assert_eq!(coverage.alive_ids(&registry).unwrap(), HashSet::from([1]));
+
+        // Suffixes match whole path components only.
+        let report = r#"{"files": {"ice/api.py": {"executed_lines": [1, 2]}}}"#;
+        let coverage = CoverageReport::parse(report, &tmp.join("service")).unwrap();
+        assert!(coverage.alive_ids(&registry).unwrap().is_empty());
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_malformed_report_is_parse_error() {
+        let err = CoverageReport::parse("{\"meta\": {}}", Path::new(".")).unwrap_err();
+        assert!(matches!(err, ReaperError::ParseError(_)));
+    }
+
+    #[test]
+    fn test_unreadable_covered_file_is_an_error() {
+        let tmp = std::env::temp_dir().join("test_reaper_coverage_gone");
+        fs::remove_dir_all(&tmp).ok();
+        fs::create_dir_all(&tmp).unwrap();
+        let source = "def handler():\n    return 1\n";
+        fs::write(tmp.join("api.py"), source).unwrap();
+        let mut registry = SymbolRegistry::new();
+        registry.insert(entry(1, &tmp.join("api.py"), source, "handler", 0));
+
+        let report = r#"{"files": {"api.py": {"executed_lines": [1, 2]}}}"#;
+        let coverage = CoverageReport::parse(report, &tmp).unwrap();
+        fs::remove_file(tmp.join("api.py")).unwrap();
+        let err = coverage.alive_ids(&registry).unwrap_err();
+        assert!(matches!(err, ReaperError::IoError(_)));
+
+        fs::remove_dir_all(tmp).ok();
+    }
+}
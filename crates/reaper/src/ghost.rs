@@ -0,0 +1,232 @@
+//! Ghost manifest: the index of everything `.janitor/ghost/` holds.
+//!
+//! Two kinds of artefacts land in the ghost directory:
+//! - **Backups** written by [`SafeDeleter`](crate::SafeDeleter) before a file is edited.
+//! - **Files** moved there whole by the Ghost Protocol (`ShadowManager::move_to_ghost`).
+//!
+//! Neither name records where the artefact came from, so every one is logged in
+//! `.janitor/ghost.manifest` — one tab-separated line per artefact:
+//!
+//! ```text
+//! {transaction}\t{kind}\t{unix_ts}\t{ghost_name}\t{original_path}
+//! ```
+//!
+//! `ghost_name` is relative to the ghost directory; `original_path` is the
+//! absolute path the artefact is restored to. Entries sharing a transaction id
+//! belong to one `janitor clean` run and are undone together by `janitor restore --all`.
+
+use crate::ReaperError;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// What a manifest entry's ghost artefact is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GhostKind {
+    /// Pre-edit copy of a file whose symbols were deleted or replaced.
+    Backup,
+    /// A whole file moved out of the source tree.
+    File,
+}
+
+impl GhostKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            GhostKind::Backup => "backup",
+            GhostKind::File => "file",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "backup" => Some(GhostKind::Backup),
+            "file" => Some(GhostKind::File),
+            _ => None,
+        }
+    }
+}
+
+/// One line of the ghost manifest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GhostRecord {
+    /// Transaction the artefact belongs to (see [`new_transaction_id`]).
+    pub transaction: String,
+    /// Backup or whole file.
+    pub kind: GhostKind,
+    /// Unix timestamp (seconds) at which the artefact was written.
+    pub timestamp: u64,
+    /// Path of the artefact relative to the ghost directory.
+    pub ghost_name: PathBuf,
+    /// Absolute path the artefact is restored to.
+    pub original: PathBuf,
+}
+
+/// Handle to `{project_root}/.janitor/ghost.manifest`.
+#[derive(Debug, Clone)]
+pub struct GhostManifest {
+    path: PathBuf,
+    ghost_dir: PathBuf,
+}
+
+impl GhostManifest {
+    /// Returns the manifest handle for `project_root`. Nothing is created until
+    /// the first [`append`](Self::append).
+    pub fn open(project_root: &Path) -> Self {
+        let janitor_dir = project_root.join(".janitor");
+        Self {
+            path: janitor_dir.join("ghost.manifest"),
+            ghost_dir: janitor_dir.join("ghost"),
+        }
+    }
+
+    /// The ghost directory every `ghost_name` is relative to.
+    pub fn ghost_dir(&self) -> &Path {
+        &self.ghost_dir
+    }
+
+    /// Reads every entry, oldest first. A missing manifest yields no entries;
+    /// malformed lines are skipped.
+    pub fn records(&self) -> Result<Vec<GhostRecord>, ReaperError> {
+        let content = match std::fs::read_to_string(&self.path) {
+            Ok(c) => c,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(content.lines().filter_map(parse_line).collect())
+    }
+
+    /// Appends `record` to the manifest.
+    pub fn append(&self, record: &GhostRecord) -> Result<(), ReaperError> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        file.write_all(format_line(record).as_bytes())?;
+        Ok(())
+    }
+
+    /// Drops every entry matching `pred`. The manifest is deleted once empty.
+    pub fn remove_where(&self, pred: impl Fn(&GhostRecord) -> bool) -> Result<(), ReaperError> {
+        let kept: Vec<String> = self
+            .records()?
+            .iter()
+            .filter(|r| !pred(r))
+            .map(format_line)
+            .collect();
+        if kept.is_empty() {
+            return match std::fs::remove_file(&self.path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+                _ => Ok(()),
+            };
+        }
+        std::fs::write(&self.path, kept.concat())?;
+        Ok(())
+    }
+
+    /// Transaction id of the most recently appended entry.
+    pub fn latest_transaction(&self) -> Result<Option<String>, ReaperError> {
+        Ok(self.records()?.pop().map(|r| r.transaction))
+    }
+
+    /// Puts `record`'s artefact back at its original path and drops its entry.
+    ///
+    /// A backup is copied over the (edited) original and then deleted; a whole
+    /// file is moved back, recreating its parent directories.
+    pub fn restore(&self, record: &GhostRecord) -> Result<(), ReaperError> {
+        let ghost_path = self.ghost_dir.join(&record.ghost_name);
+        if let Some(parent) = record.original.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        match record.kind {
+            GhostKind::Backup => {
+                std::fs::copy(&ghost_path, &record.original)?;
+                std::fs::remove_file(&ghost_path)?;
+            }
+            GhostKind::File => {
+                if std::fs::rename(&ghost_path, &record.original).is_err() {
+                    std::fs::copy(&ghost_path, &record.original)?;
+                    std::fs::remove_file(&ghost_path)?;
+                }
+            }
+        }
+        self.remove_where(|r| r == record)
+    }
+}
+
+/// Returns a fresh transaction id (Unix time in nanoseconds, hex-encoded).
+pub fn new_transaction_id() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    format!("{:x}", nanos)
+}
+
+/// Current Unix time in seconds.
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+fn format_line(record: &GhostRecord) -> String {
+    format!(
+        "{}\t{}\t{}\t{}\t{}\n",
+        record.transaction,
+        record.kind.as_str(),
+        record.timestamp,
+        record.ghost_name.display(),
+        record.original.display()
+    )
+}
+
+fn parse_line(line: &str) -> Option<GhostRecord> {
+    let mut fields = line.splitn(5, '\t');
+    Some(GhostRecord {
+        transaction: fields.next()?.to_string(),
+        kind: GhostKind::parse(fields.next()?)?,
+        timestamp: fields.next()?.parse().ok()?,
+        ghost_name: PathBuf::from(fields.next()?),
+        original: PathBuf::from(fields.next()?),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_append_restore_and_cleanup() {
+        let tmp = std::env::temp_dir().join("test_ghost_manifest");
+        fs::remove_dir_all(&tmp).ok();
+        fs::create_dir_all(tmp.join(".janitor/ghost/pkg")).ok();
+        fs::write(tmp.join(".janitor/ghost/pkg/old.py"), b"x = 1\n").ok();
+
+        let manifest = GhostManifest::open(&tmp);
+        let record = GhostRecord {
+            transaction: "t1".into(),
+            kind: GhostKind::File,
+            timestamp: 7,
+            ghost_name: PathBuf::from("pkg/old.py"),
+            original: tmp.join("pkg/old.py"),
+        };
+        manifest.append(&record).unwrap();
+        assert_eq!(manifest.records().unwrap(), vec![record.clone()]);
+        assert_eq!(
+            manifest.latest_transaction().unwrap().as_deref(),
+            Some("t1")
+        );
+
+        manifest.restore(&record).unwrap();
+        assert_eq!(fs::read(tmp.join("pkg/old.py")).unwrap(), b"x = 1\n");
+        assert!(!tmp.join(".janitor/ghost/pkg/old.py").exists());
+        assert!(!tmp.join(".janitor/ghost.manifest").exists());
+
+        fs::remove_dir_all(tmp).ok();
+    }
+}
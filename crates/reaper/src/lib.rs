@@ -1,10 +1,18 @@
+pub mod coverage;
+pub mod ghost;
+pub mod patch;
+pub mod proxy;
 pub mod safe_delete;
 pub mod test_fingerprint;
 
-pub use safe_delete::{DeletionTarget, ReplacementTarget, SafeDeleter};
+pub use safe_delete::{
+    delete_ranges, replace_ranges, DeletionOutcome, DeletionStatus, DeletionTarget,
+    ReplacementTarget, SafeDeleter,
+};
 
 use aho_corasick::AhoCorasick;
-use std::collections::HashSet;
+use common::text::is_word_match;
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 use std::path::Path;
@@ -16,6 +24,16 @@ pub enum ReaperError {
     IoError(#[from] std::io::Error),
     #[error("Parse error: {0}")]
     ParseError(String),
+    #[error("{0}")]
+    Source(#[from] common::source::SourceError),
+    #[error("Cannot proxy `{name}`: {reason}")]
+    Unproxyable { name: String, reason: String },
+    #[error("pytest collection failed (exit code {code}): {detail}")]
+    CollectionFailed { code: i32, detail: String },
+    #[error("cannot build the symbol name matcher: {0}")]
+    Matcher(#[from] aho_corasick::BuildError),
+    #[error("{} is outside the project root", path.display())]
+    OutsideRoot { path: std::path::PathBuf },
 }
 
 /// Ingests liveness signals from log files to determine symbol usage.
@@ -32,8 +50,15 @@ pub trait LivenessTracker {
 
 /// Simple log-based liveness tracker backed by an Aho-Corasick automaton.
 ///
-/// Searches log lines for symbol qualified names (substring match).
-/// Marks symbols as alive if their name appears in any log line.
+/// Searches log lines for symbol qualified names. By default a match only
+/// counts when it is not glued to a neighbouring identifier character, so
+/// `run` is not kept alive by "running" (see [`common::text::is_word_match`]).
+/// [`SimpleLogTracker::new_substring`] restores raw substring matching.
+///
+/// Each distinct name is searched once. A name shared by several symbols
+/// (`save` defined in two modules) marks **all** of them alive: a log line
+/// rarely says which file it came from, and keeping a dead symbol one more
+/// scan costs far less than deleting a live one. Empty names are ignored.
 ///
 /// # Memory
 /// - `pattern_ids`: O(N) where N = total symbols
@@ -43,8 +68,10 @@ pub trait LivenessTracker {
 /// - O(N) where N = total bytes in log — each byte processed exactly once per line.
 pub struct SimpleLogTracker {
     automaton: AhoCorasick,
-    pattern_ids: Vec<u64>,
+    /// Symbol ids per pattern; one name can belong to several symbols.
+    pattern_ids: Vec<Vec<u64>>,
     alive: HashSet<u64>,
+    word_boundary: bool,
 }
 
 impl SimpleLogTracker {
@@ -56,20 +83,49 @@ impl SimpleLogTracker {
     /// let tracker = SimpleLogTracker::new(vec![
     ///     (1, "module.foo".into()),
     ///     (2, "module.bar".into()),
-    /// ]);
+    /// ])?;
     /// assert_eq!(tracker.alive_count(), 0);
+    /// # Ok::<(), reaper::ReaperError>(())
     /// ```
-    pub fn new(symbols: impl IntoIterator<Item = (u64, String)>) -> Self {
-        let pairs: Vec<(u64, String)> = symbols.into_iter().collect();
-        let pattern_ids: Vec<u64> = pairs.iter().map(|(id, _)| *id).collect();
-        let patterns: Vec<&str> = pairs.iter().map(|(_, name)| name.as_str()).collect();
-        let automaton =
-            AhoCorasick::new(&patterns).expect("Failed to build Aho-Corasick automaton");
-        Self {
-            automaton,
+    ///
+    /// # Errors
+    /// `Matcher` when the automaton cannot be built (pattern set too large).
+    pub fn new(symbols: impl IntoIterator<Item = (u64, String)>) -> Result<Self, ReaperError> {
+        Self::build(symbols, true)
+    }
+
+    /// Same as [`SimpleLogTracker::new`], but any substring occurrence counts
+    /// as a liveness signal.
+    pub fn new_substring(
+        symbols: impl IntoIterator<Item = (u64, String)>,
+    ) -> Result<Self, ReaperError> {
+        Self::build(symbols, false)
+    }
+
+    fn build(
+        symbols: impl IntoIterator<Item = (u64, String)>,
+        word_boundary: bool,
+    ) -> Result<Self, ReaperError> {
+        let mut index: HashMap<String, usize> = HashMap::new();
+        let mut patterns: Vec<String> = Vec::new();
+        let mut pattern_ids: Vec<Vec<u64>> = Vec::new();
+        for (id, name) in symbols {
+            if name.is_empty() {
+                continue;
+            }
+            let pattern = *index.entry(name).or_insert_with_key(|name| {
+                patterns.push(name.clone());
+                pattern_ids.push(Vec::new());
+                patterns.len() - 1
+            });
+            pattern_ids[pattern].push(id);
+        }
+        Ok(Self {
+            automaton: AhoCorasick::new(&patterns)?,
             pattern_ids,
             alive: HashSet::new(),
-        }
+            word_boundary,
+        })
     }
 
     /// Returns the set of alive symbol IDs.
@@ -91,10 +147,19 @@ impl LivenessTracker for SimpleLogTracker {
 
         for line in reader.lines() {
             let line = line?;
-            for mat in self.automaton.find_iter(&line) {
-                let id = self.pattern_ids[mat.pattern().as_usize()];
-                if self.alive.insert(id) {
-                    signal_count += 1;
+            if self.word_boundary {
+                // Overlapping search: a rejected `foo` must not hide `foo.bar`.
+                for mat in self.automaton.find_overlapping_iter(&line) {
+                    if !is_word_match(line.as_bytes(), mat.start(), mat.end()) {
+                        continue;
+                    }
+                    let ids = &self.pattern_ids[mat.pattern().as_usize()];
+                    signal_count += mark_alive(&mut self.alive, ids);
+                }
+            } else {
+                for mat in self.automaton.find_iter(&line) {
+                    let ids = &self.pattern_ids[mat.pattern().as_usize()];
+                    signal_count += mark_alive(&mut self.alive, ids);
                 }
             }
         }
@@ -103,6 +168,11 @@ impl LivenessTracker for SimpleLogTracker {
     }
 }
 
+/// Adds `ids` to `alive`; returns how many were not there yet.
+fn mark_alive(alive: &mut HashSet<u64>, ids: &[u64]) -> u64 {
+    ids.iter().filter(|&&id| alive.insert(id)).count() as u64
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -113,7 +183,7 @@ mod tests {
         let tmp = std::env::temp_dir().join("test_log_empty.txt");
         fs::write(&tmp, "").ok();
 
-        let mut tracker = SimpleLogTracker::new(vec![(1, "foo".into())]);
+        let mut tracker = SimpleLogTracker::new(vec![(1, "foo".into())]).unwrap();
         let signals = tracker.ingest_log(&tmp).unwrap();
 
         assert_eq!(signals, 0);
@@ -127,7 +197,7 @@ mod tests {
         let tmp = std::env::temp_dir().join("test_log_single.txt");
         fs::write(&tmp, "INFO: module.foo called\n").ok();
 
-        let mut tracker = SimpleLogTracker::new(vec![(1, "module.foo".into())]);
+        let mut tracker = SimpleLogTracker::new(vec![(1, "module.foo".into())]).unwrap();
         let signals = tracker.ingest_log(&tmp).unwrap();
 
         assert_eq!(signals, 1);
@@ -142,7 +212,7 @@ mod tests {
         let tmp = std::env::temp_dir().join("test_log_nomatch.txt");
         fs::write(&tmp, "INFO: something else happened\n").ok();
 
-        let mut tracker = SimpleLogTracker::new(vec![(1, "module.foo".into())]);
+        let mut tracker = SimpleLogTracker::new(vec![(1, "module.foo".into())]).unwrap();
         let signals = tracker.ingest_log(&tmp).unwrap();
 
         assert_eq!(signals, 0);
@@ -156,7 +226,7 @@ mod tests {
         let tmp = std::env::temp_dir().join("test_log_dup.txt");
         fs::write(&tmp, "module.foo\nmodule.foo\nmodule.foo\n").ok();
 
-        let mut tracker = SimpleLogTracker::new(vec![(1, "module.foo".into())]);
+        let mut tracker = SimpleLogTracker::new(vec![(1, "module.foo".into())]).unwrap();
         let signals = tracker.ingest_log(&tmp).unwrap();
 
         assert_eq!(signals, 1); // Only counts the first time
@@ -174,7 +244,8 @@ mod tests {
             (1, "module.foo".into()),
             (2, "module.bar".into()),
             (3, "module.baz".into()),
-        ]);
+        ])
+        .unwrap();
         let signals = tracker.ingest_log(&tmp).unwrap();
 
         assert_eq!(signals, 2);
@@ -185,4 +256,95 @@ mod tests {
 
         fs::remove_file(tmp).ok();
     }
+
+    #[test]
+    fn test_shared_name_marks_every_symbol_alive() {
+        let tmp = std::env::temp_dir().join("test_log_shared_name.txt");
+        fs::write(&tmp, "INFO: save called\n").ok();
+        // `save` in `models/user.py` and in `models/order.py`.
+        let symbols = || vec![(1, "save".into()), (2, "save".into()), (3, "load".into())];
+
+        let mut tracker = SimpleLogTracker::new(symbols()).unwrap();
+        assert_eq!(tracker.ingest_log(&tmp).unwrap(), 2);
+        assert_eq!(tracker.alive_set(), &HashSet::from([1, 2]));
+
+        let mut substring = SimpleLogTracker::new_substring(symbols()).unwrap();
+        assert_eq!(substring.ingest_log(&tmp).unwrap(), 2);
+        assert_eq!(substring.alive_set(), &HashSet::from([1, 2]));
+
+        fs::remove_file(tmp).ok();
+    }
+
+    #[test]
+    fn test_empty_names_are_ignored() {
+        let tmp = std::env::temp_dir().join("test_log_empty_names.txt");
+        fs::write(&tmp, "INFO: foo called\n").ok();
+
+        let mut none = SimpleLogTracker::new(Vec::new()).unwrap();
+        assert_eq!(none.ingest_log(&tmp).unwrap(), 0);
+
+        let mut tracker =
+            SimpleLogTracker::new(vec![(1, String::new()), (2, "foo".into())]).unwrap();
+        assert_eq!(tracker.ingest_log(&tmp).unwrap(), 1);
+        assert_eq!(tracker.alive_set(), &HashSet::from([2]));
+
+        let mut only_empty = SimpleLogTracker::new_substring(vec![(1, String::new())]).unwrap();
+        assert_eq!(only_empty.ingest_log(&tmp).unwrap(), 0);
+
+        fs::remove_file(tmp).ok();
+    }
+
+    #[test]
+    fn test_word_boundary_rejects_longer_identifier() {
+        let tmp = std::env::temp_dir().join("test_log_boundary_run.txt");
+        fs::write(&tmp, "INFO: running migrations\n").ok();
+
+        let mut tracker = SimpleLogTracker::new(vec![(1, "run".into())]).unwrap();
+        assert_eq!(tracker.ingest_log(&tmp).unwrap(), 0);
+
+        let mut substring = SimpleLogTracker::new_substring(vec![(1, "run".into())]).unwrap();
+        assert_eq!(substring.ingest_log(&tmp).unwrap(), 1);
+
+        fs::write(&tmp, "INFO: run finished\n").ok();
+        assert_eq!(tracker.ingest_log(&tmp).unwrap(), 1);
+
+        fs::remove_file(tmp).ok();
+    }
+
+    #[test]
+    fn test_word_boundary_line_edges_and_punctuation() {
+        let tmp = std::env::temp_dir().join("test_log_boundary_edges.txt");
+        fs::write(&tmp, "foo.bar\ncalled foo.baz\nx = foo.qux(1)\nfoo.barn\n").ok();
+
+        let mut tracker = SimpleLogTracker::new(vec![
+            (1, "foo.bar".into()),
+            (2, "foo.baz".into()),
+            (3, "foo.qux".into()),
+            (4, "foo.bar.inner".into()),
+        ])
+        .unwrap();
+        assert_eq!(tracker.ingest_log(&tmp).unwrap(), 3);
+        assert!(tracker.alive_set().contains(&1)); // line start and end
+        assert!(tracker.alive_set().contains(&2)); // line end
+        assert!(tracker.alive_set().contains(&3)); // followed by `(`
+        assert!(!tracker.alive_set().contains(&4));
+
+        fs::remove_file(tmp).ok();
+    }
+
+    #[test]
+    fn test_word_boundary_prefix_pattern_does_not_hide_longer() {
+        let tmp = std::env::temp_dir().join("test_log_boundary_prefix.txt");
+        fs::write(&tmp, "enter module.foo_helper\n").ok();
+
+        let mut tracker = SimpleLogTracker::new(vec![
+            (1, "module.foo".into()),
+            (2, "module.foo_helper".into()),
+        ])
+        .unwrap();
+        assert_eq!(tracker.ingest_log(&tmp).unwrap(), 1);
+        assert!(tracker.alive_set().contains(&2));
+
+        fs::remove_file(tmp).ok();
+    }
 }
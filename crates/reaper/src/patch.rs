@@ -0,0 +1,714 @@
+//! Review patches: the edits a [`SafeDeleter`](crate::SafeDeleter) would make,
+//! collected in memory and rendered as a unified diff.
+//!
+//! A [`Patch`] reads each file once, applies deletions and replacements to an
+//! in-memory copy with the same splices as `SafeDeleter`
+//! ([`delete_ranges`], [`replace_ranges`]), and never writes. [`Patch::render`]
+//! produces a `git apply`-compatible diff: paths relative to the project root
+//! under `a/` and `b/`, three lines of context, and hunk headers counted the
+//! way git counts them. Removed files become `deleted file` entries.
+//!
+//! Lines are compared as bytes in the file's own encoding, so a Latin-1 file
+//! yields a Latin-1 diff that applies to it byte for byte.
+
+use crate::safe_delete::{delete_ranges, replace_ranges};
+use crate::{DeletionOutcome, DeletionTarget, ReaperError, ReplacementTarget};
+use common::source::Encoding;
+use std::collections::btree_map::{BTreeMap, Entry};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Lines of unchanged context around each hunk.
+const CONTEXT: usize = 3;
+
+/// In-memory edits to a project, rendered as a unified diff.
+pub struct Patch {
+    root: PathBuf,
+    /// Keyed by path relative to `root`, forward slashes.
+    files: BTreeMap<String, FileChange>,
+}
+
+/// One touched file: its bytes on disk and the edited, decoded text.
+struct FileChange {
+    encoding: Encoding,
+    original: Vec<u8>,
+    content: Vec<u8>,
+    /// Removed whole; `content` is ignored.
+    deleted: bool,
+    /// Git file mode, for `deleted file mode`.
+    mode: u32,
+}
+
+impl Patch {
+    /// Starts an empty patch for the project at `project_root`.
+    pub fn new(project_root: &Path) -> Result<Self, ReaperError> {
+        Ok(Self {
+            root: dunce::canonicalize(project_root)?,
+            files: BTreeMap::new(),
+        })
+    }
+
+    /// Excises `targets` from the in-memory copy of `file_path`, as
+    /// [`SafeDeleter::delete_symbols`](crate::SafeDeleter::delete_symbols)
+    /// would on disk.
+    pub fn delete_symbols(
+        &mut self,
+        file_path: &Path,
+        targets: &mut [DeletionTarget],
+    ) -> Result<Vec<DeletionOutcome>, ReaperError> {
+        if targets.is_empty() {
+            return Ok(Vec::new());
+        }
+        self.edit(file_path, |content| delete_ranges(content, targets))
+    }
+
+    /// Replaces `targets` in the in-memory copy of `file_path`, as
+    /// [`SafeDeleter::replace_symbols`](crate::SafeDeleter::replace_symbols)
+    /// would on disk.
+    pub fn replace_symbols(
+        &mut self,
+        file_path: &Path,
+        targets: &mut [ReplacementTarget],
+    ) -> Result<usize, ReaperError> {
+        if targets.is_empty() {
+            return Ok(0);
+        }
+        self.edit(file_path, |content| replace_ranges(content, targets))
+    }
+
+    /// Hands the decoded in-memory copy of `file_path` to `edit`. The file is
+    /// read on first touch; later edits see the earlier ones.
+    pub fn edit<T>(
+        &mut self,
+        file_path: &Path,
+        edit: impl FnOnce(&mut Vec<u8>) -> T,
+    ) -> Result<T, ReaperError> {
+        let change = self.touch(file_path)?;
+        Ok(edit(&mut change.content))
+    }
+
+    /// Removes `file_path` whole.
+    pub fn delete_file(&mut self, file_path: &Path) -> Result<(), ReaperError> {
+        self.touch(file_path)?.deleted = true;
+        Ok(())
+    }
+
+    /// Paths (relative to the project root) the patch changes.
+    pub fn changed_files(&self) -> Vec<&str> {
+        self.files
+            .iter()
+            .filter(|(_, change)| change.is_changed())
+            .map(|(rel, _)| rel.as_str())
+            .collect()
+    }
+
+    /// `true` when no file differs from disk.
+    pub fn is_empty(&self) -> bool {
+        self.changed_files().is_empty()
+    }
+
+    /// The unified diff of every changed file, in path order.
+    ///
+    /// # Errors
+    /// `Source` when edited text cannot be encoded back to its file's encoding.
+    pub fn render(&self) -> Result<Vec<u8>, ReaperError> {
+        let mut out = Vec::new();
+        for (rel, change) in &self.files {
+            if change.deleted {
+                write_deletion(&mut out, rel, &change.original, change.mode)?;
+                continue;
+            }
+            let new = change.encoding.encode(&change.content)?;
+            if *new != change.original[..] {
+                write_file_diff(&mut out, rel, &change.original, &new)?;
+            }
+        }
+        Ok(out)
+    }
+
+    /// The entry for `file_path`, reading the file on first touch.
+    fn touch(&mut self, file_path: &Path) -> Result<&mut FileChange, ReaperError> {
+        let absolute = dunce::canonicalize(file_path)?;
+        let rel = absolute
+            .strip_prefix(&self.root)
+            .map_err(|_| ReaperError::OutsideRoot {
+                path: absolute.clone(),
+            })?
+            .to_string_lossy()
+            .replace('\\', "/");
+        match self.files.entry(rel) {
+            Entry::Occupied(entry) => Ok(entry.into_mut()),
+            Entry::Vacant(entry) => {
+                let source = common::source::read(&absolute)?;
+                Ok(entry.insert(FileChange {
+                    encoding: source.encoding(),
+                    original: std::fs::read(&absolute)?,
+                    content: source.into_vec(),
+                    deleted: false,
+                    mode: git_mode(&absolute)?,
+                }))
+            }
+        }
+    }
+}
+
+impl FileChange {
+    /// `true` when the file is deleted or its edited text differs from disk.
+    fn is_changed(&self) -> bool {
+        self.deleted
+            || self
+                .encoding
+                .encode(&self.content)
+                .map_or(true, |new| *new != self.original[..])
+    }
+}
+
+/// `100755` for an executable file, `100644` otherwise.
+fn git_mode(path: &Path) -> std::io::Result<u32> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if std::fs::metadata(path)?.permissions().mode() & 0o111 != 0 {
+            return Ok(0o100755);
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+    Ok(0o100644)
+}
+
+// ---------------------------------------------------------------------------
+// Unified diff
+// ---------------------------------------------------------------------------
+
+/// One step of a line edit script.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// Lines of `text`, each with its `\n` (the last may lack one).
+fn lines(text: &[u8]) -> Vec<&[u8]> {
+    text.split_inclusive(|&b| b == b'\n').collect()
+}
+
+fn write_file_diff(
+    out: &mut Vec<u8>,
+    rel: &str,
+    old: &[u8],
+    new: &[u8],
+) -> Result<(), ReaperError> {
+    writeln!(out, "diff --git a/{rel} b/{rel}")?;
+    writeln!(out, "--- a/{rel}")?;
+    writeln!(out, "+++ b/{rel}")?;
+    let (a, b) = (lines(old), lines(new));
+    write_hunks(out, &a, &b, &edit_script(&a, &b))?;
+    Ok(())
+}
+
+fn write_deletion(out: &mut Vec<u8>, rel: &str, old: &[u8], mode: u32) -> Result<(), ReaperError> {
+    writeln!(out, "diff --git a/{rel} b/{rel}")?;
+    writeln!(out, "deleted file mode {mode:o}")?;
+    if old.is_empty() {
+        return Ok(());
+    }
+    writeln!(out, "--- a/{rel}")?;
+    writeln!(out, "+++ /dev/null")?;
+    let a = lines(old);
+    write_hunks(out, &a, &[], &vec![Op::Delete; a.len()])?;
+    Ok(())
+}
+
+/// Shortest edit script turning `a` into `b` (Myers' O(ND) algorithm).
+fn edit_script(a: &[&[u8]], b: &[&[u8]]) -> Vec<Op> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let max = n + m;
+    let offset = max + 1;
+    let mut v = vec![0isize; 2 * max as usize + 3];
+    // `trace[d]` holds the furthest x per diagonal k in -d..=d after round d.
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+    'search: for d in 0..=max {
+        for k in (-d..=d).step_by(2) {
+            let i = (k + offset) as usize;
+            let mut x = if k == -d || (k != d && v[i - 1] < v[i + 1]) {
+                v[i + 1]
+            } else {
+                v[i - 1] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[i] = x;
+            if x >= n && y >= m {
+                trace.push(v[(offset - d) as usize..=(offset + d) as usize].to_vec());
+                break 'search;
+            }
+        }
+        trace.push(v[(offset - d) as usize..=(offset + d) as usize].to_vec());
+    }
+
+    // Walk the trace back from (n, m), emitting the script in reverse.
+    let mut ops = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for d in (1..trace.len() as isize).rev() {
+        let prev = &trace[d as usize - 1];
+        let at = |k: isize| prev[(k + d - 1) as usize];
+        let k = x - y;
+        let prev_k = if k == -d || (k != d && at(k - 1) < at(k + 1)) {
+            k + 1
+        } else {
+            k - 1
+        };
+        let prev_x = at(prev_k);
+        let prev_y = prev_x - prev_k;
+        while x > prev_x && y > prev_y {
+            ops.push(Op::Equal);
+            x -= 1;
+            y -= 1;
+        }
+        if x == prev_x {
+            ops.push(Op::Insert);
+            y -= 1;
+        } else {
+            ops.push(Op::Delete);
+            x -= 1;
+        }
+    }
+    ops.extend(std::iter::repeat_n(Op::Equal, x as usize));
+    ops.reverse();
+    ops
+}
+
+/// Writes the hunks of `ops`, each with up to [`CONTEXT`] lines around its
+/// changes; changes closer than twice that share a hunk.
+fn write_hunks(out: &mut Vec<u8>, a: &[&[u8]], b: &[&[u8]], ops: &[Op]) -> std::io::Result<()> {
+    // Old and new line index before each op.
+    let mut positions = Vec::with_capacity(ops.len() + 1);
+    let (mut i, mut j) = (0usize, 0usize);
+    for op in ops {
+        positions.push((i, j));
+        match op {
+            Op::Equal => {
+                i += 1;
+                j += 1;
+            }
+            Op::Delete => i += 1,
+            Op::Insert => j += 1,
+        }
+    }
+    positions.push((i, j));
+
+    let mut next = 0usize;
+    while let Some(first) = ops[next..].iter().position(|op| *op != Op::Equal) {
+        let first = next + first;
+        let start = first.saturating_sub(CONTEXT).max(next);
+        // Extend over changes separated by at most 2 * CONTEXT equal lines.
+        let mut end = first;
+        loop {
+            while end < ops.len() && ops[end] != Op::Equal {
+                end += 1;
+            }
+            let equal = ops[end..].iter().take_while(|op| **op == Op::Equal).count();
+            if end + equal == ops.len() || equal > 2 * CONTEXT {
+                end += equal.min(CONTEXT);
+                break;
+            }
+            end += equal;
+        }
+
+        let (old_start, new_start) = positions[start];
+        let (old_end, new_end) = positions[end];
+        writeln!(
+            out,
+            "@@ -{} +{} @@",
+            hunk_range(old_start, old_end - old_start),
+            hunk_range(new_start, new_end - new_start)
+        )?;
+        for (op, &(i, j)) in ops[start..end].iter().zip(&positions[start..end]) {
+            let (marker, line) = match op {
+                Op::Equal => (b' ', a[i]),
+                Op::Delete => (b'-', a[i]),
+                Op::Insert => (b'+', b[j]),
+            };
+            out.push(marker);
+            out.extend_from_slice(line);
+            if !line.ends_with(b"\n") {
+                out.extend_from_slice(b"\n\\ No newline at end of file\n");
+            }
+        }
+        next = end;
+    }
+    Ok(())
+}
+
+/// `start,count` of a hunk header: 1-based, the count omitted when it is 1,
+/// and the line before the hunk as start when the range is empty.
+fn hunk_range(before: usize, count: usize) -> String {
+    match count {
+        0 => format!("{},0", before),
+        1 => format!("{}", before + 1),
+        _ => format!("{},{}", before + 1, count),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SafeDeleter;
+    use std::collections::HashMap;
+    use std::fs;
+
+    fn tmp_dir(name: &str) -> PathBuf {
+        let d = std::env::temp_dir().join(name);
+        fs::remove_dir_all(&d).ok();
+        fs::create_dir_all(&d).unwrap();
+        d
+    }
+
+    /// Applies `patch` to `files` the way `git apply` does: every context and
+    /// removed line must match at the position the hunk header names, and
+    /// the counts in the header must match the hunk body.
+    fn apply(
+        patch: &[u8],
+        files: &HashMap<String, Vec<u8>>,
+    ) -> Result<HashMap<String, Option<Vec<u8>>>, String> {
+        let mut result = HashMap::new();
+        let mut rest = lines(patch).into_iter().peekable();
+        while let Some(line) = rest.next() {
+            let header = std::str::from_utf8(line).map_err(|e| e.to_string())?;
+            let rel = header
+                .strip_prefix("diff --git a/")
+                .and_then(|h| h.trim_end().split(" b/").next())
+                .ok_or_else(|| format!("expected a file header, got {header:?}"))?
+                .to_string();
+            let original = files
+                .get(&rel)
+                .ok_or_else(|| format!("{rel}: not in the tree"))?;
+            let mut deleted = false;
+            let mut old = lines(original);
+            let mut new: Vec<Vec<u8>> = Vec::new();
+            let mut copied = 0usize;
+            while let Some(line) = rest.next_if(|l| !l.starts_with(b"diff --git ")) {
+                let line = std::str::from_utf8(line).map_err(|e| e.to_string())?;
+                if line.starts_with("deleted file mode ") {
+                    deleted = true;
+                } else if let Some(path) = line.strip_prefix("--- ") {
+                    assert_eq!(path.trim_end(), format!("a/{rel}"));
+                } else if let Some(path) = line.strip_prefix("+++ ") {
+                    let expected = if deleted {
+                        "/dev/null".to_string()
+                    } else {
+                        format!("b/{rel}")
+                    };
+                    assert_eq!(path.trim_end(), expected);
+                } else if let Some(ranges) = line.strip_prefix("@@ -") {
+                    let ranges = ranges.trim_end().trim_end_matches(" @@");
+                    let (old_range, new_range) = ranges
+                        .split_once(" +")
+                        .ok_or_else(|| format!("bad hunk header {line:?}"))?;
+                    let parse = |r: &str| -> (usize, usize) {
+                        match r.split_once(',') {
+                            Some((s, c)) => (s.parse().unwrap(), c.parse().unwrap()),
+                            None => (r.parse().unwrap(), 1),
+                        }
+                    };
+                    let (old_start, old_count) = parse(old_range);
+                    let (_, new_count) = parse(new_range);
+                    let at = if old_count == 0 {
+                        old_start
+                    } else {
+                        old_start - 1
+                    };
+                    if at < copied {
+                        return Err(format!("{rel}: hunks out of order"));
+                    }
+                    new.extend(old[copied..at].iter().map(|l| l.to_vec()));
+                    let mut cursor = at;
+                    let (mut seen_old, mut seen_new) = (0, 0);
+                    while seen_old < old_count || seen_new < new_count {
+                        let body = rest.next().ok_or(format!("{rel}: truncated hunk"))?;
+                        let mut text = body[1..].to_vec();
+                        if rest.next_if(|l| l.starts_with(b"\\ ")).is_some() {
+                            text.pop();
+                        }
+                        match body[0] {
+                            b' ' | b'-' => {
+                                if old.get(cursor).copied() != Some(&text[..]) {
+                                    return Err(format!(
+                                        "{rel}: hunk does not apply at line {}",
+                                        cursor + 1
+                                    ));
+                                }
+                                cursor += 1;
+                                seen_old += 1;
+                                if body[0] == b' ' {
+                                    new.push(text);
+                                    seen_new += 1;
+                                }
+                            }
+                            b'+' => {
+                                new.push(text);
+                                seen_new += 1;
+                            }
+                            other => return Err(format!("{rel}: bad line marker {other}")),
+                        }
+                    }
+                    if seen_old != old_count || seen_new != new_count {
+                        return Err(format!("{rel}: hunk counts do not match its body"));
+                    }
+                    copied = cursor;
+                } else {
+                    return Err(format!("{rel}: unexpected line {line:?}"));
+                }
+            }
+            if deleted {
+                if copied != old.len() {
+                    return Err(format!("{rel}: deletion leaves lines behind"));
+                }
+                result.insert(rel, None);
+            } else {
+                new.extend(old.drain(copied..).map(|l| l.to_vec()));
+                result.insert(rel, Some(new.concat()));
+            }
+        }
+        Ok(result)
+    }
+
+    /// The unified diff of `old` → `new` for one file named `f.py`.
+    fn diff(old: &str, new: &str) -> String {
+        let mut out = Vec::new();
+        write_file_diff(&mut out, "f.py", old.as_bytes(), new.as_bytes()).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    fn round_trip(old: &str, new: &str) {
+        let files = HashMap::from([("f.py".to_string(), old.as_bytes().to_vec())]);
+        let applied = apply(diff(old, new).as_bytes(), &files).unwrap();
+        assert_eq!(
+            applied["f.py"].as_deref(),
+            Some(new.as_bytes()),
+            "{old:?} -> {new:?}"
+        );
+    }
+
+    #[test]
+    fn test_hunk_headers_match_git() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\n";
+        let new = "a\nb\nc\nd\nE\nf\ng\nh\ni\nj\n";
+        assert_eq!(
+            diff(old, new),
+            "diff --git a/f.py b/f.py\n--- a/f.py\n+++ b/f.py\n\
+             @@ -2,7 +2,7 @@\n b\n c\n d\n-e\n+E\n f\n g\n h\n"
+        );
+
+        // Pure insertion after line 1, and one-line ranges without a count.
+        assert_eq!(
+            diff("a\n", "a\nb\n"),
+            "diff --git a/f.py b/f.py\n--- a/f.py\n+++ b/f.py\n@@ -1 +1,2 @@\n a\n+b\n"
+        );
+        assert_eq!(
+            diff("a\nb\n", "b\n"),
+            "diff --git a/f.py b/f.py\n--- a/f.py\n+++ b/f.py\n@@ -1,2 +1 @@\n-a\n b\n"
+        );
+        assert_eq!(
+            diff("", "x\n"),
+            "diff --git a/f.py b/f.py\n--- a/f.py\n+++ b/f.py\n@@ -0,0 +1 @@\n+x\n"
+        );
+    }
+
+    #[test]
+    fn test_distant_changes_get_separate_hunks() {
+        let old: String = (1..=20).map(|i| format!("line{i}\n")).collect();
+        let new = old
+            .replace("line2\n", "two\n")
+            .replace("line18\n", "eighteen\n");
+        let patch = diff(&old, &new);
+        assert_eq!(patch.matches("@@ -").count(), 2);
+        assert!(patch.contains("@@ -1,5 +1,5 @@\n"));
+        assert!(patch.contains("@@ -15,6 +15,6 @@\n"));
+        round_trip(&old, &new);
+
+        // Six equal lines or fewer between changes: one hunk.
+        let near = old.replace("line9\n", "nine\n").replace("line16\n", "x\n");
+        assert_eq!(diff(&old, &near).matches("@@ -").count(), 1);
+        round_trip(&old, &near);
+    }
+
+    #[test]
+    fn test_missing_final_newline_is_marked() {
+        let patch = diff("a\nb", "a\nc");
+        assert!(
+            patch.ends_with("-b\n\\ No newline at end of file\n+c\n\\ No newline at end of file\n")
+        );
+        round_trip("a\nb", "a\nc");
+        round_trip("a\nb", "a\nb\n");
+        round_trip("a\nb\n", "a\nb");
+    }
+
+    #[test]
+    fn test_diff_round_trips_on_generated_edits() {
+        // Deterministic pseudo-random edits over a small alphabet, so lines
+        // repeat and the script has to pick among equal-cost alignments.
+        let mut seed = 0x2545_f491_u64;
+        let mut next = move |bound: u64| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            seed % bound
+        };
+        for _ in 0..200 {
+            let old: Vec<String> = (0..next(30)).map(|_| format!("l{}\n", next(6))).collect();
+            let mut new = old.clone();
+            for _ in 0..next(6) {
+                let at = next(new.len() as u64 + 1) as usize;
+                match next(3) {
+                    0 if at < new.len() => {
+                        new.remove(at);
+                    }
+                    1 if at < new.len() => new[at] = format!("x{}\n", next(6)),
+                    _ => new.insert(at, format!("n{}\n", next(6))),
+                }
+            }
+            round_trip(&old.concat(), &new.concat());
+        }
+    }
+
+    #[test]
+    fn test_patch_matches_safe_deleter_and_leaves_files_alone() {
+        let tmp = tmp_dir("test_patch_matches_deleter");
+        fs::create_dir_all(tmp.join("pkg")).unwrap();
+        let views = "import os\n\n\ndef live():\n    return 1\n\n\ndef dead():\n    return 2\n\n\nclass Box:\n    def gone(self):\n        pass\n";
+        let util = "def helper(x):\n    return x * 2\n";
+        fs::write(tmp.join("pkg/views.py"), views).unwrap();
+        fs::write(tmp.join("pkg/util.py"), util).unwrap();
+        fs::write(tmp.join("stale.py"), "def old():\n    pass\n").unwrap();
+
+        let span = |text: &str| {
+            let start = views.find(text).unwrap();
+            (start as u32, (start + text.len()) as u32)
+        };
+        let (dead_start, dead_end) = span("def dead():\n    return 2");
+        let (gone_start, gone_end) = span("def gone(self):\n        pass");
+        let deletions = || {
+            vec![
+                DeletionTarget {
+                    qualified_name: "dead".into(),
+                    start_byte: dead_start,
+                    end_byte: dead_end,
+                },
+                DeletionTarget {
+                    qualified_name: "Box.gone".into(),
+                    start_byte: gone_start,
+                    end_byte: gone_end,
+                },
+            ]
+        };
+        let replacements = || {
+            vec![ReplacementTarget {
+                qualified_name: "helper".into(),
+                start_byte: 0,
+                end_byte: util.len() as u32,
+                replacement: "def helper(x):\n    return _impl(x)\n".into(),
+            }]
+        };
+
+        let mut patch = Patch::new(&tmp).unwrap();
+        let outcomes = patch
+            .delete_symbols(&tmp.join("pkg/views.py"), &mut deletions())
+            .unwrap();
+        assert_eq!(outcomes.len(), 2);
+        patch
+            .replace_symbols(&tmp.join("pkg/util.py"), &mut replacements())
+            .unwrap();
+        patch.delete_file(&tmp.join("stale.py")).unwrap();
+        assert_eq!(
+            patch.changed_files(),
+            vec!["pkg/util.py", "pkg/views.py", "stale.py"]
+        );
+        let rendered = patch.render().unwrap();
+        let text = String::from_utf8(rendered.clone()).unwrap();
+        assert!(text.contains("--- a/pkg/views.py\n+++ b/pkg/views.py\n"));
+        assert!(text.contains("deleted file mode 100644\n--- a/stale.py\n+++ /dev/null\n"));
+
+        // Nothing was written.
+        assert_eq!(fs::read_to_string(tmp.join("pkg/views.py")).unwrap(), views);
+        let originals: HashMap<String, Vec<u8>> = ["pkg/views.py", "pkg/util.py", "stale.py"]
+            .iter()
+            .map(|rel| (rel.to_string(), fs::read(tmp.join(rel)).unwrap()))
+            .collect();
+        let applied = apply(&rendered, &originals).unwrap();
+
+        // The same edits on disk give what the patch applies to.
+        let mut deleter = SafeDeleter::new(&tmp).unwrap();
+        deleter
+            .delete_symbols(&tmp.join("pkg/views.py"), &mut deletions())
+            .unwrap();
+        deleter
+            .replace_symbols(&tmp.join("pkg/util.py"), &mut replacements())
+            .unwrap();
+        for rel in ["pkg/views.py", "pkg/util.py"] {
+            assert_eq!(
+                applied[rel].as_deref(),
+                Some(&fs::read(tmp.join(rel)).unwrap()[..]),
+                "{rel}"
+            );
+        }
+        assert_eq!(applied["stale.py"], None);
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_latin1_file_diffs_in_its_own_encoding() {
+        let tmp = tmp_dir("test_patch_latin1");
+        let original = b"# caf\xe9\ndef dead():\n    pass\nx = '\xe9'\n".to_vec();
+        fs::write(tmp.join("legacy.py"), &original).unwrap();
+
+        let mut patch = Patch::new(&tmp).unwrap();
+        patch.edit(&tmp.join("legacy.py"), |_| ()).unwrap();
+        assert!(patch.is_empty());
+        patch
+            .edit(&tmp.join("legacy.py"), |content| {
+                let text = String::from_utf8(content.clone()).unwrap();
+                *content = text.replace("def dead():\n    pass\n", "").into_bytes();
+            })
+            .unwrap();
+        let rendered = patch.render().unwrap();
+        assert!(rendered
+            .windows(b" # caf\xe9\n".len())
+            .any(|w| w == b" # caf\xe9\n"));
+
+        let files = HashMap::from([("legacy.py".to_string(), original)]);
+        let applied = apply(&rendered, &files).unwrap();
+        assert_eq!(
+            applied["legacy.py"].as_deref(),
+            Some(&b"# caf\xe9\nx = '\xe9'\n"[..])
+        );
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_unchanged_and_outside_files() {
+        let tmp = tmp_dir("test_patch_unchanged");
+        fs::write(tmp.join("same.py"), "x = 1\n").unwrap();
+        let mut patch = Patch::new(&tmp.join(".")).unwrap();
+        patch.edit(&tmp.join("same.py"), |_| ()).unwrap();
+        assert!(patch.is_empty());
+        assert!(patch.render().unwrap().is_empty());
+
+        let outside = std::env::temp_dir().join("test_patch_outside.py");
+        fs::write(&outside, "y = 2\n").unwrap();
+        assert!(matches!(
+            patch.delete_file(&outside),
+            Err(ReaperError::OutsideRoot { .. })
+        ));
+
+        fs::remove_file(outside).ok();
+        fs::remove_dir_all(tmp).ok();
+    }
+}
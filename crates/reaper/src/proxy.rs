@@ -0,0 +1,598 @@
+//! Safe Proxy generation for `janitor dedup --apply`.
+//!
+//! A duplicated function keeps its signature, decorators, and docstring; only
+//! its statements are replaced by a call into a shared module-level `_impl`:
+//!
+//! ```python
+//! @cached
+//! def total(rows, *, strict=False) -> int:
+//!     """Sum of rows."""
+//!     return _total_impl(rows, strict=strict)
+//!
+//! def _total_impl(rows, *, strict) -> int:
+//!     ...original statements...
+//! ```
+//!
+//! The signature is read from the tree-sitter AST, so defaults containing
+//! commas, `*args`/`**kwargs`, and the `*` and `/` markers are handled. The
+//! `_impl` drops defaults: the proxy always passes every argument, and a
+//! method's defaults may name class attributes that do not exist at module
+//! level. Methods are proxied with `self`/`cls` passed explicitly.
+//!
+//! Shapes whose behaviour would change once the body moves are refused with
+//! [`ReaperError::Unproxyable`]: nested functions (closures), async generators,
+//! method bodies using zero-argument `super()` or `__private` name mangling, and
+//! re-indented bodies containing multi-line strings.
+
+use crate::ReaperError;
+use tree_sitter::{Node, Parser};
+
+/// How a parameter is bound, which decides how the proxy forwards it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParamKind {
+    /// Positional-or-keyword (or positional-only): forwarded positionally.
+    Positional,
+    /// After `*` or `*args`: forwarded as `name=value`.
+    KeywordOnly,
+    /// `*args`
+    VarArgs,
+    /// `**kwargs`
+    VarKwargs,
+    /// The bare `*` marker.
+    KeywordSeparator,
+    /// The `/` marker.
+    PositionalSeparator,
+}
+
+/// One entry of a function's parameter list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Param {
+    pub kind: ParamKind,
+    /// Empty for the `*` and `/` markers.
+    pub name: String,
+    /// Annotation text, verbatim.
+    pub annotation: Option<String>,
+}
+
+/// A function definition analysed for proxying.
+#[derive(Debug, Clone)]
+pub struct ProxyTarget {
+    pub name: String,
+    pub is_async: bool,
+    pub is_generator: bool,
+    /// Defined directly in a class body.
+    pub is_method: bool,
+    pub params: Vec<Param>,
+    /// `[T]` of a PEP 695 generic function, verbatim.
+    pub type_params: Option<String>,
+    /// Return annotation text, verbatim.
+    pub return_type: Option<String>,
+    /// Byte range of the statements the proxy call replaces (everything after
+    /// the signature and docstring).
+    pub body_start: u32,
+    pub body_end: u32,
+    /// Indentation of the replaced statements; empty for `def f(): return x`.
+    indent: String,
+    /// The replaced statements, indented for module level.
+    impl_body: String,
+}
+
+impl ProxyTarget {
+    /// Analyses the function whose definition (including decorators) starts
+    /// at `start_byte` in `source`.
+    pub fn parse(source: &[u8], start_byte: u32) -> Result<Self, ReaperError> {
+        let mut parser = Parser::new();
+        parser
+            .set_language(&tree_sitter_python::LANGUAGE.into())
+            .map_err(|e| ReaperError::ParseError(e.to_string()))?;
+        let tree = parser
+            .parse(source, None)
+            .ok_or_else(|| ReaperError::ParseError("tree-sitter returned no tree".into()))?;
+        let def = function_at(tree.root_node(), start_byte as usize).ok_or_else(|| {
+            ReaperError::ParseError(format!("no function definition at byte {}", start_byte))
+        })?;
+
+        let text = |n: Node<'_>| String::from_utf8_lossy(&source[n.byte_range()]).into_owned();
+        let name = def
+            .child_by_field_name("name")
+            .map(text)
+            .unwrap_or_default();
+        let refuse = |reason: &str| ReaperError::Unproxyable {
+            name: name.clone(),
+            reason: reason.to_string(),
+        };
+
+        if def.has_error() {
+            return Err(refuse("the definition does not parse cleanly"));
+        }
+        let mut outer = def.parent();
+        while let Some(node) = outer {
+            if node.kind() == "function_definition" {
+                return Err(refuse(
+                    "it is nested in another function and may close over its variables",
+                ));
+            }
+            outer = node.parent();
+        }
+        // `def` (or its decorated wrapper) → block → class.
+        let wrapper = def
+            .parent()
+            .filter(|p| p.kind() == "decorated_definition")
+            .unwrap_or(def);
+        let is_method = wrapper
+            .parent()
+            .and_then(|block| block.parent())
+            .is_some_and(|c| c.kind() == "class_definition");
+
+        let is_async = def.child(0).is_some_and(|c| c.kind() == "async");
+        let body = def
+            .child_by_field_name("body")
+            .ok_or_else(|| refuse("it has no body"))?;
+        let is_generator = contains_yield(body);
+        if is_async && is_generator {
+            return Err(refuse("async generators cannot be forwarded with `await`"));
+        }
+        if is_method {
+            if let Some(reason) = class_bound_usage(body, source) {
+                return Err(refuse(reason));
+            }
+        }
+
+        let params = match def.child_by_field_name("parameters") {
+            Some(p) => parse_params(p, source)
+                .map_err(|kind| refuse(&format!("unsupported parameter form `{}`", kind)))?,
+            None => Vec::new(),
+        };
+
+        // Statements after the docstring.
+        let mut cursor = body.walk();
+        let mut statements: Vec<Node<'_>> = body
+            .named_children(&mut cursor)
+            .filter(|n| n.kind() != "comment")
+            .collect();
+        if statements.first().is_some_and(|s| is_docstring(*s)) {
+            statements.remove(0);
+        }
+        let first = *statements
+            .first()
+            .ok_or_else(|| refuse("its body is only a docstring"))?;
+
+        let line_start = source[..first.start_byte()]
+            .iter()
+            .rposition(|&b| b == b'\n')
+            .map_or(0, |i| i + 1);
+        let lead = &source[line_start..first.start_byte()];
+        let body_end = def.end_byte();
+        let (body_start, indent, impl_body) = if lead.iter().all(|b| *b == b' ' || *b == b'\t') {
+            let indent = String::from_utf8_lossy(lead).into_owned();
+            let region = String::from_utf8_lossy(&source[line_start..body_end]).into_owned();
+            if indent != "    " && statements.iter().any(|s| has_multiline_string(*s)) {
+                return Err(refuse(
+                    "its body must be re-indented but contains a multi-line string",
+                ));
+            }
+            (line_start, indent.clone(), reindent(&region, &indent))
+        } else {
+            // `def f(x): return x`
+            let region = String::from_utf8_lossy(&source[first.start_byte()..body_end]);
+            (first.start_byte(), String::new(), format!("    {}", region))
+        };
+
+        Ok(Self {
+            is_async,
+            is_generator,
+            is_method,
+            params,
+            type_params: def.child_by_field_name("type_parameters").map(text),
+            return_type: def.child_by_field_name("return_type").map(text),
+            body_start: body_start as u32,
+            body_end: body_end as u32,
+            indent,
+            impl_body,
+            name,
+        })
+    }
+
+    /// The module-level `_impl` definition, preceded by a blank line.
+    pub fn impl_definition(&self, impl_name: &str) -> String {
+        let params: Vec<String> = self
+            .params
+            .iter()
+            .map(|p| {
+                let prefix = match p.kind {
+                    ParamKind::KeywordSeparator => return "*".to_string(),
+                    ParamKind::PositionalSeparator => return "/".to_string(),
+                    ParamKind::VarArgs => "*",
+                    ParamKind::VarKwargs => "**",
+                    ParamKind::Positional | ParamKind::KeywordOnly => "",
+                };
+                match &p.annotation {
+                    Some(ann) => format!("{}{}: {}", prefix, p.name, ann),
+                    None => format!("{}{}", prefix, p.name),
+                }
+            })
+            .collect();
+        format!(
+            "\n\n{}def {}{}({}){}:\n{}\n",
+            if self.is_async { "async " } else { "" },
+            impl_name,
+            self.type_params.as_deref().unwrap_or(""),
+            params.join(", "),
+            self.return_type
+                .as_deref()
+                .map(|r| format!(" -> {}", r))
+                .unwrap_or_default(),
+            self.impl_body.trim_end(),
+        )
+    }
+
+    /// The statement that replaces `[body_start, body_end)`: a call to
+    /// `impl_name`, the `_impl` generated from `canonical`.
+    ///
+    /// `self` may name its parameters differently from `canonical` (the
+    /// duplicates are only structurally equal); keyword-only arguments are
+    /// passed under the canonical names.
+    pub fn proxy_body(
+        &self,
+        impl_name: &str,
+        canonical: &ProxyTarget,
+    ) -> Result<String, ReaperError> {
+        let refuse = |reason: String| ReaperError::Unproxyable {
+            name: self.name.clone(),
+            reason,
+        };
+        if self.is_async != canonical.is_async || self.is_generator != canonical.is_generator {
+            return Err(refuse(format!(
+                "it is not the same kind of function (async/generator) as `{}`",
+                canonical.name
+            )));
+        }
+        let shape = |t: &ProxyTarget| t.params.iter().map(|p| p.kind).collect::<Vec<_>>();
+        if shape(self) != shape(canonical) {
+            return Err(refuse(format!(
+                "its parameter list differs in shape from `{}`",
+                canonical.name
+            )));
+        }
+
+        let args: Vec<String> = self
+            .params
+            .iter()
+            .zip(&canonical.params)
+            .filter_map(|(own, canon)| match own.kind {
+                ParamKind::Positional => Some(own.name.clone()),
+                ParamKind::KeywordOnly => Some(format!("{}={}", canon.name, own.name)),
+                ParamKind::VarArgs => Some(format!("*{}", own.name)),
+                ParamKind::VarKwargs => Some(format!("**{}", own.name)),
+                ParamKind::KeywordSeparator | ParamKind::PositionalSeparator => None,
+            })
+            .collect();
+        let call = format!("{}({})", impl_name, args.join(", "));
+        let value = if self.is_async {
+            format!("await {}", call)
+        } else if self.is_generator {
+            format!("(yield from {})", call)
+        } else {
+            call
+        };
+        Ok(format!("{}return {}", self.indent, value))
+    }
+}
+
+/// The `function_definition` whose definition (or decorators) starts at `byte`.
+fn function_at(root: Node<'_>, byte: usize) -> Option<Node<'_>> {
+    let mut node = root.descendant_for_byte_range(byte, byte)?;
+    while node.start_byte() == byte {
+        match node.kind() {
+            "decorated_definition" => {
+                let def = node.child_by_field_name("definition")?;
+                return (def.kind() == "function_definition").then_some(def);
+            }
+            "function_definition" => return Some(node),
+            _ => node = node.parent()?,
+        }
+    }
+    None
+}
+
+/// Parses a `parameters` node; on failure returns the offending node kind.
+fn parse_params(params: Node<'_>, source: &[u8]) -> Result<Vec<Param>, String> {
+    let text = |n: Node<'_>| String::from_utf8_lossy(&source[n.byte_range()]).into_owned();
+    let mut out = Vec::new();
+    let mut keyword_only = false;
+    let mut cursor = params.walk();
+    for child in params.named_children(&mut cursor) {
+        let annotation = child.child_by_field_name("type").map(text);
+        // The node that carries the name: itself, its `name` field, or (for
+        // `typed_parameter`) its first named child.
+        let target = match child.kind() {
+            "default_parameter" | "typed_default_parameter" => child.child_by_field_name("name"),
+            "typed_parameter" => child.named_child(0),
+            "comment" => continue,
+            _ => Some(child),
+        }
+        .ok_or_else(|| child.kind().to_string())?;
+
+        let (kind, name) = match target.kind() {
+            "identifier" if keyword_only => (ParamKind::KeywordOnly, text(target)),
+            "identifier" => (ParamKind::Positional, text(target)),
+            "list_splat_pattern" => {
+                keyword_only = true;
+                let name = target
+                    .named_child(0)
+                    .ok_or_else(|| target.kind().to_string())?;
+                (ParamKind::VarArgs, text(name))
+            }
+            "dictionary_splat_pattern" => {
+                let name = target
+                    .named_child(0)
+                    .ok_or_else(|| target.kind().to_string())?;
+                (ParamKind::VarKwargs, text(name))
+            }
+            "keyword_separator" => {
+                keyword_only = true;
+                (ParamKind::KeywordSeparator, String::new())
+            }
+            "positional_separator" => (ParamKind::PositionalSeparator, String::new()),
+            other => return Err(other.to_string()),
+        };
+        out.push(Param {
+            kind,
+            name,
+            annotation,
+        });
+    }
+    Ok(out)
+}
+
+fn is_docstring(statement: Node<'_>) -> bool {
+    statement.kind() == "expression_statement"
+        && statement.named_child_count() == 1
+        && statement
+            .named_child(0)
+            .is_some_and(|c| c.kind() == "string")
+}
+
+/// Whether `node` yields, ignoring nested functions, lambdas, and classes.
+fn contains_yield(node: Node<'_>) -> bool {
+    if node.kind() == "yield" {
+        return true;
+    }
+    let mut cursor = node.walk();
+    let result = node.named_children(&mut cursor).any(|child| {
+        !matches!(
+            child.kind(),
+            "function_definition" | "lambda" | "class_definition"
+        ) && contains_yield(child)
+    });
+    result
+}
+
+/// Why a method body depends on being compiled inside its class, if it does.
+fn class_bound_usage(node: Node<'_>, source: &[u8]) -> Option<&'static str> {
+    if node.kind() == "call" {
+        let function = node.child_by_field_name("function");
+        let args = node.child_by_field_name("arguments");
+        let is_super = function.is_some_and(|f| &source[f.byte_range()] == b"super");
+        if is_super && args.is_some_and(|a| a.named_child_count() == 0) {
+            return Some("its body calls zero-argument `super()`, which needs the class scope");
+        }
+    }
+    if node.kind() == "identifier" {
+        let name = &source[node.byte_range()];
+        if name == b"__class__" {
+            return Some("its body reads `__class__`, which needs the class scope");
+        }
+        if name.starts_with(b"__") && !name.ends_with(b"__") {
+            return Some("its body uses a `__private` name, which is mangled by the class");
+        }
+    }
+    let mut cursor = node.walk();
+    let result = node
+        .children(&mut cursor)
+        .find_map(|child| class_bound_usage(child, source));
+    result
+}
+
+fn has_multiline_string(node: Node<'_>) -> bool {
+    if node.kind() == "string" && node.start_position().row != node.end_position().row {
+        return true;
+    }
+    let mut cursor = node.walk();
+    let result = node.children(&mut cursor).any(has_multiline_string);
+    result
+}
+
+/// Replaces the `indent` prefix of each line with four spaces. Lines that do
+/// not start with it (blank lines, bracket continuations) are kept as-is.
+fn reindent(region: &str, indent: &str) -> String {
+    region
+        .split_inclusive('\n')
+        .map(|line| match line.strip_prefix(indent) {
+            Some(rest) => format!("    {}", rest),
+            None => line.to_string(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target(src: &str, start: &str) -> Result<ProxyTarget, ReaperError> {
+        let offset = src.find(start).expect("start marker") as u32;
+        ProxyTarget::parse(src.as_bytes(), offset)
+    }
+
+    fn proxy(src: &str, start: &str) -> (String, String) {
+        let t = target(src, start).unwrap();
+        let body = t.proxy_body("_impl", &t).unwrap();
+        (t.impl_definition("_impl"), body)
+    }
+
+    fn reason(result: Result<ProxyTarget, ReaperError>) -> String {
+        match result {
+            Err(ReaperError::Unproxyable { reason, .. }) => reason,
+            other => panic!("expected Unproxyable, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_plain_function() {
+        let src = "def add(a, b):\n    total = a + b\n    return total\n";
+        let (def, body) = proxy(src, "def add");
+        assert_eq!(body, "    return _impl(a, b)");
+        assert_eq!(
+            def,
+            "\n\ndef _impl(a, b):\n    total = a + b\n    return total\n"
+        );
+        let t = target(src, "def add").unwrap();
+        assert_eq!(
+            &src[t.body_start as usize..t.body_end as usize],
+            "    total = a + b\n    return total"
+        );
+    }
+
+    #[test]
+    fn test_defaults_with_commas_are_dropped_from_impl() {
+        let src = "def f(x=[1, 2], y={'a': (3, 4)}, z=g(5, 6)):\n    return x, y, z\n";
+        let (def, body) = proxy(src, "def f");
+        assert_eq!(body, "    return _impl(x, y, z)");
+        assert!(def.starts_with("\n\ndef _impl(x, y, z):\n"));
+    }
+
+    #[test]
+    fn test_star_args_kwargs_and_keyword_only() {
+        let src = "def f(a, *args, key=None, **kwargs):\n    return a\n";
+        let (def, body) = proxy(src, "def f");
+        assert_eq!(body, "    return _impl(a, *args, key=key, **kwargs)");
+        assert!(def.contains("def _impl(a, *args, key, **kwargs):"));
+
+        let src = "def g(a, /, b, *, flag=False):\n    return a\n";
+        let (def, body) = proxy(src, "def g");
+        assert_eq!(body, "    return _impl(a, b, flag=flag)");
+        assert!(def.contains("def _impl(a, /, b, *, flag):"));
+    }
+
+    #[test]
+    fn test_annotations_and_return_type_preserved() {
+        let src = "def f(a: int, *rest: str, opt: dict[str, int] = {}, **kw: Any) -> list[int]:\n    return [a]\n";
+        let (def, body) = proxy(src, "def f");
+        assert_eq!(body, "    return _impl(a, *rest, opt=opt, **kw)");
+        assert!(def.contains(
+            "def _impl(a: int, *rest: str, opt: dict[str, int], **kw: Any) -> list[int]:"
+        ));
+    }
+
+    #[test]
+    fn test_async_function_awaits_async_impl() {
+        let src = "async def fetch(url):\n    resp = await get(url)\n    return resp\n";
+        let (def, body) = proxy(src, "async def");
+        assert_eq!(body, "    return await _impl(url)");
+        assert!(def.starts_with("\n\nasync def _impl(url):\n"));
+    }
+
+    #[test]
+    fn test_generator_delegates_with_yield_from() {
+        let src = "def rows(items):\n    for i in items:\n        yield i\n";
+        let (_, body) = proxy(src, "def rows");
+        assert_eq!(body, "    return (yield from _impl(items))");
+
+        // A yield inside a nested function does not make the outer one a generator.
+        let src = "def outer(items):\n    def inner():\n        yield 1\n    return inner\n";
+        assert!(!target(src, "def outer").unwrap().is_generator);
+    }
+
+    #[test]
+    fn test_async_generator_refused() {
+        let src = "async def stream(xs):\n    for x in xs:\n        yield x\n";
+        assert!(reason(target(src, "async def")).contains("async generator"));
+    }
+
+    #[test]
+    fn test_method_passes_self_and_dedents_impl() {
+        let src = "class Invoice:\n    @property\n    def total(self, rate=RATE):\n        \"\"\"Net total.\n\n        Multi-line docstrings stay behind.\n        \"\"\"\n        value = self.net * rate\n        return value\n";
+        let t = target(src, "@property").unwrap();
+        assert!(t.is_method);
+        assert_eq!(
+            t.proxy_body("_impl", &t).unwrap(),
+            "        return _impl(self, rate)"
+        );
+        assert_eq!(
+            t.impl_definition("_impl"),
+            "\n\ndef _impl(self, rate):\n    value = self.net * rate\n    return value\n"
+        );
+        // The decorator and docstring stay on the proxy.
+        assert!(src[..t.body_start as usize].ends_with("stay behind.\n        \"\"\"\n"));
+    }
+
+    #[test]
+    fn test_classmethod_and_staticmethod() {
+        let src = "class A:\n    @classmethod\n    def make(cls, x):\n        return cls(x)\n\n    @staticmethod\n    def util(x, y):\n        return x + y\n";
+        let (_, body) = proxy(src, "@classmethod");
+        assert_eq!(body, "        return _impl(cls, x)");
+        let (_, body) = proxy(src, "@staticmethod");
+        assert_eq!(body, "        return _impl(x, y)");
+    }
+
+    #[test]
+    fn test_class_bound_method_bodies_refused() {
+        let src = "class A(B):\n    def run(self):\n        return super().run()\n";
+        assert!(reason(target(src, "def run")).contains("super()"));
+        let src = "class A:\n    def run(self):\n        return self.__secret\n";
+        assert!(reason(target(src, "def run")).contains("__private"));
+        // Explicit two-argument super and dunder names are fine.
+        let src = "class A(B):\n    def run(self):\n        return super(A, self).__len__()\n";
+        assert!(target(src, "def run").is_ok());
+    }
+
+    #[test]
+    fn test_nested_function_refused() {
+        let src = "def outer(n):\n    def inner(x):\n        return x + n\n    return inner\n";
+        assert!(reason(target(src, "def inner")).contains("nested"));
+    }
+
+    #[test]
+    fn test_multiline_string_in_reindented_body_refused() {
+        let src = "class A:\n    def sql(self):\n        q = \"\"\"\n            SELECT 1\n        \"\"\"\n        return q\n";
+        assert!(reason(target(src, "def sql")).contains("multi-line string"));
+        // At module level no re-indent is needed.
+        let src = "def sql():\n    q = \"\"\"\n  SELECT 1\n\"\"\"\n    return q\n";
+        assert!(target(src, "def sql").is_ok());
+    }
+
+    #[test]
+    fn test_one_line_function() {
+        let src = "def ident(x): return x\n";
+        let t = target(src, "def").unwrap();
+        assert_eq!(t.proxy_body("_impl", &t).unwrap(), "return _impl(x)");
+        assert_eq!(&src[t.body_start as usize..t.body_end as usize], "return x");
+        assert_eq!(
+            t.impl_definition("_impl"),
+            "\n\ndef _impl(x):\n    return x\n"
+        );
+    }
+
+    #[test]
+    fn test_member_names_map_onto_canonical_keywords() {
+        let src = "def a(x, *, fast=False):\n    return x\n\ndef b(y, *, quick=True):\n    return y\n\ndef c(y, z):\n    return y\n";
+        let canon = target(src, "def a").unwrap();
+        let member = target(src, "def b").unwrap();
+        assert_eq!(
+            member.proxy_body("_a_impl", &canon).unwrap(),
+            "    return _a_impl(y, fast=quick)"
+        );
+        let mismatched = target(src, "def c").unwrap();
+        assert!(matches!(
+            mismatched.proxy_body("_a_impl", &canon),
+            Err(ReaperError::Unproxyable { .. })
+        ));
+    }
+
+    #[test]
+    fn test_generic_function_keeps_type_params() {
+        let src = "def first[T](xs: list[T]) -> T:\n    return xs[0]\n";
+        let (def, _) = proxy(src, "def first");
+        assert!(def.contains("def _impl[T](xs: list[T]) -> T:"));
+    }
+}
@@ -8,12 +8,26 @@
 //! 3. `replace_symbols(file, targets)` — backs up the file on first touch,
 //!    then substitutes each byte range with replacement text, also bottom-to-top.
 //! 4. `commit()` — success path: removes backup files.
-//! 5. `restore_all()` — failure path: copies every backup back to its original path.
+//! 5. `restore_all()` — failure path: copies every backup of the transaction back
+//!    to its original path, reading the mapping from the manifest.
+//! 6. `seal()` — success path that keeps the backups so `janitor restore` can
+//!    undo the transaction later.
+//!
+//! Every backup is logged in the [ghost manifest](crate::ghost) under the
+//! deleter's transaction id; `commit` and `restore_all` drop those entries.
+//!
+//! Files are read with [`common::source::read`], the reader the scan parsed
+//! them with, so byte offsets into a Latin-1 file land where the scan found
+//! them; the edited text is encoded back to Latin-1 on write.
+//!
+//! The splices themselves are [`delete_ranges`] and [`replace_ranges`], which
+//! work on an in-memory buffer; [`crate::patch`] uses them to build a diff
+//! without writing.
 
+use crate::ghost::{self, GhostKind, GhostManifest, GhostRecord};
 use crate::ReaperError;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
-use std::time::{SystemTime, UNIX_EPOCH};
 
 /// Minimal description of a symbol to be excised.
 ///
@@ -29,6 +43,27 @@ pub struct DeletionTarget {
     pub end_byte: u32,
 }
 
+/// What `SafeDeleter::delete_symbols` did with one [`DeletionTarget`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeletionStatus {
+    /// The byte range was excised.
+    Removed,
+    /// The range does not fit the file on disk (stale registry); nothing was touched.
+    SkippedOutOfRange,
+    /// The range lies inside (or intersects) another target's range, which was
+    /// removed instead — e.g. a method of a deleted class.
+    SkippedOverlap,
+}
+
+/// Per-target result of `SafeDeleter::delete_symbols`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeletionOutcome {
+    /// Fully-qualified symbol name, copied from the target.
+    pub qualified_name: String,
+    /// What happened to the target.
+    pub status: DeletionStatus,
+}
+
 /// Description of a symbol whose body should be replaced with new text.
 ///
 /// Used by `SafeDeleter::replace_symbols` to surgically substitute code.
@@ -46,73 +81,58 @@ pub struct ReplacementTarget {
 
 /// Transactional file editor that backs up files before modifying them.
 ///
-/// Ghost directory layout: `{project_root}/.janitor/ghost/{ts}_{filename}.bak`
+/// Ghost directory layout: `{project_root}/.janitor/ghost/{ts}_{path_hash}_{filename}[-n].bak`,
+/// where `path_hash` is a short BLAKE3 digest of the original absolute path so
+/// same-named files from different packages never share a backup, and `-n`
+/// keeps a second backup of the same file within one second from overwriting the first.
 pub struct SafeDeleter {
     ghost_dir: PathBuf,
+    manifest: GhostManifest,
+    transaction: String,
     /// `original_path → backup_path`
     backups: HashMap<PathBuf, PathBuf>,
 }
 
 impl SafeDeleter {
     /// Creates (or reuses) the ghost directory under `project_root/.janitor/ghost`.
+    ///
+    /// Backups are logged under a fresh transaction id.
     pub fn new(project_root: &Path) -> Result<Self, ReaperError> {
-        let ghost_dir = project_root.join(".janitor").join("ghost");
+        Self::with_transaction(project_root, &ghost::new_transaction_id())
+    }
+
+    /// Like [`new`](Self::new), but logs backups under `transaction` so several
+    /// deleters can form one restorable unit.
+    pub fn with_transaction(project_root: &Path, transaction: &str) -> Result<Self, ReaperError> {
+        let manifest = GhostManifest::open(project_root);
+        let ghost_dir = manifest.ghost_dir().to_path_buf();
         std::fs::create_dir_all(&ghost_dir)?;
         Ok(Self {
             ghost_dir,
+            manifest,
+            transaction: transaction.to_string(),
             backups: HashMap::new(),
         })
     }
 
-    /// Backs up `file_path` (if not already done), then excises all listed byte ranges.
-    ///
-    /// Targets are processed **bottom-to-top** (descending `start_byte`) so that
-    /// earlier offsets remain valid after each splice.
+    /// Backs up `file_path` (if not already done), then excises all listed
+    /// byte ranges with [`delete_ranges`].
     ///
-    /// Returns the number of symbols actually removed.
+    /// Returns one outcome per target, in the order `targets` is left in
+    /// (sorted by descending `start_byte`).
     pub fn delete_symbols(
         &mut self,
         file_path: &Path,
         targets: &mut [DeletionTarget],
-    ) -> Result<usize, ReaperError> {
+    ) -> Result<Vec<DeletionOutcome>, ReaperError> {
         if targets.is_empty() {
-            return Ok(0);
+            return Ok(Vec::new());
         }
-
-        self.ensure_backup(file_path)?;
-
-        let mut content = std::fs::read(file_path)?;
-
-        // Sort DESCENDING — bottom-to-top so earlier offsets stay valid.
-        targets.sort_by(|a, b| b.start_byte.cmp(&a.start_byte));
-
-        let mut removed = 0usize;
-        for target in targets.iter() {
-            let start = snap_char_boundary_bwd(&content, target.start_byte as usize);
-            let mut end = snap_char_boundary_fwd(&content, target.end_byte as usize);
-
-            if start >= content.len() || end > content.len() || start >= end {
-                continue;
-            }
-
-            // Consume the trailing newline (if present) to avoid a blank line.
-            if end < content.len() && content[end] == b'\n' {
-                end += 1;
-            }
-
-            content.drain(start..end);
-            removed += 1;
-        }
-
-        std::fs::write(file_path, &content)?;
-        Ok(removed)
+        self.edit(file_path, |content| delete_ranges(content, targets))
     }
 
     /// Backs up `file_path` (if not already done), then replaces each listed
-    /// byte range with the corresponding `ReplacementTarget::replacement` text.
-    ///
-    /// Targets are processed **bottom-to-top** (descending `start_byte`) so that
-    /// earlier offsets remain valid after each splice.
+    /// byte range with [`replace_ranges`].
     ///
     /// Returns the number of replacements applied.
     pub fn replace_symbols(
@@ -123,55 +143,57 @@ impl SafeDeleter {
         if targets.is_empty() {
             return Ok(0);
         }
+        self.edit(file_path, |content| replace_ranges(content, targets))
+    }
 
+    /// Backs up `file_path` (if not already done), hands its decoded contents
+    /// to `edit`, and writes the result back in the file's encoding.
+    pub fn edit<T>(
+        &mut self,
+        file_path: &Path,
+        edit: impl FnOnce(&mut Vec<u8>) -> T,
+    ) -> Result<T, ReaperError> {
         self.ensure_backup(file_path)?;
 
-        let mut content = std::fs::read(file_path)?;
-
-        // Sort DESCENDING — bottom-to-top.
-        targets.sort_by(|a, b| b.start_byte.cmp(&a.start_byte));
-
-        let mut replaced = 0usize;
-        for target in targets.iter() {
-            let start = snap_char_boundary_bwd(&content, target.start_byte as usize);
-            let end = snap_char_boundary_fwd(&content, target.end_byte as usize);
-
-            if start >= content.len() || end > content.len() || start >= end {
-                continue;
-            }
-
-            let replacement = target.replacement.as_bytes();
-            let mut new_content =
-                Vec::with_capacity(content.len() - (end - start) + replacement.len());
-            new_content.extend_from_slice(&content[..start]);
-            new_content.extend_from_slice(replacement);
-            new_content.extend_from_slice(&content[end..]);
-            content = new_content;
-            replaced += 1;
-        }
-
-        std::fs::write(file_path, &content)?;
-        Ok(replaced)
+        let source = common::source::read(file_path)?;
+        let encoding = source.encoding();
+        let mut content = source.into_vec();
+        let result = edit(&mut content);
+        std::fs::write(file_path, encoding.encode(&content)?)?;
+        Ok(result)
     }
 
-    /// Copies all backup files back to their original paths.
+    /// Copies every backup logged under this transaction back to its original path.
     ///
-    /// Called on test failure to revert the transaction.
+    /// Called on test failure to revert the transaction. The backup → original
+    /// mapping comes from the ghost manifest, so a deleter re-created with
+    /// [`with_transaction`](Self::with_transaction) after a crash can still roll back.
     pub fn restore_all(&self) -> Result<(), ReaperError> {
-        for (original, backup) in &self.backups {
-            std::fs::copy(backup, original)?;
+        let records: Vec<GhostRecord> = self
+            .manifest
+            .records()?
+            .into_iter()
+            .filter(|r| r.transaction == self.transaction && r.kind == GhostKind::Backup)
+            .collect();
+        // Newest first, so a file backed up twice ends at its oldest state.
+        for record in records.iter().rev() {
+            std::fs::copy(self.ghost_dir.join(&record.ghost_name), &record.original)?;
         }
-        Ok(())
+        for record in &records {
+            std::fs::remove_file(self.ghost_dir.join(&record.ghost_name)).ok();
+        }
+        self.manifest.remove_where(|r| records.contains(r))
     }
 
     /// Deletes all backup files after a successful transaction.
     pub fn commit(&self) -> Result<(), ReaperError> {
-        for backup in self.backups.values() {
-            std::fs::remove_file(backup).ok();
-        }
-        Ok(())
+        self.discard_backups()
     }
 
+    /// Ends a successful transaction but keeps its backups (and their manifest
+    /// entries) so it can be undone later with `janitor restore`.
+    pub fn seal(self) {}
+
     /// Returns the number of files currently backed up.
     pub fn backup_count(&self) -> usize {
         self.backups.len()
@@ -181,6 +203,17 @@ impl SafeDeleter {
     pub fn ensure_backup(&mut self, file_path: &Path) -> Result<(), ReaperError> {
         if !self.backups.contains_key(file_path) {
             let bak = self.backup_file(file_path)?;
+            let original = std::fs::canonicalize(file_path)?;
+            self.manifest.append(&GhostRecord {
+                transaction: self.transaction.clone(),
+                kind: GhostKind::Backup,
+                timestamp: ghost::unix_now(),
+                ghost_name: bak
+                    .strip_prefix(&self.ghost_dir)
+                    .unwrap_or(&bak)
+                    .to_path_buf(),
+                original,
+            })?;
             self.backups.insert(file_path.to_path_buf(), bak);
         }
         Ok(())
@@ -188,22 +221,259 @@ impl SafeDeleter {
 
     // --- private ---
 
+    fn discard_backups(&self) -> Result<(), ReaperError> {
+        for backup in self.backups.values() {
+            std::fs::remove_file(backup).ok();
+        }
+        let ghost_dir = &self.ghost_dir;
+        let backups: Vec<&PathBuf> = self.backups.values().collect();
+        self.manifest.remove_where(|r| {
+            r.transaction == self.transaction
+                && r.kind == GhostKind::Backup
+                && backups.contains(&&ghost_dir.join(&r.ghost_name))
+        })
+    }
+
     fn backup_file(&self, file_path: &Path) -> Result<PathBuf, ReaperError> {
         let filename = file_path
             .file_name()
             .and_then(|n| n.to_str())
             .unwrap_or("unknown");
-        let ts = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_secs();
-        let bak_name = format!("{}_{}.bak", ts, filename);
-        let bak_path = self.ghost_dir.join(bak_name);
+        let ts = ghost::unix_now();
+        let absolute = std::fs::canonicalize(file_path)?;
+        let path_hash = blake3::hash(absolute.to_string_lossy().as_bytes()).to_hex();
+        let base = format!("{}_{}_{}", ts, &path_hash[..12], filename);
+        // A second deleter in the same transaction may back the file up again
+        // within the second; never overwrite the earlier (older) copy.
+        let mut bak_path = self.ghost_dir.join(format!("{}.bak", base));
+        let mut n = 1;
+        while bak_path.exists() {
+            bak_path = self.ghost_dir.join(format!("{}-{}.bak", base, n));
+            n += 1;
+        }
         std::fs::copy(file_path, &bak_path)?;
         Ok(bak_path)
     }
 }
 
+// ---------------------------------------------------------------------------
+// In-memory splicing
+// ---------------------------------------------------------------------------
+
+/// Excises every target's byte range from `content`.
+///
+/// Ranges that do not fit `content` are reported as
+/// [`DeletionStatus::SkippedOutOfRange`]. When ranges intersect, the one
+/// starting first (the outer one, for nested symbols) is removed and the
+/// other is reported as [`DeletionStatus::SkippedOverlap`].
+///
+/// Surviving targets are processed **bottom-to-top** (descending `start_byte`)
+/// so that earlier offsets remain valid after each splice. A range that is
+/// the only content of its line takes the line's indentation with it, and a
+/// suite (class or function body, `if` branch, ...) whose every statement
+/// is removed keeps a `pass` so the file still parses.
+///
+/// Returns one outcome per target, in the order `targets` is left in
+/// (sorted by descending `start_byte`).
+pub fn delete_ranges(
+    content: &mut Vec<u8>,
+    targets: &mut [DeletionTarget],
+) -> Vec<DeletionOutcome> {
+    // Sort DESCENDING — bottom-to-top so earlier offsets stay valid.
+    targets.sort_by_key(|t| std::cmp::Reverse(t.start_byte));
+
+    // Resolve every range against the original content before touching it.
+    let mut ranges: Vec<Option<(usize, usize)>> = Vec::with_capacity(targets.len());
+    let mut statuses = vec![DeletionStatus::Removed; targets.len()];
+    for (i, target) in targets.iter().enumerate() {
+        debug_assert_ordered(target.start_byte, target.end_byte, &target.qualified_name);
+        let start = snap_char_boundary_bwd(content, target.start_byte as usize);
+        let end = snap_char_boundary_fwd(content, target.end_byte as usize);
+        if start >= content.len() || end > content.len() || start >= end {
+            statuses[i] = DeletionStatus::SkippedOutOfRange;
+            ranges.push(None);
+        } else {
+            ranges.push(Some((start, end)));
+        }
+    }
+
+    // Overlap sweep in ascending start order; ties keep the longer (outer) range.
+    let mut order: Vec<usize> = (0..targets.len())
+        .filter(|&i| ranges[i].is_some())
+        .collect();
+    order.sort_by_key(|&i| {
+        let (start, end) = ranges[i].unwrap_or_default();
+        (start, std::cmp::Reverse(end))
+    });
+    let mut covered_to = 0usize;
+    for &i in &order {
+        let (start, end) = ranges[i].unwrap_or_default();
+        if start < covered_to {
+            statuses[i] = DeletionStatus::SkippedOverlap;
+        } else {
+            covered_to = end;
+        }
+    }
+
+    let removed: Vec<(usize, usize)> = ranges
+        .iter()
+        .zip(&statuses)
+        .filter_map(|(r, s)| (*s == DeletionStatus::Removed).then_some((*r)?))
+        .collect();
+    let needs_pass = emptied_suite_starts(content, &removed);
+
+    for (i, range) in ranges.iter().enumerate() {
+        let Some((start, mut end)) = *range else {
+            continue;
+        };
+        if statuses[i] != DeletionStatus::Removed {
+            continue;
+        }
+
+        // Consume the trailing newline (if present) to avoid a blank line.
+        if end < content.len() && content[end] == b'\n' {
+            end += 1;
+        }
+
+        let line_start = indentation_start(content, start);
+        if needs_pass.contains(&start) {
+            let mut placeholder = content[line_start..start].to_vec();
+            placeholder.extend_from_slice(b"pass\n");
+            content.splice(line_start..end, placeholder);
+        } else {
+            content.drain(line_start..end);
+        }
+    }
+
+    targets
+        .iter()
+        .zip(statuses)
+        .map(|(t, status)| DeletionOutcome {
+            qualified_name: t.qualified_name.clone(),
+            status,
+        })
+        .collect()
+}
+
+/// Replaces each target's byte range in `content` with its
+/// [`ReplacementTarget::replacement`] text. Ranges that do not fit `content`
+/// are left alone.
+///
+/// Targets are processed **bottom-to-top** (descending `start_byte`) so that
+/// earlier offsets remain valid after each splice.
+///
+/// Returns the number of replacements applied.
+pub fn replace_ranges(content: &mut Vec<u8>, targets: &mut [ReplacementTarget]) -> usize {
+    // Sort DESCENDING — bottom-to-top.
+    targets.sort_by_key(|t| std::cmp::Reverse(t.start_byte));
+
+    let mut replaced = 0usize;
+    for target in targets.iter() {
+        debug_assert_ordered(target.start_byte, target.end_byte, &target.qualified_name);
+        let start = snap_char_boundary_bwd(content, target.start_byte as usize);
+        let end = snap_char_boundary_fwd(content, target.end_byte as usize);
+
+        if start >= content.len() || end > content.len() || start >= end {
+            continue;
+        }
+
+        let replacement = target.replacement.as_bytes();
+        let mut new_content = Vec::with_capacity(content.len() - (end - start) + replacement.len());
+        new_content.extend_from_slice(&content[..start]);
+        new_content.extend_from_slice(replacement);
+        new_content.extend_from_slice(&content[end..]);
+        *content = new_content;
+        replaced += 1;
+    }
+
+    replaced
+}
+
+// ---------------------------------------------------------------------------
+// Structural helpers
+// ---------------------------------------------------------------------------
+
+/// Returns the start of `offset`'s line when only spaces/tabs precede it there,
+/// otherwise `offset` itself.
+fn indentation_start(buf: &[u8], offset: usize) -> usize {
+    let line_start = buf[..offset]
+        .iter()
+        .rposition(|&b| b == b'\n')
+        .map_or(0, |i| i + 1);
+    if buf[line_start..offset]
+        .iter()
+        .all(|&b| b == b' ' || b == b'\t')
+    {
+        line_start
+    } else {
+        offset
+    }
+}
+
+/// Finds the suites `removed` would leave without a statement.
+///
+/// Parses the pre-splice `content` and returns, for each `block` whose every
+/// non-comment statement lies inside a removed range (and which is not itself
+/// being removed), the start of the first such range — that range is replaced
+/// by `pass` instead of being deleted outright.
+fn emptied_suite_starts(content: &[u8], removed: &[(usize, usize)]) -> HashSet<usize> {
+    let mut starts = HashSet::new();
+    if removed.is_empty() {
+        return starts;
+    }
+    let mut parser = tree_sitter::Parser::new();
+    if parser
+        .set_language(&tree_sitter_python::LANGUAGE.into())
+        .is_err()
+    {
+        return starts;
+    }
+    let Some(tree) = parser.parse(content, None) else {
+        return starts;
+    };
+
+    let covering = |node: tree_sitter::Node<'_>| {
+        removed
+            .iter()
+            .find(|(s, e)| *s <= node.start_byte() && node.end_byte() <= *e)
+            .map(|(s, _)| *s)
+    };
+
+    let mut stack = vec![tree.root_node()];
+    while let Some(node) = stack.pop() {
+        if covering(node).is_some() {
+            continue; // removed wholesale — nothing inside it survives anyway
+        }
+        if node.kind() == "block" {
+            let mut cursor = node.walk();
+            let statements: Vec<_> = node
+                .named_children(&mut cursor)
+                .filter(|c| c.kind() != "comment")
+                .collect();
+            let covers: Option<Vec<usize>> = statements.iter().map(|c| covering(*c)).collect();
+            if let Some(first) = covers.and_then(|c| c.into_iter().min()) {
+                starts.insert(first);
+            }
+        }
+        let mut cursor = node.walk();
+        stack.extend(node.children(&mut cursor));
+    }
+    starts
+}
+
+/// Panics in debug builds on a range that ends before it starts. Past-EOF
+/// ranges are legitimate (the file changed since the scan) and are skipped;
+/// an inverted one can only come from an extraction bug.
+fn debug_assert_ordered(start_byte: u32, end_byte: u32, qualified_name: &str) {
+    debug_assert!(
+        start_byte <= end_byte,
+        "inverted byte range {}..{} for {}",
+        start_byte,
+        end_byte,
+        qualified_name
+    );
+}
+
 // ---------------------------------------------------------------------------
 // UTF-8 boundary helpers
 // ---------------------------------------------------------------------------
@@ -281,8 +551,9 @@ mod tests {
                 end_byte: src.len() as u32,
             },
         ];
-        let removed = deleter.delete_symbols(&file, &mut targets).unwrap();
-        assert_eq!(removed, 2);
+        let outcomes = deleter.delete_symbols(&file, &mut targets).unwrap();
+        assert!(outcomes.iter().all(|o| o.status == DeletionStatus::Removed));
+        assert_eq!(outcomes.len(), 2);
 
         let result = fs::read_to_string(&file).unwrap();
         assert!(
@@ -339,6 +610,96 @@ mod tests {
         fs::remove_dir_all(tmp).ok();
     }
 
+    #[test]
+    fn test_seal_keeps_restorable_backup() {
+        let tmp = tmp_dir("test_seal_backup");
+        let original = b"def unused():\n    pass\nx = 1\n";
+        let file = tmp.join("app.py");
+        fs::write(&file, original).ok();
+
+        let mut deleter = SafeDeleter::with_transaction(&tmp, "txn").unwrap();
+        let mut targets = vec![DeletionTarget {
+            qualified_name: "unused".into(),
+            start_byte: 0,
+            end_byte: 22,
+        }];
+        deleter.delete_symbols(&file, &mut targets).unwrap();
+        deleter.seal();
+
+        let manifest = GhostManifest::open(&tmp);
+        let records = manifest.records().unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].transaction, "txn");
+        assert_eq!(records[0].original, fs::canonicalize(&file).unwrap());
+
+        manifest.restore(&records[0]).unwrap();
+        assert_eq!(fs::read(&file).unwrap(), original);
+        assert!(manifest.records().unwrap().is_empty());
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_same_basename_backups_do_not_collide() {
+        let tmp = tmp_dir("test_bak_collision");
+        fs::create_dir_all(tmp.join("app")).ok();
+        fs::create_dir_all(tmp.join("billing")).ok();
+        let app = tmp.join("app/models.py");
+        let billing = tmp.join("billing/models.py");
+        fs::write(&app, b"class App:\n    pass\n").ok();
+        fs::write(&billing, b"class Invoice:\n    pass\n").ok();
+
+        let mut deleter = SafeDeleter::with_transaction(&tmp, "txn").unwrap();
+        deleter.ensure_backup(&app).unwrap();
+        deleter.ensure_backup(&billing).unwrap();
+        assert_eq!(fs::read_dir(tmp.join(".janitor/ghost")).unwrap().count(), 2);
+
+        fs::write(&app, b"corrupt").ok();
+        fs::write(&billing, b"corrupt").ok();
+
+        // Simulate a crash: roll back from a fresh deleter that only knows the id.
+        drop(deleter);
+        SafeDeleter::with_transaction(&tmp, "txn")
+            .unwrap()
+            .restore_all()
+            .unwrap();
+
+        assert_eq!(fs::read(&app).unwrap(), b"class App:\n    pass\n");
+        assert_eq!(fs::read(&billing).unwrap(), b"class Invoice:\n    pass\n");
+        assert!(GhostManifest::open(&tmp).records().unwrap().is_empty());
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_second_backup_in_same_second_keeps_the_first() {
+        let tmp = tmp_dir("test_bak_same_second");
+        let original = b"def dead():\n    pass\nx = 1\n";
+        let file = tmp.join("app.py");
+        fs::write(&file, original).ok();
+
+        let mut first = SafeDeleter::with_transaction(&tmp, "txn").unwrap();
+        let mut targets = vec![DeletionTarget {
+            qualified_name: "dead".into(),
+            start_byte: 0,
+            end_byte: 22,
+        }];
+        first.delete_symbols(&file, &mut targets).unwrap();
+
+        // A second deleter in the same transaction backs up the edited file.
+        let mut second = SafeDeleter::with_transaction(&tmp, "txn").unwrap();
+        second
+            .edit(&file, |content| content.extend_from_slice(b"y = 2\n"))
+            .unwrap();
+        assert_eq!(fs::read_dir(tmp.join(".janitor/ghost")).unwrap().count(), 2);
+
+        second.restore_all().unwrap();
+        assert_eq!(fs::read(&file).unwrap(), original);
+        assert!(GhostManifest::open(&tmp).records().unwrap().is_empty());
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
     #[test]
     fn test_empty_targets_noop() {
         let tmp = tmp_dir("test_empty_noop");
@@ -346,8 +707,8 @@ mod tests {
         fs::write(&file, b"x = 1\n").ok();
 
         let mut deleter = SafeDeleter::new(&tmp).unwrap();
-        let removed = deleter.delete_symbols(&file, &mut vec![]).unwrap();
-        assert_eq!(removed, 0);
+        let outcomes = deleter.delete_symbols(&file, &mut []).unwrap();
+        assert!(outcomes.is_empty());
         assert_eq!(deleter.backup_count(), 0);
 
         fs::remove_dir_all(tmp).ok();
@@ -415,8 +776,184 @@ mod tests {
             start_byte: 0,
             end_byte: src_bytes.len() as u32,
         }];
-        let removed = deleter.delete_symbols(&file, &mut targets).unwrap();
-        assert_eq!(removed, 1);
+        let outcomes = deleter.delete_symbols(&file, &mut targets).unwrap();
+        assert_eq!(outcomes[0].status, DeletionStatus::Removed);
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_latin1_file_uses_transcoded_offsets() {
+        let tmp = tmp_dir("test_latin1_offsets");
+        let file = tmp.join("legacy.py");
+        fs::write(
+            &file,
+            b"# caf\xe9\ndef a():\n    return '\xe9'\ndef b():\n    pass\n",
+        )
+        .ok();
+
+        // The scan saw the UTF-8 text, where each `é` takes two bytes.
+        let text = "# café\ndef a():\n    return 'é'\ndef b():\n    pass\n";
+        let start = text.find("def b").unwrap() as u32;
+        let mut targets = vec![target("b", start, text.len() as u32 - 1)];
+        let mut deleter = SafeDeleter::new(&tmp).unwrap();
+        let outcomes = deleter.delete_symbols(&file, &mut targets).unwrap();
+
+        assert_eq!(outcomes[0].status, DeletionStatus::Removed);
+        assert_eq!(
+            fs::read(&file).unwrap(),
+            b"# caf\xe9\ndef a():\n    return '\xe9'\n"
+        );
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    fn target(name: &str, start: u32, end: u32) -> DeletionTarget {
+        DeletionTarget {
+            qualified_name: name.into(),
+            start_byte: start,
+            end_byte: end,
+        }
+    }
+
+    fn status_of(outcomes: &[DeletionOutcome], name: &str) -> DeletionStatus {
+        outcomes
+            .iter()
+            .find(|o| o.qualified_name == name)
+            .map(|o| o.status)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_nested_method_inside_class() {
+        let tmp = tmp_dir("test_nested_delete");
+        let src = "class A:\n    def m(self):\n        pass\nx = 1\n";
+        let file = tmp.join("nested.py");
+        fs::write(&file, src).ok();
+
+        let class_end = src.find("x = 1").unwrap() as u32 - 1;
+        let m_start = src.find("def m").unwrap() as u32;
+        let mut deleter = SafeDeleter::new(&tmp).unwrap();
+        let mut targets = vec![target("A", 0, class_end), target("A.m", m_start, class_end)];
+        let outcomes = deleter.delete_symbols(&file, &mut targets).unwrap();
+
+        assert_eq!(status_of(&outcomes, "A"), DeletionStatus::Removed);
+        assert_eq!(status_of(&outcomes, "A.m"), DeletionStatus::SkippedOverlap);
+        assert_eq!(fs::read_to_string(&file).unwrap(), "x = 1\n");
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_partially_overlapping_ranges() {
+        let tmp = tmp_dir("test_overlap_delete");
+        let src = "def a():\n    pass\ndef b():\n    pass\ndef c():\n    pass\n";
+        let file = tmp.join("overlap.py");
+        fs::write(&file, src).ok();
+
+        // "a" covers a() and half of b(); "b" starts inside "a".
+        let b_start = src.find("def b").unwrap() as u32;
+        let c_start = src.find("def c").unwrap() as u32;
+        let mut deleter = SafeDeleter::new(&tmp).unwrap();
+        let mut targets = vec![
+            target("a", 0, b_start + 4),
+            target("b", b_start, c_start - 1),
+        ];
+        let outcomes = deleter.delete_symbols(&file, &mut targets).unwrap();
+
+        assert_eq!(status_of(&outcomes, "a"), DeletionStatus::Removed);
+        assert_eq!(status_of(&outcomes, "b"), DeletionStatus::SkippedOverlap);
+        assert!(fs::read_to_string(&file)
+            .unwrap()
+            .ends_with("def c():\n    pass\n"));
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_emptied_class_body_gets_pass() {
+        let tmp = tmp_dir("test_emptied_class");
+        let src = "class Foo:\n    def a(self):\n        pass\n\n    def b(self):\n        return 1\n\nx = Foo\n";
+        let file = tmp.join("emptied.py");
+        fs::write(&file, src).ok();
+
+        let a_start = src.find("def a").unwrap() as u32;
+        let a_end = src.find("pass").unwrap() as u32 + 4;
+        let b_start = src.find("def b").unwrap() as u32;
+        let b_end = src.find("return 1").unwrap() as u32 + 8;
+        let mut deleter = SafeDeleter::new(&tmp).unwrap();
+        let mut targets = vec![
+            target("Foo.a", a_start, a_end),
+            target("Foo.b", b_start, b_end),
+        ];
+        let outcomes = deleter.delete_symbols(&file, &mut targets).unwrap();
+        assert!(outcomes.iter().all(|o| o.status == DeletionStatus::Removed));
+
+        let result = fs::read_to_string(&file).unwrap();
+        assert_eq!(result, "class Foo:\n    pass\n\n\nx = Foo\n");
+
+        let mut parser = tree_sitter::Parser::new();
+        parser
+            .set_language(&tree_sitter_python::LANGUAGE.into())
+            .unwrap();
+        let tree = parser.parse(result.as_bytes(), None).unwrap();
+        assert!(!tree.root_node().has_error());
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    fn test_partial_class_body_keeps_no_pass() {
+        let tmp = tmp_dir("test_partial_class");
+        let src =
+            "class Foo:\n    def a(self):\n        pass\n    def b(self):\n        return 1\n";
+        let file = tmp.join("partial.py");
+        fs::write(&file, src).ok();
+
+        let a_start = src.find("def a").unwrap() as u32;
+        let b_start = src.find("    def b").unwrap() as u32 - 1;
+        let mut deleter = SafeDeleter::new(&tmp).unwrap();
+        let mut targets = vec![target("Foo.a", a_start, b_start)];
+        deleter.delete_symbols(&file, &mut targets).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&file).unwrap(),
+            "class Foo:\n    def b(self):\n        return 1\n"
+        );
+
+        fs::remove_dir_all(tmp).ok();
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "inverted byte range 17..3 for a")]
+    fn test_inverted_range_panics_in_debug() {
+        let tmp = tmp_dir("test_inverted_delete");
+        let file = tmp.join("inverted.py");
+        fs::write(&file, "def a():\n    pass\n").ok();
+
+        let mut deleter = SafeDeleter::new(&tmp).unwrap();
+        let mut targets = vec![target("a", 17, 3)];
+        deleter.delete_symbols(&file, &mut targets).ok();
+    }
+
+    #[test]
+    fn test_stale_range_past_eof() {
+        let tmp = tmp_dir("test_stale_delete");
+        let src = "def a():\n    pass\n";
+        let file = tmp.join("stale.py");
+        fs::write(&file, src).ok();
+
+        let mut deleter = SafeDeleter::new(&tmp).unwrap();
+        let mut targets = vec![target("a", 0, 17), target("gone", 40, 80)];
+        let outcomes = deleter.delete_symbols(&file, &mut targets).unwrap();
+
+        assert_eq!(status_of(&outcomes, "a"), DeletionStatus::Removed);
+        assert_eq!(
+            status_of(&outcomes, "gone"),
+            DeletionStatus::SkippedOutOfRange
+        );
+        assert!(fs::read_to_string(&file).unwrap().trim().is_empty());
 
         fs::remove_dir_all(tmp).ok();
     }
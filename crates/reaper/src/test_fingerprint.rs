@@ -4,12 +4,87 @@
 //! (e.g. `tests/test_api.py::test_create_user`). Returns all leaf segment
 //! names (the function portion after the last `::`) so callers can check
 //! whether a symbol name appears in the test surface.
+//!
+//! [`collect_test_surface`] additionally keeps the collected test files, so
+//! the pipeline can tell helpers referenced only from tests apart from dead code.
 
 use crate::ReaperError;
 use std::collections::HashSet;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
+/// pytest exit code for "no tests were collected".
+const EXIT_NO_TESTS: i32 = 5;
+
+/// What `pytest --collect-only` reports about a project's test suite.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TestSurface {
+    /// Class and function segments of every node ID, parametrization stripped
+    /// (`test_add` for `tests/test_math.py::TestOps::test_add[1-2]`).
+    pub names: HashSet<String>,
+    /// Collected test files under the project root, sorted and de-duplicated.
+    pub files: Vec<PathBuf>,
+}
+
+/// Collects the test surface of the project at `project_root`.
+///
+/// A project without tests (pytest exit code 5) yields an empty surface.
+///
+/// # Errors
+/// - `ReaperError::IoError` with kind `NotFound` when pytest is not installed.
+/// - `ReaperError::CollectionFailed` when collection errors out (exit code 2
+///   for import or syntax errors in test files, or any other failure).
+pub fn collect_test_surface(project_root: &Path) -> Result<TestSurface, ReaperError> {
+    let output = Command::new("pytest")
+        .args(["--collect-only", "-q", "--no-header"])
+        .current_dir(project_root)
+        .output()?;
+
+    match output.status.code() {
+        Some(0) | Some(EXIT_NO_TESTS) => Ok(parse_test_surface(&output.stdout, project_root)),
+        code => {
+            let text = String::from_utf8_lossy(&output.stdout);
+            let detail = text
+                .lines()
+                .rev()
+                .find(|l| !l.trim().is_empty())
+                .unwrap_or("no output")
+                .trim()
+                .to_string();
+            Err(ReaperError::CollectionFailed {
+                code: code.unwrap_or(-1),
+                detail,
+            })
+        }
+    }
+}
+
+/// Parses the stdout of `pytest --collect-only -q` into a [`TestSurface`].
+pub fn parse_test_surface(stdout: &[u8], project_root: &Path) -> TestSurface {
+    let text = String::from_utf8_lossy(stdout);
+    let mut surface = TestSurface::default();
+
+    for line in text.lines().map(str::trim) {
+        if !line.contains("::") || line.starts_with('=') {
+            continue;
+        }
+        let mut segments = line.split("::");
+        if let Some(file) = segments.next() {
+            surface.files.push(project_root.join(file));
+        }
+        for segment in segments {
+            let name = segment.split('[').next().unwrap_or(segment).trim();
+            if !name.is_empty() {
+                surface.names.insert(name.to_string());
+            }
+        }
+    }
+
+    surface.files.sort();
+    surface.files.dedup();
+    surface
+}
+
 /// Collects pytest test node IDs from the project at `project_root`.
 ///
 /// Runs `pytest --collect-only -q --no-header` in `project_root`.
@@ -93,6 +168,27 @@ mod tests {
         assert!(ids.is_empty());
     }
 
+    #[test]
+    fn test_parse_test_surface_strips_parametrization() {
+        let stdout = b"tests/test_math.py::TestOps::test_add[1-2]\n\
+tests/test_math.py::TestOps::test_add[3-4]\n\
+tests/test_io.py::test_read[case[nested]]\n\
+\n3 tests collected in 0.01s\n";
+        let surface = parse_test_surface(stdout, Path::new("/proj"));
+
+        assert_eq!(
+            surface.names,
+            HashSet::from(["TestOps".into(), "test_add".into(), "test_read".into()])
+        );
+        assert_eq!(
+            surface.files,
+            vec![
+                PathBuf::from("/proj/tests/test_io.py"),
+                PathBuf::from("/proj/tests/test_math.py")
+            ]
+        );
+    }
+
     #[test]
     fn test_parse_skips_summary_lines() {
         let stdout = b"tests/test_foo.py::test_bar\n= 1 test collected =\n";
@@ -1,5 +1,7 @@
+use std::collections::HashSet;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
 use walkdir::WalkDir;
 
 /// Errors from shadow tree operations.
@@ -11,28 +13,115 @@ pub enum ShadowError {
     WalkError(#[from] walkdir::Error),
     #[error("Symlink failure: {0}")]
     SymlinkFailure(String),
+    #[error("Hard link failure: {0}")]
+    HardlinkFailure(String),
+    #[error("Cannot ghost {shadow}: resolved file {resolved} does not exist")]
+    GhostSourceMissing { shadow: PathBuf, resolved: PathBuf },
 }
 
-/// Manages the symlink-based shadow source tree.
+/// Name of the file in the shadow root recording its [`LinkStrategy`].
+const STRATEGY_FILE: &str = ".strategy";
+
+/// How the shadow tree mirrors each source file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkStrategy {
+    /// Symbolic links (the zero-copy default).
+    Symlink,
+    /// Hard links: zero-copy too, but only within one volume.
+    Hardlink,
+    /// Plain copies. Edits to the source tree are only picked up by
+    /// [`ShadowManager::sync`] or [`ShadowManager::remap`].
+    Copy,
+}
+
+impl LinkStrategy {
+    /// Name persisted in `.janitor/shadow_src/.strategy`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LinkStrategy::Symlink => "symlink",
+            LinkStrategy::Hardlink => "hardlink",
+            LinkStrategy::Copy => "copy",
+        }
+    }
+
+    fn parse(name: &str) -> Option<Self> {
+        match name.trim() {
+            "symlink" => Some(LinkStrategy::Symlink),
+            "hardlink" => Some(LinkStrategy::Hardlink),
+            "copy" => Some(LinkStrategy::Copy),
+            _ => None,
+        }
+    }
+
+    /// The next strategy to try when this one cannot create links.
+    fn fallback(self) -> Option<Self> {
+        match self {
+            LinkStrategy::Symlink => Some(LinkStrategy::Hardlink),
+            LinkStrategy::Hardlink => Some(LinkStrategy::Copy),
+            LinkStrategy::Copy => None,
+        }
+    }
+}
+
+/// Manages the link-based shadow source tree.
 ///
 /// The shadow tree mirrors the source directory structure but uses symlinks
-/// for files instead of copies, satisfying the zero-copy constraint.
+/// for files instead of copies, satisfying the zero-copy constraint. Where
+/// symlinks are unavailable it falls back to hard links, then to copies
+/// (see [`LinkStrategy`]).
 pub struct ShadowManager {
     source_root: PathBuf,
     shadow_root: PathBuf,
+    strategy: LinkStrategy,
+}
+
+/// Link counts from one [`ShadowManager::sync`] pass.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SyncReport {
+    /// Links created for source files the shadow tree did not mirror yet.
+    pub added: usize,
+    /// Links removed because their source file is gone, excluded, or changed.
+    pub removed: usize,
+    /// Links already mirroring their source file.
+    pub kept: usize,
 }
 
 impl ShadowManager {
     /// Initialize the shadow tree from a source directory.
     ///
-    /// Creates a symlink-based mirror of `source` at `shadow`, skipping
-    /// excluded directories (target, .git, .janitor, venv, __pycache__, .venv).
+    /// Creates a symlink-based mirror of `source` at `shadow`, skipping the
+    /// paths excluded by [`common::walk`] so the shadow tree holds exactly the
+    /// files the scanner saw. Idempotent: an existing shadow tree is brought
+    /// up to date with [`sync`](Self::sync) rather than rebuilt.
+    ///
+    /// Starts from the strategy recorded by a previous run, or from symlinks.
+    /// When links cannot be created (WSL/Windows without Developer Mode, or
+    /// hard links across volumes) it falls back to the next [`LinkStrategy`];
+    /// the strategy that succeeded is persisted for [`open`](Self::open).
+    pub fn initialize(source: &Path, shadow: &Path) -> Result<Self, ShadowError> {
+        let mut strategy = read_strategy(shadow).unwrap_or(LinkStrategy::Symlink);
+        loop {
+            match Self::initialize_with(source, shadow, strategy) {
+                Err(e @ (ShadowError::SymlinkFailure(_) | ShadowError::HardlinkFailure(_))) => {
+                    strategy = strategy.fallback().ok_or(e)?;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Initialize the shadow tree with a fixed [`LinkStrategy`], without fallback.
     ///
     /// # Errors
     ///
-    /// Returns `ShadowError::SymlinkFailure` on WSL/Windows permission issues.
-    /// Enable Developer Mode or run as Administrator to create symlinks.
-    pub fn initialize(source: &Path, shadow: &Path) -> Result<Self, ShadowError> {
+    /// Returns `ShadowError::SymlinkFailure` on WSL/Windows permission issues
+    /// (enable Developer Mode or run as Administrator to create symlinks) and
+    /// `ShadowError::HardlinkFailure` when hard links cannot be created.
+    pub fn initialize_with(
+        source: &Path,
+        shadow: &Path,
+        strategy: LinkStrategy,
+    ) -> Result<Self, ShadowError> {
         // Canonicalize source path to absolute form
         let source_root = fs::canonicalize(source)?;
 
@@ -40,24 +129,36 @@ impl ShadowManager {
         fs::create_dir_all(shadow)?;
         let shadow_root = fs::canonicalize(shadow)?;
 
-        // Skip list for excluded directories
-        let skip_list = ["target", ".git", ".janitor", "venv", "__pycache__", ".venv"];
+        let manager = ShadowManager {
+            source_root,
+            shadow_root,
+            strategy,
+        };
+        manager.sync()?;
+        fs::write(manager.shadow_root.join(STRATEGY_FILE), strategy.as_str())?;
+        Ok(manager)
+    }
 
-        // Walk source tree lazily (never collect into memory)
-        for entry in WalkDir::new(&source_root).into_iter().filter_entry(|e| {
-            // Skip excluded directories
-            if let Some(name) = e.file_name().to_str() {
-                !skip_list.contains(&name)
-            } else {
-                true
-            }
-        }) {
-            let entry = entry?;
+    /// Reconciles the shadow tree with the current source tree.
+    ///
+    /// Links source files the shadow does not mirror yet, relinks entries
+    /// that no longer mirror their source (symlinks pointing elsewhere, stale
+    /// copies), removes entries whose source file was deleted, renamed, or
+    /// newly excluded, and prunes shadow directories left empty. The ghost
+    /// directory is never touched, nor are regular files in a symlinked tree.
+    pub fn sync(&self) -> Result<SyncReport, ShadowError> {
+        let mut report = SyncReport::default();
+        let mut files: HashSet<PathBuf> = HashSet::new();
+        let mut dirs: HashSet<PathBuf> = HashSet::new();
+
+        // Walk source tree lazily (never collect entries into memory)
+        for entry in common::walk::walk(&self.source_root) {
+            let entry = entry.map_err(|e| ShadowError::IoError(std::io::Error::other(e)))?;
             let entry_path = entry.path();
 
             // Get relative path from source root
             let relative = entry_path
-                .strip_prefix(&source_root)
+                .strip_prefix(&self.source_root)
                 .map_err(|e| ShadowError::IoError(std::io::Error::other(e)))?;
 
             // Skip the root itself
@@ -65,133 +166,249 @@ impl ShadowManager {
                 continue;
             }
 
-            let shadow_path = shadow_root.join(relative);
+            let shadow_path = self.shadow_root.join(relative);
 
-            if entry.file_type().is_dir() {
-                // Create directory in shadow tree
+            if entry.file_type().is_some_and(|t| t.is_dir()) {
                 fs::create_dir_all(&shadow_path)?;
-            } else if entry.file_type().is_file() {
-                // Create symlink to original file
-                #[cfg(unix)]
-                {
-                    if let Err(e) = std::os::unix::fs::symlink(entry_path, &shadow_path) {
-                        if e.kind() == std::io::ErrorKind::PermissionDenied {
-                            return Err(ShadowError::SymlinkFailure(format!(
-                                "WSL/Windows symlink failure: Enable Developer Mode or run as Admin. Path: {}",
-                                shadow_path.display()
-                            )));
-                        }
-                        return Err(ShadowError::IoError(e));
+                dirs.insert(relative.to_path_buf());
+            } else if entry.file_type().is_some_and(|t| t.is_file()) {
+                files.insert(relative.to_path_buf());
+                if shadow_path.is_symlink() || shadow_path.exists() {
+                    if self.mirrors(entry_path, &shadow_path)? {
+                        report.kept += 1;
+                        continue;
                     }
-                }
-                #[cfg(windows)]
-                {
-                    if let Err(e) = std::os::windows::fs::symlink_file(entry_path, &shadow_path) {
-                        if e.kind() == std::io::ErrorKind::PermissionDenied {
-                            return Err(ShadowError::SymlinkFailure(format!(
-                                "Windows symlink failure: Enable Developer Mode or run as Admin. Path: {}",
-                                shadow_path.display()
-                            )));
-                        }
-                        return Err(ShadowError::IoError(e));
+                    if !self.manages(&shadow_path) {
+                        continue;
                     }
+                    fs::remove_file(&shadow_path)?;
+                    report.removed += 1;
                 }
+                self.link(entry_path, &shadow_path)?;
+                report.added += 1;
             }
         }
 
-        Ok(ShadowManager {
-            source_root,
-            shadow_root,
+        // Children before parents, so emptied directories can be pruned.
+        let ghost_dir = self.ghost_path(Path::new(""));
+        let shadow_entries = WalkDir::new(&self.shadow_root)
+            .follow_links(false)
+            .contents_first(true)
+            .into_iter()
+            .filter_entry(|e| e.path() != ghost_dir);
+        for entry in shadow_entries {
+            let entry = entry?;
+            let Ok(relative) = entry.path().strip_prefix(&self.shadow_root) else {
+                continue;
+            };
+            if relative.as_os_str().is_empty() || relative == Path::new(STRATEGY_FILE) {
+                continue;
+            }
+            if !entry.file_type().is_dir() && self.manages(entry.path()) {
+                if !files.contains(relative) || !entry.path().exists() {
+                    fs::remove_file(entry.path())?;
+                    report.removed += 1;
+                }
+            } else if entry.file_type().is_dir()
+                && !dirs.contains(relative)
+                && fs::read_dir(entry.path())?.next().is_none()
+            {
+                fs::remove_dir(entry.path())?;
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// `true` when `shadow_path` already mirrors `source_file` under this strategy.
+    fn mirrors(&self, source_file: &Path, shadow_path: &Path) -> Result<bool, ShadowError> {
+        Ok(match self.strategy {
+            LinkStrategy::Symlink => {
+                shadow_path.is_symlink() && fs::read_link(shadow_path)? == source_file
+            }
+            LinkStrategy::Hardlink => {
+                !shadow_path.is_symlink() && same_file::is_same_file(source_file, shadow_path)?
+            }
+            LinkStrategy::Copy => {
+                !shadow_path.is_symlink() && fs::read(source_file)? == fs::read(shadow_path)?
+            }
         })
     }
 
-    /// Verify that all symlinks in the shadow tree are valid.
+    /// `true` when the shadow entry at `path` was created by this strategy and
+    /// may be replaced or removed. A symlinked tree leaves regular files alone.
+    fn manages(&self, path: &Path) -> bool {
+        path.is_symlink() || self.strategy != LinkStrategy::Symlink
+    }
+
+    /// Mirrors `target` at `link` using this manager's strategy.
+    fn link(&self, target: &Path, link: &Path) -> Result<(), ShadowError> {
+        match self.strategy {
+            LinkStrategy::Symlink => symlink(target, link),
+            LinkStrategy::Hardlink => fs::hard_link(target, link)
+                .map_err(|e| ShadowError::HardlinkFailure(format!("{}: {}", link.display(), e))),
+            LinkStrategy::Copy => fs::copy(target, link).map(|_| ()).map_err(Into::into),
+        }
+    }
+
+    /// Lists the shadow symlinks whose target does not exist (empty = healthy).
     ///
-    /// Returns `Ok(true)` if all symlinks exist and point to valid files,
-    /// `Ok(false)` if any symlink is broken.
-    pub fn verify_integrity(&self) -> Result<bool, ShadowError> {
+    /// Relative targets are resolved against the link's own directory, as the
+    /// file system does, never against the process working directory.
+    pub fn verify_integrity(&self) -> Result<Vec<PathBuf>, ShadowError> {
+        let mut broken = Vec::new();
         for entry in WalkDir::new(&self.shadow_root).follow_links(false) {
             let entry = entry?;
             let path = entry.path();
 
             // Check symlinks specifically
             if entry.path_is_symlink() {
-                // Use fs::read_link to check if target exists
-                if let Ok(target) = fs::read_link(path) {
-                    if !target.exists() {
-                        return Ok(false);
-                    }
-                } else {
-                    // read_link failed - symlink is broken
-                    return Ok(false);
+                let valid = fs::read_link(path)
+                    .map(|target| resolve_link_target(path, target).exists())
+                    .unwrap_or(false);
+                if !valid {
+                    broken.push(path.to_path_buf());
                 }
             }
         }
-        Ok(true)
+        Ok(broken)
+    }
+
+    /// `true` when every symlink in the shadow tree resolves to an existing file.
+    pub fn is_healthy(&self) -> Result<bool, ShadowError> {
+        Ok(self.verify_integrity()?.is_empty())
     }
 
-    /// Moves a real file to `.janitor/ghost/{relative_path}` and removes its symlink.
+    /// Moves a real file to `.janitor/ghost/{relative_path}` and removes its shadow link.
     ///
     /// ## Ghost Protocol
-    /// 1. Locate the symlink at `shadow_src/{relative_path}`.
-    /// 2. Resolve it to the real file path.
-    /// 3. Move the real file into `.janitor/ghost/{relative_path}` (creating parent dirs).
-    /// 4. Remove the symlink — the file disappears from the compiler's view.
+    /// 1. Resolve the real file: through the symlink at `shadow_src/{relative_path}`
+    ///    (a relative target is taken against the link's directory), or as
+    ///    `source_root/{relative_path}` when the entry is not a symlink — a hard
+    ///    link, a copy, or already removed by [`unmap`](Self::unmap).
+    /// 2. Move the real file into `.janitor/ghost/{relative_path}` (creating parent
+    ///    dirs). A file ghosted there by an earlier run is kept: the new one gets a
+    ///    Unix-timestamp suffix instead.
+    /// 3. Remove the shadow entry — the file disappears from the compiler's view.
     ///
-    /// The file survives in the Necropolis (`ghost/`) and can be recovered manually.
+    /// Returns the ghost name (the path under `.janitor/ghost/`). The file
+    /// survives in the Necropolis (`ghost/`) and can be recovered with
+    /// [`restore_from_ghost`](Self::restore_from_ghost).
     ///
     /// # Errors
-    /// - `ShadowError::SymlinkFailure` if the symlink cannot be resolved.
+    /// - `ShadowError::GhostSourceMissing` if the resolved file does not exist.
     /// - `ShadowError::IoError` on file-system failures.
-    pub fn move_to_ghost(&self, relative_path: &Path) -> Result<(), ShadowError> {
+    pub fn move_to_ghost(&self, relative_path: &Path) -> Result<PathBuf, ShadowError> {
         let shadow_path = self.shadow_root.join(relative_path);
 
-        // Resolve the symlink to the real file.
-        let real_path = fs::read_link(&shadow_path).map_err(|e| {
-            ShadowError::SymlinkFailure(format!(
-                "Cannot resolve symlink at {}: {}",
-                shadow_path.display(),
-                e
-            ))
-        })?;
-
-        // Build the ghost destination path.
-        let ghost_path = self
-            .source_root
-            .join(".janitor")
-            .join("ghost")
-            .join(relative_path);
+        let real_path = match fs::read_link(&shadow_path) {
+            Ok(target) => resolve_link_target(&shadow_path, target),
+            Err(_) => self.source_root.join(relative_path),
+        };
+        if !real_path.is_file() {
+            return Err(ShadowError::GhostSourceMissing {
+                shadow: shadow_path,
+                resolved: real_path,
+            });
+        }
 
+        let ghost_name = self.free_ghost_name(relative_path);
+        let ghost_path = self.ghost_path(&ghost_name);
         if let Some(parent) = ghost_path.parent() {
             fs::create_dir_all(parent)?;
         }
 
-        // Attempt an atomic rename first (same-filesystem); fall back to copy + delete.
-        if fs::rename(&real_path, &ghost_path).is_err() {
-            fs::copy(&real_path, &ghost_path)?;
-            fs::remove_file(&real_path)?;
+        move_file(&real_path, &ghost_path)?;
+
+        // Remove the now-stale shadow entry from shadow_src.
+        self.unmap(relative_path)?;
+        Ok(ghost_name)
+    }
+
+    /// `relative_path` if nothing is ghosted there yet, otherwise the first free
+    /// `{relative_path}.{unix_secs}[-n]`.
+    fn free_ghost_name(&self, relative_path: &Path) -> PathBuf {
+        if !self.ghost_path(relative_path).exists() {
+            return relative_path.to_path_buf();
+        }
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let base = format!("{}.{}", relative_path.display(), secs);
+        let mut name = PathBuf::from(&base);
+        let mut n = 1;
+        while self.ghost_path(&name).exists() {
+            name = PathBuf::from(format!("{}-{}", base, n));
+            n += 1;
+        }
+        name
+    }
+
+    /// Moves every file in `relative_paths` to the ghost directory as one unit,
+    /// returning their ghost names in order.
+    ///
+    /// If any move fails, files already ghosted are restored to the source tree,
+    /// all symlinks are remapped, and the error is returned — the source tree and
+    /// the ghost directory are left as they were before the call.
+    pub fn ghost_all(&self, relative_paths: &[PathBuf]) -> Result<Vec<PathBuf>, ShadowError> {
+        let mut ghost_names = Vec::with_capacity(relative_paths.len());
+        for rel in relative_paths {
+            match self.move_to_ghost(rel) {
+                Ok(name) => ghost_names.push(name),
+                Err(e) => {
+                    for (done, name) in relative_paths.iter().zip(&ghost_names) {
+                        self.restore_from_ghost(done, name).ok();
+                    }
+                    for rel in relative_paths {
+                        self.remap(rel).ok();
+                    }
+                    return Err(e);
+                }
+            }
         }
+        Ok(ghost_names)
+    }
 
-        // Remove the now-dangling symlink from shadow_src.
-        fs::remove_file(&shadow_path)?;
+    /// Moves `.janitor/ghost/{ghost_name}` back to `relative_path` in the source
+    /// tree and remaps its shadow link. Reverses [`move_to_ghost`](Self::move_to_ghost).
+    pub fn restore_from_ghost(
+        &self,
+        relative_path: &Path,
+        ghost_name: &Path,
+    ) -> Result<(), ShadowError> {
+        let real_path = self.source_root.join(relative_path);
+        if let Some(parent) = real_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        move_file(&self.ghost_path(ghost_name), &real_path)?;
+        self.remap(relative_path)
+    }
 
-        Ok(())
+    fn ghost_path(&self, relative_path: &Path) -> PathBuf {
+        self.source_root
+            .join(".janitor")
+            .join("ghost")
+            .join(relative_path)
     }
 
     /// Opens an existing shadow tree without re-scanning the source directory.
     ///
     /// Use this when the shadow tree was already created by [`initialize`] and
-    /// you only need a `ShadowManager` handle to call `unmap` / `remap`.
+    /// you only need a `ShadowManager` handle to call `unmap` / `remap`. The
+    /// link strategy is read back from `.strategy` (symlinks when absent).
     pub fn open(source: &Path, shadow: &Path) -> Result<Self, ShadowError> {
         let source_root = fs::canonicalize(source)?;
         let shadow_root = fs::canonicalize(shadow)?;
+        let strategy = read_strategy(&shadow_root).unwrap_or(LinkStrategy::Symlink);
         Ok(ShadowManager {
             source_root,
             shadow_root,
+            strategy,
         })
     }
 
-    /// Removes the symlink for `relative_path` from the shadow tree.
+    /// Removes the shadow entry for `relative_path` from the shadow tree.
     ///
     /// This is the **Shadow Simulation** unmap step: the file disappears from
     /// the shadow tree's view so tests can run as if the file were deleted.
@@ -200,16 +417,17 @@ impl ShadowManager {
     /// Call [`remap`] to reverse this operation on test failure.
     pub fn unmap(&self, relative_path: &Path) -> Result<(), ShadowError> {
         let shadow_path = self.shadow_root.join(relative_path);
-        if shadow_path.is_symlink() {
+        if shadow_path.is_symlink() || (self.manages(&shadow_path) && shadow_path.is_file()) {
             fs::remove_file(&shadow_path)?;
         }
         Ok(())
     }
 
-    /// Recreates the symlink for `relative_path` in the shadow tree.
+    /// Recreates the shadow entry for `relative_path` in the shadow tree.
     ///
-    /// Used to restore a symlink that was removed by [`unmap`] after a failed
-    /// Shadow Simulation.
+    /// Used to restore an entry that was removed by [`unmap`] after a failed
+    /// Shadow Simulation. Under [`LinkStrategy::Copy`] this re-copies the
+    /// source file, picking up any edit made since the copy was taken.
     pub fn remap(&self, relative_path: &Path) -> Result<(), ShadowError> {
         let real_path = self.source_root.join(relative_path);
         let shadow_path = self.shadow_root.join(relative_path);
@@ -218,25 +436,7 @@ impl ShadowManager {
             return Ok(()); // already present
         }
 
-        #[cfg(unix)]
-        std::os::unix::fs::symlink(&real_path, &shadow_path).map_err(|e| {
-            ShadowError::SymlinkFailure(format!(
-                "remap symlink failed for {}: {}",
-                shadow_path.display(),
-                e
-            ))
-        })?;
-
-        #[cfg(windows)]
-        std::os::windows::fs::symlink_file(&real_path, &shadow_path).map_err(|e| {
-            ShadowError::SymlinkFailure(format!(
-                "remap symlink failed for {}: {}",
-                shadow_path.display(),
-                e
-            ))
-        })?;
-
-        Ok(())
+        self.link(&real_path, &shadow_path)
     }
 
     /// Get the source root path.
@@ -248,6 +448,58 @@ impl ShadowManager {
     pub fn shadow_root(&self) -> &Path {
         &self.shadow_root
     }
+
+    /// Get the strategy used to mirror source files.
+    pub fn strategy(&self) -> LinkStrategy {
+        self.strategy
+    }
+}
+
+/// Resolves a symlink's `target` as the file system does: a relative target
+/// is taken against the directory holding `link`.
+fn resolve_link_target(link: &Path, target: PathBuf) -> PathBuf {
+    match link.parent() {
+        Some(dir) if target.is_relative() => dir.join(target),
+        _ => target,
+    }
+}
+
+/// Reads the [`LinkStrategy`] a previous run recorded in `shadow`.
+fn read_strategy(shadow: &Path) -> Option<LinkStrategy> {
+    LinkStrategy::parse(&fs::read_to_string(shadow.join(STRATEGY_FILE)).ok()?)
+}
+
+/// Creates a file symlink at `link` pointing at `target`.
+///
+/// # Errors
+///
+/// Returns `ShadowError::SymlinkFailure` when the platform refuses symlinks
+/// (WSL/Windows without Developer Mode).
+fn symlink(target: &Path, link: &Path) -> Result<(), ShadowError> {
+    #[cfg(unix)]
+    let created = std::os::unix::fs::symlink(target, link);
+    #[cfg(windows)]
+    let created = std::os::windows::fs::symlink_file(target, link);
+
+    created.map_err(|e| {
+        if e.kind() == std::io::ErrorKind::PermissionDenied {
+            ShadowError::SymlinkFailure(format!(
+                "WSL/Windows symlink failure: Enable Developer Mode or run as Admin. Path: {}",
+                link.display()
+            ))
+        } else {
+            ShadowError::IoError(e)
+        }
+    })
+}
+
+/// Renames `from` to `to`, falling back to copy + delete across filesystems.
+fn move_file(from: &Path, to: &Path) -> Result<(), ShadowError> {
+    if fs::rename(from, to).is_err() {
+        fs::copy(from, to)?;
+        fs::remove_file(from)?;
+    }
+    Ok(())
 }
 
 #[cfg(test)]
@@ -315,6 +567,26 @@ mod tests {
         fs::remove_dir_all(&temp_dir).ok();
     }
 
+    #[test]
+    fn test_skip_gitignored_dirs() {
+        let temp_dir =
+            std::env::temp_dir().join(format!("shadow_gitignore_{}", std::process::id()));
+        let source = temp_dir.join("source");
+        let shadow = temp_dir.join("shadow");
+
+        fs::create_dir_all(source.join("vendored")).unwrap();
+        fs::write(source.join(".gitignore"), "vendored/\n").unwrap();
+        File::create(source.join("vendored").join("big.py")).unwrap();
+        File::create(source.join("app.py")).unwrap();
+
+        ShadowManager::initialize(&source, &shadow).unwrap();
+
+        assert!(!shadow.join("vendored").exists());
+        assert!(shadow.join("app.py").exists());
+
+        fs::remove_dir_all(&temp_dir).ok();
+    }
+
     #[test]
     fn test_verify_integrity_valid() {
         let temp_dir = std::env::temp_dir().join(format!("shadow_verify_{}", std::process::id()));
@@ -327,7 +599,7 @@ mod tests {
         let manager = ShadowManager::initialize(&source, &shadow).unwrap();
 
         // Integrity should be valid
-        assert!(manager.verify_integrity().unwrap());
+        assert!(manager.is_healthy().unwrap());
 
         // Cleanup
         fs::remove_dir_all(&temp_dir).ok();
@@ -369,6 +641,298 @@ mod tests {
         fs::remove_dir_all(&temp_dir).ok();
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn test_move_to_ghost_after_unmap_and_restore() {
+        let temp_dir =
+            std::env::temp_dir().join(format!("shadow_ghost_unmapped_{}", std::process::id()));
+        let source = temp_dir.join("source");
+        let shadow = temp_dir.join("shadow");
+
+        fs::create_dir_all(source.join("pkg")).unwrap();
+        fs::write(source.join("pkg/orphan.py"), b"x = 1\n").unwrap();
+
+        let manager = ShadowManager::initialize(&source, &shadow).unwrap();
+        let rel = std::path::Path::new("pkg/orphan.py");
+        manager.unmap(rel).unwrap();
+        assert_eq!(manager.move_to_ghost(rel).unwrap(), rel);
+
+        assert!(!source.join("pkg/orphan.py").exists());
+        assert!(source.join(".janitor/ghost/pkg/orphan.py").exists());
+
+        manager.restore_from_ghost(rel, rel).unwrap();
+        assert_eq!(fs::read(source.join("pkg/orphan.py")).unwrap(), b"x = 1\n");
+        assert!(shadow.join("pkg/orphan.py").is_symlink());
+        assert!(!source.join(".janitor/ghost/pkg/orphan.py").exists());
+
+        fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_ghost_all_rolls_back_on_failure() {
+        let temp_dir =
+            std::env::temp_dir().join(format!("shadow_ghost_all_{}", std::process::id()));
+        let source = temp_dir.join("source");
+        let shadow = temp_dir.join("shadow");
+
+        fs::create_dir_all(&source).unwrap();
+        fs::write(source.join("a.py"), b"a = 1\n").unwrap();
+
+        let manager = ShadowManager::initialize(&source, &shadow).unwrap();
+        let paths = vec![PathBuf::from("a.py"), PathBuf::from("missing.py")];
+        assert!(manager.ghost_all(&paths).is_err());
+
+        // a.py was ghosted, then restored when missing.py failed.
+        assert!(source.join("a.py").exists());
+        assert!(shadow.join("a.py").is_symlink());
+        assert!(!source.join(".janitor/ghost/a.py").exists());
+
+        fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_sync_converges_after_add_delete_rename() {
+        let temp_dir = std::env::temp_dir().join(format!("shadow_sync_{}", std::process::id()));
+        let source = temp_dir.join("source");
+        let shadow = temp_dir.join("shadow");
+
+        fs::create_dir_all(source.join("pkg")).unwrap();
+        fs::write(source.join("keep.py"), b"k = 1\n").unwrap();
+        fs::write(source.join("gone.py"), b"g = 1\n").unwrap();
+        fs::write(source.join("pkg/old.py"), b"o = 1\n").unwrap();
+
+        ShadowManager::initialize(&source, &shadow).unwrap();
+
+        // Between runs: add a file in a new dir, delete one, rename the package.
+        fs::create_dir_all(source.join("extra")).unwrap();
+        fs::write(source.join("extra/new.py"), b"n = 1\n").unwrap();
+        fs::remove_file(source.join("gone.py")).unwrap();
+        fs::rename(source.join("pkg"), source.join("lib")).unwrap();
+        fs::rename(source.join("lib/old.py"), source.join("lib/renamed.py")).unwrap();
+
+        // `initialize` on an existing shadow reconciles instead of failing.
+        let manager = ShadowManager::initialize(&source, &shadow).unwrap();
+        assert!(shadow.join("extra/new.py").is_symlink());
+        assert!(shadow.join("lib/renamed.py").is_symlink());
+        assert!(!shadow.join("gone.py").is_symlink());
+        assert!(!shadow.join("pkg").exists());
+        assert!(manager.is_healthy().unwrap());
+
+        // A converged tree reports no changes.
+        let report = manager.sync().unwrap();
+        assert_eq!(
+            report,
+            SyncReport {
+                added: 0,
+                removed: 0,
+                kept: 3
+            }
+        );
+
+        fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_sync_counts_and_spares_ghost_dir() {
+        let temp_dir =
+            std::env::temp_dir().join(format!("shadow_sync_report_{}", std::process::id()));
+        let source = temp_dir.join("source");
+        let shadow = source.join(".janitor/shadow_src");
+
+        fs::create_dir_all(&source).unwrap();
+        fs::write(source.join("a.py"), b"a = 1\n").unwrap();
+        fs::write(source.join("b.py"), b"b = 1\n").unwrap();
+
+        let manager = ShadowManager::initialize(&source, &shadow).unwrap();
+        manager.move_to_ghost(Path::new("b.py")).unwrap();
+        fs::write(source.join("c.py"), b"c = 1\n").unwrap();
+        manager.unmap(Path::new("a.py")).unwrap();
+
+        let report = manager.sync().unwrap();
+        assert_eq!(
+            report,
+            SyncReport {
+                added: 2,
+                removed: 0,
+                kept: 0
+            }
+        );
+        assert!(source.join(".janitor/ghost/b.py").exists());
+        assert!(!shadow.join("b.py").exists());
+
+        fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[test]
+    fn test_hardlink_strategy_round_trip() {
+        let temp_dir = std::env::temp_dir().join(format!("shadow_hardlink_{}", std::process::id()));
+        let source = temp_dir.join("source");
+        let shadow = source.join(".janitor/shadow_src");
+
+        fs::create_dir_all(source.join("pkg")).unwrap();
+        fs::write(source.join("pkg/mod.py"), b"x = 1\n").unwrap();
+
+        let manager =
+            ShadowManager::initialize_with(&source, &shadow, LinkStrategy::Hardlink).unwrap();
+        let linked = shadow.join("pkg/mod.py");
+        assert!(linked.is_file() && !linked.is_symlink());
+        assert!(same_file::is_same_file(source.join("pkg/mod.py"), &linked).unwrap());
+
+        // Edits in place are visible through the hard link.
+        fs::write(source.join("pkg/mod.py"), b"x = 2\n").unwrap();
+        assert_eq!(fs::read(&linked).unwrap(), b"x = 2\n");
+
+        // `open` picks the persisted strategy back up.
+        let reopened = ShadowManager::open(&source, &shadow).unwrap();
+        assert_eq!(reopened.strategy(), LinkStrategy::Hardlink);
+        assert_eq!(reopened.sync().unwrap().kept, 1);
+
+        let rel = Path::new("pkg/mod.py");
+        manager.unmap(rel).unwrap();
+        assert!(!linked.exists());
+        manager.remap(rel).unwrap();
+        assert!(same_file::is_same_file(source.join("pkg/mod.py"), &linked).unwrap());
+
+        // Ghosting resolves the real file from the source root, not the link.
+        manager.move_to_ghost(rel).unwrap();
+        assert!(!linked.exists());
+        assert!(!source.join("pkg/mod.py").exists());
+        assert_eq!(
+            fs::read(source.join(".janitor/ghost/pkg/mod.py")).unwrap(),
+            b"x = 2\n"
+        );
+        manager.restore_from_ghost(rel, rel).unwrap();
+        assert!(same_file::is_same_file(source.join("pkg/mod.py"), &linked).unwrap());
+
+        fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[test]
+    fn test_copy_strategy_sync_and_remap() {
+        let temp_dir = std::env::temp_dir().join(format!("shadow_copy_{}", std::process::id()));
+        let source = temp_dir.join("source");
+        let shadow = temp_dir.join("shadow");
+
+        fs::create_dir_all(&source).unwrap();
+        fs::write(source.join("a.py"), b"a = 1\n").unwrap();
+        fs::write(source.join("b.py"), b"b = 1\n").unwrap();
+
+        let manager = ShadowManager::initialize_with(&source, &shadow, LinkStrategy::Copy).unwrap();
+        assert_eq!(
+            fs::read_to_string(shadow.join(STRATEGY_FILE)).unwrap(),
+            "copy"
+        );
+        assert!(!shadow.join("a.py").is_symlink());
+        assert_eq!(fs::read(shadow.join("a.py")).unwrap(), b"a = 1\n");
+
+        // Copies go stale on edit; `remap` after `unmap` re-copies.
+        fs::write(source.join("a.py"), b"a = 2\n").unwrap();
+        assert_eq!(fs::read(shadow.join("a.py")).unwrap(), b"a = 1\n");
+        manager.unmap(Path::new("a.py")).unwrap();
+        assert!(!shadow.join("a.py").exists());
+        manager.remap(Path::new("a.py")).unwrap();
+        assert_eq!(fs::read(shadow.join("a.py")).unwrap(), b"a = 2\n");
+
+        // `sync` refreshes stale copies and drops copies of deleted files.
+        fs::write(source.join("b.py"), b"b = 2\n").unwrap();
+        fs::remove_file(source.join("a.py")).unwrap();
+        let report = ShadowManager::initialize(&source, &shadow)
+            .unwrap()
+            .sync()
+            .unwrap();
+        assert_eq!(
+            report,
+            SyncReport {
+                added: 0,
+                removed: 0,
+                kept: 1
+            }
+        );
+        assert!(!shadow.join("a.py").exists());
+        assert_eq!(fs::read(shadow.join("b.py")).unwrap(), b"b = 2\n");
+        assert!(shadow.join(STRATEGY_FILE).exists());
+
+        fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_move_to_ghost_relative_symlink_target() {
+        let temp_dir =
+            std::env::temp_dir().join(format!("shadow_ghost_relative_{}", std::process::id()));
+        let source = temp_dir.join("source");
+        let shadow = source.join(".janitor/shadow_src");
+
+        fs::create_dir_all(source.join("pkg")).unwrap();
+        fs::write(source.join("pkg/rel.py"), b"r = 1\n").unwrap();
+        let manager = ShadowManager::initialize(&source, &shadow).unwrap();
+
+        // Replace the absolute link with one relative to its own directory.
+        let link = shadow.join("pkg/rel.py");
+        fs::remove_file(&link).unwrap();
+        std::os::unix::fs::symlink("../../../pkg/rel.py", &link).unwrap();
+        assert!(link.exists());
+
+        manager.move_to_ghost(Path::new("pkg/rel.py")).unwrap();
+        assert!(!source.join("pkg/rel.py").exists());
+        assert!(!link.is_symlink());
+        assert_eq!(
+            fs::read(source.join(".janitor/ghost/pkg/rel.py")).unwrap(),
+            b"r = 1\n"
+        );
+
+        // A dangling link names both paths instead of failing in the move.
+        fs::write(source.join("pkg/rel.py"), b"r = 2\n").unwrap();
+        manager.remap(Path::new("pkg/rel.py")).unwrap();
+        fs::remove_file(source.join("pkg/rel.py")).unwrap();
+        match manager.move_to_ghost(Path::new("pkg/rel.py")) {
+            Err(ShadowError::GhostSourceMissing { shadow, resolved }) => {
+                assert!(shadow.ends_with("pkg/rel.py"));
+                assert_eq!(resolved, manager.source_root().join("pkg/rel.py"));
+            }
+            other => panic!("expected GhostSourceMissing, got {:?}", other),
+        }
+
+        fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[test]
+    fn test_repeat_ghosting_keeps_earlier_ghost() {
+        let temp_dir =
+            std::env::temp_dir().join(format!("shadow_ghost_repeat_{}", std::process::id()));
+        let source = temp_dir.join("source");
+        let shadow = source.join(".janitor/shadow_src");
+
+        fs::create_dir_all(&source).unwrap();
+        let rel = Path::new("dup.py");
+        let manager = ShadowManager::initialize_with(&source, &shadow, LinkStrategy::Copy).unwrap();
+
+        let mut names = Vec::new();
+        for run in 1..=3 {
+            fs::write(source.join(rel), format!("run = {}\n", run)).unwrap();
+            manager.remap(rel).unwrap();
+            names.push(manager.move_to_ghost(rel).unwrap());
+        }
+
+        assert_eq!(names[0], rel);
+        assert_ne!(names[1], names[2]);
+        for (run, name) in names.iter().enumerate() {
+            assert!(name.to_string_lossy().starts_with("dup.py"));
+            assert_eq!(
+                fs::read_to_string(source.join(".janitor/ghost").join(name)).unwrap(),
+                format!("run = {}\n", run + 1)
+            );
+        }
+
+        manager.restore_from_ghost(rel, &names[1]).unwrap();
+        assert_eq!(fs::read_to_string(source.join(rel)).unwrap(), "run = 2\n");
+
+        fs::remove_dir_all(&temp_dir).ok();
+    }
+
     #[test]
     fn test_verify_integrity_broken() {
         let temp_dir = std::env::temp_dir().join(format!("shadow_broken_{}", std::process::id()));
@@ -383,10 +947,44 @@ mod tests {
         // Delete the original file to break the symlink
         fs::remove_file(source.join("file.txt")).unwrap();
 
-        // Integrity should be broken
-        assert!(!manager.verify_integrity().unwrap());
+        // Integrity should be broken, naming the dangling link
+        assert!(!manager.is_healthy().unwrap());
+        assert_eq!(
+            manager.verify_integrity().unwrap(),
+            vec![manager.shadow_root().join("file.txt")]
+        );
 
         // Cleanup
         fs::remove_dir_all(&temp_dir).ok();
     }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_verify_integrity_relative_targets() {
+        let temp_dir =
+            std::env::temp_dir().join(format!("shadow_verify_relative_{}", std::process::id()));
+        let source = temp_dir.join("source");
+        let shadow = temp_dir.join("shadow");
+
+        fs::create_dir_all(source.join("pkg")).unwrap();
+        fs::write(source.join("pkg/ok.py"), b"").unwrap();
+        fs::write(source.join("pkg/gone.py"), b"").unwrap();
+        let manager = ShadowManager::initialize(&source, &shadow).unwrap();
+
+        // Relative to `shadow/pkg`, never to the process working directory.
+        for name in ["ok.py", "gone.py"] {
+            let link = shadow.join("pkg").join(name);
+            fs::remove_file(&link).unwrap();
+            std::os::unix::fs::symlink(format!("../../source/pkg/{}", name), &link).unwrap();
+        }
+        assert!(manager.is_healthy().unwrap());
+
+        fs::remove_file(source.join("pkg/gone.py")).unwrap();
+        assert_eq!(
+            manager.verify_integrity().unwrap(),
+            vec![manager.shadow_root().join("pkg/gone.py")]
+        );
+
+        fs::remove_dir_all(&temp_dir).ok();
+    }
 }
@@ -0,0 +1,314 @@
+//! # Testkit: Deterministic Synthetic Projects
+//!
+//! Generates Python projects of a chosen shape for benchmarks and integration
+//! tests. The same settings always produce the same bytes, so timings from two
+//! commits are comparable and tests can state exactly which symbols are live.
+//!
+//! ```
+//! let project = testkit::SyntheticProject::new(4).with_functions(3);
+//! assert_eq!(project.module_path(2), "pkg0/mod2.py");
+//! assert!(project.module_source(2).contains("def m2_f0(value, scale=2):"));
+//! ```
+
+use std::collections::BTreeSet;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// A generated Python project.
+///
+/// Module `i` lives at `pkg{i / modules_per_package}/mod{i}.py` and defines
+/// functions `m{i}_f0` … `m{i}_f{functions - 1}`. It imports `imports`
+/// functions from earlier modules and spreads `call_sites` calls over its own
+/// functions, each calling one of the imported names (or, in a module with
+/// nothing to import, the next local function).
+#[derive(Debug, Clone)]
+pub struct SyntheticProject {
+    files: usize,
+    functions: usize,
+    call_sites: usize,
+    imports: usize,
+    modules_per_package: usize,
+    config_files: usize,
+    seed: u64,
+}
+
+impl SyntheticProject {
+    /// A project of `files` modules with 10 functions, 3 imports and 6 call
+    /// sites each, 50 modules per package and no config files.
+    pub fn new(files: usize) -> Self {
+        Self {
+            files,
+            functions: 10,
+            call_sites: 6,
+            imports: 3,
+            modules_per_package: 50,
+            config_files: 0,
+            seed: 1,
+        }
+    }
+
+    /// Functions defined per module.
+    pub fn with_functions(mut self, functions: usize) -> Self {
+        self.functions = functions.max(1);
+        self
+    }
+
+    /// Calls per module.
+    pub fn with_call_sites(mut self, call_sites: usize) -> Self {
+        self.call_sites = call_sites;
+        self
+    }
+
+    /// Functions each module imports from earlier modules.
+    pub fn with_imports(mut self, imports: usize) -> Self {
+        self.imports = imports;
+        self
+    }
+
+    /// Modules per `pkg{n}/` package directory.
+    pub fn with_modules_per_package(mut self, modules: usize) -> Self {
+        self.modules_per_package = modules.max(1);
+        self
+    }
+
+    /// YAML files under `config/` naming every seventh function, as input
+    /// for the grep shield.
+    pub fn with_config_files(mut self, config_files: usize) -> Self {
+        self.config_files = config_files;
+        self
+    }
+
+    /// Seed for the choice of imported functions.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Number of Python modules.
+    pub fn files(&self) -> usize {
+        self.files
+    }
+
+    /// Path of module `i`, relative to the project root.
+    pub fn module_path(&self, i: usize) -> String {
+        format!("pkg{}/mod{}.py", i / self.modules_per_package, i)
+    }
+
+    /// Every function the project defines, module by module.
+    pub fn function_names(&self) -> Vec<String> {
+        (0..self.files)
+            .flat_map(|i| (0..self.functions).map(move |f| function_name(i, f)))
+            .collect()
+    }
+
+    /// Functions called from somewhere in the project.
+    pub fn referenced(&self) -> BTreeSet<String> {
+        (0..self.files)
+            .flat_map(|i| self.calls(i))
+            .map(|(_, target)| target)
+            .collect()
+    }
+
+    /// Source of module `i`.
+    pub fn module_source(&self, i: usize) -> String {
+        let mut out = String::new();
+        for (module, name) in self.imported(i) {
+            let package = module / self.modules_per_package;
+            out.push_str(&format!("from pkg{package}.mod{module} import {name}\n"));
+        }
+        let calls = self.calls(i);
+        for f in 0..self.functions {
+            out.push_str(&format!(
+                "\n\ndef {}(value, scale=2):\n    total = 0\n",
+                function_name(i, f)
+            ));
+            out.push_str("    for item in range(value):\n");
+            out.push_str("        if item % scale == 0:\n");
+            out.push_str("            total += item\n");
+            for (_, target) in calls.iter().filter(|(caller, _)| *caller == f) {
+                out.push_str(&format!("    total += {target}(value)\n"));
+            }
+            out.push_str("    return total\n");
+        }
+        out
+    }
+
+    /// Source of config file `n`: a YAML task list naming every seventh
+    /// function, padded with settings that name nothing.
+    pub fn config_source(&self, n: usize) -> String {
+        let mut out = format!("# generated settings {n}\ntasks:\n");
+        let names = self.function_names();
+        for name in names.iter().skip(n % 7).step_by(7).take(40) {
+            out.push_str(&format!("  - handler: \"pkg.{name}\"\n    retries: 3\n"));
+        }
+        out.push_str("settings:\n");
+        for k in 0..60 {
+            out.push_str(&format!(
+                "  option_{n}_{k}: \"value {k} for setting {n}\"\n"
+            ));
+        }
+        out
+    }
+
+    /// Writes the project under `root`, which is created if needed.
+    pub fn write(&self, root: &Path) -> io::Result<()> {
+        for package in 0..self.files.div_ceil(self.modules_per_package) {
+            let dir = root.join(format!("pkg{package}"));
+            std::fs::create_dir_all(&dir)?;
+            std::fs::write(dir.join("__init__.py"), "")?;
+        }
+        for i in 0..self.files {
+            std::fs::write(root.join(self.module_path(i)), self.module_source(i))?;
+        }
+        if self.config_files > 0 {
+            std::fs::create_dir_all(root.join("config"))?;
+        }
+        for n in 0..self.config_files {
+            let path = root.join("config").join(format!("settings{n}.yaml"));
+            std::fs::write(path, self.config_source(n))?;
+        }
+        Ok(())
+    }
+
+    /// Writes the project to a fresh `name` directory under the system temp
+    /// directory and returns its path.
+    pub fn write_temp(&self, name: &str) -> io::Result<PathBuf> {
+        let root = std::env::temp_dir().join(name);
+        if root.exists() {
+            std::fs::remove_dir_all(&root)?;
+        }
+        self.write(&root)?;
+        Ok(root)
+    }
+
+    /// `(module, function name)` of each import of module `i`.
+    fn imported(&self, i: usize) -> Vec<(usize, String)> {
+        if i == 0 {
+            return Vec::new();
+        }
+        let mut rng = Rng::new(self.seed ^ (i as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+        let mut seen = BTreeSet::new();
+        let mut imported = Vec::new();
+        for _ in 0..self.imports {
+            let module = rng.below(i);
+            let name = function_name(module, rng.below(self.functions));
+            if seen.insert(name.clone()) {
+                imported.push((module, name));
+            }
+        }
+        imported
+    }
+
+    /// `(calling function index, called name)` of each call site in module `i`.
+    fn calls(&self, i: usize) -> Vec<(usize, String)> {
+        let imported = self.imported(i);
+        (0..self.call_sites)
+            .filter_map(|c| {
+                let caller = c % self.functions;
+                let target = if imported.is_empty() {
+                    let next = (caller + 1) % self.functions;
+                    (next != caller).then(|| function_name(i, next))?
+                } else {
+                    imported[c % imported.len()].1.clone()
+                };
+                Some((caller, target))
+            })
+            .collect()
+    }
+}
+
+/// A function nested `depth` `if` blocks deep.
+pub fn deep_function(depth: usize) -> String {
+    let mut out = String::from("def deep(value):\n");
+    for level in 0..depth {
+        let indent = "    ".repeat(level + 1);
+        out.push_str(&format!("{indent}if value > {level}:\n"));
+        out.push_str(&format!("{indent}    value = value - 1\n"));
+    }
+    out.push_str(&format!("{}return value\n", "    ".repeat(depth + 1)));
+    out
+}
+
+/// A function of `width` flat statements.
+pub fn wide_function(width: usize) -> String {
+    let mut out = String::from("def wide(value):\n");
+    for n in 0..width {
+        out.push_str(&format!("    value = value + {n}\n"));
+    }
+    out.push_str("    return value\n");
+    out
+}
+
+fn function_name(module: usize, function: usize) -> String {
+    format!("m{module}_f{function}")
+}
+
+/// xorshift64*: tiny, seedable and stable across platforms and releases.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Self(seed.max(1))
+    }
+
+    fn below(&mut self, bound: usize) -> usize {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        (self.0.wrapping_mul(0x2545_F491_4F6C_DD1D) % bound as u64) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generation_is_deterministic() {
+        let a = SyntheticProject::new(30).with_imports(5);
+        let b = SyntheticProject::new(30).with_imports(5);
+        for i in 0..30 {
+            assert_eq!(a.module_source(i), b.module_source(i));
+        }
+        let other = SyntheticProject::new(30).with_imports(5).with_seed(2);
+        assert!((1..30).any(|i| a.module_source(i) != other.module_source(i)));
+    }
+
+    #[test]
+    fn test_every_call_target_is_imported_or_local() {
+        let project = SyntheticProject::new(20).with_call_sites(8);
+        for i in 0..20 {
+            let source = project.module_source(i);
+            for (_, target) in project.calls(i) {
+                let local = target.starts_with(&format!("m{i}_"));
+                assert!(
+                    local || source.contains(&format!("import {target}\n")),
+                    "{target} in module {i}"
+                );
+            }
+        }
+        // Module 0 has nothing to import, so it calls along its own functions.
+        assert!(project.module_source(0).contains("total += m0_f1(value)"));
+        assert!(project.referenced().contains("m0_f1"));
+    }
+
+    #[test]
+    fn test_write_lays_out_packages() {
+        let project = SyntheticProject::new(5)
+            .with_modules_per_package(2)
+            .with_config_files(1);
+        let root = project.write_temp("test_testkit_write").unwrap();
+        assert!(root.join("pkg2/__init__.py").is_file());
+        assert!(root.join("pkg2/mod4.py").is_file());
+        let config = std::fs::read_to_string(root.join("config/settings0.yaml")).unwrap();
+        assert!(config.contains("handler: \"pkg.m0_f0\""));
+        std::fs::remove_dir_all(root).ok();
+    }
+
+    #[test]
+    fn test_shape_functions() {
+        assert_eq!(deep_function(3).matches("if value").count(), 3);
+        assert!(deep_function(3).contains("                return value\n"));
+        assert_eq!(wide_function(4).lines().count(), 6);
+    }
+}
@@ -9,9 +9,20 @@
 //!    Ed25519 private key and returns the base64-encoded signature as a token.
 //! 3. The tool embeds the corresponding verifying key and calls
 //!    [`SigningOracle::verify_token`] before any destructive operation.
+//!
+//! ## v2 tokens
+//! A v2 token is `base64(claims JSON) "." base64(signature of those bytes)`.
+//! The [`TokenClaims`] carry an expiry, an optional not-before time, an
+//! optional project binding, and a nonce, so a leaked token stops working and
+//! can be limited to one project. [`SigningOracle::verify_token_v2`] checks all
+//! of them offline and says why a token was rejected. Legacy tokens (the bare
+//! signature above) are still accepted with the `legacy-tokens` feature.
 
-use ed25519_dalek::{Signature, SigningKey, Verifier, VerifyingKey};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
 use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 /// The message that all purge tokens must be a valid signature of.
 const PURGE_MESSAGE: &[u8] = b"JANITOR_PURGE_AUTHORIZED";
@@ -55,6 +66,67 @@ fn get_verifying_key() -> &'static VerifyingKey {
     })
 }
 
+/// Seconds a v2 token's `exp` / `nbf` may be off from the local clock.
+pub const CLOCK_SKEW_SECS: u64 = 300;
+
+/// The signed payload of a v2 token.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenClaims {
+    /// Expiry, in seconds since the Unix epoch.
+    pub exp: u64,
+    /// Not valid before this Unix time, when set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nbf: Option<u64>,
+    /// [`project_hash`] of the only project the token unlocks, when set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project_hash: Option<String>,
+    /// Random value making every minted token unique.
+    pub nonce: String,
+}
+
+impl TokenClaims {
+    /// Encodes and signs the claims as a v2 token.
+    pub fn sign(&self, key: &SigningKey) -> String {
+        use base64::Engine;
+
+        let payload = serde_json::to_vec(self).expect("token claims serialize to JSON");
+        let signature = key.sign(&payload);
+        let engine = base64::engine::general_purpose::STANDARD;
+        format!(
+            "{}.{}",
+            engine.encode(&payload),
+            engine.encode(signature.to_bytes())
+        )
+    }
+}
+
+/// Why a token was rejected.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum TokenError {
+    #[error("token is malformed: {0}")]
+    Malformed(String),
+    #[error("token signature is not valid for this build")]
+    BadSignature,
+    #[error("token expired at {0} (Unix time)")]
+    Expired(u64),
+    #[error("token is not valid before {0} (Unix time); check the system clock")]
+    NotYetValid(u64),
+    #[error("token is bound to a different project")]
+    WrongProject,
+    #[error("legacy tokens are not accepted by this build; request a v2 token")]
+    LegacyDisabled,
+}
+
+/// BLAKE3 hex digest of the canonical `project_root`, as bound in
+/// [`TokenClaims::project_hash`].
+pub fn project_hash(project_root: &Path) -> String {
+    let root = dunce::canonicalize(project_root).unwrap_or_else(|_| project_root.into());
+    let root = root.to_string_lossy().replace('\\', "/");
+    blake3::hash(root.trim_end_matches('/').as_bytes())
+        .to_hex()
+        .to_string()
+}
+
 /// Token-based access control for destructive operations.
 pub struct SigningOracle;
 
@@ -82,13 +154,86 @@ impl SigningOracle {
         // 3. Verify against the embedded verifying key.
         get_verifying_key().verify(PURGE_MESSAGE, &sig).is_ok()
     }
+
+    /// Verifies a v2 token for `project_root`: signature, expiry and
+    /// not-before time (within [`CLOCK_SKEW_SECS`] of the system clock), and
+    /// the project binding when present.
+    ///
+    /// A token without a `.` is a legacy token, accepted when its signature is
+    /// valid and the `legacy-tokens` feature is enabled.
+    pub fn verify_token_v2(token: &str, project_root: &Path) -> Result<TokenClaims, TokenError> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        Self::verify_token_at(token, project_root, now)
+    }
+
+    /// [`verify_token_v2`](Self::verify_token_v2) at Unix time `now`.
+    pub fn verify_token_at(
+        token: &str,
+        project_root: &Path,
+        now: u64,
+    ) -> Result<TokenClaims, TokenError> {
+        use base64::Engine;
+
+        let Some((payload, signature)) = token.trim().split_once('.') else {
+            return Self::verify_legacy(token);
+        };
+        let engine = base64::engine::general_purpose::STANDARD;
+        let payload = engine
+            .decode(payload)
+            .map_err(|e| TokenError::Malformed(format!("claims are not base64: {}", e)))?;
+        let signature = engine
+            .decode(signature)
+            .map_err(|e| TokenError::Malformed(format!("signature is not base64: {}", e)))?;
+        let signature: [u8; 64] = signature
+            .as_slice()
+            .try_into()
+            .map_err(|_| TokenError::Malformed("signature is not 64 bytes".to_string()))?;
+        get_verifying_key()
+            .verify(&payload, &Signature::from_bytes(&signature))
+            .map_err(|_| TokenError::BadSignature)?;
+
+        let claims: TokenClaims = serde_json::from_slice(&payload)
+            .map_err(|e| TokenError::Malformed(format!("invalid claims: {}", e)))?;
+        if now > claims.exp.saturating_add(CLOCK_SKEW_SECS) {
+            return Err(TokenError::Expired(claims.exp));
+        }
+        if let Some(nbf) = claims.nbf {
+            if now.saturating_add(CLOCK_SKEW_SECS) < nbf {
+                return Err(TokenError::NotYetValid(nbf));
+            }
+        }
+        if let Some(bound) = &claims.project_hash {
+            if !bound.eq_ignore_ascii_case(&project_hash(project_root)) {
+                return Err(TokenError::WrongProject);
+            }
+        }
+        Ok(claims)
+    }
+
+    /// Accepts a valid legacy token as unbound, never-expiring claims.
+    fn verify_legacy(token: &str) -> Result<TokenClaims, TokenError> {
+        if !cfg!(feature = "legacy-tokens") {
+            return Err(TokenError::LegacyDisabled);
+        }
+        if !Self::verify_token(token) {
+            return Err(TokenError::BadSignature);
+        }
+        Ok(TokenClaims {
+            exp: u64::MAX,
+            nbf: None,
+            project_hash: None,
+            nonce: String::new(),
+        })
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use base64::Engine;
-    use ed25519_dalek::Signer;
 
     /// Private key seed that matches the `VERIFYING_KEY_BYTES` embedded in this
     /// crate.  Used solely by the test suite — never exposed in production.
@@ -131,4 +276,94 @@ mod tests {
         let token = make_token(&other_seed, PURGE_MESSAGE);
         assert!(!SigningOracle::verify_token(&token));
     }
+
+    fn claims(exp: u64, nbf: Option<u64>, project_hash: Option<String>) -> TokenClaims {
+        TokenClaims {
+            exp,
+            nbf,
+            project_hash,
+            nonce: "00112233".to_string(),
+        }
+    }
+
+    const NOW: u64 = 1_800_000_000;
+
+    #[test]
+    fn test_v2_token_accepted_within_validity() {
+        let key = SigningKey::from_bytes(&TEST_SIGNING_KEY_SEED);
+        let token = claims(NOW + 3600, Some(NOW - 60), None).sign(&key);
+        let accepted = SigningOracle::verify_token_at(&token, Path::new("."), NOW).unwrap();
+        assert_eq!(accepted.exp, NOW + 3600);
+        // Within the clock-skew tolerance of the expiry.
+        assert!(SigningOracle::verify_token_at(&token, Path::new("."), NOW + 3700).is_ok());
+    }
+
+    #[test]
+    fn test_v2_expired_and_not_yet_valid_rejected() {
+        let key = SigningKey::from_bytes(&TEST_SIGNING_KEY_SEED);
+        let expired = claims(NOW - 3600, None, None).sign(&key);
+        let err = SigningOracle::verify_token_at(&expired, Path::new("."), NOW).unwrap_err();
+        assert_eq!(err, TokenError::Expired(NOW - 3600));
+        assert!(err.to_string().contains("expired"));
+
+        let future = claims(NOW + 7200, Some(NOW + 3600), None).sign(&key);
+        let err = SigningOracle::verify_token_at(&future, Path::new("."), NOW).unwrap_err();
+        assert_eq!(err, TokenError::NotYetValid(NOW + 3600));
+    }
+
+    #[test]
+    fn test_v2_project_binding() {
+        let tmp = std::env::temp_dir().join("test_vault_project_binding");
+        std::fs::create_dir_all(tmp.join("a")).unwrap();
+        std::fs::create_dir_all(tmp.join("b")).unwrap();
+        let key = SigningKey::from_bytes(&TEST_SIGNING_KEY_SEED);
+        let token = claims(NOW + 60, None, Some(project_hash(&tmp.join("a")))).sign(&key);
+
+        assert!(SigningOracle::verify_token_at(&token, &tmp.join("a"), NOW).is_ok());
+        // The same directory spelled differently is the same project.
+        assert!(SigningOracle::verify_token_at(&token, &tmp.join("b/../a"), NOW).is_ok());
+        assert_eq!(
+            SigningOracle::verify_token_at(&token, &tmp.join("b"), NOW).unwrap_err(),
+            TokenError::WrongProject
+        );
+
+        std::fs::remove_dir_all(&tmp).ok();
+    }
+
+    #[test]
+    fn test_v2_tampered_or_foreign_token_rejected() {
+        let key = SigningKey::from_bytes(&TEST_SIGNING_KEY_SEED);
+        let token = claims(NOW + 60, None, None).sign(&key);
+        let (_, signature) = token.split_once('.').unwrap();
+        let forged = base64::engine::general_purpose::STANDARD
+            .encode(serde_json::to_vec(&claims(u64::MAX, None, None)).unwrap());
+        let tampered = format!("{}.{}", forged, signature);
+        assert_eq!(
+            SigningOracle::verify_token_at(&tampered, Path::new("."), NOW).unwrap_err(),
+            TokenError::BadSignature
+        );
+
+        let foreign = claims(NOW + 60, None, None).sign(&SigningKey::from_bytes(&[0x42u8; 32]));
+        assert_eq!(
+            SigningOracle::verify_token_at(&foreign, Path::new("."), NOW).unwrap_err(),
+            TokenError::BadSignature
+        );
+        assert!(matches!(
+            SigningOracle::verify_token_at("e30=.AAAA", Path::new("."), NOW),
+            Err(TokenError::Malformed(_))
+        ));
+    }
+
+    #[test]
+    fn test_legacy_token_behind_feature() {
+        let legacy = make_token(&TEST_SIGNING_KEY_SEED, PURGE_MESSAGE);
+        let verdict = SigningOracle::verify_token_at(&legacy, Path::new("."), NOW);
+        if cfg!(feature = "legacy-tokens") {
+            assert_eq!(verdict.unwrap().project_hash, None);
+        } else {
+            assert_eq!(verdict.unwrap_err(), TokenError::LegacyDisabled);
+        }
+        let wrong = make_token(&TEST_SIGNING_KEY_SEED, b"DIFFERENT_MESSAGE");
+        assert!(SigningOracle::verify_token_at(&wrong, Path::new("."), NOW).is_err());
+    }
 }
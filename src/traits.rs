@@ -1,77 +0,0 @@
-use anyhow::Result;
-use async_trait::async_trait;
-use bytes::Bytes;
-use std::path::Path;
-use uuid::Uuid;
-
-/// THE ANATOMIST: Parser & IR Analyst
-/// Responsible for semantic fingerprinting at the LLVM-IR level.
-pub trait Anatomist {
-    /// Ingests source code and extracts basic Datalog facts.
-    fn dissect(&self, path: &Path, content: &str) -> Result<Vec<Fact>>;
-    
-    /// Identifies logic-identical functions at the LLVM-IR level.
-    fn fingerprint_ir(&self, bitcode: &[u8]) -> Result<Vec<IrFingerprint>>;
-}
-
-/// THE ORACLE: Global Reachability Engine
-/// Uses Datalog to compute the transitive closure of the call graph.
-pub trait Oracle {
-    /// Ingests facts and identifies candidates for deletion.
-    fn solve_reachability(&self, facts: Vec<Fact>) -> Result<Vec<Candidte>>;
-    
-    /// Provides the mathematical "Heat Factor" for a given module.
-    fn heat_factor(&self, module_id: Uuid) -> f64;
-}
-
-/// THE REAPER: Symbolic Verification & Deletion
-/// Uses Z3 to prove non-impact and performs the actual "Chronos-Vaulting".
-#[async_trait]
-pub trait Reaper {
-    /// Symbolically executes a candidate to prove unreachability or equivalence.
-    async fn prove_apoptosis(&self, candidate: Candidte) -> Result<bool>;
-    
-    /// Executes "Chronos-Vaulting": moves dead code to The Necropolis (.janitor/ghost)
-    /// and triggers VFS unmapping.
-    fn execute_vaulting(&self, symbol_id: Uuid) -> Result<()>;
-}
-
-/// THE SHADOW: Dynamic Trace Replay & Interception
-/// Replays production traces in a hermetic sandbox.
-pub trait Shadow {
-    /// Replays a specific trace ID against a candidate symbol.
-    fn replay_trace(&self, symbol_id: Uuid, trace_data: Bytes) -> Result<bool>;
-}
-
-/// THE SHADOW INTERCEPTOR: The Law of the Sandbox
-pub trait SideEffectInterceptor {
-    fn fs_read(&self, path: &Path) -> Result<Bytes>;
-    fn fs_write(&self, path: &Path, data: &[u8]) -> Result<()>;
-    fn net_request(&self, uri: &str, payload: &[u8]) -> Result<Bytes>;
-    fn get_time(&self) -> u64;
-}
-
-#[derive(Debug, Clone)]
-pub struct IrFingerprint {
-    pub symbol_id: Uuid,
-    pub hash: [u8; 32], // BLAKE3 hash of normalized IR
-}
-
-#[derive(Debug, Clone)]
-pub struct Fact {
-    pub subject: String,
-    pub predicate: String,
-    pub object: String,
-}
-
-#[derive(Debug, Clone)]
-pub struct Candidte {
-    pub symbol_id: Uuid,
-    pub path: String,
-    pub symbol_name: String,
-}
-
-#[derive(Debug, Clone)]
-pub struct LogicCluster {
-    pub elements: Vec<Uuid>,
-}
@@ -9,15 +9,19 @@
 //! cargo run -p mint-token -- generate
 //! ```
 //!
-//! **Mint** a purge token from an existing private key:
+//! **Mint** a v2 purge token from an existing private key, valid for 30 days
+//! and bound to one project:
 //! ```sh
-//! cargo run -p mint-token -- mint --key <64-hex-chars>
+//! cargo run -p mint-token -- mint --key <64-hex-chars> --expires-in 30d --project .
 //! ```
 
 use anyhow::Context;
 use base64::Engine;
 use clap::{Parser, Subcommand};
 use ed25519_dalek::{Signer, SigningKey};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use vault::TokenClaims;
 
 const PURGE_MESSAGE: &[u8] = b"JANITOR_PURGE_AUTHORIZED";
 
@@ -39,13 +43,25 @@ enum Commands {
     /// `crates/vault/src/lib.rs`.
     Generate,
 
-    /// Sign `JANITOR_PURGE_AUTHORIZED` and print the base64 token.
+    /// Sign a v2 token (expiry, optional project binding) and print it.
     ///
     /// Use the hex private key printed by `generate`.
     Mint {
         /// Hex-encoded 32-byte private key seed (64 hex chars).
         #[arg(long)]
         key: String,
+        /// Lifetime of the token: a number with an `s`, `m`, `h`, `d` or `w`
+        /// suffix (e.g. `30d`).
+        #[arg(long, default_value = "30d")]
+        expires_in: String,
+        /// Bind the token to one project: a project root directory or the
+        /// 64-hex-char project hash.
+        #[arg(long)]
+        project: Option<String>,
+        /// Mint a never-expiring legacy token (bare signature of
+        /// `JANITOR_PURGE_AUTHORIZED`) instead.
+        #[arg(long, conflicts_with_all = ["project"])]
+        legacy: bool,
     },
 }
 
@@ -53,7 +69,12 @@ fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
     match cli.command {
         Commands::Generate => cmd_generate(),
-        Commands::Mint { key } => cmd_mint(&key),
+        Commands::Mint {
+            key,
+            expires_in,
+            project,
+            legacy,
+        } => cmd_mint(&key, &expires_in, project.as_deref(), legacy),
     }
 }
 
@@ -95,8 +116,13 @@ fn cmd_generate() -> anyhow::Result<()> {
     Ok(())
 }
 
-/// Sign `PURGE_MESSAGE` with the provided private key and print the base64 token.
-fn cmd_mint(key_hex: &str) -> anyhow::Result<()> {
+/// Sign a token with the provided private key and print it.
+fn cmd_mint(
+    key_hex: &str,
+    expires_in: &str,
+    project: Option<&str>,
+    legacy: bool,
+) -> anyhow::Result<()> {
     let key_bytes = hex::decode(key_hex).context("private key must be valid hex")?;
     let key_array: [u8; 32] = key_bytes
         .as_slice()
@@ -104,8 +130,22 @@ fn cmd_mint(key_hex: &str) -> anyhow::Result<()> {
         .map_err(|_| anyhow::anyhow!("private key must be exactly 32 bytes (64 hex chars)"))?;
 
     let signing_key = SigningKey::from_bytes(&key_array);
-    let sig = signing_key.sign(PURGE_MESSAGE);
-    let token = base64::engine::general_purpose::STANDARD.encode(sig.to_bytes());
+    let token = if legacy {
+        let sig = signing_key.sign(PURGE_MESSAGE);
+        base64::engine::general_purpose::STANDARD.encode(sig.to_bytes())
+    } else {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .context("system clock is before the Unix epoch")?
+            .as_secs();
+        let claims = TokenClaims {
+            exp: now.saturating_add(parse_duration(expires_in)?),
+            nbf: Some(now),
+            project_hash: project.map(resolve_project).transpose()?,
+            nonce: hex::encode(rand::random::<[u8; 16]>()),
+        };
+        claims.sign(&signing_key)
+    };
 
     println!("╔═══════════════════════════════════════════════╗");
     println!("║            PURGE TOKEN (BASE64)               ║");
@@ -114,3 +154,35 @@ fn cmd_mint(key_hex: &str) -> anyhow::Result<()> {
 
     Ok(())
 }
+
+/// Parses `30d`-style durations into seconds.
+fn parse_duration(spec: &str) -> anyhow::Result<u64> {
+    let spec = spec.trim();
+    let split = spec.len() - spec.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    let (count, unit) = spec.split_at(split);
+    let count: u64 = count
+        .parse()
+        .with_context(|| format!("invalid duration '{spec}': expected e.g. 30d"))?;
+    let scale = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3_600,
+        "d" => 86_400,
+        "w" => 7 * 86_400,
+        _ => anyhow::bail!("invalid duration unit '{unit}': use s, m, h, d or w"),
+    };
+    Ok(count.saturating_mul(scale))
+}
+
+/// Resolves `--project` to a project hash: an existing directory is hashed,
+/// otherwise the value must already be a 64-hex-char hash.
+fn resolve_project(project: &str) -> anyhow::Result<String> {
+    let path = Path::new(project);
+    if path.is_dir() {
+        return Ok(vault::project_hash(path));
+    }
+    if project.len() == 64 && project.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Ok(project.to_ascii_lowercase());
+    }
+    anyhow::bail!("--project '{project}' is neither a directory nor a 64-hex-char project hash")
+}
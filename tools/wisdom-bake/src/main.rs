@@ -1,26 +1,33 @@
 use anyhow::{Context, Result};
-use common::wisdom::{ImmortalityRulesWrapper, MetaPattern, WisdomSet};
-use std::fs;
-use std::path::Path;
+use common::wisdom::WisdomSet;
+use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
 
+/// Usage: `wisdom-bake [RULES_DIR] [OUTPUT]` (defaults: `rules`, `wisdom.rkyv`).
+///
+/// `janitor scan` reads `.janitor/wisdom.rkyv` when present.
 fn main() -> Result<()> {
-    let root_dir = Path::new("rules");
+    let mut args = std::env::args().skip(1);
+    let root_dir = PathBuf::from(args.next().unwrap_or_else(|| "rules".to_string()));
+    let output = PathBuf::from(args.next().unwrap_or_else(|| "wisdom.rkyv".to_string()));
     if !root_dir.exists() {
         eprintln!(
-            "Warning: 'rules' directory not found at {}. Creating empty wisdom.rkyv.",
-            root_dir.display()
+            "Warning: 'rules' directory not found at {}. Creating empty {}.",
+            root_dir.display(),
+            output.display()
         );
-        let mut empty_set = WisdomSet::default();
-        save_wisdom_set(&mut empty_set)?;
+        WisdomSet::default().save(&output)?;
         return Ok(());
     }
 
-    let wisdom_set = load_json_rules(root_dir)?;
-    save_wisdom_set(&mut wisdom_set.clone())?; // Clone because save sorts in place, but we might want to inspect or verify? Actually save consumes or takes mut ref.
+    let mut wisdom_set = load_json_rules(&root_dir)?;
+    wisdom_set
+        .save(&output)
+        .with_context(|| format!("Failed to write {}", output.display()))?;
 
     println!(
-        "Successfully compiled wisdom.rkyv with {} rules and {} meta patterns.",
+        "Successfully compiled {} with {} rules and {} meta patterns.",
+        output.display(),
         wisdom_set.immortality_rules.len(),
         wisdom_set.meta_patterns.exact_matches.len()
             + wisdom_set.meta_patterns.suffix_matches.len()
@@ -38,46 +45,14 @@ fn load_json_rules(root: &Path) -> Result<WisdomSet> {
         let path = entry.path();
         if path.extension().and_then(|s| s.to_str()) == Some("json") {
             println!("Processing: {:?}", path);
-            let content =
-                fs::read_to_string(path).with_context(|| format!("Failed to read {:?}", path))?;
-
-            // Try parsing as ImmortalityRulesWrapper
-            if let Ok(wrapper) = serde_json::from_str::<ImmortalityRulesWrapper>(&content) {
-                wisdom_set
-                    .immortality_rules
-                    .extend(wrapper.immortality_rules);
-                continue;
-            }
-
-            // Try parsing as MetaPattern
-            if let Ok(pattern) = serde_json::from_str::<MetaPattern>(&content) {
-                wisdom_set.meta_patterns.merge(pattern);
-                continue;
+            // Same reader `janitor scan --rules` uses, so a file that bakes
+            // also loads at scan time, and vice versa.
+            match WisdomSet::from_rules_file(path) {
+                Ok(rules) => wisdom_set.merge(rules),
+                Err(e) => eprintln!("Warning: {}. Skipping.", e),
             }
-
-            // Fallback: Check if it's a raw list of ImmortalityRule (some frameworks might just be a list?)
-            // The spec says "Format 3: Framework-Keyed Rules (JS/TS)".
-            // We might need to handle that later. For now, we stick to the mandate.
-            eprintln!(
-                "Warning: Could not identify JSON schema for {:?}. Skipping.",
-                path
-            );
         }
     }
 
     Ok(wisdom_set)
 }
-
-fn save_wisdom_set(wisdom_set: &mut WisdomSet) -> Result<()> {
-    // 1. Sort for determinism
-    wisdom_set.sort();
-
-    // 2. Serialize
-    let bytes = rkyv::to_bytes::<rkyv::rancor::Error>(wisdom_set)
-        .map_err(|e| anyhow::anyhow!("Serialization failed: {}", e))?;
-
-    // 3. Write to disk
-    fs::write("wisdom.rkyv", bytes).context("Failed to write wisdom.rkyv")?;
-
-    Ok(())
-}